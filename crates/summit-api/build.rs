@@ -0,0 +1,24 @@
+//! Records the commit being built as `SUMMIT_GIT_HASH`, reported by
+//! `GET /info`. Outside a git checkout (or without git) it is left unset,
+//! unless the variable is already set in the build environment.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=SUMMIT_GIT_HASH");
+    println!("cargo:rerun-if-changed=../../.git/HEAD");
+    println!("cargo:rerun-if-changed=../../.git/refs/heads");
+    if std::env::var_os("SUMMIT_GIT_HASH").is_some() {
+        return;
+    }
+
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok());
+    if let Some(hash) = hash {
+        println!("cargo:rustc-env=SUMMIT_GIT_HASH={}", hash.trim());
+    }
+}
@@ -5,15 +5,20 @@ use axum::http::StatusCode;
 use axum::Json;
 use serde::{Deserialize, Serialize};
 
-use summit_services::{ComputeEnvelope, OutgoingChunk, SendTarget, TaskSubmit};
+use summit_services::compute_types::msg_types;
+use summit_services::{
+    ComputeEnvelope, OutgoingChunk, SendTarget, TaskAck, TaskStatus, TaskSubmit,
+};
 
-use super::{parse_pubkey, ApiState};
+use super::{enqueue, parse_pubkey, ApiError, ApiState};
 
 // ── /compute/tasks (GET) ──────────────────────────────────────────────────────
 
 #[derive(Serialize)]
 pub struct ComputeAllTasksResponse {
     pub tasks: Vec<ComputeTaskJson>,
+    /// Remote tasks waiting for an executor slot on this node.
+    pub queue_depth: usize,
 }
 
 pub async fn handle_compute_all_tasks(
@@ -26,7 +31,10 @@ pub async fn handle_compute_all_tasks(
         .map(task_to_json)
         .collect();
 
-    Json(ComputeAllTasksResponse { tasks })
+    Json(ComputeAllTasksResponse {
+        tasks,
+        queue_depth: state.compute_store.queue_depth(),
+    })
 }
 
 // ── /compute/tasks/{peer_pubkey} (GET) ────────────────────────────────────────
@@ -35,6 +43,8 @@ pub async fn handle_compute_all_tasks(
 pub struct ComputeTasksResponse {
     pub peer_pubkey: String,
     pub tasks: Vec<ComputeTaskJson>,
+    /// Remote tasks from all peers waiting for an executor slot on this node.
+    pub queue_depth: usize,
 }
 
 #[derive(Serialize)]
@@ -43,17 +53,41 @@ pub struct ComputeTaskJson {
     pub status: String,
     pub submitted_at: u64,
     pub updated_at: u64,
+    /// When the task started running and when it finished, Unix ms.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<u64>,
+    /// Time waiting for an executor slot, and time executing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_ms: Option<u64>,
+    pub priority: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub elapsed_ms: Option<u64>,
     pub payload: serde_json::Value,
+    /// Output streamed by the worker so far, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub output: Vec<OutputLineJson>,
+    pub output_truncated: bool,
+    /// Local paths of output files received from the worker.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub received_files: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct OutputLineJson {
+    pub stream: String,
+    pub data: String,
 }
 
 pub async fn handle_compute_tasks(
     State(state): State<ApiState>,
     Path(peer_pubkey): Path<String>,
-) -> Result<Json<ComputeTasksResponse>, (StatusCode, String)> {
+) -> Result<Json<ComputeTasksResponse>, ApiError> {
     let pubkey = parse_pubkey(&peer_pubkey)?;
 
     let task_ids = state.compute_store.tasks_for_peer(&pubkey);
@@ -63,7 +97,11 @@ pub async fn handle_compute_tasks(
         .map(task_to_json)
         .collect();
 
-    Ok(Json(ComputeTasksResponse { peer_pubkey, tasks }))
+    Ok(Json(ComputeTasksResponse {
+        peer_pubkey,
+        tasks,
+        queue_depth: state.compute_store.queue_depth(),
+    }))
 }
 
 // ── /compute/submit (POST) ────────────────────────────────────────────────────
@@ -72,6 +110,12 @@ pub async fn handle_compute_tasks(
 pub struct ComputeSubmitRequest {
     pub to: String,
     pub payload: serde_json::Value,
+    /// Higher runs first on the worker. Default 0.
+    #[serde(default)]
+    pub priority: i32,
+    /// Submit even if the peer's announced services don't include compute.
+    #[serde(default)]
+    pub force: bool,
 }
 
 #[derive(Serialize)]
@@ -83,22 +127,37 @@ pub struct ComputeSubmitResponse {
 pub async fn handle_compute_submit(
     State(state): State<ApiState>,
     Json(req): Json<ComputeSubmitRequest>,
-) -> Result<Json<ComputeSubmitResponse>, (StatusCode, String)> {
+) -> Result<Json<ComputeSubmitResponse>, ApiError> {
     let to = parse_pubkey(&req.to)?;
     let from = state.keypair.public;
 
+    // Only a peer whose full service set we hold can be known not to run
+    // compute; unknown or half-discovered peers get the benefit of the doubt.
+    if !req.force {
+        if let Some(peer) = state.registry.get(&to) {
+            if peer.is_complete() && !peer.has_service(&summit_core::wire::compute_hash()) {
+                return Err(ApiError::bad_request(
+                    "compute_not_offered",
+                    "peer does not advertise the compute service (set force to submit anyway)",
+                ));
+            }
+        }
+    }
+
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis() as u64;
 
-    let payload_bytes = serde_json::to_vec(&req.payload)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let payload_bytes = serde_json::to_vec(&req.payload).map_err(ApiError::internal)?;
 
+    // The nonce keeps identical payloads submitted in the same millisecond
+    // from sharing an id and overwriting each other.
     let task_id = {
         let mut h = blake3::Hasher::new();
         h.update(&from);
         h.update(&timestamp.to_le_bytes());
+        h.update(&summit_core::crypto::generate_nonce());
         h.update(&payload_bytes);
         hex::encode(h.finalize().as_bytes())
     };
@@ -108,31 +167,13 @@ pub async fn handle_compute_submit(
         sender: hex::encode(from),
         timestamp,
         payload: req.payload,
+        priority: req.priority,
     };
 
-    let envelope = ComputeEnvelope {
-        msg_type: summit_services::compute_types::msg_types::TASK_SUBMIT.to_string(),
-        payload: serde_json::to_value(&submit)
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
-    };
-
-    let raw = serde_json::to_vec(&envelope)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let chunk = OutgoingChunk {
-        type_tag: 0,
-        schema_id: summit_core::wire::compute_hash(),
-        payload: bytes::Bytes::from(raw),
-        priority_flags: 0x02,
-    };
+    let chunk = compute_chunk(msg_types::TASK_SUBMIT, &submit)?;
 
     let target = SendTarget::Peer { public_key: to };
-    state.chunk_tx.send((target, chunk)).await.map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "send queue closed".to_string(),
-        )
-    })?;
+    enqueue(&state, target, chunk).await?;
 
     state.compute_store.track_submitted(to, submit);
 
@@ -145,8 +186,91 @@ pub async fn handle_compute_submit(
     Ok(Json(ComputeSubmitResponse { task_id, timestamp }))
 }
 
+// ── /compute/cancel (POST) ────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ComputeCancelRequest {
+    pub task_id: String,
+}
+
+#[derive(Serialize)]
+pub struct ComputeCancelResponse {
+    pub task_id: String,
+    pub status: String,
+}
+
+/// Cancel a task on either side of the exchange.
+///
+/// As the submitter we record the cancellation and ask the worker to stop.
+/// As the worker we kill the task (if executing) and tell the submitter.
+pub async fn handle_compute_cancel(
+    State(state): State<ApiState>,
+    Json(req): Json<ComputeCancelRequest>,
+) -> Result<Json<ComputeCancelResponse>, ApiError> {
+    let task = state
+        .compute_store
+        .get_task(&req.task_id)
+        .ok_or_else(|| ApiError::not_found("task_not_found", "task not found"))?;
+
+    if !state.compute_store.cancel(&req.task_id) {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "task_finished",
+            format!("task already finished ({:?})", task.status),
+        ));
+    }
+
+    let chunk = if task.local {
+        compute_chunk(
+            msg_types::TASK_CANCEL,
+            &serde_json::json!({ "task_id": req.task_id }),
+        )?
+    } else {
+        compute_chunk(
+            msg_types::TASK_ACK,
+            &TaskAck {
+                task_id: req.task_id.clone(),
+                status: TaskStatus::Cancelled,
+            },
+        )?
+    };
+
+    let target = SendTarget::Peer {
+        public_key: task.peer_pubkey,
+    };
+    enqueue(&state, target, chunk).await?;
+
+    tracing::info!(
+        task_id = summit_services::short_id(&req.task_id),
+        local = task.local,
+        "compute task cancelled"
+    );
+
+    Ok(Json(ComputeCancelResponse {
+        task_id: req.task_id,
+        status: format!("{:?}", TaskStatus::Cancelled),
+    }))
+}
+
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
+/// Wrap a compute message in a `ComputeEnvelope` chunk.
+fn compute_chunk<T: Serialize>(msg_type: &str, payload: &T) -> Result<OutgoingChunk, ApiError> {
+    let envelope = ComputeEnvelope {
+        msg_type: msg_type.to_string(),
+        payload: serde_json::to_value(payload).map_err(ApiError::internal)?,
+    };
+
+    let raw = serde_json::to_vec(&envelope).map_err(ApiError::internal)?;
+
+    Ok(OutgoingChunk {
+        type_tag: 0,
+        schema_id: summit_core::wire::compute_hash(),
+        payload: bytes::Bytes::from(raw),
+        priority_flags: 0x02,
+    })
+}
+
 fn task_to_json(t: summit_services::ComputeTask) -> ComputeTaskJson {
     let (result, elapsed_ms) = match &t.result {
         Some(r) => (Some(r.result.clone()), Some(r.elapsed_ms)),
@@ -157,8 +281,27 @@ fn task_to_json(t: summit_services::ComputeTask) -> ComputeTaskJson {
         status: format!("{:?}", t.status),
         submitted_at: t.submitted_at,
         updated_at: t.updated_at,
+        started_at: t.started_at,
+        completed_at: t.completed_at,
+        queue_ms: t.queue_ms(),
+        run_ms: t.run_ms(),
+        priority: t.submit.priority,
         result,
         elapsed_ms,
         payload: t.submit.payload.clone(),
+        output: t
+            .output
+            .iter()
+            .map(|l| OutputLineJson {
+                stream: l.stream.clone(),
+                data: l.data.clone(),
+            })
+            .collect(),
+        output_truncated: t.output_truncated,
+        received_files: t
+            .output_files
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect(),
     }
 }
@@ -0,0 +1,80 @@
+//! /events handler — pushes live events to clients over a WebSocket.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+
+use summit_services::{FileAbandoned, PeerPendingApproval, PresenceEvent};
+
+use super::ApiState;
+
+/// One event frame, sent as a JSON text message tagged by `type`.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ApiEvent {
+    /// A peer started or stopped typing.
+    Presence(PresenceEvent),
+    /// A file being received was given up on with chunks still missing.
+    FileAbandoned(FileAbandoned),
+    /// An untrusted peer's chunks started buffering, awaiting trust.
+    PeerPendingApproval(PeerPendingApproval),
+}
+
+pub async fn handle_events(ws: WebSocketUpgrade, State(state): State<ApiState>) -> Response {
+    ws.on_upgrade(move |socket| stream_events(socket, state))
+}
+
+async fn stream_events(mut socket: WebSocket, state: ApiState) {
+    let mut presence = state.presence.subscribe();
+    let mut abandoned = state.reassembler.subscribe_abandoned();
+    let mut pending = state.untrusted_buffer.subscribe_pending();
+    loop {
+        tokio::select! {
+            event = presence.recv() => {
+                let event = match event {
+                    Ok(e) => ApiEvent::Presence(e),
+                    // A slow client misses events rather than stalling others.
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                };
+                if send_event(&mut socket, &event).await.is_err() {
+                    return;
+                }
+            }
+            event = abandoned.recv() => {
+                let event = match event {
+                    Ok(e) => ApiEvent::FileAbandoned(e),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                };
+                if send_event(&mut socket, &event).await.is_err() {
+                    return;
+                }
+            }
+            event = pending.recv() => {
+                let event = match event {
+                    Ok(e) => ApiEvent::PeerPendingApproval(e),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                };
+                if send_event(&mut socket, &event).await.is_err() {
+                    return;
+                }
+            }
+            // Clients only listen; anything but a close is ignored.
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn send_event(socket: &mut WebSocket, event: &ApiEvent) -> Result<(), axum::Error> {
+    let Ok(text) = serde_json::to_string(event) else {
+        return Ok(());
+    };
+    socket.send(Message::Text(text.into())).await
+}
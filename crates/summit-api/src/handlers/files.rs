@@ -1,17 +1,22 @@
-//! /send, /files handlers — file transfer endpoints.
+//! /send, /files, /transfers handlers — file transfer endpoints.
 
-use axum::extract::{Multipart, State};
-use axum::http::StatusCode;
+use axum::extract::{Multipart, Path, State};
+use axum::http::{header, StatusCode};
 use axum::Json;
 use serde::Serialize;
 
-use summit_services::SendTarget;
+use summit_core::recovery::Have;
+use summit_services::{one_session_per_peer, FecScheme, KnownSchema, SendTarget, SessionMeta};
 
-use super::ApiState;
+use super::{enqueue, ApiError, ApiState};
 
 /// Maximum upload size per file (256 MB).
 const MAX_UPLOAD_BYTES: usize = 256 * 1024 * 1024;
 
+/// How long to wait for receivers to say which chunks they already hold.
+/// Receivers that don't answer in time get every chunk.
+const HAVE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
 // ── /send ─────────────────────────────────────────────────────────────────────
 
 #[derive(Serialize)]
@@ -19,20 +24,34 @@ pub struct SendResponse {
     pub filename: String,
     pub bytes: u64,
     pub chunks_sent: usize,
+    /// Chunks every receiver already held, so were not sent.
+    pub chunks_skipped: usize,
+    /// FEC parity chunks among `chunks_sent`. 0 without FEC.
+    pub parity_chunks: usize,
+    /// Some of the file's chunks are still waiting in the outbound queue.
+    /// Its drain time shows up in `/transfers` once they have gone out.
+    pub queued: bool,
 }
 
-pub async fn handle_send(
-    State(state): State<ApiState>,
-    mut multipart: Multipart,
-) -> Result<Json<SendResponse>, (StatusCode, String)> {
+/// A file upload: the multipart body `/send` and `/send/plan` take.
+struct Upload {
+    filename: String,
+    data: Vec<u8>,
+    target: SendTarget,
+    /// Send parity chunks too. The `fec` field: `K:M`, or `on` for the default.
+    fec: Option<FecScheme>,
+}
+
+async fn read_upload(mut multipart: Multipart) -> Result<Upload, ApiError> {
     let mut file_data = Vec::new();
     let mut filename = String::from("uploaded_file");
     let mut target = SendTarget::Broadcast;
+    let mut fec = None;
 
     while let Some(field) = multipart
         .next_field()
         .await
-        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?
+        .map_err(|e| ApiError::bad_request("invalid_upload", e.to_string()))?
     {
         let field_name = field.name().unwrap_or("").to_string();
 
@@ -40,9 +59,24 @@ pub async fn handle_send(
             let target_str = field
                 .text()
                 .await
-                .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
-            target = serde_json::from_str(&target_str)
-                .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid target: {e}")))?;
+                .map_err(|e| ApiError::bad_request("invalid_upload", e.to_string()))?;
+            target = serde_json::from_str(&target_str).map_err(|e| {
+                ApiError::bad_request("invalid_target", format!("invalid target: {e}"))
+            })?;
+        } else if field_name == "fec" {
+            let scheme = field
+                .text()
+                .await
+                .map_err(|e| ApiError::bad_request("invalid_upload", e.to_string()))?;
+            fec = match scheme.trim() {
+                "" | "on" | "true" => Some(FecScheme::default()),
+                "off" | "false" => None,
+                scheme => Some(
+                    scheme
+                        .parse()
+                        .map_err(|e| ApiError::bad_request("invalid_fec", format!("{e:#}")))?,
+                ),
+            };
         } else {
             if let Some(name) = field.file_name() {
                 filename = sanitize_filename(name);
@@ -50,10 +84,11 @@ pub async fn handle_send(
             let data = field
                 .bytes()
                 .await
-                .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+                .map_err(|e| ApiError::bad_request("invalid_upload", e.to_string()))?;
             if file_data.len() + data.len() > MAX_UPLOAD_BYTES {
-                return Err((
+                return Err(ApiError::new(
                     StatusCode::PAYLOAD_TOO_LARGE,
+                    "file_too_large",
                     format!("file exceeds {} byte limit", MAX_UPLOAD_BYTES),
                 ));
             }
@@ -62,47 +97,135 @@ pub async fn handle_send(
     }
 
     if file_data.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "no file data".to_string()));
+        return Err(ApiError::bad_request("empty_upload", "no file data"));
     }
 
     if filename.is_empty() {
-        return Err((StatusCode::BAD_REQUEST, "empty filename".to_string()));
+        return Err(ApiError::bad_request("empty_filename", "empty filename"));
+    }
+
+    Ok(Upload {
+        filename,
+        data: file_data,
+        target,
+        fec,
+    })
+}
+
+pub async fn handle_send(
+    State(state): State<ApiState>,
+    multipart: Multipart,
+) -> Result<Json<SendResponse>, ApiError> {
+    let Upload {
+        filename,
+        data: file_data,
+        target,
+        fec,
+    } = read_upload(multipart).await?;
+
+    // A broadcast that reaches nobody would still report chunks queued.
+    if matches!(target, SendTarget::Broadcast) && target_sessions(&state, &target).is_empty() {
+        return Err(no_trusted_peers(&state));
     }
 
     // Write to temp file
     let temp_path = std::env::temp_dir().join(&filename);
-    std::fs::write(&temp_path, &file_data)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    std::fs::write(&temp_path, &file_data).map_err(ApiError::internal)?;
 
-    // Chunk the file
-    let chunks = summit_services::chunk_file(&temp_path)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    // Chunk the file, parity chunks (if any) after the data
+    let chunks = match fec {
+        Some(scheme) => summit_services::chunk_file_fec(&temp_path, scheme),
+        None => summit_services::chunk_file(&temp_path),
+    }
+    .map_err(ApiError::internal)?;
+    let parity_chunks = fec.map_or(0, |s| {
+        s.parity_chunks(file_data.len().div_ceil(summit_services::MAX_CHUNK_SIZE))
+    });
 
     // Clean up temp file immediately after chunking
     let _ = std::fs::remove_file(&temp_path);
 
     let bytes = file_data.len() as u64;
-    let chunks_sent = chunks.len();
-
-    // Push all chunks to send queue with target, pacing to avoid overwhelming slow receivers
-    for chunk in chunks {
-        state
-            .chunk_tx
-            .send((target.clone(), chunk))
+    let mut chunks = chunks.into_iter();
+    let Some(metadata) = chunks.next() else {
+        return Err(ApiError::internal("no metadata chunk"));
+    };
+    let data_chunks: Vec<_> = chunks.collect();
+
+    let transfer = state
+        .transfers
+        .start(filename.clone(), bytes, data_chunks.len() + 1);
+
+    // Send the metadata first and ask each receiver what it already holds.
+    let peers = target_peers(&state, &target);
+    let metadata_hash = summit_core::crypto::hash(&metadata.payload);
+    let waiters: Vec<_> = peers
+        .iter()
+        .map(|peer| (*peer, state.reassembler.expect_have(*peer, metadata_hash)))
+        .collect();
+    if let Err(e) = enqueue(&state, target.clone(), metadata).await {
+        for peer in &peers {
+            state.reassembler.forget_have(*peer, metadata_hash);
+        }
+        state.transfers.abandon(transfer);
+        return Err(e);
+    }
+    let deadline = tokio::time::Instant::now() + HAVE_TIMEOUT;
+    let mut haves: Vec<([u8; 32], Option<Have>)> = Vec::with_capacity(waiters.len());
+    for (peer, rx) in waiters {
+        let have = tokio::time::timeout_at(deadline, rx)
             .await
-            .map_err(|_| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "send queue closed".to_string(),
-                )
-            })?;
+            .ok()
+            .and_then(Result::ok);
+        if have.is_none() {
+            state.reassembler.forget_have(peer, metadata_hash);
+        }
+        haves.push((peer, have));
+    }
+
+    // Push the chunks someone is missing, pacing to avoid overwhelming slow receivers.
+    // A chunk everyone lacks goes to the original target; one only some lack
+    // goes to just those peers. A full queue makes this wait, so a large
+    // upload drains at network speed.
+    let mut chunks_sent = 1;
+    let mut chunks_skipped = 0;
+    for (index, chunk) in data_chunks.into_iter().enumerate() {
+        let missing: Vec<[u8; 32]> = haves
+            .iter()
+            .filter(|(_, have)| !have.as_ref().is_some_and(|h| h.holds(index)))
+            .map(|(peer, _)| *peer)
+            .collect();
+        let targets = if missing.len() == haves.len() {
+            vec![target.clone()]
+        } else {
+            missing
+                .into_iter()
+                .map(|public_key| SendTarget::Peer { public_key })
+                .collect()
+        };
+        if targets.is_empty() {
+            chunks_skipped += 1;
+            continue;
+        }
+        for t in targets {
+            if let Err(e) = enqueue(&state, t, chunk.clone()).await {
+                state.transfers.abandon(transfer);
+                return Err(e);
+            }
+        }
+        chunks_sent += 1;
         tokio::time::sleep(std::time::Duration::from_millis(1)).await;
     }
 
+    let queue_len = || state.chunk_tx.max_capacity() - state.chunk_tx.capacity();
+    state.transfers.enqueued(transfer, queue_len);
+
     tracing::info!(
         filename,
         bytes,
         chunks_sent,
+        chunks_skipped,
+        parity_chunks,
         ?target,
         "file queued for sending"
     );
@@ -111,6 +234,101 @@ pub async fn handle_send(
         filename,
         bytes,
         chunks_sent,
+        chunks_skipped,
+        parity_chunks,
+        queued: queue_len() > 0,
+    }))
+}
+
+/// 409 for a broadcast with no trusted peer to go to, saying whether there
+/// are sessions at all.
+fn no_trusted_peers(state: &ApiState) -> ApiError {
+    let message = match state.sessions.len() {
+        0 => "no sessions — nothing would be sent; wait for a peer to connect".to_string(),
+        n => format!(
+            "none of the {n} session(s) is with a trusted peer — nothing would be sent; \
+             trust a peer (`summit-ctl trust add`) or send to one with --peer"
+        ),
+    };
+    ApiError::new(StatusCode::CONFLICT, "no_trusted_peers", message)
+}
+
+/// Peers with a session that `target` reaches — the ones asked for a HAVE.
+fn target_peers(state: &ApiState, target: &SendTarget) -> Vec<[u8; 32]> {
+    let mut peers: Vec<[u8; 32]> = target_sessions(state, target)
+        .into_iter()
+        .map(|(_, peer)| peer)
+        .collect();
+    peers.sort_unstable();
+    peers.dedup();
+    peers
+}
+
+/// (session id, peer) pairs file chunks for `target` would go out on,
+/// resolved the way the send worker does: one session per peer, trusted
+/// peers only for a broadcast.
+fn target_sessions(state: &ApiState, target: &SendTarget) -> Vec<([u8; 32], [u8; 32])> {
+    let metas: Vec<SessionMeta> = state
+        .sessions
+        .iter()
+        .map(|e| e.value().meta.clone())
+        .filter(|meta| match target {
+            SendTarget::Broadcast => state.trust.check(&meta.peer_pubkey).gets_broadcasts(),
+            SendTarget::Peer { public_key } => meta.peer_pubkey == *public_key,
+            SendTarget::Session { session_id } => meta.session_id == *session_id,
+        })
+        .collect();
+    one_session_per_peer(&metas, &KnownSchema::FileData.id())
+}
+
+// ── /send/plan ────────────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct SendPlanResponse {
+    pub filename: String,
+    pub bytes: u64,
+    /// Chunks the file would be sent as, the metadata chunk included.
+    pub chunks: usize,
+    /// FEC parity chunks among `chunks`. 0 without FEC.
+    pub parity_chunks: usize,
+    /// Data bytes per chunk (the last one may be shorter).
+    pub chunk_size: usize,
+    /// Sessions the chunks would go out on, one per peer.
+    pub targets: Vec<PlanTarget>,
+}
+
+#[derive(Serialize)]
+pub struct PlanTarget {
+    pub session_id: String,
+    pub peer_pubkey: String,
+}
+
+/// Dry run of `/send`: what the upload would be sent as, and to whom.
+/// Nothing is enqueued.
+pub async fn handle_send_plan(
+    State(state): State<ApiState>,
+    multipart: Multipart,
+) -> Result<Json<SendPlanResponse>, ApiError> {
+    let upload = read_upload(multipart).await?;
+    let chunk_size = summit_services::MAX_CHUNK_SIZE;
+    let targets = target_sessions(&state, &upload.target)
+        .into_iter()
+        .map(|(session_id, peer)| PlanTarget {
+            session_id: hex::encode(session_id),
+            peer_pubkey: hex::encode(peer),
+        })
+        .collect();
+
+    let data_chunks = upload.data.len().div_ceil(chunk_size);
+    let parity_chunks = upload.fec.map_or(0, |s| s.parity_chunks(data_chunks));
+
+    Ok(Json(SendPlanResponse {
+        filename: upload.filename,
+        bytes: upload.data.len() as u64,
+        chunks: data_chunks + parity_chunks + 1,
+        parity_chunks,
+        chunk_size,
+        targets,
     }))
 }
 
@@ -147,6 +365,13 @@ fn sanitize_filename(raw: &str) -> String {
 pub struct FilesResponse {
     pub received: Vec<String>,
     pub in_progress: Vec<String>,
+    /// Files being reassembled right now.
+    pub active_assemblies: usize,
+    /// Cap on `active_assemblies`; new transfers are dropped past it. 0 = unlimited.
+    pub max_concurrent_assemblies: usize,
+    /// Data chunks received since startup that arrived after a later chunk
+    /// of their file.
+    pub reordered_chunks: u64,
 }
 
 pub async fn handle_files(State(state): State<ApiState>) -> Json<FilesResponse> {
@@ -165,10 +390,75 @@ pub async fn handle_files(State(state): State<ApiState>) -> Json<FilesResponse>
 
     Json(FilesResponse {
         received,
+        active_assemblies: in_progress.len(),
         in_progress,
+        max_concurrent_assemblies: state.reassembler.max_assemblies(),
+        reordered_chunks: state.reassembler.reordered_chunks(),
     })
 }
 
+/// A received file's contents, decrypted if it was stored encrypted.
+pub async fn handle_file_get(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+) -> Result<([(header::HeaderName, &'static str); 1], Vec<u8>), ApiError> {
+    let reassembler = state.reassembler.clone();
+    let lookup = name.clone();
+    let data = tokio::task::spawn_blocking(move || reassembler.read_received(&lookup))
+        .await
+        .map_err(ApiError::internal)?
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                ApiError::not_found("file_not_found", format!("no received file {name:?}"))
+            }
+            std::io::ErrorKind::InvalidInput => {
+                ApiError::bad_request("invalid_filename", e.to_string())
+            }
+            _ => ApiError::internal(e),
+        })?;
+    Ok(([(header::CONTENT_TYPE, "application/octet-stream")], data))
+}
+
+// ── /transfers ────────────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct TransfersResponse {
+    pub transfers: Vec<TransferInfo>,
+}
+
+#[derive(Serialize)]
+pub struct TransferInfo {
+    pub filename: String,
+    pub bytes: u64,
+    pub chunks: usize,
+    /// Unix time the send was requested.
+    pub started_at: u64,
+    pub complete: bool,
+    /// Request to last chunk sent; null while chunks are still queued.
+    pub duration_ms: Option<u64>,
+    pub mb_per_sec: Option<f64>,
+}
+
+/// Recent outgoing transfers, newest first.
+pub async fn handle_transfers(State(state): State<ApiState>) -> Json<TransfersResponse> {
+    let transfers = state
+        .transfers
+        .recent()
+        .into_iter()
+        .rev()
+        .map(|t| TransferInfo {
+            complete: t.is_complete(),
+            duration_ms: t.duration.map(|d| d.as_millis() as u64),
+            mb_per_sec: t.mb_per_sec(),
+            filename: t.filename,
+            bytes: t.bytes,
+            chunks: t.chunks,
+            started_at: t.started_unix,
+        })
+        .collect();
+    Json(TransfersResponse { transfers })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
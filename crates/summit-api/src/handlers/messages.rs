@@ -1,22 +1,44 @@
 //! /messages handlers — messaging endpoints.
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::Json;
 use serde::{Deserialize, Serialize};
 
-use summit_services::{messaging_schema_id, MessageEnvelope, OutgoingChunk, SendTarget};
+use summit_services::{
+    message_chunks, message_group_id, messaging_schema_id, msg_chunk_types, MessageContent,
+    MessageEnvelope, OutgoingChunk, SendTarget, MAX_MESSAGE_BYTES,
+};
 
-use super::{parse_pubkey, ApiState};
+use super::{enqueue, parse_pubkey, ApiError, ApiState};
 
 // ── /messages/{peer_pubkey} (GET) ─────────────────────────────────────────────
 
 #[derive(Serialize)]
 pub struct MessagesResponse {
     pub peer_pubkey: String,
+    /// Messages from the peer that were unread before this request.
+    pub unread: usize,
     pub messages: Vec<MessageJson>,
 }
 
+#[derive(Deserialize)]
+pub struct GetMessagesQuery {
+    /// Clear the peer's unread count. Default true; false just peeks.
+    #[serde(default = "default_mark_read")]
+    pub mark_read: bool,
+}
+
+impl Default for GetMessagesQuery {
+    fn default() -> Self {
+        Self { mark_read: true }
+    }
+}
+
+fn default_mark_read() -> bool {
+    true
+}
+
 #[derive(Serialize)]
 pub struct MessageJson {
     pub msg_id: String,
@@ -24,65 +46,320 @@ pub struct MessageJson {
     pub to: String,
     pub msg_type: String,
     pub timestamp: u64,
+    /// Set for messages sent to several peers at once.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    /// Unix ms after which the message is deleted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// Typed content tagged with `content_type` for text, markdown, blob and
+    /// file_ref messages; the raw payload for any other `msg_type`.
     pub content: serde_json::Value,
 }
 
+/// Messages exchanged with one peer. Reading them marks them read unless
+/// `?mark_read=false`.
 pub async fn handle_get_messages(
     State(state): State<ApiState>,
     Path(peer_pubkey): Path<String>,
-) -> Result<Json<MessagesResponse>, (StatusCode, String)> {
+    Query(query): Query<GetMessagesQuery>,
+) -> Result<Json<MessagesResponse>, ApiError> {
     let pubkey = parse_pubkey(&peer_pubkey)?;
 
     let messages = state.message_store.get(&pubkey);
+    let unread = if query.mark_read {
+        state.message_store.mark_read(&pubkey)
+    } else {
+        state.message_store.unread(&pubkey)
+    };
 
     let messages_json: Vec<MessageJson> = messages
         .into_iter()
-        .map(|m| MessageJson {
+        .map(|m| MessageJson::new(m, peer_pubkey.clone()))
+        .collect();
+
+    Ok(Json(MessagesResponse {
+        peer_pubkey,
+        unread,
+        messages: messages_json,
+    }))
+}
+
+// ── /messages/{peer_pubkey}/read (POST) ───────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct MarkReadResponse {
+    pub peer_pubkey: String,
+    /// Messages that were unread.
+    pub cleared: usize,
+}
+
+/// Mark everything from a peer read without fetching it.
+pub async fn handle_mark_read(
+    State(state): State<ApiState>,
+    Path(peer_pubkey): Path<String>,
+) -> Result<Json<MarkReadResponse>, ApiError> {
+    let pubkey = parse_pubkey(&peer_pubkey)?;
+    let cleared = state.message_store.mark_read(&pubkey);
+    Ok(Json(MarkReadResponse {
+        peer_pubkey,
+        cleared,
+    }))
+}
+
+// ── /messages/summary (GET) ───────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct MessageSummaryResponse {
+    /// Sum of `unread` over all peers.
+    pub unread_total: usize,
+    /// Most recently active first.
+    pub peers: Vec<PeerMessageSummary>,
+}
+
+#[derive(Serialize)]
+pub struct PeerMessageSummary {
+    pub peer_pubkey: String,
+    pub messages: usize,
+    pub unread: usize,
+    pub last_timestamp: u64,
+}
+
+/// Per-peer message and unread counts. Doesn't mark anything read.
+pub async fn handle_message_summary(State(state): State<ApiState>) -> Json<MessageSummaryResponse> {
+    let peers: Vec<PeerMessageSummary> = state
+        .message_store
+        .summary()
+        .into_iter()
+        .map(|p| PeerMessageSummary {
+            peer_pubkey: hex::encode(p.peer_pubkey),
+            messages: p.messages,
+            unread: p.unread,
+            last_timestamp: p.last_timestamp,
+        })
+        .collect();
+    Json(MessageSummaryResponse {
+        unread_total: peers.iter().map(|p| p.unread).sum(),
+        peers,
+    })
+}
+
+impl MessageJson {
+    fn new(m: MessageEnvelope, to: String) -> Self {
+        Self {
+            content: MessageContent::from_envelope(&m)
+                .map(|c| c.to_payload())
+                .unwrap_or(m.payload),
             msg_id: m.msg_id,
             from: m.sender,
-            to: peer_pubkey.clone(),
+            to,
             msg_type: m.msg_type,
             timestamp: m.timestamp,
-            content: m.payload,
+            group_id: m.group_id,
+            expires_at: m.expires_at,
+        }
+    }
+}
+
+// ── /messages (GET) ───────────────────────────────────────────────────────────
+
+const DEFAULT_INBOX_LIMIT: usize = 50;
+/// Most messages one `/messages` page returns, whatever `?limit=` says.
+pub const MAX_INBOX_LIMIT: usize = 500;
+
+#[derive(Deserialize, Default)]
+pub struct InboxQuery {
+    /// Page size, capped at `MAX_INBOX_LIMIT`.
+    pub limit: Option<usize>,
+    /// Messages to skip, newest first.
+    pub offset: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct InboxResponse {
+    /// Messages across all peers and groups.
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub messages: Vec<InboxMessage>,
+}
+
+#[derive(Serialize)]
+pub struct InboxMessage {
+    /// The conversation's peers: one, or a group's members.
+    pub peers: Vec<String>,
+    #[serde(flatten)]
+    pub message: MessageJson,
+}
+
+/// Recent messages across every peer, newest first, a page at a time —
+/// the inbox view. `to` is the conversation's peer, or for a group message
+/// its members comma-separated.
+pub async fn handle_inbox(
+    State(state): State<ApiState>,
+    Query(query): Query<InboxQuery>,
+) -> Json<InboxResponse> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_INBOX_LIMIT)
+        .min(MAX_INBOX_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let messages = state
+        .message_store
+        .recent(offset.saturating_add(limit))
+        .into_iter()
+        .skip(offset)
+        .map(|(peers, m)| {
+            let peers: Vec<String> = peers.iter().map(hex::encode).collect();
+            InboxMessage {
+                message: MessageJson::new(m, peers.join(",")),
+                peers,
+            }
         })
         .collect();
 
-    Ok(Json(MessagesResponse {
-        peer_pubkey,
-        messages: messages_json,
-    }))
+    Json(InboxResponse {
+        total: state.message_store.total(),
+        offset,
+        limit,
+        messages,
+    })
 }
 
 // ── /messages/send (POST) ─────────────────────────────────────────────────────
 
+/// One recipient, or several for a group message.
 #[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Recipients {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for Recipients {
+    fn default() -> Self {
+        Self::One(String::new())
+    }
+}
+
+impl From<String> for Recipients {
+    fn from(to: String) -> Self {
+        Self::One(to)
+    }
+}
+
+impl Recipients {
+    /// Parsed, de-duplicated recipient keys, in request order.
+    fn parse(&self) -> Result<Vec<[u8; 32]>, ApiError> {
+        let raw = match self {
+            Self::One(to) => std::slice::from_ref(to),
+            Self::Many(to) => to.as_slice(),
+        };
+        let mut keys = Vec::with_capacity(raw.len());
+        for to in raw {
+            let key = parse_pubkey(to)?;
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        if keys.is_empty() {
+            return Err(ApiError::bad_request("no_recipients", "no recipients"));
+        }
+        Ok(keys)
+    }
+}
+
+#[derive(Deserialize, Default)]
 pub struct SendMessageRequest {
-    pub to: String,
+    /// A public key, or a list of them to send one group message.
+    pub to: Recipients,
+    /// "text" (the default), "markdown", "blob" or "file_ref".
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Body of a text or markdown message.
+    #[serde(default)]
     pub text: String,
+    /// Blob MIME type, e.g. "image/png".
+    #[serde(default)]
+    pub mime_type: String,
+    /// Blob bytes, hex-encoded.
+    #[serde(default)]
+    pub data: String,
+    /// Hash of a file sent over file transfer, hex-encoded.
+    #[serde(default)]
+    pub file_hash: String,
+    #[serde(default)]
+    pub filename: String,
+    /// Delete the message, here and at the recipients, this many seconds
+    /// after sending.
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+impl SendMessageRequest {
+    fn content(self) -> Result<MessageContent, ApiError> {
+        let content = match self.content_type.as_deref().unwrap_or("text") {
+            "text" => MessageContent::Text { text: self.text },
+            "markdown" => MessageContent::Markdown { text: self.text },
+            "blob" => MessageContent::Blob {
+                mime_type: self.mime_type,
+                data: self.data,
+            },
+            "file_ref" => MessageContent::FileRef {
+                file_hash: self.file_hash,
+                filename: self.filename,
+            },
+            other => {
+                return Err(ApiError::bad_request(
+                    "invalid_content_type",
+                    format!("unknown content_type '{other}'"),
+                ))
+            }
+        };
+        content
+            .validate()
+            .map_err(|e| ApiError::bad_request("invalid_content", e.to_string()))?;
+        Ok(content)
+    }
 }
 
 #[derive(Serialize)]
 pub struct SendMessageResponse {
     pub msg_id: String,
     pub timestamp: u64,
+    /// Set when the message went to more than one recipient.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
 }
 
 pub async fn handle_send_message(
     State(state): State<ApiState>,
     Json(req): Json<SendMessageRequest>,
-) -> Result<Json<SendMessageResponse>, (StatusCode, String)> {
-    let to = parse_pubkey(&req.to)?;
+) -> Result<Json<SendMessageResponse>, ApiError> {
+    let to = req.to.parse()?;
+    if req.ttl_secs == Some(0) {
+        return Err(ApiError::bad_request(
+            "invalid_ttl",
+            "ttl_secs must be positive",
+        ));
+    }
+    let ttl_secs = req.ttl_secs;
+    let content = req.content()?;
     let from = state.keypair.public;
+    let group_id = (to.len() > 1).then(|| {
+        let mut members = to.clone();
+        members.push(from);
+        message_group_id(&members)
+    });
 
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis() as u64;
 
-    let payload_value = serde_json::json!({ "text": req.text });
+    let payload_value = content.to_payload();
 
-    let payload_bytes = serde_json::to_vec(&payload_value)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let payload_bytes = serde_json::to_vec(&payload_value).map_err(ApiError::internal)?;
     let msg_id = {
         let mut h = blake3::Hasher::new();
         h.update(&from);
@@ -93,31 +370,99 @@ pub async fn handle_send_message(
 
     let envelope = MessageEnvelope {
         msg_id: msg_id.clone(),
-        msg_type: summit_services::msg_types::TEXT.to_string(),
+        msg_type: content.msg_type().to_string(),
         sender: hex::encode(from),
         timestamp,
         payload: payload_value,
+        group_id: group_id.clone(),
+        expires_at: ttl_secs.map(|ttl| timestamp.saturating_add(ttl.saturating_mul(1000))),
     };
 
-    let raw = serde_json::to_vec(&envelope)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let raw = serde_json::to_vec(&envelope).map_err(ApiError::internal)?;
+    if raw.len() > MAX_MESSAGE_BYTES {
+        return Err(ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "message_too_large",
+            format!(
+                "message is {} bytes, limit is {MAX_MESSAGE_BYTES}",
+                raw.len()
+            ),
+        ));
+    }
+
+    // Messages larger than one chunk go out as fragments.
+    let chunks = message_chunks(bytes::Bytes::from(raw));
+
+    for public_key in &to {
+        for chunk in &chunks {
+            let target = SendTarget::Peer {
+                public_key: *public_key,
+            };
+            enqueue(&state, target, chunk.clone()).await?;
+        }
+    }
+
+    match &group_id {
+        Some(id) => state.message_store.add_group(id.clone(), to, envelope),
+        None => state.message_store.add(to[0], envelope),
+    }
+
+    Ok(Json(SendMessageResponse {
+        msg_id,
+        timestamp,
+        group_id,
+    }))
+}
+
+// ── /messages/typing (POST) ───────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct TypingRequest {
+    pub to: String,
+    pub typing: bool,
+}
+
+#[derive(Serialize)]
+pub struct TypingResponse {
+    pub sent: bool,
+}
+
+/// Tell a peer we started or stopped typing. Not stored on either side.
+/// More than `PRESENCE_BURST` signals in quick succession, or repeating an
+/// unchanged state within `PRESENCE_REPEAT`, gets 429.
+pub async fn handle_typing(
+    State(state): State<ApiState>,
+    Json(req): Json<TypingRequest>,
+) -> Result<Json<TypingResponse>, ApiError> {
+    let to = parse_pubkey(&req.to)?;
+    if !state.presence.allow_send(to, req.typing) {
+        return Err(ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "typing_unchanged",
+            "typing state unchanged — resend later",
+        ));
+    }
+
+    let from = state.keypair.public;
+    let timestamp = summit_services::messaging_service::unix_millis();
+    let envelope = MessageEnvelope {
+        msg_id: String::new(),
+        msg_type: summit_services::msg_types::PRESENCE.to_string(),
+        sender: hex::encode(from),
+        timestamp,
+        payload: serde_json::json!({ "typing": req.typing }),
+        group_id: None,
+        expires_at: None,
+    };
+    let raw = serde_json::to_vec(&envelope).map_err(ApiError::internal)?;
 
     let chunk = OutgoingChunk {
-        type_tag: 0,
+        type_tag: msg_chunk_types::ENVELOPE,
         schema_id: messaging_schema_id(),
         payload: bytes::Bytes::from(raw),
         priority_flags: 0x02,
     };
+    enqueue(&state, SendTarget::Peer { public_key: to }, chunk).await?;
 
-    let target = SendTarget::Peer { public_key: to };
-    state.chunk_tx.send((target, chunk)).await.map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "send queue closed".to_string(),
-        )
-    })?;
-
-    state.message_store.add(to, envelope);
-
-    Ok(Json(SendMessageResponse { msg_id, timestamp }))
+    Ok(Json(TypingResponse { sent: true }))
 }
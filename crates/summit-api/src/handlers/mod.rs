@@ -1,128 +1,374 @@
 //! HTTP API handlers — exposes daemon state as JSON.
 
 pub mod compute;
+pub mod events;
 pub mod files;
 pub mod messages;
 pub mod sessions;
 pub mod status;
 pub mod trust;
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use axum::http::StatusCode;
 
 use summit_core::crypto::Keypair;
 use summit_services::{
-    BufferedChunk, ChunkCache, ComputeStore, MessageStore, OutgoingChunk, PeerRegistry, SendTarget,
-    SessionTable, TrustRegistry, UntrustedBuffer,
+    AnnouncementStats, BufferedChunk, ChunkCache, ComputeStore, ConnectRequest, DeliveryTracker,
+    HandshakeSource, MessageStore, OutgoingChunk, PeerBandwidth, PeerRegistry, PresenceHub,
+    SendTarget, SessionTable, TransferTracker, TrustRegistry, UntrustedBuffer,
 };
 
+use crate::ApiError;
+
 #[derive(Clone)]
 pub struct ApiState {
     pub sessions: SessionTable,
+    /// Handshakes still in flight, for diagnosing peers that never connect.
+    pub handshakes: Arc<dyn HandshakeSource>,
+    /// Asks the session initiator to handshake with a peer right away.
+    pub connect_tx: tokio::sync::mpsc::Sender<ConnectRequest>,
     pub cache: ChunkCache,
     pub registry: PeerRegistry,
+    /// Counters for announcement datagrams dropped or collapsed by the listener.
+    pub announcements: AnnouncementStats,
     pub chunk_tx: tokio::sync::mpsc::Sender<(SendTarget, OutgoingChunk)>,
     pub reassembler: Arc<summit_services::FileReassembler>,
     pub trust: TrustRegistry,
     pub untrusted_buffer: UntrustedBuffer,
     pub message_store: MessageStore,
+    /// Typing signals: incoming ones to push to /events, outgoing rate limits.
+    pub presence: PresenceHub,
     pub compute_store: ComputeStore,
+    /// Per-chunk arrival records used for multipath/duplicate stats.
+    pub delivery: DeliveryTracker,
+    /// Recent outgoing file transfers and how fast they drained.
+    pub transfers: TransferTracker,
+    /// Per-peer byte rates and upload cap.
+    pub bandwidth: PeerBandwidth,
     pub keypair: Arc<Keypair>,
+    /// Network interface the daemon runs on.
+    pub interface: String,
     /// Directory where received files are written.
     pub file_transfer_path: std::path::PathBuf,
-    /// Names of services enabled in the current config, e.g. "messaging", "compute".
-    pub enabled_services: Vec<String>,
+    /// Names of services enabled, e.g. "messaging", "compute". Updated when
+    /// the config is reloaded or a service is switched through the API.
+    pub enabled_services: tokio::sync::watch::Sender<Vec<String>>,
     /// Channel to replay buffered chunks when a peer becomes trusted.
     pub replay_tx: tokio::sync::mpsc::UnboundedSender<([u8; 32], BufferedChunk)>,
-    /// Shutdown broadcast sender — signals graceful daemon shutdown.
+    /// Shutdown broadcast sender — signals graceful daemon shutdown. Send
+    /// through `shutdown()` so requests can tell a stopping daemon apart.
     pub shutdown_tx: tokio::sync::broadcast::Sender<()>,
+    /// Set by `shutdown()` before the signal goes out.
+    pub shutting_down: Arc<AtomicBool>,
+    /// `network.max_sessions`, for /status. 0 = unlimited.
+    pub max_sessions: usize,
+    /// Peers not to handshake with for a while after a session drop.
+    pub session_cooldowns: summit_services::SessionCooldowns,
+    /// Set once the multicast listener and session socket are bound.
+    pub ready: Arc<AtomicBool>,
+    /// When the daemon started, for the uptime in /info.
+    pub started_at: std::time::Instant,
+}
+
+impl ApiState {
+    /// Signal every daemon task to stop.
+    pub fn shutdown(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+        let _ = self.shutdown_tx.send(());
+    }
 }
 
 // ── Shared helpers ────────────────────────────────────────────────────────────
 
 /// Parse a hex-encoded 32-byte public key.
-fn parse_pubkey(hex_str: &str) -> Result<[u8; 32], (StatusCode, String)> {
-    let bytes =
-        hex::decode(hex_str).map_err(|_| (StatusCode::BAD_REQUEST, "invalid hex".to_string()))?;
-    if bytes.len() != 32 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "public key must be 32 bytes".to_string(),
-        ));
-    }
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&bytes);
-    Ok(arr)
+fn parse_pubkey(hex_str: &str) -> Result<[u8; 32], ApiError> {
+    parse_hex32(hex_str)
+        .map_err(|msg| ApiError::bad_request("invalid_pubkey", format!("public key {msg}")))
 }
 
 /// Parse a hex-encoded 32-byte session ID.
-fn parse_session_id(hex_str: &str) -> Result<[u8; 32], (StatusCode, String)> {
-    let bytes =
-        hex::decode(hex_str).map_err(|_| (StatusCode::BAD_REQUEST, "invalid hex".to_string()))?;
-    if bytes.len() != 32 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "session_id must be 32 bytes".to_string(),
+fn parse_session_id(hex_str: &str) -> Result<[u8; 32], ApiError> {
+    parse_hex32(hex_str)
+        .map_err(|msg| ApiError::bad_request("invalid_session_id", format!("session_id {msg}")))
+}
+
+/// Decode 32 bytes of hex, ignoring surrounding whitespace, an optional
+/// `0x` prefix and letter case.
+fn parse_hex32(hex_str: &str) -> Result<[u8; 32], String> {
+    let trimmed = hex_str.trim();
+    let digits = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+    if digits.len() != 64 {
+        return Err(format!(
+            "must be 32 bytes (64 hex characters), got {} characters",
+            digits.len()
         ));
     }
     let mut arr = [0u8; 32];
-    arr.copy_from_slice(&bytes);
+    hex::decode_to_slice(digits, &mut arr).map_err(|_| "is not valid hex".to_string())?;
     Ok(arr)
 }
 
+/// How long an API request waits on a full outbound queue before giving up.
+const SEND_QUEUE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Push a chunk onto the outbound queue, waiting for room.
+/// A queue that stays full for `SEND_QUEUE_TIMEOUT` yields 503, as does a
+/// queue closed because the daemon is shutting down.
+async fn enqueue(
+    state: &ApiState,
+    target: SendTarget,
+    chunk: OutgoingChunk,
+) -> Result<(), ApiError> {
+    enqueue_within(
+        &state.chunk_tx,
+        &state.shutting_down,
+        target,
+        chunk,
+        SEND_QUEUE_TIMEOUT,
+    )
+    .await
+}
+
+async fn enqueue_within(
+    chunk_tx: &tokio::sync::mpsc::Sender<(SendTarget, OutgoingChunk)>,
+    shutting_down: &AtomicBool,
+    target: SendTarget,
+    chunk: OutgoingChunk,
+    timeout: std::time::Duration,
+) -> Result<(), ApiError> {
+    use tokio::sync::mpsc::error::SendTimeoutError;
+
+    chunk_tx
+        .send_timeout((target, chunk), timeout)
+        .await
+        .map_err(|e| match e {
+            SendTimeoutError::Timeout(_) => ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "queue_full",
+                "send queue full — retry later",
+            ),
+            SendTimeoutError::Closed(_) if shutting_down.load(Ordering::Relaxed) => ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "shutting_down",
+                "daemon shutting down",
+            ),
+            SendTimeoutError::Closed(_) => {
+                // Only shutdown stops the send worker.
+                tracing::error!("send queue closed while the daemon is running — this is a bug");
+                ApiError::internal("send queue closed")
+            }
+        })
+}
+
 // Re-export handler functions for use in router setup.
-pub use compute::{handle_compute_all_tasks, handle_compute_submit, handle_compute_tasks};
-pub use files::{handle_files, handle_send};
-pub use messages::{handle_get_messages, handle_send_message};
-pub use sessions::{handle_session_drop, handle_session_inspect};
+pub use compute::{
+    handle_compute_all_tasks, handle_compute_cancel, handle_compute_submit, handle_compute_tasks,
+};
+pub use events::handle_events;
+pub use files::{handle_file_get, handle_files, handle_send, handle_send_plan, handle_transfers};
+pub use messages::{
+    handle_get_messages, handle_inbox, handle_mark_read, handle_message_summary,
+    handle_send_message, handle_typing,
+};
+pub use sessions::{
+    handle_handshakes, handle_session_connect, handle_session_drop, handle_session_inspect,
+    handle_session_list,
+};
 pub use status::{
-    handle_cache, handle_cache_clear, handle_peers, handle_schema_list, handle_services,
-    handle_shutdown, handle_status,
+    handle_cache, handle_cache_clear, handle_cache_list, handle_delivery, handle_health,
+    handle_identity, handle_info, handle_metrics, handle_peers, handle_ready, handle_schema_list,
+    handle_service_toggle, handle_services, handle_shutdown, handle_status,
+};
+pub use trust::{
+    handle_trust_add, handle_trust_block, handle_trust_list, handle_trust_pending,
+    handle_trust_receive_only,
 };
-pub use trust::{handle_trust_add, handle_trust_block, handle_trust_list, handle_trust_pending};
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::sync::Arc;
 
-    use axum::extract::{Path, State};
+    use axum::extract::{Path, Query, State};
     use axum::Json;
 
+    /// Handshake source reporting a fixed list.
+    struct FixedHandshakes(Vec<summit_services::HandshakeInfo>);
+
+    impl HandshakeSource for FixedHandshakes {
+        fn handshakes(
+            &self,
+        ) -> std::pin::Pin<
+            Box<dyn std::future::Future<Output = Vec<summit_services::HandshakeInfo>> + Send + '_>,
+        > {
+            Box::pin(async move { self.0.clone() })
+        }
+    }
+
     fn test_state() -> ApiState {
         let (chunk_tx, chunk_rx) = tokio::sync::mpsc::channel(64);
         let (replay_tx, replay_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (connect_tx, connect_rx) = tokio::sync::mpsc::channel(1);
         let (shutdown_tx, _shutdown_rx) = tokio::sync::broadcast::channel(1);
 
         // Leak receivers so the senders remain valid for the test's duration.
         std::mem::forget(chunk_rx);
         std::mem::forget(replay_rx);
+        std::mem::forget(connect_rx);
 
         let tmp = std::env::temp_dir().join(format!("summit-api-test-{}", std::process::id()));
 
-        let cache = summit_services::ChunkCache::new(tmp.join("cache")).unwrap();
+        let cache = summit_services::ChunkCache::in_memory();
 
         let reassembler = Arc::new(summit_services::FileReassembler::new(tmp.join("files")));
 
         ApiState {
             sessions: summit_services::new_session_table(),
+            handshakes: Arc::new(FixedHandshakes(Vec::new())),
+            connect_tx,
             cache,
             registry: summit_services::new_registry(),
+            announcements: AnnouncementStats::default(),
             chunk_tx,
             reassembler,
             trust: summit_services::TrustRegistry::new(),
             untrusted_buffer: summit_services::UntrustedBuffer::new(),
             message_store: summit_services::MessageStore::new(),
+            presence: PresenceHub::new(),
             compute_store: summit_services::ComputeStore::new(),
+            delivery: summit_services::DeliveryTracker::new(),
+            transfers: summit_services::TransferTracker::new(),
+            bandwidth: summit_services::PeerBandwidth::new(0),
             keypair: Arc::new(summit_core::crypto::Keypair::generate()),
+            interface: "veth-test".into(),
             file_transfer_path: tmp.join("received"),
-            enabled_services: vec!["messaging".into(), "compute".into()],
+            enabled_services: tokio::sync::watch::Sender::new(vec![
+                "messaging".into(),
+                "compute".into(),
+            ]),
             replay_tx,
             shutdown_tx,
+            ready: Arc::new(AtomicBool::new(false)),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            max_sessions: 256,
+            session_cooldowns: summit_services::SessionCooldowns::new(),
+            started_at: std::time::Instant::now(),
+        }
+    }
+
+    // ── enqueue tests ────────────────────────────────────────────────────
+
+    fn ping_chunk() -> OutgoingChunk {
+        OutgoingChunk {
+            type_tag: 1,
+            schema_id: [0u8; 32],
+            payload: bytes::Bytes::from_static(b"ping"),
+            priority_flags: 0x02,
         }
     }
 
+    #[tokio::test]
+    async fn enqueue_full_queue_returns_503() {
+        let (chunk_tx, _chunk_rx) = tokio::sync::mpsc::channel(1);
+        let running = AtomicBool::new(false);
+        let timeout = std::time::Duration::from_millis(10);
+        enqueue_within(
+            &chunk_tx,
+            &running,
+            SendTarget::Broadcast,
+            ping_chunk(),
+            timeout,
+        )
+        .await
+        .unwrap();
+
+        let err = enqueue_within(
+            &chunk_tx,
+            &running,
+            SendTarget::Broadcast,
+            ping_chunk(),
+            timeout,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn enqueue_waits_for_room() {
+        let (chunk_tx, mut chunk_rx) = tokio::sync::mpsc::channel(1);
+        let running = AtomicBool::new(false);
+        let timeout = std::time::Duration::from_secs(5);
+        enqueue_within(
+            &chunk_tx,
+            &running,
+            SendTarget::Broadcast,
+            ping_chunk(),
+            timeout,
+        )
+        .await
+        .unwrap();
+
+        let drain = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            chunk_rx.recv().await.unwrap();
+            chunk_rx
+        });
+        enqueue_within(
+            &chunk_tx,
+            &running,
+            SendTarget::Broadcast,
+            ping_chunk(),
+            timeout,
+        )
+        .await
+        .unwrap();
+        drain.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn enqueue_closed_queue_returns_500() {
+        let (chunk_tx, chunk_rx) = tokio::sync::mpsc::channel(1);
+        drop(chunk_rx);
+        let err = enqueue_within(
+            &chunk_tx,
+            &AtomicBool::new(false),
+            SendTarget::Broadcast,
+            ping_chunk(),
+            std::time::Duration::from_millis(10),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn send_during_shutdown_returns_503() {
+        let (chunk_tx, chunk_rx) = tokio::sync::mpsc::channel(1);
+        drop(chunk_rx);
+        let state = ApiState {
+            chunk_tx,
+            ..test_state()
+        };
+        state.shutdown();
+        let req = messages::SendMessageRequest {
+            to: "bb".repeat(32).into(),
+            text: "hi".into(),
+            ..Default::default()
+        };
+        let result = messages::handle_send_message(State(state), Json(req)).await;
+        let Err(ApiError { status, code, .. }) = result else {
+            panic!("expected error");
+        };
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(code, "shutting_down");
+    }
+
     // ── parse_pubkey tests ───────────────────────────────────────────────
 
     #[test]
@@ -133,11 +379,27 @@ mod tests {
         assert_eq!(result.unwrap(), [0xAA; 32]);
     }
 
+    #[test]
+    fn parse_pubkey_accepts_prefix_and_mixed_case() {
+        let key = format!("0x{}", "aB".repeat(32));
+        assert_eq!(parse_pubkey(&key).unwrap(), [0xAB; 32]);
+        let key = format!(" 0X{} ", "Cd".repeat(32));
+        assert_eq!(parse_pubkey(&key).unwrap(), [0xCD; 32]);
+    }
+
+    #[test]
+    fn parse_pubkey_non_hex_of_right_length() {
+        let err = parse_pubkey(&"zz".repeat(32)).unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert_eq!(err.code, "invalid_pubkey");
+        assert!(err.message.contains("not valid hex"));
+    }
+
     #[test]
     fn parse_pubkey_invalid_hex() {
         let result = parse_pubkey("zzzz");
         assert!(result.is_err());
-        let (status, _) = result.unwrap_err();
+        let ApiError { status, .. } = result.unwrap_err();
         assert_eq!(status, StatusCode::BAD_REQUEST);
     }
 
@@ -146,7 +408,11 @@ mod tests {
         let hex = "aa".repeat(16); // 16 bytes, not 32
         let result = parse_pubkey(&hex);
         assert!(result.is_err());
-        let (status, msg) = result.unwrap_err();
+        let ApiError {
+            status,
+            message: msg,
+            ..
+        } = result.unwrap_err();
         assert_eq!(status, StatusCode::BAD_REQUEST);
         assert!(msg.contains("32 bytes"));
     }
@@ -172,7 +438,7 @@ mod tests {
         let hex = "cc".repeat(10);
         let result = parse_session_id(&hex);
         assert!(result.is_err());
-        let (_, msg) = result.unwrap_err();
+        let ApiError { message: msg, .. } = result.unwrap_err();
         assert!(msg.contains("32 bytes"));
     }
 
@@ -196,6 +462,7 @@ mod tests {
                 sender: "a".repeat(64),
                 timestamp: 100,
                 payload: serde_json::json!({}),
+                priority: 0,
             },
         );
         let Json(resp) = compute::handle_compute_all_tasks(State(state)).await;
@@ -214,6 +481,7 @@ mod tests {
                 sender: "a".repeat(64),
                 timestamp: 100,
                 payload: serde_json::json!({}),
+                priority: 0,
             },
         );
         let peer_hex = "aa".repeat(32);
@@ -222,13 +490,15 @@ mod tests {
             panic!("expected Ok");
         };
         assert_eq!(resp.tasks.len(), 1);
+        assert_eq!(resp.tasks[0].started_at, None);
+        assert_eq!(resp.tasks[0].queue_ms, None);
     }
 
     #[tokio::test]
     async fn compute_tasks_invalid_hex() {
         let state = test_state();
         let result = compute::handle_compute_tasks(State(state), Path("zzzz".into())).await;
-        let Err((status, _)) = result else {
+        let Err(ApiError { status, .. }) = result else {
             panic!("expected error");
         };
         assert_eq!(status, StatusCode::BAD_REQUEST);
@@ -241,6 +511,8 @@ mod tests {
         let req = compute::ComputeSubmitRequest {
             to: peer_hex,
             payload: serde_json::json!({ "run": "echo hi" }),
+            priority: 0,
+            force: false,
         };
         let Ok(Json(resp)) = compute::handle_compute_submit(State(state.clone()), Json(req)).await
         else {
@@ -253,6 +525,157 @@ mod tests {
         assert!(task.local);
     }
 
+    #[tokio::test]
+    async fn compute_submit_identical_payloads_get_distinct_ids() {
+        let state = test_state();
+        let req = || compute::ComputeSubmitRequest {
+            to: "bb".repeat(32),
+            payload: serde_json::json!({ "run": "echo same" }),
+            priority: 0,
+            force: false,
+        };
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let Ok(Json(resp)) =
+                compute::handle_compute_submit(State(state.clone()), Json(req())).await
+            else {
+                panic!("expected Ok");
+            };
+            ids.push(resp.task_id);
+        }
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(state.compute_store.all_tasks().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn compute_submit_rejects_peer_without_compute() {
+        use summit_core::wire::{messaging_hash, CapabilityAnnouncement};
+
+        let state = test_state();
+        let ann = CapabilityAnnouncement {
+            service_hash: messaging_hash(),
+            public_key: [0xbb; 32],
+            version: 1,
+            session_port: 9001,
+            chunk_port: 0,
+            contract: summit_core::wire::Contract::Bulk as u8,
+            flags: 0,
+            service_count: 1,
+            service_index: 0,
+            addr: [0; 16],
+            sequence: 1,
+            signature: [0; 64],
+        };
+        state.registry.insert(
+            [0xbb; 32],
+            summit_services::PeerEntry::from_first_announcement(
+                std::net::Ipv6Addr::LOCALHOST,
+                &ann,
+            ),
+        );
+        let req = |force| compute::ComputeSubmitRequest {
+            to: "bb".repeat(32),
+            payload: serde_json::json!({ "run": "echo hi" }),
+            priority: 0,
+            force,
+        };
+
+        let result = compute::handle_compute_submit(State(state.clone()), Json(req(false))).await;
+        let Err(ApiError { status, code, .. }) = result else {
+            panic!("expected error");
+        };
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(code, "compute_not_offered");
+        assert!(state.compute_store.all_tasks().is_empty());
+
+        // Still announcing more services: not yet known to lack compute.
+        state
+            .registry
+            .get_mut(&[0xbb; 32])
+            .unwrap()
+            .expected_service_count = 2;
+        assert!(
+            compute::handle_compute_submit(State(state.clone()), Json(req(false)))
+                .await
+                .is_ok()
+        );
+
+        state
+            .registry
+            .get_mut(&[0xbb; 32])
+            .unwrap()
+            .expected_service_count = 1;
+        assert!(
+            compute::handle_compute_submit(State(state.clone()), Json(req(true)))
+                .await
+                .is_ok()
+        );
+        assert_eq!(state.compute_store.all_tasks().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn compute_cancel_local_task_notifies_worker() {
+        let (chunk_tx, mut chunk_rx) = tokio::sync::mpsc::channel(8);
+        let state = ApiState {
+            chunk_tx,
+            ..test_state()
+        };
+        let worker = [0xBB; 32];
+        state.compute_store.track_submitted(
+            worker,
+            summit_services::TaskSubmit {
+                task_id: "t-cancel".to_string(),
+                sender: "a".repeat(64),
+                timestamp: 100,
+                payload: serde_json::json!({ "run": "sleep 60" }),
+                priority: 0,
+            },
+        );
+
+        let req = compute::ComputeCancelRequest {
+            task_id: "t-cancel".to_string(),
+        };
+        let Ok(Json(resp)) = compute::handle_compute_cancel(State(state.clone()), Json(req)).await
+        else {
+            panic!("expected Ok");
+        };
+        assert_eq!(resp.status, "Cancelled");
+        assert_eq!(
+            state.compute_store.get_task("t-cancel").unwrap().status,
+            summit_services::TaskStatus::Cancelled
+        );
+
+        let (target, chunk) = chunk_rx.try_recv().unwrap();
+        assert!(matches!(target, SendTarget::Peer { public_key } if public_key == worker));
+        let env: summit_services::ComputeEnvelope = serde_json::from_slice(&chunk.payload).unwrap();
+        assert_eq!(env.msg_type, "task_cancel");
+
+        // A second cancel conflicts.
+        let req = compute::ComputeCancelRequest {
+            task_id: "t-cancel".to_string(),
+        };
+        let Err(ApiError { status, .. }) =
+            compute::handle_compute_cancel(State(state), Json(req)).await
+        else {
+            panic!("expected error");
+        };
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn compute_cancel_unknown_task() {
+        let state = test_state();
+        let req = compute::ComputeCancelRequest {
+            task_id: "nope".to_string(),
+        };
+        let Err(ApiError { status, .. }) =
+            compute::handle_compute_cancel(State(state), Json(req)).await
+        else {
+            panic!("expected error");
+        };
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
     // ── message handler tests ────────────────────────────────────────────
 
     #[tokio::test]
@@ -267,21 +690,126 @@ mod tests {
                 sender: "a".repeat(64),
                 timestamp: 100,
                 payload: serde_json::json!({ "text": "hi" }),
+                group_id: None,
+                expires_at: None,
             },
         );
         let peer_hex = "cc".repeat(32);
-        let Ok(Json(resp)) = messages::handle_get_messages(State(state), Path(peer_hex)).await
+        let Ok(Json(resp)) =
+            messages::handle_get_messages(State(state), Path(peer_hex), Query(Default::default()))
+                .await
         else {
             panic!("expected Ok");
         };
         assert_eq!(resp.messages.len(), 1);
     }
 
+    #[tokio::test]
+    async fn inbox_merges_peers_newest_first_and_pages() {
+        let state = test_state();
+        let envelope = |id: &str, timestamp| summit_services::MessageEnvelope {
+            msg_id: id.into(),
+            msg_type: "text".into(),
+            sender: "a".repeat(64),
+            timestamp,
+            payload: serde_json::json!({ "text": id }),
+            group_id: None,
+            expires_at: None,
+        };
+        state.message_store.add([0xAA; 32], envelope("m1", 100));
+        state.message_store.add([0xBB; 32], envelope("m2", 300));
+        state.message_store.add([0xAA; 32], envelope("m3", 200));
+
+        let inbox = |limit, offset| {
+            messages::handle_inbox(
+                State(state.clone()),
+                Query(messages::InboxQuery {
+                    limit: Some(limit),
+                    offset: Some(offset),
+                }),
+            )
+        };
+        let Json(resp) = inbox(2, 0).await;
+        assert_eq!(resp.total, 3);
+        let ids: Vec<_> = resp
+            .messages
+            .iter()
+            .map(|m| m.message.msg_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["m2", "m3"]);
+        assert_eq!(resp.messages[0].peers, vec!["bb".repeat(32)]);
+        assert_eq!(resp.messages[1].message.to, "aa".repeat(32));
+
+        let Json(resp) = inbox(2, 2).await;
+        assert_eq!(resp.messages.len(), 1);
+        assert_eq!(resp.messages[0].message.msg_id, "m1");
+
+        let Json(resp) = inbox(100_000, 0).await;
+        assert_eq!(resp.limit, messages::MAX_INBOX_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn unread_counts_reset_on_read() {
+        let state = test_state();
+        let peer = [0x11; 32];
+        for (id, timestamp) in [("m1", 100), ("m2", 200)] {
+            state.message_store.add(
+                peer,
+                summit_services::MessageEnvelope {
+                    msg_id: id.into(),
+                    msg_type: "text".into(),
+                    sender: hex::encode(peer),
+                    timestamp,
+                    payload: serde_json::json!({ "text": id }),
+                    group_id: None,
+                    expires_at: None,
+                },
+            );
+        }
+        let peer_hex = hex::encode(peer);
+
+        let Json(summary) = messages::handle_message_summary(State(state.clone())).await;
+        assert_eq!(summary.unread_total, 2);
+        assert_eq!(summary.peers[0].peer_pubkey, peer_hex);
+        assert_eq!((summary.peers[0].messages, summary.peers[0].unread), (2, 2));
+
+        // Peeking leaves them unread; a plain fetch marks them read.
+        let get = |mark_read| {
+            messages::handle_get_messages(
+                State(state.clone()),
+                Path(peer_hex.clone()),
+                Query(messages::GetMessagesQuery { mark_read }),
+            )
+        };
+        let Ok(Json(resp)) = get(false).await else {
+            panic!("expected Ok");
+        };
+        assert_eq!(resp.unread, 2);
+        let Ok(Json(resp)) = get(true).await else {
+            panic!("expected Ok");
+        };
+        assert_eq!(resp.unread, 2);
+        let Json(summary) = messages::handle_message_summary(State(state.clone())).await;
+        assert_eq!(summary.unread_total, 0);
+        assert_eq!(summary.peers[0].messages, 2);
+
+        let Ok(Json(resp)) =
+            messages::handle_mark_read(State(state.clone()), Path(peer_hex.clone())).await
+        else {
+            panic!("expected Ok");
+        };
+        assert_eq!(resp.cleared, 0);
+    }
+
     #[tokio::test]
     async fn get_messages_invalid_hex() {
         let state = test_state();
-        let Err((status, _)) =
-            messages::handle_get_messages(State(state), Path("nope".into())).await
+        let Err(ApiError { status, .. }) = messages::handle_get_messages(
+            State(state),
+            Path("nope".into()),
+            Query(Default::default()),
+        )
+        .await
         else {
             panic!("expected error");
         };
@@ -293,8 +821,9 @@ mod tests {
         let state = test_state();
         let peer_hex = "dd".repeat(32);
         let req = messages::SendMessageRequest {
-            to: peer_hex,
+            to: peer_hex.into(),
             text: "hello world".into(),
+            ..Default::default()
         };
         let Ok(Json(resp)) = messages::handle_send_message(State(state.clone()), Json(req)).await
         else {
@@ -308,6 +837,142 @@ mod tests {
         assert_eq!(msgs.len(), 1);
     }
 
+    #[tokio::test]
+    async fn send_message_typed_content() {
+        let state = test_state();
+        let req = messages::SendMessageRequest {
+            to: "dd".repeat(32).into(),
+            content_type: Some("markdown".into()),
+            text: "**hi**".into(),
+            ..Default::default()
+        };
+        let Ok(_) = messages::handle_send_message(State(state.clone()), Json(req)).await else {
+            panic!("expected Ok");
+        };
+
+        let Ok(Json(resp)) = messages::handle_get_messages(
+            State(state.clone()),
+            Path("dd".repeat(32)),
+            Query(Default::default()),
+        )
+        .await
+        else {
+            panic!("expected Ok");
+        };
+        assert_eq!(resp.messages[0].msg_type, "markdown");
+        assert_eq!(resp.messages[0].content["content_type"], "markdown");
+        assert_eq!(resp.messages[0].content["text"], "**hi**");
+
+        let req = messages::SendMessageRequest {
+            to: "dd".repeat(32).into(),
+            content_type: Some("blob".into()),
+            mime_type: "image/png".into(),
+            data: "00".repeat(summit_services::MAX_INLINE_BLOB + 1),
+            ..Default::default()
+        };
+        let Err(ApiError { status, .. }) =
+            messages::handle_send_message(State(state), Json(req)).await
+        else {
+            panic!("expected error");
+        };
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn send_message_too_large_is_rejected() {
+        let state = test_state();
+        let req = messages::SendMessageRequest {
+            to: "dd".repeat(32).into(),
+            text: "x".repeat(summit_services::MAX_MESSAGE_BYTES),
+            ..Default::default()
+        };
+        let Err(ApiError { status, .. }) =
+            messages::handle_send_message(State(state.clone()), Json(req)).await
+        else {
+            panic!("expected error");
+        };
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(state.message_store.get(&[0xDD; 32]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn send_message_to_group_stores_once() {
+        let state = test_state();
+        let req: messages::SendMessageRequest = serde_json::from_value(serde_json::json!({
+            "to": ["dd".repeat(32), "ee".repeat(32), "dd".repeat(32)],
+            "text": "hi all",
+        }))
+        .unwrap();
+        let Ok(Json(resp)) = messages::handle_send_message(State(state.clone()), Json(req)).await
+        else {
+            panic!("expected Ok");
+        };
+        let group_id = resp.group_id.expect("group message");
+
+        for peer in [[0xDD; 32], [0xEE; 32]] {
+            let msgs = state.message_store.get(&peer);
+            assert_eq!(msgs.len(), 1);
+            assert_eq!(msgs[0].group_id.as_deref(), Some(group_id.as_str()));
+        }
+        assert_eq!(state.message_store.get_group(&group_id).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn short_ttl_message_vanishes() {
+        let state = test_state();
+        let req = messages::SendMessageRequest {
+            to: "dd".repeat(32).into(),
+            text: "gone soon".into(),
+            ttl_secs: Some(1),
+            ..Default::default()
+        };
+        let Ok(_) = messages::handle_send_message(State(state.clone()), Json(req)).await else {
+            panic!("expected Ok");
+        };
+
+        let list = || {
+            messages::handle_get_messages(
+                State(state.clone()),
+                Path("dd".repeat(32)),
+                Query(Default::default()),
+            )
+        };
+        let Ok(Json(resp)) = list().await else {
+            panic!("expected Ok");
+        };
+        assert_eq!(resp.messages.len(), 1);
+        assert!(resp.messages[0].expires_at.is_some());
+
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        let Ok(Json(resp)) = list().await else {
+            panic!("expected Ok");
+        };
+        assert!(resp.messages.is_empty());
+        assert_eq!(state.message_store.purge_expired(), 1);
+    }
+
+    #[tokio::test]
+    async fn typing_repeat_is_rate_limited() {
+        let state = test_state();
+        let req = || messages::TypingRequest {
+            to: "ee".repeat(32),
+            typing: true,
+        };
+        let Ok(Json(resp)) = messages::handle_typing(State(state.clone()), Json(req())).await
+        else {
+            panic!("expected Ok");
+        };
+        assert!(resp.sent);
+        assert!(state.message_store.get(&[0xee; 32]).is_empty());
+
+        let Err(ApiError { status, .. }) =
+            messages::handle_typing(State(state.clone()), Json(req())).await
+        else {
+            panic!("expected Err");
+        };
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
+
     // ── trust handler tests ──────────────────────────────────────────────
 
     #[tokio::test]
@@ -347,6 +1012,7 @@ mod tests {
             0,
             [0u8; 32],
             bytes::Bytes::from_static(b"data"),
+            false,
         );
         assert_eq!(state.untrusted_buffer.count(&peer), 1);
 
@@ -361,6 +1027,35 @@ mod tests {
         assert_eq!(state.untrusted_buffer.count(&peer), 0);
     }
 
+    #[tokio::test]
+    async fn trust_receive_only_flushes_buffer_and_lists_level() {
+        let state = test_state();
+        let peer = [0x22; 32];
+        state.untrusted_buffer.add(
+            peer,
+            [0u8; 32],
+            0,
+            [0u8; 32],
+            bytes::Bytes::from_static(b"data"),
+            false,
+        );
+
+        let req = trust::TrustReceiveOnlyRequest {
+            public_key: "22".repeat(32),
+        };
+        let Ok(Json(resp)) =
+            trust::handle_trust_receive_only(State(state.clone()), Json(req)).await
+        else {
+            panic!("expected Ok");
+        };
+        assert_eq!(resp.flushed_chunks, 1);
+        assert_eq!(state.untrusted_buffer.count(&peer), 0);
+
+        let Json(list) = trust::handle_trust_list(State(state)).await;
+        assert_eq!(list.rules.len(), 1);
+        assert_eq!(list.rules[0].level, "ReceiveOnly");
+    }
+
     #[tokio::test]
     async fn trust_pending_shows_buffered_peers() {
         let state = test_state();
@@ -371,6 +1066,7 @@ mod tests {
             0,
             [0u8; 32],
             bytes::Bytes::from_static(b"x"),
+            false,
         );
         let Json(resp) = trust::handle_trust_pending(State(state)).await;
         assert_eq!(resp.peers.len(), 1);
@@ -382,8 +1078,24 @@ mod tests {
     #[tokio::test]
     async fn session_drop_invalid_id() {
         let state = test_state();
-        match sessions::handle_session_drop(State(state), Path("bad".into())).await {
-            Err((status, _)) => assert_eq!(status, StatusCode::BAD_REQUEST),
+        let query = Query(sessions::SessionDropQuery { cooldown_secs: 0 });
+        match sessions::handle_session_drop(State(state), Path("bad".into()), query).await {
+            Err(ApiError { status, .. }) => assert_eq!(status, StatusCode::BAD_REQUEST),
+            Ok(_) => panic!("expected error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn session_drop_cooldown_is_capped() {
+        let state = test_state();
+        let query = Query(sessions::SessionDropQuery {
+            cooldown_secs: 24 * 60 * 60 + 1,
+        });
+        match sessions::handle_session_drop(State(state), Path("00".repeat(32)), query).await {
+            Err(ApiError { status, code, .. }) => {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                assert_eq!(code, "invalid_cooldown");
+            }
             Ok(_) => panic!("expected error"),
         }
     }
@@ -393,7 +1105,126 @@ mod tests {
         let state = test_state();
         let id_hex = "00".repeat(32);
         match sessions::handle_session_inspect(State(state), Path(id_hex)).await {
-            Err((status, _)) => assert_eq!(status, StatusCode::NOT_FOUND),
+            Err(ApiError { status, .. }) => assert_eq!(status, StatusCode::NOT_FOUND),
+            Ok(_) => panic!("expected error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn session_connect_reports_initiator_outcome() {
+        use summit_services::ConnectOutcome;
+
+        let (connect_tx, mut connect_rx) = tokio::sync::mpsc::channel(1);
+        let state = ApiState {
+            connect_tx,
+            ..test_state()
+        };
+        // Stand-in initiator: knows peer 0x11, and is full for peer 0x33.
+        tokio::spawn(async move {
+            while let Some((peer, reply)) = connect_rx.recv().await {
+                let outcome = if peer == [0x11; 32] {
+                    ConnectOutcome::Initiated
+                } else if peer == [0x33; 32] {
+                    ConnectOutcome::SessionLimit
+                } else {
+                    ConnectOutcome::UnknownPeer
+                };
+                let _ = reply.send(outcome);
+            }
+        });
+        let req = |key: &str| sessions::ConnectRequestJson {
+            public_key: key.to_string(),
+        };
+        state.session_cooldowns.start(
+            [0x11; 32],
+            std::net::Ipv6Addr::LOCALHOST,
+            std::time::Duration::from_secs(60),
+        );
+
+        match sessions::handle_session_connect(State(state.clone()), Json(req(&"11".repeat(32))))
+            .await
+        {
+            Ok(Json(resp)) => {
+                assert!(resp.initiated);
+                assert_eq!(resp.status, "initiated");
+            }
+            Err(e) => panic!("expected Ok, got {}", e.code),
+        }
+        // An explicit connect lifts a drop cool-down.
+        assert!(state.session_cooldowns.remaining(&[0x11; 32]).is_none());
+        match sessions::handle_session_connect(State(state.clone()), Json(req(&"22".repeat(32))))
+            .await
+        {
+            Err(ApiError { status, code, .. }) => {
+                assert_eq!(status, StatusCode::NOT_FOUND);
+                assert_eq!(code, "peer_not_found");
+            }
+            Ok(_) => panic!("expected error"),
+        }
+        match sessions::handle_session_connect(State(state.clone()), Json(req(&"33".repeat(32))))
+            .await
+        {
+            Err(ApiError { status, code, .. }) => {
+                assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+                assert_eq!(code, "session_limit");
+            }
+            Ok(_) => panic!("expected error"),
+        }
+        match sessions::handle_session_connect(State(state), Json(req("bad"))).await {
+            Err(ApiError { status, .. }) => assert_eq!(status, StatusCode::BAD_REQUEST),
+            Ok(_) => panic!("expected error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn handshakes_report_phase_and_age() {
+        use std::time::Duration;
+        use summit_services::{HandshakeInfo, HandshakePhase};
+
+        let mut state = test_state();
+        let info = |phase, age_ms| HandshakeInfo {
+            peer_addr: "fe80::1".parse().unwrap(),
+            peer_pubkey: [7u8; 32],
+            service_hash: summit_core::wire::file_transfer_hash(),
+            phase,
+            age: Duration::from_millis(age_ms),
+        };
+        state.handshakes = Arc::new(FixedHandshakes(vec![
+            info(HandshakePhase::Initiator, 100),
+            info(HandshakePhase::ResponderWaitingChunk, 2500),
+        ]));
+
+        let Json(resp) = sessions::handle_handshakes(State(state)).await;
+        assert_eq!(resp.handshakes.len(), 2);
+        // Longest-stuck first
+        assert_eq!(resp.handshakes[0].phase, "responder_waiting_chunk");
+        assert_eq!(resp.handshakes[0].age_ms, 2500);
+        assert_eq!(resp.handshakes[0].service, "file_transfer");
+        assert_eq!(resp.handshakes[1].phase, "initiator");
+        assert_eq!(resp.handshakes[1].peer_pubkey, hex::encode([7u8; 32]));
+        assert_eq!(resp.timeout_secs, summit_core::wire::HANDSHAKE_TIMEOUT_SECS);
+    }
+
+    #[tokio::test]
+    async fn session_list_empty() {
+        let state = test_state();
+        let query = sessions::SessionListQuery {
+            peer: Some("ab".repeat(32)),
+        };
+        let Json(resp) = sessions::handle_session_list(State(state), Query(query))
+            .await
+            .unwrap();
+        assert!(resp.sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn session_list_invalid_peer() {
+        let state = test_state();
+        let query = sessions::SessionListQuery {
+            peer: Some("nothex".into()),
+        };
+        match sessions::handle_session_list(State(state), Query(query)).await {
+            Err(ApiError { status, .. }) => assert_eq!(status, StatusCode::BAD_REQUEST),
             Ok(_) => panic!("expected error"),
         }
     }
@@ -406,6 +1237,43 @@ mod tests {
         let Json(resp) = status::handle_cache(State(state)).await;
         assert_eq!(resp.chunks, 0);
         assert_eq!(resp.bytes, 0);
+        assert_eq!(resp.max_bytes, 0);
+        assert_eq!(resp.utilization_pct, None);
+        assert_eq!(resp.root, None);
+    }
+
+    #[tokio::test]
+    async fn cache_list_pages_and_caps_limit() {
+        let state = test_state();
+        for i in 0u8..3 {
+            let data = vec![i; 8];
+            state
+                .cache
+                .put(&summit_core::crypto::hash(&data), &data)
+                .unwrap();
+        }
+
+        let query = status::CacheListQuery {
+            limit: Some(2),
+            offset: Some(0),
+        };
+        let Json(resp) = status::handle_cache_list(State(state.clone()), Query(query)).await;
+        assert_eq!(resp.total, 3);
+        assert_eq!(resp.chunks.len(), 2);
+        assert_eq!(
+            resp.chunks[0].content_hash,
+            hex::encode(summit_core::crypto::hash(&[2u8; 8]))
+        );
+        assert_eq!(resp.chunks[0].bytes, 8);
+        assert!(resp.chunks[0].last_access_ms > 0);
+
+        let query = status::CacheListQuery {
+            limit: Some(usize::MAX),
+            offset: Some(2),
+        };
+        let Json(resp) = status::handle_cache_list(State(state), Query(query)).await;
+        assert_eq!(resp.limit, status::MAX_CACHE_LIST_LIMIT);
+        assert_eq!(resp.chunks.len(), 1);
     }
 
     #[tokio::test]
@@ -415,11 +1283,133 @@ mod tests {
         assert_eq!(resp.cleared, 0);
     }
 
+    #[tokio::test]
+    async fn delivery_reports_multipath_and_duplicates() {
+        let state = test_state();
+        state.delivery.record([1u8; 32], "[fe80::1]:9000".into());
+        state.delivery.record([1u8; 32], "[fe80::2]:9000".into());
+        state.delivery.record([2u8; 32], "[fe80::1]:9000".into());
+        state.delivery.record([2u8; 32], "[fe80::1]:9000".into());
+
+        let Json(resp) = status::handle_delivery(State(state)).await;
+        assert_eq!(resp.tracked_chunks, 2);
+        assert_eq!(resp.multipath_chunks, 1);
+        assert_eq!(resp.duplicate_deliveries, 2);
+
+        let multi = resp
+            .chunks
+            .iter()
+            .find(|c| c.content_hash == hex::encode([1u8; 32]))
+            .unwrap();
+        assert!(multi.multipath);
+        assert_eq!(multi.paths.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn identity_reports_own_public_key() {
+        let state = test_state();
+        let expected = hex::encode(state.keypair.public);
+        let Json(resp) = status::handle_identity(State(state)).await;
+        assert_eq!(resp.public_key, expected);
+        assert_eq!(resp.wire_version, summit_core::wire::WIRE_VERSION);
+        assert_eq!(resp.interface, "veth-test");
+        assert_eq!(resp.services, vec!["messaging", "compute"]);
+    }
+
+    #[tokio::test]
+    async fn info_reports_version_and_uptime() {
+        let state = ApiState {
+            started_at: std::time::Instant::now() - std::time::Duration::from_secs(90),
+            ..test_state()
+        };
+        let Json(resp) = status::handle_info(State(state)).await;
+        assert_eq!(resp.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(resp.wire_version, summit_core::wire::WIRE_VERSION);
+        assert_eq!(resp.interface, "veth-test");
+        assert!(resp.uptime_secs >= 90);
+    }
+
+    #[tokio::test]
+    async fn metrics_reports_dropped_announcements() {
+        use std::sync::atomic::Ordering;
+
+        let state = test_state();
+        state.announcements.rate_limited.store(3, Ordering::Relaxed);
+        state.announcements.invalid.store(2, Ordering::Relaxed);
+        state.announcements.stale.store(4, Ordering::Relaxed);
+        let (_, body) = status::handle_metrics(State(state)).await;
+        assert!(body.contains("summit_announcements_dropped_total{reason=\"rate_limited\"} 3\n"));
+        assert!(body.contains("summit_announcements_dropped_total{reason=\"invalid\"} 2\n"));
+        assert!(body.contains("summit_announcements_dropped_total{reason=\"stale\"} 4\n"));
+        assert!(body.contains("summit_peers_discovered 0\n"));
+        assert!(body.contains("# TYPE summit_session_decrypt_drops_total counter\n"));
+        assert!(body.contains("# TYPE summit_session_receive_queue_drops_total counter\n"));
+    }
+
+    #[tokio::test]
+    async fn peers_filter_by_service() {
+        use summit_core::wire::{compute_hash, messaging_hash, CapabilityAnnouncement};
+
+        let state = test_state();
+        for (key, service) in [(1u8, compute_hash()), (2, messaging_hash())] {
+            let ann = CapabilityAnnouncement {
+                service_hash: service,
+                public_key: [key; 32],
+                version: 1,
+                session_port: 9001,
+                chunk_port: 0,
+                contract: summit_core::wire::Contract::Bulk as u8,
+                flags: 0,
+                service_count: 1,
+                service_index: 0,
+                addr: [0; 16],
+                sequence: 1,
+                signature: [0; 64],
+            };
+            let entry = summit_services::PeerEntry::from_first_announcement(
+                std::net::Ipv6Addr::LOCALHOST,
+                &ann,
+            );
+            state.registry.insert([key; 32], entry);
+        }
+
+        let peers = |service: Option<&str>| {
+            let state = state.clone();
+            let query = status::PeersQuery {
+                service: service.map(String::from),
+            };
+            async move {
+                let Json(resp) = status::handle_peers(State(state), Query(query)).await;
+                resp.peers
+                    .into_iter()
+                    .map(|p| p.public_key)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(peers(None).await.len(), 2);
+        let compute = vec![hex::encode([1u8; 32])];
+        assert_eq!(peers(Some("compute")).await, compute);
+        assert_eq!(peers(Some("summit.compute")).await, compute);
+        assert_eq!(peers(Some(&hex::encode(compute_hash()))).await, compute);
+        assert!(peers(Some("stream_udp")).await.is_empty());
+
+        let Json(resp) = status::handle_peers(State(state), Query(Default::default())).await;
+        let peer = resp
+            .peers
+            .iter()
+            .find(|p| p.public_key == compute[0])
+            .unwrap();
+        assert_eq!(peer.service_details.len(), 1);
+        assert_eq!(peer.service_details[0].name, "compute");
+        assert_eq!(peer.service_details[0].contract, "Bulk");
+    }
+
     #[tokio::test]
     async fn services_returns_list_with_enabled() {
         let state = test_state();
         let Json(resp) = status::handle_services(State(state)).await;
-        assert_eq!(resp.services.len(), 4);
+        assert_eq!(resp.services.len(), 5);
 
         let messaging = resp
             .services
@@ -430,6 +1420,7 @@ mod tests {
 
         let compute = resp.services.iter().find(|s| s.name == "compute").unwrap();
         assert!(compute.enabled);
+        assert_eq!(compute.active_sessions, 0);
 
         let stream = resp
             .services
@@ -437,11 +1428,322 @@ mod tests {
             .find(|s| s.name == "stream_udp")
             .unwrap();
         assert!(!stream.enabled);
+
+        let sync = resp.services.iter().find(|s| s.name == "sync").unwrap();
+        assert!(!sync.enabled);
+        assert_eq!(sync.contract, "Background");
     }
 
     #[tokio::test]
-    async fn schema_list_returns_five() {
+    async fn service_toggle_switches_runtime_services_only() {
+        let state = test_state();
+        let mut names = state.enabled_services.subscribe();
+        let toggle = |name: &str, enabled| {
+            status::handle_service_toggle(
+                State(state.clone()),
+                Path(name.to_string()),
+                Json(status::ServiceToggleRequest { enabled }),
+            )
+        };
+
+        let Json(resp) = toggle("messaging", false).await.unwrap();
+        assert!(resp.changed);
+        assert!(names.has_changed().unwrap());
+        assert_eq!(*names.borrow_and_update(), vec!["compute".to_string()]);
+
+        let Json(resp) = toggle("messaging", false).await.unwrap();
+        assert!(!resp.changed);
+        assert!(!names.has_changed().unwrap());
+
+        let Json(resp) = toggle("stream_udp", true).await.unwrap();
+        assert!(resp.enabled && resp.changed);
+        let Json(resp) = status::handle_services(State(state.clone())).await;
+        let enabled = |name| resp.services.iter().any(|s| s.name == name && s.enabled);
+        assert!(enabled("stream_udp"));
+        assert!(!enabled("messaging"));
+
+        match toggle("compute", false).await {
+            Err(ApiError { code, .. }) => assert_eq!(code, "service_needs_restart"),
+            Ok(_) => panic!("compute switched at runtime"),
+        }
+        match toggle("sync", true).await {
+            Err(ApiError { code, .. }) => assert_eq!(code, "service_needs_restart"),
+            Ok(_) => panic!("sync switched at runtime"),
+        }
+        assert!(enabled("compute"));
+        match toggle("telepathy", true).await {
+            Err(ApiError { status, .. }) => assert_eq!(status, StatusCode::NOT_FOUND),
+            Ok(_) => panic!("expected error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn schema_list_returns_every_known_schema() {
         let Json(resp) = status::handle_schema_list().await;
-        assert_eq!(resp.schemas.len(), 5);
+        assert_eq!(
+            resp.schemas.len(),
+            summit_services::KnownSchema::all().len()
+        );
+
+        let message = resp
+            .schemas
+            .iter()
+            .find(|s| s.name == "summit.messaging")
+            .unwrap();
+        assert_eq!(message.contract, "Bulk");
+        let recovery = resp
+            .schemas
+            .iter()
+            .find(|s| s.name == "summit.recovery")
+            .unwrap();
+        assert_eq!(recovery.contract, "Realtime");
+        assert_eq!(recovery.type_tag, None);
+    }
+
+    // ── auth tests ──────────────────────────────────────────────────────
+
+    fn api_config(token: &str, max_requests_per_sec: u32) -> summit_core::config::ApiConfig {
+        summit_core::config::ApiConfig {
+            token: token.into(),
+            max_requests_per_sec,
+            ..Default::default()
+        }
+    }
+
+    async fn request_status(
+        app: &axum::Router,
+        method: &str,
+        path: &str,
+        auth: Option<&str>,
+    ) -> axum::http::StatusCode {
+        use tower::ServiceExt;
+
+        let mut req = axum::http::Request::builder().method(method).uri(path);
+        if let Some(auth) = auth {
+            req = req.header(axum::http::header::AUTHORIZATION, auth);
+        }
+        app.clone()
+            .oneshot(req.body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn token_required_when_configured() {
+        let app = crate::router(test_state(), &api_config("s3cret", 0));
+        assert_eq!(
+            request_status(&app, "GET", "/api/status", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            request_status(&app, "GET", "/api/status", Some("Bearer wrong")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            request_status(&app, "GET", "/api/status", Some("Bearer s3cret")).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn no_token_configured_allows_all() {
+        let app = crate::router(test_state(), &api_config("", 0));
+        assert_eq!(
+            request_status(&app, "GET", "/api/status", None).await,
+            StatusCode::OK
+        );
+    }
+
+    // ── error body tests ────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn errors_are_json_with_code_and_message() {
+        use tower::ServiceExt;
+
+        let app = crate::router(test_state(), &api_config("", 0));
+        let req = axum::http::Request::builder()
+            .uri("/api/sessions?peer=zz")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "invalid_pubkey");
+        assert_eq!(
+            body["error"]["message"],
+            "public key must be 32 bytes (64 hex characters), got 2 characters"
+        );
+    }
+
+    // ── /send tests ─────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn broadcast_without_trusted_peers_is_refused() {
+        use tower::ServiceExt;
+
+        let app = crate::router(test_state(), &api_config("", 0));
+        let body = "--X\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\r\n\
+            hello\r\n\
+            --X--\r\n";
+        let req = axum::http::Request::builder()
+            .method("POST")
+            .uri("/api/send")
+            .header("content-type", "multipart/form-data; boundary=X")
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "no_trusted_peers");
+    }
+
+    // ── /files tests ────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn files_reports_assemblies_against_cap() {
+        let dir = std::env::temp_dir().join(format!("summit-api-asm-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut state = test_state();
+        state.reassembler =
+            Arc::new(summit_services::FileReassembler::new(dir.clone()).with_max_assemblies(1));
+
+        for name in ["a.bin", "b.bin"] {
+            let metadata = summit_services::FileMetadata {
+                filename: name.into(),
+                total_bytes: 1,
+                chunk_hashes: vec![[1u8; 32]],
+                task_id: None,
+                fec: None,
+            };
+            state.reassembler.add_metadata(metadata, [0xAA; 32]).await;
+        }
+
+        let Json(resp) = files::handle_files(State(state)).await;
+        assert_eq!(resp.in_progress, vec!["a.bin"]);
+        assert_eq!(resp.active_assemblies, 1);
+        assert_eq!(resp.max_concurrent_assemblies, 1);
+        assert_eq!(resp.reordered_chunks, 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // ── file download tests ─────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn file_get_decrypts_encrypted_files() {
+        use summit_core::crypto::StorageCipher;
+        use tower::ServiceExt;
+
+        let dir = std::env::temp_dir().join(format!("summit-api-files-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let cipher = StorageCipher::new([9u8; 32]);
+        std::fs::write(dir.join("secret.txt"), cipher.seal(b"decrypted on read")).unwrap();
+
+        let mut state = test_state();
+        state.reassembler =
+            Arc::new(summit_services::FileReassembler::new(dir.clone()).with_encryption(cipher));
+        let app = crate::router(state, &api_config("", 0));
+
+        let get = |uri: &str| {
+            axum::http::Request::builder()
+                .uri(uri)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(get("/api/files/secret.txt"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"decrypted on read");
+
+        let resp = app
+            .clone()
+            .oneshot(get("/api/files/missing.txt"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = app.oneshot(get("/api/files/a/../../etc")).await.unwrap();
+        assert_ne!(resp.status(), StatusCode::OK);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // ── health tests ────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn health_ok_ready_waits_for_sockets() {
+        let state = test_state();
+        let Json(health) = status::handle_health().await;
+        assert_eq!(health.status, "ok");
+
+        let err = status::handle_ready(State(state.clone()))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.status, StatusCode::SERVICE_UNAVAILABLE);
+
+        state
+            .ready
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        let Json(ready) = status::handle_ready(State(state)).await.unwrap();
+        assert_eq!(ready.status, "ready");
+    }
+
+    // ── rate limit tests ────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn rate_limit_separates_reads_and_writes() {
+        let app = crate::router(test_state(), &api_config("", 2));
+        for _ in 0..2 {
+            assert_eq!(
+                request_status(&app, "GET", "/api/status", None).await,
+                StatusCode::OK
+            );
+        }
+        assert_eq!(
+            request_status(&app, "GET", "/api/status", None).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        // Reads exhausted; writes and probes still pass.
+        assert_ne!(
+            request_status(&app, "POST", "/api/cache/clear", None).await,
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        for _ in 0..5 {
+            assert_eq!(
+                request_status(&app, "GET", "/api/health", None).await,
+                StatusCode::OK
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limit_off_by_default() {
+        let app = crate::router(test_state(), &api_config("", 0));
+        for _ in 0..20 {
+            assert_eq!(
+                request_status(&app, "GET", "/api/status", None).await,
+                StatusCode::OK
+            );
+        }
     }
 }
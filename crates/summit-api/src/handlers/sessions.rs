@@ -1,34 +1,243 @@
 //! /sessions handlers — session inspection and management.
 
-use axum::extract::{Path, State};
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::Json;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use summit_services::{ActiveSession, ConnectOutcome, DecryptDropCounts, TokenBucket};
+use tokio::sync::Mutex;
+
+use super::{parse_pubkey, parse_session_id, ApiError, ApiState};
+
+// ── /sessions (GET) ───────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct SessionListQuery {
+    /// Only sessions with this peer (hex public key).
+    pub peer: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SessionListResponse {
+    pub sessions: Vec<SessionInspectResponse>,
+}
+
+pub async fn handle_session_list(
+    State(state): State<ApiState>,
+    Query(query): Query<SessionListQuery>,
+) -> Result<Json<SessionListResponse>, ApiError> {
+    let peer = query.peer.as_deref().map(parse_pubkey).transpose()?;
+
+    // Bucket locks are awaited once the session table is let go.
+    let inspected: Vec<_> = state
+        .sessions
+        .iter()
+        .filter(|entry| peer.is_none_or(|pk| entry.value().meta.peer_pubkey == pk))
+        .map(|entry| (inspect(&state, entry.value()), buckets(entry.value())))
+        .collect();
+    let mut sessions = Vec::with_capacity(inspected.len());
+    for (mut session, session_buckets) in inspected {
+        session.buckets = bucket_stats(session_buckets).await;
+        sessions.push(session);
+    }
+    sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+
+    Ok(Json(SessionListResponse { sessions }))
+}
+
+// ── /handshakes ───────────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct HandshakesResponse {
+    pub handshakes: Vec<HandshakeJson>,
+    /// Handshakes older than this are discarded and retried.
+    pub timeout_secs: u64,
+}
+
+#[derive(Serialize)]
+pub struct HandshakeJson {
+    /// Peer link-local address — handshakes are tracked per address.
+    pub peer_addr: String,
+    pub peer_pubkey: String,
+    pub service: String,
+    /// initiator, responder, initiator_waiting_chunk or responder_waiting_chunk.
+    pub phase: String,
+    /// Time spent in this phase so far.
+    pub age_ms: u64,
+}
+
+pub async fn handle_handshakes(State(state): State<ApiState>) -> Json<HandshakesResponse> {
+    let mut handshakes: Vec<HandshakeJson> = state
+        .handshakes
+        .handshakes()
+        .await
+        .into_iter()
+        .map(|h| HandshakeJson {
+            peer_addr: h.peer_addr.to_string(),
+            peer_pubkey: hex::encode(h.peer_pubkey),
+            service: summit_core::wire::service_name(&h.service_hash)
+                .map(String::from)
+                .unwrap_or_else(|| hex::encode(&h.service_hash[..8])),
+            phase: h.phase.as_str().to_string(),
+            age_ms: h.age.as_millis() as u64,
+        })
+        .collect();
+    handshakes.sort_by_key(|h| std::cmp::Reverse(h.age_ms));
+
+    Json(HandshakesResponse {
+        handshakes,
+        timeout_secs: summit_core::wire::HANDSHAKE_TIMEOUT_SECS,
+    })
+}
+
+// ── /sessions/connect (POST) ──────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ConnectRequestJson {
+    pub public_key: String,
+}
+
+#[derive(Serialize)]
+pub struct ConnectResponse {
+    pub public_key: String,
+    /// Whether a handshake was started by this request.
+    pub initiated: bool,
+    /// initiated, connected (a session already exists) or in_progress.
+    pub status: &'static str,
+}
 
-use super::{parse_session_id, ApiState};
+/// How long to wait for the initiator to act on a connect request.
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Handshake with a known peer now, skipping the initiator tick and the
+/// lower-key-initiates rule for this one attempt.
+pub async fn handle_session_connect(
+    State(state): State<ApiState>,
+    Json(req): Json<ConnectRequestJson>,
+) -> Result<Json<ConnectResponse>, ApiError> {
+    let pubkey = parse_pubkey(&req.public_key)?;
+    let unavailable = || {
+        ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "initiator_unavailable",
+            "session initiator is not running",
+        )
+    };
+
+    // Asking for a session by hand overrides a drop cool-down.
+    state.session_cooldowns.clear(&pubkey);
+
+    let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+    tokio::time::timeout(CONNECT_TIMEOUT, state.connect_tx.send((pubkey, reply_tx)))
+        .await
+        .map_err(|_| unavailable())?
+        .map_err(|_| unavailable())?;
+    let outcome = tokio::time::timeout(CONNECT_TIMEOUT, reply_rx)
+        .await
+        .map_err(|_| unavailable())?
+        .map_err(|_| unavailable())?;
+
+    let status = match outcome {
+        ConnectOutcome::Initiated => "initiated",
+        ConnectOutcome::Connected => "connected",
+        ConnectOutcome::InProgress => "in_progress",
+        ConnectOutcome::UnknownPeer => {
+            return Err(ApiError::not_found(
+                "peer_not_found",
+                "peer not discovered — it must be announcing on the link",
+            ))
+        }
+        ConnectOutcome::Incompatible => {
+            return Err(ApiError::new(
+                StatusCode::CONFLICT,
+                "incompatible_version",
+                "peer's wire version is too old",
+            ))
+        }
+        ConnectOutcome::SessionLimit => {
+            return Err(ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "session_limit",
+                "network.max_sessions reached — drop a session first",
+            ))
+        }
+        ConnectOutcome::Failed => {
+            return Err(ApiError::internal(
+                "failed to send handshake, see daemon log",
+            ))
+        }
+    };
+
+    Ok(Json(ConnectResponse {
+        public_key: hex::encode(pubkey),
+        initiated: outcome == ConnectOutcome::Initiated,
+        status,
+    }))
+}
 
 // ── /sessions/:id (DELETE) ────────────────────────────────────────────────────
 
+/// Longest cool-down a drop may ask for; block the peer for longer.
+const MAX_DROP_COOLDOWN_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Deserialize)]
+pub struct SessionDropQuery {
+    /// Refuse handshakes with the session's peer, both ways, for this long.
+    #[serde(default)]
+    pub cooldown_secs: u64,
+}
+
 #[derive(Serialize)]
 pub struct SessionDropResponse {
     pub session_id: String,
     pub dropped: bool,
+    /// Cool-down started for the peer; 0 when none (or nothing was dropped).
+    pub cooldown_secs: u64,
 }
 
 pub async fn handle_session_drop(
     State(state): State<ApiState>,
     Path(session_id): Path<String>,
-) -> Result<Json<SessionDropResponse>, (StatusCode, String)> {
+    Query(query): Query<SessionDropQuery>,
+) -> Result<Json<SessionDropResponse>, ApiError> {
     let id = parse_session_id(&session_id)?;
-    let dropped = state.sessions.remove(&id).is_some();
+    if query.cooldown_secs > MAX_DROP_COOLDOWN_SECS {
+        return Err(ApiError::bad_request(
+            "invalid_cooldown",
+            format!(
+                "cooldown_secs is at most {MAX_DROP_COOLDOWN_SECS}; block the peer to keep it out longer"
+            ),
+        ));
+    }
+    let removed = state.sessions.remove(&id);
+    let dropped = removed.is_some();
 
-    if dropped {
-        tracing::info!(session_id = %session_id, "session dropped via API");
+    let mut cooldown_secs = 0;
+    if let Some((_, session)) = removed {
+        // Start the cool-down before closing, so the initiator can't get
+        // in first.
+        if query.cooldown_secs > 0 {
+            cooldown_secs = query.cooldown_secs;
+            let addr = match session.meta.peer_addr.ip() {
+                std::net::IpAddr::V6(ip) => ip,
+                std::net::IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+            };
+            state.session_cooldowns.start(
+                session.meta.peer_pubkey,
+                addr,
+                std::time::Duration::from_secs(cooldown_secs),
+            );
+        }
+        session.close();
+        tracing::info!(session_id = %session_id, reason = "api", cooldown_secs, "session dropped");
     }
 
     Ok(Json(SessionDropResponse {
         session_id,
         dropped,
+        cooldown_secs,
     }))
 }
 
@@ -43,23 +252,93 @@ pub struct SessionInspectResponse {
     pub chunk_port: u16,
     pub uptime_secs: u64,
     pub trust_level: String,
+    /// Wire protocol version negotiated with the peer.
+    pub wire_version: u32,
+    /// Services negotiated for this session.
+    pub services: Vec<SessionServiceJson>,
+    /// Packets received on this session that failed to decrypt, by reason.
+    pub decrypt_drops: DecryptDropCounts,
+    /// Chunks dropped because the session's handler queue was full.
+    pub receive_queue_drops: u64,
+    /// Send-side token buckets: Bulk, then Background.
+    pub buckets: Vec<BucketJson>,
+}
+
+#[derive(Serialize)]
+pub struct BucketJson {
+    pub contract: String,
+    /// Tokens available now; the send worker spends one per chunk,
+    /// whatever its size.
+    pub tokens: f64,
+    pub capacity: f64,
+    /// Tokens refilled per second.
+    pub rate: f64,
+    /// Chunks dropped for want of tokens.
+    pub denied: u64,
+}
+
+#[derive(Serialize)]
+pub struct SessionServiceJson {
+    pub name: String,
+    pub contract: String,
+    pub chunk_port: u16,
 }
 
 pub async fn handle_session_inspect(
     State(state): State<ApiState>,
     Path(session_id): Path<String>,
-) -> Result<Json<SessionInspectResponse>, (StatusCode, String)> {
+) -> Result<Json<SessionInspectResponse>, ApiError> {
     let id = parse_session_id(&session_id)?;
 
-    let session = state
-        .sessions
-        .get(&id)
-        .ok_or((StatusCode::NOT_FOUND, "session not found".to_string()))?;
+    let (mut resp, session_buckets) = {
+        let session = state
+            .sessions
+            .get(&id)
+            .ok_or_else(|| ApiError::not_found("session_not_found", "session not found"))?;
+        (inspect(&state, session.value()), buckets(session.value()))
+    };
+    resp.buckets = bucket_stats(session_buckets).await;
+
+    Ok(Json(resp))
+}
 
-    let meta = &session.value().meta;
+fn buckets(session: &ActiveSession) -> [Arc<Mutex<TokenBucket>>; 2] {
+    [session.bucket.clone(), session.background_bucket.clone()]
+}
+
+async fn bucket_stats(buckets: [Arc<Mutex<TokenBucket>>; 2]) -> Vec<BucketJson> {
+    let mut out = Vec::with_capacity(buckets.len());
+    for bucket in buckets {
+        let stats = bucket.lock().await.stats();
+        out.push(BucketJson {
+            contract: format!("{:?}", stats.contract),
+            tokens: stats.tokens,
+            capacity: stats.capacity,
+            rate: stats.rate,
+            denied: stats.denied,
+        });
+    }
+    out
+}
+
+fn inspect(state: &ApiState, session: &ActiveSession) -> SessionInspectResponse {
+    let meta = &session.meta;
     let trust_level = state.trust.check(&meta.peer_pubkey);
 
-    Ok(Json(SessionInspectResponse {
+    let mut services: Vec<SessionServiceJson> = meta
+        .active_services
+        .iter()
+        .map(|(hash, svc)| SessionServiceJson {
+            name: summit_core::wire::service_name(hash)
+                .map(String::from)
+                .unwrap_or_else(|| hex::encode(&hash[..8])),
+            contract: format!("{:?}", svc.contract),
+            chunk_port: svc.chunk_port,
+        })
+        .collect();
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+
+    SessionInspectResponse {
         session_id: hex::encode(meta.session_id),
         peer_addr: meta.peer_addr.to_string(),
         peer_pubkey: hex::encode(meta.peer_pubkey),
@@ -67,5 +346,12 @@ pub async fn handle_session_inspect(
         chunk_port: meta.chunk_port,
         uptime_secs: meta.established_at.elapsed().as_secs(),
         trust_level: format!("{:?}", trust_level),
-    }))
+        wire_version: meta.wire_version,
+        services,
+        decrypt_drops: session.decrypt_drops.counts(),
+        receive_queue_drops: session
+            .receive_queue_drops
+            .load(std::sync::atomic::Ordering::Relaxed),
+        buckets: Vec::new(),
+    }
 }
@@ -1,18 +1,163 @@
-//! /status, /peers, /cache, /services, /schema, /daemon/shutdown handlers.
+//! /health, /ready, /identity, /status, /peers, /cache, /cache/list,
+//! /delivery, /services, /schema, /daemon/shutdown handlers.
 
-use axum::extract::State;
+use std::sync::atomic::Ordering;
+
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
 use axum::Json;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use summit_core::wire::ServiceHash;
 use summit_services::KnownSchema;
 
-use super::ApiState;
+use super::{ApiError, ApiState};
+
+// ── /health, /ready ──────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct HealthResponse {
+    pub status: &'static str,
+}
+
+/// Liveness — the API only serves once the daemon's core tasks are spawned.
+pub async fn handle_health() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "ok" })
+}
+
+/// Readiness — 503 until the multicast listener and session socket are bound.
+pub async fn handle_ready(State(state): State<ApiState>) -> Result<Json<HealthResponse>, ApiError> {
+    if !state.ready.load(Ordering::Relaxed) {
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "not_ready",
+            "multicast listener not bound yet",
+        ));
+    }
+    Ok(Json(HealthResponse { status: "ready" }))
+}
+
+// ── /metrics ─────────────────────────────────────────────────────────────────
+
+/// Prometheus text exposition of daemon counters.
+pub async fn handle_metrics(
+    State(state): State<ApiState>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    use std::fmt::Write;
+
+    let ann = &state.announcements;
+    let mut body = format!(
+        "# HELP summit_peers_discovered Peers currently in the registry.\n\
+         # TYPE summit_peers_discovered gauge\n\
+         summit_peers_discovered {peers}\n\
+         # HELP summit_sessions_active Established sessions.\n\
+         # TYPE summit_sessions_active gauge\n\
+         summit_sessions_active {sessions}\n\
+         # HELP summit_announcements_dropped_total Announcement datagrams dropped by the listener.\n\
+         # TYPE summit_announcements_dropped_total counter\n\
+         summit_announcements_dropped_total{{reason=\"rate_limited\"}} {rate_limited}\n\
+         summit_announcements_dropped_total{{reason=\"invalid\"}} {invalid}\n\
+         summit_announcements_dropped_total{{reason=\"stale\"}} {stale}\n\
+         # HELP summit_announcements_refreshed_total Repeated announcements that only refreshed last_seen.\n\
+         # TYPE summit_announcements_refreshed_total counter\n\
+         summit_announcements_refreshed_total {refreshed}\n\
+         # HELP summit_cache_lookups_total Chunk cache lookups by outcome.\n\
+         # TYPE summit_cache_lookups_total counter\n\
+         summit_cache_lookups_total{{result=\"hit\"}} {cache_hits}\n\
+         summit_cache_lookups_total{{result=\"miss\"}} {cache_misses}\n",
+        peers = state.registry.len(),
+        sessions = state.sessions.len(),
+        rate_limited = ann.rate_limited.load(Ordering::Relaxed),
+        invalid = ann.invalid.load(Ordering::Relaxed),
+        stale = ann.stale.load(Ordering::Relaxed),
+        refreshed = ann.refreshed.load(Ordering::Relaxed),
+        cache_hits = state.cache.hits(),
+        cache_misses = state.cache.misses(),
+    );
+    body.push_str(
+        "# HELP summit_session_decrypt_drops_total Packets a session failed to decrypt, by reason.\n\
+         # TYPE summit_session_decrypt_drops_total counter\n",
+    );
+    for e in state.sessions.iter() {
+        let session = hex::encode(&e.key()[..8]);
+        let c = e.value().decrypt_drops.counts();
+        for (reason, n) in [
+            ("replayed", c.replayed),
+            ("too_old", c.too_old),
+            ("too_short", c.too_short),
+            ("auth_failed", c.auth_failed),
+        ] {
+            let _ = writeln!(
+                body,
+                "summit_session_decrypt_drops_total{{session=\"{session}\",reason=\"{reason}\"}} {n}"
+            );
+        }
+    }
+    body.push_str(
+        "# HELP summit_session_receive_queue_drops_total Chunks a session dropped because its handler queue was full.\n\
+         # TYPE summit_session_receive_queue_drops_total counter\n",
+    );
+    for e in state.sessions.iter() {
+        let _ = writeln!(
+            body,
+            "summit_session_receive_queue_drops_total{{session=\"{}\"}} {}",
+            hex::encode(&e.key()[..8]),
+            e.value().receive_queue_drops.load(Ordering::Relaxed)
+        );
+    }
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+// ── /identity ────────────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct IdentityResponse {
+    /// Hex-encoded static public key — what peers trust us by.
+    pub public_key: String,
+    pub wire_version: u32,
+    pub interface: String,
+    /// Enabled services, e.g. "file_transfer", "messaging".
+    pub services: Vec<String>,
+}
+
+pub async fn handle_identity(State(state): State<ApiState>) -> Json<IdentityResponse> {
+    Json(IdentityResponse {
+        public_key: hex::encode(state.keypair.public),
+        wire_version: summit_core::wire::WIRE_VERSION,
+        interface: state.interface.clone(),
+        services: state.enabled_services.borrow().clone(),
+    })
+}
+
+// ── /info ────────────────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct InfoResponse {
+    pub version: &'static str,
+    /// Commit the daemon was built from; null if built outside a checkout.
+    pub git_hash: Option<&'static str>,
+    pub wire_version: u32,
+    pub interface: String,
+    pub uptime_secs: u64,
+}
+
+pub async fn handle_info(State(state): State<ApiState>) -> Json<InfoResponse> {
+    Json(InfoResponse {
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: option_env!("SUMMIT_GIT_HASH"),
+        wire_version: summit_core::wire::WIRE_VERSION,
+        interface: state.interface.clone(),
+        uptime_secs: state.started_at.elapsed().as_secs(),
+    })
+}
 
 // ── /status ──────────────────────────────────────────────────────────────────
 
 #[derive(Serialize)]
 pub struct StatusResponse {
     pub sessions: Vec<SessionInfo>,
+    /// Sessions allowed at once (`network.max_sessions`). 0 = unlimited.
+    pub max_sessions: usize,
     pub cache: CacheInfo,
     pub peers_discovered: usize,
 }
@@ -32,6 +177,37 @@ pub struct SessionInfo {
 pub struct CacheInfo {
     pub chunks: usize,
     pub bytes: u64,
+    /// Configured size cap. 0 = unlimited.
+    pub max_bytes: u64,
+    /// `bytes` as a percentage of `max_bytes`; null when unlimited.
+    pub utilization_pct: Option<f64>,
+    /// Lookups that found / didn't find their chunk, since startup.
+    pub hits: u64,
+    pub misses: u64,
+    /// `hits` as a percentage of all lookups; null before the first one.
+    pub hit_ratio_pct: Option<f64>,
+    /// Directory chunks are actually stored in — a temp directory if the
+    /// configured one was unusable. Null for the in-memory backend.
+    pub root: Option<String>,
+}
+
+impl CacheInfo {
+    fn of(cache: &summit_services::ChunkCache) -> Self {
+        let bytes = cache.size();
+        let max_bytes = cache.max_bytes();
+        let (hits, misses) = (cache.hits(), cache.misses());
+        let lookups = hits + misses;
+        Self {
+            chunks: cache.count(),
+            bytes,
+            max_bytes,
+            utilization_pct: (max_bytes > 0).then(|| bytes as f64 * 100.0 / max_bytes as f64),
+            hits,
+            misses,
+            hit_ratio_pct: (lookups > 0).then(|| hits as f64 * 100.0 / lookups as f64),
+            root: cache.root().map(|r| r.display().to_string()),
+        }
+    }
 }
 
 pub async fn handle_status(State(state): State<ApiState>) -> Json<StatusResponse> {
@@ -53,15 +229,13 @@ pub async fn handle_status(State(state): State<ApiState>) -> Json<StatusResponse
         })
         .collect();
 
-    let cache = CacheInfo {
-        chunks: state.cache.count(),
-        bytes: state.cache.size(),
-    };
+    let cache = CacheInfo::of(&state.cache);
 
     let peers_discovered = state.registry.len();
 
     Json(StatusResponse {
         sessions,
+        max_sessions: state.max_sessions,
         cache,
         peers_discovered,
     })
@@ -79,37 +253,95 @@ pub struct PeerInfo {
     pub public_key: String,
     pub addr: String,
     pub session_port: u16,
+    /// Hex hashes of the announced services.
     pub services: Vec<String>,
+    /// The same services with their contract and chunk port, in announcement order.
+    pub service_details: Vec<PeerServiceJson>,
     pub service_count: usize,
     pub is_complete: bool,
     pub version: u32,
     pub last_seen_secs: u64,
     pub trust_level: String,
     pub buffered_chunks: usize,
+    /// Bytes/sec sent to and received from this peer, over the last 5 seconds.
+    pub sent_bytes_per_sec: u64,
+    pub received_bytes_per_sec: u64,
+}
+
+#[derive(Deserialize, Default)]
+pub struct PeersQuery {
+    /// Only peers announcing this service: a hex service hash, a full name
+    /// ("summit.compute") or a short one ("compute").
+    pub service: Option<String>,
+}
+
+/// The service hash a `?service=` value names.
+fn service_filter(service: &str) -> ServiceHash {
+    let service = service.trim();
+    let mut hash = [0u8; 32];
+    if hex::decode_to_slice(service, &mut hash).is_ok() {
+        return hash;
+    }
+    if service.contains('.') {
+        summit_core::wire::service_hash(service.as_bytes())
+    } else {
+        summit_core::wire::service_hash(format!("summit.{service}").as_bytes())
+    }
 }
 
-pub async fn handle_peers(State(state): State<ApiState>) -> Json<PeersResponse> {
+#[derive(Serialize)]
+pub struct PeerServiceJson {
+    /// Well-known short name, or the first 8 bytes of the hash in hex.
+    pub name: String,
+    pub hash: String,
+    pub contract: String,
+    /// 0 means the peer's session port.
+    pub chunk_port: u16,
+}
+
+pub async fn handle_peers(
+    State(state): State<ApiState>,
+    Query(query): Query<PeersQuery>,
+) -> Json<PeersResponse> {
+    let wanted = query.service.as_deref().map(service_filter);
     let peers = state
         .registry
         .iter()
+        .filter(|e| wanted.is_none_or(|hash| e.value().has_service(&hash)))
         .map(|e| {
             let p = e.value();
             let pubkey = *e.key();
             let trust_level = state.trust.check(&pubkey);
             let buffered_chunks = state.untrusted_buffer.count(&pubkey);
-            let services: Vec<String> = p.services.keys().map(hex::encode).collect();
+            let services: Vec<String> = p.services.iter().map(|s| hex::encode(s.hash)).collect();
+            let service_details = p
+                .services
+                .iter()
+                .map(|s| PeerServiceJson {
+                    name: summit_core::wire::service_name(&s.hash)
+                        .map(String::from)
+                        .unwrap_or_else(|| hex::encode(&s.hash[..8])),
+                    hash: hex::encode(s.hash),
+                    contract: format!("{:?}", s.contract),
+                    chunk_port: s.chunk_port,
+                })
+                .collect();
+            let rates = state.bandwidth.rates(&pubkey);
 
             PeerInfo {
                 public_key: hex::encode(p.public_key),
                 addr: p.addr.to_string(),
                 session_port: p.session_port,
                 services,
+                service_details,
                 service_count: p.expected_service_count as usize,
                 is_complete: p.is_complete(),
                 version: p.version,
                 last_seen_secs: p.last_seen.elapsed().as_secs(),
                 trust_level: format!("{:?}", trust_level),
                 buffered_chunks,
+                sent_bytes_per_sec: rates.sent_bytes_per_sec,
+                received_bytes_per_sec: rates.received_bytes_per_sec,
             }
         })
         .collect();
@@ -120,9 +352,69 @@ pub async fn handle_peers(State(state): State<ApiState>) -> Json<PeersResponse>
 // ── /cache ────────────────────────────────────────────────────────────────────
 
 pub async fn handle_cache(State(state): State<ApiState>) -> Json<CacheInfo> {
-    Json(CacheInfo {
-        chunks: state.cache.count(),
-        bytes: state.cache.size(),
+    Json(CacheInfo::of(&state.cache))
+}
+
+/// Chunks listed when `?limit=` is not given.
+const DEFAULT_CACHE_LIST_LIMIT: usize = 100;
+
+/// Most chunks one `/cache/list` page returns, whatever `?limit=` says.
+pub const MAX_CACHE_LIST_LIMIT: usize = 1000;
+
+#[derive(Deserialize, Default)]
+pub struct CacheListQuery {
+    /// Page size, capped at `MAX_CACHE_LIST_LIMIT`.
+    pub limit: Option<usize>,
+    /// Chunks to skip, most recently accessed first.
+    pub offset: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct CacheListResponse {
+    /// Chunks in the whole cache.
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub chunks: Vec<CachedChunkInfo>,
+}
+
+#[derive(Serialize)]
+pub struct CachedChunkInfo {
+    pub content_hash: String,
+    pub bytes: u64,
+    /// Unix timestamp in milliseconds of the last read or write.
+    pub last_access_ms: u64,
+}
+
+/// Individual chunks, most recently accessed first, a page at a time.
+pub async fn handle_cache_list(
+    State(state): State<ApiState>,
+    Query(query): Query<CacheListQuery>,
+) -> Json<CacheListResponse> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CACHE_LIST_LIMIT)
+        .min(MAX_CACHE_LIST_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let chunks = state
+        .cache
+        .list(offset, limit)
+        .into_iter()
+        .map(|c| CachedChunkInfo {
+            content_hash: hex::encode(c.hash),
+            bytes: c.size,
+            last_access_ms: c
+                .last_access
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        })
+        .collect();
+    Json(CacheListResponse {
+        total: state.cache.count(),
+        offset,
+        limit,
+        chunks,
     })
 }
 
@@ -138,6 +430,52 @@ pub async fn handle_cache_clear(State(state): State<ApiState>) -> Json<ClearResp
     Json(ClearResponse { cleared })
 }
 
+// ── /delivery ─────────────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+pub struct DeliveryResponse {
+    pub tracked_chunks: usize,
+    pub multipath_chunks: usize,
+    pub duplicate_deliveries: usize,
+    pub chunks: Vec<DeliveryInfo>,
+}
+
+#[derive(Serialize)]
+pub struct DeliveryInfo {
+    pub content_hash: String,
+    /// Distinct peer addresses the chunk arrived from.
+    pub paths: Vec<String>,
+    pub deliveries: usize,
+    pub duplicates: usize,
+    pub multipath: bool,
+    pub first_seen_secs: u64,
+    pub last_seen_secs: u64,
+}
+
+pub async fn handle_delivery(State(state): State<ApiState>) -> Json<DeliveryResponse> {
+    let chunks: Vec<DeliveryInfo> = state
+        .delivery
+        .stats()
+        .into_iter()
+        .map(|s| DeliveryInfo {
+            content_hash: hex::encode(s.content_hash),
+            deliveries: s.deliveries,
+            duplicates: s.duplicates(),
+            multipath: s.is_multipath(),
+            first_seen_secs: s.first_seen.as_secs(),
+            last_seen_secs: s.last_seen.as_secs(),
+            paths: s.paths,
+        })
+        .collect();
+
+    Json(DeliveryResponse {
+        tracked_chunks: chunks.len(),
+        multipath_chunks: chunks.iter().filter(|c| c.multipath).count(),
+        duplicate_deliveries: chunks.iter().map(|c| c.duplicates).sum(),
+        chunks,
+    })
+}
+
 // ── /schema ───────────────────────────────────────────────────────────────────
 
 #[derive(Serialize)]
@@ -149,37 +487,22 @@ pub struct SchemaListResponse {
 pub struct SchemaInfoItem {
     pub id: String,
     pub name: String,
-    pub type_tag: u8,
+    /// Chunk type_tag, or null when the schema uses several.
+    pub type_tag: Option<u16>,
+    /// QoS contract chunks of this schema are sent under.
+    pub contract: String,
 }
 
 pub async fn handle_schema_list() -> Json<SchemaListResponse> {
-    let schemas = vec![
-        SchemaInfoItem {
-            id: hex::encode(KnownSchema::TestPing.id()),
-            name: KnownSchema::TestPing.name().to_string(),
-            type_tag: 1,
-        },
-        SchemaInfoItem {
-            id: hex::encode(KnownSchema::FileData.id()),
-            name: KnownSchema::FileData.name().to_string(),
-            type_tag: 2,
-        },
-        SchemaInfoItem {
-            id: hex::encode(KnownSchema::FileMetadata.id()),
-            name: KnownSchema::FileMetadata.name().to_string(),
-            type_tag: 3,
-        },
-        SchemaInfoItem {
-            id: hex::encode(KnownSchema::Message.id()),
-            name: KnownSchema::Message.name().to_string(),
-            type_tag: 4,
-        },
-        SchemaInfoItem {
-            id: hex::encode(KnownSchema::ComputeTask.id()),
-            name: KnownSchema::ComputeTask.name().to_string(),
-            type_tag: 5,
-        },
-    ];
+    let schemas = KnownSchema::all()
+        .iter()
+        .map(|schema| SchemaInfoItem {
+            id: hex::encode(schema.id()),
+            name: schema.name().to_string(),
+            type_tag: schema.type_tag(),
+            contract: format!("{:?}", schema.contract()),
+        })
+        .collect();
 
     Json(SchemaListResponse { schemas })
 }
@@ -196,6 +519,8 @@ pub struct ServiceStatus {
     pub name: String,
     pub enabled: bool,
     pub contract: String,
+    /// Sessions on which this service was negotiated.
+    pub active_sessions: usize,
 }
 
 pub async fn handle_services(State(state): State<ApiState>) -> Json<ServicesResponse> {
@@ -204,20 +529,93 @@ pub async fn handle_services(State(state): State<ApiState>) -> Json<ServicesResp
         ("messaging", "Bulk"),
         ("stream_udp", "Realtime"),
         ("compute", "Bulk"),
+        ("sync", "Background"),
     ];
 
+    let mut active: std::collections::HashMap<&'static str, usize> = Default::default();
+    for session in state.sessions.iter() {
+        for hash in session.meta.active_services.keys() {
+            if let Some(name) = summit_core::wire::service_name(hash) {
+                *active.entry(name).or_default() += 1;
+            }
+        }
+    }
+
+    let enabled = state.enabled_services.borrow().clone();
     let services = all
         .iter()
         .map(|(name, contract)| ServiceStatus {
             name: name.to_string(),
-            enabled: state.enabled_services.iter().any(|s| s == name),
+            enabled: enabled.iter().any(|s| s == name),
             contract: contract.to_string(),
+            active_sessions: active.get(name).copied().unwrap_or(0),
         })
         .collect();
 
     Json(ServicesResponse { services })
 }
 
+/// Services that can be switched without a restart. Compute's executor
+/// and the sync offer loop only start with the daemon.
+const RUNTIME_SERVICES: [&str; 3] = ["file_transfer", "messaging", "stream_udp"];
+
+#[derive(Deserialize)]
+pub struct ServiceToggleRequest {
+    pub enabled: bool,
+}
+
+#[derive(Serialize)]
+pub struct ServiceToggleResponse {
+    pub name: String,
+    pub enabled: bool,
+    /// False when the service was already in the requested state.
+    pub changed: bool,
+}
+
+/// Switch a service on or off. It is announced (or no longer announced) on
+/// the next broadcast and its incoming chunks are routed (or dropped) from
+/// now on; transfers already in progress are not cut short.
+pub async fn handle_service_toggle(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    Json(req): Json<ServiceToggleRequest>,
+) -> Result<Json<ServiceToggleResponse>, ApiError> {
+    if name == "compute" || name == "sync" {
+        return Err(ApiError::bad_request(
+            "service_needs_restart",
+            format!("{name} can only be switched in the config, with a restart"),
+        ));
+    }
+    if !RUNTIME_SERVICES.contains(&name.as_str()) {
+        return Err(ApiError::not_found(
+            "unknown_service",
+            format!("no service named '{name}'"),
+        ));
+    }
+
+    let changed = state.enabled_services.send_if_modified(|names| {
+        let enabled = names.contains(&name);
+        if enabled == req.enabled {
+            return false;
+        }
+        if req.enabled {
+            names.push(name.clone());
+        } else {
+            names.retain(|n| *n != name);
+        }
+        true
+    });
+    if changed {
+        tracing::info!(service = %name, enabled = req.enabled, "service switched via API");
+    }
+
+    Ok(Json(ServiceToggleResponse {
+        name,
+        enabled: req.enabled,
+        changed,
+    }))
+}
+
 // ── /daemon/shutdown ──────────────────────────────────────────────────────────
 
 #[derive(Serialize)]
@@ -227,7 +625,7 @@ pub struct ShutdownResponse {
 
 pub async fn handle_shutdown(State(state): State<ApiState>) -> Json<ShutdownResponse> {
     tracing::info!("shutdown requested via API");
-    let _ = state.shutdown_tx.send(());
+    state.shutdown();
 
     Json(ShutdownResponse {
         message: "Shutdown initiated".to_string(),
@@ -1,11 +1,10 @@
 //! /trust handlers — trust management endpoints.
 
 use axum::extract::State;
-use axum::http::StatusCode;
 use axum::Json;
 use serde::{Deserialize, Serialize};
 
-use super::{parse_pubkey, ApiState};
+use super::{parse_pubkey, ApiError, ApiState};
 
 // ── /trust (GET) ──────────────────────────────────────────────────────────────
 
@@ -50,7 +49,7 @@ pub struct TrustAddResponse {
 pub async fn handle_trust_add(
     State(state): State<ApiState>,
     Json(req): Json<TrustAddRequest>,
-) -> Result<Json<TrustAddResponse>, (StatusCode, String)> {
+) -> Result<Json<TrustAddResponse>, ApiError> {
     let pubkey = parse_pubkey(&req.public_key)?;
 
     state.trust.trust(pubkey);
@@ -71,6 +70,44 @@ pub async fn handle_trust_add(
     }))
 }
 
+// ── /trust/receive-only (POST) ───────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct TrustReceiveOnlyRequest {
+    pub public_key: String,
+}
+
+#[derive(Serialize)]
+pub struct TrustReceiveOnlyResponse {
+    pub public_key: String,
+    pub flushed_chunks: usize,
+}
+
+/// Take files and messages from a peer without letting it run anything
+/// here. Buffered chunks are replayed as on `/trust/add`; those for an
+/// executing service are dropped on replay.
+pub async fn handle_trust_receive_only(
+    State(state): State<ApiState>,
+    Json(req): Json<TrustReceiveOnlyRequest>,
+) -> Result<Json<TrustReceiveOnlyResponse>, ApiError> {
+    let pubkey = parse_pubkey(&req.public_key)?;
+
+    state.trust.receive_only(pubkey);
+
+    let buffered = state.untrusted_buffer.flush(&pubkey);
+    let flushed_chunks = buffered.len();
+    for chunk in buffered {
+        if let Err(e) = state.replay_tx.send((pubkey, chunk)) {
+            tracing::warn!(error = %e, "failed to send buffered chunk for replay");
+        }
+    }
+
+    Ok(Json(TrustReceiveOnlyResponse {
+        public_key: req.public_key,
+        flushed_chunks,
+    }))
+}
+
 // ── /trust/block (POST) ──────────────────────────────────────────────────────
 
 #[derive(Deserialize)]
@@ -86,7 +123,7 @@ pub struct TrustBlockResponse {
 pub async fn handle_trust_block(
     State(state): State<ApiState>,
     Json(req): Json<TrustBlockRequest>,
-) -> Result<Json<TrustBlockResponse>, (StatusCode, String)> {
+) -> Result<Json<TrustBlockResponse>, ApiError> {
     let pubkey = parse_pubkey(&req.public_key)?;
 
     state.trust.block(pubkey);
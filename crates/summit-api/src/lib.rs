@@ -1,47 +1,142 @@
+pub mod error;
 pub mod handlers;
+mod rate_limit;
 
-use axum::extract::DefaultBodyLimit;
+use std::sync::Arc;
+
+use axum::extract::{DefaultBodyLimit, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::Response;
 use axum::routing::{delete, get, post};
 use axum::Router;
+pub use error::ApiError;
 pub use handlers::ApiState;
+use summit_core::config::ApiConfig;
+
+pub async fn serve(state: ApiState, config: &ApiConfig) -> anyhow::Result<()> {
+    let addr = config.socket_addr();
+    let authenticated = config.token().is_some();
+    if !addr.ip().is_loopback() && !authenticated {
+        tracing::warn!(
+            %addr,
+            "API bound to a non-loopback address WITHOUT a token — anyone who can \
+             reach it can control this daemon; set api.token"
+        );
+    }
+    let app = router(state, config);
 
-pub async fn serve(state: ApiState, port: u16) -> anyhow::Result<()> {
-    let api_routes = Router::new()
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!(%addr, authenticated, "API listening");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Build the API router. With a token set, every route requires
+/// `Authorization: Bearer <token>`. With `max_requests_per_sec` set, reads
+/// and mutating requests are each limited to that rate; the health probes
+/// and `/metrics` are never limited.
+pub fn router(state: ApiState, config: &ApiConfig) -> Router {
+    let probes = Router::new()
+        .route("/health", get(handlers::handle_health))
+        .route("/ready", get(handlers::handle_ready))
+        .route("/metrics", get(handlers::handle_metrics));
+
+    let mut api_routes = Router::new()
+        .route("/identity", get(handlers::handle_identity))
+        .route("/info", get(handlers::handle_info))
         .route("/status", get(handlers::handle_status))
         .route("/peers", get(handlers::handle_peers))
         .route("/cache", get(handlers::handle_cache))
+        .route("/cache/list", get(handlers::handle_cache_list))
         .route("/cache/clear", post(handlers::handle_cache_clear))
+        .route("/delivery", get(handlers::handle_delivery))
         .route(
             "/send",
             post(handlers::handle_send).layer(DefaultBodyLimit::max(256 * 1024 * 1024)),
         )
+        .route(
+            "/send/plan",
+            post(handlers::handle_send_plan).layer(DefaultBodyLimit::max(256 * 1024 * 1024)),
+        )
         .route("/files", get(handlers::handle_files))
+        .route("/files/{*name}", get(handlers::handle_file_get))
+        .route("/transfers", get(handlers::handle_transfers))
         .route("/trust", get(handlers::handle_trust_list))
         .route("/trust/add", post(handlers::handle_trust_add))
+        .route(
+            "/trust/receive-only",
+            post(handlers::handle_trust_receive_only),
+        )
         .route("/trust/block", post(handlers::handle_trust_block))
         .route("/trust/pending", get(handlers::handle_trust_pending))
         .route("/daemon/shutdown", post(handlers::handle_shutdown))
+        .route("/sessions", get(handlers::handle_session_list))
+        .route("/sessions/connect", post(handlers::handle_session_connect))
+        .route("/handshakes", get(handlers::handle_handshakes))
         .route("/sessions/{id}", delete(handlers::handle_session_drop))
         .route("/sessions/{id}", get(handlers::handle_session_inspect))
         .route("/schema", get(handlers::handle_schema_list))
+        .route("/messages", get(handlers::handle_inbox))
+        .route("/messages/summary", get(handlers::handle_message_summary))
+        .route(
+            "/messages/{peer_pubkey}/read",
+            post(handlers::handle_mark_read),
+        )
         .route(
             "/messages/{peer_pubkey}",
             get(handlers::handle_get_messages),
         )
         .route("/messages/send", post(handlers::handle_send_message))
+        .route("/messages/typing", post(handlers::handle_typing))
+        .route("/events", get(handlers::handle_events))
         .route("/services", get(handlers::handle_services))
+        .route("/services/{name}", post(handlers::handle_service_toggle))
         .route("/compute/tasks", get(handlers::handle_compute_all_tasks))
         .route(
             "/compute/tasks/{peer_pubkey}",
             get(handlers::handle_compute_tasks),
         )
         .route("/compute/submit", post(handlers::handle_compute_submit))
-        .with_state(state);
+        .route("/compute/cancel", post(handlers::handle_compute_cancel));
+    if config.max_requests_per_sec > 0 {
+        api_routes = api_routes.layer(middleware::from_fn_with_state(
+            rate_limit::RateLimiter::new(config.max_requests_per_sec),
+            rate_limit::limit,
+        ));
+    }
 
-    let app = Router::new().nest("/api", api_routes);
+    let app = Router::new().nest("/api", api_routes.merge(probes).with_state(state));
+    match config.token() {
+        Some(token) => app.layer(middleware::from_fn_with_state(
+            Arc::new(token),
+            require_token,
+        )),
+        None => app,
+    }
+}
 
-    let listener = tokio::net::TcpListener::bind(format!("127.0.0.1:{}", port)).await?;
-    tracing::info!(port, "API listening on 127.0.0.1");
-    axum::serve(listener, app).await?;
-    Ok(())
+/// Reject requests whose bearer token does not match with 401.
+async fn require_token(
+    State(token): State<Arc<String>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    // Compare digests so the check takes the same time however much matches.
+    let valid =
+        presented.is_some_and(|p| blake3::hash(p.as_bytes()) == blake3::hash(token.as_bytes()));
+    if !valid {
+        return Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "missing or invalid API token",
+        ));
+    }
+    Ok(next.run(request).await)
 }
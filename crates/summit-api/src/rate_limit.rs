@@ -0,0 +1,58 @@
+//! Per-category request rate limiting.
+//!
+//! Reads (GET/HEAD) and mutating requests draw from separate token buckets,
+//! so UI polling of `/status` never eats into `/send` throughput.
+
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Request, State};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use summit_core::wire::Contract;
+use summit_services::TokenBucket;
+
+use crate::ApiError;
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    reads: Arc<Mutex<TokenBucket>>,
+    writes: Arc<Mutex<TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Each category allows `per_sec` requests per second, bursting to one
+    /// second's worth.
+    pub fn new(per_sec: u32) -> Self {
+        let bucket = || {
+            let mut b = TokenBucket::new(Contract::Bulk);
+            b.reconfigure(per_sec as f64, per_sec as f64);
+            Arc::new(Mutex::new(b))
+        };
+        Self {
+            reads: bucket(),
+            writes: bucket(),
+        }
+    }
+}
+
+/// Reject the request with 429 when its category's bucket is empty.
+pub async fn limit(
+    State(limiter): State<RateLimiter>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let bucket = match *request.method() {
+        Method::GET | Method::HEAD => &limiter.reads,
+        _ => &limiter.writes,
+    };
+    if !bucket.lock().unwrap().allow() {
+        return Err(ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limited",
+            "rate limit exceeded — retry later",
+        ));
+    }
+    Ok(next.run(request).await)
+}
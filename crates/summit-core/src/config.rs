@@ -8,7 +8,8 @@
 //!   3. ~/.config/summit/config.toml
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::path::{Path, PathBuf};
 
 /// Top-level configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,8 +17,13 @@ use std::path::PathBuf;
 pub struct SummitConfig {
     pub identity: IdentityConfig,
     pub network: NetworkConfig,
+    pub api: ApiConfig,
+    pub cache: CacheConfig,
+    pub storage: StorageConfig,
+    pub qos: QosConfig,
     pub trust: TrustConfig,
     pub services: ServicesConfig,
+    pub log: LogConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,13 +42,164 @@ pub struct NetworkConfig {
     pub session_port: u16,
     /// UDP port for chunk data. 0 = use session_port.
     pub chunk_port: u16,
-    /// HTTP API port for status/control.
-    pub api_port: u16,
     /// Bulk receive capacity (tokens/sec). Advertised to peers.
     /// Higher = faster file transfers. 0 = use default (128).
     pub bulk_rate: u32,
     /// Bulk burst capacity. 0 = use default (64).
     pub bulk_burst: u32,
+    /// Outbound chunk queue capacity. Producers wait when it is full. 0 = use default (256).
+    pub send_queue_capacity: usize,
+    /// Chunks a session's receive loop may queue for its handler. When the
+    /// queue is full further chunks are dropped and counted. 0 = use default (100).
+    pub recv_channel_depth: usize,
+    /// Drop a session after this many seconds without receiving a chunk. 0 = never.
+    pub session_idle_secs: u64,
+    /// Sessions held at once, counting handshakes in flight. Past it, new
+    /// handshakes are neither sent nor answered. 0 = unlimited.
+    pub max_sessions: usize,
+    /// IPv6 multicast group capability announcements are sent to. Clusters
+    /// sharing a link stay apart by using different groups or ports.
+    pub multicast_group: Ipv6Addr,
+    /// UDP port capability announcements are sent to and received on.
+    pub discovery_port: u16,
+    /// Forget a discovered peer after this many seconds without an
+    /// announcement. Raise it on links that drop multicast.
+    pub peer_ttl_secs: u64,
+    /// How often, in seconds, the registry is swept for expired peers.
+    pub peer_expiry_interval_secs: u64,
+    /// What carries datagrams: UDP on `interface`, or an in-process
+    /// loopback network for tests.
+    pub transport: Transport,
+    /// How peers are found: multicast announcements, or only `static_peers`.
+    pub discovery: Discovery,
+    /// Peers to handshake with without waiting for an announcement, as
+    /// `<public key hex>@[<ipv6>]:<session port>`. The peer needs a fixed
+    /// `session_port`, and must list us too unless it runs multicast
+    /// discovery.
+    pub static_peers: Vec<String>,
+    /// Deprecated alias for `api.port`, from configs written before `[api]`
+    /// existed. Used only when `api.port` is not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_port: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    /// Address the HTTP API listens on. Keep it loopback unless a token is set.
+    pub bind_addr: IpAddr,
+    /// HTTP API port for status/control.
+    pub port: u16,
+    /// Bearer token required on every API request. Empty = no auth.
+    pub token: String,
+    /// Requests per second allowed for reads, and separately for mutating
+    /// requests. Health probes are exempt. 0 = unlimited.
+    pub max_requests_per_sec: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Chunk cache size cap. Least-recently-used chunks are evicted past it.
+    /// 0 = unlimited.
+    pub max_bytes: u64,
+    /// Where chunks are stored: on disk, or in memory only.
+    pub backend: CacheBackend,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Where the daemon keeps its own state: trust decisions, the compute
+    /// task log and, unless `SUMMIT_CACHE` says otherwise, the chunk cache.
+    pub data_dir: PathBuf,
+    /// Encrypt received files on disk with ChaCha20-Poly1305. The API
+    /// decrypts them on read. Protects data at rest only.
+    pub encrypt_at_rest: bool,
+    /// 32-byte key as hex. Empty = derive from the identity keypair.
+    pub encryption_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QosConfig {
+    /// Upload cap per peer in bytes/sec, across all of its sessions. Bulk
+    /// chunks over it are held, background chunks dropped; realtime traffic
+    /// is never capped. 0 = unlimited.
+    pub max_bytes_per_sec_per_peer: u64,
+    /// Least gap between two chunks on one session, in microseconds, by
+    /// contract. Smooths a file's chunks out of a single burst. 0 = unpaced.
+    pub pacing_us_realtime: u64,
+    pub pacing_us_bulk: u64,
+    pub pacing_us_background: u64,
+    /// Random extra gap of up to this many microseconds on paced chunks.
+    pub pacing_jitter_us: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    /// UDP over IPv6 link-local, multicast discovery on the interface.
+    Udp,
+    /// In-memory channels between daemons in the same process. Needs no
+    /// interface, IPv6 or root; nothing leaves the process.
+    Loopback,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Discovery {
+    /// Announce on and listen to `multicast_group`, plus `static_peers`.
+    Multicast,
+    /// No multicast traffic; `static_peers` are the only peers.
+    Static,
+    /// Same as `Static`, for configs that think of it as discovery disabled.
+    Off,
+}
+
+/// A peer from `network.static_peers`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaticPeer {
+    pub public_key: [u8; 32],
+    pub addr: Ipv6Addr,
+    pub session_port: u16,
+}
+
+/// What to do with a received file whose name is already taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// Replace the existing file.
+    Overwrite,
+    /// Keep both: the new file gets a numeric suffix, `name-1.ext`, ….
+    Rename,
+    /// Keep the existing file and drop the new one.
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheBackend {
+    /// Git-style directory tree; survives restarts.
+    Disk,
+    /// Process memory; nothing is written to disk.
+    Memory,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Log line format. Filtering stays with RUST_LOG.
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable single-line output.
+    Text,
+    /// One JSON object per line, for log pipelines.
+    Json,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,19 +219,25 @@ pub struct ServicesConfig {
     pub messaging: bool,
     pub stream_udp: bool,
     pub compute: bool,
+    /// Background replication of cached chunks to peers that also run it.
+    pub sync: bool,
 
     /// Per-service settings.
     pub file_transfer_settings: FileTransferSettings,
     pub messaging_settings: MessagingSettings,
     pub compute_settings: ComputeSettings,
+    pub sync_settings: SyncSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct FileTransferSettings {
     pub storage_path: PathBuf,
-    /// Max chunk cache bytes. 0 = unlimited.
-    pub cache_max_bytes: u64,
+    /// Files reassembled at once. Metadata for further files is dropped
+    /// until one finishes or times out. 0 = unlimited.
+    pub max_concurrent_assemblies: usize,
+    /// A completed file named like one already received.
+    pub on_conflict: OnConflict,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -85,6 +248,15 @@ pub struct MessagingSettings {
     pub retention_days: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSettings {
+    /// How often each sync peer is offered our recently used chunks.
+    pub interval_secs: u64,
+    /// Chunk hashes per offer, most recently used first.
+    pub max_offer_chunks: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ComputeSettings {
@@ -97,6 +269,18 @@ pub struct ComputeSettings {
     pub max_memory_bytes: u64,
     /// Per-task timeout in seconds. 0 = 300 (5 minutes).
     pub task_timeout_secs: u64,
+    /// Max bytes of stdout/stderr kept per stream. Excess is truncated. 0 = unlimited.
+    pub max_output_bytes: u64,
+    /// Max total bytes of output files returned per task. Files that would
+    /// exceed it are not sent. 0 = unlimited.
+    pub max_output_file_bytes: u64,
+    /// Command names peers may execute. Empty = allow any.
+    /// Shell-mode tasks (`run`) are only permitted if `sh` is listed.
+    pub allowed_commands: Vec<String>,
+    /// Task history log, replayed on startup. Unset = `compute_tasks.jsonl`
+    /// under `storage.data_dir`; empty = keep task history in memory only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_log: Option<PathBuf>,
 }
 
 // ── Defaults ──────────────────────────────────────────────────────────────────
@@ -106,8 +290,13 @@ impl Default for SummitConfig {
         Self {
             identity: IdentityConfig::default(),
             network: NetworkConfig::default(),
+            api: ApiConfig::default(),
+            cache: CacheConfig::default(),
+            storage: StorageConfig::default(),
+            qos: QosConfig::default(),
             trust: TrustConfig::default(),
             services: ServicesConfig::default(),
+            log: LogConfig::default(),
         }
     }
 }
@@ -126,9 +315,70 @@ impl Default for NetworkConfig {
             interface: String::new(),
             session_port: 0,
             chunk_port: 0,
-            api_port: 9001,
             bulk_rate: 128,
             bulk_burst: 64,
+            send_queue_capacity: 256,
+            recv_channel_depth: 100,
+            session_idle_secs: 30,
+            max_sessions: 256,
+            multicast_group: crate::wire::MULTICAST_ADDR_V6,
+            discovery_port: crate::wire::DISCOVERY_PORT,
+            peer_ttl_secs: crate::wire::PEER_TTL_SECS,
+            peer_expiry_interval_secs: 1,
+            transport: Transport::Udp,
+            discovery: Discovery::Multicast,
+            static_peers: Vec::new(),
+            api_port: None,
+        }
+    }
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 9001,
+            token: String::new(),
+            max_requests_per_sec: 0,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1_073_741_824, // 1 GB
+            backend: CacheBackend::Disk,
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: data_dir(),
+            encrypt_at_rest: false,
+            encryption_key: String::new(),
+        }
+    }
+}
+
+impl Default for QosConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes_per_sec_per_peer: 0,
+            pacing_us_realtime: 0,
+            pacing_us_bulk: 0,
+            pacing_us_background: 0,
+            pacing_jitter_us: 0,
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Text,
         }
     }
 }
@@ -149,9 +399,11 @@ impl Default for ServicesConfig {
             messaging: true,
             stream_udp: false,
             compute: false,
+            sync: false,
             file_transfer_settings: FileTransferSettings::default(),
             messaging_settings: MessagingSettings::default(),
             compute_settings: ComputeSettings::default(),
+            sync_settings: SyncSettings::default(),
         }
     }
 }
@@ -160,7 +412,8 @@ impl Default for FileTransferSettings {
     fn default() -> Self {
         Self {
             storage_path: data_dir().join("received"),
-            cache_max_bytes: 1_073_741_824, // 1 GB
+            max_concurrent_assemblies: 64,
+            on_conflict: OnConflict::Rename,
         }
     }
 }
@@ -174,6 +427,15 @@ impl Default for MessagingSettings {
     }
 }
 
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 30,
+            max_offer_chunks: 256,
+        }
+    }
+}
+
 impl Default for ComputeSettings {
     fn default() -> Self {
         Self {
@@ -182,10 +444,97 @@ impl Default for ComputeSettings {
             max_cpu_cores: 0,
             max_memory_bytes: 0,
             task_timeout_secs: 300,
+            max_output_bytes: 1_048_576,           // 1 MB
+            max_output_file_bytes: 64 * 1_048_576, // 64 MB
+            allowed_commands: Vec::new(),
+            task_log: None,
         }
     }
 }
 
+impl ComputeSettings {
+    /// Where the task log lives, given `storage.data_dir`. `None` when it
+    /// is configured empty.
+    pub fn task_log_path(&self, data_dir: &Path) -> Option<PathBuf> {
+        match &self.task_log {
+            None => Some(data_dir.join("compute_tasks.jsonl")),
+            Some(path) if path.as_os_str().is_empty() => None,
+            Some(path) => Some(path.clone()),
+        }
+    }
+}
+
+impl StorageConfig {
+    /// The explicit encryption key, if one is configured.
+    pub fn key(&self) -> Result<Option<[u8; 32]>, ConfigError> {
+        let hex = self.encryption_key.trim();
+        if hex.is_empty() {
+            return Ok(None);
+        }
+        let hex = hex
+            .strip_prefix("0x")
+            .or_else(|| hex.strip_prefix("0X"))
+            .unwrap_or(hex);
+        decode_hex32(hex).map(Some).ok_or_else(|| {
+            ConfigError::Invalid(
+                "storage.encryption_key must be 32 bytes as 64 hex characters".to_string(),
+            )
+        })
+    }
+}
+
+impl NetworkConfig {
+    /// `static_peers`, parsed. Errors name the first malformed entry.
+    pub fn static_peers(&self) -> Result<Vec<StaticPeer>, ConfigError> {
+        self.static_peers
+            .iter()
+            .map(|entry| {
+                let invalid = || {
+                    ConfigError::Invalid(format!(
+                        "network.static_peers entry {entry:?} is not \
+                         <public key hex>@[<ipv6>]:<session port>"
+                    ))
+                };
+                let (key, addr) = entry.trim().split_once('@').ok_or_else(invalid)?;
+                let public_key = decode_hex32(key).ok_or_else(invalid)?;
+                let addr: SocketAddrV6 = addr.parse().map_err(|_| invalid())?;
+                if addr.port() == 0 {
+                    return Err(invalid());
+                }
+                Ok(StaticPeer {
+                    public_key,
+                    addr: *addr.ip(),
+                    session_port: addr.port(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// 32 bytes from exactly 64 hex characters.
+fn decode_hex32(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+impl ApiConfig {
+    /// Socket address the API listens on.
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind_addr, self.port)
+    }
+
+    /// The configured token, if any.
+    pub fn token(&self) -> Option<String> {
+        Some(self.token.clone()).filter(|t| !t.is_empty())
+    }
+}
+
 // ── Path helpers ──────────────────────────────────────────────────────────────
 
 fn config_dir() -> PathBuf {
@@ -220,6 +569,8 @@ pub enum ConfigError {
     WriteFailed(PathBuf, std::io::Error),
     #[error("failed to serialize: {0}")]
     SerializeFailed(toml::ser::Error),
+    #[error("invalid config: {0}")]
+    Invalid(String),
 }
 
 // ── Loading ───────────────────────────────────────────────────────────────────
@@ -231,14 +582,90 @@ impl SummitConfig {
         let mut config = if path.exists() {
             let text = std::fs::read_to_string(&path)
                 .map_err(|e| ConfigError::ReadFailed(path.clone(), e))?;
-            toml::from_str(&text).map_err(|e| ConfigError::ParseFailed(path.clone(), e))?
+            Self::parse(&text).map_err(|e| ConfigError::ParseFailed(path.clone(), e))?
         } else {
             SummitConfig::default()
         };
         config.apply_env_overrides();
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parse a config file, mapping deprecated keys onto their replacements.
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        let mut config: SummitConfig = toml::from_str(text)?;
+        if let Some(port) = config.network.api_port {
+            let raw: toml::Table = toml::from_str(text)?;
+            let api_port_set = raw.get("api").and_then(|api| api.get("port")).is_some();
+            if !api_port_set {
+                config.api.port = port;
+            }
+        }
         Ok(config)
     }
 
+    /// Reject values that parse but cannot work.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !self.network.multicast_group.is_multicast() {
+            return Err(ConfigError::Invalid(format!(
+                "network.multicast_group {} is not a multicast address (ff00::/8)",
+                self.network.multicast_group
+            )));
+        }
+        if self.network.discovery_port == 0 {
+            return Err(ConfigError::Invalid(
+                "network.discovery_port must not be 0".to_string(),
+            ));
+        }
+        if self.network.peer_ttl_secs <= crate::wire::ANNOUNCE_INTERVAL_SECS {
+            return Err(ConfigError::Invalid(format!(
+                "network.peer_ttl_secs must exceed the {}s announcement interval",
+                crate::wire::ANNOUNCE_INTERVAL_SECS
+            )));
+        }
+        if self.network.peer_expiry_interval_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "network.peer_expiry_interval_secs must not be 0".to_string(),
+            ));
+        }
+        if self.services.sync && self.services.sync_settings.interval_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "services.sync_settings.interval_secs must not be 0".to_string(),
+            ));
+        }
+        self.network.static_peers()?;
+        self.storage.key()?;
+        Ok(())
+    }
+
+    /// Dotted paths of every setting that differs from `other`, e.g.
+    /// `network.session_port`. Lists compare as a whole.
+    pub fn changed_fields(&self, other: &SummitConfig) -> Vec<String> {
+        fn diff(prefix: &str, a: &serde_json::Value, b: &serde_json::Value, out: &mut Vec<String>) {
+            match (a, b) {
+                (serde_json::Value::Object(a), serde_json::Value::Object(b)) => {
+                    for (key, value) in a {
+                        let path = if prefix.is_empty() {
+                            key.clone()
+                        } else {
+                            format!("{prefix}.{key}")
+                        };
+                        diff(&path, value, &b[key], out);
+                    }
+                }
+                _ if a != b => out.push(prefix.to_string()),
+                _ => {}
+            }
+        }
+
+        // Only non-UTF-8 paths fail to serialize; such configs compare equal.
+        let a = serde_json::to_value(self).unwrap_or_default();
+        let b = serde_json::to_value(other).unwrap_or_default();
+        let mut changed = Vec::new();
+        diff("", &a, &b, &mut changed);
+        changed
+    }
+
     /// Config file path.
     pub fn file_path() -> PathBuf {
         std::env::var("SUMMIT_CONFIG")
@@ -271,6 +698,140 @@ impl SummitConfig {
                 self.network.session_port = p;
             }
         }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__SEND_QUEUE_CAPACITY") {
+            if let Ok(n) = v.parse() {
+                self.network.send_queue_capacity = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__RECV_CHANNEL_DEPTH") {
+            if let Ok(n) = v.parse() {
+                self.network.recv_channel_depth = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__SESSION_IDLE_SECS") {
+            if let Ok(n) = v.parse() {
+                self.network.session_idle_secs = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__MAX_SESSIONS") {
+            if let Ok(n) = v.parse() {
+                self.network.max_sessions = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__MULTICAST_GROUP") {
+            if let Ok(a) = v.parse() {
+                self.network.multicast_group = a;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__DISCOVERY_PORT") {
+            if let Ok(p) = v.parse() {
+                self.network.discovery_port = p;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__PEER_TTL_SECS") {
+            if let Ok(n) = v.parse() {
+                self.network.peer_ttl_secs = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__PEER_EXPIRY_INTERVAL_SECS") {
+            if let Ok(n) = v.parse() {
+                self.network.peer_expiry_interval_secs = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__TRANSPORT") {
+            match v.as_str() {
+                "udp" => self.network.transport = Transport::Udp,
+                "loopback" => self.network.transport = Transport::Loopback,
+                _ => {}
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__DISCOVERY") {
+            match v.as_str() {
+                "multicast" => self.network.discovery = Discovery::Multicast,
+                "static" => self.network.discovery = Discovery::Static,
+                "off" => self.network.discovery = Discovery::Off,
+                _ => {}
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_NETWORK__STATIC_PEERS") {
+            self.network.static_peers = v
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        if let Ok(v) = std::env::var("SUMMIT_API__BIND_ADDR") {
+            if let Ok(a) = v.parse() {
+                self.api.bind_addr = a;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_API__PORT") {
+            if let Ok(p) = v.parse() {
+                self.api.port = p;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_API__TOKEN") {
+            self.api.token = v;
+        }
+        if let Ok(v) = std::env::var("SUMMIT_API__MAX_REQUESTS_PER_SEC") {
+            if let Ok(n) = v.parse() {
+                self.api.max_requests_per_sec = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_CACHE__MAX_BYTES") {
+            if let Ok(n) = v.parse() {
+                self.cache.max_bytes = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_CACHE__BACKEND") {
+            match v.as_str() {
+                "disk" => self.cache.backend = CacheBackend::Disk,
+                "memory" => self.cache.backend = CacheBackend::Memory,
+                _ => {}
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_STORAGE__DATA_DIR") {
+            self.storage.data_dir = PathBuf::from(v);
+        }
+        if let Ok(v) = std::env::var("SUMMIT_STORAGE__ENCRYPT_AT_REST") {
+            self.storage.encrypt_at_rest = v == "true" || v == "1";
+        }
+        if let Ok(v) = std::env::var("SUMMIT_STORAGE__ENCRYPTION_KEY") {
+            self.storage.encryption_key = v;
+        }
+        if let Ok(v) = std::env::var("SUMMIT_QOS__MAX_BYTES_PER_SEC_PER_PEER") {
+            if let Ok(n) = v.parse() {
+                self.qos.max_bytes_per_sec_per_peer = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_QOS__PACING_US_REALTIME") {
+            if let Ok(n) = v.parse() {
+                self.qos.pacing_us_realtime = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_QOS__PACING_US_BULK") {
+            if let Ok(n) = v.parse() {
+                self.qos.pacing_us_bulk = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_QOS__PACING_US_BACKGROUND") {
+            if let Ok(n) = v.parse() {
+                self.qos.pacing_us_background = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_QOS__PACING_JITTER_US") {
+            if let Ok(n) = v.parse() {
+                self.qos.pacing_jitter_us = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_LOG__FORMAT") {
+            match v.as_str() {
+                "text" => self.log.format = LogFormat::Text,
+                "json" => self.log.format = LogFormat::Json,
+                _ => {}
+            }
+        }
         if let Ok(v) = std::env::var("SUMMIT_TRUST__AUTO_TRUST") {
             self.trust.auto_trust = v == "true" || v == "1";
         }
@@ -280,6 +841,22 @@ impl SummitConfig {
         if let Ok(v) = std::env::var("SUMMIT_FILE_TRANSFER__STORAGE_PATH") {
             self.services.file_transfer_settings.storage_path = PathBuf::from(v);
         }
+        if let Ok(v) = std::env::var("SUMMIT_FILE_TRANSFER__MAX_CONCURRENT_ASSEMBLIES") {
+            if let Ok(n) = v.parse() {
+                self.services
+                    .file_transfer_settings
+                    .max_concurrent_assemblies = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_FILE_TRANSFER__ON_CONFLICT") {
+            let policy = &mut self.services.file_transfer_settings.on_conflict;
+            match v.as_str() {
+                "overwrite" => *policy = OnConflict::Overwrite,
+                "rename" => *policy = OnConflict::Rename,
+                "reject" => *policy = OnConflict::Reject,
+                _ => {}
+            }
+        }
         if let Ok(v) = std::env::var("SUMMIT_SERVICES__MESSAGING") {
             self.services.messaging = v == "true" || v == "1";
         }
@@ -289,6 +866,45 @@ impl SummitConfig {
         if let Ok(v) = std::env::var("SUMMIT_SERVICES__COMPUTE") {
             self.services.compute = v == "true" || v == "1";
         }
+        if let Ok(v) = std::env::var("SUMMIT_COMPUTE__TASK_LOG") {
+            self.services.compute_settings.task_log = Some(PathBuf::from(v));
+        }
+        if let Ok(v) = std::env::var("SUMMIT_SERVICES__SYNC") {
+            self.services.sync = v == "true" || v == "1";
+        }
+        if let Ok(v) = std::env::var("SUMMIT_SYNC__INTERVAL_SECS") {
+            if let Ok(n) = v.parse() {
+                self.services.sync_settings.interval_secs = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_COMPUTE__MAX_CONCURRENT_TASKS") {
+            if let Ok(n) = v.parse() {
+                self.services.compute_settings.max_concurrent_tasks = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_COMPUTE__TASK_TIMEOUT_SECS") {
+            if let Ok(n) = v.parse() {
+                self.services.compute_settings.task_timeout_secs = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_COMPUTE__MAX_OUTPUT_BYTES") {
+            if let Ok(n) = v.parse() {
+                self.services.compute_settings.max_output_bytes = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_COMPUTE__MAX_OUTPUT_FILE_BYTES") {
+            if let Ok(n) = v.parse() {
+                self.services.compute_settings.max_output_file_bytes = n;
+            }
+        }
+        if let Ok(v) = std::env::var("SUMMIT_COMPUTE__ALLOWED_COMMANDS") {
+            self.services.compute_settings.allowed_commands = v
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect();
+        }
     }
 }
 
@@ -303,6 +919,238 @@ mod tests {
         assert!(config.services.messaging);
         assert!(!config.services.stream_udp);
         assert!(!config.services.compute);
+        assert!(!config.services.sync);
+    }
+
+    #[test]
+    fn sync_interval_validated_only_when_enabled() {
+        let config: SummitConfig =
+            toml::from_str("[services.sync_settings]\ninterval_secs = 0\n").unwrap();
+        assert!(config.validate().is_ok());
+
+        let config: SummitConfig = toml::from_str(
+            "[services]\nsync = true\n[services.sync_settings]\ninterval_secs = 0\n",
+        )
+        .unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+        assert_eq!(SyncSettings::default().interval_secs, 30);
+    }
+
+    #[test]
+    fn compute_task_log_defaults_under_data_dir() {
+        let data_dir = Path::new("/var/lib/summit");
+        let path = |text: &str| {
+            let config: SummitConfig = toml::from_str(text).unwrap();
+            config.services.compute_settings.task_log_path(data_dir)
+        };
+        assert_eq!(
+            path(""),
+            Some(PathBuf::from("/var/lib/summit/compute_tasks.jsonl"))
+        );
+        assert_eq!(path("[services.compute_settings]\ntask_log = \"\"\n"), None);
+        assert_eq!(
+            path("[services.compute_settings]\ntask_log = \"/tmp/tasks.jsonl\"\n"),
+            Some(PathBuf::from("/tmp/tasks.jsonl"))
+        );
+    }
+
+    #[test]
+    fn default_api_binds_loopback() {
+        let api = SummitConfig::default().api;
+        assert_eq!(api.socket_addr().to_string(), "127.0.0.1:9001");
+        assert!(api.socket_addr().ip().is_loopback());
+        assert_eq!(api.token(), None);
+    }
+
+    #[test]
+    fn discovery_defaults_and_validation() {
+        let config = SummitConfig::default();
+        assert_eq!(config.network.multicast_group.to_string(), "ff02::1");
+        assert_eq!(config.network.discovery_port, 9000);
+        assert!(config.validate().is_ok());
+
+        let config: SummitConfig =
+            toml::from_str("[network]\nmulticast_group = \"ff02::5ed\"\ndiscovery_port = 9100\n")
+                .unwrap();
+        assert!(config.validate().is_ok());
+
+        let config: SummitConfig =
+            toml::from_str("[network]\nmulticast_group = \"fe80::1\"\n").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+
+        let config: SummitConfig = toml::from_str("[network]\ndiscovery_port = 0\n").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn peer_ttl_defaults_and_validation() {
+        let network = SummitConfig::default().network;
+        assert_eq!(network.peer_ttl_secs, crate::wire::PEER_TTL_SECS);
+        assert_eq!(network.peer_expiry_interval_secs, 1);
+
+        let config: SummitConfig =
+            toml::from_str("[network]\npeer_ttl_secs = 30\npeer_expiry_interval_secs = 5\n")
+                .unwrap();
+        assert_eq!(config.network.peer_ttl_secs, 30);
+        assert!(config.validate().is_ok());
+
+        let config: SummitConfig = toml::from_str("[network]\npeer_ttl_secs = 2\n").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+
+        let config: SummitConfig =
+            toml::from_str("[network]\npeer_expiry_interval_secs = 0\n").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn cache_backend_parses_lowercase() {
+        let config: SummitConfig = toml::from_str("[cache]\nbackend = \"memory\"\n").unwrap();
+        assert_eq!(config.cache.backend, CacheBackend::Memory);
+        assert_eq!(SummitConfig::default().cache.backend, CacheBackend::Disk);
+    }
+
+    #[test]
+    fn received_file_conflicts_default_to_rename() {
+        let config: SummitConfig =
+            toml::from_str("[services.file_transfer_settings]\non_conflict = \"reject\"\n")
+                .unwrap();
+        assert_eq!(
+            config.services.file_transfer_settings.on_conflict,
+            OnConflict::Reject
+        );
+        assert_eq!(
+            SummitConfig::default()
+                .services
+                .file_transfer_settings
+                .on_conflict,
+            OnConflict::Rename
+        );
+    }
+
+    #[test]
+    fn static_peers_parse_and_reject_malformed_entries() {
+        let key = "ab".repeat(32);
+        let mut config = SummitConfig::default();
+        assert_eq!(config.network.discovery, Discovery::Multicast);
+
+        config.network.static_peers = vec![format!("{key}@[fe80::2]:7000")];
+        assert_eq!(
+            config.network.static_peers().unwrap(),
+            vec![StaticPeer {
+                public_key: [0xab; 32],
+                addr: "fe80::2".parse().unwrap(),
+                session_port: 7000,
+            }]
+        );
+        assert!(config.validate().is_ok());
+
+        for bad in [
+            format!("{key}@fe80::2"),
+            format!("{key}@[fe80::2]:0"),
+            "abcd@[fe80::2]:7000".to_string(),
+            "[fe80::2]:7000".to_string(),
+        ] {
+            config.network.static_peers = vec![bad];
+            assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+        }
+    }
+
+    #[test]
+    fn transport_defaults_to_udp() {
+        let config: SummitConfig = toml::from_str("[network]\ntransport = \"loopback\"\n").unwrap();
+        assert_eq!(config.network.transport, Transport::Loopback);
+        assert_eq!(SummitConfig::default().network.transport, Transport::Udp);
+    }
+
+    #[test]
+    fn storage_encryption_is_opt_in_and_key_validated() {
+        let config = SummitConfig::default();
+        assert!(!config.storage.encrypt_at_rest);
+        assert_eq!(config.storage.key().unwrap(), None);
+
+        let key = "ab".repeat(32);
+        let config: SummitConfig = toml::from_str(&format!(
+            "[storage]\nencrypt_at_rest = true\nencryption_key = \"0x{key}\"\n"
+        ))
+        .unwrap();
+        assert!(config.storage.encrypt_at_rest);
+        assert_eq!(config.storage.key().unwrap(), Some([0xab; 32]));
+        assert!(config.validate().is_ok());
+
+        let config: SummitConfig =
+            toml::from_str("[storage]\nencryption_key = \"abcd\"\n").unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+
+        let bad = "zz".repeat(32);
+        let config: SummitConfig =
+            toml::from_str(&format!("[storage]\nencryption_key = \"{bad}\"\n")).unwrap();
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+    }
+
+    #[test]
+    fn changed_fields_lists_dotted_paths() {
+        let old = SummitConfig::default();
+        assert!(old.changed_fields(&old.clone()).is_empty());
+
+        let mut new = old.clone();
+        new.network.session_port = 7000;
+        new.trust.trusted_peers.push("ab".repeat(32));
+        new.services.compute_settings.task_timeout_secs = 60;
+        assert_eq!(
+            old.changed_fields(&new),
+            vec![
+                "network.session_port",
+                "services.compute_settings.task_timeout_secs",
+                "trust.trusted_peers",
+            ]
+        );
+    }
+
+    #[test]
+    fn qos_peer_cap_defaults_to_unlimited() {
+        assert_eq!(SummitConfig::default().qos.max_bytes_per_sec_per_peer, 0);
+        let config: SummitConfig =
+            toml::from_str("[qos]\nmax_bytes_per_sec_per_peer = 1048576\n").unwrap();
+        assert_eq!(config.qos.max_bytes_per_sec_per_peer, 1_048_576);
+    }
+
+    #[test]
+    fn pacing_defaults_to_off() {
+        let qos = SummitConfig::default().qos;
+        assert_eq!(
+            (
+                qos.pacing_us_realtime,
+                qos.pacing_us_bulk,
+                qos.pacing_us_background,
+                qos.pacing_jitter_us
+            ),
+            (0, 0, 0, 0)
+        );
+        let config: SummitConfig =
+            toml::from_str("[qos]\npacing_us_bulk = 200\npacing_jitter_us = 50\n").unwrap();
+        assert_eq!(config.qos.pacing_us_bulk, 200);
+        assert_eq!(config.qos.pacing_jitter_us, 50);
+    }
+
+    #[test]
+    fn log_format_defaults_to_text() {
+        assert_eq!(SummitConfig::default().log.format, LogFormat::Text);
+        let config: SummitConfig = toml::from_str("[log]\nformat = \"json\"\n").unwrap();
+        assert_eq!(config.log.format, LogFormat::Json);
+    }
+
+    #[test]
+    fn network_api_port_is_an_alias_for_api_port() {
+        let config = SummitConfig::parse("[network]\napi_port = 9100\n").unwrap();
+        assert_eq!(config.api.port, 9100);
+        assert_eq!(config.network.api_port, Some(9100));
+
+        let config =
+            SummitConfig::parse("[network]\napi_port = 9100\n[api]\nport = 9200\n").unwrap();
+        assert_eq!(config.api.port, 9200);
+
+        let text = toml::to_string_pretty(&SummitConfig::default()).unwrap();
+        assert!(!text.contains("api_port"));
     }
 
     #[test]
@@ -336,6 +1184,13 @@ mod tests {
         assert!(config.services.file_transfer);
         assert!(config.services.messaging);
 
+        // A file that exists but doesn't parse is an error, not defaults.
+        std::fs::write(&config_path, "[network\n").unwrap();
+        assert!(matches!(
+            SummitConfig::load(),
+            Err(ConfigError::ParseFailed(..))
+        ));
+
         // Clean up
         unsafe {
             std::env::remove_var("SUMMIT_CONFIG");
@@ -1,8 +1,11 @@
 //! Cryptographic primitives for Summit.
 //!
-//! Provides two things:
+//! Provides four things:
 //!   1. BLAKE3 hashing — content hashes, schema IDs, session ID derivation
 //!   2. Noise_XX session establishment — authenticated key exchange
+//!   3. XEdDSA signatures — capability announcements signed with the same
+//!      X25519 static key that Noise authenticates
+//!   4. At-rest encryption — ChaCha20-Poly1305 sealing of data written to disk
 //!
 //! Keypairs are managed via x25519-dalek for explicit key control.
 //! snow drives the Noise_XX state machine using those keys.
@@ -10,7 +13,13 @@
 //! All key material derives ZeroizeOnDrop — wiped from memory when dropped.
 //! There is no unsafe code in this module.
 
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::{clamp_integer, Scalar};
 use rand::RngCore;
+use sha2::{Digest, Sha512};
 use snow::{Builder, HandshakeState, StatelessTransportState};
 use thiserror::Error;
 use x25519_dalek::{PublicKey, StaticSecret};
@@ -123,6 +132,82 @@ impl Keypair {
     }
 }
 
+// ── XEdDSA ────────────────────────────────────────────────────────────────────
+//
+// Signal's XEdDSA: the X25519 private key is reused as an Ed25519 signing
+// scalar, so a signature verifies against the Montgomery public key itself.
+// No separate signing key is stored, announced, or needs binding to the
+// Noise identity.
+
+impl Keypair {
+    /// Sign a message with the static key (XEdDSA).
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        let k = Scalar::from_bytes_mod_order(clamp_integer(*self.private));
+        let big_a = EdwardsPoint::mul_base(&k).compress();
+        // The Edwards public key is defined with sign bit zero.
+        let (a, big_a) = if big_a.as_bytes()[31] & 0x80 != 0 {
+            let a = -k;
+            (a, EdwardsPoint::mul_base(&a).compress())
+        } else {
+            (k, big_a)
+        };
+
+        let mut z = Zeroizing::new([0u8; 64]);
+        rand::thread_rng().fill_bytes(&mut *z);
+
+        let mut prefix = [0xFFu8; 32];
+        prefix[0] = 0xFE;
+        let r = hash_to_scalar(
+            Sha512::new()
+                .chain_update(prefix)
+                .chain_update(a.as_bytes())
+                .chain_update(message)
+                .chain_update(z.as_slice()),
+        );
+        let big_r = EdwardsPoint::mul_base(&r).compress();
+        let h = hash_to_scalar(
+            Sha512::new()
+                .chain_update(big_r.as_bytes())
+                .chain_update(big_a.as_bytes())
+                .chain_update(message),
+        );
+        let s = r + h * a;
+
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(big_r.as_bytes());
+        signature[32..].copy_from_slice(s.as_bytes());
+        signature
+    }
+}
+
+/// SHA-512 reduced mod the group order.
+fn hash_to_scalar(hasher: Sha512) -> Scalar {
+    Scalar::from_bytes_mod_order_wide(&hasher.finalize().into())
+}
+
+/// Verify an XEdDSA signature against an X25519 public key.
+pub fn verify(public: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    let Some(big_a) = MontgomeryPoint(*public).to_edwards(0) else {
+        return false;
+    };
+    if big_a.is_small_order() {
+        return false;
+    }
+    let r_bytes: [u8; 32] = signature[..32].try_into().expect("32-byte half");
+    let s_bytes: [u8; 32] = signature[32..].try_into().expect("32-byte half");
+    let Some(s) = Option::<Scalar>::from(Scalar::from_canonical_bytes(s_bytes)) else {
+        return false;
+    };
+    let big_r = CompressedEdwardsY(r_bytes);
+    let h = hash_to_scalar(
+        Sha512::new()
+            .chain_update(big_r.as_bytes())
+            .chain_update(big_a.compress().as_bytes())
+            .chain_update(message),
+    );
+    EdwardsPoint::vartime_double_scalar_mul_basepoint(&-h, &big_a, &s).compress() == big_r
+}
+
 // ── Noise Handshake ───────────────────────────────────────────────────────────
 
 /// Generate a cryptographically random 16-byte nonce.
@@ -344,8 +429,8 @@ impl ReplayWindow {
 
     /// Returns true if the nonce is acceptable (not replayed, not too old).
     pub fn check(&self, nonce: u64) -> bool {
-        if nonce + WINDOW_SIZE < self.highest {
-            return false; // too old
+        if self.is_too_old(nonce) {
+            return false;
         }
         if nonce > self.highest {
             return true; // ahead of window
@@ -355,6 +440,12 @@ impl ReplayWindow {
         self.bitmap[word] & (1u64 << bit) == 0
     }
 
+    /// Whether the nonce has fallen behind the window, so can no longer be
+    /// told apart from a replay.
+    pub fn is_too_old(&self, nonce: u64) -> bool {
+        nonce + WINDOW_SIZE < self.highest
+    }
+
     /// Mark a nonce as seen. Call after successful decrypt.
     pub fn mark(&mut self, nonce: u64) {
         if nonce > self.highest {
@@ -436,7 +527,8 @@ impl Session {
     /// Decrypt ciphertext into `out`. Reads the 8-byte LE nonce prefix,
     /// checks the replay window, and verifies the Poly1305 MAC.
     ///
-    /// Returns Err on replay, truncation, or MAC failure.
+    /// Returns Err on replay, a nonce behind the replay window, truncation,
+    /// or MAC failure — each its own `CryptoError` variant.
     pub fn decrypt(&mut self, ciphertext: &[u8], out: &mut Vec<u8>) -> Result<(), CryptoError> {
         if ciphertext.len() < 8 + 16 {
             return Err(CryptoError::TooShort);
@@ -444,6 +536,9 @@ impl Session {
 
         let nonce = u64::from_le_bytes(ciphertext[..8].try_into().unwrap());
 
+        if self.recv_window.is_too_old(nonce) {
+            return Err(CryptoError::TooOld);
+        }
         if !self.recv_window.check(nonce) {
             return Err(CryptoError::Replay);
         }
@@ -460,6 +555,81 @@ impl Session {
     }
 }
 
+// ── At-rest encryption ────────────────────────────────────────────────────────
+//
+// Noise protects data in transit only; once a file is reassembled it is
+// plaintext on disk. StorageCipher seals what summitd writes so a copied disk
+// or backup reveals nothing without the key. Sealed layout:
+//
+//   magic (8) || nonce (12) || ciphertext || tag (16)
+
+/// Prefix identifying a sealed blob.
+const SEALED_MAGIC: &[u8; 8] = b"SUMMITS1";
+const SEALED_NONCE_LEN: usize = 12;
+const SEALED_OVERHEAD: usize = SEALED_MAGIC.len() + SEALED_NONCE_LEN + 16;
+
+/// BLAKE3 derive_key context for the identity-derived storage key.
+const STORAGE_KEY_CONTEXT: &str = "summit 2025-01 at-rest storage key";
+
+/// Symmetric key for encrypting data at rest with ChaCha20-Poly1305.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct StorageCipher {
+    key: [u8; 32],
+}
+
+impl StorageCipher {
+    /// Use an explicit 32-byte key, e.g. from `storage.encryption_key`.
+    pub fn new(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+
+    /// Derive the key from the device's identity keypair. Anyone holding the
+    /// keypair file can decrypt, so keep it off the disk being protected if
+    /// that matters.
+    pub fn from_identity(keypair: &Keypair) -> Self {
+        Self {
+            key: blake3::derive_key(STORAGE_KEY_CONTEXT, keypair.private.as_ref()),
+        }
+    }
+
+    /// Encrypt `plaintext` under a fresh random nonce.
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; SEALED_NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = ChaCha20Poly1305::new((&self.key).into())
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .expect("ChaCha20-Poly1305 encryption cannot fail for in-memory buffers");
+
+        let mut out = Vec::with_capacity(SEALED_OVERHEAD + plaintext.len());
+        out.extend_from_slice(SEALED_MAGIC);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        out
+    }
+
+    /// Decrypt a blob produced by [`seal`](Self::seal).
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if !Self::is_sealed(sealed) {
+            return Err(CryptoError::NotSealed);
+        }
+        let (nonce, ciphertext) = sealed[SEALED_MAGIC.len()..].split_at(SEALED_NONCE_LEN);
+        ChaCha20Poly1305::new((&self.key).into())
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CryptoError::Decrypt)
+    }
+
+    /// Size of the blob [`seal`](Self::seal) makes from `plaintext_len` bytes.
+    pub fn sealed_len(plaintext_len: usize) -> usize {
+        SEALED_OVERHEAD + plaintext_len
+    }
+
+    /// Whether `data` looks like a sealed blob. Files written before
+    /// encryption was enabled are plaintext and fail this check.
+    pub fn is_sealed(data: &[u8]) -> bool {
+        data.len() >= SEALED_OVERHEAD && data.starts_with(SEALED_MAGIC)
+    }
+}
+
 // ── Errors ────────────────────────────────────────────────────────────────────
 
 #[derive(Debug, Error)]
@@ -473,8 +643,17 @@ pub enum CryptoError {
     #[error("ciphertext too short (need at least 24 bytes: 8 nonce + 16 MAC)")]
     TooShort,
 
-    #[error("replayed or too-old nonce")]
+    #[error("replayed nonce")]
     Replay,
+
+    #[error("nonce too old — behind the replay window")]
+    TooOld,
+
+    #[error("data is not sealed with a storage key")]
+    NotSealed,
+
+    #[error("decryption failed — wrong storage key or corrupted data")]
+    Decrypt,
 }
 
 // ── Tests ─────────────────────────────────────────────────────────────────────
@@ -483,6 +662,59 @@ pub enum CryptoError {
 mod tests {
     use super::*;
 
+    #[test]
+    fn storage_cipher_round_trip() {
+        let cipher = StorageCipher::new([7u8; 32]);
+        let sealed = cipher.seal(b"hello at rest");
+        assert!(StorageCipher::is_sealed(&sealed));
+        assert!(!sealed.windows(13).any(|w| w == b"hello at rest"));
+        assert_eq!(cipher.open(&sealed).unwrap(), b"hello at rest");
+
+        // Fresh nonce per seal
+        assert_ne!(cipher.seal(b"hello at rest"), sealed);
+    }
+
+    #[test]
+    fn storage_cipher_rejects_wrong_key_and_plaintext() {
+        let kp = Keypair::generate();
+        let cipher = StorageCipher::from_identity(&kp);
+        let sealed = cipher.seal(b"secret");
+
+        // Same identity derives the same key
+        let again = StorageCipher::from_identity(&Keypair::from_private(*kp.private_bytes()));
+        assert_eq!(again.open(&sealed).unwrap(), b"secret");
+
+        let other = StorageCipher::from_identity(&Keypair::generate());
+        assert!(matches!(other.open(&sealed), Err(CryptoError::Decrypt)));
+        assert!(matches!(
+            cipher.open(b"plain file contents, never sealed"),
+            Err(CryptoError::NotSealed)
+        ));
+    }
+
+    #[test]
+    fn signature_round_trip() {
+        let kp = Keypair::generate();
+        let sig = kp.sign(b"announce");
+        assert!(verify(&kp.public, b"announce", &sig));
+    }
+
+    #[test]
+    fn signature_rejects_tampered_message() {
+        let kp = Keypair::generate();
+        let sig = kp.sign(b"announce");
+        assert!(!verify(&kp.public, b"announcf", &sig));
+    }
+
+    #[test]
+    fn signature_rejects_other_key() {
+        let kp = Keypair::generate();
+        let other = Keypair::generate();
+        let sig = kp.sign(b"announce");
+        assert!(!verify(&other.public, b"announce", &sig));
+        assert!(!verify(&[0u8; 32], b"announce", &sig));
+    }
+
     /// Helper: run a complete handshake and return both sessions ready for use.
     fn completed_sessions() -> (Session, Session) {
         let ikp = Keypair::generate();
@@ -608,7 +840,7 @@ mod tests {
 
         let responder = NoiseResponder::new(&rkp).unwrap();
         let r_nonce = *responder.nonce();
-        let (pending, msg2) = responder.respond(&msg1, &i_nonce).unwrap();
+        let (_pending, msg2) = responder.respond(&msg1, &i_nonce).unwrap();
         println!("msg2 size: {}", msg2.len());
 
         let (_session, msg3) = initiator.finish(&msg2, &r_nonce).unwrap();
@@ -789,7 +1021,28 @@ mod tests {
 
         // Second decrypt of same ciphertext is rejected
         let result = r_sess.decrypt(&ct, &mut pt);
-        assert!(result.is_err(), "replayed ciphertext should be rejected");
+        assert!(
+            matches!(result, Err(CryptoError::Replay)),
+            "replayed ciphertext should be rejected"
+        );
+    }
+
+    #[test]
+    fn nonce_behind_window_is_too_old_not_replay() {
+        let (mut i_sess, mut r_sess) = completed_sessions();
+
+        let mut first = Vec::new();
+        let mut ct = Vec::new();
+        let mut pt = Vec::new();
+        i_sess.encrypt(b"held back", &mut first).unwrap();
+        for _ in 0..WINDOW_SIZE + 1 {
+            i_sess.encrypt(b"later", &mut ct).unwrap();
+        }
+        r_sess.decrypt(&ct, &mut pt).unwrap();
+
+        // Never seen, but too far behind to tell from a replay
+        let result = r_sess.decrypt(&first, &mut pt);
+        assert!(matches!(result, Err(CryptoError::TooOld)));
     }
 
     #[test]
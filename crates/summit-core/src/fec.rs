@@ -0,0 +1,241 @@
+//! Forward error correction — systematic Reed-Solomon over GF(2^8).
+//!
+//! `k` data shards are extended with `m` parity shards such that any `k`
+//! of the `k + m` recover the data. The code matrix is the identity on top
+//! of a Cauchy matrix, every square submatrix of which is invertible, so
+//! there is no combination of `k` shards that fails to decode.
+//!
+//! Shards in a group share one length; the caller pads short data shards
+//! with zeros and trims them again after reconstruction.
+
+/// Most shards (data + parity) in one group — the field has 256 elements.
+pub const MAX_SHARDS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FecError {
+    #[error("{0} shards exceeds the maximum of {MAX_SHARDS}")]
+    TooManyShards(usize),
+
+    #[error("no data shards")]
+    NoData,
+
+    #[error("shards differ in length")]
+    ShardLength,
+
+    #[error("only {present} of the {needed} shards needed are present")]
+    NotEnoughShards { present: usize, needed: usize },
+}
+
+const fn exp_log_tables() -> ([u8; 512], [u8; 256]) {
+    let mut exp = [0u8; 512];
+    let mut log = [0u8; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        exp[i + 255] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11d;
+        }
+        i += 1;
+    }
+    (exp, log)
+}
+
+const TABLES: ([u8; 512], [u8; 256]) = exp_log_tables();
+const EXP: [u8; 512] = TABLES.0;
+const LOG: [u8; 256] = TABLES.1;
+
+fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+}
+
+fn inv(a: u8) -> u8 {
+    debug_assert_ne!(a, 0);
+    EXP[255 - LOG[a as usize] as usize]
+}
+
+/// Row `row` of the code matrix over `k` data shards: a unit row for a
+/// data shard, a Cauchy row for a parity shard.
+fn matrix_row(row: usize, k: usize) -> Vec<u8> {
+    if row < k {
+        let mut unit = vec![0u8; k];
+        unit[row] = 1;
+        return unit;
+    }
+    (0..k).map(|col| inv(row as u8 ^ col as u8)).collect()
+}
+
+/// `out ^= coef * input`, byte by byte.
+fn mul_add(out: &mut [u8], coef: u8, input: &[u8]) {
+    if coef == 0 {
+        return;
+    }
+    for (o, i) in out.iter_mut().zip(input) {
+        *o ^= mul(coef, *i);
+    }
+}
+
+/// Compute `parity` parity shards for `data`. Data shards shorter than the
+/// longest are treated as zero-padded; every parity shard has the longest
+/// length.
+pub fn encode(data: &[&[u8]], parity: usize) -> Result<Vec<Vec<u8>>, FecError> {
+    let k = data.len();
+    if k == 0 {
+        return Err(FecError::NoData);
+    }
+    if k + parity > MAX_SHARDS {
+        return Err(FecError::TooManyShards(k + parity));
+    }
+    let len = data.iter().map(|d| d.len()).max().unwrap_or(0);
+    Ok((k..k + parity)
+        .map(|row| {
+            let mut shard = vec![0u8; len];
+            for (coef, input) in matrix_row(row, k).into_iter().zip(data) {
+                mul_add(&mut shard, coef, input);
+            }
+            shard
+        })
+        .collect())
+}
+
+/// Invert a square matrix by Gauss-Jordan elimination. The matrices built
+/// here are always invertible; `None` means a bug, not bad input.
+fn invert(mut m: Vec<Vec<u8>>) -> Option<Vec<Vec<u8>>> {
+    let n = m.len();
+    let mut out: Vec<Vec<u8>> = (0..n).map(|r| matrix_row(r, n)).collect();
+    for col in 0..n {
+        let pivot = (col..n).find(|&r| m[r][col] != 0)?;
+        m.swap(col, pivot);
+        out.swap(col, pivot);
+        let scale = inv(m[col][col]);
+        for c in 0..n {
+            m[col][c] = mul(m[col][c], scale);
+            out[col][c] = mul(out[col][c], scale);
+        }
+        for r in 0..n {
+            let factor = m[r][col];
+            if r == col || factor == 0 {
+                continue;
+            }
+            for c in 0..n {
+                m[r][c] ^= mul(factor, m[col][c]);
+                out[r][c] ^= mul(factor, out[col][c]);
+            }
+        }
+    }
+    Some(out)
+}
+
+/// Fill in the missing data shards of a group. `shards` holds the `k`
+/// data shards followed by the parity shards, `None` for the ones not
+/// received; all present shards must be the same length. Missing parity
+/// shards are left missing.
+pub fn reconstruct(shards: &mut [Option<Vec<u8>>], k: usize) -> Result<(), FecError> {
+    if k == 0 {
+        return Err(FecError::NoData);
+    }
+    if shards.len() > MAX_SHARDS {
+        return Err(FecError::TooManyShards(shards.len()));
+    }
+    if shards.len() >= k && shards[..k].iter().all(Option::is_some) {
+        return Ok(());
+    }
+
+    // Any k present shards will do; data shards first, as they're free.
+    let present: Vec<usize> = (0..shards.len())
+        .filter(|&i| shards[i].is_some())
+        .take(k)
+        .collect();
+    if present.len() < k {
+        return Err(FecError::NotEnoughShards {
+            present: present.len(),
+            needed: k,
+        });
+    }
+    let len = shards[present[0]].as_ref().map_or(0, Vec::len);
+    if present
+        .iter()
+        .any(|&i| shards[i].as_ref().map_or(0, Vec::len) != len)
+    {
+        return Err(FecError::ShardLength);
+    }
+
+    let decode = invert(present.iter().map(|&r| matrix_row(r, k)).collect())
+        .expect("Cauchy code matrix is always invertible");
+    for missing in (0..k).filter(|&i| shards[i].is_none()).collect::<Vec<_>>() {
+        let mut shard = vec![0u8; len];
+        for (coef, &src) in decode[missing].iter().zip(&present) {
+            if let Some(input) = &shards[src] {
+                mul_add(&mut shard, *coef, input);
+            }
+        }
+        shards[missing] = Some(shard);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_shards(k: usize, len: usize) -> Vec<Vec<u8>> {
+        (0..k)
+            .map(|i| (0..len).map(|b| (i * 31 + b * 7) as u8).collect())
+            .collect()
+    }
+
+    #[test]
+    fn field_inverse_round_trips() {
+        for a in 1..=255u8 {
+            assert_eq!(mul(a, inv(a)), 1, "a = {a}");
+        }
+    }
+
+    #[test]
+    fn any_k_shards_recover_the_data() {
+        let (k, m) = (4, 3);
+        let data = data_shards(k, 100);
+        let refs: Vec<&[u8]> = data.iter().map(Vec::as_slice).collect();
+        let parity = encode(&refs, m).unwrap();
+        assert_eq!(parity.len(), m);
+
+        let all: Vec<Vec<u8>> = data.iter().chain(&parity).cloned().collect();
+        // Every way of losing m shards out of k + m.
+        for lost in 0u32..(1 << (k + m)) {
+            if lost.count_ones() as usize != m {
+                continue;
+            }
+            let mut shards: Vec<Option<Vec<u8>>> = all
+                .iter()
+                .enumerate()
+                .map(|(i, s)| (lost & (1 << i) == 0).then(|| s.clone()))
+                .collect();
+            reconstruct(&mut shards, k).unwrap();
+            for i in 0..k {
+                assert_eq!(shards[i].as_ref(), Some(&data[i]), "lost {lost:b}");
+            }
+        }
+    }
+
+    #[test]
+    fn too_few_shards_is_an_error() {
+        let data = data_shards(3, 10);
+        let refs: Vec<&[u8]> = data.iter().map(Vec::as_slice).collect();
+        let parity = encode(&refs, 1).unwrap();
+        let mut shards = vec![Some(data[0].clone()), None, None, Some(parity[0].clone())];
+        assert_eq!(
+            reconstruct(&mut shards, 3),
+            Err(FecError::NotEnoughShards {
+                present: 2,
+                needed: 3
+            })
+        );
+        assert_eq!(encode(&refs, 254), Err(FecError::TooManyShards(257)));
+    }
+}
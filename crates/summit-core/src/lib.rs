@@ -4,5 +4,6 @@
 
 pub mod config;
 pub mod crypto;
+pub mod fec;
 pub mod recovery;
 pub mod wire;
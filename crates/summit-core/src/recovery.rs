@@ -4,6 +4,10 @@
 //! metadata), it sends a NACK listing the missing content hashes. The
 //! sender looks them up in its ChunkCache and re-sends. If the cache has
 //! been evicted, the sender responds with GONE so the receiver can give up.
+//!
+//! Before any data is sent, the receiver answers a file's metadata with a
+//! HAVE bitmap of the chunks already in its own cache, and the sender sends
+//! only the rest.
 
 use serde::{Deserialize, Serialize};
 
@@ -45,3 +49,36 @@ pub struct Gone {
     /// Content hashes the sender no longer has.
     pub hashes: Vec<[u8; 32]>,
 }
+
+/// HAVE payload — the receiver's answer to a file metadata chunk, marking
+/// which of the file's chunks it already holds.
+///
+/// Wire: schema_id = recovery_hash(), type_tag = recovery::HAVE
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Have {
+    /// Content hash of the metadata chunk being answered.
+    pub metadata_hash: [u8; 32],
+    /// Bit `i` (least significant first within each byte) is set when
+    /// chunk `i` is held.
+    pub bitmap: Vec<u8>,
+}
+
+impl Have {
+    pub fn new(metadata_hash: [u8; 32], held: &[bool]) -> Self {
+        let mut bitmap = vec![0u8; held.len().div_ceil(8)];
+        for (i, _) in held.iter().enumerate().filter(|(_, h)| **h) {
+            bitmap[i / 8] |= 1 << (i % 8);
+        }
+        Self {
+            metadata_hash,
+            bitmap,
+        }
+    }
+
+    /// Does the receiver hold chunk `index`?
+    pub fn holds(&self, index: usize) -> bool {
+        self.bitmap
+            .get(index / 8)
+            .is_some_and(|b| b & (1 << (index % 8)) != 0)
+    }
+}
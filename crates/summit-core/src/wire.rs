@@ -5,7 +5,7 @@
 //! zerocopy derives for safe, allocation-free serialization. There is no
 //! unsafe code in this module.
 
-use static_assertions::assert_eq_size;
+use static_assertions::{assert_eq_size, const_assert};
 use zerocopy::{AsBytes, FromBytes, FromZeroes};
 
 // ── Chunk Header ─────────────────────────────────────────────────────────────
@@ -41,7 +41,8 @@ pub struct ChunkHeader {
     /// Bit flags:
     ///   bits 0-1: priority class (mirrors Contract — 0x01 realtime, 0x02 bulk, 0x03 background)
     ///   bit    2: payload is zstd-compressed (reserved, not implemented in Zenith)
-    ///   bits 3-7: reserved, must be zero
+    ///   bit    3: sent to this peer alone (`FLAG_TARGETED`)
+    ///   bits 4-7: reserved, must be zero
     pub flags: u8,
 
     /// Wire format version. Currently 0x01.
@@ -52,6 +53,24 @@ pub struct ChunkHeader {
 // Compile-time size guard. If this fails, the wire format has silently changed.
 assert_eq_size!(ChunkHeader, [u8; 72]);
 
+/// Split a decrypted chunk into its header and payload, checking the
+/// payload against the header's `content_hash`.
+///
+/// A chunk that fails this must never be cached or dispatched — the session
+/// MAC only proves who sent it, not that the sender hashed it correctly.
+pub fn open_chunk(plaintext: &[u8]) -> Result<(ChunkHeader, &[u8]), WireError> {
+    if plaintext.len() < HEADER_SIZE {
+        return Err(WireError::Truncated(plaintext.len()));
+    }
+    let header =
+        ChunkHeader::read_from_prefix(plaintext).ok_or(WireError::Truncated(plaintext.len()))?;
+    let payload = &plaintext[HEADER_SIZE..];
+    if crate::crypto::hash(payload) != header.content_hash {
+        return Err(WireError::HashMismatch);
+    }
+    Ok((header, payload))
+}
+
 // ── Service Hashes ────────────────────────────────────────────────────────────
 
 /// Service identifier — BLAKE3 hash of a canonical service name.
@@ -86,6 +105,30 @@ pub fn recovery_hash() -> ServiceHash {
     service_hash(b"summit.recovery")
 }
 
+pub fn sync_hash() -> ServiceHash {
+    service_hash(b"summit.sync")
+}
+
+pub fn control_hash() -> ServiceHash {
+    service_hash(b"summit.control")
+}
+
+/// Short name of a well-known service hash ("file_transfer", "compute", …).
+pub fn service_name(hash: &ServiceHash) -> Option<&'static str> {
+    [
+        (file_transfer_hash(), "file_transfer"),
+        (messaging_hash(), "messaging"),
+        (stream_udp_hash(), "stream_udp"),
+        (compute_hash(), "compute"),
+        (recovery_hash(), "recovery"),
+        (sync_hash(), "sync"),
+        (control_hash(), "control"),
+    ]
+    .into_iter()
+    .find(|(h, _)| h == hash)
+    .map(|(_, name)| name)
+}
+
 /// Type tags for the recovery protocol.
 /// These use schema_id = recovery_hash() to distinguish from application chunks.
 pub mod recovery {
@@ -99,6 +142,31 @@ pub mod recovery {
     /// Receiver -> Sender: "Here's my bulk receive capacity."
     /// Sent post-handshake so the sender can tune its token bucket.
     pub const CAPACITY: u16 = 3;
+
+    /// Receiver -> Sender: "Of this file's chunks, I already hold these."
+    /// Answers a file metadata chunk so the sender can skip cached chunks.
+    pub const HAVE: u16 = 4;
+}
+
+/// Type tags for session control messages. Chunks use schema_id =
+/// control_hash(); like any chunk they are encrypted within the session and
+/// pass its replay window, so only the peer can send one, and only once.
+pub mod control {
+    /// "I'm going offline" — the receiver prunes the session at once
+    /// instead of waiting for it to time out. Empty payload.
+    pub const GOODBYE: u16 = 1;
+}
+
+/// Type tags for background cache sync. Chunks use schema_id = sync_hash().
+pub mod sync {
+    /// "These chunks are in my cache" — recently used hashes.
+    pub const OFFER: u16 = 1;
+
+    /// Answer to an offer: "send me these, I don't have them."
+    pub const WANT: u16 = 2;
+
+    /// One requested chunk; the payload is the chunk's bytes.
+    pub const DATA: u16 = 3;
 }
 
 // ── Capability Announcement ───────────────────────────────────────────────────
@@ -109,7 +177,12 @@ pub mod recovery {
 /// Receivers collect datagrams by `public_key` and build the peer's full
 /// service set when `service_index` values 0..service_count-1 are all present.
 ///
-/// Wire size: 76 bytes
+/// Every datagram is signed with the sender's static key, over its
+/// link-local address and a sequence number too. A captured announcement
+/// replayed from another address, or replayed at all once a newer one has
+/// been seen, is rejected.
+///
+/// Wire size: 164 bytes
 #[derive(Debug, Clone, AsBytes, FromBytes, FromZeroes)]
 #[repr(C, packed)]
 pub struct CapabilityAnnouncement {
@@ -117,7 +190,8 @@ pub struct CapabilityAnnouncement {
     /// Was `capability_hash` — renamed for clarity.
     pub service_hash: [u8; 32],
 
-    /// Ed25519 public key of the announcing peer.
+    /// X25519 static public key of the announcing peer — the key Noise
+    /// authenticates, and the key `signature` verifies against.
     pub public_key: [u8; 32],
 
     /// Protocol version.
@@ -142,9 +216,40 @@ pub struct CapabilityAnnouncement {
 
     /// Zero-indexed position of this service in the broadcast set.
     pub service_index: u8,
+
+    /// The sender's IPv6 link-local address. Receivers drop the datagram
+    /// unless it came from this address.
+    pub addr: [u8; 16],
+
+    /// Strictly increasing across every datagram the sender signs, restarts
+    /// included. Receivers drop one no newer than the last they accepted.
+    pub sequence: u64,
+
+    /// XEdDSA signature over every preceding field, by `public_key`.
+    pub signature: [u8; 64],
 }
 
-assert_eq_size!(CapabilityAnnouncement, [u8; 76]);
+assert_eq_size!(CapabilityAnnouncement, [u8; 164]);
+
+impl CapabilityAnnouncement {
+    /// The bytes covered by `signature` — everything before it.
+    pub fn signed_bytes(&self) -> &[u8] {
+        let bytes = self.as_bytes();
+        &bytes[..bytes.len() - 64]
+    }
+
+    /// Fill in `signature`. `keypair.public` must equal `public_key`.
+    pub fn sign(&mut self, keypair: &crate::crypto::Keypair) {
+        self.signature = keypair.sign(self.signed_bytes());
+    }
+
+    /// Whether `signature` was made by the holder of `public_key`.
+    pub fn verify(&self) -> bool {
+        let public_key = self.public_key;
+        let signature = self.signature;
+        crate::crypto::verify(&public_key, self.signed_bytes(), &signature)
+    }
+}
 
 // ── Handshake ─────────────────────────────────────────────────────────────────
 
@@ -157,10 +262,12 @@ assert_eq_size!(CapabilityAnnouncement, [u8; 76]);
 /// that contributes to the derived session ID.
 ///
 /// Noise_XX handshake message 1 — sent by the session initiator.
-/// Wire size: 80 bytes
+/// Wire size: 84 bytes
 #[derive(Debug, Clone, AsBytes, FromBytes, FromZeroes)]
 #[repr(C, packed)]
 pub struct HandshakeInit {
+    /// Initiator's `WIRE_VERSION`. The responder refuses incompatible versions.
+    pub version: u32,
     /// Initiator nonce — contributes to session ID derivation.
     pub nonce: [u8; 16],
     /// The service hash being requested.
@@ -169,7 +276,7 @@ pub struct HandshakeInit {
     pub noise_msg: [u8; 32],
 }
 
-assert_eq_size!(HandshakeInit, [u8; 80]);
+assert_eq_size!(HandshakeInit, [u8; 84]);
 
 /// Noise_XX handshake message 2 — sent by the responder.
 /// Wire size: 112 bytes (16 nonce + 96 noise message)
@@ -246,6 +353,24 @@ pub const SCHEMA_ID_RAW: [u8; 32] = [0u8; 32];
 /// Current chunk format version.
 pub const CHUNK_VERSION: u8 = 0x01;
 
+/// `ChunkHeader::flags` bit for a chunk sent to one peer rather than
+/// broadcast. The receiver keeps it to itself rather than offering it on.
+pub const FLAG_TARGETED: u8 = 0x08;
+
+/// Protocol version carried in announcements and HandshakeInit.
+/// Bump on any incompatible change to the handshake, chunk or message formats.
+pub const WIRE_VERSION: u32 = 5;
+
+/// Oldest peer version we still speak. Raise it to drop support for a
+/// version; keep it below `WIRE_VERSION` while both remain compatible.
+pub const MIN_WIRE_VERSION: u32 = 5;
+
+/// The version to run a session at with a peer announcing `theirs`,
+/// or `None` if the peer is too old to talk to.
+pub fn negotiate_version(theirs: u32) -> Option<u32> {
+    (theirs >= MIN_WIRE_VERSION).then(|| theirs.min(WIRE_VERSION))
+}
+
 /// Maximum payload size in bytes.
 /// Larger data must be split by the sender into multiple chunks.
 pub const MAX_PAYLOAD: usize = 65535;
@@ -259,21 +384,43 @@ pub const NONCE_SIZE: usize = 8;
 /// Poly1305 MAC appended by Noise encryption.
 pub const MAC_SIZE: usize = 16;
 
-/// Maximum UDP receive buffer size.
-///
-/// Fits the largest possible encrypted chunk:
+// ── Datagram size limits ─────────────────────────────────────────────────────
+//
+// Each socket accepts datagrams up to a fixed size per message kind. Receive
+// buffers are one byte larger than that, so a datagram that fills its buffer
+// was oversized — and possibly truncated by the kernel — and is dropped
+// rather than parsed.
+
+/// Largest encrypted chunk datagram:
 ///   NONCE_SIZE(8) + HEADER_SIZE(72) + MAX_PAYLOAD(65535) + MAC_SIZE(16) = 65631
-///
-/// Rounded up to 65632 for alignment.
-pub const MAX_UDP_BUF: usize = NONCE_SIZE + HEADER_SIZE + MAX_PAYLOAD + MAC_SIZE + 1;
+pub const MAX_CHUNK_DATAGRAM: usize = NONCE_SIZE + HEADER_SIZE + MAX_PAYLOAD + MAC_SIZE;
+
+/// Largest datagram on the session port: the biggest handshake message.
+/// The encrypted chunk-port exchange (NONCE_SIZE + 2 + MAC_SIZE) is smaller.
+pub const MAX_HANDSHAKE_DATAGRAM: usize = std::mem::size_of::<HandshakeResponse>();
+
+/// Largest datagram on the discovery port. Announcements are parsed by
+/// prefix, which leaves later versions room to append fields.
+pub const MAX_ANNOUNCEMENT_DATAGRAM: usize = 1023;
 
-/// IPv6 link-local multicast address for capability announcements (string form).
+/// Chunk receive buffer size — one byte past `MAX_CHUNK_DATAGRAM`.
+pub const MAX_UDP_BUF: usize = MAX_CHUNK_DATAGRAM + 1;
+
+const_assert!(MAX_HANDSHAKE_DATAGRAM >= std::mem::size_of::<HandshakeInit>());
+const_assert!(MAX_HANDSHAKE_DATAGRAM >= std::mem::size_of::<HandshakeComplete>());
+const_assert!(MAX_HANDSHAKE_DATAGRAM >= NONCE_SIZE + 2 + MAC_SIZE);
+const_assert!(MAX_ANNOUNCEMENT_DATAGRAM >= std::mem::size_of::<CapabilityAnnouncement>());
+
+/// Default IPv6 link-local multicast address for capability announcements (string form).
 pub const MULTICAST_ADDR: &str = "ff02::1";
 
 /// Pre-parsed multicast address — avoids runtime `.parse().unwrap()`.
 pub const MULTICAST_ADDR_V6: std::net::Ipv6Addr =
     std::net::Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
 
+/// Default UDP port for capability announcements.
+pub const DISCOVERY_PORT: u16 = 9000;
+
 /// Default capability announcement interval in seconds.
 pub const ANNOUNCE_INTERVAL_SECS: u64 = 2;
 
@@ -301,6 +448,12 @@ pub enum WireError {
 
     #[error("reserved flags are non-zero: 0x{0:02x}")]
     ReservedFlagsSet(u8),
+
+    #[error("chunk of {0} bytes is shorter than its header")]
+    Truncated(usize),
+
+    #[error("payload does not match content_hash")]
+    HashMismatch,
 }
 
 // ── Tests ─────────────────────────────────────────────────────────────────────
@@ -310,7 +463,6 @@ mod tests {
     use super::*;
     use zerocopy::AsBytes;
 
-    #[allow(dead_code)]
     fn zeroed_chunk_header() -> ChunkHeader {
         ChunkHeader {
             content_hash: [0u8; 32],
@@ -348,6 +500,46 @@ mod tests {
         assert_eq!(recovered.version, original.version);
     }
 
+    fn framed(header: &ChunkHeader, payload: &[u8]) -> Vec<u8> {
+        let mut buf = header.as_bytes().to_vec();
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    #[test]
+    fn open_chunk_accepts_matching_hash() {
+        let payload = b"hello summit";
+        let header = ChunkHeader {
+            content_hash: crate::crypto::hash(payload),
+            length: payload.len() as u32,
+            ..zeroed_chunk_header()
+        };
+        let buf = framed(&header, payload);
+
+        let (opened, body) = open_chunk(&buf).unwrap();
+        assert_eq!(opened.content_hash, header.content_hash);
+        assert_eq!(body, payload);
+    }
+
+    #[test]
+    fn open_chunk_rejects_payload_not_matching_hash() {
+        let header = ChunkHeader {
+            content_hash: crate::crypto::hash(b"what the sender claimed"),
+            ..zeroed_chunk_header()
+        };
+        let buf = framed(&header, b"what actually arrived");
+
+        assert_eq!(open_chunk(&buf).unwrap_err(), WireError::HashMismatch);
+    }
+
+    #[test]
+    fn open_chunk_rejects_truncated_header() {
+        assert_eq!(
+            open_chunk(&[0u8; HEADER_SIZE - 1]).unwrap_err(),
+            WireError::Truncated(HEADER_SIZE - 1)
+        );
+    }
+
     #[test]
     fn announcement_round_trip() {
         let original = CapabilityAnnouncement {
@@ -360,10 +552,13 @@ mod tests {
             flags: 0,
             service_count: 3,
             service_index: 1,
+            addr: [0x44; 16],
+            sequence: 42,
+            signature: [0x33; 64],
         };
 
         let bytes = original.as_bytes();
-        assert_eq!(bytes.len(), 76);
+        assert_eq!(bytes.len(), 164);
 
         let recovered = CapabilityAnnouncement::read_from(bytes).unwrap();
 
@@ -376,6 +571,8 @@ mod tests {
         let recovered_version = recovered.version;
         let recovered_service_count = recovered.service_count;
         let recovered_service_index = recovered.service_index;
+        let recovered_addr = recovered.addr;
+        let recovered_sequence = recovered.sequence;
 
         assert_eq!(recovered_service_hash, original.service_hash);
         assert_eq!(recovered_public_key, original.public_key);
@@ -385,6 +582,56 @@ mod tests {
         assert_eq!(recovered_version, 7);
         assert_eq!(recovered_service_count, 3);
         assert_eq!(recovered_service_index, 1);
+        assert_eq!(recovered_addr, [0x44; 16]);
+        assert_eq!(recovered_sequence, 42);
+    }
+
+    #[test]
+    fn announcement_signature_covers_fields_and_key() {
+        let keypair = crate::crypto::Keypair::generate();
+        let mut announcement = CapabilityAnnouncement {
+            service_hash: [0x11; 32],
+            public_key: keypair.public,
+            version: WIRE_VERSION,
+            session_port: 9000,
+            chunk_port: 0,
+            contract: Contract::Bulk as u8,
+            flags: 0,
+            service_count: 1,
+            service_index: 0,
+            addr: [0xfe; 16],
+            sequence: 1,
+            signature: [0; 64],
+        };
+        assert!(!announcement.verify());
+
+        announcement.sign(&keypair);
+        assert!(announcement.verify());
+
+        let mut moved = announcement.clone();
+        moved.session_port = 9999;
+        assert!(!moved.verify());
+
+        let mut relocated = announcement.clone();
+        relocated.addr = [0xaa; 16];
+        assert!(!relocated.verify());
+
+        let mut renumbered = announcement.clone();
+        renumbered.sequence = 2;
+        assert!(!renumbered.verify());
+
+        let mut stolen = announcement.clone();
+        stolen.public_key = crate::crypto::Keypair::generate().public;
+        assert!(!stolen.verify());
+    }
+
+    #[test]
+    fn service_name_resolves_known_hashes() {
+        assert_eq!(service_name(&compute_hash()), Some("compute"));
+        assert_eq!(service_name(&stream_udp_hash()), Some("stream_udp"));
+        assert_eq!(service_name(&sync_hash()), Some("sync"));
+        assert_eq!(service_name(&control_hash()), Some("control"));
+        assert_eq!(service_name(&service_hash(b"summit.unknown")), None);
     }
 
     #[test]
@@ -399,18 +646,28 @@ mod tests {
     #[test]
     fn handshake_init_round_trip() {
         let original = HandshakeInit {
+            version: WIRE_VERSION,
             nonce: [0x55; 16],
             service_hash: [0x44; 32],
             noise_msg: [0x33; 32],
         };
         let bytes = original.as_bytes();
-        assert_eq!(bytes.len(), 80);
+        assert_eq!(bytes.len(), 84);
         let recovered = HandshakeInit::read_from_prefix(bytes).unwrap();
+        let version = recovered.version;
+        assert_eq!(version, WIRE_VERSION);
         assert_eq!(recovered.nonce, original.nonce);
         assert_eq!(recovered.service_hash, original.service_hash);
         assert_eq!(recovered.noise_msg, original.noise_msg);
     }
 
+    #[test]
+    fn negotiate_version_rejects_old_and_caps_new() {
+        assert_eq!(negotiate_version(WIRE_VERSION), Some(WIRE_VERSION));
+        assert_eq!(negotiate_version(WIRE_VERSION + 1), Some(WIRE_VERSION));
+        assert_eq!(negotiate_version(MIN_WIRE_VERSION - 1), None);
+    }
+
     #[test]
     fn handshake_response_round_trip() {
         let original = HandshakeResponse {
@@ -1,14 +1,19 @@
 //! Compute task commands.
 
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use super::http::{base_url, get_json, post_json_body};
+use super::http::{base_url, decode, get_json, post_json_body};
 
 #[derive(Deserialize)]
 struct ComputeTasksResponse {
     peer_pubkey: String,
     tasks: Vec<ComputeTaskJson>,
+    #[serde(default)]
+    queue_depth: usize,
 }
 
 #[derive(Deserialize)]
@@ -18,19 +23,41 @@ struct ComputeTaskJson {
     status: String,
     submitted_at: u64,
     updated_at: u64,
+    started_at: Option<u64>,
+    completed_at: Option<u64>,
+    queue_ms: Option<u64>,
+    run_ms: Option<u64>,
+    #[serde(default)]
+    priority: i32,
     result: Option<serde_json::Value>,
     elapsed_ms: Option<u64>,
+    #[serde(default)]
+    output: Vec<OutputLineJson>,
+    #[serde(default)]
+    output_truncated: bool,
+    #[serde(default)]
+    received_files: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct OutputLineJson {
+    stream: String,
+    data: String,
 }
 
 #[derive(Deserialize)]
 struct ComputeAllTasksResponse {
     tasks: Vec<ComputeTaskJson>,
+    #[serde(default)]
+    queue_depth: usize,
 }
 
 #[derive(Serialize)]
 struct ComputeSubmitRequest {
     to: String,
     payload: serde_json::Value,
+    priority: i32,
+    force: bool,
 }
 
 #[derive(Deserialize)]
@@ -39,9 +66,14 @@ struct ComputeSubmitResponse {
     timestamp: u64,
 }
 
-pub async fn cmd_compute_tasks(port: u16, peer_pubkey: &str) -> Result<()> {
-    let resp: ComputeTasksResponse =
-        get_json(&format!("{}/compute/tasks/{}", base_url(port), peer_pubkey)).await?;
+pub async fn cmd_compute_tasks(port: u16, json: bool, peer_pubkey: &str) -> Result<()> {
+    let Some(resp) = decode::<ComputeTasksResponse>(
+        get_json(&format!("{}/compute/tasks/{}", base_url(port), peer_pubkey)).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
 
     if resp.tasks.is_empty() {
         println!(
@@ -57,6 +89,7 @@ pub async fn cmd_compute_tasks(port: u16, peer_pubkey: &str) -> Result<()> {
         &resp.peer_pubkey[..16.min(resp.peer_pubkey.len())]
     );
     println!("═══════════════════════════════════════");
+    print_queue_depth(resp.queue_depth);
 
     for t in &resp.tasks {
         print_task(t);
@@ -65,9 +98,14 @@ pub async fn cmd_compute_tasks(port: u16, peer_pubkey: &str) -> Result<()> {
     Ok(())
 }
 
-pub async fn cmd_compute_tasks_all(port: u16) -> Result<()> {
-    let resp: ComputeAllTasksResponse =
-        get_json(&format!("{}/compute/tasks", base_url(port))).await?;
+pub async fn cmd_compute_tasks_all(port: u16, json: bool) -> Result<()> {
+    let Some(resp) = decode::<ComputeAllTasksResponse>(
+        get_json(&format!("{}/compute/tasks", base_url(port))).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
 
     if resp.tasks.is_empty() {
         println!("No compute tasks.");
@@ -77,6 +115,7 @@ pub async fn cmd_compute_tasks_all(port: u16) -> Result<()> {
     println!("═══════════════════════════════════════");
     println!("  All Compute Tasks ({})", resp.tasks.len());
     println!("═══════════════════════════════════════");
+    print_queue_depth(resp.queue_depth);
 
     for t in &resp.tasks {
         print_task(t);
@@ -85,17 +124,112 @@ pub async fn cmd_compute_tasks_all(port: u16) -> Result<()> {
     Ok(())
 }
 
-pub async fn cmd_compute_submit(port: u16, to: &str, payload_str: &str) -> Result<()> {
+/// Poll compute tasks and print streamed output as it arrives.
+/// Returns once no task is queued or running. Under `--json`, each output
+/// line and status change is printed as one JSON object per line.
+pub async fn cmd_compute_tasks_follow(
+    port: u16,
+    json: bool,
+    peer_pubkey: Option<&str>,
+) -> Result<()> {
+    let url = match peer_pubkey {
+        Some(peer) => format!("{}/compute/tasks/{}", base_url(port), peer),
+        None => format!("{}/compute/tasks", base_url(port)),
+    };
+
+    let mut statuses: HashMap<String, String> = HashMap::new();
+    let mut printed: HashMap<String, usize> = HashMap::new();
+    let mut truncated: HashSet<String> = HashSet::new();
+
+    loop {
+        let tasks = match peer_pubkey {
+            Some(_) => get_json::<ComputeTasksResponse>(&url).await?.tasks,
+            None => get_json::<ComputeAllTasksResponse>(&url).await?.tasks,
+        };
+
+        if tasks.is_empty() {
+            if !json {
+                println!("No compute tasks.");
+            }
+            return Ok(());
+        }
+
+        for t in &tasks {
+            let short = &t.task_id[..16.min(t.task_id.len())];
+
+            let seen = printed.entry(t.task_id.clone()).or_default();
+            for line in t.output.iter().skip(*seen) {
+                if json {
+                    let event = serde_json::json!({
+                        "task_id": t.task_id,
+                        "stream": line.stream,
+                        "data": line.data,
+                    });
+                    println!("{}", event);
+                    continue;
+                }
+                for text in line.data.lines() {
+                    println!("[{}] {}: {}", short, line.stream, text);
+                }
+            }
+            *seen = (*seen).max(t.output.len());
+
+            if t.output_truncated && truncated.insert(t.task_id.clone()) {
+                if json {
+                    let event = serde_json::json!({ "task_id": t.task_id, "truncated": true });
+                    println!("{}", event);
+                } else {
+                    println!("[{}] (output truncated)", short);
+                }
+            }
+
+            if statuses.get(&t.task_id) != Some(&t.status) {
+                if json {
+                    let event = serde_json::json!({ "task_id": t.task_id, "status": t.status });
+                    println!("{}", event);
+                } else {
+                    println!("[{}] status: {}", short, t.status);
+                }
+                statuses.insert(t.task_id.clone(), t.status.clone());
+            }
+        }
+
+        if tasks
+            .iter()
+            .all(|t| t.status != "Queued" && t.status != "Running")
+        {
+            return Ok(());
+        }
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+pub async fn cmd_compute_submit(
+    port: u16,
+    json: bool,
+    to: &str,
+    payload_str: &str,
+    priority: i32,
+    force: bool,
+) -> Result<()> {
     let payload: serde_json::Value =
         serde_json::from_str(payload_str).context("payload must be valid JSON")?;
 
     let req = ComputeSubmitRequest {
         to: to.to_string(),
         payload,
+        priority,
+        force,
     };
 
-    let resp: ComputeSubmitResponse =
-        post_json_body(&format!("{}/compute/submit", base_url(port)), &req).await?;
+    let Some(resp) = decode::<ComputeSubmitResponse>(
+        post_json_body(&format!("{}/compute/submit", base_url(port)), &req).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
 
     println!("Compute task submitted:");
     println!(
@@ -107,16 +241,84 @@ pub async fn cmd_compute_submit(port: u16, to: &str, payload_str: &str) -> Resul
     Ok(())
 }
 
+#[derive(Serialize)]
+struct ComputeCancelRequest {
+    task_id: String,
+}
+
+#[derive(Deserialize)]
+struct ComputeCancelResponse {
+    task_id: String,
+    status: String,
+}
+
+pub async fn cmd_compute_cancel(port: u16, json: bool, task_id: &str) -> Result<()> {
+    let req = ComputeCancelRequest {
+        task_id: task_id.to_string(),
+    };
+
+    let Some(resp) = decode::<ComputeCancelResponse>(
+        post_json_body(&format!("{}/compute/cancel", base_url(port)), &req).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+
+    println!("Compute task cancelled:");
+    println!(
+        "  Task ID   : {}...",
+        &resp.task_id[..16.min(resp.task_id.len())]
+    );
+    println!("  Status    : {}", resp.status);
+
+    Ok(())
+}
+
+/// Tasks from peers waiting for an executor slot on this node.
+fn print_queue_depth(depth: usize) {
+    if depth > 0 {
+        println!("  Waiting for a slot: {}", depth);
+    }
+}
+
 fn print_task(t: &ComputeTaskJson) {
     println!("  ┌─ {}...", &t.task_id[..16.min(t.task_id.len())]);
     println!("  │  status       : {}", t.status);
     println!("  │  submitted_at : {}", t.submitted_at);
     println!("  │  updated_at   : {}", t.updated_at);
+    if let Some(at) = t.started_at {
+        println!("  │  started_at   : {}", at);
+    }
+    if let Some(at) = t.completed_at {
+        println!("  │  completed_at : {}", at);
+    }
+    if let Some(ms) = t.queue_ms {
+        println!("  │  queued for   : {}ms", ms);
+    }
+    if let Some(ms) = t.run_ms {
+        println!("  │  ran for      : {}ms", ms);
+    }
+    if t.priority != 0 {
+        println!("  │  priority     : {}", t.priority);
+    }
     if let Some(ms) = t.elapsed_ms {
         println!("  │  elapsed      : {}ms", ms);
     }
     if let Some(ref result) = t.result {
         print_result(result);
+    } else if !t.output.is_empty() {
+        for line in &t.output {
+            for text in line.data.lines() {
+                println!("  │  {:<12} : {}", line.stream, text);
+            }
+        }
+        if t.output_truncated {
+            println!("  │  (output truncated)");
+        }
+    }
+    for path in &t.received_files {
+        println!("  │  received     : {}", path);
     }
     println!("  └─");
 }
@@ -141,4 +343,13 @@ fn print_result(result: &serde_json::Value) {
             println!("  │  output files : {}", names.join(", "));
         }
     }
+    if let Some(files) = result
+        .get("output_files_skipped")
+        .and_then(|v| v.as_array())
+    {
+        let names: Vec<&str> = files.iter().filter_map(|v| v.as_str()).collect();
+        if !names.is_empty() {
+            println!("  │  not sent     : {} (over size cap)", names.join(", "));
+        }
+    }
 }
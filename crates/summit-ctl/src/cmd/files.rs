@@ -3,39 +3,103 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
 
-use super::http::{base_url, get_json};
+use super::http::{base_url, decode, get_json, send, transfer_client};
 
 #[derive(Deserialize)]
 struct SendResponse {
     filename: String,
     bytes: u64,
     chunks_sent: usize,
+    #[serde(default)]
+    chunks_skipped: usize,
+    #[serde(default)]
+    parity_chunks: usize,
+    #[serde(default)]
+    queued: bool,
+}
+
+#[derive(Deserialize)]
+struct SendPlanResponse {
+    filename: String,
+    bytes: u64,
+    chunks: usize,
+    #[serde(default)]
+    parity_chunks: usize,
+    chunk_size: usize,
+    targets: Vec<PlanTarget>,
+}
+
+#[derive(Deserialize)]
+struct PlanTarget {
+    session_id: String,
+    peer_pubkey: String,
 }
 
 #[derive(Deserialize)]
 struct FilesResponse {
     received: Vec<String>,
     in_progress: Vec<String>,
+    #[serde(default)]
+    max_concurrent_assemblies: usize,
 }
 
+#[derive(Deserialize)]
+struct TransfersResponse {
+    transfers: Vec<TransferInfo>,
+}
+
+#[derive(Deserialize)]
+struct TransferInfo {
+    filename: String,
+    bytes: u64,
+    complete: bool,
+    duration_ms: Option<u64>,
+    mb_per_sec: Option<f64>,
+}
+
+/// Where `send` reads the file's bytes from.
+pub enum FileSource<'a> {
+    Path(&'a str),
+    /// Streamed into the upload as it is read; requires a `--name`.
+    Stdin,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_send(
     port: u16,
-    path: &str,
+    json: bool,
+    source: FileSource<'_>,
+    name: Option<&str>,
     target_peer: Option<&str>,
     target_session: Option<&str>,
+    fec: Option<&str>,
+    dry_run: bool,
 ) -> Result<()> {
     use reqwest::multipart;
 
-    let file_data =
-        std::fs::read(path).with_context(|| format!("failed to read file: {}", path))?;
+    let part = match source {
+        FileSource::Path(path) => {
+            let file_data =
+                std::fs::read(path).with_context(|| format!("failed to read file: {}", path))?;
 
-    let filename = std::path::Path::new(path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("file")
-        .to_string();
+            let filename = name
+                .or_else(|| {
+                    std::path::Path::new(path)
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                })
+                .unwrap_or("file")
+                .to_string();
 
-    let part = multipart::Part::bytes(file_data).file_name(filename.clone());
+            multipart::Part::bytes(file_data).file_name(filename)
+        }
+        FileSource::Stdin => {
+            let filename = name.context("--name is required when sending from stdin")?;
+            let stdin = tokio_util::io::ReaderStream::new(tokio::io::stdin());
+            multipart::Part::stream(reqwest::Body::wrap_stream(stdin))
+                .file_name(filename.to_string())
+        }
+    };
 
     let target_json = if let Some(peer) = target_peer {
         serde_json::json!({
@@ -56,20 +120,12 @@ pub async fn cmd_send(
     let target_part =
         multipart::Part::text(target_json.to_string()).mime_str("application/json")?;
 
-    let form = multipart::Form::new()
+    let mut form = multipart::Form::new()
         .part("file", part)
         .part("target", target_part);
-
-    let client = reqwest::Client::new();
-    let resp: SendResponse = client
-        .post(format!("{}/send", base_url(port)))
-        .multipart(form)
-        .send()
-        .await
-        .context("failed to send file to daemon")?
-        .json()
-        .await
-        .context("failed to parse send response")?;
+    if let Some(scheme) = fec {
+        form = form.text("fec", scheme.to_string());
+    }
 
     let target_desc = if target_peer.is_some() {
         "to peer"
@@ -79,40 +135,178 @@ pub async fn cmd_send(
         "to all trusted peers (broadcast)"
     };
 
+    if dry_run {
+        let url = format!("{}/send/plan", base_url(port));
+        let body = send(transfer_client()?.post(&url).multipart(form), &url)
+            .await
+            .context("failed to plan send with daemon")?
+            .json()
+            .await
+            .context("failed to parse send plan")?;
+        let Some(plan) = decode::<SendPlanResponse>(body, json)? else {
+            return Ok(());
+        };
+
+        println!("Dry run — nothing sent. Would send {}:", target_desc);
+        println!("  Filename : {}", plan.filename);
+        println!("  Bytes    : {}", plan.bytes);
+        println!(
+            "  Chunks   : {} ({} bytes each, plus metadata)",
+            plan.chunks, plan.chunk_size
+        );
+        if plan.parity_chunks > 0 {
+            println!("  Parity   : {} of those (FEC)", plan.parity_chunks);
+        }
+        if plan.targets.is_empty() {
+            println!("  Targets  : none — no matching session");
+        } else {
+            println!("  Targets  : {}", plan.targets.len());
+            for t in &plan.targets {
+                println!(
+                    "    peer {}  session {}",
+                    &t.peer_pubkey[..16.min(t.peer_pubkey.len())],
+                    &t.session_id[..16.min(t.session_id.len())]
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    let url = format!("{}/send", base_url(port));
+    let body = send(transfer_client()?.post(&url).multipart(form), &url)
+        .await
+        .context("failed to send file to daemon")?
+        .json()
+        .await
+        .context("failed to parse send response")?;
+    let Some(resp) = decode::<SendResponse>(body, json)? else {
+        return Ok(());
+    };
+
     println!("File queued for sending {}:", target_desc);
     println!("  Filename : {}", resp.filename);
     println!("  Bytes    : {}", resp.bytes);
     println!("  Chunks   : {}", resp.chunks_sent);
+    if resp.parity_chunks > 0 {
+        println!("  Parity   : {} of those (FEC)", resp.parity_chunks);
+    }
+    if resp.chunks_skipped > 0 {
+        println!(
+            "  Skipped  : {} (already held by receiver)",
+            resp.chunks_skipped
+        );
+    }
+    if resp.queued {
+        println!("  Still draining from the send queue — see 'summit-ctl transfers'");
+    }
 
     Ok(())
 }
 
-pub async fn cmd_files(port: u16) -> Result<()> {
-    let resp: FilesResponse = get_json(&format!("{}/files", base_url(port))).await?;
+pub async fn cmd_files(port: u16, json: bool) -> Result<()> {
+    let Some(resp) =
+        decode::<FilesResponse>(get_json(&format!("{}/files", base_url(port))).await?, json)?
+    else {
+        return Ok(());
+    };
 
     if resp.received.is_empty() && resp.in_progress.is_empty() {
         println!("No files received yet.");
-        return Ok(());
-    }
+    } else {
+        println!("═══════════════════════════════════════");
+        println!("  Received Files");
+        println!("═══════════════════════════════════════");
 
-    println!("═══════════════════════════════════════");
-    println!("  Received Files");
-    println!("═══════════════════════════════════════");
+        if resp.received.is_empty() {
+            println!("  (none)");
+        } else {
+            for file in &resp.received {
+                println!("  ✓ {}", file);
+            }
+        }
 
-    if resp.received.is_empty() {
-        println!("  (none)");
-    } else {
-        for file in &resp.received {
-            println!("  ✓ {}", file);
+        if !resp.in_progress.is_empty() {
+            match resp.max_concurrent_assemblies {
+                0 => println!("\n  In Progress:"),
+                max => println!("\n  In Progress ({}/{}):", resp.in_progress.len(), max),
+            }
+            for file in &resp.in_progress {
+                println!("  ⋯ {}", file);
+            }
         }
     }
 
-    if !resp.in_progress.is_empty() {
-        println!("\n  In Progress:");
-        for file in &resp.in_progress {
-            println!("  ⋯ {}", file);
+    let transfers: TransfersResponse = get_json(&format!("{}/transfers", base_url(port))).await?;
+    if !transfers.transfers.is_empty() {
+        println!();
+        print_transfers(&transfers.transfers);
+    }
+
+    Ok(())
+}
+
+/// Download a received file. The daemon decrypts it if it is stored
+/// encrypted; with no `output` the bytes go to stdout.
+pub async fn cmd_file_get(port: u16, name: &str, output: Option<&str>) -> Result<()> {
+    let url = format!("{}/files/{}", base_url(port), name);
+    let data = send(transfer_client()?.get(&url), &url)
+        .await?
+        .bytes()
+        .await
+        .context("failed to read file from summitd")?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, &data).with_context(|| format!("failed to write {}", path))?;
+            eprintln!("Wrote {} ({} bytes)", path, data.len());
+        }
+        None => {
+            use std::io::Write;
+            std::io::stdout()
+                .write_all(&data)
+                .context("failed to write to stdout")?;
         }
     }
+    Ok(())
+}
+
+pub async fn cmd_transfers(port: u16, json: bool) -> Result<()> {
+    let Some(resp) = decode::<TransfersResponse>(
+        get_json(&format!("{}/transfers", base_url(port))).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
 
+    if resp.transfers.is_empty() {
+        println!("No files sent yet.");
+        return Ok(());
+    }
+    print_transfers(&resp.transfers);
     Ok(())
 }
+
+fn print_transfers(transfers: &[TransferInfo]) {
+    println!("═══════════════════════════════════════");
+    println!("  Sent Files ({})", transfers.len());
+    println!("═══════════════════════════════════════");
+
+    for t in transfers {
+        match (t.complete, t.duration_ms) {
+            (true, Some(ms)) => {
+                let rate = t
+                    .mb_per_sec
+                    .map_or_else(String::new, |r| format!(", {:.2} MB/s", r));
+                println!(
+                    "  ✓ {} — {} bytes in {:.1}s{}",
+                    t.filename,
+                    t.bytes,
+                    ms as f64 / 1000.0,
+                    rate
+                );
+            }
+            _ => println!("  ⋯ {} — {} bytes, draining", t.filename, t.bytes),
+        }
+    }
+}
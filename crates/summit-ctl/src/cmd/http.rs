@@ -1,27 +1,99 @@
 //! Shared HTTP request helpers for CLI commands.
 
+use std::time::Duration;
+
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 pub fn base_url(port: u16) -> String {
     format!("http://127.0.0.1:{}/api", port)
 }
 
-pub async fn get_json<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T> {
-    reqwest::get(url)
+/// How long connecting to the daemon may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a request may take, start to finish, before giving up on a
+/// daemon that accepted the connection but stopped answering.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// HTTP client for the daemon API. Sends `SUMMIT_API_TOKEN`, if set, as a
+/// bearer token on every request, and gives up after `REQUEST_TIMEOUT`.
+pub fn client() -> Result<reqwest::Client> {
+    builder()?
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("failed to build HTTP client")
+}
+
+/// Like [`client`], without the overall timeout, for requests that carry a
+/// file: the daemon takes an upload only as fast as it can queue its chunks.
+pub fn transfer_client() -> Result<reqwest::Client> {
+    builder()?.build().context("failed to build HTTP client")
+}
+
+fn builder() -> Result<reqwest::ClientBuilder> {
+    let mut headers = HeaderMap::new();
+    if let Ok(token) = std::env::var("SUMMIT_API_TOKEN")
+        && !token.is_empty()
+    {
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+            .context("SUMMIT_API_TOKEN contains invalid characters")?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+    Ok(reqwest::Client::builder()
+        .default_headers(headers)
+        .connect_timeout(CONNECT_TIMEOUT))
+}
+
+/// Error body the daemon sends with every failed request.
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    code: String,
+    message: String,
+}
+
+/// Send a request, turning a 401 into a hint about the API token and any
+/// other failure status into an error carrying the daemon's message.
+pub async fn send(request: reqwest::RequestBuilder, url: &str) -> Result<reqwest::Response> {
+    let resp = request
+        .send()
         .await
-        .with_context(|| format!("failed to connect to summitd at {} — is it running?", url))?
+        .with_context(|| format!("failed to connect to summitd at {} — is it running?", url))?;
+    let status = resp.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        anyhow::bail!(
+            "summitd rejected the request — set SUMMIT_API_TOKEN to the daemon's api.token"
+        );
+    }
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        match serde_json::from_str::<ErrorBody>(&text) {
+            Ok(body) => anyhow::bail!("{} ({})", body.error.message, body.error.code),
+            // Errors raised before a handler runs, e.g. a malformed body.
+            Err(_) => anyhow::bail!("summitd returned {}: {}", status, text.trim()),
+        }
+    }
+    Ok(resp)
+}
+
+pub async fn get_json<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T> {
+    send(client()?.get(url), url)
+        .await?
         .json::<T>()
         .await
         .context("failed to parse response")
 }
 
 pub async fn post_json<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T> {
-    reqwest::Client::new()
-        .post(url)
-        .send()
-        .await
-        .with_context(|| format!("failed to connect to summitd at {} — is it running?", url))?
+    send(client()?.post(url), url)
+        .await?
         .json::<T>()
         .await
         .context("failed to parse response")
@@ -32,13 +104,21 @@ where
     T: Serialize,
     R: for<'de> Deserialize<'de>,
 {
-    reqwest::Client::new()
-        .post(url)
-        .json(body)
-        .send()
-        .await
-        .with_context(|| format!("failed to connect to summitd at {} — is it running?", url))?
+    send(client()?.post(url).json(body), url)
+        .await?
         .json::<R>()
         .await
         .context("failed to parse response")
 }
+
+/// Under `--json`, print the response verbatim and return `None`; otherwise
+/// decode it for the human-readable view.
+pub fn decode<T: DeserializeOwned>(body: serde_json::Value, json: bool) -> Result<Option<T>> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(&body)?);
+        return Ok(None);
+    }
+    serde_json::from_value(body)
+        .map(Some)
+        .context("failed to parse response")
+}
@@ -0,0 +1,39 @@
+//! Public key and session id arguments, checked before they reach the daemon.
+
+use anyhow::{Result, bail};
+
+/// Normalize a 32-byte hex argument to the lowercase form the daemon prints:
+/// surrounding whitespace and an optional `0x` prefix are dropped. `what`
+/// names the argument in errors, e.g. "public key".
+pub fn hex32(input: &str, what: &str) -> Result<String> {
+    let trimmed = input.trim();
+    let digits = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+    if digits.len() != 64 {
+        bail!(
+            "{} should be 64 hex characters, got {}: {}",
+            what,
+            digits.len(),
+            input
+        );
+    }
+    if let Some(bad) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        bail!(
+            "{} contains '{}', which is not a hex digit: {}",
+            what,
+            bad,
+            input
+        );
+    }
+    Ok(digits.to_ascii_lowercase())
+}
+
+pub fn pubkey(input: &str) -> Result<String> {
+    hex32(input, "public key")
+}
+
+pub fn session_id(input: &str) -> Result<String> {
+    hex32(input, "session id")
+}
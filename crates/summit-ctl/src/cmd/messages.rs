@@ -3,14 +3,49 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-use super::http::{base_url, get_json, post_json_body};
+use super::http::{base_url, decode, get_json, post_json, post_json_body};
+use super::keys;
 
 #[derive(Deserialize)]
 struct MessagesResponse {
     peer_pubkey: String,
+    #[serde(default)]
+    unread: usize,
     messages: Vec<MessageJson>,
 }
 
+#[derive(Deserialize)]
+struct MarkReadResponse {
+    cleared: usize,
+}
+
+#[derive(Deserialize)]
+struct InboxResponse {
+    total: usize,
+    offset: usize,
+    messages: Vec<InboxMessage>,
+}
+
+#[derive(Deserialize)]
+struct MessageSummary {
+    unread_total: usize,
+    peers: Vec<PeerMessageSummary>,
+}
+
+#[derive(Deserialize)]
+struct PeerMessageSummary {
+    peer_pubkey: String,
+    messages: usize,
+    unread: usize,
+}
+
+#[derive(Deserialize)]
+struct InboxMessage {
+    peers: Vec<String>,
+    #[serde(flatten)]
+    message: MessageJson,
+}
+
 #[derive(Deserialize)]
 #[allow(dead_code)]
 struct MessageJson {
@@ -19,12 +54,13 @@ struct MessageJson {
     to: String,
     msg_type: String,
     timestamp: u64,
+    group_id: Option<String>,
     content: serde_json::Value,
 }
 
 #[derive(Serialize)]
 struct SendMessageRequest {
-    to: String,
+    to: Vec<String>,
     text: String,
 }
 
@@ -32,11 +68,17 @@ struct SendMessageRequest {
 struct SendMessageResponse {
     msg_id: String,
     timestamp: u64,
+    group_id: Option<String>,
 }
 
-pub async fn cmd_messages(port: u16, peer_pubkey: &str) -> Result<()> {
-    let resp: MessagesResponse =
-        get_json(&format!("{}/messages/{}", base_url(port), peer_pubkey)).await?;
+pub async fn cmd_messages(port: u16, json: bool, peer_pubkey: &str) -> Result<()> {
+    let Some(resp) = decode::<MessagesResponse>(
+        get_json(&format!("{}/messages/{}", base_url(port), peer_pubkey)).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
 
     if resp.messages.is_empty() {
         println!(
@@ -48,33 +90,128 @@ pub async fn cmd_messages(port: u16, peer_pubkey: &str) -> Result<()> {
 
     println!("═══════════════════════════════════════");
     println!(
-        "  Messages from {}...",
-        &resp.peer_pubkey[..16.min(resp.peer_pubkey.len())]
+        "  Messages from {}... ({} new)",
+        &resp.peer_pubkey[..16.min(resp.peer_pubkey.len())],
+        resp.unread
     );
     println!("═══════════════════════════════════════");
 
     for m in &resp.messages {
-        println!("  ┌─ {} [{}]", m.msg_type, m.timestamp);
-        println!("  │  from : {}...", &m.from[..16.min(m.from.len())]);
-        println!("  │  id   : {}...", &m.msg_id[..16.min(m.msg_id.len())]);
-        if let Some(text) = m.content.get("text").and_then(|v| v.as_str()) {
-            println!("  └─ {}", text);
-        } else {
-            println!("  └─ {:?}", m.content);
+        print_message(m, &[]);
+    }
+
+    Ok(())
+}
+
+pub async fn cmd_inbox(port: u16, json: bool, limit: Option<usize>, offset: usize) -> Result<()> {
+    let mut url = format!("{}/messages?offset={}", base_url(port), offset);
+    if let Some(limit) = limit {
+        url.push_str(&format!("&limit={limit}"));
+    }
+    let Some(resp) = decode::<InboxResponse>(get_json(&url).await?, json)? else {
+        return Ok(());
+    };
+    let summary: MessageSummary = get_json(&format!("{}/messages/summary", base_url(port))).await?;
+
+    if resp.messages.is_empty() {
+        println!(
+            "No messages at offset {} ({} total).",
+            resp.offset, resp.total
+        );
+        return Ok(());
+    }
+
+    println!("═══════════════════════════════════════");
+    println!(
+        "  Inbox — {}-{} of {}, newest first",
+        resp.offset + 1,
+        resp.offset + resp.messages.len(),
+        resp.total
+    );
+    println!("═══════════════════════════════════════");
+    let unread: Vec<_> = summary.peers.iter().filter(|p| p.unread > 0).collect();
+    if unread.is_empty() {
+        println!("  No unread messages.");
+    } else {
+        println!("  Unread ({} total):", summary.unread_total);
+        for p in unread {
+            println!(
+                "    {}...  {} unread of {}",
+                &p.peer_pubkey[..16.min(p.peer_pubkey.len())],
+                p.unread,
+                p.messages
+            );
         }
     }
+    println!();
+
+    for m in &resp.messages {
+        print_message(&m.message, &m.peers);
+    }
 
     Ok(())
 }
 
-pub async fn cmd_messages_send(port: u16, to: &str, text: &str) -> Result<()> {
+/// `peers` is the conversation the message belongs to, shown in the inbox.
+fn print_message(m: &MessageJson, peers: &[String]) {
+    println!("  ┌─ {} [{}]", m.msg_type, m.timestamp);
+    if !peers.is_empty() {
+        let short: Vec<&str> = peers.iter().map(|p| &p[..16.min(p.len())]).collect();
+        println!("  │  with : {}...", short.join("..., "));
+    }
+    println!("  │  from : {}...", &m.from[..16.min(m.from.len())]);
+    println!("  │  id   : {}...", &m.msg_id[..16.min(m.msg_id.len())]);
+    if let Some(group_id) = &m.group_id {
+        println!("  │  group: {}", group_id);
+    }
+    let field = |name: &str| m.content.get(name).and_then(|v| v.as_str()).unwrap_or("");
+    match m.content.get("content_type").and_then(|v| v.as_str()) {
+        Some("blob") => println!(
+            "  └─ [{} blob, {} bytes]",
+            field("mime_type"),
+            field("data").len() / 2
+        ),
+        Some("file_ref") => println!(
+            "  └─ [file {} — {}...]",
+            field("filename"),
+            &field("file_hash")[..16.min(field("file_hash").len())]
+        ),
+        _ if m.content.get("text").is_some() => println!("  └─ {}", field("text")),
+        _ => println!("  └─ {:?}", m.content),
+    }
+}
+
+pub async fn cmd_messages_read(port: u16, json: bool, peer_pubkey: &str) -> Result<()> {
+    let Some(resp) = decode::<MarkReadResponse>(
+        post_json(&format!("{}/messages/{}/read", base_url(port), peer_pubkey)).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+    println!("Marked {} messages read.", resp.cleared);
+    Ok(())
+}
+
+/// `to` is one public key, or several comma-separated for a group message.
+pub async fn cmd_messages_send(port: u16, json: bool, to: &str, text: &str) -> Result<()> {
     let req = SendMessageRequest {
-        to: to.to_string(),
+        to: to
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(keys::pubkey)
+            .collect::<Result<_>>()?,
         text: text.to_string(),
     };
 
-    let resp: SendMessageResponse =
-        post_json_body(&format!("{}/messages/send", base_url(port)), &req).await?;
+    let Some(resp) = decode::<SendMessageResponse>(
+        post_json_body(&format!("{}/messages/send", base_url(port)), &req).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
 
     println!("Message sent:");
     println!(
@@ -82,6 +219,9 @@ pub async fn cmd_messages_send(port: u16, to: &str, text: &str) -> Result<()> {
         &resp.msg_id[..16.min(resp.msg_id.len())]
     );
     println!("  Timestamp : {}", resp.timestamp);
+    if let Some(group_id) = resp.group_id {
+        println!("  Group     : {}", group_id);
+    }
 
     Ok(())
 }
@@ -3,6 +3,7 @@
 pub mod compute;
 pub mod files;
 pub mod http;
+pub mod keys;
 pub mod messages;
 pub mod sessions;
 pub mod status;
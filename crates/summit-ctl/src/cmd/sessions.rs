@@ -3,26 +3,76 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
 
-use super::http::{base_url, get_json};
+use super::http::{base_url, client, decode, get_json, post_json_body, send};
 
-pub async fn cmd_session_drop(port: u16, session_id: &str) -> Result<()> {
+pub async fn cmd_session_connect(port: u16, json: bool, pubkey: &str) -> Result<()> {
+    #[derive(Deserialize)]
+    struct ConnectResponse {
+        public_key: String,
+        status: String,
+    }
+
+    let Some(resp) = decode::<ConnectResponse>(
+        post_json_body(
+            &format!("{}/sessions/connect", base_url(port)),
+            &serde_json::json!({ "public_key": pubkey }),
+        )
+        .await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+
+    let peer = &resp.public_key[..16];
+    match resp.status.as_str() {
+        "initiated" => {
+            println!("✓ Handshake started with {}...", peer);
+            println!("  Check 'summit-ctl sessions --peer {}'", resp.public_key);
+        }
+        "connected" => println!("Already connected to {}...", peer),
+        _ => println!("Handshake with {}... already in progress", peer),
+    }
+
+    Ok(())
+}
+
+pub async fn cmd_session_drop(
+    port: u16,
+    json: bool,
+    session_id: &str,
+    cooldown_secs: Option<u64>,
+) -> Result<()> {
     #[derive(Deserialize)]
     struct DropResponse {
         session_id: String,
         dropped: bool,
+        #[serde(default)]
+        cooldown_secs: u64,
     }
 
-    let resp: DropResponse = reqwest::Client::new()
-        .delete(format!("{}/sessions/{}", base_url(port), session_id))
-        .send()
+    let mut url = format!("{}/sessions/{}", base_url(port), session_id);
+    if let Some(secs) = cooldown_secs {
+        url.push_str(&format!("?cooldown_secs={secs}"));
+    }
+    let body = send(client()?.delete(&url), &url)
         .await
         .context("failed to drop session")?
         .json()
         .await
         .context("failed to parse response")?;
+    let Some(resp) = decode::<DropResponse>(body, json)? else {
+        return Ok(());
+    };
 
     if resp.dropped {
         println!("✓ Session dropped: {}...", &resp.session_id[..16]);
+        if resp.cooldown_secs > 0 {
+            println!(
+                "  No new session with this peer for {}s (sessions connect overrides)",
+                resp.cooldown_secs
+            );
+        }
     } else {
         println!("Session not found: {}", session_id);
     }
@@ -30,24 +80,190 @@ pub async fn cmd_session_drop(port: u16, session_id: &str) -> Result<()> {
     Ok(())
 }
 
-pub async fn cmd_session_inspect(port: u16, session_id: &str) -> Result<()> {
+/// Session as rendered by `/sessions` and `/sessions/{id}`.
+#[derive(Deserialize)]
+struct SessionDetails {
+    session_id: String,
+    peer_addr: String,
+    peer_pubkey: String,
+    contract: String,
+    chunk_port: u16,
+    uptime_secs: u64,
+    trust_level: String,
+    #[serde(default)]
+    wire_version: u32,
+    #[serde(default)]
+    services: Vec<SessionService>,
+    #[serde(default)]
+    decrypt_drops: DecryptDrops,
+    #[serde(default)]
+    receive_queue_drops: u64,
+    #[serde(default)]
+    buckets: Vec<Bucket>,
+}
+
+#[derive(Deserialize)]
+struct Bucket {
+    contract: String,
+    tokens: f64,
+    capacity: f64,
+    rate: f64,
+    denied: u64,
+}
+
+#[derive(Deserialize, Default)]
+struct DecryptDrops {
+    replayed: u64,
+    too_old: u64,
+    too_short: u64,
+    auth_failed: u64,
+}
+
+#[derive(Deserialize)]
+struct SessionList {
+    sessions: Vec<SessionDetails>,
+}
+
+#[derive(Deserialize)]
+struct SessionService {
+    name: String,
+    contract: String,
+    chunk_port: u16,
+}
+
+pub async fn cmd_session_inspect(port: u16, json: bool, session_id: &str) -> Result<()> {
+    let Some(resp) = decode::<SessionDetails>(
+        get_json(&format!("{}/sessions/{}", base_url(port), session_id)).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+
+    println!("═══════════════════════════════════════");
+    println!("  Session Details");
+    println!("═══════════════════════════════════════");
+    print_session(&resp);
+
+    Ok(())
+}
+
+pub async fn cmd_sessions_list(port: u16, json: bool) -> Result<()> {
+    let Some(resp) = decode::<SessionList>(
+        get_json(&format!("{}/sessions", base_url(port))).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+
+    if resp.sessions.is_empty() {
+        println!("No active sessions.");
+        return Ok(());
+    }
+
+    println!("═══════════════════════════════════════");
+    println!("  Active Sessions ({})", resp.sessions.len());
+    println!("═══════════════════════════════════════");
+    println!(
+        "    {:<16}  {:<16}  {:<10}  {:<9}  UPTIME",
+        "SESSION", "PEER", "CONTRACT", "TRUST"
+    );
+    for s in &resp.sessions {
+        let trust_icon = match s.trust_level.as_str() {
+            "Trusted" => "✓",
+            "ReceiveOnly" => "↓",
+            "Blocked" => "✗",
+            _ => "?",
+        };
+        println!(
+            "  {} {:<16}  {:<16}  {:<10}  {:<9}  {}s",
+            trust_icon,
+            &s.session_id[..16],
+            &s.peer_pubkey[..16],
+            s.contract,
+            s.trust_level,
+            s.uptime_secs
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn cmd_sessions_with_peer(port: u16, json: bool, peer: &str) -> Result<()> {
+    let Some(resp) = decode::<SessionList>(
+        get_json(&format!("{}/sessions?peer={}", base_url(port), peer)).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+
+    if resp.sessions.is_empty() {
+        println!("No sessions with peer {}", peer);
+        return Ok(());
+    }
+
+    println!("═══════════════════════════════════════");
+    println!("  Sessions with Peer ({})", resp.sessions.len());
+    println!("═══════════════════════════════════════");
+    for (i, session) in resp.sessions.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print_session(session);
+    }
+
+    Ok(())
+}
+
+pub async fn cmd_handshakes(port: u16, json: bool) -> Result<()> {
     #[derive(Deserialize)]
-    struct InspectResponse {
-        session_id: String,
+    struct HandshakesResponse {
+        handshakes: Vec<Handshake>,
+        timeout_secs: u64,
+    }
+
+    #[derive(Deserialize)]
+    struct Handshake {
         peer_addr: String,
         peer_pubkey: String,
-        contract: String,
-        chunk_port: u16,
-        uptime_secs: u64,
-        trust_level: String,
+        service: String,
+        phase: String,
+        age_ms: u64,
     }
 
-    let resp: InspectResponse =
-        get_json(&format!("{}/sessions/{}", base_url(port), session_id)).await?;
+    let Some(resp) = decode::<HandshakesResponse>(
+        get_json(&format!("{}/handshakes", base_url(port))).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+
+    if resp.handshakes.is_empty() {
+        println!("No handshakes in flight.");
+        return Ok(());
+    }
 
     println!("═══════════════════════════════════════");
-    println!("  Session Details");
+    println!("  Handshakes in Flight ({})", resp.handshakes.len());
     println!("═══════════════════════════════════════");
+    for h in &resp.handshakes {
+        println!("  ┌─ {} {}", h.peer_addr, h.phase);
+        println!("  │  pubkey  : {}", h.peer_pubkey);
+        println!("  │  service : {}", h.service);
+        println!(
+            "  └─ age     : {:.1}s (discarded after {}s)",
+            h.age_ms as f64 / 1000.0,
+            resp.timeout_secs
+        );
+    }
+
+    Ok(())
+}
+
+fn print_session(resp: &SessionDetails) {
     println!("  ID       : {}", resp.session_id);
     println!("  Peer     : {}", resp.peer_addr);
     println!("  Pubkey   : {}", resp.peer_pubkey);
@@ -55,6 +271,42 @@ pub async fn cmd_session_inspect(port: u16, session_id: &str) -> Result<()> {
     println!("  Port     : {}", resp.chunk_port);
     println!("  Uptime   : {}s", resp.uptime_secs);
     println!("  Trust    : {}", resp.trust_level);
-
-    Ok(())
+    println!("  Wire     : v{}", resp.wire_version);
+    if resp.services.is_empty() {
+        println!("  Services : (none negotiated)");
+    } else {
+        println!("  Services :");
+        for svc in &resp.services {
+            if svc.chunk_port != 0 {
+                println!(
+                    "    • {:<14} {} (port {})",
+                    svc.name, svc.contract, svc.chunk_port
+                );
+            } else {
+                println!("    • {:<14} {}", svc.name, svc.contract);
+            }
+        }
+    }
+    let d = &resp.decrypt_drops;
+    if d.replayed + d.too_old + d.too_short + d.auth_failed > 0 {
+        println!(
+            "  Dropped  : {} replayed, {} too old, {} too short, {} failed auth",
+            d.replayed, d.too_old, d.too_short, d.auth_failed
+        );
+    }
+    if resp.receive_queue_drops > 0 {
+        println!(
+            "  Overflow : {} chunks dropped, handler queue full",
+            resp.receive_queue_drops
+        );
+    }
+    if !resp.buckets.is_empty() {
+        println!("  Buckets  :");
+        for b in &resp.buckets {
+            println!(
+                "    • {:<10} {:.1} / {:.0} tokens, {:.0}/s, {} denied",
+                b.contract, b.tokens, b.capacity, b.rate, b.denied
+            );
+        }
+    }
 }
@@ -1,15 +1,17 @@
-//! Daemon status, peers, cache, services, schema, shutdown commands.
+//! Daemon identity, status, peers, cache, delivery, services, schema, shutdown commands.
 
 use anyhow::Result;
 use serde::Deserialize;
 
-use super::http::{base_url, get_json, post_json};
+use super::http::{base_url, decode, get_json, post_json, post_json_body};
 
 // ── Response types ────────────────────────────────────────────────────────────
 
 #[derive(Deserialize)]
 struct StatusResponse {
     sessions: Vec<SessionInfo>,
+    #[serde(default)]
+    max_sessions: usize,
     cache: CacheInfo,
     peers_discovered: usize,
 }
@@ -38,18 +40,55 @@ struct PeerInfo {
     addr: String,
     session_port: u16,
     services: Vec<String>,
+    #[serde(default)]
+    service_details: Vec<PeerService>,
     service_count: usize,
     is_complete: bool,
     version: u32,
     last_seen_secs: u64,
     trust_level: String,
     buffered_chunks: usize,
+    #[serde(default)]
+    sent_bytes_per_sec: u64,
+    #[serde(default)]
+    received_bytes_per_sec: u64,
+}
+
+#[derive(Deserialize)]
+struct PeerService {
+    name: String,
 }
 
 #[derive(Deserialize)]
 struct CacheInfo {
     chunks: usize,
     bytes: u64,
+    #[serde(default)]
+    max_bytes: u64,
+    #[serde(default)]
+    utilization_pct: Option<f64>,
+    #[serde(default)]
+    hits: u64,
+    #[serde(default)]
+    misses: u64,
+    #[serde(default)]
+    hit_ratio_pct: Option<f64>,
+    #[serde(default)]
+    root: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CacheListResponse {
+    total: usize,
+    offset: usize,
+    chunks: Vec<CachedChunkInfo>,
+}
+
+#[derive(Deserialize)]
+struct CachedChunkInfo {
+    content_hash: String,
+    bytes: u64,
+    last_access_ms: u64,
 }
 
 #[derive(Deserialize)]
@@ -57,6 +96,26 @@ struct ClearResponse {
     cleared: usize,
 }
 
+#[derive(Deserialize)]
+struct DeliveryResponse {
+    tracked_chunks: usize,
+    multipath_chunks: usize,
+    duplicate_deliveries: usize,
+    chunks: Vec<DeliveryInfo>,
+}
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct DeliveryInfo {
+    content_hash: String,
+    paths: Vec<String>,
+    deliveries: usize,
+    duplicates: usize,
+    multipath: bool,
+    first_seen_secs: u64,
+    last_seen_secs: u64,
+}
+
 #[derive(Deserialize)]
 struct ServicesResponse {
     services: Vec<ServiceStatus>,
@@ -67,20 +126,76 @@ struct ServiceStatus {
     name: String,
     enabled: bool,
     contract: String,
+    #[serde(default)]
+    active_sessions: usize,
+}
+
+#[derive(Deserialize)]
+struct IdentityResponse {
+    public_key: String,
+    wire_version: u32,
+    interface: String,
+    services: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct InfoResponse {
+    version: String,
+    git_hash: Option<String>,
+    uptime_secs: u64,
 }
 
 // ── Commands ──────────────────────────────────────────────────────────────────
 
-pub async fn cmd_status(port: u16) -> Result<()> {
-    let resp: StatusResponse = get_json(&format!("{}/status", base_url(port))).await?;
+pub async fn cmd_whoami(port: u16, json: bool) -> Result<()> {
+    let Some(resp) = decode::<IdentityResponse>(
+        get_json(&format!("{}/identity", base_url(port))).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+
+    println!("═══════════════════════════════════════");
+    println!("  This Node");
+    println!("═══════════════════════════════════════");
+    println!("  Public key : {}", resp.public_key);
+    println!("  Interface  : {}", resp.interface);
+    println!("  Wire       : v{}", resp.wire_version);
+    if resp.services.is_empty() {
+        println!("  Services   : (none enabled)");
+    } else {
+        println!("  Services   : {}", resp.services.join(", "));
+    }
+    println!(
+        "\nOn the other node: summit-ctl trust add {}",
+        resp.public_key
+    );
+
+    Ok(())
+}
+
+pub async fn cmd_status(port: u16, json: bool) -> Result<()> {
+    let Some(resp) =
+        decode::<StatusResponse>(get_json(&format!("{}/status", base_url(port))).await?, json)?
+    else {
+        return Ok(());
+    };
 
     println!("═══════════════════════════════════════");
     println!("  Summit Daemon Status");
     println!("═══════════════════════════════════════");
     println!("  Peers discovered : {}", resp.peers_discovered);
-    println!("  Active sessions  : {}", resp.sessions.len());
+    match resp.max_sessions {
+        0 => println!("  Active sessions  : {}", resp.sessions.len()),
+        max => println!("  Active sessions  : {} / {}", resp.sessions.len(), max),
+    }
     println!("  Cache chunks     : {}", resp.cache.chunks);
     println!("  Cache size       : {} bytes", resp.cache.bytes);
+    println!(
+        "  Cache root       : {}",
+        resp.cache.root.as_deref().unwrap_or("(in memory)")
+    );
 
     if resp.sessions.is_empty() {
         println!("\n  No active sessions.");
@@ -89,26 +204,62 @@ pub async fn cmd_status(port: u16) -> Result<()> {
         for s in &resp.sessions {
             let trust_icon = match s.trust_level.as_str() {
                 "Trusted" => "✓",
+                "ReceiveOnly" => "↓",
                 "Blocked" => "✗",
                 _ => "?",
             };
             println!("  ┌─ {} {}", trust_icon, &s.session_id[..16]);
             println!("  │  peer     : {}", s.peer);
-            println!("  │  pubkey   : {}", &s.peer_pubkey);
+            println!("  │  pubkey   : {}", s.peer_pubkey);
             println!("  │  contract : {}", s.contract);
             println!("  │  trust    : {}", s.trust_level);
             println!("  └─ uptime   : {}s", s.established_secs);
         }
     }
 
+    // Daemons older than /info just go without the footer.
+    if let Ok(info) = get_json::<InfoResponse>(&format!("{}/info", base_url(port))).await {
+        let build = match &info.git_hash {
+            Some(hash) => format!("{} ({})", info.version, hash),
+            None => info.version,
+        };
+        println!(
+            "\n  summitd {} · up {}",
+            build,
+            format_uptime(info.uptime_secs)
+        );
+    }
+
     Ok(())
 }
 
-pub async fn cmd_peers(port: u16) -> Result<()> {
-    let resp: PeersResponse = get_json(&format!("{}/peers", base_url(port))).await?;
+/// `3d 4h`, `2h 5m`, `7m 12s`, `40s`.
+fn format_uptime(secs: u64) -> String {
+    let (days, hours, mins) = (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60);
+    match (days, hours, mins) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, _) => format!("{}m {}s", mins, secs % 60),
+        (0, _, _) => format!("{}h {}m", hours, mins),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+/// `service` limits the list to peers offering it ("compute",
+/// "summit.compute" or a hex service hash).
+pub async fn cmd_peers(port: u16, json: bool, service: Option<&str>) -> Result<()> {
+    let url = match service {
+        Some(service) => format!("{}/peers?service={}", base_url(port), service),
+        None => format!("{}/peers", base_url(port)),
+    };
+    let Some(resp) = decode::<PeersResponse>(get_json(&url).await?, json)? else {
+        return Ok(());
+    };
 
     if resp.peers.is_empty() {
-        println!("No peers discovered yet.");
+        match service {
+            Some(service) => println!("No discovered peer offers {}.", service),
+            None => println!("No peers discovered yet."),
+        }
         return Ok(());
     }
 
@@ -119,13 +270,14 @@ pub async fn cmd_peers(port: u16) -> Result<()> {
     for p in &resp.peers {
         let trust_icon = match p.trust_level.as_str() {
             "Trusted" => "✓",
+            "ReceiveOnly" => "↓",
             "Blocked" => "✗",
             _ => "?",
         };
 
         let complete_marker = if p.is_complete { "" } else { " (incomplete)" };
 
-        println!("  ┌─ {} {}", trust_icon, &p.public_key);
+        println!("  ┌─ {} {}", trust_icon, p.public_key);
         println!("  │  addr         : {}", p.addr);
         println!("  │  session port : {}", p.session_port);
         println!(
@@ -133,24 +285,42 @@ pub async fn cmd_peers(port: u16) -> Result<()> {
             p.services.len(),
             p.service_count,
             complete_marker,
-            p.services
-                .iter()
-                .map(|s| s[..8].to_string())
-                .collect::<Vec<_>>()
-                .join(", ")
+            if p.service_details.is_empty() {
+                p.services
+                    .iter()
+                    .map(|s| s[..8].to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            } else {
+                p.service_details
+                    .iter()
+                    .map(|s| s.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
         );
         println!("  │  trust        : {}", p.trust_level);
         if p.buffered_chunks > 0 {
             println!("  │  buffered     : {} chunks", p.buffered_chunks);
         }
+        if p.sent_bytes_per_sec > 0 || p.received_bytes_per_sec > 0 {
+            println!(
+                "  │  rate         : {} B/s up, {} B/s down",
+                p.sent_bytes_per_sec, p.received_bytes_per_sec
+            );
+        }
         println!("  └─ last seen    : {}s ago", p.last_seen_secs);
     }
 
     Ok(())
 }
 
-pub async fn cmd_cache(port: u16) -> Result<()> {
-    let resp: CacheInfo = get_json(&format!("{}/cache", base_url(port))).await?;
+pub async fn cmd_cache(port: u16, json: bool) -> Result<()> {
+    let Some(resp) =
+        decode::<CacheInfo>(get_json(&format!("{}/cache", base_url(port))).await?, json)?
+    else {
+        return Ok(());
+    };
 
     println!("═══════════════════════════════════════");
     println!("  Cache Stats");
@@ -161,18 +331,139 @@ pub async fn cmd_cache(port: u16) -> Result<()> {
         resp.bytes,
         resp.bytes as f64 / 1024.0
     );
+    match resp.utilization_pct {
+        Some(pct) => println!(
+            "  Cap    : {} ({:.1} MB, {:.1}% used)",
+            resp.max_bytes,
+            resp.max_bytes as f64 / (1024.0 * 1024.0),
+            pct
+        ),
+        None => println!("  Cap    : unlimited"),
+    }
+    match resp.hit_ratio_pct {
+        Some(pct) => println!(
+            "  Hits   : {} of {} lookups ({:.1}% hit ratio)",
+            resp.hits,
+            resp.hits + resp.misses,
+            pct
+        ),
+        None => println!("  Hits   : no lookups yet"),
+    }
+    println!(
+        "  Root   : {}",
+        resp.root.as_deref().unwrap_or("(in memory)")
+    );
+
+    Ok(())
+}
+
+pub async fn cmd_cache_list(
+    port: u16,
+    json: bool,
+    limit: Option<usize>,
+    offset: usize,
+) -> Result<()> {
+    let mut url = format!("{}/cache/list?offset={}", base_url(port), offset);
+    if let Some(limit) = limit {
+        url.push_str(&format!("&limit={limit}"));
+    }
+    let Some(resp) = decode::<CacheListResponse>(get_json(&url).await?, json)? else {
+        return Ok(());
+    };
+
+    if resp.chunks.is_empty() {
+        println!(
+            "No cached chunks at offset {} ({} total).",
+            resp.offset, resp.total
+        );
+        return Ok(());
+    }
 
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    println!(
+        "Chunks {}-{} of {}, most recently used first:",
+        resp.offset + 1,
+        resp.offset + resp.chunks.len(),
+        resp.total
+    );
+    for c in &resp.chunks {
+        let idle_secs = now_ms.saturating_sub(c.last_access_ms) / 1000;
+        println!(
+            "  {}  {:>6} B  used {}s ago",
+            c.content_hash, c.bytes, idle_secs
+        );
+    }
     Ok(())
 }
 
-pub async fn cmd_cache_clear(port: u16) -> Result<()> {
-    let resp: ClearResponse = post_json(&format!("{}/cache/clear", base_url(port))).await?;
+pub async fn cmd_cache_clear(port: u16, json: bool) -> Result<()> {
+    let Some(resp) = decode::<ClearResponse>(
+        post_json(&format!("{}/cache/clear", base_url(port))).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
     println!("Cleared {} chunks from cache.", resp.cleared);
     Ok(())
 }
 
-pub async fn cmd_services(port: u16) -> Result<()> {
-    let resp: ServicesResponse = get_json(&format!("{}/services", base_url(port))).await?;
+pub async fn cmd_delivery(port: u16, json: bool) -> Result<()> {
+    let Some(resp) = decode::<DeliveryResponse>(
+        get_json(&format!("{}/delivery", base_url(port))).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+
+    println!("═══════════════════════════════════════");
+    println!("  Delivery Stats");
+    println!("═══════════════════════════════════════");
+    println!("  Tracked chunks   : {}", resp.tracked_chunks);
+    println!("  Multipath chunks : {}", resp.multipath_chunks);
+    println!("  Duplicates       : {}", resp.duplicate_deliveries);
+
+    // Single clean deliveries are the common case — only list the interesting ones.
+    let notable: Vec<_> = resp.chunks.iter().filter(|c| c.duplicates > 0).collect();
+    if notable.is_empty() {
+        println!("\n  No multipath or duplicate deliveries.");
+        return Ok(());
+    }
+
+    println!();
+    for c in notable {
+        let kind = if c.multipath {
+            "multipath"
+        } else {
+            "retransmit"
+        };
+        println!("  ┌─ {} ({})", &c.content_hash[..16], kind);
+        println!("  │  paths      : {}", c.paths.join(", "));
+        println!(
+            "  │  deliveries : {} ({} duplicate)",
+            c.deliveries, c.duplicates
+        );
+        println!(
+            "  └─ seen       : {}s ago → {}s ago",
+            c.first_seen_secs, c.last_seen_secs
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn cmd_services(port: u16, json: bool) -> Result<()> {
+    let Some(resp) = decode::<ServicesResponse>(
+        get_json(&format!("{}/services", base_url(port))).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
 
     println!("═══════════════════════════════════════");
     println!("  Services");
@@ -181,13 +472,50 @@ pub async fn cmd_services(port: u16) -> Result<()> {
     for svc in &resp.services {
         let icon = if svc.enabled { "✓" } else { "○" };
         let state = if svc.enabled { "enabled" } else { "disabled" };
-        println!("  {} {:<16} {} ({})", icon, svc.name, state, svc.contract);
+        println!(
+            "  {} {:<16} {} ({}, {} sessions)",
+            icon, svc.name, state, svc.contract, svc.active_sessions
+        );
     }
 
     Ok(())
 }
 
-pub async fn cmd_schema_list(port: u16) -> Result<()> {
+pub async fn cmd_service_toggle(port: u16, json: bool, name: &str, enabled: bool) -> Result<()> {
+    #[derive(serde::Serialize)]
+    struct ServiceToggleRequest {
+        enabled: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct ServiceToggleResponse {
+        name: String,
+        enabled: bool,
+        changed: bool,
+    }
+
+    let Some(resp) = decode::<ServiceToggleResponse>(
+        post_json_body(
+            &format!("{}/services/{}", base_url(port), name),
+            &ServiceToggleRequest { enabled },
+        )
+        .await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+
+    let state = if resp.enabled { "enabled" } else { "disabled" };
+    if resp.changed {
+        println!("✓ {} {}", resp.name, state);
+    } else {
+        println!("{} already {}", resp.name, state);
+    }
+    Ok(())
+}
+
+pub async fn cmd_schema_list(port: u16, json: bool) -> Result<()> {
     #[derive(Deserialize)]
     struct SchemaListResponse {
         schemas: Vec<SchemaItem>,
@@ -197,30 +525,45 @@ pub async fn cmd_schema_list(port: u16) -> Result<()> {
     struct SchemaItem {
         id: String,
         name: String,
-        type_tag: u8,
+        type_tag: Option<u16>,
+        contract: String,
     }
 
-    let resp: SchemaListResponse = get_json(&format!("{}/schema", base_url(port))).await?;
+    let Some(resp) =
+        decode::<SchemaListResponse>(get_json(&format!("{}/schema", base_url(port))).await?, json)?
+    else {
+        return Ok(());
+    };
 
     println!("═══════════════════════════════════════");
     println!("  Known Schemas ({})", resp.schemas.len());
     println!("═══════════════════════════════════════");
 
     for schema in &resp.schemas {
-        println!("  ┌─ {} (tag: {})", schema.name, schema.type_tag);
+        let tag = schema
+            .type_tag
+            .map_or_else(|| "varies".to_string(), |t| t.to_string());
+        println!("  ┌─ {} (tag: {})", schema.name, tag);
+        println!("  │  contract: {}", schema.contract);
         println!("  └─ id: {}...", &schema.id[..16]);
     }
 
     Ok(())
 }
 
-pub async fn cmd_shutdown(port: u16) -> Result<()> {
+pub async fn cmd_shutdown(port: u16, json: bool) -> Result<()> {
     #[derive(Deserialize)]
     struct ShutdownResponse {
         message: String,
     }
 
-    let resp: ShutdownResponse = post_json(&format!("{}/daemon/shutdown", base_url(port))).await?;
+    let Some(resp) = decode::<ShutdownResponse>(
+        post_json(&format!("{}/daemon/shutdown", base_url(port))).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
     println!("{}", resp.message);
     Ok(())
 }
@@ -3,7 +3,7 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-use super::http::{base_url, get_json, post_json_body};
+use super::http::{base_url, decode, get_json, post_json_body};
 
 #[derive(Deserialize)]
 struct TrustListResponse {
@@ -27,6 +27,17 @@ struct TrustAddResponse {
     flushed_chunks: usize,
 }
 
+#[derive(Serialize)]
+struct TrustReceiveOnlyRequest {
+    public_key: String,
+}
+
+#[derive(Deserialize)]
+struct TrustReceiveOnlyResponse {
+    public_key: String,
+    flushed_chunks: usize,
+}
+
 #[derive(Serialize)]
 struct TrustBlockRequest {
     public_key: String,
@@ -48,8 +59,12 @@ struct PendingPeer {
     buffered_chunks: usize,
 }
 
-pub async fn cmd_trust_list(port: u16) -> Result<()> {
-    let resp: TrustListResponse = get_json(&format!("{}/trust", base_url(port))).await?;
+pub async fn cmd_trust_list(port: u16, json: bool) -> Result<()> {
+    let Some(resp) =
+        decode::<TrustListResponse>(get_json(&format!("{}/trust", base_url(port))).await?, json)?
+    else {
+        return Ok(());
+    };
 
     if resp.rules.is_empty() {
         println!("No explicit trust rules. All peers default to Untrusted.");
@@ -63,22 +78,28 @@ pub async fn cmd_trust_list(port: u16) -> Result<()> {
     for rule in &resp.rules {
         let icon = match rule.level.as_str() {
             "Trusted" => "✓",
+            "ReceiveOnly" => "↓",
             "Blocked" => "✗",
             _ => "?",
         };
-        println!("  {} {} — {}", icon, &rule.public_key, rule.level);
+        println!("  {} {} — {}", icon, rule.public_key, rule.level);
     }
 
     Ok(())
 }
 
-pub async fn cmd_trust_add(port: u16, pubkey: &str) -> Result<()> {
+pub async fn cmd_trust_add(port: u16, json: bool, pubkey: &str) -> Result<()> {
     let req = TrustAddRequest {
         public_key: pubkey.to_string(),
     };
 
-    let resp: TrustAddResponse =
-        post_json_body(&format!("{}/trust/add", base_url(port)), &req).await?;
+    let Some(resp) = decode::<TrustAddResponse>(
+        post_json_body(&format!("{}/trust/add", base_url(port)), &req).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
 
     println!("✓ Peer trusted: {}", &resp.public_key[..16]);
     if resp.flushed_chunks > 0 {
@@ -88,21 +109,53 @@ pub async fn cmd_trust_add(port: u16, pubkey: &str) -> Result<()> {
     Ok(())
 }
 
-pub async fn cmd_trust_block(port: u16, pubkey: &str) -> Result<()> {
+pub async fn cmd_trust_receive_only(port: u16, json: bool, pubkey: &str) -> Result<()> {
+    let req = TrustReceiveOnlyRequest {
+        public_key: pubkey.to_string(),
+    };
+
+    let Some(resp) = decode::<TrustReceiveOnlyResponse>(
+        post_json_body(&format!("{}/trust/receive-only", base_url(port)), &req).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
+
+    println!("↓ Peer receive-only: {}", &resp.public_key[..16]);
+    if resp.flushed_chunks > 0 {
+        println!("  Processed {} buffered chunks", resp.flushed_chunks);
+    }
+
+    Ok(())
+}
+
+pub async fn cmd_trust_block(port: u16, json: bool, pubkey: &str) -> Result<()> {
     let req = TrustBlockRequest {
         public_key: pubkey.to_string(),
     };
 
-    let resp: TrustBlockResponse =
-        post_json_body(&format!("{}/trust/block", base_url(port)), &req).await?;
+    let Some(resp) = decode::<TrustBlockResponse>(
+        post_json_body(&format!("{}/trust/block", base_url(port)), &req).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
 
     println!("✗ Peer blocked: {}", &resp.public_key[..16]);
 
     Ok(())
 }
 
-pub async fn cmd_trust_pending(port: u16) -> Result<()> {
-    let resp: TrustPendingResponse = get_json(&format!("{}/trust/pending", base_url(port))).await?;
+pub async fn cmd_trust_pending(port: u16, json: bool) -> Result<()> {
+    let Some(resp) = decode::<TrustPendingResponse>(
+        get_json(&format!("{}/trust/pending", base_url(port))).await?,
+        json,
+    )?
+    else {
+        return Ok(());
+    };
 
     if resp.peers.is_empty() {
         println!("No buffered chunks from untrusted peers.");
@@ -1,27 +1,43 @@
 //! summit-ctl — command-line interface for the Summit daemon.
 
+use std::time::Duration;
+
 use anyhow::{Context, Result};
 
 mod cmd;
 
 const DEFAULT_PORT: u16 = 9001;
 
+/// Refresh interval for `--watch` when `--interval` is not given.
+const DEFAULT_WATCH_SECS: u64 = 2;
+
 fn print_usage() {
-    println!("Usage: summit-ctl [--port <port>] <command>");
+    println!("Usage: summit-ctl [--port <port>] [--json] <command>");
     println!();
     println!("Daemon");
+    println!("  whoami                          Show this node's public key and services");
     println!("  shutdown                        Gracefully shut down the daemon");
     println!("  status                          Sessions, cache, and peer summary");
+    println!("  status --watch [--interval N]   Refresh the status view every N seconds");
     println!("  services                        Show enabled/disabled services");
+    println!("  services enable|disable <name>  Switch a service without restarting");
     println!();
     println!("Peers & Sessions");
     println!("  peers                           List discovered peers with trust status");
+    println!("  peers --watch [--interval N]    Refresh the peer list every N seconds");
+    println!("  peers --service <name>          Only peers offering a service, e.g. compute");
+    println!("  sessions                        List established sessions");
+    println!("  sessions --peer <pubkey>        Show sessions with one peer");
+    println!("  sessions connect <pubkey>       Handshake with a discovered peer now");
     println!("  sessions drop <id>              Drop a specific session");
+    println!("  sessions drop <id> --cooldown N Drop it and refuse the peer for N seconds");
     println!("  sessions inspect <id>           Show detailed session info");
+    println!("  handshakes                      Show handshakes still in progress");
     println!();
     println!("Trust");
     println!("  trust list                      Show trust rules");
     println!("  trust add <pubkey>              Trust a peer (flushes buffered chunks)");
+    println!("  trust receive-only <pubkey>     Take files/messages from a peer, no compute");
     println!("  trust block <pubkey>            Block a peer");
     println!("  trust pending                   Untrusted peers with buffered chunks");
     println!();
@@ -29,46 +45,75 @@ fn print_usage() {
     println!("  send <file>                     Broadcast file to all trusted peers");
     println!("  send <file> --peer <pubkey>     Send file to specific peer");
     println!("  send <file> --session <id>      Send file to specific session");
-    println!("  files                           List received and in-progress files");
+    println!("  send --stdin --name <filename>  Send bytes read from stdin (also: send -)");
+    println!("  send <file> --name <filename>   Send under a different filename");
+    println!("  send <file> --dry-run           Show chunk count and targets, send nothing");
+    println!("  send <file> --fec [K:M]         Add K:M Reed-Solomon parity (default 16:4)");
+    println!("  files                           List received, in-progress and sent files");
+    println!(
+        "  files get <name> [-o <path>]    Fetch a received file (decrypted) to stdout or path"
+    );
+    println!("  transfers                       Recent sends with duration and MB/s");
     println!();
     println!("Messaging");
-    println!("  messages <pubkey>               List messages from a peer");
+    println!("  messages [--limit N] [--offset N]  Recent messages from all peers");
+    println!("  messages <pubkey>               List messages from a peer, marking them read");
+    println!("  messages read <pubkey>          Mark a peer's messages read");
     println!("  messages send <pubkey> <text>   Send a text message to a peer");
+    println!("  messages send <pk1,pk2> <text>  Send one group message to several peers");
     println!();
     println!("Compute");
     println!("  compute tasks                   List all compute tasks");
     println!("  compute tasks <pubkey>          List compute tasks from a specific peer");
+    println!("  compute tasks [<pubkey>] --follow  Stream task output until tasks finish");
     println!("  compute submit <pubkey> -- <cmd>  Submit a shell command to a peer");
     println!("  compute submit <pubkey> <json>    Submit a JSON task payload");
+    println!("  compute submit ... --priority N   Run before lower-priority queued tasks");
+    println!(
+        "  compute submit ... --force        Submit even if the peer doesn't advertise compute"
+    );
+    println!("  compute cancel <task_id>        Cancel a queued or running task");
     println!();
     println!("Cache & Schema");
     println!("  cache                           Show cache statistics");
+    println!("  cache list [--limit N] [--offset N]  Cached chunks, most recently used first");
     println!("  cache clear                     Clear the chunk cache");
+    println!("  delivery                        Multipath and duplicate delivery stats");
     println!("  schema list                     List all known schemas");
     println!();
     println!(
         "Options:\n  --port <port>                   API port (default: {})",
         DEFAULT_PORT
     );
+    println!("  --json                          Print the raw API response as JSON");
+    println!();
+    println!(
+        "Environment:\n  SUMMIT_API_TOKEN                Bearer token, if the daemon sets api.token"
+    );
     println!();
     println!("Examples:");
+    println!("  summit-ctl whoami");
     println!("  summit-ctl status");
+    println!("  summit-ctl peers --watch --interval 5");
     println!("  summit-ctl services");
     println!("  summit-ctl trust add 5c8c7d3c9eff6572...");
     println!("  summit-ctl send document.pdf");
     println!("  summit-ctl send photo.jpg --peer 99b1db0b1849c7f8...");
+    println!("  tar cz project/ | summit-ctl send - --name project.tgz");
     println!("  summit-ctl messages send 99b1db0b... 'hello world'");
     println!("  summit-ctl compute submit 99b1db0b... -- uname -a");
     println!("  summit-ctl compute submit 99b1db0b... -- hostnamectl > info.txt");
     println!("  summit-ctl compute tasks");
+    println!("  summit-ctl --json peers | jq '.peers[].public_key'");
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().skip(1).collect();
 
-    // Parse --port option
+    // Parse global options
     let mut port = DEFAULT_PORT;
+    let mut json = false;
     let mut remaining: Vec<String> = Vec::new();
     let mut i = 0;
     while i < args.len() {
@@ -79,6 +124,12 @@ async fn main() -> Result<()> {
                 .context("--port requires a value")?
                 .parse()
                 .context("--port must be a number")?;
+        } else if args[i] == "--json" {
+            json = true;
+        } else if args[i] == "--" {
+            // Everything after `--` belongs to the command (compute submit)
+            remaining.extend_from_slice(&args[i..]);
+            break;
         } else {
             remaining.push(args[i].clone());
         }
@@ -89,11 +140,15 @@ async fn main() -> Result<()> {
 
     // Handle send command with optional targeting
     if remaining_refs.first() == Some(&"send") && remaining_refs.len() >= 2 {
-        let path = remaining_refs[1];
+        let mut path = None;
+        let mut from_stdin = false;
+        let mut name = None;
         let mut target_peer = None;
         let mut target_session = None;
+        let mut fec = None;
+        let mut dry_run = false;
 
-        let mut i = 2;
+        let mut i = 1;
         while i < remaining_refs.len() {
             match remaining_refs[i] {
                 "--peer" => {
@@ -104,6 +159,23 @@ async fn main() -> Result<()> {
                     i += 1;
                     target_session = remaining_refs.get(i).copied();
                 }
+                "--name" => {
+                    i += 1;
+                    name = Some(*remaining_refs.get(i).context("--name requires a value")?);
+                }
+                "--stdin" | "-" => from_stdin = true,
+                "--fec" => {
+                    // An optional DATA:PARITY scheme; the daemon's default otherwise.
+                    match remaining_refs.get(i + 1) {
+                        Some(scheme) if scheme.contains(':') => {
+                            i += 1;
+                            fec = Some(*scheme);
+                        }
+                        _ => fec = Some("on"),
+                    }
+                }
+                "--dry-run" => dry_run = true,
+                arg if !arg.starts_with("--") && path.is_none() => path = Some(arg),
                 _ => {
                     anyhow::bail!("Unknown option: {}", remaining_refs[i]);
                 }
@@ -111,43 +183,244 @@ async fn main() -> Result<()> {
             i += 1;
         }
 
-        return cmd::files::cmd_send(port, path, target_peer, target_session).await;
+        let source = match (path, from_stdin) {
+            (Some(_), true) => anyhow::bail!("give either a file path or --stdin, not both"),
+            (Some(path), false) => cmd::files::FileSource::Path(path),
+            (None, true) => cmd::files::FileSource::Stdin,
+            (None, false) => anyhow::bail!("send requires a file path, - or --stdin"),
+        };
+        let target_peer = target_peer.map(cmd::keys::pubkey).transpose()?;
+        let target_session = target_session.map(cmd::keys::session_id).transpose()?;
+
+        return cmd::files::cmd_send(
+            port,
+            json,
+            source,
+            name,
+            target_peer.as_deref(),
+            target_session.as_deref(),
+            fec,
+            dry_run,
+        )
+        .await;
     }
 
-    // Handle: compute submit <pubkey> -- <shell command...>
-    if remaining_refs.len() >= 4
-        && remaining_refs[0] == "compute"
-        && remaining_refs[1] == "submit"
-        && let Some(sep) = remaining_refs.iter().position(|s| *s == "--")
+    // Handle: status|peers --watch [--interval N], peers --service <name>
+    if let [command @ ("status" | "peers"), opts @ ..] = remaining_refs.as_slice()
+        && !opts.is_empty()
     {
-        let to = remaining_refs[2];
-        let shell_cmd = remaining[sep + 1..].join(" ");
-        let payload = serde_json::json!({ "run": shell_cmd }).to_string();
-        return cmd::compute::cmd_compute_submit(port, to, &payload).await;
+        let mut watch_mode = false;
+        let mut interval = None;
+        let mut service = None;
+        let mut i = 0;
+        while i < opts.len() {
+            match opts[i] {
+                "--watch" => watch_mode = true,
+                "--service" if *command == "peers" => {
+                    i += 1;
+                    service = Some(*opts.get(i).context("--service requires a value")?);
+                }
+                "--interval" => {
+                    i += 1;
+                    let secs: u64 = opts
+                        .get(i)
+                        .context("--interval requires a value")?
+                        .parse()
+                        .context("--interval must be a number of seconds")?;
+                    interval = Some(secs);
+                    if secs == 0 {
+                        anyhow::bail!("--interval must be at least 1 second");
+                    }
+                }
+                other => anyhow::bail!("Unknown option: {}", other),
+            }
+            i += 1;
+        }
+        if !watch_mode {
+            if interval.is_some() {
+                anyhow::bail!("--interval requires --watch");
+            }
+            return cmd::status::cmd_peers(port, json, service).await;
+        }
+        let secs = interval.unwrap_or(DEFAULT_WATCH_SECS);
+        return watch(port, json, command, service, secs).await;
+    }
+
+    // Handle: cache list [--limit N] [--offset N]
+    if let ["cache", "list", opts @ ..] = remaining_refs.as_slice() {
+        let mut limit = None;
+        let mut offset = 0;
+        let mut i = 0;
+        while i < opts.len() {
+            match opts[i] {
+                "--limit" => {
+                    i += 1;
+                    limit = Some(
+                        opts.get(i)
+                            .context("--limit requires a value")?
+                            .parse()
+                            .context("--limit must be a number")?,
+                    );
+                }
+                "--offset" => {
+                    i += 1;
+                    offset = opts
+                        .get(i)
+                        .context("--offset requires a value")?
+                        .parse()
+                        .context("--offset must be a number")?;
+                }
+                other => anyhow::bail!("Unknown option: {}", other),
+            }
+            i += 1;
+        }
+        return cmd::status::cmd_cache_list(port, json, limit, offset).await;
+    }
+
+    // Handle: messages [--limit N] [--offset N] — the inbox across all peers
+    if let ["messages", opts @ ..] = remaining_refs.as_slice()
+        && opts.first().is_none_or(|o| o.starts_with("--"))
+    {
+        let mut limit = None;
+        let mut offset = 0;
+        let mut i = 0;
+        while i < opts.len() {
+            match opts[i] {
+                "--limit" => {
+                    i += 1;
+                    limit = Some(
+                        opts.get(i)
+                            .context("--limit requires a value")?
+                            .parse()
+                            .context("--limit must be a number")?,
+                    );
+                }
+                "--offset" => {
+                    i += 1;
+                    offset = opts
+                        .get(i)
+                        .context("--offset requires a value")?
+                        .parse()
+                        .context("--offset must be a number")?;
+                }
+                other => anyhow::bail!("Unknown option: {}", other),
+            }
+            i += 1;
+        }
+        return cmd::messages::cmd_inbox(port, json, limit, offset).await;
+    }
+
+    // Handle: compute submit <pubkey> [--priority N] [--force] (<json> | -- <shell command...>)
+    if let ["compute", "submit", to, opts @ ..] = remaining_refs.as_slice() {
+        let mut payload = None;
+        let mut priority = 0;
+        let mut force = false;
+        let mut i = 0;
+        while i < opts.len() {
+            match opts[i] {
+                "--priority" => {
+                    i += 1;
+                    priority = opts
+                        .get(i)
+                        .context("--priority requires a value")?
+                        .parse()
+                        .context("--priority must be an integer")?;
+                }
+                "--force" => force = true,
+                "--" => {
+                    let shell_cmd = opts[i + 1..].join(" ");
+                    payload = Some(serde_json::json!({ "run": shell_cmd }).to_string());
+                    break;
+                }
+                arg if !arg.starts_with("--") && payload.is_none() => {
+                    payload = Some(arg.to_string())
+                }
+                other => anyhow::bail!("Unknown option: {}", other),
+            }
+            i += 1;
+        }
+        let payload = payload.context("compute submit requires a JSON payload or -- <command>")?;
+        let to = cmd::keys::pubkey(to)?;
+        return cmd::compute::cmd_compute_submit(port, json, &to, &payload, priority, force).await;
     }
 
     match remaining_refs.as_slice() {
-        ["shutdown"] => cmd::status::cmd_shutdown(port).await,
-        ["status"] | [] => cmd::status::cmd_status(port).await,
-        ["services"] => cmd::status::cmd_services(port).await,
-        ["peers"] => cmd::status::cmd_peers(port).await,
-        ["sessions", "drop", id] => cmd::sessions::cmd_session_drop(port, id).await,
-        ["sessions", "inspect", id] => cmd::sessions::cmd_session_inspect(port, id).await,
-        ["cache"] => cmd::status::cmd_cache(port).await,
-        ["cache", "clear"] => cmd::status::cmd_cache_clear(port).await,
-        ["files"] => cmd::files::cmd_files(port).await,
-        ["trust", "list"] | ["trust"] => cmd::trust::cmd_trust_list(port).await,
-        ["trust", "add", pubkey] => cmd::trust::cmd_trust_add(port, pubkey).await,
-        ["trust", "block", pubkey] => cmd::trust::cmd_trust_block(port, pubkey).await,
-        ["trust", "pending"] => cmd::trust::cmd_trust_pending(port).await,
-        ["messages", peer] => cmd::messages::cmd_messages(port, peer).await,
-        ["messages", "send", to, text] => cmd::messages::cmd_messages_send(port, to, text).await,
-        ["compute", "tasks"] => cmd::compute::cmd_compute_tasks_all(port).await,
-        ["compute", "tasks", peer] => cmd::compute::cmd_compute_tasks(port, peer).await,
-        ["compute", "submit", to, payload] => {
-            cmd::compute::cmd_compute_submit(port, to, payload).await
-        }
-        ["schema", "list"] | ["schema"] => cmd::status::cmd_schema_list(port).await,
+        ["whoami"] => cmd::status::cmd_whoami(port, json).await,
+        ["shutdown"] => cmd::status::cmd_shutdown(port, json).await,
+        ["status"] | [] => cmd::status::cmd_status(port, json).await,
+        ["services"] => cmd::status::cmd_services(port, json).await,
+        ["services", "enable", name] => {
+            cmd::status::cmd_service_toggle(port, json, name, true).await
+        }
+        ["services", "disable", name] => {
+            cmd::status::cmd_service_toggle(port, json, name, false).await
+        }
+        ["peers"] => cmd::status::cmd_peers(port, json, None).await,
+        ["sessions"] | ["sessions", "list"] => cmd::sessions::cmd_sessions_list(port, json).await,
+        ["sessions", "--peer", peer] => {
+            cmd::sessions::cmd_sessions_with_peer(port, json, &cmd::keys::pubkey(peer)?).await
+        }
+        ["handshakes"] => cmd::sessions::cmd_handshakes(port, json).await,
+        ["sessions", "connect", peer] => {
+            cmd::sessions::cmd_session_connect(port, json, &cmd::keys::pubkey(peer)?).await
+        }
+        ["sessions", "drop", id] => {
+            cmd::sessions::cmd_session_drop(port, json, &cmd::keys::session_id(id)?, None).await
+        }
+        ["sessions", "drop", id, "--cooldown", secs] => {
+            let secs: u64 = secs
+                .parse()
+                .context("--cooldown must be a number of seconds")?;
+            cmd::sessions::cmd_session_drop(port, json, &cmd::keys::session_id(id)?, Some(secs))
+                .await
+        }
+        ["sessions", "inspect", id] => {
+            cmd::sessions::cmd_session_inspect(port, json, &cmd::keys::session_id(id)?).await
+        }
+        ["cache"] => cmd::status::cmd_cache(port, json).await,
+        ["cache", "clear"] => cmd::status::cmd_cache_clear(port, json).await,
+        ["delivery"] => cmd::status::cmd_delivery(port, json).await,
+        ["files"] => cmd::files::cmd_files(port, json).await,
+        ["files", "get", name] => cmd::files::cmd_file_get(port, name, None).await,
+        ["files", "get", name, "-o" | "--output", out] => {
+            cmd::files::cmd_file_get(port, name, Some(out)).await
+        }
+        ["transfers"] => cmd::files::cmd_transfers(port, json).await,
+        ["trust", "list"] | ["trust"] => cmd::trust::cmd_trust_list(port, json).await,
+        ["trust", "add", pubkey] => {
+            cmd::trust::cmd_trust_add(port, json, &cmd::keys::pubkey(pubkey)?).await
+        }
+        ["trust", "receive-only", pubkey] => {
+            cmd::trust::cmd_trust_receive_only(port, json, &cmd::keys::pubkey(pubkey)?).await
+        }
+        ["trust", "block", pubkey] => {
+            cmd::trust::cmd_trust_block(port, json, &cmd::keys::pubkey(pubkey)?).await
+        }
+        ["trust", "pending"] => cmd::trust::cmd_trust_pending(port, json).await,
+        ["messages", peer] => {
+            cmd::messages::cmd_messages(port, json, &cmd::keys::pubkey(peer)?).await
+        }
+        ["messages", "read", peer] => {
+            cmd::messages::cmd_messages_read(port, json, &cmd::keys::pubkey(peer)?).await
+        }
+        ["messages", "send", to, text] => {
+            cmd::messages::cmd_messages_send(port, json, to, text).await
+        }
+        ["compute", "tasks"] => cmd::compute::cmd_compute_tasks_all(port, json).await,
+        ["compute", "tasks", "--follow"] => {
+            cmd::compute::cmd_compute_tasks_follow(port, json, None).await
+        }
+        ["compute", "tasks", peer, "--follow"] => {
+            let peer = cmd::keys::pubkey(peer)?;
+            cmd::compute::cmd_compute_tasks_follow(port, json, Some(&peer)).await
+        }
+        ["compute", "tasks", peer] => {
+            cmd::compute::cmd_compute_tasks(port, json, &cmd::keys::pubkey(peer)?).await
+        }
+        ["compute", "cancel", task_id] => {
+            cmd::compute::cmd_compute_cancel(port, json, task_id).await
+        }
+        ["schema", "list"] | ["schema"] => cmd::status::cmd_schema_list(port, json).await,
         ["help"] | ["--help"] | ["-h"] => {
             print_usage();
             Ok(())
@@ -160,3 +433,46 @@ async fn main() -> Result<()> {
         }
     }
 }
+
+/// Clear the screen and re-run `status` or `peers` every `secs` seconds
+/// until Ctrl-C. A daemon that goes away is reported and retried rather
+/// than ending the watch.
+async fn watch(
+    port: u16,
+    json: bool,
+    command: &str,
+    service: Option<&str>,
+    secs: u64,
+) -> Result<()> {
+    let mut ticker = tokio::time::interval(Duration::from_secs(secs));
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = ticker.tick() => {}
+        }
+
+        // Clear screen, cursor home
+        print!("\x1b[2J\x1b[H");
+        println!(
+            "Every {}s: summit-ctl {}    (Ctrl-C to stop)\n",
+            secs, command
+        );
+
+        // A daemon that has stopped answering mustn't hold off Ctrl-C
+        // until the request times out.
+        let fetch = async {
+            match command {
+                "peers" => cmd::status::cmd_peers(port, json, service).await,
+                _ => cmd::status::cmd_status(port, json).await,
+            }
+        };
+        let result = tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            result = fetch => result,
+        };
+        if let Err(e) = result {
+            println!("{}", e);
+            println!("\nWaiting for summitd — retrying in {}s…", secs);
+        }
+    }
+}
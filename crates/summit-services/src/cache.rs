@@ -5,146 +5,455 @@
 //!
 //! This is the same layout Git uses for objects. Files are immutable —
 //! if the hash exists, the content is correct. No TTLs, no invalidation.
+//! An in-memory backend offers the same interface without touching disk.
+//!
+//! With a size cap, the least-recently-accessed chunks are evicted once the
+//! cache grows past it. Access order is tracked in memory and seeded from
+//! file modification times when the cache is opened.
+//!
+//! Every `get` counts as a hit or a miss, so operators can see how often
+//! retransmissions and dedup are actually served from the cache. `list`
+//! pages through individual chunks, most recently accessed first.
+//!
+//! Chunks are private to the transfer that brought them unless `share`
+//! marks them as fit to pass on to any peer; `mark_exchanged` records the
+//! peers a private chunk went to or came from, who hold it already. Both
+//! marks live in memory only, so after a restart nothing is served again
+//! until it is marked again.
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 use anyhow::{Context, Result};
 use bytes::Bytes;
+use dashmap::DashMap;
 use memmap2::Mmap;
 
 /// Content-addressed chunk cache.
 #[derive(Clone)]
 pub struct ChunkCache {
-    root: PathBuf,
+    store: Store,
+    /// Size cap in bytes. 0 = unlimited.
+    max_bytes: u64,
+    index: Arc<Mutex<CacheIndex>>,
+    stats: Arc<CacheStats>,
+}
+
+/// Lookups since the cache was opened. Not reset by `clear`.
+#[derive(Default)]
+struct CacheStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Where chunk bytes live.
+#[derive(Clone)]
+enum Store {
+    /// Two-level directory tree under the cache root.
+    Disk(PathBuf),
+    /// Process memory only — nothing touches the filesystem.
+    Memory(Arc<DashMap<[u8; 32], Bytes>>),
+}
+
+/// One cached chunk, as listed by `ChunkCache::list`.
+#[derive(Debug, Clone)]
+pub struct CachedChunk {
+    pub hash: [u8; 32],
+    pub size: u64,
+    /// Last `get` or `put`, or the file's modification time if untouched
+    /// since the cache was opened.
+    pub last_access: SystemTime,
+}
+
+/// Per-chunk size and access order, for eviction and stats.
+#[derive(Default)]
+struct CacheIndex {
+    /// hash → (size, last access tick, last access time)
+    entries: HashMap<[u8; 32], (u64, u64, SystemTime)>,
+    /// last access tick → hash, oldest first
+    by_access: BTreeMap<u64, [u8; 32]>,
+    /// Chunks marked with `share`.
+    shared: HashSet<[u8; 32]>,
+    /// Peers each private chunk went to or came from, marked with
+    /// `mark_exchanged`.
+    exchanged: HashMap<[u8; 32], HashSet<[u8; 32]>>,
+    total_bytes: u64,
+    next_tick: u64,
+}
+
+impl CacheIndex {
+    /// Record an access (or insertion) of `hash`, making it most recent.
+    fn touch(&mut self, hash: [u8; 32], size: u64) {
+        self.touch_at(hash, size, SystemTime::now());
+    }
+
+    fn touch_at(&mut self, hash: [u8; 32], size: u64, at: SystemTime) {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        match self.entries.insert(hash, (size, tick, at)) {
+            Some((old_size, old_tick, _)) => {
+                self.by_access.remove(&old_tick);
+                self.total_bytes = self.total_bytes - old_size + size;
+            }
+            None => self.total_bytes += size,
+        }
+        self.by_access.insert(tick, hash);
+    }
+
+    /// Pop the least-recently-accessed chunk.
+    fn pop_oldest(&mut self) -> Option<[u8; 32]> {
+        let (_, hash) = self.by_access.pop_first()?;
+        if let Some((size, _, _)) = self.entries.remove(&hash) {
+            self.total_bytes -= size;
+        }
+        self.shared.remove(&hash);
+        self.exchanged.remove(&hash);
+        Some(hash)
+    }
 }
 
 impl ChunkCache {
-    /// Create a cache rooted at the given directory.
+    /// Create an unbounded cache rooted at the given directory.
     ///
     /// For production: /var/cache/summit/chunks
     /// For testing: /tmp/summit-cache-{pid}
     pub fn new(root: impl Into<PathBuf>) -> Result<Self> {
+        Self::with_max_bytes(root, 0)
+    }
+
+    /// Create a cache that evicts least-recently-accessed chunks once it
+    /// holds more than `max_bytes`. 0 = unlimited.
+    ///
+    /// Fails if the root can't be created or written to.
+    pub fn with_max_bytes(root: impl Into<PathBuf>, max_bytes: u64) -> Result<Self> {
         let root = root.into();
         fs::create_dir_all(&root)
             .with_context(|| format!("failed to create cache root: {}", root.display()))?;
-        Ok(Self { root })
+        let probe = root.join(".write-probe");
+        fs::write(&probe, b"")
+            .with_context(|| format!("cache root is not writable: {}", root.display()))?;
+        fs::remove_file(&probe).ok();
+        let cache = Self {
+            index: Arc::new(Mutex::new(Self::scan(&root))),
+            store: Store::Disk(root),
+            max_bytes,
+            stats: Arc::default(),
+        };
+        cache.evict();
+        Ok(cache)
     }
 
-    /// Check if a chunk exists in the cache.
-    pub fn has(&self, hash: &[u8; 32]) -> bool {
-        self.chunk_path(hash).exists()
+    /// Create an unbounded cache held entirely in memory. Nothing is
+    /// written to disk; contents vanish with the process.
+    pub fn in_memory() -> Self {
+        Self::in_memory_with_max_bytes(0)
     }
 
-    /// Retrieve a chunk from the cache.
-    ///
-    /// Returns None if not present. The returned Bytes is backed by mmap,
-    /// so reads are zero-copy and page faults bring data from disk on demand.
-    pub fn get(&self, hash: &[u8; 32]) -> Result<Option<Bytes>> {
-        let path = self.chunk_path(hash);
-        if !path.exists() {
-            return Ok(None);
+    /// In-memory cache with the same LRU size cap as `with_max_bytes`.
+    pub fn in_memory_with_max_bytes(max_bytes: u64) -> Self {
+        Self {
+            store: Store::Memory(Arc::new(DashMap::new())),
+            max_bytes,
+            index: Arc::new(Mutex::new(CacheIndex::default())),
+            stats: Arc::default(),
         }
+    }
 
-        let file = fs::File::open(&path)
-            .with_context(|| format!("failed to open chunk: {}", path.display()))?;
+    /// Directory chunks are stored under; None for the in-memory backend.
+    pub fn root(&self) -> Option<&Path> {
+        match &self.store {
+            Store::Disk(root) => Some(root),
+            Store::Memory(_) => None,
+        }
+    }
 
-        // Safety: file is opened read-only and we don't mutate the mmap
-        let mmap = unsafe {
-            Mmap::map(&file).with_context(|| format!("failed to mmap chunk: {}", path.display()))?
-        };
+    /// True when chunks are kept in memory rather than on disk.
+    pub fn is_in_memory(&self) -> bool {
+        matches!(self.store, Store::Memory(_))
+    }
+
+    /// Build the index from chunks already on disk, oldest-modified first.
+    fn scan(root: &Path) -> CacheIndex {
+        let mut found = Vec::new();
+        for entry in fs::read_dir(root).into_iter().flatten().flatten() {
+            for chunk in fs::read_dir(entry.path()).into_iter().flatten().flatten() {
+                let Ok(meta) = chunk.metadata() else { continue };
+                let hash = hex::decode(chunk.file_name().as_encoded_bytes())
+                    .ok()
+                    .and_then(|b| <[u8; 32]>::try_from(b).ok());
+                if let Some(hash) = hash {
+                    let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    found.push((modified, hash, meta.len()));
+                }
+            }
+        }
+        found.sort_by_key(|(modified, _, _)| *modified);
 
-        // Copy mmap into Bytes — this is still zero-copy in the sense that
-        // Bytes::copy_from_slice is cheap for small sizes, and large mmaps
-        // benefit from kernel page cache. For true zero-copy we'd need to
-        // return the Mmap directly, but Bytes is more convenient.
-        Ok(Some(Bytes::copy_from_slice(&mmap)))
+        let mut index = CacheIndex::default();
+        for (modified, hash, size) in found {
+            index.touch_at(hash, size, modified);
+        }
+        index
     }
 
-    /// Store a chunk in the cache.
-    ///
-    /// Writes are atomic: write to temp file, then rename. If the chunk
-    /// already exists, this is a no-op (immutability = idempotence).
-    pub fn put(&self, hash: &[u8; 32], data: &[u8]) -> Result<()> {
-        let path = self.chunk_path(hash);
+    /// Configured size cap in bytes. 0 = unlimited.
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
 
-        // Already exists? Nothing to do.
-        if path.exists() {
-            return Ok(());
+    /// Remove least-recently-accessed chunks until the cache fits its cap.
+    fn evict(&self) {
+        if self.max_bytes == 0 {
+            return;
+        }
+        let mut index = self.index.lock().unwrap();
+        let mut evicted = 0usize;
+        while index.total_bytes > self.max_bytes {
+            let Some(hash) = index.pop_oldest() else {
+                break;
+            };
+            self.remove(&hash);
+            evicted += 1;
+        }
+        if evicted > 0 {
+            tracing::debug!(
+                evicted,
+                bytes = index.total_bytes,
+                max_bytes = self.max_bytes,
+                "evicted least-recently-used chunks"
+            );
         }
+    }
 
-        // Ensure directory exists
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create cache dir: {}", parent.display()))?;
+    /// Check if a chunk exists in the cache.
+    pub fn has(&self, hash: &[u8; 32]) -> bool {
+        match &self.store {
+            Store::Disk(root) => chunk_path(root, hash).exists(),
+            Store::Memory(chunks) => chunks.contains_key(hash),
         }
+    }
 
-        // Atomic write: tmp file → rename
-        let tmp_path = path.with_extension("tmp");
-        {
-            let mut file = fs::File::create(&tmp_path)
-                .with_context(|| format!("failed to create temp file: {}", tmp_path.display()))?;
-            file.write_all(data).context("failed to write chunk data")?;
-            file.sync_all().context("failed to sync chunk to disk")?;
+    /// Check if a chunk exists from the index alone: no disk access, and
+    /// not counted as a hit or a miss.
+    pub fn contains(&self, hash: &[u8; 32]) -> bool {
+        self.index.lock().unwrap().entries.contains_key(hash)
+    }
+
+    /// Mark a cached chunk as fit to pass on to any peer — broadcast
+    /// content, say, as opposed to a file sent to one peer. No-op for a
+    /// chunk not in the cache.
+    pub fn share(&self, hash: &[u8; 32]) {
+        let mut index = self.index.lock().unwrap();
+        if index.entries.contains_key(hash) {
+            index.shared.insert(*hash);
         }
+    }
 
-        fs::rename(&tmp_path, &path).with_context(|| {
-            format!(
-                "failed to rename {} to {}",
-                tmp_path.display(),
-                path.display()
-            )
-        })?;
+    /// Record that a cached chunk was sent to `peer` alone, or came from
+    /// `peer` sent to us alone. No-op for a chunk not in the cache.
+    pub fn mark_exchanged(&self, hash: &[u8; 32], peer: [u8; 32]) {
+        let mut index = self.index.lock().unwrap();
+        if index.entries.contains_key(hash) {
+            index.exchanged.entry(*hash).or_default().insert(peer);
+        }
+    }
 
-        tracing::trace!(hash = hex::encode(hash), "chunk cached");
-        Ok(())
+    /// Was this chunk marked as exchanged with `peer`?
+    pub fn exchanged_with(&self, hash: &[u8; 32], peer: &[u8; 32]) -> bool {
+        let index = self.index.lock().unwrap();
+        index
+            .exchanged
+            .get(hash)
+            .is_some_and(|peers| peers.contains(peer))
     }
 
-    /// Get the filesystem path for a chunk.
-    fn chunk_path(&self, hash: &[u8; 32]) -> PathBuf {
-        let hex = hex::encode(hash);
-        // Two-level: chunks/ab/abc123...
-        self.root.join(&hex[0..2]).join(&hex)
+    /// May `peer` be sent this chunk, or told we hold it: is it shared, or
+    /// did it pass between us and that peer in the first place?
+    pub fn servable_to(&self, hash: &[u8; 32], peer: &[u8; 32]) -> bool {
+        let index = self.index.lock().unwrap();
+        index.shared.contains(hash)
+            || index
+                .exchanged
+                .get(hash)
+                .is_some_and(|peers| peers.contains(peer))
     }
 
-    /// Count total chunks in cache (for stats/debugging).
-    pub fn count(&self) -> usize {
-        let mut total = 0;
-        if let Ok(entries) = fs::read_dir(&self.root) {
-            for entry in entries.flatten() {
-                if let Ok(subdir) = fs::read_dir(entry.path()) {
-                    total += subdir.count();
+    /// Up to `limit` chunks marked with `share`, most recently accessed
+    /// first. Listing does not count as an access.
+    pub fn list_shared(&self, limit: usize) -> Vec<[u8; 32]> {
+        let index = self.index.lock().unwrap();
+        index
+            .by_access
+            .values()
+            .rev()
+            .filter(|hash| index.shared.contains(*hash))
+            .take(limit)
+            .copied()
+            .collect()
+    }
+
+    /// Retrieve a chunk from the cache. Returns None if not present.
+    pub fn get(&self, hash: &[u8; 32]) -> Result<Option<Bytes>> {
+        let data = match &self.store {
+            Store::Disk(root) => read_chunk(&chunk_path(root, hash))?,
+            Store::Memory(chunks) => chunks.get(hash).map(|d| d.clone()),
+        };
+        match &data {
+            Some(data) => {
+                self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                self.index.lock().unwrap().touch(*hash, data.len() as u64);
+            }
+            None => {
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        Ok(data)
+    }
+
+    /// `get` calls that found their chunk.
+    pub fn hits(&self) -> u64 {
+        self.stats.hits.load(Ordering::Relaxed)
+    }
+
+    /// `get` calls that found nothing.
+    pub fn misses(&self) -> u64 {
+        self.stats.misses.load(Ordering::Relaxed)
+    }
+
+    /// Store a chunk in the cache.
+    ///
+    /// If the chunk already exists, this only refreshes its access time
+    /// (immutability = idempotence).
+    pub fn put(&self, hash: &[u8; 32], data: &[u8]) -> Result<()> {
+        if !self.has(hash) {
+            match &self.store {
+                Store::Disk(root) => write_chunk(&chunk_path(root, hash), data)?,
+                Store::Memory(chunks) => {
+                    chunks.insert(*hash, Bytes::copy_from_slice(data));
                 }
             }
+            tracing::trace!(hash = hex::encode(hash), "chunk cached");
+        }
+        self.index.lock().unwrap().touch(*hash, data.len() as u64);
+        self.evict();
+        Ok(())
+    }
+
+    /// Drop one chunk from the backing store.
+    fn remove(&self, hash: &[u8; 32]) {
+        match &self.store {
+            Store::Disk(root) => {
+                let _ = fs::remove_file(chunk_path(root, hash));
+            }
+            Store::Memory(chunks) => {
+                chunks.remove(hash);
+            }
         }
-        total
+    }
+
+    /// Count total chunks in cache (for stats/debugging).
+    pub fn count(&self) -> usize {
+        self.index.lock().unwrap().entries.len()
     }
 
     /// Total cache size in bytes (for stats/debugging).
     pub fn size(&self) -> u64 {
-        let mut total = 0u64;
-        if let Ok(entries) = fs::read_dir(&self.root) {
-            for entry in entries.flatten() {
-                if let Ok(subdir) = fs::read_dir(entry.path()) {
-                    for chunk in subdir.flatten() {
-                        if let Ok(meta) = chunk.metadata() {
-                            total += meta.len();
-                        }
+        self.index.lock().unwrap().total_bytes
+    }
+
+    /// Up to `limit` chunks, most recently accessed first, skipping the
+    /// first `offset`. Listing does not count as an access.
+    pub fn list(&self, offset: usize, limit: usize) -> Vec<CachedChunk> {
+        let index = self.index.lock().unwrap();
+        index
+            .by_access
+            .values()
+            .rev()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|hash| {
+                let (size, _, last_access) = index.entries.get(hash)?;
+                Some(CachedChunk {
+                    hash: *hash,
+                    size: *size,
+                    last_access: *last_access,
+                })
+            })
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        let mut index = self.index.lock().unwrap();
+        match &self.store {
+            Store::Disk(root) => {
+                if let Ok(entries) = std::fs::read_dir(root) {
+                    for entry in entries.flatten() {
+                        let _ = std::fs::remove_dir_all(entry.path());
                     }
                 }
             }
+            Store::Memory(chunks) => chunks.clear(),
         }
-        total
+        *index = CacheIndex::default();
     }
+}
 
-    pub fn clear(&self) {
-        if let Ok(entries) = std::fs::read_dir(&self.root) {
-            for entry in entries.flatten() {
-                let _ = std::fs::remove_dir_all(entry.path());
-            }
-        }
+/// Get the filesystem path for a chunk.
+fn chunk_path(root: &Path, hash: &[u8; 32]) -> PathBuf {
+    let hex = hex::encode(hash);
+    // Two-level: chunks/ab/abc123...
+    root.join(&hex[0..2]).join(&hex)
+}
+
+/// Read a chunk file, or None if it does not exist.
+fn read_chunk(path: &Path) -> Result<Option<Bytes>> {
+    if !path.exists() {
+        return Ok(None);
     }
+
+    let file = fs::File::open(path)
+        .with_context(|| format!("failed to open chunk: {}", path.display()))?;
+
+    // Safety: file is opened read-only and we don't mutate the mmap
+    let mmap = unsafe {
+        Mmap::map(&file).with_context(|| format!("failed to mmap chunk: {}", path.display()))?
+    };
+
+    // Copy mmap into Bytes — this is still zero-copy in the sense that
+    // Bytes::copy_from_slice is cheap for small sizes, and large mmaps
+    // benefit from kernel page cache. For true zero-copy we'd need to
+    // return the Mmap directly, but Bytes is more convenient.
+    Ok(Some(Bytes::copy_from_slice(&mmap)))
+}
+
+/// Write a chunk file atomically: write to temp file, then rename.
+fn write_chunk(path: &Path, data: &[u8]) -> Result<()> {
+    // Ensure directory exists
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create cache dir: {}", parent.display()))?;
+    }
+
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut file = fs::File::create(&tmp_path)
+            .with_context(|| format!("failed to create temp file: {}", tmp_path.display()))?;
+        file.write_all(data).context("failed to write chunk data")?;
+        file.sync_all().context("failed to sync chunk to disk")?;
+    }
+
+    fs::rename(&tmp_path, path).with_context(|| {
+        format!(
+            "failed to rename {} to {}",
+            tmp_path.display(),
+            path.display()
+        )
+    })
 }
 
 #[cfg(test)]
@@ -170,12 +479,87 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
         assert!(!dir.exists());
 
-        let _cache = ChunkCache::new(&dir).unwrap();
+        let cache = ChunkCache::new(&dir).unwrap();
         assert!(dir.exists());
+        assert_eq!(cache.root(), Some(dir.as_path()));
+        assert!(!dir.join(".write-probe").exists());
+        assert_eq!(ChunkCache::in_memory().root(), None);
 
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn unusable_root_is_an_error() {
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let file =
+            std::env::temp_dir().join(format!("summit-cache-file-{}-{}", std::process::id(), id));
+        std::fs::write(&file, b"not a directory").unwrap();
+
+        assert!(ChunkCache::new(file.join("chunks")).is_err());
+
+        let _ = std::fs::remove_file(&file);
+    }
+
+    #[test]
+    fn only_shared_chunks_are_listed_as_shared() {
+        let cache = ChunkCache::in_memory_with_max_bytes(8);
+        let (a, b, c) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        cache.share(&a); // not cached yet: no-op
+        for hash in [a, b, c] {
+            cache.put(&hash, b"ab").unwrap();
+        }
+        cache.share(&a);
+        cache.share(&c);
+        assert_eq!(cache.list_shared(10), vec![c, a]);
+        assert_eq!(cache.list_shared(1), vec![c]);
+        assert!(cache.contains(&b));
+        assert_eq!(cache.hits() + cache.misses(), 0);
+
+        // Evicted chunks drop their mark.
+        cache.put(&[4u8; 32], b"abcd").unwrap();
+        assert!(!cache.contains(&a));
+        assert_eq!(cache.list_shared(10), vec![c]);
+    }
+
+    #[test]
+    fn exchanged_chunks_are_servable_to_their_peer_only() {
+        let cache = ChunkCache::in_memory_with_max_bytes(8);
+        let (a, b) = ([1u8; 32], [2u8; 32]);
+        let (peer, other) = ([7u8; 32], [8u8; 32]);
+        cache.mark_exchanged(&a, peer); // not cached yet: no-op
+        cache.put(&a, b"ab").unwrap();
+        assert!(!cache.exchanged_with(&a, &peer));
+        cache.mark_exchanged(&a, peer);
+        assert!(cache.exchanged_with(&a, &peer));
+        assert!(cache.servable_to(&a, &peer));
+        assert!(!cache.servable_to(&a, &other));
+
+        cache.put(&b, b"ab").unwrap();
+        cache.share(&b);
+        assert!(cache.servable_to(&b, &other));
+
+        // Evicted chunks drop their mark.
+        cache.put(&[4u8; 32], b"abcdef").unwrap();
+        assert!(!cache.contains(&a));
+        assert!(!cache.exchanged_with(&a, &peer));
+    }
+
+    #[test]
+    fn get_counts_hits_and_misses() {
+        let cache = ChunkCache::in_memory();
+        let hash = summit_core::crypto::hash(b"x");
+        assert!(cache.get(&hash).unwrap().is_none());
+        cache.put(&hash, b"x").unwrap();
+        cache.get(&hash).unwrap();
+        cache.get(&hash).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+
+        // Counters outlive a clear; has() is not a lookup.
+        cache.clear();
+        assert!(!cache.has(&hash));
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+    }
+
     #[test]
     fn put_and_get_roundtrip() {
         let cache = temp_cache();
@@ -245,4 +629,106 @@ mod tests {
         assert_eq!(cache.count(), 0);
         assert!(!cache.has(&hash));
     }
+
+    #[test]
+    fn list_pages_most_recent_first() {
+        let cache = temp_cache();
+        let chunks: Vec<(Vec<u8>, [u8; 32])> = (0u8..4)
+            .map(|i| {
+                let data = vec![i; 10 + i as usize];
+                let hash = summit_core::crypto::hash(&data);
+                (data, hash)
+            })
+            .collect();
+        for (data, hash) in &chunks {
+            cache.put(hash, data).unwrap();
+        }
+        cache.get(&chunks[1].1).unwrap().unwrap();
+
+        let hashes = |page: Vec<CachedChunk>| page.iter().map(|c| c.hash).collect::<Vec<_>>();
+        let first = cache.list(0, 2);
+        assert_eq!(hashes(first.clone()), vec![chunks[1].1, chunks[3].1]);
+        assert_eq!(first[0].size, 11);
+        assert_eq!(hashes(cache.list(2, 2)), vec![chunks[2].1, chunks[0].1]);
+        assert!(cache.list(4, 2).is_empty());
+
+        // Listing is not an access.
+        assert_eq!(hashes(cache.list(0, 1)), vec![chunks[1].1]);
+        assert_eq!(cache.hits(), 1);
+
+        cache.clear();
+    }
+
+    #[test]
+    fn eviction_drops_least_recently_used() {
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir =
+            std::env::temp_dir().join(format!("summit-cache-lru-{}-{}", std::process::id(), id));
+        let _ = std::fs::remove_dir_all(&dir);
+        // Room for three 100-byte chunks.
+        let cache = ChunkCache::with_max_bytes(&dir, 300).unwrap();
+
+        let chunks: Vec<(Vec<u8>, [u8; 32])> = (0u8..5)
+            .map(|i| {
+                let data = vec![i; 100];
+                let hash = summit_core::crypto::hash(&data);
+                (data, hash)
+            })
+            .collect();
+
+        for (data, hash) in &chunks[..3] {
+            cache.put(hash, data).unwrap();
+        }
+        // Touch the oldest so the second chunk becomes least recent.
+        cache.get(&chunks[0].1).unwrap().unwrap();
+
+        for (data, hash) in &chunks[3..] {
+            cache.put(hash, data).unwrap();
+        }
+
+        assert_eq!(cache.count(), 3);
+        assert!(cache.size() <= cache.max_bytes());
+        assert!(cache.has(&chunks[0].1), "recently read chunk survives");
+        assert!(!cache.has(&chunks[1].1), "least recently used is evicted");
+        assert!(!cache.has(&chunks[2].1));
+        assert!(cache.has(&chunks[3].1));
+        assert!(cache.has(&chunks[4].1), "newest survives");
+
+        // Reopening rebuilds the index from disk.
+        let reopened = ChunkCache::with_max_bytes(&dir, 300).unwrap();
+        assert_eq!(reopened.count(), 3);
+        assert_eq!(reopened.size(), 300);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn in_memory_matches_disk_behaviour() {
+        let cache = ChunkCache::in_memory_with_max_bytes(200);
+        assert!(cache.is_in_memory());
+
+        let a = vec![1u8; 100];
+        let b = vec![2u8; 100];
+        let c = vec![3u8; 100];
+        let (ha, hb, hc) = (
+            summit_core::crypto::hash(&a),
+            summit_core::crypto::hash(&b),
+            summit_core::crypto::hash(&c),
+        );
+
+        cache.put(&ha, &a).unwrap();
+        cache.put(&hb, &b).unwrap();
+        assert_eq!(&cache.get(&ha).unwrap().unwrap()[..], &a[..]);
+        cache.put(&hc, &c).unwrap();
+
+        assert_eq!(cache.count(), 2);
+        assert_eq!(cache.size(), 200);
+        assert!(cache.has(&ha));
+        assert!(!cache.has(&hb), "least recently used is evicted");
+        assert!(cache.get(&hb).unwrap().is_none());
+
+        cache.clear();
+        assert_eq!(cache.count(), 0);
+        assert!(!cache.has(&hc));
+    }
 }
@@ -20,4 +20,6 @@ pub struct IncomingChunk {
     pub type_tag: u16,
     pub schema_id: [u8; 32],
     pub payload: Bytes,
+    /// Sent to us alone (`FLAG_TARGETED`) rather than broadcast.
+    pub targeted: bool,
 }
@@ -4,23 +4,45 @@
 //! spawns a subprocess for each one, and sends `task_ack(Running)` then
 //! `task_result` back to the submitting peer.
 //!
+//! At most `max_concurrent_tasks` run at once. Tasks waiting for a slot
+//! stay `Queued` and are started highest `priority` first, oldest first
+//! within a priority.
+//!
 //! Each task runs in its own subdirectory of `work_dir`. After execution,
 //! any files produced in the directory are sent back to the submitter via
-//! the existing file transfer infrastructure.
+//! the existing file transfer infrastructure, tagged with the task id and
+//! capped in total by `max_output_file_bytes`.
+//!
+//! Tasks are sandboxed by `TaskLimits`: a wall-clock timeout (the whole
+//! process group is killed when it fires), rlimits for memory and CPU, a cap
+//! on captured stdout/stderr, and an optional command allowlist.
+//!
+//! While a task runs, complete lines of stdout/stderr are streamed to the
+//! submitter as `task_output` chunks so progress is visible before the
+//! final `task_result`.
+//!
+//! A running task can be cancelled through `ComputeStore::cancel`, which
+//! signals the kill handle registered by `ComputeStore::start_running`.
 
 use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
+use tokio::io::{AsyncRead, AsyncReadExt};
 use tokio::sync::mpsc;
-use tokio::sync::Semaphore;
+use tokio::sync::{Notify, Semaphore};
 
 use crate::chunk_types::OutgoingChunk;
-use crate::compute_store::ComputeStore;
-use crate::compute_types::{msg_types, ComputeEnvelope, TaskAck, TaskResult, TaskStatus};
-use crate::file_transfer::chunk_file;
+use crate::compute_store::{now_ms, ComputeStore};
+use crate::compute_types::{
+    msg_types, short_id, ComputeEnvelope, ComputeOutput, TaskAck, TaskResult, TaskStatus, TaskTimes,
+};
+use crate::file_transfer::chunk_task_output;
 use crate::send_target::SendTarget;
 use crate::trust::{TrustLevel, TrustRegistry};
 use summit_core::config::ComputeSettings;
+use summit_core::crypto::Keypair;
 
 use std::sync::Arc;
 
@@ -30,6 +52,7 @@ pub async fn run(
     settings: ComputeSettings,
     chunk_tx: mpsc::Sender<(SendTarget, OutgoingChunk)>,
     trust: TrustRegistry,
+    keypair: Arc<Keypair>,
 ) {
     let max_tasks = if settings.max_concurrent_tasks == 0 {
         std::thread::available_parallelism()
@@ -39,22 +62,18 @@ pub async fn run(
         settings.max_concurrent_tasks as usize
     };
 
-    let task_timeout = Duration::from_secs(if settings.task_timeout_secs == 0 {
-        300
-    } else {
-        settings.task_timeout_secs
-    });
-
-    let max_memory_bytes = settings.max_memory_bytes;
-    let max_cpu_cores = settings.max_cpu_cores;
+    let limits = Arc::new(TaskLimits::from_settings(&settings));
 
     let semaphore = Arc::new(Semaphore::new(max_tasks));
 
     tracing::info!(
         max_concurrent = max_tasks,
-        timeout_secs = task_timeout.as_secs(),
-        max_memory_bytes,
-        max_cpu_cores,
+        timeout_secs = limits.timeout.as_secs(),
+        max_memory_bytes = limits.max_memory_bytes,
+        max_cpu_cores = limits.max_cpu_cores,
+        max_output_bytes = limits.max_output_bytes,
+        max_output_file_bytes = limits.max_output_file_bytes,
+        allowed_commands = limits.allowed_commands.len(),
         "compute executor started"
     );
 
@@ -63,7 +82,13 @@ pub async fn run(
     loop {
         interval.tick().await;
 
-        let queued = store.queued_remote_tasks();
+        let mut queued = store.queued_remote_tasks();
+        queued.sort_by(|a, b| {
+            b.submit
+                .priority
+                .cmp(&a.submit.priority)
+                .then(a.submitted_at.cmp(&b.submitted_at))
+        });
         for task in queued {
             let task_id = task.submit.task_id.clone();
             let peer_pubkey = task.peer_pubkey;
@@ -73,7 +98,7 @@ pub async fn run(
                 TrustLevel::Trusted => {}
                 level => {
                     tracing::warn!(
-                        task_id = &task_id[..16.min(task_id.len())],
+                        task_id = short_id(&task_id),
                         peer = hex::encode(&peer_pubkey[..8]),
                         ?level,
                         "rejecting compute task from non-trusted peer"
@@ -84,60 +109,95 @@ pub async fn run(
                 }
             }
 
-            // Mark running immediately so the next poll doesn't re-pick it.
-            store.update_status(&task_id, TaskStatus::Running);
-
-            let permit = match semaphore.clone().acquire_owned().await {
-                Ok(p) => p,
-                Err(_) => break, // semaphore closed
+            // No free slot: this and every lower-priority task stay Queued
+            // until a running task finishes.
+            let Ok(permit) = semaphore.clone().try_acquire_owned() else {
+                break;
             };
 
+            // Mark running before spawning so the next poll doesn't re-pick it.
+            // Registering the kill handle now means a cancel that lands before
+            // the process starts still reaches the task.
+            let kill = store.start_running(&task_id);
+
             let store = store.clone();
             let chunk_tx = chunk_tx.clone();
             let work_dir = settings.work_dir.clone();
+            let limits = limits.clone();
+            let keypair = keypair.clone();
 
             tokio::spawn(async move {
                 // Each task gets its own subdirectory for isolation.
-                let task_dir = work_dir.join(&task_id[..16.min(task_id.len())]);
+                let task_dir = work_dir.join(short_id(&task_id));
+
+                // Cancelled before the process started — never start it.
+                if store
+                    .get_task(&task_id)
+                    .is_some_and(|t| t.status == TaskStatus::Cancelled)
+                {
+                    store.finish_running(&task_id);
+                    drop(permit);
+                    return;
+                }
 
                 // Tell the submitter we're running.
                 send_ack(&chunk_tx, &peer_pubkey, &task_id, TaskStatus::Running).await;
 
+                let forwarder = OutputForwarder::new(chunk_tx.clone(), peer_pubkey, &task_id);
                 let start = Instant::now();
-                let result_value = match tokio::time::timeout(
-                    task_timeout,
-                    execute_task(
-                        &task.submit.payload,
-                        &task_dir,
-                        max_memory_bytes,
-                        max_cpu_cores,
-                    ),
+                let result_value = execute_task(
+                    &task.submit.payload,
+                    &task_dir,
+                    &limits,
+                    &kill,
+                    Some(&forwarder),
                 )
-                .await
-                {
-                    Ok(r) => r,
-                    Err(_) => Err(format!("task timed out after {}s", task_timeout.as_secs())),
-                };
+                .await;
                 let elapsed_ms = start.elapsed().as_millis() as u64;
+                store.finish_running(&task_id);
 
                 let (status, mut result_json) = match result_value {
                     Ok(output) => (TaskStatus::Completed, output),
+                    Err(err) if err == CANCELLED => {
+                        (TaskStatus::Cancelled, serde_json::json!({ "error": err }))
+                    }
                     Err(err) => (TaskStatus::Failed, serde_json::json!({ "error": err })),
                 };
 
                 // Collect and send back any output files.
-                let output_files = collect_output_files(&task_dir).await;
+                let output_files = if status == TaskStatus::Cancelled {
+                    Vec::new()
+                } else {
+                    collect_output_files(&task_dir).await
+                };
                 if !output_files.is_empty() {
-                    let file_names: Vec<String> = output_files
-                        .iter()
-                        .filter_map(|p| p.file_name().and_then(|n| n.to_str()))
-                        .map(String::from)
-                        .collect();
-
+                    let mut file_names = Vec::new();
+                    let mut skipped = Vec::new();
+                    let mut total_bytes = 0u64;
                     let mut sent = 0usize;
                     for path in &output_files {
-                        match send_output_file(&chunk_tx, &peer_pubkey, path).await {
-                            Ok(n) => sent += n,
+                        let name = path
+                            .file_name()
+                            .and_then(|n| n.to_str())
+                            .unwrap_or("unknown")
+                            .to_string();
+                        let len = tokio::fs::metadata(path).await.map_or(0, |m| m.len());
+                        if total_bytes + len > limits.max_output_file_bytes {
+                            tracing::warn!(
+                                task_id = short_id(&task_id),
+                                file = %name,
+                                bytes = len,
+                                "output file exceeds max_output_file_bytes, not sent"
+                            );
+                            skipped.push(name);
+                            continue;
+                        }
+                        match send_output_file(&chunk_tx, &peer_pubkey, &task_id, path).await {
+                            Ok(n) => {
+                                sent += n;
+                                total_bytes += len;
+                                file_names.push(name);
+                            }
                             Err(e) => {
                                 tracing::warn!(
                                     path = %path.display(),
@@ -152,37 +212,56 @@ pub async fn run(
                     if let Some(obj) = result_json.as_object_mut() {
                         obj.insert("output_files".to_string(), serde_json::json!(file_names));
                         obj.insert("output_chunks_sent".to_string(), serde_json::json!(sent));
+                        if !skipped.is_empty() {
+                            obj.insert(
+                                "output_files_skipped".to_string(),
+                                serde_json::json!(skipped),
+                            );
+                        }
                     }
 
                     tracing::info!(
-                        task_id = &task_id[..16.min(task_id.len())],
+                        task_id = short_id(&task_id),
                         files = file_names.len(),
                         chunks = sent,
                         "output files sent to submitter"
                     );
                 }
 
-                // Update local store.
-                if status == TaskStatus::Completed {
-                    store.store_result(TaskResult {
-                        task_id: task_id.clone(),
-                        result: result_json.clone(),
-                        elapsed_ms,
+                // Update local store. The lifecycle timestamps travel with
+                // the result so the submitter can split queue from run time.
+                let times = store
+                    .get_task(&task_id)
+                    .and_then(|t| t.started_at)
+                    .map(|started_at| TaskTimes {
+                        queued_at: task.submitted_at,
+                        started_at,
+                        completed_at: now_ms(),
                     });
-                } else {
-                    store.update_status(&task_id, status);
-                }
-
-                // Send result back to submitter.
                 let tr = TaskResult {
                     task_id: task_id.clone(),
                     result: result_json,
                     elapsed_ms,
-                };
-                send_result(&chunk_tx, &peer_pubkey, &tr).await;
+                    status,
+                    digest: None,
+                    signature: None,
+                    times,
+                }
+                .signed(&task.submit.payload, &keypair);
+                if status == TaskStatus::Completed {
+                    store.store_result(tr.clone());
+                } else {
+                    store.store_failure(tr.clone(), status);
+                }
+
+                // Send result back to submitter. A cancelled task has already
+                // been acknowledged by whoever issued the cancel.
+                if status != TaskStatus::Cancelled {
+                    send_result(&chunk_tx, &peer_pubkey, &tr).await;
+                }
 
                 tracing::info!(
-                    task_id = &task_id[..16.min(task_id.len())],
+                    task_id = short_id(&task_id),
                     ?status,
                     elapsed_ms,
                     "compute task finished"
@@ -203,6 +282,59 @@ pub async fn run(
     }
 }
 
+/// Error string reported for a task killed by `ComputeStore::cancel`.
+const CANCELLED: &str = "cancelled";
+
+/// Per-task sandbox limits, resolved from `ComputeSettings`.
+#[derive(Debug, Clone)]
+struct TaskLimits {
+    /// Wall-clock budget. The task's process group is killed when it expires.
+    timeout: Duration,
+    max_memory_bytes: u64,
+    max_cpu_cores: u32,
+    /// Max bytes kept per output stream. `usize::MAX` = unlimited.
+    max_output_bytes: usize,
+    /// Max total bytes of output files sent back. `u64::MAX` = unlimited.
+    max_output_file_bytes: u64,
+    /// Permitted program names. Empty = allow any.
+    allowed_commands: Vec<String>,
+}
+
+impl TaskLimits {
+    fn from_settings(settings: &ComputeSettings) -> Self {
+        Self {
+            timeout: Duration::from_secs(if settings.task_timeout_secs == 0 {
+                300
+            } else {
+                settings.task_timeout_secs
+            }),
+            max_memory_bytes: settings.max_memory_bytes,
+            max_cpu_cores: settings.max_cpu_cores,
+            max_output_bytes: if settings.max_output_bytes == 0 {
+                usize::MAX
+            } else {
+                settings.max_output_bytes as usize
+            },
+            max_output_file_bytes: if settings.max_output_file_bytes == 0 {
+                u64::MAX
+            } else {
+                settings.max_output_file_bytes
+            },
+            allowed_commands: settings.allowed_commands.clone(),
+        }
+    }
+
+    /// Check `program` against the allowlist. Matching is exact, so an
+    /// allowed `echo` does not also permit `/tmp/echo`.
+    fn check_allowed(&self, program: &str) -> Result<(), String> {
+        if self.allowed_commands.is_empty() || self.allowed_commands.iter().any(|c| c == program) {
+            Ok(())
+        } else {
+            Err(format!("command '{program}' is not in the allowlist"))
+        }
+    }
+}
+
 /// Apply resource limits (RLIMIT_AS for memory, RLIMIT_CPU for CPU time)
 /// via `pre_exec`. Runs after fork, before exec — only affects the child.
 fn apply_resource_limits(
@@ -245,32 +377,38 @@ fn apply_resource_limits(
     }
 }
 
-/// Execute a task payload.
+/// Execute a task payload under `limits`.
 ///
 /// Supported formats:
 ///   `{"run": "hostnamectl > out.txt"}`  — shell command (via `sh -c`)
 ///   `{"cmd": "echo", "args": ["hi"]}`  — direct exec (no shell)
+///
+/// Output is streamed through `forward` as it is produced, if given.
+///
+/// Returns `Err("timeout")` if the task outlives `limits.timeout`, or
+/// `Err("cancelled")` if `kill` is notified first.
 async fn execute_task(
     payload: &serde_json::Value,
     task_dir: &Path,
-    max_memory_bytes: u64,
-    max_cpu_cores: u32,
+    limits: &TaskLimits,
+    kill: &Notify,
+    forward: Option<&OutputForwarder>,
 ) -> Result<serde_json::Value, String> {
     // Ensure task directory exists.
     tokio::fs::create_dir_all(task_dir)
         .await
         .map_err(|e| format!("failed to create task dir: {e}"))?;
 
-    let output = if let Some(run) = payload.get("run").and_then(|v| v.as_str()) {
-        // Shell mode: pipes, redirections, globs all work.
+    let (mut cmd, spawn_what) = if let Some(run) = payload.get("run").and_then(|v| v.as_str()) {
+        // Shell mode: pipes, redirections, globs all work. The shell can run
+        // anything, so an allowlist must name `sh` explicitly to permit it.
+        limits.check_allowed("sh")?;
         let mut cmd = tokio::process::Command::new("sh");
-        cmd.args(["-c", run]).current_dir(task_dir);
-        apply_resource_limits(&mut cmd, max_memory_bytes, max_cpu_cores);
-        cmd.output()
-            .await
-            .map_err(|e| format!("failed to spawn shell: {e}"))?
+        cmd.args(["-c", run]);
+        (cmd, "shell".to_string())
     } else if let Some(cmd_str) = payload.get("cmd").and_then(|v| v.as_str()) {
         // Direct exec mode: no shell interpretation.
+        limits.check_allowed(cmd_str)?;
         let args: Vec<&str> = payload
             .get("args")
             .and_then(|v| v.as_array())
@@ -278,26 +416,69 @@ async fn execute_task(
             .unwrap_or_default();
 
         let mut cmd = tokio::process::Command::new(cmd_str);
-        cmd.args(&args).current_dir(task_dir);
-        apply_resource_limits(&mut cmd, max_memory_bytes, max_cpu_cores);
-        cmd.output()
-            .await
-            .map_err(|e| format!("failed to spawn '{}': {e}", cmd_str))?
+        cmd.args(&args);
+        (cmd, format!("'{cmd_str}'"))
     } else {
         return Err("payload must contain \"run\" (shell string) or \"cmd\" (direct exec)".into());
     };
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    // Own process group so a timeout can kill everything the task forked.
+    cmd.current_dir(task_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true);
+    apply_resource_limits(&mut cmd, limits.max_memory_bytes, limits.max_cpu_cores);
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("failed to spawn {spawn_what}: {e}"))?;
+    let pid = child.id();
+    let stdout_pipe = child.stdout.take();
+    let stderr_pipe = child.stderr.take();
+
+    let run = async {
+        tokio::join!(
+            read_stream(stdout_pipe, limits.max_output_bytes, |data| {
+                if let Some(f) = forward {
+                    f.forward("stdout", data);
+                }
+            }),
+            read_stream(stderr_pipe, limits.max_output_bytes, |data| {
+                if let Some(f) = forward {
+                    f.forward("stderr", data);
+                }
+            }),
+            child.wait(),
+        )
+    };
 
-    if output.status.success() {
+    let outcome = tokio::select! {
+        r = tokio::time::timeout(limits.timeout, run) => r.map_err(|_| "timeout"),
+        _ = kill.notified() => Err(CANCELLED),
+    };
+    let ((stdout, stdout_trunc), (stderr, stderr_trunc), status) = match outcome {
+        Ok(r) => r,
+        Err(reason) => {
+            kill_process_group(pid);
+            return Err(reason.into());
+        }
+    };
+    let status = status.map_err(|e| format!("failed to wait for task: {e}"))?;
+
+    let stdout = String::from_utf8_lossy(&stdout).to_string();
+    let stderr = String::from_utf8_lossy(&stderr).to_string();
+
+    if status.success() {
         Ok(serde_json::json!({
             "exit_code": 0,
             "stdout": stdout,
             "stderr": stderr,
+            "truncated": stdout_trunc || stderr_trunc,
         }))
     } else {
-        let code = output.status.code().unwrap_or(-1);
+        let code = status.code().unwrap_or(-1);
         Err(format!(
             "exit code {}: {}",
             code,
@@ -306,6 +487,114 @@ async fn execute_task(
     }
 }
 
+/// Pending output without a newline is flushed once it reaches this size,
+/// keeping each streamed chunk well under `MAX_PAYLOAD`.
+const STREAM_FLUSH_BYTES: usize = 16 * 1024;
+
+/// Drain `reader` to EOF, keeping at most `max` bytes.
+///
+/// Kept output is handed to `on_data` as complete lines become available
+/// (or in `STREAM_FLUSH_BYTES` pieces for very long lines), so streaming is
+/// bounded by the same cap as the final capture.
+/// Returns the kept bytes and whether anything was discarded.
+async fn read_stream<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    max: usize,
+    mut on_data: impl FnMut(&[u8]),
+) -> (Vec<u8>, bool) {
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut streamed = 0usize;
+    let Some(mut reader) = reader else {
+        return (kept, truncated);
+    };
+    let mut buf = [0u8; 8192];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let room = max.saturating_sub(kept.len());
+                if n > room {
+                    truncated = true;
+                }
+                kept.extend_from_slice(&buf[..n.min(room)]);
+
+                let pending = &kept[streamed..];
+                let end = if pending.len() >= STREAM_FLUSH_BYTES {
+                    Some(pending.len())
+                } else {
+                    pending.iter().rposition(|&b| b == b'\n').map(|i| i + 1)
+                };
+                if let Some(end) = end {
+                    on_data(&pending[..end]);
+                    streamed += end;
+                }
+            }
+        }
+    }
+    if streamed < kept.len() {
+        on_data(&kept[streamed..]);
+    }
+    (kept, truncated)
+}
+
+/// Streams task output to the submitter as `task_output` chunks.
+///
+/// Best effort: if the send queue is full the piece is dropped — the final
+/// `task_result` still carries the complete (capped) capture.
+struct OutputForwarder {
+    chunk_tx: mpsc::Sender<(SendTarget, OutgoingChunk)>,
+    peer_pubkey: [u8; 32],
+    task_id: String,
+    seq: AtomicU64,
+}
+
+impl OutputForwarder {
+    fn new(
+        chunk_tx: mpsc::Sender<(SendTarget, OutgoingChunk)>,
+        peer_pubkey: [u8; 32],
+        task_id: &str,
+    ) -> Self {
+        Self {
+            chunk_tx,
+            peer_pubkey,
+            task_id: task_id.to_string(),
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    fn forward(&self, stream: &str, data: &[u8]) {
+        let output = ComputeOutput {
+            task_id: self.task_id.clone(),
+            seq: self.seq.fetch_add(1, Ordering::Relaxed),
+            stream: stream.to_string(),
+            data: String::from_utf8_lossy(data).into_owned(),
+        };
+        let Some(chunk) = compute_chunk(msg_types::TASK_OUTPUT, &output) else {
+            return;
+        };
+        let target = SendTarget::Peer {
+            public_key: self.peer_pubkey,
+        };
+        if self.chunk_tx.try_send((target, chunk)).is_err() {
+            tracing::debug!(
+                task_id = short_id(&self.task_id),
+                "send queue full, dropping streamed output"
+            );
+        }
+    }
+}
+
+/// SIGKILL the process group led by `pid` (the task and anything it forked).
+fn kill_process_group(pid: Option<u32>) {
+    if let Some(pid) = pid {
+        // Safety: plain syscall; a stale pgid just yields ESRCH.
+        unsafe {
+            libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        }
+    }
+}
+
 /// Walk the task directory and collect all regular files.
 async fn collect_output_files(task_dir: &Path) -> Vec<PathBuf> {
     let mut files = Vec::new();
@@ -331,14 +620,15 @@ async fn collect_output_files(task_dir: &Path) -> Vec<PathBuf> {
     files
 }
 
-/// Chunk a file and enqueue the chunks for sending to the submitter.
-/// Returns the number of chunks enqueued.
+/// Chunk an output file of `task_id` and enqueue the chunks for sending to
+/// the submitter. Returns the number of chunks enqueued.
 async fn send_output_file(
     chunk_tx: &mpsc::Sender<(SendTarget, OutgoingChunk)>,
     peer_pubkey: &[u8; 32],
+    task_id: &str,
     path: &Path,
 ) -> Result<usize, String> {
-    let chunks = chunk_file(path).map_err(|e| format!("{e}"))?;
+    let chunks = chunk_task_output(path, task_id).map_err(|e| format!("{e}"))?;
     let count = chunks.len();
     let target = SendTarget::Peer {
         public_key: *peer_pubkey,
@@ -366,22 +656,8 @@ async fn send_ack(
         task_id: task_id.to_string(),
         status,
     };
-    let envelope = ComputeEnvelope {
-        msg_type: msg_types::TASK_ACK.to_string(),
-        payload: match serde_json::to_value(&ack) {
-            Ok(v) => v,
-            Err(_) => return,
-        },
-    };
-    let raw = match serde_json::to_vec(&envelope) {
-        Ok(v) => v,
-        Err(_) => return,
-    };
-    let chunk = OutgoingChunk {
-        type_tag: 0,
-        schema_id: summit_core::wire::compute_hash(),
-        payload: bytes::Bytes::from(raw),
-        priority_flags: 0x02,
+    let Some(chunk) = compute_chunk(msg_types::TASK_ACK, &ack) else {
+        return;
     };
     let _ = chunk_tx
         .send((
@@ -398,22 +674,8 @@ async fn send_result(
     peer_pubkey: &[u8; 32],
     result: &TaskResult,
 ) {
-    let envelope = ComputeEnvelope {
-        msg_type: msg_types::TASK_RESULT.to_string(),
-        payload: match serde_json::to_value(result) {
-            Ok(v) => v,
-            Err(_) => return,
-        },
-    };
-    let raw = match serde_json::to_vec(&envelope) {
-        Ok(v) => v,
-        Err(_) => return,
-    };
-    let chunk = OutgoingChunk {
-        type_tag: 0,
-        schema_id: summit_core::wire::compute_hash(),
-        payload: bytes::Bytes::from(raw),
-        priority_flags: 0x02,
+    let Some(chunk) = compute_chunk(msg_types::TASK_RESULT, result) else {
+        return;
     };
     let _ = chunk_tx
         .send((
@@ -425,6 +687,21 @@ async fn send_result(
         .await;
 }
 
+/// Wrap a compute message in a `ComputeEnvelope` chunk.
+fn compute_chunk<T: serde::Serialize>(msg_type: &str, payload: &T) -> Option<OutgoingChunk> {
+    let envelope = ComputeEnvelope {
+        msg_type: msg_type.to_string(),
+        payload: serde_json::to_value(payload).ok()?,
+    };
+    let raw = serde_json::to_vec(&envelope).ok()?;
+    Some(OutgoingChunk {
+        type_tag: 0,
+        schema_id: summit_core::wire::compute_hash(),
+        payload: bytes::Bytes::from(raw),
+        priority_flags: 0x02,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -437,13 +714,22 @@ mod tests {
         std::env::temp_dir().join(format!("summit-exec-test-{}-{}", std::process::id(), id))
     }
 
+    fn limits() -> TaskLimits {
+        TaskLimits::from_settings(&ComputeSettings {
+            task_timeout_secs: 60,
+            ..ComputeSettings::default()
+        })
+    }
+
     // ── execute_task tests ───────────────────────────────────────────────
 
     #[tokio::test]
     async fn execute_task_shell_echo() {
         let dir = temp_dir();
         let payload = serde_json::json!({ "run": "echo hello" });
-        let result = execute_task(&payload, &dir, 0, 0).await.unwrap();
+        let result = execute_task(&payload, &dir, &limits(), &Notify::new(), None)
+            .await
+            .unwrap();
         assert_eq!(result["exit_code"], 0);
         assert!(result["stdout"].as_str().unwrap().contains("hello"));
         let _ = tokio::fs::remove_dir_all(&dir).await;
@@ -453,7 +739,9 @@ mod tests {
     async fn execute_task_direct_exec() {
         let dir = temp_dir();
         let payload = serde_json::json!({ "cmd": "echo", "args": ["hi"] });
-        let result = execute_task(&payload, &dir, 0, 0).await.unwrap();
+        let result = execute_task(&payload, &dir, &limits(), &Notify::new(), None)
+            .await
+            .unwrap();
         assert_eq!(result["exit_code"], 0);
         assert!(result["stdout"].as_str().unwrap().contains("hi"));
         let _ = tokio::fs::remove_dir_all(&dir).await;
@@ -463,7 +751,9 @@ mod tests {
     async fn execute_task_invalid_payload() {
         let dir = temp_dir();
         let payload = serde_json::json!({ "nope": true });
-        let err = execute_task(&payload, &dir, 0, 0).await.unwrap_err();
+        let err = execute_task(&payload, &dir, &limits(), &Notify::new(), None)
+            .await
+            .unwrap_err();
         assert!(err.contains("run"));
         assert!(err.contains("cmd"));
         let _ = tokio::fs::remove_dir_all(&dir).await;
@@ -473,7 +763,9 @@ mod tests {
     async fn execute_task_failing_command() {
         let dir = temp_dir();
         let payload = serde_json::json!({ "run": "false" });
-        let err = execute_task(&payload, &dir, 0, 0).await.unwrap_err();
+        let err = execute_task(&payload, &dir, &limits(), &Notify::new(), None)
+            .await
+            .unwrap_err();
         assert!(err.contains("exit code"));
         let _ = tokio::fs::remove_dir_all(&dir).await;
     }
@@ -482,12 +774,98 @@ mod tests {
     async fn execute_task_creates_output_file() {
         let dir = temp_dir();
         let payload = serde_json::json!({ "run": "echo data > out.txt" });
-        let result = execute_task(&payload, &dir, 0, 0).await.unwrap();
+        let result = execute_task(&payload, &dir, &limits(), &Notify::new(), None)
+            .await
+            .unwrap();
         assert_eq!(result["exit_code"], 0);
         assert!(dir.join("out.txt").exists());
         let _ = tokio::fs::remove_dir_all(&dir).await;
     }
 
+    #[tokio::test]
+    async fn execute_task_truncates_output() {
+        let dir = temp_dir();
+        let mut limits = limits();
+        limits.max_output_bytes = 16;
+        let payload = serde_json::json!({ "run": "head -c 100000 /dev/zero | tr '\\0' x" });
+        let result = execute_task(&payload, &dir, &limits, &Notify::new(), None)
+            .await
+            .unwrap();
+        assert_eq!(result["stdout"].as_str().unwrap().len(), 16);
+        assert_eq!(result["truncated"], true);
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn execute_task_streams_output_lines() {
+        let dir = temp_dir();
+        let (chunk_tx, mut chunk_rx) = mpsc::channel(16);
+        let peer = [0xDDu8; 32];
+        let forwarder = OutputForwarder::new(chunk_tx, peer, "stream-task-0001");
+
+        let payload = serde_json::json!({ "run": "echo one; sleep 0.2; echo two" });
+        let result = execute_task(&payload, &dir, &limits(), &Notify::new(), Some(&forwarder))
+            .await
+            .unwrap();
+        assert!(result["stdout"].as_str().unwrap().contains("two"));
+
+        let mut streamed = Vec::new();
+        while let Ok((target, chunk)) = chunk_rx.try_recv() {
+            assert!(matches!(target, SendTarget::Peer { public_key } if public_key == peer));
+            let env: ComputeEnvelope = serde_json::from_slice(&chunk.payload).unwrap();
+            assert_eq!(env.msg_type, msg_types::TASK_OUTPUT);
+            let out: ComputeOutput = serde_json::from_value(env.payload).unwrap();
+            assert_eq!(out.stream, "stdout");
+            streamed.push((out.seq, out.data));
+        }
+        assert_eq!(
+            streamed,
+            [(0, "one\n".to_string()), (1, "two\n".to_string())]
+        );
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn execute_task_rejects_command_outside_allowlist() {
+        let dir = temp_dir();
+        let mut limits = limits();
+        limits.allowed_commands = vec!["echo".to_string()];
+
+        let ok = serde_json::json!({ "cmd": "echo", "args": ["hi"] });
+        assert!(execute_task(&ok, &dir, &limits, &Notify::new(), None)
+            .await
+            .is_ok());
+
+        let denied = serde_json::json!({ "cmd": "uname" });
+        let err = execute_task(&denied, &dir, &limits, &Notify::new(), None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("allowlist"));
+
+        // Shell mode needs `sh` on the list.
+        let shell = serde_json::json!({ "run": "echo hi" });
+        let err = execute_task(&shell, &dir, &limits, &Notify::new(), None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("'sh'"));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn execute_task_times_out() {
+        let dir = temp_dir();
+        let mut limits = limits();
+        limits.timeout = Duration::from_millis(200);
+        let payload = serde_json::json!({ "cmd": "sleep", "args": ["30"] });
+        let start = Instant::now();
+        let err = execute_task(&payload, &dir, &limits, &Notify::new(), None)
+            .await
+            .unwrap_err();
+        assert_eq!(err, "timeout");
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
     // ── collect_output_files tests ───────────────────────────────────────
 
     #[tokio::test]
@@ -534,6 +912,7 @@ mod tests {
             sender: hex::encode(peer),
             timestamp: 100,
             payload: serde_json::json!({ "run": "echo no" }),
+            priority: 0,
         };
         store.submit(peer, submit);
 
@@ -565,4 +944,188 @@ mod tests {
             _ => panic!("expected Peer target"),
         }
     }
+
+    // ── executor loop tests ──────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn sleeping_task_times_out_and_fails() {
+        let store = ComputeStore::new();
+        let trust = TrustRegistry::new();
+        let (chunk_tx, _chunk_rx) = mpsc::channel(64);
+
+        let peer = [0xBBu8; 32];
+        trust.trust(peer);
+        store.submit(
+            peer,
+            crate::compute_types::TaskSubmit {
+                task_id: "sleepy-task-0001".to_string(),
+                sender: hex::encode(peer),
+                timestamp: 100,
+                payload: serde_json::json!({ "run": "sleep 30" }),
+                priority: 0,
+            },
+        );
+
+        let settings = ComputeSettings {
+            work_dir: temp_dir(),
+            max_concurrent_tasks: 1,
+            task_timeout_secs: 2,
+            ..ComputeSettings::default()
+        };
+        let keypair = Arc::new(Keypair::generate());
+        let executor = tokio::spawn(run(
+            store.clone(),
+            settings,
+            chunk_tx,
+            trust,
+            keypair.clone(),
+        ));
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let task = loop {
+            let task = store.get_task("sleepy-task-0001").unwrap();
+            if task.status == TaskStatus::Failed || Instant::now() > deadline {
+                break task;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        };
+        executor.abort();
+
+        assert_eq!(task.status, TaskStatus::Failed);
+        let result = task.result.unwrap();
+        assert_eq!(result.result["error"], "timeout");
+        assert!(result.matches(&task.submit.payload, &keypair.public));
+    }
+
+    #[tokio::test]
+    async fn queued_tasks_serialize_by_priority() {
+        let store = ComputeStore::new();
+        let trust = TrustRegistry::new();
+        let (chunk_tx, _chunk_rx) = mpsc::channel(256);
+
+        let peer = [0xDDu8; 32];
+        trust.trust(peer);
+        let tasks = [
+            ("pool-low-0001", 0),
+            ("pool-high-001", 5),
+            ("pool-mid-0001", 1),
+        ];
+        for (task_id, priority) in tasks {
+            store.submit(
+                peer,
+                crate::compute_types::TaskSubmit {
+                    task_id: task_id.to_string(),
+                    sender: hex::encode(peer),
+                    timestamp: 100,
+                    payload: serde_json::json!({ "run": "sleep 0.2" }),
+                    priority,
+                },
+            );
+        }
+
+        let settings = ComputeSettings {
+            work_dir: temp_dir(),
+            max_concurrent_tasks: 1,
+            task_timeout_secs: 10,
+            ..ComputeSettings::default()
+        };
+        let executor = tokio::spawn(run(
+            store.clone(),
+            settings,
+            chunk_tx,
+            trust,
+            Arc::new(Keypair::generate()),
+        ));
+
+        // Record the order tasks start in; never more than one at a time.
+        let mut started = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(15);
+        while started.len() < tasks.len() || store.queue_depth() > 0 {
+            assert!(Instant::now() < deadline, "tasks never finished");
+            let running: Vec<String> = store
+                .all_tasks()
+                .into_iter()
+                .filter(|t| t.status == TaskStatus::Running)
+                .map(|t| t.submit.task_id)
+                .collect();
+            assert!(running.len() <= 1, "more tasks running than slots");
+            for id in running {
+                if !started.contains(&id) {
+                    started.push(id);
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while store
+            .all_tasks()
+            .iter()
+            .any(|t| t.status != TaskStatus::Completed)
+        {
+            assert!(Instant::now() < deadline, "a task did not complete");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        executor.abort();
+
+        assert_eq!(started, ["pool-high-001", "pool-mid-0001", "pool-low-0001"]);
+    }
+
+    #[tokio::test]
+    async fn cancelled_task_is_killed_promptly() {
+        let store = ComputeStore::new();
+        let trust = TrustRegistry::new();
+        let (chunk_tx, _chunk_rx) = mpsc::channel(64);
+
+        let peer = [0xCCu8; 32];
+        trust.trust(peer);
+        store.submit(
+            peer,
+            crate::compute_types::TaskSubmit {
+                task_id: "cancel-task-0001".to_string(),
+                sender: hex::encode(peer),
+                timestamp: 100,
+                payload: serde_json::json!({ "cmd": "sleep", "args": ["60"] }),
+                priority: 0,
+            },
+        );
+
+        let settings = ComputeSettings {
+            work_dir: temp_dir(),
+            max_concurrent_tasks: 1,
+            task_timeout_secs: 120,
+            ..ComputeSettings::default()
+        };
+        let executor = tokio::spawn(run(
+            store.clone(),
+            settings,
+            chunk_tx,
+            trust,
+            Arc::new(Keypair::generate()),
+        ));
+
+        // Wait for the executor to pick the task up.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while store.get_task("cancel-task-0001").unwrap().status != TaskStatus::Running {
+            assert!(Instant::now() < deadline, "task never started");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert!(store.cancel("cancel-task-0001"));
+        let cancelled_at = Instant::now();
+
+        // The executor records the kill once the child has actually exited.
+        let deadline = cancelled_at + Duration::from_secs(2);
+        let task = loop {
+            let task = store.get_task("cancel-task-0001").unwrap();
+            if task.result.is_some() || Instant::now() > deadline {
+                break task;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        };
+        executor.abort();
+
+        assert_eq!(task.status, TaskStatus::Cancelled);
+        assert_eq!(task.result.unwrap().result["error"], "cancelled");
+    }
 }
@@ -2,10 +2,12 @@
 //!
 //! When a `task_submit` arrives the service stores it, then sends a
 //! `task_ack` back to the submitter so they can see the task was received.
+//! On the submitter, acks, streamed output and the final `task_result` from
+//! the worker update the tracked task.
 
 use crate::chunk_types::OutgoingChunk;
 use crate::compute_store::ComputeStore;
-use crate::compute_types::{msg_types, ComputeEnvelope, TaskAck, TaskStatus, TaskSubmit};
+use crate::compute_types::{msg_types, short_id, ComputeEnvelope, TaskAck, TaskStatus, TaskSubmit};
 use crate::send_target::SendTarget;
 use crate::service::ChunkService;
 use summit_core::config::ComputeSettings;
@@ -79,6 +81,18 @@ impl ChunkService for ComputeService {
         Contract::Bulk
     }
 
+    /// A task_submit queues work to run here.
+    fn executes(&self) -> bool {
+        true
+    }
+
+    /// Only a task_submit is new work; acks, output and results answer
+    /// tasks already submitted.
+    fn opens_work(&self, _header: &ChunkHeader, payload: &[u8]) -> bool {
+        serde_json::from_slice::<ComputeEnvelope>(payload)
+            .map_or(true, |envelope| envelope.msg_type == msg_types::TASK_SUBMIT)
+    }
+
     fn on_activate(&self, peer_pubkey: &[u8; 32]) {
         tracing::info!(
             peer = hex::encode(&peer_pubkey[..8]),
@@ -107,7 +121,7 @@ impl ChunkService for ComputeService {
                 let submit: TaskSubmit = serde_json::from_value(envelope.payload)
                     .map_err(|e| anyhow::anyhow!("invalid task_submit payload: {e}"))?;
                 tracing::info!(
-                    task_id = &submit.task_id[..16.min(submit.task_id.len())],
+                    task_id = short_id(&submit.task_id),
                     peer = hex::encode(&peer_pubkey[..8]),
                     "compute task_submit received"
                 );
@@ -119,23 +133,79 @@ impl ChunkService for ComputeService {
             msg_types::TASK_ACK => {
                 let ack: TaskAck = serde_json::from_value(envelope.payload)
                     .map_err(|e| anyhow::anyhow!("invalid task_ack payload: {e}"))?;
-                tracing::info!(
-                    task_id = &ack.task_id[..16.min(ack.task_id.len())],
-                    status = ?ack.status,
-                    "compute task_ack received"
-                );
-                self.store.ack(&ack.task_id, ack.status);
+                // Only accept acks for tasks we submitted to this peer.
+                match self.store.get_task(&ack.task_id) {
+                    Some(task) if task.local && task.peer_pubkey == *peer_pubkey => {
+                        tracing::info!(
+                            task_id = short_id(&ack.task_id),
+                            status = ?ack.status,
+                            "compute task_ack received"
+                        );
+                        self.store.ack(&ack.task_id, ack.status);
+                    }
+                    _ => {
+                        tracing::debug!(
+                            task_id = short_id(&ack.task_id),
+                            "ignoring task_ack for unknown task"
+                        );
+                    }
+                }
+            }
+            msg_types::TASK_OUTPUT => {
+                let output: crate::compute_types::ComputeOutput =
+                    serde_json::from_value(envelope.payload)
+                        .map_err(|e| anyhow::anyhow!("invalid task_output payload: {e}"))?;
+                // Only accept output for tasks we submitted to this peer.
+                match self.store.get_task(&output.task_id) {
+                    Some(task) if task.local && task.peer_pubkey == *peer_pubkey => {
+                        self.store.append_output(output);
+                    }
+                    _ => {
+                        tracing::debug!(
+                            task_id = short_id(&output.task_id),
+                            "ignoring task_output for unknown task"
+                        );
+                    }
+                }
             }
             msg_types::TASK_RESULT => {
                 let result: crate::compute_types::TaskResult =
                     serde_json::from_value(envelope.payload)
                         .map_err(|e| anyhow::anyhow!("invalid task_result payload: {e}"))?;
-                tracing::info!(
-                    task_id = &result.task_id[..16.min(result.task_id.len())],
-                    elapsed_ms = result.elapsed_ms,
-                    "compute task_result received"
-                );
-                self.store.store_result(result);
+                // Only accept results for tasks we submitted to this peer,
+                // that answer the payload we submitted and that the peer
+                // signed.
+                match self.store.get_task(&result.task_id) {
+                    Some(task)
+                        if task.local
+                            && task.peer_pubkey == *peer_pubkey
+                            && !result.matches(&task.submit.payload, peer_pubkey) =>
+                    {
+                        tracing::warn!(
+                            task_id = short_id(&result.task_id),
+                            peer = hex::encode(&peer_pubkey[..8]),
+                            "ignoring task_result without a valid signed digest of the submission"
+                        );
+                    }
+                    Some(task) if task.local && task.peer_pubkey == *peer_pubkey => {
+                        tracing::info!(
+                            task_id = short_id(&result.task_id),
+                            status = ?result.status,
+                            elapsed_ms = result.elapsed_ms,
+                            "compute task_result received"
+                        );
+                        match result.status {
+                            TaskStatus::Completed => self.store.store_result(result),
+                            status => self.store.store_failure(result, status),
+                        }
+                    }
+                    _ => {
+                        tracing::debug!(
+                            task_id = short_id(&result.task_id),
+                            "ignoring task_result for unknown task"
+                        );
+                    }
+                }
             }
             msg_types::TASK_CANCEL => {
                 let task_id = envelope
@@ -143,11 +213,21 @@ impl ChunkService for ComputeService {
                     .get("task_id")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| anyhow::anyhow!("task_cancel missing task_id"))?;
-                tracing::info!(
-                    task_id = &task_id[..16.min(task_id.len())],
-                    "compute task_cancel received"
-                );
-                self.store.update_status(task_id, TaskStatus::Cancelled);
+                tracing::info!(task_id = short_id(task_id), "compute task_cancel received");
+                // Only the peer the task belongs to may cancel it.
+                match self.store.get_task(task_id) {
+                    Some(task) if task.peer_pubkey == *peer_pubkey => {
+                        self.store.cancel(task_id);
+                    }
+                    Some(_) => {
+                        tracing::warn!(
+                            task_id = short_id(task_id),
+                            peer = hex::encode(&peer_pubkey[..8]),
+                            "ignoring task_cancel from a peer that does not own the task"
+                        );
+                    }
+                    None => {}
+                }
             }
             other => {
                 tracing::warn!(msg_type = other, "compute: unknown msg_type, ignoring");
@@ -162,6 +242,7 @@ impl ChunkService for ComputeService {
 mod tests {
     use super::*;
     use crate::compute_types::{ComputeEnvelope, TaskResult, TaskSubmit};
+    use summit_core::crypto::Keypair;
 
     fn make_service() -> (ComputeService, mpsc::Receiver<(SendTarget, OutgoingChunk)>) {
         let (tx, rx) = mpsc::channel(64);
@@ -172,6 +253,10 @@ mod tests {
             max_cpu_cores: 0,
             max_memory_bytes: 0,
             task_timeout_secs: 60,
+            max_output_bytes: 0,
+            max_output_file_bytes: 0,
+            allowed_commands: Vec::new(),
+            task_log: None,
         };
         let svc = ComputeService::new(store, settings, tx);
         (svc, rx)
@@ -194,6 +279,7 @@ mod tests {
             sender: "a".repeat(64),
             timestamp: 100,
             payload: serde_json::json!({ "run": "echo hi" }),
+            priority: 0,
         }
     }
 
@@ -205,6 +291,12 @@ mod tests {
         .unwrap()
     }
 
+    #[test]
+    fn compute_is_an_executing_service() {
+        let (svc, _rx) = make_service();
+        assert!(svc.executes());
+    }
+
     #[test]
     fn handle_chunk_task_submit() {
         let (svc, mut rx) = make_service();
@@ -231,7 +323,7 @@ mod tests {
         let peer = [1u8; 32];
 
         // Submit first
-        svc.store.submit(peer, make_submit("task-ack-1"));
+        svc.store.track_submitted(peer, make_submit("task-ack-1"));
 
         let ack = crate::compute_types::TaskAck {
             task_id: "task-ack-1".to_string(),
@@ -245,24 +337,76 @@ mod tests {
         assert_eq!(task.status, TaskStatus::Running);
     }
 
+    #[test]
+    fn short_id_cuts_on_a_char_boundary() {
+        assert_eq!(short_id(&"ab".repeat(32)), "ab".repeat(8));
+        assert_eq!(short_id("abc"), "abc");
+        // Byte 16 falls inside the 'é'.
+        assert_eq!(short_id("abcdefabcdefabcéé"), "abcdefabcdefabcé");
+    }
+
+    #[test]
+    fn task_ack_from_another_peer_is_ignored() {
+        let (svc, _rx) = make_service();
+        let worker = [1u8; 32];
+        let sender = [2u8; 32];
+        // One task we submitted to the worker, one a peer submitted to us.
+        svc.store.track_submitted(worker, make_submit("task-ours"));
+        svc.store.submit(sender, make_submit("task-theirs"));
+
+        let ack = |task_id: &str| {
+            encode_envelope(
+                msg_types::TASK_ACK,
+                serde_json::to_value(crate::compute_types::TaskAck {
+                    task_id: task_id.to_string(),
+                    status: TaskStatus::Failed,
+                })
+                .unwrap(),
+            )
+        };
+        svc.handle_chunk(&[4u8; 32], &dummy_header(), &ack("task-ours"))
+            .unwrap();
+        svc.handle_chunk(&sender, &dummy_header(), &ack("task-theirs"))
+            .unwrap();
+
+        let ours = svc.store.get_task("task-ours").unwrap();
+        assert_eq!(ours.status, TaskStatus::Queued);
+        let theirs = svc.store.get_task("task-theirs").unwrap();
+        assert_eq!(theirs.status, TaskStatus::Queued);
+    }
+
     #[test]
     fn handle_chunk_task_result() {
         let (svc, _rx) = make_service();
-        let peer = [1u8; 32];
+        let keypair = Keypair::generate();
+        let worker = keypair.public;
+        let submit = make_submit("task-result-1");
 
-        svc.store.submit(peer, make_submit("task-result-1"));
+        svc.store.track_submitted(worker, submit.clone());
 
         let result = TaskResult {
             task_id: "task-result-1".to_string(),
             result: serde_json::json!({ "output": 42 }),
             elapsed_ms: 123,
-        };
+            status: TaskStatus::Completed,
+            digest: None,
+            signature: None,
+            times: None,
+        }
+        .signed(&submit.payload, &keypair);
         let payload = encode_envelope(
             msg_types::TASK_RESULT,
             serde_json::to_value(&result).unwrap(),
         );
 
-        svc.handle_chunk(&peer, &dummy_header(), &payload).unwrap();
+        // From some other peer — ignored.
+        svc.handle_chunk(&[4u8; 32], &dummy_header(), &payload)
+            .unwrap();
+        let task = svc.store.get_task("task-result-1").unwrap();
+        assert_eq!(task.status, TaskStatus::Queued);
+
+        svc.handle_chunk(&worker, &dummy_header(), &payload)
+            .unwrap();
 
         let task = svc.store.get_task("task-result-1").unwrap();
         assert_eq!(task.status, TaskStatus::Completed);
@@ -270,6 +414,150 @@ mod tests {
         assert_eq!(task.result.unwrap().elapsed_ms, 123);
     }
 
+    #[test]
+    fn task_result_for_unknown_task_is_ignored() {
+        let (svc, _rx) = make_service();
+        let worker = [1u8; 32];
+        svc.store.track_submitted(worker, make_submit("task-known"));
+
+        let result = TaskResult {
+            task_id: "task-never-submitted".to_string(),
+            result: serde_json::json!({ "output": "forged" }),
+            elapsed_ms: 1,
+            status: TaskStatus::Completed,
+            digest: None,
+            signature: None,
+            times: None,
+        };
+        let payload = encode_envelope(
+            msg_types::TASK_RESULT,
+            serde_json::to_value(&result).unwrap(),
+        );
+        svc.handle_chunk(&worker, &dummy_header(), &payload)
+            .unwrap();
+
+        assert!(svc.store.get_task("task-never-submitted").is_none());
+        let task = svc.store.get_task("task-known").unwrap();
+        assert_eq!(task.status, TaskStatus::Queued);
+        assert!(task.result.is_none());
+    }
+
+    #[test]
+    fn task_result_must_be_signed_over_the_submission() {
+        let (svc, _rx) = make_service();
+        let keypair = Keypair::generate();
+        let worker = keypair.public;
+        let submit = make_submit("task-digest");
+        svc.store.track_submitted(worker, submit.clone());
+
+        let result = TaskResult {
+            task_id: "task-digest".to_string(),
+            result: serde_json::json!({ "output": 42 }),
+            elapsed_ms: 5,
+            status: TaskStatus::Completed,
+            digest: None,
+            signature: None,
+            times: None,
+        };
+        let send = |result: &TaskResult| {
+            let payload = encode_envelope(
+                msg_types::TASK_RESULT,
+                serde_json::to_value(result).unwrap(),
+            );
+            svc.handle_chunk(&worker, &dummy_header(), &payload)
+                .unwrap();
+        };
+
+        let ignored = |result: &TaskResult| {
+            send(result);
+            svc.store.get_task("task-digest").unwrap().status == TaskStatus::Queued
+        };
+
+        // Unsigned.
+        assert!(ignored(&result));
+        // Digest over a different payload than the one submitted.
+        assert!(ignored(
+            &result
+                .clone()
+                .signed(&serde_json::json!({ "run": "rm -rf /" }), &keypair)
+        ));
+        // Signed by a key other than the worker's.
+        assert!(ignored(
+            &result.clone().signed(&submit.payload, &Keypair::generate())
+        ));
+        // Digest without its signature.
+        let mut unsigned = result.clone().signed(&submit.payload, &keypair);
+        unsigned.signature = None;
+        assert!(ignored(&unsigned));
+
+        send(&result.signed(&submit.payload, &keypair));
+        let task = svc.store.get_task("task-digest").unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert!(task.result.unwrap().signature.is_some());
+    }
+
+    #[test]
+    fn handle_chunk_failed_task_result() {
+        let (svc, _rx) = make_service();
+        let keypair = Keypair::generate();
+        let worker = keypair.public;
+        let submit = make_submit("task-result-2");
+        svc.store.track_submitted(worker, submit.clone());
+
+        let result: TaskResult = serde_json::from_value(serde_json::json!({
+            "task_id": "task-result-2",
+            "result": { "error": "exit code 1: boom" },
+            "elapsed_ms": 7,
+            "status": "failed",
+        }))
+        .unwrap();
+        let payload = encode_envelope(
+            msg_types::TASK_RESULT,
+            serde_json::to_value(result.signed(&submit.payload, &keypair)).unwrap(),
+        );
+        svc.handle_chunk(&worker, &dummy_header(), &payload)
+            .unwrap();
+
+        let task = svc.store.get_task("task-result-2").unwrap();
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(task.result.unwrap().result["error"], "exit code 1: boom");
+    }
+
+    #[test]
+    fn handle_chunk_task_output_appends_for_local_task() {
+        let (svc, _rx) = make_service();
+        let worker = [3u8; 32];
+        svc.store
+            .track_submitted(worker, make_submit("task-output-1"));
+
+        let output = crate::compute_types::ComputeOutput {
+            task_id: "task-output-1".to_string(),
+            seq: 0,
+            stream: "stdout".to_string(),
+            data: "line one\n".to_string(),
+        };
+        let payload = encode_envelope(
+            msg_types::TASK_OUTPUT,
+            serde_json::to_value(&output).unwrap(),
+        );
+
+        // From some other peer — ignored.
+        svc.handle_chunk(&[4u8; 32], &dummy_header(), &payload)
+            .unwrap();
+        assert!(svc
+            .store
+            .get_task("task-output-1")
+            .unwrap()
+            .output
+            .is_empty());
+
+        svc.handle_chunk(&worker, &dummy_header(), &payload)
+            .unwrap();
+        let task = svc.store.get_task("task-output-1").unwrap();
+        assert_eq!(task.output.len(), 1);
+        assert_eq!(task.output[0].data, "line one\n");
+    }
+
     #[test]
     fn handle_chunk_task_cancel() {
         let (svc, _rx) = make_service();
@@ -288,6 +576,22 @@ mod tests {
         assert_eq!(task.status, TaskStatus::Cancelled);
     }
 
+    #[test]
+    fn handle_chunk_task_cancel_from_other_peer_is_ignored() {
+        let (svc, _rx) = make_service();
+        svc.store.submit([1u8; 32], make_submit("task-cancel-2"));
+
+        let payload = encode_envelope(
+            msg_types::TASK_CANCEL,
+            serde_json::json!({ "task_id": "task-cancel-2" }),
+        );
+        svc.handle_chunk(&[2u8; 32], &dummy_header(), &payload)
+            .unwrap();
+
+        let task = svc.store.get_task("task-cancel-2").unwrap();
+        assert_eq!(task.status, TaskStatus::Queued);
+    }
+
     #[test]
     fn handle_chunk_unknown_msg_type() {
         let (svc, _rx) = make_service();
@@ -1,7 +1,13 @@
-use crate::compute_types::{TaskResult, TaskStatus, TaskSubmit};
+use crate::compute_types::{ComputeOutput, TaskResult, TaskStatus, TaskSubmit};
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
 
 /// Full state of a compute task.
 #[derive(Debug, Clone)]
@@ -16,23 +22,225 @@ pub struct ComputeTask {
     pub submitted_at: u64,
     /// Unix ms when the status was last changed.
     pub updated_at: u64,
+    /// Unix ms when the task started running: when execution began on the
+    /// worker, when the worker's Running ack arrived on the submitter.
+    pub started_at: Option<u64>,
+    /// Unix ms when the task finished, was cancelled or failed. On the
+    /// submitter, when the result arrived.
+    pub completed_at: Option<u64>,
     /// `true` = we originated this task (track-only).
     /// `false` = a peer sent it to us (eligible for execution).
     pub local: bool,
     /// Peer pubkey associated with this task (sender on receiver, receiver on sender).
     pub peer_pubkey: [u8; 32],
+    /// Output streamed by the worker while the task runs (submitter side).
+    /// Capped at `MAX_STREAMED_OUTPUT_BYTES`.
+    pub output: Vec<OutputLine>,
+    /// Bytes of `data` held in `output`.
+    pub output_bytes: usize,
+    /// `true` once the cap was hit and later output was dropped.
+    pub output_truncated: bool,
+    /// Output files received from the worker (submitter side).
+    pub output_files: Vec<PathBuf>,
 }
 
-/// In-memory store for compute tasks.
+impl ComputeTask {
+    /// Move to `status`, stamping `started_at` on the first Running and
+    /// `completed_at` on the first final status.
+    fn set_status(&mut self, status: TaskStatus) {
+        self.set_status_at(status, now_ms());
+    }
+
+    fn set_status_at(&mut self, status: TaskStatus, now: u64) {
+        self.status = status;
+        self.updated_at = now;
+        match status {
+            TaskStatus::Queued => {}
+            TaskStatus::Running => {
+                self.started_at.get_or_insert(now);
+            }
+            _ => {
+                self.completed_at.get_or_insert(now);
+            }
+        }
+    }
+
+    /// Time spent queued before execution began. Taken from the worker's
+    /// own timestamps when its result carries them, so clock skew between
+    /// peers doesn't leak in.
+    pub fn queue_ms(&self) -> Option<u64> {
+        match self.result.as_ref().and_then(|r| r.times) {
+            Some(times) => Some(times.queue_ms()),
+            None => Some(self.started_at?.saturating_sub(self.submitted_at)),
+        }
+    }
+
+    /// Time spent executing, from the worker's timestamps when present.
+    pub fn run_ms(&self) -> Option<u64> {
+        match self.result.as_ref().and_then(|r| r.times) {
+            Some(times) => Some(times.run_ms()),
+            None => Some(self.completed_at?.saturating_sub(self.started_at?)),
+        }
+    }
+}
+
+/// One streamed piece of task output.
+#[derive(Debug, Clone)]
+pub struct OutputLine {
+    pub seq: u64,
+    /// "stdout" or "stderr".
+    pub stream: String,
+    pub data: String,
+}
+
+/// Max streamed output retained per task on the submitter side.
+pub const MAX_STREAMED_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// One line of the persisted task log. Streamed output is not logged; the
+/// final result carries the worker-side capture.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LogRecord {
+    Submit {
+        submit: TaskSubmit,
+        peer: String,
+        local: bool,
+        at: u64,
+    },
+    Status {
+        task_id: String,
+        status: TaskStatus,
+        at: u64,
+    },
+    Result {
+        status: TaskStatus,
+        result: TaskResult,
+        at: u64,
+    },
+    OutputFile {
+        task_id: String,
+        path: PathBuf,
+    },
+}
+
+/// Store for compute tasks, in memory unless built `with_persistence`.
 #[derive(Clone, Default)]
 pub struct ComputeStore {
     /// task_id → ComputeTask
     tasks: Arc<DashMap<String, ComputeTask>>,
     /// peer pubkey → list of task_ids they submitted
     peer_tasks: Arc<DashMap<[u8; 32], Vec<String>>>,
+    /// task_id → kill handle for tasks whose child process is executing.
+    /// The executor owns the `Child`; notifying the handle makes it kill the
+    /// child's process group.
+    running: Arc<DashMap<String, Arc<Notify>>>,
+    /// Task log every change is appended to, if persistent.
+    log: Arc<Option<TaskLog>>,
+}
+
+/// The log is compacted once it has grown to twice its compacted size,
+/// and to at least this many bytes.
+const COMPACT_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Writer for the task log. Records are handed to a dedicated thread that
+/// appends them through a buffer — callers append while holding a task's
+/// map entry, so they never touch the file themselves. Dropping the last
+/// store clone flushes and joins the thread.
+struct TaskLog {
+    tx: Option<mpsc::Sender<String>>,
+    writer: Option<std::thread::JoinHandle<()>>,
+}
+
+impl TaskLog {
+    fn spawn(
+        path: PathBuf,
+        tasks: Arc<DashMap<String, ComputeTask>>,
+        size: u64,
+        compact_min: u64,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let writer = std::thread::Builder::new()
+            .name("compute-task-log".into())
+            .spawn(move || write_log(&path, &tasks, rx, size, compact_min))
+            .map_err(|e| tracing::warn!(error = %e, "failed to start compute task log writer"))
+            .ok();
+        Self {
+            tx: Some(tx),
+            writer,
+        }
+    }
+
+    fn append(&self, line: String) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(line);
+        }
+    }
 }
 
-fn now_ms() -> u64 {
+impl Drop for TaskLog {
+    fn drop(&mut self) {
+        self.tx.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// The task log writer thread: append each line as it arrives, flush
+/// whenever the queue runs dry, and compact once the log has outgrown
+/// `compact_min` and twice its last compacted size. Records queued while
+/// compacting are appended after it; replaying one whose change the
+/// compacted state already holds is a no-op.
+fn write_log(
+    path: &Path,
+    tasks: &DashMap<String, ComputeTask>,
+    rx: mpsc::Receiver<String>,
+    mut size: u64,
+    compact_min: u64,
+) {
+    let mut compacted_size = size;
+    let mut file: Option<BufWriter<std::fs::File>> = None;
+    let warn = |e: std::io::Error| {
+        tracing::warn!(error = %e, path = %path.display(), "failed to persist compute task");
+    };
+    while let Ok(line) = rx.recv() {
+        let mut next = Some(line);
+        while let Some(line) = next {
+            if file.is_none() {
+                match std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                {
+                    Ok(f) => file = Some(BufWriter::new(f)),
+                    Err(e) => warn(e),
+                }
+            }
+            if let Some(f) = &mut file {
+                match writeln!(f, "{line}") {
+                    Ok(()) => size += line.len() as u64 + 1,
+                    Err(e) => warn(e),
+                }
+            }
+            next = rx.try_recv().ok();
+        }
+        if let Some(Err(e)) = file.as_mut().map(|f| f.flush()) {
+            warn(e);
+        }
+        if size > compact_min.max(compacted_size * 2) {
+            file = None;
+            if let Some(written) = rewrite(path, &compacted(tasks)) {
+                size = written;
+                compacted_size = written;
+            }
+        }
+    }
+    if let Some(Err(e)) = file.as_mut().map(|f| f.flush()) {
+        warn(e);
+    }
+}
+
+pub(crate) fn now_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_millis() as u64)
@@ -44,44 +252,77 @@ impl ComputeStore {
         Self {
             tasks: Arc::new(DashMap::new()),
             peer_tasks: Arc::new(DashMap::new()),
+            running: Arc::new(DashMap::new()),
+            log: Arc::new(None),
         }
     }
 
+    /// Create a store that appends every task change to the log at `path`
+    /// and replays it on startup, so task history survives a restart.
+    ///
+    /// A task that was running here when the daemon stopped comes back
+    /// `Interrupted`; queued ones stay queued and run again. Tasks we
+    /// submitted keep their last known status — the worker may still answer.
+    /// The log is rewritten compacted after loading, and again whenever it
+    /// has doubled in size since.
+    pub fn with_persistence(path: PathBuf) -> Self {
+        Self::with_log(path, COMPACT_MIN_BYTES)
+    }
+
+    fn with_log(path: PathBuf, compact_min: u64) -> Self {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let mut store = Self::new();
+        let size = store.load_from_disk(&path);
+        store.log = Arc::new(Some(TaskLog::spawn(
+            path,
+            store.tasks.clone(),
+            size,
+            compact_min,
+        )));
+        store
+    }
+
     /// Store a task received from a peer (eligible for execution).
     /// Duplicate `task_id`s are silently ignored.
     pub fn submit(&self, peer_pubkey: [u8; 32], submit: TaskSubmit) {
-        let task_id = submit.task_id.clone();
-        self.tasks
-            .entry(task_id.clone())
-            .or_insert_with(|| ComputeTask {
-                submit,
-                status: TaskStatus::Queued,
-                result: None,
-                submitted_at: now_ms(),
-                updated_at: now_ms(),
-                local: false,
-                peer_pubkey,
-            });
-        self.peer_tasks
-            .entry(peer_pubkey)
-            .or_default()
-            .push(task_id);
+        self.insert(peer_pubkey, submit, false, now_ms(), true);
     }
 
     /// Track a task we submitted locally (status-tracking only, not executed here).
     pub fn track_submitted(&self, peer_pubkey: [u8; 32], submit: TaskSubmit) {
+        self.insert(peer_pubkey, submit, true, now_ms(), true);
+    }
+
+    fn insert(&self, peer_pubkey: [u8; 32], submit: TaskSubmit, local: bool, at: u64, log: bool) {
         let task_id = submit.task_id.clone();
-        self.tasks
-            .entry(task_id.clone())
-            .or_insert_with(|| ComputeTask {
-                submit,
-                status: TaskStatus::Queued,
-                result: None,
-                submitted_at: now_ms(),
-                updated_at: now_ms(),
-                local: true,
-                peer_pubkey,
+        let Entry::Vacant(entry) = self.tasks.entry(task_id.clone()) else {
+            return;
+        };
+        if log {
+            self.append(&LogRecord::Submit {
+                submit: submit.clone(),
+                peer: hex::encode(peer_pubkey),
+                local,
+                at,
             });
+        }
+        entry.insert(ComputeTask {
+            submit,
+            status: TaskStatus::Queued,
+            result: None,
+            submitted_at: at,
+            updated_at: at,
+            started_at: None,
+            completed_at: None,
+            local,
+            peer_pubkey,
+            output: Vec::new(),
+            output_bytes: 0,
+            output_truncated: false,
+            output_files: Vec::new(),
+        });
         self.peer_tasks
             .entry(peer_pubkey)
             .or_default()
@@ -89,30 +330,139 @@ impl ComputeStore {
     }
 
     /// Record a peer acknowledgment and update task status.
+    ///
+    /// Acks can arrive after the result, so one never moves a task out of
+    /// Completed, Failed or Cancelled.
     pub fn ack(&self, task_id: &str, status: TaskStatus) {
-        if let Some(mut task) = self.tasks.get_mut(task_id) {
-            task.status = status;
-            task.updated_at = now_ms();
+        let Some(mut task) = self.tasks.get_mut(task_id) else {
+            return;
+        };
+        if matches!(
+            task.status,
+            TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled
+        ) {
+            return;
         }
+        task.set_status(status);
+        self.append(&LogRecord::Status {
+            task_id: task_id.to_string(),
+            status,
+            at: task.updated_at,
+        });
     }
 
     /// Update task status.
     pub fn update_status(&self, task_id: &str, status: TaskStatus) {
         if let Some(mut task) = self.tasks.get_mut(task_id) {
-            task.status = status;
-            task.updated_at = now_ms();
+            task.set_status(status);
+            self.append(&LogRecord::Status {
+                task_id: task_id.to_string(),
+                status,
+                at: task.updated_at,
+            });
         }
     }
 
     /// Store a task result and mark the task as Completed.
     pub fn store_result(&self, result: TaskResult) {
-        if let Some(mut task) = self.tasks.get_mut(&result.task_id) {
-            task.status = TaskStatus::Completed;
+        self.store_failure(result, TaskStatus::Completed);
+    }
+
+    /// Append streamed output to a task, keeping it ordered by `seq`.
+    ///
+    /// Output past `MAX_STREAMED_OUTPUT_BYTES` is dropped and the task is
+    /// flagged `output_truncated`; the final result still carries the
+    /// worker-side capture.
+    pub fn append_output(&self, output: ComputeOutput) {
+        let Some(mut task) = self.tasks.get_mut(&output.task_id) else {
+            return;
+        };
+        if task.output_bytes + output.data.len() > MAX_STREAMED_OUTPUT_BYTES {
+            task.output_truncated = true;
+            return;
+        }
+        task.output_bytes += output.data.len();
+        let pos = task.output.partition_point(|l| l.seq < output.seq);
+        task.output.insert(
+            pos,
+            OutputLine {
+                seq: output.seq,
+                stream: output.stream,
+                data: output.data,
+            },
+        );
+        task.updated_at = now_ms();
+    }
+
+    /// Record an output file received for a task we submitted.
+    pub fn add_output_file(&self, task_id: &str, path: PathBuf) {
+        if let Some(mut task) = self.tasks.get_mut(task_id) {
+            if !task.output_files.contains(&path) {
+                self.append(&LogRecord::OutputFile {
+                    task_id: task_id.to_string(),
+                    path: path.clone(),
+                });
+                task.output_files.push(path);
+            }
             task.updated_at = now_ms();
+        }
+    }
+
+    /// Store a result for a task that did not succeed and mark it with `status`
+    /// (typically `Failed`). The result carries the error detail.
+    pub fn store_failure(&self, result: TaskResult, status: TaskStatus) {
+        if let Some(mut task) = self.tasks.get_mut(&result.task_id) {
+            task.set_status(status);
+            self.append(&LogRecord::Result {
+                status,
+                result: result.clone(),
+                at: task.updated_at,
+            });
             task.result = Some(result);
         }
     }
 
+    /// Mark a task `Running` and register its kill handle. The executor waits
+    /// on the returned handle alongside the child process.
+    pub fn start_running(&self, task_id: &str) -> Arc<Notify> {
+        self.update_status(task_id, TaskStatus::Running);
+        let handle = Arc::new(Notify::new());
+        self.running.insert(task_id.to_string(), handle.clone());
+        handle
+    }
+
+    /// Drop the kill handle once the task's process has exited.
+    pub fn finish_running(&self, task_id: &str) {
+        self.running.remove(task_id);
+    }
+
+    /// Cancel a task: mark it `Cancelled` and, if its process is executing,
+    /// signal the executor to kill it.
+    ///
+    /// Returns `false` if the task is unknown or already finished.
+    pub fn cancel(&self, task_id: &str) -> bool {
+        let Some(mut task) = self.tasks.get_mut(task_id) else {
+            return false;
+        };
+        if !matches!(task.status, TaskStatus::Queued | TaskStatus::Running) {
+            return false;
+        }
+        task.set_status(TaskStatus::Cancelled);
+        self.append(&LogRecord::Status {
+            task_id: task_id.to_string(),
+            status: TaskStatus::Cancelled,
+            at: task.updated_at,
+        });
+        drop(task);
+
+        if let Some(handle) = self.running.get(task_id) {
+            // notify_one stores a permit, so a kill issued before the
+            // executor starts waiting is not lost.
+            handle.notify_one();
+        }
+        true
+    }
+
     /// Get all task_ids submitted by a peer.
     pub fn tasks_for_peer(&self, peer_pubkey: &[u8; 32]) -> Vec<String> {
         self.peer_tasks
@@ -134,6 +484,14 @@ impl ComputeStore {
             .collect()
     }
 
+    /// Number of remote tasks waiting for an executor slot.
+    pub fn queue_depth(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|entry| !entry.local && entry.status == TaskStatus::Queued)
+            .count()
+    }
+
     /// Return remote tasks that are queued and ready for execution.
     pub fn queued_remote_tasks(&self) -> Vec<ComputeTask> {
         self.tasks
@@ -147,6 +505,166 @@ impl ComputeStore {
     }
 }
 
+impl ComputeStore {
+    /// Queue one record for the task log. Best-effort — the writer logs
+    /// failures.
+    fn append(&self, record: &LogRecord) {
+        let Some(log) = self.log.as_ref() else {
+            return;
+        };
+        match serde_json::to_string(record) {
+            Ok(line) => log.append(line),
+            Err(e) => tracing::warn!(error = %e, "failed to serialize compute task record"),
+        }
+    }
+
+    /// Replay the task log, then rewrite it holding just the current state.
+    /// Called once during construction; returns the rewritten log's size.
+    fn load_from_disk(&self, path: &Path) -> u64 {
+        let text = match std::fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return 0,
+            Err(e) => {
+                tracing::warn!(error = %e, path = %path.display(), "failed to read compute tasks");
+                return 0;
+            }
+        };
+        let mut skipped = 0usize;
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            // A crash mid-append can leave a torn last line.
+            let Ok(record) = serde_json::from_str::<LogRecord>(line) else {
+                skipped += 1;
+                continue;
+            };
+            self.replay(record);
+        }
+        if skipped > 0 {
+            tracing::warn!(skipped, path = %path.display(), "unreadable compute task records");
+        }
+
+        let mut interrupted = 0usize;
+        for mut task in self.tasks.iter_mut() {
+            if !task.local && task.status == TaskStatus::Running {
+                task.set_status(TaskStatus::Interrupted);
+                interrupted += 1;
+            }
+        }
+        let size = rewrite(path, &compacted(&self.tasks)).unwrap_or(text.len() as u64);
+        if !self.tasks.is_empty() {
+            tracing::info!(
+                count = self.tasks.len(),
+                interrupted,
+                path = %path.display(),
+                "loaded persisted compute tasks"
+            );
+        }
+        size
+    }
+
+    fn replay(&self, record: LogRecord) {
+        match record {
+            LogRecord::Submit {
+                submit,
+                peer,
+                local,
+                at,
+            } => {
+                let mut peer_pubkey = [0u8; 32];
+                if hex::decode_to_slice(&peer, &mut peer_pubkey).is_ok() {
+                    self.insert(peer_pubkey, submit, local, at, false);
+                }
+            }
+            LogRecord::Status {
+                task_id,
+                status,
+                at,
+            } => {
+                if let Some(mut task) = self.tasks.get_mut(&task_id) {
+                    task.set_status_at(status, at);
+                }
+            }
+            LogRecord::Result { status, result, at } => {
+                if let Some(mut task) = self.tasks.get_mut(&result.task_id) {
+                    task.set_status_at(status, at);
+                    task.result = Some(result);
+                }
+            }
+            LogRecord::OutputFile { task_id, path } => {
+                if let Some(mut task) = self.tasks.get_mut(&task_id) {
+                    if !task.output_files.contains(&path) {
+                        task.output_files.push(path);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The log compacted: one submit record per task plus what brings it to
+/// its current state.
+fn compacted(tasks: &DashMap<String, ComputeTask>) -> String {
+    let mut tasks: Vec<ComputeTask> = tasks.iter().map(|entry| entry.value().clone()).collect();
+    tasks.sort_by_key(|t| t.submitted_at);
+    let mut out = String::new();
+    let mut push = |record: LogRecord| {
+        if let Ok(line) = serde_json::to_string(&record) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    };
+    for task in tasks {
+        let task_id = task.submit.task_id.clone();
+        push(LogRecord::Submit {
+            submit: task.submit,
+            peer: hex::encode(task.peer_pubkey),
+            local: task.local,
+            at: task.submitted_at,
+        });
+        if let Some(started) = task.started_at {
+            push(LogRecord::Status {
+                task_id: task_id.clone(),
+                status: TaskStatus::Running,
+                at: started,
+            });
+        }
+        match task.result {
+            Some(result) => push(LogRecord::Result {
+                status: task.status,
+                result,
+                at: task.completed_at.unwrap_or(task.updated_at),
+            }),
+            // Running comes back through `started_at` above.
+            None if !matches!(task.status, TaskStatus::Queued | TaskStatus::Running) => {
+                push(LogRecord::Status {
+                    task_id: task_id.clone(),
+                    status: task.status,
+                    at: task.updated_at,
+                })
+            }
+            None => {}
+        }
+        for path in task.output_files {
+            push(LogRecord::OutputFile {
+                task_id: task_id.clone(),
+                path,
+            });
+        }
+    }
+    out
+}
+
+/// Replace the log at `path` with `text`. Returns its size.
+fn rewrite(path: &Path, text: &str) -> Option<u64> {
+    let tmp = path.with_extension("tmp");
+    match std::fs::write(&tmp, text).and_then(|_| std::fs::rename(&tmp, path)) {
+        Ok(()) => Some(text.len() as u64),
+        Err(e) => {
+            tracing::warn!(error = %e, path = %path.display(), "failed to compact compute tasks");
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,6 +676,7 @@ mod tests {
             sender: "a".repeat(64),
             timestamp: 100,
             payload: serde_json::json!({}),
+            priority: 0,
         }
     }
 
@@ -251,6 +770,10 @@ mod tests {
             task_id: "task-1".to_string(),
             result: serde_json::json!({ "output": 42 }),
             elapsed_ms: 500,
+            status: TaskStatus::Completed,
+            digest: None,
+            signature: None,
+            times: None,
         };
         store.store_result(result);
 
@@ -259,4 +782,272 @@ mod tests {
         assert!(task.result.is_some());
         assert_eq!(task.result.unwrap().elapsed_ms, 500);
     }
+
+    #[test]
+    fn cancel_queued_task() {
+        let store = ComputeStore::new();
+        let peer = [1u8; 32];
+        store.submit(peer, make_submit("task-1"));
+
+        assert!(store.cancel("task-1"));
+        assert_eq!(
+            store.get_task("task-1").unwrap().status,
+            TaskStatus::Cancelled
+        );
+        assert!(store.queued_remote_tasks().is_empty());
+        // Already cancelled — nothing left to cancel.
+        assert!(!store.cancel("task-1"));
+        assert!(!store.cancel("missing"));
+    }
+
+    #[tokio::test]
+    async fn cancel_running_task_signals_handle() {
+        let store = ComputeStore::new();
+        let peer = [1u8; 32];
+        store.submit(peer, make_submit("task-1"));
+
+        let handle = store.start_running("task-1");
+        assert!(store.cancel("task-1"));
+        tokio::time::timeout(std::time::Duration::from_secs(1), handle.notified())
+            .await
+            .expect("kill handle should be notified");
+
+        store.finish_running("task-1");
+        assert_eq!(
+            store.get_task("task-1").unwrap().status,
+            TaskStatus::Cancelled
+        );
+    }
+
+    fn output(seq: u64, data: &str) -> ComputeOutput {
+        ComputeOutput {
+            task_id: "task-1".to_string(),
+            seq,
+            stream: "stdout".to_string(),
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn append_output_orders_by_seq() {
+        let store = ComputeStore::new();
+        store.track_submitted([1u8; 32], make_submit("task-1"));
+
+        store.append_output(output(1, "b\n"));
+        store.append_output(output(0, "a\n"));
+
+        let task = store.get_task("task-1").unwrap();
+        let data: Vec<&str> = task.output.iter().map(|l| l.data.as_str()).collect();
+        assert_eq!(data, ["a\n", "b\n"]);
+        assert_eq!(task.output_bytes, 4);
+        assert!(!task.output_truncated);
+    }
+
+    #[test]
+    fn append_output_is_bounded() {
+        let store = ComputeStore::new();
+        store.track_submitted([1u8; 32], make_submit("task-1"));
+
+        let big = "x".repeat(MAX_STREAMED_OUTPUT_BYTES - 1);
+        store.append_output(output(0, &big));
+        store.append_output(output(1, "overflow\n"));
+
+        let task = store.get_task("task-1").unwrap();
+        assert_eq!(task.output.len(), 1);
+        assert!(task.output_truncated);
+    }
+
+    #[test]
+    fn store_failure_keeps_error_detail() {
+        let store = ComputeStore::new();
+        let peer = [1u8; 32];
+        store.submit(peer, make_submit("task-1"));
+
+        store.store_failure(
+            TaskResult {
+                task_id: "task-1".to_string(),
+                result: serde_json::json!({ "error": "timeout" }),
+                elapsed_ms: 2000,
+                status: TaskStatus::Failed,
+                digest: None,
+                signature: None,
+                times: None,
+            },
+            TaskStatus::Failed,
+        );
+
+        let task = store.get_task("task-1").unwrap();
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(task.result.unwrap().result["error"], "timeout");
+    }
+
+    #[test]
+    fn lifecycle_timestamps_split_queue_and_run() {
+        let store = ComputeStore::new();
+        let peer = [1u8; 32];
+        store.submit(peer, make_submit("task-1"));
+        let task = store.get_task("task-1").unwrap();
+        assert_eq!((task.started_at, task.completed_at), (None, None));
+        assert_eq!((task.queue_ms(), task.run_ms()), (None, None));
+
+        store.start_running("task-1");
+        store.update_status("task-1", TaskStatus::Running);
+        let started = store.get_task("task-1").unwrap().started_at.unwrap();
+        assert!(started >= task.submitted_at);
+
+        store.store_failure(
+            TaskResult {
+                task_id: "task-1".to_string(),
+                result: serde_json::json!({ "error": "exit 1" }),
+                elapsed_ms: 0,
+                status: TaskStatus::Failed,
+                digest: None,
+                signature: None,
+                times: None,
+            },
+            TaskStatus::Failed,
+        );
+        let task = store.get_task("task-1").unwrap();
+        // A repeated Running doesn't move the start.
+        assert_eq!(task.started_at, Some(started));
+        let completed = task.completed_at.unwrap();
+        assert!(completed >= started);
+        assert_eq!(task.queue_ms(), Some(started - task.submitted_at));
+        assert_eq!(task.run_ms(), Some(completed - started));
+    }
+
+    #[test]
+    fn late_ack_does_not_reopen_a_finished_task() {
+        let store = ComputeStore::new();
+        let peer = [1u8; 32];
+        store.track_submitted(peer, make_submit("task-1"));
+        store.store_result(TaskResult {
+            task_id: "task-1".to_string(),
+            result: serde_json::json!({ "output": 42 }),
+            elapsed_ms: 5,
+            status: TaskStatus::Completed,
+            digest: None,
+            signature: None,
+            times: None,
+        });
+
+        store.ack("task-1", TaskStatus::Queued);
+        store.ack("task-1", TaskStatus::Running);
+
+        let task = store.get_task("task-1").unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert!(task.result.is_some());
+    }
+
+    #[test]
+    fn submitter_prefers_worker_times_from_the_result() {
+        let store = ComputeStore::new();
+        let peer = [1u8; 32];
+        store.track_submitted(peer, make_submit("task-1"));
+        store.ack("task-1", TaskStatus::Running);
+
+        // The worker's clock is far off ours; only its differences count.
+        let result: TaskResult = serde_json::from_value(serde_json::json!({
+            "task_id": "task-1",
+            "result": { "stdout": "ok" },
+            "elapsed_ms": 250,
+            "times": { "queued_at": 1000, "started_at": 1400, "completed_at": 1650 },
+        }))
+        .unwrap();
+        store.store_result(result);
+
+        let task = store.get_task("task-1").unwrap();
+        assert!(task.started_at.is_some() && task.completed_at.is_some());
+        assert_eq!(task.queue_ms(), Some(400));
+        assert_eq!(task.run_ms(), Some(250));
+    }
+
+    #[test]
+    fn persisted_tasks_survive_restart() {
+        let dir = std::env::temp_dir().join(format!("summit-compute-log-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("compute_tasks.jsonl");
+        let peer = [1u8; 32];
+
+        {
+            let store = ComputeStore::with_persistence(path.clone());
+            store.submit(peer, make_submit("queued"));
+            store.submit(peer, make_submit("running"));
+            store.start_running("running");
+            store.submit(peer, make_submit("done"));
+            store.store_result(TaskResult {
+                task_id: "done".into(),
+                status: TaskStatus::Completed,
+                result: serde_json::json!({"stdout": "hi"}),
+                elapsed_ms: 5,
+                digest: None,
+                signature: None,
+                times: None,
+            });
+            store.track_submitted(peer, make_submit("ours"));
+            store.ack("ours", TaskStatus::Running);
+        }
+        // A crash mid-append leaves a torn line; it is skipped.
+        let mut log = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut log, b"{\"op\":\"sta").unwrap();
+
+        let store = ComputeStore::with_persistence(path.clone());
+        assert_eq!(store.all_tasks().len(), 4);
+        assert_eq!(store.tasks_for_peer(&peer).len(), 4);
+        assert_eq!(store.get_task("queued").unwrap().status, TaskStatus::Queued);
+        let interrupted = store.get_task("running").unwrap();
+        assert_eq!(interrupted.status, TaskStatus::Interrupted);
+        assert!(interrupted.started_at.is_some() && interrupted.completed_at.is_some());
+        let done = store.get_task("done").unwrap();
+        assert_eq!(done.status, TaskStatus::Completed);
+        assert_eq!(done.result.unwrap().elapsed_ms, 5);
+        // The worker may still answer a task we submitted.
+        let ours = store.get_task("ours").unwrap();
+        assert!(ours.local);
+        assert_eq!(ours.status, TaskStatus::Running);
+        assert_eq!(store.queued_remote_tasks().len(), 1);
+
+        // The compacted log reloads to the same state.
+        let again = ComputeStore::with_persistence(path);
+        assert_eq!(again.all_tasks().len(), 4);
+        assert_eq!(
+            again.get_task("running").unwrap().status,
+            TaskStatus::Interrupted
+        );
+        assert_eq!(
+            again.get_task("done").unwrap().status,
+            TaskStatus::Completed
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn task_log_is_compacted_as_it_grows() {
+        let dir =
+            std::env::temp_dir().join(format!("summit-compute-compact-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("compute_tasks.jsonl");
+        let peer = [1u8; 32];
+
+        {
+            let store = ComputeStore::with_log(path.clone(), 4096);
+            store.track_submitted(peer, make_submit("busy"));
+            for _ in 0..1000 {
+                store.ack("busy", TaskStatus::Running);
+            }
+        }
+        // A thousand status records would be tens of KB uncompacted.
+        let size = std::fs::metadata(&path).unwrap().len();
+        assert!(size < 3 * 4096, "log not compacted: {size} bytes");
+
+        let store = ComputeStore::with_persistence(path);
+        assert_eq!(store.get_task("busy").unwrap().status, TaskStatus::Running);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -4,6 +4,7 @@
 //! Payloads are kept as `serde_json::Value` — actual compute semantics are future work.
 
 use serde::{Deserialize, Serialize};
+use summit_core::crypto::Keypair;
 
 // ── Envelope ──────────────────────────────────────────────────────────────────
 
@@ -13,16 +14,24 @@ use serde::{Deserialize, Serialize};
 /// deserialize `payload` according to the type.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComputeEnvelope {
-    /// Discriminator: "task_submit", "task_ack", "task_result", "task_cancel".
+    /// Discriminator: "task_submit", "task_ack", "task_output", "task_result", "task_cancel".
     pub msg_type: String,
     /// Type-specific content. Structure is defined by `msg_type`.
     pub payload: serde_json::Value,
 }
 
+/// The first 16 characters of a task id, for log lines and the worker's
+/// task directory. Ids from peers need not be hex, so this cuts on a char
+/// boundary rather than at byte 16.
+pub fn short_id(id: &str) -> &str {
+    id.char_indices().nth(16).map_or(id, |(end, _)| &id[..end])
+}
+
 /// Well-known `msg_type` strings.
 pub mod msg_types {
     pub const TASK_SUBMIT: &str = "task_submit";
     pub const TASK_ACK: &str = "task_ack";
+    pub const TASK_OUTPUT: &str = "task_output";
     pub const TASK_RESULT: &str = "task_result";
     pub const TASK_CANCEL: &str = "task_cancel";
 }
@@ -32,7 +41,9 @@ pub mod msg_types {
 /// Task submission — sent by the client to request execution.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskSubmit {
-    /// Hex-encoded BLAKE3 hash identifying this task (deduplication key).
+    /// Hex-encoded BLAKE3 hash identifying this task (deduplication key):
+    /// `blake3(sender || timestamp_le || nonce[16] || payload_json)`, the
+    /// random nonce making every submission distinct.
     pub task_id: String,
     /// Sender public key, hex-encoded.
     pub sender: String,
@@ -40,6 +51,9 @@ pub struct TaskSubmit {
     pub timestamp: u64,
     /// Opaque task definition. Structure is defined by the execution engine (future work).
     pub payload: serde_json::Value,
+    /// Higher runs first when tasks wait for an executor slot. Default 0.
+    #[serde(default)]
+    pub priority: i32,
 }
 
 /// Peer acknowledgment of task receipt.
@@ -51,6 +65,23 @@ pub struct TaskAck {
     pub status: TaskStatus,
 }
 
+/// Incremental task output — streamed by the worker while the task runs.
+///
+/// `data` holds one or more complete lines (or a forced flush of a very long
+/// line). `seq` increases per task so identical lines still produce distinct
+/// chunks rather than being deduplicated by content hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComputeOutput {
+    /// Task producing the output.
+    pub task_id: String,
+    /// Per-task sequence number, starting at 0.
+    pub seq: u64,
+    /// "stdout" or "stderr".
+    pub stream: String,
+    /// Output text (lossily decoded as UTF-8).
+    pub data: String,
+}
+
 /// Task result — returned when execution completes or fails.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskResult {
@@ -60,6 +91,97 @@ pub struct TaskResult {
     pub result: serde_json::Value,
     /// Wall-clock milliseconds elapsed during execution.
     pub elapsed_ms: u64,
+    /// Final status — `Completed`, or `Failed` with the detail in `result`.
+    /// Absent from older workers, which only reported successes.
+    #[serde(default = "completed")]
+    pub status: TaskStatus,
+    /// Hex BLAKE3 digest binding this result to the submission it answers;
+    /// see `TaskResult::digest_for`. Required by the submitter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Hex XEdDSA signature over `digest` by the worker's identity key;
+    /// see `TaskResult::signed`. Required by the submitter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// When the worker queued, started and finished the task. Absent from
+    /// older workers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub times: Option<TaskTimes>,
+}
+
+/// A task's lifecycle on the worker, in Unix ms on the worker's clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskTimes {
+    pub queued_at: u64,
+    pub started_at: u64,
+    pub completed_at: u64,
+}
+
+impl TaskTimes {
+    /// Time spent waiting for an executor slot.
+    pub fn queue_ms(&self) -> u64 {
+        self.started_at.saturating_sub(self.queued_at)
+    }
+
+    /// Time spent executing.
+    pub fn run_ms(&self) -> u64 {
+        self.completed_at.saturating_sub(self.started_at)
+    }
+}
+
+fn completed() -> TaskStatus {
+    TaskStatus::Completed
+}
+
+impl TaskResult {
+    /// Digest over `task_id`, the submitted payload, `status` and `result`,
+    /// each length-prefixed. The worker computes it from the submission it
+    /// ran; the submitter recomputes it from the one it sent.
+    pub fn digest_for(&self, submitted: &serde_json::Value) -> String {
+        let mut buf = Vec::new();
+        for part in [
+            self.task_id.as_bytes().to_vec(),
+            submitted.to_string().into_bytes(),
+            serde_json::to_vec(&self.status).unwrap_or_default(),
+            self.result.to_string().into_bytes(),
+        ] {
+            buf.extend_from_slice(&(part.len() as u64).to_le_bytes());
+            buf.extend_from_slice(&part);
+        }
+        hex::encode(summit_core::crypto::hash(&buf))
+    }
+
+    /// Set `digest` for the submission this result answers and sign it
+    /// with the worker's identity key.
+    pub fn signed(mut self, submitted: &serde_json::Value, keypair: &Keypair) -> Self {
+        let digest = self.digest_for(submitted);
+        let signature = keypair.sign(&Self::signed_message(&digest));
+        self.digest = Some(digest);
+        self.signature = Some(hex::encode(signature));
+        self
+    }
+
+    /// True only when the result carries a digest matching `submitted`,
+    /// signed by `worker` — the peer the task was submitted to.
+    pub fn matches(&self, submitted: &serde_json::Value, worker: &[u8; 32]) -> bool {
+        let (Some(digest), Some(signature)) = (&self.digest, &self.signature) else {
+            return false;
+        };
+        let Some(signature) = hex::decode(signature)
+            .ok()
+            .and_then(|s| <[u8; 64]>::try_from(s).ok())
+        else {
+            return false;
+        };
+        *digest == self.digest_for(submitted)
+            && summit_core::crypto::verify(worker, &Self::signed_message(digest), &signature)
+    }
+
+    /// What the worker signs: a domain tag, so the signature can't be
+    /// replayed as any other message signed by the same key, then the digest.
+    fn signed_message(digest: &str) -> Vec<u8> {
+        [b"summit-task-result:".as_slice(), digest.as_bytes()].concat()
+    }
 }
 
 /// Lifecycle status of a compute task.
@@ -71,4 +193,7 @@ pub enum TaskStatus {
     Completed,
     Failed,
     Cancelled,
+    /// Was running here when the daemon stopped; found on reload of a
+    /// persisted store. Its process is gone and no result will come.
+    Interrupted,
 }
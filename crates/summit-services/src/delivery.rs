@@ -9,8 +9,34 @@ use tokio::time::Instant;
 /// How long to keep delivery records before eviction.
 const DELIVERY_TTL: Duration = Duration::from_secs(120);
 
+/// Delivery summary for a single chunk.
+#[derive(Debug, Clone)]
+pub struct DeliveryStats {
+    pub content_hash: [u8; 32],
+    /// Distinct peer addresses the chunk arrived from, in arrival order.
+    pub paths: Vec<String>,
+    /// Total arrivals, including retransmissions.
+    pub deliveries: usize,
+    /// Time since the first arrival.
+    pub first_seen: Duration,
+    /// Time since the most recent arrival.
+    pub last_seen: Duration,
+}
+
+impl DeliveryStats {
+    /// Arrivals beyond the first.
+    pub fn duplicates(&self) -> usize {
+        self.deliveries.saturating_sub(1)
+    }
+
+    /// Delivered over more than one distinct path.
+    pub fn is_multipath(&self) -> bool {
+        self.paths.len() > 1
+    }
+}
+
 /// Tracks chunk deliveries for multipath analysis.
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct DeliveryTracker {
     // content_hash -> Vec<(peer_addr, received_at)>
     #[allow(clippy::type_complexity)]
@@ -59,6 +85,34 @@ impl DeliveryTracker {
         }
     }
 
+    /// Snapshot of every tracked chunk, most recently delivered first.
+    pub fn stats(&self) -> Vec<DeliveryStats> {
+        let mut stats: Vec<DeliveryStats> = self
+            .deliveries
+            .iter()
+            .filter_map(|entry| {
+                let deliveries = entry.value();
+                let first = deliveries.first()?.1;
+                let last = deliveries.last()?.1;
+                let mut paths: Vec<String> = Vec::new();
+                for (peer, _) in deliveries {
+                    if !paths.contains(peer) {
+                        paths.push(peer.clone());
+                    }
+                }
+                Some(DeliveryStats {
+                    content_hash: *entry.key(),
+                    paths,
+                    deliveries: deliveries.len(),
+                    first_seen: first.elapsed(),
+                    last_seen: last.elapsed(),
+                })
+            })
+            .collect();
+        stats.sort_by_key(|s| s.last_seen);
+        stats
+    }
+
     /// Print delivery stats for chunks with multiple deliveries.
     pub fn print_stats(&self) {
         use std::collections::HashSet;
@@ -107,3 +161,31 @@ impl DeliveryTracker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_counts_paths_and_duplicates() {
+        let tracker = DeliveryTracker::new();
+        tracker.record([1u8; 32], "a".into());
+        tracker.record([1u8; 32], "b".into());
+        tracker.record([1u8; 32], "a".into());
+        tracker.record([2u8; 32], "a".into());
+
+        let stats = tracker.stats();
+        assert_eq!(stats.len(), 2);
+
+        let multi = stats.iter().find(|s| s.content_hash == [1u8; 32]).unwrap();
+        assert_eq!(multi.paths, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(multi.deliveries, 3);
+        assert_eq!(multi.duplicates(), 2);
+        assert!(multi.is_multipath());
+        assert!(multi.first_seen >= multi.last_seen);
+
+        let single = stats.iter().find(|s| s.content_hash == [2u8; 32]).unwrap();
+        assert_eq!(single.duplicates(), 0);
+        assert!(!single.is_multipath());
+    }
+}
@@ -0,0 +1,329 @@
+//! Routes incoming chunks to the appropriate service.
+//!
+//! Services are registered once, by whoever assembles the node — the daemon
+//! or an embedding application — and then looked up by the chunk's
+//! `schema_id`, falling back to its `type_tag`:
+//!
+//! ```ignore
+//! let mut dispatcher = ServiceDispatcher::new();
+//! dispatcher
+//!     .register(reassembler)
+//!     .register(messaging)
+//!     .register_by_schema(my_schema_id, my_plugin);
+//! ```
+//!
+//! A registered service can be switched off and on again at runtime with
+//! `set_enabled`; while off, chunks that would open new work (see
+//! `ChunkService::opens_work`) are dropped as if unrouted, and the rest
+//! still reach it so work in hand can finish.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use summit_core::wire::{ChunkHeader, ServiceHash, CHUNK_VERSION};
+
+use crate::chunk_types::IncomingChunk;
+use crate::service::ChunkService;
+
+/// Maps schema_ids and type_tags to services and dispatches incoming chunks.
+#[derive(Default)]
+pub struct ServiceDispatcher {
+    /// schema_id -> service. Multiple schema_ids can map to one service.
+    by_schema: HashMap<[u8; 32], Arc<dyn ChunkService>>,
+    /// type_tag -> service, for chunks whose schema_id matched nothing.
+    by_type_tag: HashMap<u16, Arc<dyn ChunkService>>,
+    /// All registered services by service hash (for activate/deactivate).
+    services: HashMap<ServiceHash, Arc<dyn ChunkService>>,
+    /// Services switched off at runtime, by service hash.
+    disabled: RwLock<HashSet<ServiceHash>>,
+}
+
+impl ServiceDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a service under every schema and type tag it declares in
+    /// `ChunkService::schema_ids` and `ChunkService::type_tags`.
+    pub fn register(&mut self, service: Arc<dyn ChunkService>) -> &mut Self {
+        for schema_id in service.schema_ids() {
+            self.by_schema.insert(schema_id, service.clone());
+        }
+        for type_tag in service.type_tags() {
+            self.by_type_tag.insert(type_tag, service.clone());
+        }
+        self.services.insert(service.service_hash(), service);
+        self
+    }
+
+    /// Route one more schema_id to a service. A later registration for the
+    /// same schema_id replaces the earlier one.
+    pub fn register_by_schema(
+        &mut self,
+        schema_id: [u8; 32],
+        service: Arc<dyn ChunkService>,
+    ) -> &mut Self {
+        self.by_schema.insert(schema_id, service.clone());
+        self.services
+            .entry(service.service_hash())
+            .or_insert(service);
+        self
+    }
+
+    /// Route a type_tag to a service when the chunk's schema_id is not
+    /// registered.
+    pub fn register_by_type_tag(
+        &mut self,
+        type_tag: u16,
+        service: Arc<dyn ChunkService>,
+    ) -> &mut Self {
+        self.by_type_tag.insert(type_tag, service.clone());
+        self.services
+            .entry(service.service_hash())
+            .or_insert(service);
+        self
+    }
+
+    /// The service a chunk would be routed to, if any.
+    pub fn route(&self, schema_id: &[u8; 32], type_tag: u16) -> Option<&Arc<dyn ChunkService>> {
+        self.by_schema
+            .get(schema_id)
+            .or_else(|| self.by_type_tag.get(&type_tag))
+    }
+
+    /// Whether a chunk would be routed to a service that executes on the
+    /// sender's behalf (see `ChunkService::executes`).
+    pub fn executes(&self, schema_id: &[u8; 32], type_tag: u16) -> bool {
+        self.route(schema_id, type_tag)
+            .is_some_and(|service| service.executes())
+    }
+
+    /// Stop or resume routing chunks that open new work to the service with
+    /// this hash. Work the service already has in hand is left alone.
+    pub fn set_enabled(&self, service: ServiceHash, enabled: bool) {
+        let mut disabled = self.disabled.write().unwrap();
+        if enabled {
+            disabled.remove(&service);
+        } else {
+            disabled.insert(service);
+        }
+    }
+
+    /// False while the service is switched off with `set_enabled`.
+    pub fn is_enabled(&self, service: &ServiceHash) -> bool {
+        !self.disabled.read().unwrap().contains(service)
+    }
+
+    /// Dispatch an incoming chunk to the appropriate service.
+    /// Returns false if no service handles it, or its service is switched
+    /// off and the chunk would open new work.
+    pub fn dispatch(&self, peer_pubkey: &[u8; 32], chunk: &IncomingChunk) -> bool {
+        let Some(service) = self.route(&chunk.schema_id, chunk.type_tag) else {
+            return false;
+        };
+        let header = ChunkHeader {
+            content_hash: chunk.content_hash,
+            schema_id: chunk.schema_id,
+            type_tag: chunk.type_tag,
+            length: chunk.payload.len() as u32,
+            flags: 0,
+            version: CHUNK_VERSION,
+        };
+        if !self.is_enabled(&service.service_hash()) && service.opens_work(&header, &chunk.payload)
+        {
+            return false;
+        }
+        if let Err(e) = service.handle_chunk(peer_pubkey, &header, &chunk.payload) {
+            tracing::warn!(
+                schema_id = hex::encode(chunk.schema_id),
+                type_tag = chunk.type_tag,
+                error = %e,
+                "service chunk handling failed"
+            );
+        }
+        true
+    }
+
+    /// Notify services when a session is established.
+    pub fn activate_session(&self, peer_pubkey: &[u8; 32], active_service_hashes: &[ServiceHash]) {
+        for hash in active_service_hashes {
+            if let Some(service) = self.services.get(hash) {
+                service.on_activate(peer_pubkey);
+            }
+        }
+    }
+
+    /// Notify services when a session ends.
+    pub fn deactivate_session(
+        &self,
+        peer_pubkey: &[u8; 32],
+        active_service_hashes: &[ServiceHash],
+    ) {
+        for hash in active_service_hashes {
+            if let Some(service) = self.services.get(hash) {
+                service.on_deactivate(peer_pubkey);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use std::sync::Mutex;
+    use summit_core::wire::{service_hash, Contract};
+
+    /// Records the type_tag of every chunk it handles. Chunks tagged
+    /// `continuing` carry on work in hand rather than opening new work.
+    struct Recorder {
+        name: &'static [u8],
+        tags: Vec<u16>,
+        continuing: Option<u16>,
+        seen: Mutex<Vec<u16>>,
+    }
+
+    impl Recorder {
+        fn new(name: &'static [u8]) -> Arc<Self> {
+            Self::with_tags(name, Vec::new())
+        }
+
+        fn with_tags(name: &'static [u8], tags: Vec<u16>) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                tags,
+                continuing: None,
+                seen: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn seen(&self) -> Vec<u16> {
+            self.seen.lock().unwrap().clone()
+        }
+    }
+
+    impl ChunkService for Recorder {
+        fn service_hash(&self) -> ServiceHash {
+            service_hash(self.name)
+        }
+
+        fn contract(&self) -> Contract {
+            Contract::Bulk
+        }
+
+        fn type_tags(&self) -> Vec<u16> {
+            self.tags.clone()
+        }
+
+        fn opens_work(&self, header: &ChunkHeader, _payload: &[u8]) -> bool {
+            self.continuing != Some(header.type_tag)
+        }
+
+        fn on_activate(&self, _peer_pubkey: &[u8; 32]) {}
+
+        fn on_deactivate(&self, _peer_pubkey: &[u8; 32]) {}
+
+        fn handle_chunk(
+            &self,
+            _peer_pubkey: &[u8; 32],
+            header: &ChunkHeader,
+            _payload: &[u8],
+        ) -> anyhow::Result<()> {
+            self.seen.lock().unwrap().push(header.type_tag);
+            Ok(())
+        }
+    }
+
+    fn chunk(schema_id: [u8; 32], type_tag: u16) -> IncomingChunk {
+        IncomingChunk {
+            content_hash: [0u8; 32],
+            type_tag,
+            schema_id,
+            payload: Bytes::from_static(b"x"),
+            targeted: false,
+        }
+    }
+
+    #[test]
+    fn routes_by_service_hash_and_extra_schema() {
+        let plugin = Recorder::new(b"test.plugin");
+        let extra = [7u8; 32];
+        let mut dispatcher = ServiceDispatcher::new();
+        dispatcher
+            .register(plugin.clone())
+            .register_by_schema(extra, plugin.clone());
+
+        let peer = [1u8; 32];
+        assert!(dispatcher.dispatch(&peer, &chunk(service_hash(b"test.plugin"), 1)));
+        assert!(dispatcher.dispatch(&peer, &chunk(extra, 2)));
+        assert!(!dispatcher.dispatch(&peer, &chunk([9u8; 32], 3)));
+        assert_eq!(plugin.seen(), vec![1, 2]);
+    }
+
+    #[test]
+    fn schema_routing_takes_precedence_over_type_tag() {
+        let by_schema = Recorder::new(b"test.schema");
+        let by_tag = Recorder::new(b"test.tag");
+        let mut dispatcher = ServiceDispatcher::new();
+        dispatcher
+            .register(by_schema.clone())
+            .register_by_type_tag(5, by_tag.clone());
+
+        let peer = [1u8; 32];
+        assert!(dispatcher.dispatch(&peer, &chunk(service_hash(b"test.schema"), 5)));
+        assert!(dispatcher.dispatch(&peer, &chunk([0u8; 32], 5)));
+        assert!(!dispatcher.dispatch(&peer, &chunk([0u8; 32], 6)));
+        assert_eq!(by_schema.seen(), vec![5]);
+        assert_eq!(by_tag.seen(), vec![5]);
+    }
+
+    #[test]
+    fn register_routes_declared_type_tags() {
+        let files = Recorder::with_tags(b"test.files", vec![2, 3]);
+        let mut dispatcher = ServiceDispatcher::new();
+        dispatcher.register(files.clone());
+
+        let peer = [1u8; 32];
+        assert!(dispatcher.dispatch(&peer, &chunk([0u8; 32], 2)));
+        assert!(dispatcher.dispatch(&peer, &chunk([0u8; 32], 3)));
+        assert!(!dispatcher.dispatch(&peer, &chunk([0u8; 32], 4)));
+        assert_eq!(files.seen(), vec![2, 3]);
+    }
+
+    #[test]
+    fn disabled_service_gets_no_chunks_until_reenabled() {
+        let files = Recorder::with_tags(b"test.files", vec![2]);
+        let mut dispatcher = ServiceDispatcher::new();
+        dispatcher.register(files.clone());
+
+        let peer = [1u8; 32];
+        let hash = service_hash(b"test.files");
+        dispatcher.set_enabled(hash, false);
+        assert!(!dispatcher.is_enabled(&hash));
+        assert!(!dispatcher.dispatch(&peer, &chunk(hash, 2)));
+        assert!(!dispatcher.dispatch(&peer, &chunk([0u8; 32], 2)));
+        assert!(files.seen().is_empty());
+
+        dispatcher.set_enabled(hash, true);
+        assert!(dispatcher.dispatch(&peer, &chunk([0u8; 32], 2)));
+        assert_eq!(files.seen(), vec![2]);
+    }
+
+    #[test]
+    fn disabled_service_still_gets_chunks_for_work_in_hand() {
+        let files = Arc::new(Recorder {
+            name: b"test.files",
+            tags: vec![2, 3],
+            continuing: Some(2),
+            seen: Mutex::new(Vec::new()),
+        });
+        let mut dispatcher = ServiceDispatcher::new();
+        dispatcher.register(files.clone());
+
+        let peer = [1u8; 32];
+        dispatcher.set_enabled(service_hash(b"test.files"), false);
+        assert!(!dispatcher.dispatch(&peer, &chunk([0u8; 32], 3)));
+        assert!(dispatcher.dispatch(&peer, &chunk([0u8; 32], 2)));
+        assert_eq!(files.seen(), vec![2]);
+    }
+}
@@ -1,29 +1,176 @@
 //! File transfer — chunking, reassembly, and metadata.
+//!
+//! A receiver with a chunk cache answers each file's metadata with a HAVE
+//! bitmap of the chunks it already holds; those are filled in from the
+//! cache and the sender skips them. Only a trusted sender is answered, and
+//! only about chunks it could NACK from us, so a HAVE is no way to probe
+//! the cache. Re-sending a file a peer mostly has
+//! costs little more than the metadata.
+//!
+//! A file sent with FEC carries Reed-Solomon parity chunks after its data
+//! chunks. The receiver rebuilds lost data chunks from them as soon as
+//! enough of a stripe has arrived, so moderate loss costs no NACK round trip.
+//!
+//! An assembly whose NACKs go unanswered `MAX_NACK_STALLS` times in a row
+//! is abandoned as soon as the last backoff runs out, rather than left for
+//! the stale timer, and a `FileAbandoned` event goes to subscribers.
 
 use anyhow::{Context, Result};
 use bytes::Bytes;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use summit_core::config::OnConflict;
+use summit_core::crypto::StorageCipher;
+use summit_core::recovery::Have;
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
 
+use crate::cache::ChunkCache;
 use crate::chunk_types::OutgoingChunk;
+use crate::compute_store::ComputeStore;
+use crate::compute_types::short_id;
 use crate::schema::KnownSchema;
+use crate::send_target::SendTarget;
+use crate::trust::TrustRegistry;
 
 /// Maximum chunk payload size (before encryption overhead)
 pub const MAX_CHUNK_SIZE: usize = 32 * 1024; // 32KB
 
+/// Size of the `chunk_index` prefix on every file data chunk.
+pub const CHUNK_INDEX_SIZE: usize = 4;
+
 /// File metadata — sent as the first chunk of a file transfer
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct FileMetadata {
     pub filename: String,
     pub total_bytes: u64,
+    /// Content hash of each data chunk payload (index prefix included), in
+    /// file order. Identical file blocks still hash differently by position.
     pub chunk_hashes: Vec<[u8; 32]>,
+    /// Set when the file is an output of a compute task we submitted. The
+    /// receiver stores it under `compute/<task>/` instead of alongside
+    /// ordinary transfers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    /// Parity sent with the file, if the sender asked for FEC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fec: Option<FecParams>,
+}
+
+/// How a file's data chunks are protected by parity chunks.
+///
+/// Data chunks are taken `data_shards` at a time in file order; each such
+/// stripe (the last may be shorter) gets `parity_shards` parity chunks.
+/// Parity chunks are file data chunks whose `chunk_index` continues past
+/// the last data chunk, stripe by stripe.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct FecParams {
+    pub data_shards: u16,
+    pub parity_shards: u16,
+    /// Content hash of each parity chunk payload, in `chunk_index` order.
+    pub parity_hashes: Vec<[u8; 32]>,
+}
+
+/// Stripe shape a file is sent with: `data_shards` data chunks to every
+/// `parity_shards` parity chunks. Any `data_shards` chunks of a stripe
+/// rebuild it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FecScheme {
+    pub data_shards: u16,
+    pub parity_shards: u16,
+}
+
+impl Default for FecScheme {
+    /// 25% overhead, surviving the loss of any 4 chunks in 16.
+    fn default() -> Self {
+        Self {
+            data_shards: 16,
+            parity_shards: 4,
+        }
+    }
+}
+
+impl FecScheme {
+    pub fn new(data_shards: u16, parity_shards: u16) -> Result<Self> {
+        if data_shards == 0 || parity_shards == 0 {
+            anyhow::bail!("FEC needs at least one data and one parity chunk per stripe");
+        }
+        let total = data_shards as usize + parity_shards as usize;
+        if total > summit_core::fec::MAX_SHARDS {
+            anyhow::bail!(
+                "FEC stripe of {total} chunks exceeds the maximum of {}",
+                summit_core::fec::MAX_SHARDS
+            );
+        }
+        Ok(Self {
+            data_shards,
+            parity_shards,
+        })
+    }
+
+    /// Parity chunks a file of `data_chunks` chunks is sent with.
+    pub fn parity_chunks(&self, data_chunks: usize) -> usize {
+        data_chunks.div_ceil(self.data_shards as usize) * self.parity_shards as usize
+    }
+}
+
+impl std::str::FromStr for FecScheme {
+    type Err = anyhow::Error;
+
+    /// `K:M` — K data chunks to M parity chunks.
+    fn from_str(s: &str) -> Result<Self> {
+        let (data, parity) = s
+            .split_once(':')
+            .context("FEC scheme must be DATA:PARITY, e.g. 16:4")?;
+        Self::new(
+            data.trim()
+                .parse()
+                .context("invalid FEC data chunk count")?,
+            parity
+                .trim()
+                .parse()
+                .context("invalid FEC parity chunk count")?,
+        )
+    }
+}
+
+/// Build a file data chunk payload: `chunk_index` (u32 LE) followed by the data.
+pub fn encode_data_chunk(chunk_index: u32, data: &[u8]) -> Bytes {
+    let mut payload = Vec::with_capacity(CHUNK_INDEX_SIZE + data.len());
+    payload.extend_from_slice(&chunk_index.to_le_bytes());
+    payload.extend_from_slice(data);
+    Bytes::from(payload)
+}
+
+/// Split a file data chunk payload into its `chunk_index` and data.
+pub fn decode_data_chunk(payload: &[u8]) -> Option<(u32, &[u8])> {
+    let (index, data) = payload.split_first_chunk::<CHUNK_INDEX_SIZE>()?;
+    Some((u32::from_le_bytes(*index), data))
 }
 
 /// Chunk a file into multiple OutgoingChunks
 pub fn chunk_file(path: &std::path::Path) -> Result<Vec<OutgoingChunk>> {
+    chunk_file_with(path, None, None)
+}
+
+/// Chunk a file as `chunk_file` does, followed by parity chunks for `scheme`.
+pub fn chunk_file_fec(path: &std::path::Path, scheme: FecScheme) -> Result<Vec<OutgoingChunk>> {
+    chunk_file_with(path, None, Some(scheme))
+}
+
+/// Chunk an output file of compute task `task_id` for return to its submitter.
+pub fn chunk_task_output(path: &std::path::Path, task_id: &str) -> Result<Vec<OutgoingChunk>> {
+    chunk_file_with(path, Some(task_id.to_string()), None)
+}
+
+fn chunk_file_with(
+    path: &std::path::Path,
+    task_id: Option<String>,
+    fec: Option<FecScheme>,
+) -> Result<Vec<OutgoingChunk>> {
     let data =
         std::fs::read(path).with_context(|| format!("failed to read file: {}", path.display()))?;
 
@@ -37,23 +184,52 @@ pub fn chunk_file(path: &std::path::Path) -> Result<Vec<OutgoingChunk>> {
     let mut chunk_hashes = Vec::new();
 
     // Split file into data chunks
-    for chunk_data in data.chunks(MAX_CHUNK_SIZE) {
-        let content_hash = summit_core::crypto::hash(chunk_data);
-        chunk_hashes.push(content_hash);
+    for (index, chunk_data) in data.chunks(MAX_CHUNK_SIZE).enumerate() {
+        let payload = encode_data_chunk(index as u32, chunk_data);
+        chunk_hashes.push(summit_core::crypto::hash(&payload));
 
         chunks.push(OutgoingChunk {
             type_tag: 2, // File data chunk
             schema_id: KnownSchema::FileData.id(),
-            payload: Bytes::copy_from_slice(chunk_data),
+            payload,
             priority_flags: 0x02, // Bulk
         });
     }
 
+    // Parity chunks follow the data, indexed on from the last data chunk
+    let fec = match fec {
+        Some(scheme) => {
+            let blocks: Vec<&[u8]> = data.chunks(MAX_CHUNK_SIZE).collect();
+            let mut parity_hashes = Vec::new();
+            for group in blocks.chunks(scheme.data_shards as usize) {
+                for shard in summit_core::fec::encode(group, scheme.parity_shards as usize)? {
+                    let index = blocks.len() + parity_hashes.len();
+                    let payload = encode_data_chunk(index as u32, &shard);
+                    parity_hashes.push(summit_core::crypto::hash(&payload));
+                    chunks.push(OutgoingChunk {
+                        type_tag: 2, // File data chunk
+                        schema_id: KnownSchema::FileData.id(),
+                        payload,
+                        priority_flags: 0x02, // Bulk
+                    });
+                }
+            }
+            Some(FecParams {
+                data_shards: scheme.data_shards,
+                parity_shards: scheme.parity_shards,
+                parity_hashes,
+            })
+        }
+        None => None,
+    };
+
     // Create metadata chunk (goes first)
     let metadata = FileMetadata {
         filename,
         total_bytes: data.len() as u64,
         chunk_hashes: chunk_hashes.clone(),
+        task_id,
+        fec,
     };
 
     let metadata_bytes = serde_json::to_vec(&metadata)?;
@@ -70,22 +246,255 @@ pub fn chunk_file(path: &std::path::Path) -> Result<Vec<OutgoingChunk>> {
     Ok(chunks)
 }
 
+/// Senders waiting for a HAVE, keyed by (peer, metadata chunk hash).
+type HaveWaiters = DashMap<([u8; 32], [u8; 32]), oneshot::Sender<Have>>;
+
+type Dedup = (
+    ChunkCache,
+    mpsc::Sender<(SendTarget, OutgoingChunk)>,
+    TrustRegistry,
+);
+
 /// Tracks files being reassembled from incoming chunks
 pub struct FileReassembler {
     /// In-progress file reassembly state
-    active: Arc<Mutex<HashMap<String, FileAssembly>>>,
+    active: Arc<Mutex<HashMap<AssemblyKey, FileAssembly>>>,
     /// Where to write completed files
     output_dir: PathBuf,
+    /// Compute tasks we submitted. Task output files are only accepted when
+    /// set, and only from the task's worker.
+    compute: Option<ComputeStore>,
+    /// Receiver side: cache checked for a file's chunks when its metadata
+    /// arrives, the queue the HAVE reply goes out on, and who may get one.
+    dedup: Option<Dedup>,
+    /// Sender side: sends waiting for a HAVE, by (peer, metadata hash).
+    have_waiters: Arc<HaveWaiters>,
+    /// Seals completed files before they hit the disk. None = plaintext.
+    cipher: Option<Arc<StorageCipher>>,
+    /// Cap on `active`. 0 = unlimited.
+    max_assemblies: usize,
+    /// A completed file whose name is already taken in `output_dir`.
+    on_conflict: OnConflict,
+    /// Abandoned assemblies, pushed to /events.
+    abandoned: broadcast::Sender<FileAbandoned>,
+    /// Data chunks that arrived behind a later chunk of their file.
+    reordered: Arc<AtomicU64>,
+}
+
+/// An assembly's sender and its path relative to `output_dir`. Two peers
+/// sending files of the same name at once get one assembly each.
+type AssemblyKey = ([u8; 32], String);
+
+/// What became of a file once its last chunk arrived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Written {
+    /// Written to this path.
+    Stored(PathBuf),
+    /// Dropped: the name was taken by a different file and `on_conflict`
+    /// is `Reject`.
+    Rejected,
+}
+
+impl Written {
+    /// Where the file was written, if it was.
+    pub fn into_path(self) -> Option<PathBuf> {
+        match self {
+            Written::Stored(path) => Some(path),
+            Written::Rejected => None,
+        }
+    }
+}
+
+/// Undelivered abandonment events kept per subscriber.
+const EVENT_CAPACITY: usize = 64;
+
+/// A file given up on with chunks still missing.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileAbandoned {
+    pub filename: String,
+    /// Sender public key, hex-encoded.
+    pub sender: String,
+    pub chunks_missing: usize,
+    pub chunks_total: usize,
+    /// Unix timestamp in milliseconds.
+    pub timestamp: u64,
 }
 
 struct FileAssembly {
     metadata: FileMetadata,
-    chunks_received: HashMap<[u8; 32], Bytes>,
+    /// Received data, indexed by chunk_index.
+    chunks: Vec<Option<Bytes>>,
+    /// Received parity, indexed by chunk_index less the data chunk count.
+    parity: Vec<Option<Bytes>>,
+    chunks_received: usize,
+    /// Highest data chunk index received so far.
+    highest_index: Option<usize>,
+    /// Data chunks received below `highest_index`.
+    reordered: usize,
     started_at: Instant,
     last_chunk_at: Instant,
     nack_count: u8,
     sender_pubkey: [u8; 32],
     missing_at_last_nack: usize,
+    /// When the most recent NACK went out.
+    last_nack_at: Option<Instant>,
+    /// Hashes requested by the most recent NACK still awaiting an RTT sample.
+    /// Cleared once the first of them arrives.
+    nack_pending: HashSet<[u8; 32]>,
+    /// Smoothed NACK → retransmission round-trip for this assembly.
+    srtt: Option<Duration>,
+}
+
+/// Exponential NACK backoff bounds.
+///
+/// The base delay is twice the assembly's smoothed NACK round-trip (or
+/// `min` before any sample exists), doubled for each attempt without
+/// progress and capped at `max`.
+#[derive(Debug, Clone, Copy)]
+pub struct NackBackoff {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl NackBackoff {
+    /// How long to wait before NACK attempt `attempt`.
+    pub fn delay(&self, srtt: Option<Duration>, attempt: u8) -> Duration {
+        let base = srtt
+            .map(|rtt| rtt * 2)
+            .unwrap_or(self.min)
+            .clamp(self.min, self.max);
+        base.saturating_mul(1u32 << attempt.min(16)).min(self.max)
+    }
+}
+
+impl FileAssembly {
+    fn new(metadata: FileMetadata, sender_pubkey: [u8; 32]) -> Self {
+        let now = Instant::now();
+        Self {
+            chunks: vec![None; metadata.chunk_hashes.len()],
+            parity: vec![None; metadata.fec.as_ref().map_or(0, |f| f.parity_hashes.len())],
+            chunks_received: 0,
+            highest_index: None,
+            reordered: 0,
+            metadata,
+            started_at: now,
+            last_chunk_at: now,
+            nack_count: 0,
+            sender_pubkey,
+            missing_at_last_nack: 0,
+            last_nack_at: None,
+            nack_pending: HashSet::new(),
+            srtt: None,
+        }
+    }
+
+    /// The content hash expected at `index`, a data or parity chunk.
+    fn expected_hash(&self, index: usize) -> Option<&[u8; 32]> {
+        match index.checked_sub(self.chunks.len()) {
+            None => self.metadata.chunk_hashes.get(index),
+            Some(parity) => self.metadata.fec.as_ref()?.parity_hashes.get(parity),
+        }
+    }
+
+    /// Data bytes in chunk `index`: all but the last are full.
+    fn chunk_len(&self, index: usize) -> usize {
+        (self.metadata.total_bytes as usize)
+            .saturating_sub(index * MAX_CHUNK_SIZE)
+            .min(MAX_CHUNK_SIZE)
+    }
+
+    /// Rebuild the missing data chunks in the stripe of chunk `index` from
+    /// its parity, once enough of the stripe has arrived. Rebuilt chunks
+    /// are checked against the metadata hashes. Returns how many were filled.
+    fn recover_stripe(&mut self, index: usize) -> usize {
+        let Some(fec) = &self.metadata.fec else {
+            return 0;
+        };
+        let (k, m) = (fec.data_shards as usize, fec.parity_shards as usize);
+        let n = self.chunks.len();
+        let stripe = match index.checked_sub(n) {
+            None => index / k,
+            Some(parity) => parity / m,
+        };
+        let data = stripe * k..(stripe * k + k).min(n);
+        let parity = stripe * m..(stripe * m + m).min(self.parity.len());
+        let received = self.chunks[data.clone()].iter().flatten().count();
+        let parity_received = self.parity[parity.clone()].iter().flatten().count();
+        if received == data.len() || received + parity_received < data.len() {
+            return 0;
+        }
+
+        // Data chunks are zero-padded to the stripe's first (longest) one.
+        let len = self.chunk_len(data.start);
+        let mut shards: Vec<Option<Vec<u8>>> = self.chunks[data.clone()]
+            .iter()
+            .map(|c| {
+                c.as_ref().map(|b| {
+                    let mut shard = b.to_vec();
+                    shard.resize(len, 0);
+                    shard
+                })
+            })
+            .chain(
+                self.parity[parity]
+                    .iter()
+                    .map(|p| p.as_ref().map(|b| b.to_vec())),
+            )
+            .collect();
+        if let Err(e) = summit_core::fec::reconstruct(&mut shards, data.len()) {
+            tracing::warn!(filename = %self.metadata.filename, stripe, error = %e, "FEC recovery failed");
+            return 0;
+        }
+
+        let mut rebuilt = 0;
+        for (i, shard) in data.zip(shards) {
+            let Some(mut shard) = shard.filter(|_| self.chunks[i].is_none()) else {
+                continue;
+            };
+            shard.truncate(self.chunk_len(i));
+            let payload = encode_data_chunk(i as u32, &shard);
+            if summit_core::crypto::hash(&payload) != self.metadata.chunk_hashes[i] {
+                tracing::warn!(
+                    filename = %self.metadata.filename,
+                    chunk_index = i,
+                    "chunk rebuilt from parity fails its hash, discarding"
+                );
+                return rebuilt;
+            }
+            self.chunks[i] = Some(payload.slice(CHUNK_INDEX_SIZE..));
+            self.chunks_received += 1;
+            rebuilt += 1;
+        }
+        rebuilt
+    }
+
+    /// Content hashes of the chunks not yet received.
+    fn missing(&self) -> Vec<[u8; 32]> {
+        self.metadata
+            .chunk_hashes
+            .iter()
+            .zip(&self.chunks)
+            .filter(|(_, slot)| slot.is_none())
+            .map(|(hash, _)| *hash)
+            .collect()
+    }
+
+    /// Fold a NACK round-trip sample into `srtt` when the first chunk
+    /// requested by the last NACK arrives (TCP-style 7/8 smoothing).
+    fn sample_nack_rtt(&mut self, content_hash: &[u8; 32]) {
+        if !self.nack_pending.contains(content_hash) {
+            return;
+        }
+        self.nack_pending.clear();
+        let Some(sent_at) = self.last_nack_at else {
+            return;
+        };
+        let sample = sent_at.elapsed();
+        self.srtt = Some(match self.srtt {
+            Some(srtt) => (srtt * 7 + sample) / 8,
+            None => sample,
+        });
+    }
 }
 
 /// Info about a stalled file assembly, returned by `stalled_assemblies()`.
@@ -94,48 +503,287 @@ pub struct StalledAssembly {
     pub missing: Vec<[u8; 32]>,
     pub attempt: u8,
     pub sender_pubkey: [u8; 32],
+    /// Smoothed NACK round-trip, if any NACK for this file was answered.
+    pub srtt: Option<Duration>,
 }
 
 /// Maximum age for an in-progress file assembly before it is considered stale.
 const ASSEMBLY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
 
+/// Renamed copies tried for a taken file name before the file is dropped.
+const MAX_RENAMES: u32 = 100;
+
 impl FileReassembler {
     pub fn new(output_dir: PathBuf) -> Self {
         std::fs::create_dir_all(&output_dir).ok();
         Self {
             active: Arc::new(Mutex::new(HashMap::new())),
             output_dir,
+            compute: None,
+            dedup: None,
+            have_waiters: Arc::new(DashMap::new()),
+            cipher: None,
+            max_assemblies: 0,
+            on_conflict: OnConflict::Rename,
+            abandoned: broadcast::channel(EVENT_CAPACITY).0,
+            reordered: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Fill chunks already in `cache` as soon as a file's metadata arrives,
+    /// and tell a sender `trust` trusts which ones with a HAVE sent on
+    /// `chunk_tx`.
+    pub fn with_dedup(
+        mut self,
+        cache: ChunkCache,
+        chunk_tx: mpsc::Sender<(SendTarget, OutgoingChunk)>,
+        trust: TrustRegistry,
+    ) -> Self {
+        self.dedup = Some((cache, chunk_tx, trust));
+        self
+    }
+
+    /// Sender side: register for `peer`'s HAVE answering the metadata chunk
+    /// `metadata_hash`. Call `forget_have` if you stop waiting.
+    pub fn expect_have(&self, peer: [u8; 32], metadata_hash: [u8; 32]) -> oneshot::Receiver<Have> {
+        let (tx, rx) = oneshot::channel();
+        self.have_waiters.insert((peer, metadata_hash), tx);
+        rx
+    }
+
+    /// Stop waiting for a HAVE registered with `expect_have`.
+    pub fn forget_have(&self, peer: [u8; 32], metadata_hash: [u8; 32]) {
+        self.have_waiters.remove(&(peer, metadata_hash));
+    }
+
+    /// Hand a HAVE received from `peer` to the send waiting for it.
+    /// Unsolicited or late answers are dropped.
+    pub fn deliver_have(&self, peer: [u8; 32], have: Have) {
+        match self.have_waiters.remove(&(peer, have.metadata_hash)) {
+            Some((_, tx)) => {
+                let _ = tx.send(have);
+            }
+            None => tracing::debug!(
+                peer = hex::encode(&peer[..8]),
+                "HAVE for no pending send, ignoring"
+            ),
         }
     }
 
+    /// Accept output files of compute tasks tracked in `store`, recording
+    /// each received file on its task.
+    pub fn with_compute(mut self, store: ComputeStore) -> Self {
+        self.compute = Some(store);
+        self
+    }
+
+    /// Reassemble at most `max` files at once; metadata for more is dropped
+    /// so the ones under way can finish. 0 = unlimited.
+    pub fn with_max_assemblies(mut self, max: usize) -> Self {
+        self.max_assemblies = max;
+        self
+    }
+
+    /// The assembly cap. 0 = unlimited.
+    pub fn max_assemblies(&self) -> usize {
+        self.max_assemblies
+    }
+
+    /// What to do when a completed file's name is already taken. Default
+    /// `Rename`.
+    pub fn with_on_conflict(mut self, policy: OnConflict) -> Self {
+        self.on_conflict = policy;
+        self
+    }
+
+    /// Encrypt completed files on disk. Read them back with `read_received`.
+    pub fn with_encryption(mut self, cipher: StorageCipher) -> Self {
+        self.cipher = Some(Arc::new(cipher));
+        self
+    }
+
+    /// Read a received file, `name` relative to the output directory,
+    /// decrypting it if it was written sealed. Files written before
+    /// encryption was enabled are returned as they are.
+    ///
+    /// Errors are `InvalidInput` for a name that escapes the output
+    /// directory, `NotFound`, or `InvalidData` when decryption fails.
+    pub fn read_received(&self, name: &str) -> std::io::Result<Vec<u8>> {
+        use std::io::{Error, ErrorKind};
+
+        let relative = Path::new(name);
+        if name.is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid file name {name:?}"),
+            ));
+        }
+        let data = std::fs::read(self.output_dir.join(relative))?;
+        if !StorageCipher::is_sealed(&data) {
+            return Ok(data);
+        }
+        let cipher = self.cipher.as_ref().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{name:?} is encrypted but storage.encrypt_at_rest is off"),
+            )
+        })?;
+        cipher
+            .open(&data)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{name:?}: {e}")))
+    }
+
     /// Process a metadata chunk — start tracking this file.
     ///
     /// `sender_pubkey` is the peer that sent the metadata, used for targeted
     /// NACK recovery (attempt 0 goes to the original sender).
-    pub async fn add_metadata(&self, metadata: FileMetadata, sender_pubkey: [u8; 32]) {
+    ///
+    /// With a dedup cache, chunks it holds are filled in straight away (and
+    /// the file written if that completes it). Returns which chunks were
+    /// already held, or `None` if the file was rejected.
+    pub async fn add_metadata(
+        &self,
+        metadata: FileMetadata,
+        sender_pubkey: [u8; 32],
+    ) -> Option<Vec<bool>> {
         let mut metadata = metadata;
         metadata.filename = sanitize_filename(&metadata.filename);
+        if let Some(fec) = &metadata.fec {
+            let consistent = FecScheme::new(fec.data_shards, fec.parity_shards).is_ok_and(|s| {
+                s.parity_chunks(metadata.chunk_hashes.len()) == fec.parity_hashes.len()
+            });
+            if !consistent {
+                // The data chunks alone still make the file; NACKs fill gaps.
+                tracing::warn!(
+                    filename = %metadata.filename,
+                    "ignoring inconsistent FEC parameters"
+                );
+                metadata.fec = None;
+            }
+        }
+
+        let path = match &metadata.task_id {
+            None => metadata.filename.clone(),
+            // Task ids are hex digests; anything else is not one of ours,
+            // and must not reach a log line's slice or the path below.
+            Some(task_id) if !task_id.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                tracing::warn!(
+                    filename = %metadata.filename,
+                    "ignoring output file with a malformed task id"
+                );
+                return None;
+            }
+            Some(task_id) => {
+                let ours = self.compute.as_ref().and_then(|s| s.get_task(task_id));
+                if !ours.is_some_and(|t| t.local && t.peer_pubkey == sender_pubkey) {
+                    tracing::warn!(
+                        filename = %metadata.filename,
+                        task_id = short_id(task_id),
+                        "ignoring output file for a task not submitted to this peer"
+                    );
+                    return None;
+                }
+                format!(
+                    "compute/{}/{}",
+                    sanitize_filename(short_id(task_id)),
+                    metadata.filename
+                )
+            }
+        };
+
+        let mut assembly = FileAssembly::new(metadata, sender_pubkey);
+        if let Some((cache, _, _)) = &self.dedup {
+            for (index, hash) in assembly.metadata.chunk_hashes.iter().enumerate() {
+                let Ok(Some(payload)) = cache.get(hash) else {
+                    continue;
+                };
+                if payload.len() >= CHUNK_INDEX_SIZE {
+                    assembly.chunks[index] = Some(payload.slice(CHUNK_INDEX_SIZE..));
+                    assembly.chunks_received += 1;
+                }
+            }
+        }
+        let held = assembly.chunks.iter().map(Option::is_some).collect();
+
+        if assembly.chunks_received > 0 && assembly.chunks_received == assembly.chunks.len() {
+            tracing::info!(
+                filename = %assembly.metadata.filename,
+                chunks = assembly.chunks.len(),
+                "every chunk already cached"
+            );
+            if let Err(e) = self.write_file(&path, &assembly) {
+                tracing::warn!(filename = %path, error = %e, "failed to write cached file");
+            }
+            return Some(held);
+        }
 
+        let key = (sender_pubkey, path);
         let mut active = self.active.lock().await;
         Self::cleanup_stale(&mut active);
-        let now = Instant::now();
-        active.insert(
-            metadata.filename.clone(),
-            FileAssembly {
-                metadata,
-                chunks_received: HashMap::new(),
-                started_at: now,
-                last_chunk_at: now,
-                nack_count: 0,
-                sender_pubkey,
-                missing_at_last_nack: 0,
-            },
-        );
+        // A re-sent metadata chunk restarts its own assembly, so it doesn't count.
+        if self.max_assemblies > 0
+            && active.len() >= self.max_assemblies
+            && !active.contains_key(&key)
+        {
+            tracing::warn!(
+                filename = %key.1,
+                peer = hex::encode(&sender_pubkey[..8]),
+                max = self.max_assemblies,
+                "too many files in flight, dropping new transfer"
+            );
+            return None;
+        }
+        active.insert(key, assembly);
+        Some(held)
+    }
+
+    /// Send a HAVE for the metadata chunk `metadata_hash` back to `peer`,
+    /// claiming only the `held` chunks of `hashes` that `peer` may NACK.
+    /// No-op without a dedup cache or for a peer we don't trust — the
+    /// sender then times out and sends every chunk.
+    fn send_have(
+        &self,
+        peer: [u8; 32],
+        metadata_hash: [u8; 32],
+        hashes: &[[u8; 32]],
+        held: &[bool],
+    ) {
+        let Some((cache, chunk_tx, trust)) = &self.dedup else {
+            return;
+        };
+        if !trust.is_trusted(&peer) {
+            tracing::debug!(
+                peer = hex::encode(&peer[..8]),
+                "not answering metadata from untrusted peer with a HAVE"
+            );
+            return;
+        }
+        let held: Vec<bool> = held
+            .iter()
+            .zip(hashes)
+            .map(|(held, hash)| *held && cache.servable_to(hash, &peer))
+            .collect();
+        let Ok(payload) = serde_json::to_vec(&Have::new(metadata_hash, &held)) else {
+            return;
+        };
+        let chunk = OutgoingChunk {
+            type_tag: summit_core::wire::recovery::HAVE,
+            schema_id: summit_core::wire::recovery_hash(),
+            payload: Bytes::from(payload),
+            priority_flags: 0x01, // Realtime — the sender is waiting on it
+        };
+        if let Err(e) = chunk_tx.try_send((SendTarget::Peer { public_key: peer }, chunk)) {
+            tracing::warn!(error = %e, "failed to enqueue HAVE");
+        }
     }
 
     /// Remove assemblies older than `ASSEMBLY_TIMEOUT`.
-    fn cleanup_stale(active: &mut HashMap<String, FileAssembly>) {
-        active.retain(|filename, assembly| {
+    fn cleanup_stale(active: &mut HashMap<AssemblyKey, FileAssembly>) {
+        active.retain(|(_, filename), assembly| {
             let stale = assembly.started_at.elapsed() > ASSEMBLY_TIMEOUT;
             if stale {
                 tracing::warn!(filename, "removing stale file assembly (timed out)");
@@ -144,61 +792,146 @@ impl FileReassembler {
         });
     }
 
-    /// Process a data chunk — add to file assembly
-    pub async fn add_chunk(&self, content_hash: [u8; 32], data: Bytes) -> Result<Option<PathBuf>> {
+    /// Process a data chunk — add to file assembly.
+    ///
+    /// `payload` carries its `chunk_index`; the chunk is placed there when
+    /// `content_hash` matches that slot of a file's metadata. Returns what
+    /// became of the file once this chunk completes it, else `None`.
+    pub async fn add_chunk(
+        &self,
+        content_hash: [u8; 32],
+        payload: Bytes,
+    ) -> Result<Option<Written>> {
+        let Some((chunk_index, _)) = decode_data_chunk(&payload) else {
+            anyhow::bail!("file data chunk shorter than its index prefix");
+        };
+        let index = chunk_index as usize;
+        let data = payload.slice(CHUNK_INDEX_SIZE..);
+
         let mut active = self.active.lock().await;
         Self::cleanup_stale(&mut active);
-        // Find which file this chunk belongs to
-        let mut completed_file: Option<(String, PathBuf)> = None;
-
-        for (filename, assembly) in active.iter_mut() {
-            if assembly.metadata.chunk_hashes.contains(&content_hash) {
-                assembly.chunks_received.insert(content_hash, data);
-                assembly.last_chunk_at = Instant::now();
-
-                // Check if complete
-                if assembly.chunks_received.len() == assembly.metadata.chunk_hashes.len() {
-                    // Reassemble
-                    let mut file_data = Vec::new();
-                    for hash in &assembly.metadata.chunk_hashes {
-                        if let Some(chunk) = assembly.chunks_received.get(hash) {
-                            file_data.extend_from_slice(chunk);
-                        }
-                    }
-
-                    let output_path = self.output_dir.join(&assembly.metadata.filename);
-                    std::fs::write(&output_path, file_data)?;
 
-                    #[cfg(unix)]
-                    {
-                        use std::os::unix::fs::PermissionsExt;
-                        std::fs::set_permissions(
-                            &output_path,
-                            std::fs::Permissions::from_mode(0o755),
-                        )?;
-                    }
-
-                    tracing::info!(
-                        filename = %assembly.metadata.filename,
-                        bytes = assembly.metadata.total_bytes,
-                        chunks = assembly.metadata.chunk_hashes.len(),
-                                   path = %output_path.display(),
-                                   "file received and reassembled"
-                    );
+        // Find which file this chunk belongs to. `expected_hash` rejects an
+        // index beyond the file's data and parity chunks.
+        let Some(((_, filename), assembly)) = active
+            .iter_mut()
+            .find(|(_, a)| a.expected_hash(index) == Some(&content_hash))
+        else {
+            tracing::debug!(chunk_index, "data chunk matches no active file");
+            return Ok(None);
+        };
 
-                    completed_file = Some((filename.clone(), output_path));
-                    break;
+        match index.checked_sub(assembly.chunks.len()) {
+            None if assembly.chunks[index].is_none() => {
+                assembly.chunks[index] = Some(data);
+                assembly.chunks_received += 1;
+                if assembly.highest_index.is_some_and(|h| index < h) {
+                    assembly.reordered += 1;
+                    self.reordered.fetch_add(1, Ordering::Relaxed);
                 }
-                break;
+                assembly.highest_index = assembly.highest_index.max(Some(index));
+            }
+            None => {}
+            Some(parity) => {
+                assembly.parity[parity].get_or_insert(data);
             }
         }
+        assembly.last_chunk_at = Instant::now();
+        assembly.sample_nack_rtt(&content_hash);
+        let rebuilt = assembly.recover_stripe(index);
+        if rebuilt > 0 {
+            tracing::debug!(filename, rebuilt, "data chunks rebuilt from parity");
+        }
+
+        if assembly.chunks_received < assembly.chunks.len() {
+            return Ok(None);
+        }
+
+        let written = self.write_file(filename, assembly)?;
+        let key = (assembly.sender_pubkey, filename.clone());
+        active.remove(&key);
+        Ok(Some(written))
+    }
+
+    /// Write a complete assembly to `output_dir/key`, in index order.
+    fn write_file(&self, key: &str, assembly: &FileAssembly) -> Result<Written> {
+        let mut file_data = Vec::with_capacity(assembly.metadata.total_bytes as usize);
+        for chunk in assembly.chunks.iter().flatten() {
+            file_data.extend_from_slice(chunk);
+        }
+
+        let Some(key) = self.resolve_conflict(key, &file_data) else {
+            tracing::warn!(
+                filename = %key,
+                sender = hex::encode(&assembly.sender_pubkey[..8]),
+                "received file name already taken, dropping new file"
+            );
+            return Ok(Written::Rejected);
+        };
+        let output_path = self.output_dir.join(&key);
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        match &self.cipher {
+            Some(cipher) => std::fs::write(&output_path, cipher.seal(&file_data))?,
+            None => std::fs::write(&output_path, file_data)?,
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&output_path, std::fs::Permissions::from_mode(0o755))?;
+        }
+
+        tracing::info!(
+            filename = %assembly.metadata.filename,
+            bytes = assembly.metadata.total_bytes,
+            chunks = assembly.chunks.len(),
+            reordered = assembly.reordered,
+            path = %output_path.display(),
+            "file received and reassembled"
+        );
+
+        if let (Some(task_id), Some(store)) = (&assembly.metadata.task_id, &self.compute) {
+            store.add_output_file(task_id, output_path.clone());
+        }
 
-        // Remove from active after iteration complete
-        if let Some((filename, path)) = completed_file {
-            active.remove(&filename);
-            return Ok(Some(path));
+        Ok(Written::Stored(output_path))
+    }
+
+    /// The key to write `data` under when `key` may already be taken, or
+    /// `None` to drop it. A file identical to the one on disk — the same
+    /// file delivered again — reuses its name under every policy. Renaming
+    /// gives up after `MAX_RENAMES` taken names.
+    fn resolve_conflict(&self, key: &str, data: &[u8]) -> Option<String> {
+        // Only a file of the right size, plaintext or sealed, is read back.
+        let taken = |key: &str| {
+            let Ok(meta) = std::fs::metadata(self.output_dir.join(key)) else {
+                return false;
+            };
+            let same_size = [data.len(), StorageCipher::sealed_len(data.len())]
+                .contains(&(meta.len() as usize));
+            !same_size || !self.read_received(key).is_ok_and(|held| held == data)
+        };
+        if !taken(key) {
+            return Some(key.to_string());
+        }
+        match self.on_conflict {
+            OnConflict::Overwrite => Some(key.to_string()),
+            OnConflict::Reject => None,
+            OnConflict::Rename => {
+                // The number goes before the extension, if the name has one:
+                // a dot that starts the name (`.bashrc`) doesn't count.
+                let name_at = key.rfind('/').map_or(0, |slash| slash + 1);
+                let (stem, ext) = match key[name_at..].rfind('.') {
+                    Some(dot) if dot > 0 => key.split_at(name_at + dot),
+                    _ => (key, ""),
+                };
+                (1..=MAX_RENAMES)
+                    .map(|n| format!("{stem}-{n}{ext}"))
+                    .find(|candidate| !taken(candidate))
+            }
         }
-        Ok(None)
     }
 
     /// Clone the inner state (for use in sync-to-async bridges).
@@ -206,12 +939,32 @@ impl FileReassembler {
         FileReassembler {
             active: self.active.clone(),
             output_dir: self.output_dir.clone(),
+            compute: self.compute.clone(),
+            dedup: self.dedup.clone(),
+            have_waiters: self.have_waiters.clone(),
+            cipher: self.cipher.clone(),
+            max_assemblies: self.max_assemblies,
+            on_conflict: self.on_conflict,
+            abandoned: self.abandoned.clone(),
+            reordered: self.reordered.clone(),
         }
     }
 
+    /// Data chunks, across all files received since startup, that arrived
+    /// after a later chunk of the same file. Chunks rebuilt from parity or
+    /// filled from the cache aren't counted.
+    pub fn reordered_chunks(&self) -> u64 {
+        self.reordered.load(Ordering::Relaxed)
+    }
+
     /// List files currently being received
     pub async fn in_progress(&self) -> Vec<String> {
-        self.active.lock().await.keys().cloned().collect()
+        self.active
+            .lock()
+            .await
+            .keys()
+            .map(|(_, filename)| filename.clone())
+            .collect()
     }
 
     /// For each in-progress assembly, return the list of content hashes
@@ -220,38 +973,21 @@ impl FileReassembler {
         let active = self.active.lock().await;
         active
             .iter()
-            .map(|(filename, assembly)| {
-                let missing: Vec<[u8; 32]> = assembly
-                    .metadata
-                    .chunk_hashes
-                    .iter()
-                    .filter(|h| !assembly.chunks_received.contains_key(*h))
-                    .copied()
-                    .collect();
-                (filename.clone(), missing)
-            })
+            .map(|((_, filename), assembly)| (filename.clone(), assembly.missing()))
             .filter(|(_, missing)| !missing.is_empty())
             .collect()
     }
 
-    /// Assemblies that have stalled (no chunk received recently) and haven't
-    /// exhausted their NACK attempts.
-    pub async fn stalled_assemblies(
-        &self,
-        nack_delay: std::time::Duration,
-    ) -> Vec<StalledAssembly> {
+    /// Assemblies that have stalled and haven't exhausted their NACK attempts.
+    ///
+    /// An assembly is stalled once neither a chunk has arrived nor a NACK
+    /// been sent for longer than its current backoff delay.
+    pub async fn stalled_assemblies(&self, backoff: NackBackoff) -> Vec<StalledAssembly> {
         let mut active = self.active.lock().await;
         active
             .iter_mut()
-            .filter(|(_, a)| a.last_chunk_at.elapsed() > nack_delay)
-            .filter_map(|(filename, a)| {
-                let missing: Vec<[u8; 32]> = a
-                    .metadata
-                    .chunk_hashes
-                    .iter()
-                    .filter(|h| !a.chunks_received.contains_key(*h))
-                    .copied()
-                    .collect();
+            .filter_map(|((_, filename), a)| {
+                let missing = a.missing();
                 if missing.is_empty() {
                     return None;
                 }
@@ -272,32 +1008,92 @@ impl FileReassembler {
                     return None;
                 }
 
+                let quiet_since = match a.last_nack_at {
+                    Some(t) if t > a.last_chunk_at => t,
+                    _ => a.last_chunk_at,
+                };
+                if quiet_since.elapsed() <= backoff.delay(a.srtt, a.nack_count) {
+                    return None;
+                }
+
                 Some(StalledAssembly {
                     filename: filename.clone(),
                     missing,
                     attempt: a.nack_count,
                     sender_pubkey: a.sender_pubkey,
+                    srtt: a.srtt,
                 })
             })
             .collect()
     }
 
-    /// Mark that a NACK was sent for this assembly, recording how many
-    /// chunks were missing at this point for progress detection.
-    pub async fn increment_nack_count(&self, filename: &str, missing_count: usize) {
+    /// Mark that a NACK was sent for `sender`'s file `filename`, recording
+    /// which chunks were requested — for progress detection and RTT sampling.
+    pub async fn record_nack(&self, sender: [u8; 32], filename: &str, requested: &[[u8; 32]]) {
         let mut active = self.active.lock().await;
-        if let Some(assembly) = active.get_mut(filename) {
+        if let Some(assembly) = active.get_mut(&(sender, filename.to_string())) {
             assembly.nack_count = assembly.nack_count.saturating_add(1);
-            assembly.missing_at_last_nack = missing_count;
+            assembly.missing_at_last_nack = requested.len();
+            assembly.last_nack_at = Some(Instant::now());
+            assembly.nack_pending = requested.iter().copied().collect();
+        }
+    }
+
+    /// Remove `sender`'s assembly of `filename` permanently. Called when
+    /// recovery is impossible.
+    pub async fn abandon(&self, sender: [u8; 32], filename: &str) {
+        let mut active = self.active.lock().await;
+        if let Some(assembly) = active.remove(&(sender, filename.to_string())) {
+            self.announce_abandoned(filename, &assembly);
         }
     }
 
-    /// Remove an assembly permanently. Called when recovery is impossible.
-    pub async fn abandon(&self, filename: &str) {
+    /// Abandon every assembly that has used up its NACK attempts and heard
+    /// nothing back within the final backoff delay. Returns their names.
+    pub async fn abandon_exhausted(&self, backoff: NackBackoff) -> Vec<String> {
         let mut active = self.active.lock().await;
-        if active.remove(filename).is_some() {
-            tracing::warn!(filename, "file assembly abandoned — chunks unrecoverable");
+        let exhausted: Vec<AssemblyKey> = active
+            .iter()
+            .filter(|(_, a)| {
+                a.nack_count >= summit_core::recovery::MAX_NACK_STALLS
+                    && a.last_nack_at.is_some_and(|t| {
+                        t >= a.last_chunk_at && t.elapsed() > backoff.delay(a.srtt, a.nack_count)
+                    })
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &exhausted {
+            if let Some(assembly) = active.remove(key) {
+                self.announce_abandoned(&key.1, &assembly);
+            }
         }
+        exhausted
+            .into_iter()
+            .map(|(_, filename)| filename)
+            .collect()
+    }
+
+    /// Subscribe to abandoned-assembly events.
+    pub fn subscribe_abandoned(&self) -> broadcast::Receiver<FileAbandoned> {
+        self.abandoned.subscribe()
+    }
+
+    fn announce_abandoned(&self, filename: &str, assembly: &FileAssembly) {
+        let chunks_total = assembly.chunks.len();
+        let chunks_missing = chunks_total - assembly.chunks_received;
+        tracing::warn!(
+            filename,
+            chunks_missing,
+            chunks_total,
+            "file assembly abandoned — chunks unrecoverable"
+        );
+        let _ = self.abandoned.send(FileAbandoned {
+            filename: filename.to_string(),
+            sender: hex::encode(assembly.sender_pubkey),
+            chunks_missing,
+            chunks_total,
+            timestamp: crate::messaging_service::unix_millis(),
+        });
     }
 }
 
@@ -315,6 +1111,23 @@ impl ChunkService for FileReassembler {
         Contract::Bulk
     }
 
+    fn schema_ids(&self) -> Vec<[u8; 32]> {
+        vec![
+            self.service_hash(),
+            KnownSchema::FileData.id(),
+            KnownSchema::FileMetadata.id(),
+        ]
+    }
+
+    fn type_tags(&self) -> Vec<u16> {
+        vec![2, 3]
+    }
+
+    /// Metadata opens a transfer; a data chunk only fills one in progress.
+    fn opens_work(&self, header: &ChunkHeader, _payload: &[u8]) -> bool {
+        header.type_tag != 2
+    }
+
     fn on_activate(&self, peer_pubkey: &[u8; 32]) {
         tracing::info!(
             peer = hex::encode(&peer_pubkey[..8]),
@@ -345,7 +1158,15 @@ impl ChunkService for FileReassembler {
             tokio::runtime::Handle::current().block_on(async move {
                 if type_tag == 3 {
                     if let Ok(metadata) = serde_json::from_slice::<FileMetadata>(&data) {
-                        this.add_metadata(metadata, sender).await;
+                        tracing::info!(
+                            filename = %metadata.filename,
+                            chunks = metadata.chunk_hashes.len(),
+                            "file transfer started"
+                        );
+                        let hashes = metadata.chunk_hashes.clone();
+                        if let Some(held) = this.add_metadata(metadata, sender).await {
+                            this.send_have(sender, content_hash, &hashes, &held);
+                        }
                     }
                 } else if type_tag == 2 {
                     if let Err(e) = this.add_chunk(content_hash, data).await {
@@ -421,27 +1242,725 @@ mod tests {
         let reassembler = FileReassembler::new(dir.clone());
 
         let data = b"reassembly test data";
-        let hash = summit_core::crypto::hash(data);
+        let payload = encode_data_chunk(0, data);
+        let hash = summit_core::crypto::hash(&payload);
 
         let metadata = FileMetadata {
             filename: "out.txt".into(),
             total_bytes: data.len() as u64,
             chunk_hashes: vec![hash],
+            task_id: None,
+            fec: None,
         };
 
         reassembler.add_metadata(metadata, [0xAA; 32]).await;
         assert_eq!(reassembler.in_progress().await.len(), 1);
 
-        let result = reassembler
-            .add_chunk(hash, Bytes::from_static(data))
+        let result = reassembler.add_chunk(hash, payload).await.unwrap();
+        let output_path = result.and_then(Written::into_path).unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), data);
+
+        // After completion, no longer in progress
+        assert!(reassembler.in_progress().await.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn same_name_files_follow_conflict_policy() {
+        let dir =
+            std::env::temp_dir().join(format!("summit-reasm-conflict-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        async fn receive(
+            reassembler: &FileReassembler,
+            sender: u8,
+            data: &[u8],
+        ) -> Option<Written> {
+            let payload = encode_data_chunk(0, data);
+            let hash = summit_core::crypto::hash(&payload);
+            let metadata = FileMetadata {
+                filename: "report.txt".into(),
+                total_bytes: data.len() as u64,
+                chunk_hashes: vec![hash],
+                task_id: None,
+                fec: None,
+            };
+            reassembler.add_metadata(metadata, [sender; 32]).await;
+            reassembler.add_chunk(hash, payload).await.unwrap()
+        }
+
+        let renaming = FileReassembler::new(dir.join("rename"));
+        let stored = |written: Option<Written>| written.and_then(Written::into_path).unwrap();
+        let first = stored(receive(&renaming, 1, b"from peer one").await);
+        let second = stored(receive(&renaming, 2, b"from peer two").await);
+        assert_eq!(first.file_name().unwrap(), "report.txt");
+        assert_eq!(second.file_name().unwrap(), "report-1.txt");
+        assert_eq!(std::fs::read(&first).unwrap(), b"from peer one");
+        assert_eq!(std::fs::read(&second).unwrap(), b"from peer two");
+        // The same file delivered again isn't kept twice.
+        assert_eq!(
+            receive(&renaming, 1, b"from peer one").await,
+            Some(Written::Stored(first))
+        );
+
+        let rejecting =
+            FileReassembler::new(dir.join("reject")).with_on_conflict(OnConflict::Reject);
+        let kept = stored(receive(&rejecting, 1, b"from peer one").await);
+        assert_eq!(
+            receive(&rejecting, 2, b"from peer two").await,
+            Some(Written::Rejected)
+        );
+        assert_eq!(std::fs::read(&kept).unwrap(), b"from peer one");
+        assert!(rejecting.in_progress().await.is_empty());
+
+        let overwriting =
+            FileReassembler::new(dir.join("overwrite")).with_on_conflict(OnConflict::Overwrite);
+        let path = stored(receive(&overwriting, 1, b"from peer one").await);
+        assert_eq!(
+            receive(&overwriting, 2, b"from peer two").await,
+            Some(Written::Stored(path.clone()))
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"from peer two");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn same_name_from_two_senders_assembles_separately() {
+        let dir =
+            std::env::temp_dir().join(format!("summit-reasm-two-senders-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let reassembler = FileReassembler::new(dir.clone());
+
+        let mut chunks = Vec::new();
+        for (sender, data) in [(1u8, b"one".as_slice()), (2u8, b"two".as_slice())] {
+            let payload = encode_data_chunk(0, data);
+            let hash = summit_core::crypto::hash(&payload);
+            let metadata = FileMetadata {
+                filename: "same.txt".into(),
+                total_bytes: data.len() as u64,
+                chunk_hashes: vec![hash],
+                task_id: None,
+                fec: None,
+            };
+            reassembler.add_metadata(metadata, [sender; 32]).await;
+            chunks.push((hash, payload));
+        }
+        // The second metadata didn't replace the first.
+        assert_eq!(reassembler.in_progress().await.len(), 2);
+
+        let mut written = Vec::new();
+        for (hash, payload) in chunks.into_iter().rev() {
+            let path = reassembler.add_chunk(hash, payload).await.unwrap();
+            written.push(path.and_then(Written::into_path).unwrap());
+        }
+        assert_eq!(std::fs::read(&written[0]).unwrap(), b"two");
+        assert_eq!(std::fs::read(&written[1]).unwrap(), b"one");
+        assert_eq!(written[1].file_name().unwrap(), "same-1.txt");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rename_keeps_leading_dot_names_whole() {
+        let dir = std::env::temp_dir().join(format!("summit-reasm-dotfile-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("compute/abc")).unwrap();
+        for name in [".bashrc", "archive.tar.gz", "compute/abc/.hidden"] {
+            std::fs::write(dir.join(name), b"held").unwrap();
+        }
+        let reassembler = FileReassembler::new(dir.clone());
+
+        let renamed = |key: &str| reassembler.resolve_conflict(key, b"new").unwrap();
+        assert_eq!(renamed(".bashrc"), ".bashrc-1");
+        assert_eq!(renamed("archive.tar.gz"), "archive.tar-1.gz");
+        assert_eq!(renamed("compute/abc/.hidden"), "compute/abc/.hidden-1");
+        assert_eq!(renamed("fresh.txt"), "fresh.txt");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rename_gives_up_once_every_candidate_is_taken() {
+        let dir = std::env::temp_dir().join(format!("summit-reasm-crowded-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("busy.txt"), b"held").unwrap();
+        for n in 1..=MAX_RENAMES {
+            std::fs::write(dir.join(format!("busy-{n}.txt")), b"held").unwrap();
+        }
+        let reassembler = FileReassembler::new(dir.clone());
+
+        assert_eq!(reassembler.resolve_conflict("busy.txt", b"new"), None);
+        // An identical file still reuses its name.
+        assert_eq!(
+            reassembler.resolve_conflict("busy.txt", b"held").as_deref(),
+            Some("busy.txt")
+        );
+        // Same size, different contents: still taken.
+        std::fs::remove_file(dir.join(format!("busy-{MAX_RENAMES}.txt"))).unwrap();
+        assert_eq!(
+            reassembler.resolve_conflict("busy.txt", b"diff").as_deref(),
+            Some(format!("busy-{MAX_RENAMES}.txt").as_str())
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn reassembler_encrypts_at_rest_and_decrypts_on_read() {
+        let dir = std::env::temp_dir().join(format!("summit-reasm-enc-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let reassembler =
+            FileReassembler::new(dir.clone()).with_encryption(StorageCipher::new([3u8; 32]));
+
+        let data = b"confidential contents";
+        let payload = encode_data_chunk(0, data);
+        let hash = summit_core::crypto::hash(&payload);
+        let metadata = FileMetadata {
+            filename: "secret.txt".into(),
+            total_bytes: data.len() as u64,
+            chunk_hashes: vec![hash],
+            task_id: None,
+            fec: None,
+        };
+        reassembler.add_metadata(metadata, [0xAA; 32]).await;
+        let output_path = reassembler
+            .add_chunk(hash, payload)
             .await
+            .unwrap()
+            .and_then(Written::into_path)
             .unwrap();
-        assert!(result.is_some());
 
-        let output_path = result.unwrap();
-        assert_eq!(std::fs::read(&output_path).unwrap(), data);
+        let on_disk = std::fs::read(&output_path).unwrap();
+        assert!(StorageCipher::is_sealed(&on_disk));
+        assert!(!on_disk.windows(data.len()).any(|w| w == data));
+        assert_eq!(reassembler.read_received("secret.txt").unwrap(), data);
 
-        // After completion, no longer in progress
+        // Plaintext left over from before encryption is served as-is
+        std::fs::write(dir.join("old.txt"), b"legacy").unwrap();
+        assert_eq!(reassembler.read_received("old.txt").unwrap(), b"legacy");
+
+        // Without the key, the sealed file can't be read
+        let plain = FileReassembler::new(dir.clone());
+        let err = plain.read_received("secret.txt").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        for name in ["../secret.txt", "/etc/passwd", ""] {
+            let err = reassembler.read_received(name).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+        let err = reassembler.read_received("missing.txt").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn reassembler_caps_concurrent_assemblies() {
+        let dir = std::env::temp_dir().join(format!("summit-reasm-cap-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let reassembler = FileReassembler::new(dir.clone()).with_max_assemblies(4);
+
+        let file = |i: usize| {
+            let payload = encode_data_chunk(0, format!("file {i}").as_bytes());
+            let hash = summit_core::crypto::hash(&payload);
+            let metadata = FileMetadata {
+                filename: format!("f{i}.txt"),
+                total_bytes: (payload.len() - CHUNK_INDEX_SIZE) as u64,
+                chunk_hashes: vec![hash],
+                task_id: None,
+                fec: None,
+            };
+            (metadata, hash, payload)
+        };
+
+        // A flood of metadata only ever opens `max` assemblies
+        for i in 0..100 {
+            let accepted = reassembler.add_metadata(file(i).0, [0xAA; 32]).await;
+            assert_eq!(accepted.is_some(), i < 4, "file {i}");
+        }
+        assert_eq!(reassembler.in_progress().await.len(), 4);
+
+        // Metadata re-sent for an assembly under way is still accepted
+        assert!(reassembler
+            .add_metadata(file(0).0, [0xAA; 32])
+            .await
+            .is_some());
+
+        // Finishing one frees a slot
+        let (_, hash, payload) = file(0);
+        assert!(reassembler
+            .add_chunk(hash, payload)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(reassembler
+            .add_metadata(file(50).0, [0xAA; 32])
+            .await
+            .is_some());
+        assert!(reassembler
+            .add_metadata(file(51).0, [0xAA; 32])
+            .await
+            .is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn reassembler_stores_task_output_per_task() {
+        let dir = std::env::temp_dir().join(format!("summit-reasm-task-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let store = ComputeStore::new();
+        let worker = [0xBB; 32];
+        let task_id = "ab".repeat(32);
+        store.track_submitted(
+            worker,
+            crate::compute_types::TaskSubmit {
+                task_id: task_id.clone(),
+                sender: "a".repeat(64),
+                timestamp: 0,
+                payload: serde_json::json!({ "run": "true" }),
+                priority: 0,
+            },
+        );
+        let reassembler = FileReassembler::new(dir.clone()).with_compute(store.clone());
+
+        let payload = encode_data_chunk(0, b"result");
+        let hash = summit_core::crypto::hash(&payload);
+        let metadata = FileMetadata {
+            filename: "out.txt".into(),
+            total_bytes: 6,
+            chunk_hashes: vec![hash],
+            task_id: Some(task_id.clone()),
+            fec: None,
+        };
+
+        // Only the worker the task was sent to may return its files.
+        reassembler.add_metadata(metadata.clone(), [0xCC; 32]).await;
+        assert!(reassembler.in_progress().await.is_empty());
+
+        // A task id that is not hex is refused, even one with a multibyte
+        // character straddling the logged prefix.
+        for bad in ["abcdefabcdefabcé", "../../../../etc"] {
+            let mut forged = metadata.clone();
+            forged.task_id = Some(bad.into());
+            reassembler.add_metadata(forged, worker).await;
+            assert!(reassembler.in_progress().await.is_empty());
+        }
+
+        reassembler.add_metadata(metadata, worker).await;
+        let path = reassembler
+            .add_chunk(hash, payload)
+            .await
+            .unwrap()
+            .and_then(Written::into_path)
+            .unwrap();
+        assert_eq!(
+            path,
+            dir.join("compute").join(&task_id[..16]).join("out.txt")
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"result");
+        assert_eq!(store.get_task(&task_id).unwrap().output_files, vec![path]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn reassembler_fills_cached_chunks_on_metadata() {
+        let dir = std::env::temp_dir().join(format!("summit-dedup-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = ChunkCache::in_memory();
+        let (chunk_tx, _chunk_rx) = mpsc::channel(4);
+        let reassembler = FileReassembler::new(dir.clone()).with_dedup(
+            cache.clone(),
+            chunk_tx,
+            TrustRegistry::new(),
+        );
+
+        let payload_a = encode_data_chunk(0, b"chunk a");
+        let payload_b = encode_data_chunk(1, b"chunk b");
+        let a = summit_core::crypto::hash(&payload_a);
+        let b = summit_core::crypto::hash(&payload_b);
+        cache.put(&a, &payload_a).unwrap();
+
+        let metadata = FileMetadata {
+            filename: "dedup.bin".into(),
+            total_bytes: 14,
+            chunk_hashes: vec![a, b],
+            task_id: None,
+            fec: None,
+        };
+        let held = reassembler.add_metadata(metadata.clone(), [0xAA; 32]).await;
+        assert_eq!(held, Some(vec![true, false]));
+        assert_eq!(reassembler.missing_chunks().await[0].1, vec![b]);
+
+        let path = reassembler
+            .add_chunk(b, payload_b.clone())
+            .await
+            .unwrap()
+            .and_then(Written::into_path)
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"chunk achunk b");
+
+        // Fully cached: the metadata alone completes the file.
+        std::fs::remove_file(&path).unwrap();
+        cache.put(&b, &payload_b).unwrap();
+        let held = reassembler.add_metadata(metadata, [0xAA; 32]).await;
+        assert_eq!(held, Some(vec![true, true]));
+        assert!(reassembler.in_progress().await.is_empty());
+        assert_eq!(std::fs::read(&path).unwrap(), b"chunk achunk b");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn have_is_sent_to_trusted_peers_for_servable_chunks_only() {
+        let cache = ChunkCache::in_memory();
+        let trust = TrustRegistry::new();
+        let (chunk_tx, mut chunk_rx) = mpsc::channel(4);
+        let reassembler = FileReassembler::new(std::env::temp_dir()).with_dedup(
+            cache.clone(),
+            chunk_tx,
+            trust.clone(),
+        );
+        let peer = [0xAA; 32];
+        let (shared, exchanged, other) = ([1; 32], [2; 32], [3; 32]);
+        for hash in [shared, exchanged, other] {
+            cache.put(&hash, b"held").unwrap();
+        }
+        cache.share(&shared);
+        cache.mark_exchanged(&exchanged, peer);
+        cache.mark_exchanged(&other, [0xBB; 32]);
+        let hashes = [shared, exchanged, other];
+
+        // Untrusted: no reply at all.
+        reassembler.send_have(peer, [9; 32], &hashes, &[true; 3]);
+        assert!(chunk_rx.try_recv().is_err());
+
+        // Trusted: the chunk exchanged with someone else is not claimed.
+        trust.trust(peer);
+        reassembler.send_have(peer, [9; 32], &hashes, &[true; 3]);
+        let (target, chunk) = chunk_rx.try_recv().unwrap();
+        assert!(matches!(target, SendTarget::Peer { public_key } if public_key == peer));
+        let have: Have = serde_json::from_slice(&chunk.payload).unwrap();
+        assert!(have.holds(0));
+        assert!(have.holds(1));
+        assert!(!have.holds(2));
+    }
+
+    #[test]
+    fn have_reaches_the_waiting_send() {
+        let reassembler = FileReassembler::new(std::env::temp_dir());
+        let peer = [0xAA; 32];
+        let mut rx = reassembler.expect_have(peer, [1; 32]);
+
+        // Wrong peer or wrong file: dropped.
+        reassembler.deliver_have([0xBB; 32], Have::new([1; 32], &[true]));
+        reassembler.deliver_have(peer, Have::new([2; 32], &[true]));
+        assert!(rx.try_recv().is_err());
+
+        reassembler.deliver_have(peer, Have::new([1; 32], &[false, true]));
+        let have = rx.try_recv().unwrap();
+        assert!(!have.holds(0));
+        assert!(have.holds(1));
+    }
+
+    #[tokio::test]
+    async fn reassembler_places_repeated_chunks_by_index() {
+        let dir = std::env::temp_dir().join(format!("summit-repeat-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let file_path = dir.join("src").join("zeros.bin");
+        std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+
+        // Three identical blocks followed by a short distinct tail.
+        let mut data = vec![0u8; MAX_CHUNK_SIZE * 3];
+        data.extend_from_slice(b"tail");
+        std::fs::write(&file_path, &data).unwrap();
+
+        let chunks = chunk_file(&file_path).unwrap();
+        let meta: FileMetadata = serde_json::from_slice(&chunks[0].payload).unwrap();
+        assert_eq!(meta.chunk_hashes.len(), 4);
+        assert_ne!(meta.chunk_hashes[0], meta.chunk_hashes[1]);
+
+        let reassembler = FileReassembler::new(dir.join("out"));
+        reassembler.add_metadata(meta, [0xAA; 32]).await;
+
+        // Deliver out of order; the last one completes the file.
+        let mut result = None;
+        for chunk in chunks[1..].iter().rev() {
+            let hash = summit_core::crypto::hash(&chunk.payload);
+            result = reassembler
+                .add_chunk(hash, chunk.payload.clone())
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            std::fs::read(result.and_then(Written::into_path).unwrap()).unwrap(),
+            data
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn reassembler_rejects_out_of_range_index() {
+        let dir = std::env::temp_dir().join(format!("summit-range-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let reassembler = FileReassembler::new(dir.clone());
+
+        let payload = encode_data_chunk(0, b"only");
+        let hash = summit_core::crypto::hash(&payload);
+        let metadata = FileMetadata {
+            filename: "one.bin".into(),
+            total_bytes: 4,
+            chunk_hashes: vec![hash],
+            task_id: None,
+            fec: None,
+        };
+        reassembler.add_metadata(metadata, [0xAA; 32]).await;
+
+        let stray = encode_data_chunk(5, b"only");
+        let stray_hash = summit_core::crypto::hash(&stray);
+        assert!(reassembler
+            .add_chunk(stray_hash, stray)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(reassembler
+            .add_chunk([0; 32], Bytes::from_static(b"ab"))
+            .await
+            .is_err());
+        assert_eq!(reassembler.missing_chunks().await[0].1, vec![hash]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn nack_backoff_doubles_and_caps() {
+        let backoff = NackBackoff {
+            min: Duration::from_millis(500),
+            max: Duration::from_secs(8),
+        };
+        assert_eq!(backoff.delay(None, 0), Duration::from_millis(500));
+        assert_eq!(backoff.delay(None, 1), Duration::from_secs(1));
+        assert_eq!(backoff.delay(None, 2), Duration::from_secs(2));
+        assert_eq!(backoff.delay(None, 10), Duration::from_secs(8));
+
+        // Base follows the observed round-trip, never below the minimum.
+        let rtt = Some(Duration::from_millis(400));
+        assert_eq!(backoff.delay(rtt, 0), Duration::from_millis(800));
+        assert_eq!(backoff.delay(rtt, 1), Duration::from_millis(1600));
+        let fast = Some(Duration::from_millis(10));
+        assert_eq!(backoff.delay(fast, 0), Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn nack_response_samples_rtt_and_backs_off() {
+        let dir = std::env::temp_dir().join(format!("summit-nack-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let reassembler = FileReassembler::new(dir.clone());
+
+        let payload_a = encode_data_chunk(0, b"chunk a");
+        let a = summit_core::crypto::hash(&payload_a);
+        let b = summit_core::crypto::hash(&encode_data_chunk(1, b"chunk b"));
+        let metadata = FileMetadata {
+            filename: "rtt.bin".into(),
+            total_bytes: 14,
+            chunk_hashes: vec![a, b],
+            task_id: None,
+            fec: None,
+        };
+        reassembler.add_metadata(metadata, [0xAA; 32]).await;
+
+        let backoff = NackBackoff {
+            min: Duration::from_millis(20),
+            max: Duration::from_secs(1),
+        };
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        let stalled = reassembler.stalled_assemblies(backoff).await;
+        assert_eq!(stalled.len(), 1);
+        assert_eq!(stalled[0].attempt, 0);
+        assert!(stalled[0].srtt.is_none());
+
+        reassembler
+            .record_nack([0xAA; 32], "rtt.bin", &stalled[0].missing)
+            .await;
+        // Just NACKed — next attempt must wait for the backoff delay.
+        assert!(reassembler.stalled_assemblies(backoff).await.is_empty());
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        reassembler.add_chunk(a, payload_a).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let stalled = reassembler.stalled_assemblies(backoff).await;
+        assert_eq!(stalled.len(), 1);
+        assert_eq!(stalled[0].missing, vec![b]);
+        // Progress reset the attempt counter; the response gave an RTT sample.
+        assert_eq!(stalled[0].attempt, 0);
+        assert!(stalled[0].srtt.unwrap() >= Duration::from_millis(10));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn exhausted_assembly_is_abandoned_after_last_backoff() {
+        let dir = std::env::temp_dir().join(format!("summit-abandon-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let reassembler = FileReassembler::new(dir.clone());
+        let mut events = reassembler.subscribe_abandoned();
+
+        let a = summit_core::crypto::hash(&encode_data_chunk(0, b"chunk a"));
+        let b = summit_core::crypto::hash(&encode_data_chunk(1, b"chunk b"));
+        let metadata = FileMetadata {
+            filename: "lost.bin".into(),
+            total_bytes: 14,
+            chunk_hashes: vec![a, b],
+            task_id: None,
+            fec: None,
+        };
+        reassembler.add_metadata(metadata, [0xAA; 32]).await;
+
+        let backoff = NackBackoff {
+            min: Duration::from_millis(10),
+            max: Duration::from_millis(40),
+        };
+        for _ in 0..summit_core::recovery::MAX_NACK_STALLS {
+            assert!(reassembler.abandon_exhausted(backoff).await.is_empty());
+            reassembler
+                .record_nack([0xAA; 32], "lost.bin", &[a, b])
+                .await;
+        }
+        // Attempts used up, but the last NACK still has its backoff to run.
+        assert!(reassembler.abandon_exhausted(backoff).await.is_empty());
+        assert_eq!(reassembler.in_progress().await.len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            reassembler.abandon_exhausted(backoff).await,
+            vec!["lost.bin"]
+        );
+        assert!(reassembler.in_progress().await.is_empty());
+
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.filename, "lost.bin");
+        assert_eq!(event.sender, hex::encode([0xAA; 32]));
+        assert_eq!((event.chunks_missing, event.chunks_total), (2, 2));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn fec_rebuilds_lost_chunks_without_nack() {
+        let dir = std::env::temp_dir().join(format!("summit-reasm-fec-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // 10 data chunks, the last one short: stripes of 4, 4 and 2.
+        let data: Vec<u8> = (0..9 * MAX_CHUNK_SIZE + 1000)
+            .map(|i| (i * 13 % 251) as u8)
+            .collect();
+        let src = dir.join("lossy.bin");
+        std::fs::write(&src, &data).unwrap();
+        let chunks = chunk_file_fec(&src, FecScheme::new(4, 2).unwrap()).unwrap();
+
+        let metadata: FileMetadata = serde_json::from_slice(&chunks[0].payload).unwrap();
+        let fec = metadata.fec.clone().unwrap();
+        assert_eq!(metadata.chunk_hashes.len(), 10);
+        assert_eq!(fec.parity_hashes.len(), 6);
+        assert_eq!(chunks.len(), 1 + 10 + 6);
+
+        // Lose up to the parity count in each stripe, data and parity alike:
+        // two data chunks of the first, one data and one parity of the
+        // second, and the short last chunk.
+        let lost = [1, 2, 5, 10 + 2, 9];
+        let reassembler = FileReassembler::new(dir.join("out"));
+        reassembler.add_metadata(metadata, [0xAA; 32]).await;
+        let mut written = None;
+        for (index, chunk) in chunks[1..].iter().enumerate() {
+            if lost.contains(&index) {
+                continue;
+            }
+            let hash = summit_core::crypto::hash(&chunk.payload);
+            if let Some(path) = reassembler
+                .add_chunk(hash, chunk.payload.clone())
+                .await
+                .unwrap()
+            {
+                written = Some(path);
+            }
+        }
+
+        let path = written
+            .and_then(Written::into_path)
+            .expect("file rebuilt from parity");
+        assert_eq!(std::fs::read(path).unwrap(), data);
+        assert!(reassembler.in_progress().await.is_empty());
+        let backoff = NackBackoff {
+            min: Duration::ZERO,
+            max: Duration::ZERO,
+        };
+        assert!(reassembler.stalled_assemblies(backoff).await.is_empty());
+
+        // One loss past the parity count leaves the stripe to NACK recovery.
+        let reassembler = FileReassembler::new(dir.join("out2"));
+        reassembler
+            .add_metadata(
+                serde_json::from_slice(&chunks[0].payload).unwrap(),
+                [0xAA; 32],
+            )
+            .await;
+        for (index, chunk) in chunks[1..].iter().enumerate() {
+            if [0, 1, 2].contains(&index) {
+                continue;
+            }
+            let hash = summit_core::crypto::hash(&chunk.payload);
+            assert!(reassembler
+                .add_chunk(hash, chunk.payload.clone())
+                .await
+                .unwrap()
+                .is_none());
+        }
+        let missing = reassembler.missing_chunks().await;
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].1.len(), 3);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn counts_chunks_arriving_behind_later_ones() {
+        let dir = std::env::temp_dir().join(format!("summit-reasm-order-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let data: Vec<u8> = (0..4 * MAX_CHUNK_SIZE).map(|i| (i % 253) as u8).collect();
+        let src = dir.join("order.bin");
+        std::fs::write(&src, &data).unwrap();
+        let chunks = chunk_file(&src).unwrap();
+        assert_eq!(chunks.len(), 1 + 4);
+
+        let reassembler = FileReassembler::new(dir.join("out"));
+        reassembler
+            .add_metadata(
+                serde_json::from_slice(&chunks[0].payload).unwrap(),
+                [0xAA; 32],
+            )
+            .await;
+        // 0, 2, 1, 3: only chunk 1 arrives after a later one. The duplicate
+        // of chunk 0 is ignored.
+        for index in [1, 3, 1, 2, 4] {
+            let chunk = &chunks[index];
+            let hash = summit_core::crypto::hash(&chunk.payload);
+            reassembler
+                .add_chunk(hash, chunk.payload.clone())
+                .await
+                .unwrap();
+        }
+        assert_eq!(reassembler.reordered_chunks(), 1);
         assert!(reassembler.in_progress().await.is_empty());
 
         let _ = std::fs::remove_dir_all(&dir);
@@ -4,31 +4,58 @@ pub mod compute_executor;
 pub mod compute_service;
 pub mod compute_store;
 pub mod compute_types;
+pub mod delivery;
+pub mod dispatch;
 pub mod file_transfer;
 pub mod message_store;
 pub mod messaging_service;
 pub mod peer;
+pub mod presence;
 pub mod qos;
 pub mod schema;
 pub mod send_target;
 pub mod service;
 pub mod session;
+pub mod sync_service;
+pub mod transfers;
+pub mod transport;
 pub mod trust;
 
-pub use cache::ChunkCache;
+pub use cache::{CachedChunk, ChunkCache};
 pub use chunk_types::{IncomingChunk, OutgoingChunk};
 pub use compute_service::ComputeService;
-pub use compute_store::{ComputeStore, ComputeTask};
-pub use compute_types::{ComputeEnvelope, TaskAck, TaskResult, TaskStatus, TaskSubmit};
+pub use compute_store::{ComputeStore, ComputeTask, OutputLine};
+pub use compute_types::{
+    short_id, ComputeEnvelope, ComputeOutput, TaskAck, TaskResult, TaskStatus, TaskSubmit,
+    TaskTimes,
+};
+pub use delivery::{DeliveryStats, DeliveryTracker};
+pub use dispatch::ServiceDispatcher;
 pub use file_transfer::{
-    chunk_file, FileMetadata, FileReassembler, StalledAssembly, MAX_CHUNK_SIZE,
+    chunk_file, chunk_file_fec, chunk_task_output, decode_data_chunk, encode_data_chunk, FecParams,
+    FecScheme, FileAbandoned, FileMetadata, FileReassembler, NackBackoff, StalledAssembly, Written,
+    CHUNK_INDEX_SIZE, MAX_CHUNK_SIZE,
+};
+pub use message_store::{MessageStore, PeerSummary};
+pub use messaging_service::{
+    message_chunks, message_group_id, messaging_schema_id, msg_chunk_types, msg_types,
+    MessageContent, MessageEnvelope, MessagingService, MAX_INLINE_BLOB, MAX_MESSAGE_BYTES,
 };
-pub use message_store::MessageStore;
-pub use messaging_service::{messaging_schema_id, msg_types, MessageEnvelope, MessagingService};
-pub use peer::{new_registry, PeerEntry, PeerRegistry};
-pub use qos::TokenBucket;
+pub use peer::{
+    expire_peers, new_registry, remote_peers, AnnouncementAssembler, AnnouncementCounters,
+    AnnouncementStats, PeerEntry, PeerRegistry, ServiceEntry,
+};
+pub use presence::{PresenceEvent, PresenceHub};
+pub use qos::{BucketStats, PeerBandwidth, PeerRates, TokenBucket};
 pub use schema::KnownSchema;
 pub use send_target::SendTarget;
 pub use service::ChunkService;
-pub use session::{new_session_table, ActiveSession, ServiceOnSession, SessionMeta, SessionTable};
-pub use trust::{BufferedChunk, TrustLevel, TrustRegistry, UntrustedBuffer};
+pub use session::{
+    chunk_service, negotiate_services, new_session_table, one_session_per_peer, preferred_session,
+    ActiveSession, ConnectOutcome, ConnectRequest, DecryptDropCounts, DecryptDrops, HandshakeInfo,
+    HandshakePhase, HandshakeSource, ServiceOnSession, SessionCooldowns, SessionMeta, SessionTable,
+};
+pub use sync_service::{SyncHashes, SyncService, MAX_SYNC_HASHES};
+pub use transfers::{TransferRecord, TransferTracker};
+pub use transport::{DatagramSocket, LoopbackNet, LoopbackSocket};
+pub use trust::{BufferedChunk, PeerPendingApproval, TrustLevel, TrustRegistry, UntrustedBuffer};
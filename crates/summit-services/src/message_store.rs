@@ -1,52 +1,216 @@
-use crate::messaging_service::MessageEnvelope;
+use crate::messaging_service::{unix_millis, MessageEnvelope};
 use dashmap::DashMap;
 use std::sync::Arc;
 
+/// Messages we sent to a group, stored once for all its members.
+#[derive(Default)]
+struct GroupThread {
+    members: Vec<[u8; 32]>,
+    messages: Vec<MessageEnvelope>,
+}
+
 /// In-memory store for received message envelopes, keyed by sender pubkey.
+/// Messages we send to a group are kept once, under the group.
 #[derive(Clone, Default)]
 pub struct MessageStore {
     messages: Arc<DashMap<[u8; 32], Vec<MessageEnvelope>>>,
+    groups: Arc<DashMap<String, GroupThread>>,
+    /// Messages from each peer stored since it was last marked read.
+    unread: Arc<DashMap<[u8; 32], usize>>,
+}
+
+/// One conversation in `MessageStore::summary`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerSummary {
+    pub peer_pubkey: [u8; 32],
+    /// Messages exchanged, group messages included.
+    pub messages: usize,
+    pub unread: usize,
+    /// Timestamp of the newest message, Unix ms.
+    pub last_timestamp: u64,
 }
 
 impl MessageStore {
     pub fn new() -> Self {
         Self {
             messages: Arc::new(DashMap::new()),
+            groups: Arc::new(DashMap::new()),
+            unread: Arc::new(DashMap::new()),
         }
     }
 
-    /// Store an envelope received from `peer_pubkey`.
+    /// Store an envelope exchanged with `peer_pubkey`. A retransmitted
+    /// envelope — same `msg_id` — is ignored. One the peer sent (rather
+    /// than one we sent to it) counts as unread until `mark_read`.
     pub fn add(&self, peer_pubkey: [u8; 32], envelope: MessageEnvelope) {
-        self.messages.entry(peer_pubkey).or_default().push(envelope);
+        let from_peer = envelope.sender == hex::encode(peer_pubkey);
+        let mut msgs = self.messages.entry(peer_pubkey).or_default();
+        if !msgs.iter().any(|m| m.msg_id == envelope.msg_id) {
+            msgs.push(envelope);
+            if from_peer {
+                *self.unread.entry(peer_pubkey).or_default() += 1;
+            }
+        }
     }
 
-    /// Get all envelopes received from `peer_pubkey`.
+    /// Unread messages from `peer_pubkey`. Never more than are still stored.
+    pub fn unread(&self, peer_pubkey: &[u8; 32]) -> usize {
+        let unread = self.unread.get(peer_pubkey).map_or(0, |n| *n);
+        if unread == 0 {
+            return 0;
+        }
+        let stored = self.messages.get(peer_pubkey).map_or(0, |msgs| {
+            let now = unix_millis();
+            let sender = hex::encode(peer_pubkey);
+            msgs.iter()
+                .filter(|m| m.sender == sender && !m.is_expired(now))
+                .count()
+        });
+        unread.min(stored)
+    }
+
+    /// Mark everything from `peer_pubkey` read. Returns how many were unread.
+    pub fn mark_read(&self, peer_pubkey: &[u8; 32]) -> usize {
+        let unread = self.unread(peer_pubkey);
+        self.unread.remove(peer_pubkey);
+        unread
+    }
+
+    /// Every peer with stored messages, most recently active first.
+    pub fn summary(&self) -> Vec<PeerSummary> {
+        let mut peers: Vec<[u8; 32]> = self.messages.iter().map(|e| *e.key()).collect();
+        for thread in self.groups.iter() {
+            peers.extend(thread.members.iter().copied());
+        }
+        peers.sort_unstable();
+        peers.dedup();
+
+        let mut out: Vec<PeerSummary> = peers
+            .into_iter()
+            .filter_map(|peer| {
+                let msgs = self.get(&peer);
+                let last = msgs.iter().map(|m| m.timestamp).max()?;
+                Some(PeerSummary {
+                    peer_pubkey: peer,
+                    messages: msgs.len(),
+                    unread: self.unread(&peer),
+                    last_timestamp: last,
+                })
+            })
+            .collect();
+        out.sort_by(|a, b| {
+            b.last_timestamp
+                .cmp(&a.last_timestamp)
+                .then(a.peer_pubkey.cmp(&b.peer_pubkey))
+        });
+        out
+    }
+
+    /// Store an envelope sent to every peer in `members`, once.
+    pub fn add_group(&self, group_id: String, members: Vec<[u8; 32]>, envelope: MessageEnvelope) {
+        let mut thread = self.groups.entry(group_id).or_default();
+        thread.members = members;
+        thread.messages.push(envelope);
+    }
+
+    /// Get all envelopes exchanged with `peer_pubkey`, including group
+    /// messages it was sent, oldest first.
     pub fn get(&self, peer_pubkey: &[u8; 32]) -> Vec<MessageEnvelope> {
-        self.messages
-            .get(peer_pubkey)
-            .map(|msgs| msgs.clone())
-            .unwrap_or_default()
+        self.collect(peer_pubkey, |_| true)
     }
 
-    /// Get envelopes received from `peer_pubkey` with `timestamp > since`.
+    /// Get envelopes exchanged with `peer_pubkey` with `timestamp > since`.
     pub fn get_since(&self, peer_pubkey: &[u8; 32], since: u64) -> Vec<MessageEnvelope> {
-        self.messages
-            .get(peer_pubkey)
-            .map(|msgs| {
-                msgs.iter()
-                    .filter(|m| m.timestamp > since)
+        self.collect(peer_pubkey, |m| m.timestamp > since)
+    }
+
+    /// Count envelopes stored for `peer_pubkey`.
+    pub fn count(&self, peer_pubkey: &[u8; 32]) -> usize {
+        self.get(peer_pubkey).len()
+    }
+
+    /// Envelopes we sent to a group.
+    pub fn get_group(&self, group_id: &str) -> Vec<MessageEnvelope> {
+        let now = unix_millis();
+        self.groups
+            .get(group_id)
+            .map(|thread| {
+                thread
+                    .messages
+                    .iter()
+                    .filter(|m| !m.is_expired(now))
                     .cloned()
                     .collect()
             })
             .unwrap_or_default()
     }
 
-    /// Count envelopes stored for `peer_pubkey`.
-    pub fn count(&self, peer_pubkey: &[u8; 32]) -> usize {
-        self.messages
+    /// The `limit` most recent messages across every peer and group, newest
+    /// first. Each comes with the peers of its conversation: the one peer,
+    /// or a group's members.
+    pub fn recent(&self, limit: usize) -> Vec<(Vec<[u8; 32]>, MessageEnvelope)> {
+        let mut out = self.all_live();
+        out.sort_by(|(_, a), (_, b)| {
+            b.timestamp
+                .cmp(&a.timestamp)
+                .then_with(|| a.msg_id.cmp(&b.msg_id))
+        });
+        out.truncate(limit);
+        out
+    }
+
+    /// Messages across every peer and group.
+    pub fn total(&self) -> usize {
+        self.all_live().len()
+    }
+
+    fn all_live(&self) -> Vec<(Vec<[u8; 32]>, MessageEnvelope)> {
+        let now = unix_millis();
+        let mut out = Vec::new();
+        for entry in self.messages.iter() {
+            out.extend(
+                entry
+                    .value()
+                    .iter()
+                    .filter(|m| !m.is_expired(now))
+                    .map(|m| (vec![*entry.key()], m.clone())),
+            );
+        }
+        for thread in self.groups.iter() {
+            out.extend(
+                thread
+                    .messages
+                    .iter()
+                    .filter(|m| !m.is_expired(now))
+                    .map(|m| (thread.members.clone(), m.clone())),
+            );
+        }
+        out
+    }
+
+    /// Expired messages are never returned, even before a sweep removes them.
+    fn collect(
+        &self,
+        peer_pubkey: &[u8; 32],
+        keep: impl Fn(&MessageEnvelope) -> bool,
+    ) -> Vec<MessageEnvelope> {
+        let now = unix_millis();
+        let keep = |m: &MessageEnvelope| !m.is_expired(now) && keep(m);
+        let mut out: Vec<MessageEnvelope> = self
+            .messages
             .get(peer_pubkey)
-            .map(|msgs| msgs.len())
-            .unwrap_or(0)
+            .map(|msgs| msgs.iter().filter(|m| keep(m)).cloned().collect())
+            .unwrap_or_default();
+        let before = out.len();
+        for thread in self.groups.iter() {
+            if thread.members.contains(peer_pubkey) {
+                out.extend(thread.messages.iter().filter(|m| keep(m)).cloned());
+            }
+        }
+        if out.len() > before {
+            out.sort_by_key(|m| m.timestamp);
+        }
+        out
     }
 
     /// Remove messages older than `retention_days`. Returns count removed.
@@ -67,14 +231,41 @@ impl MessageStore {
             entry.value_mut().retain(|m| m.timestamp >= cutoff_ms);
             removed += before - entry.value().len();
         }
+        for mut thread in self.groups.iter_mut() {
+            let before = thread.messages.len();
+            thread.messages.retain(|m| m.timestamp >= cutoff_ms);
+            removed += before - thread.messages.len();
+        }
         // Drop empty entries
         self.messages.retain(|_, msgs| !msgs.is_empty());
+        self.groups.retain(|_, thread| !thread.messages.is_empty());
+        removed
+    }
+
+    /// Remove messages whose TTL has run out. Returns count removed.
+    pub fn purge_expired(&self) -> usize {
+        let now = unix_millis();
+        let mut removed = 0usize;
+        for mut entry in self.messages.iter_mut() {
+            let before = entry.value().len();
+            entry.value_mut().retain(|m| !m.is_expired(now));
+            removed += before - entry.value().len();
+        }
+        for mut thread in self.groups.iter_mut() {
+            let before = thread.messages.len();
+            thread.messages.retain(|m| !m.is_expired(now));
+            removed += before - thread.messages.len();
+        }
+        self.messages.retain(|_, msgs| !msgs.is_empty());
+        self.groups.retain(|_, thread| !thread.messages.is_empty());
         removed
     }
 
     /// Clear all stored messages.
     pub fn clear(&self) {
         self.messages.clear();
+        self.groups.clear();
+        self.unread.clear();
     }
 }
 
@@ -89,6 +280,8 @@ mod tests {
             sender: "a".repeat(64),
             timestamp,
             payload: serde_json::json!({ "text": "hello" }),
+            group_id: None,
+            expires_at: None,
         }
     }
 
@@ -113,6 +306,16 @@ mod tests {
         assert_eq!(msgs[1].timestamp, 200);
     }
 
+    #[test]
+    fn add_ignores_duplicate_msg_id() {
+        let store = MessageStore::new();
+        let peer = [1u8; 32];
+        store.add(peer, make_envelope(100));
+        store.add(peer, make_envelope(100));
+
+        assert_eq!(store.count(&peer), 1);
+    }
+
     #[test]
     fn get_since_filters_by_timestamp() {
         let store = MessageStore::new();
@@ -138,6 +341,91 @@ mod tests {
         assert_eq!(store.count(&peer), 2);
     }
 
+    #[test]
+    fn group_message_stored_once_and_shown_to_each_member() {
+        let store = MessageStore::new();
+        let (a, b) = ([1u8; 32], [2u8; 32]);
+        store.add(a, make_envelope(100));
+        store.add_group("g".into(), vec![a, b], make_envelope(150));
+        store.add(a, make_envelope(200));
+
+        let timestamps =
+            |msgs: Vec<MessageEnvelope>| -> Vec<u64> { msgs.iter().map(|m| m.timestamp).collect() };
+        assert_eq!(timestamps(store.get(&a)), vec![100, 150, 200]);
+        assert_eq!(timestamps(store.get(&b)), vec![150]);
+        assert_eq!(store.get_group("g").len(), 1);
+        assert_eq!(store.messages.len(), 1);
+    }
+
+    #[test]
+    fn recent_merges_peers_and_groups_newest_first() {
+        let store = MessageStore::new();
+        let (a, b) = ([1u8; 32], [2u8; 32]);
+        store.add(a, make_envelope(100));
+        store.add(b, make_envelope(300));
+        store.add_group("g".into(), vec![a, b], make_envelope(200));
+        store.add(a, make_envelope(400));
+
+        let recent = store.recent(3);
+        let timestamps: Vec<u64> = recent.iter().map(|(_, m)| m.timestamp).collect();
+        assert_eq!(timestamps, vec![400, 300, 200]);
+        assert_eq!(recent[0].0, vec![a]);
+        assert_eq!(recent[1].0, vec![b]);
+        assert_eq!(recent[2].0, vec![a, b]);
+        assert_eq!(store.total(), 4);
+        assert_eq!(store.recent(10).len(), 4);
+    }
+
+    #[test]
+    fn unread_counts_messages_from_the_peer_until_marked_read() {
+        let store = MessageStore::new();
+        // Not [0xAA; 32]: make_envelope's sender is "aa..." — us, here.
+        let (a, b) = ([0x11; 32], [0x22; 32]);
+        let from = |peer: &[u8; 32], timestamp| MessageEnvelope {
+            sender: hex::encode(peer),
+            ..make_envelope(timestamp)
+        };
+        store.add(a, from(&a, 100));
+        store.add(a, from(&a, 100)); // retransmission
+        store.add(a, from(&a, 200));
+        store.add(a, make_envelope(300)); // one we sent to a
+        store.add(b, from(&b, 150));
+        store.add_group("g".into(), vec![a, b], make_envelope(400));
+
+        assert_eq!(store.unread(&a), 2);
+        assert_eq!(store.unread(&b), 1);
+
+        let summary = store.summary();
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].peer_pubkey, a);
+        assert_eq!((summary[0].messages, summary[0].unread), (4, 2));
+        assert_eq!(summary[0].last_timestamp, 400);
+        assert_eq!((summary[1].messages, summary[1].unread), (2, 1));
+
+        assert_eq!(store.mark_read(&a), 2);
+        assert_eq!(store.unread(&a), 0);
+        assert_eq!(store.mark_read(&a), 0);
+        store.add(a, from(&a, 500));
+        assert_eq!(store.unread(&a), 1);
+    }
+
+    #[test]
+    fn expired_messages_hidden_then_purged() {
+        let store = MessageStore::new();
+        let peer = [1u8; 32];
+        let mut expired = make_envelope(100);
+        expired.expires_at = Some(unix_millis() - 1);
+        let mut live = make_envelope(200);
+        live.expires_at = Some(unix_millis() + 60_000);
+        store.add(peer, expired);
+        store.add(peer, live);
+        store.add(peer, make_envelope(300));
+
+        assert_eq!(store.count(&peer), 2);
+        assert_eq!(store.purge_expired(), 1);
+        assert_eq!(store.messages.get(&peer).unwrap().len(), 2);
+    }
+
     #[test]
     fn clear_wipes_all_messages() {
         let store = MessageStore::new();
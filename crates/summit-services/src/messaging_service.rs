@@ -3,9 +3,20 @@
 //! `MessageEnvelope` is the JSON wire format for all messaging chunks.
 //! It is defined here because this service is the sole parser of that
 //! format on the wire; `summit-core` has no opinion about chunk payloads.
+//!
+//! An envelope too big for one chunk is split into fragments (see
+//! [`message_chunks`]) and put back together here before it is handled.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use crate::chunk_types::OutgoingChunk;
+use crate::file_transfer::{decode_data_chunk, encode_data_chunk, MAX_CHUNK_SIZE};
 use crate::message_store::MessageStore;
+use crate::presence::PresenceHub;
 use crate::service::ChunkService;
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use summit_core::wire::{service_hash, ChunkHeader, Contract, ServiceHash};
 
@@ -28,13 +39,138 @@ pub struct MessageEnvelope {
     pub timestamp: u64,
     /// Type-specific content. Structure is defined by `msg_type`.
     pub payload: serde_json::Value,
+    /// Set when the message went to several peers at once; shared by every
+    /// message to the same set of members.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    /// Unix time in milliseconds after which the message is deleted by
+    /// sender and recipients alike. `None` = kept per normal retention.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl MessageEnvelope {
+    /// Has this message's TTL run out at `now_ms`?
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        self.expires_at.is_some_and(|t| t <= now_ms)
+    }
+}
+
+/// Current Unix time in milliseconds — the clock message timestamps use.
+pub fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Group id for a set of members (sender included): the same set always
+/// yields the same id, whoever sends.
+pub fn message_group_id(members: &[[u8; 32]]) -> String {
+    let mut sorted = members.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let mut h = summit_core::crypto::Hasher::new();
+    for member in &sorted {
+        h.update(member);
+    }
+    hex::encode(&h.finalize()[..16])
 }
 
 /// Well-known `msg_type` strings.
 pub mod msg_types {
     pub const TEXT: &str = "text";
+    pub const MARKDOWN: &str = "markdown";
+    pub const BLOB: &str = "blob";
+    pub const FILE_REF: &str = "file_ref";
     pub const ACK: &str = "ack";
     pub const READ: &str = "read";
+    /// Ephemeral typing signal, payload `{"typing": bool}`. Never stored.
+    pub const PRESENCE: &str = "presence";
+}
+
+/// Largest inline blob, in bytes before hex encoding. Keeps a blob message
+/// within one chunk; anything bigger goes over file transfer and is sent
+/// as a `FileRef`.
+pub const MAX_INLINE_BLOB: usize = 16 * 1024;
+
+/// Typed content of a message, for the `msg_type`s that carry content.
+///
+/// Serialises as the envelope payload, tagged with `content_type` (always
+/// equal to the envelope's `msg_type`), so `{"text": ..}` stays readable by
+/// peers that only know text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "content_type", rename_all = "snake_case")]
+pub enum MessageContent {
+    Text {
+        text: String,
+    },
+    Markdown {
+        text: String,
+    },
+    /// A small inline blob, e.g. an image. `data` is hex-encoded.
+    Blob {
+        mime_type: String,
+        data: String,
+    },
+    /// A file sent separately over file transfer.
+    FileRef {
+        file_hash: String,
+        filename: String,
+    },
+}
+
+impl MessageContent {
+    /// The envelope `msg_type` for this content.
+    pub fn msg_type(&self) -> &'static str {
+        match self {
+            Self::Text { .. } => msg_types::TEXT,
+            Self::Markdown { .. } => msg_types::MARKDOWN,
+            Self::Blob { .. } => msg_types::BLOB,
+            Self::FileRef { .. } => msg_types::FILE_REF,
+        }
+    }
+
+    /// The envelope payload for this content.
+    pub fn to_payload(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("message content serialises")
+    }
+
+    /// Typed content of an envelope, or `None` for other `msg_type`s and
+    /// malformed payloads.
+    pub fn from_envelope(envelope: &MessageEnvelope) -> Option<Self> {
+        let mut payload = envelope.payload.clone();
+        payload.as_object_mut()?.insert(
+            "content_type".to_string(),
+            serde_json::Value::String(envelope.msg_type.clone()),
+        );
+        serde_json::from_value(payload).ok()
+    }
+
+    /// Check blob size and encoding, and that a file reference names a hash.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Text { .. } | Self::Markdown { .. } => Ok(()),
+            Self::Blob { mime_type, data } => {
+                if mime_type.is_empty() {
+                    anyhow::bail!("blob needs a mime_type");
+                }
+                let len = hex::decode(data)
+                    .map_err(|_| anyhow::anyhow!("blob data must be hex"))?
+                    .len();
+                if len > MAX_INLINE_BLOB {
+                    anyhow::bail!("blob is {len} bytes, limit is {MAX_INLINE_BLOB}");
+                }
+                Ok(())
+            }
+            Self::FileRef { file_hash, .. } => {
+                if hex::decode(file_hash).map(|h| h.len()) != Ok(32) {
+                    anyhow::bail!("file_hash must be 32 hex-encoded bytes");
+                }
+                Ok(())
+            }
+        }
+    }
 }
 
 /// Schema identifier for messaging chunks (used in `ChunkHeader.schema_id`).
@@ -42,15 +178,195 @@ pub fn messaging_schema_id() -> ServiceHash {
     service_hash(b"summit.messaging")
 }
 
+// ── Fragmentation ─────────────────────────────────────────────────────────────
+
+/// Messaging chunk `type_tag`s. They only mean something under
+/// `messaging_schema_id()`: the dispatcher routes messaging chunks on their
+/// schema_id, so these may overlap another service's tags. The content type
+/// travels in the envelope's `msg_type`, never in the tag.
+pub mod msg_chunk_types {
+    /// The payload is a whole JSON envelope.
+    pub const ENVELOPE: u16 = 0;
+    /// The payload is one fragment of a larger envelope.
+    pub const FRAGMENT: u16 = 1;
+}
+
+/// Largest serialised envelope accepted for sending or reassembly.
+pub const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+
+/// Fragment header: blake3 of the whole envelope, then the fragment count
+/// (u32 LE). An `encode_data_chunk` index and data follow.
+const FRAGMENT_HEADER_SIZE: usize = 32 + 4;
+
+/// Incomplete envelopes are dropped after this long without a new fragment.
+const FRAGMENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Envelopes one peer may have in reassembly at once. A new one past this
+/// drops that peer's oldest.
+const MAX_ASSEMBLIES_PER_PEER: usize = 8;
+
+/// Chunks carrying serialised envelope `raw`: one chunk if it fits, else
+/// fragments of at most `MAX_CHUNK_SIZE` bytes of envelope each.
+pub fn message_chunks(raw: Bytes) -> Vec<OutgoingChunk> {
+    let chunk = |type_tag, payload| OutgoingChunk {
+        type_tag,
+        schema_id: messaging_schema_id(),
+        payload,
+        priority_flags: 0x02, // Bulk
+    };
+    if raw.len() <= MAX_CHUNK_SIZE {
+        return vec![chunk(msg_chunk_types::ENVELOPE, raw)];
+    }
+
+    let hash = summit_core::crypto::hash(&raw);
+    let count = raw.len().div_ceil(MAX_CHUNK_SIZE) as u32;
+    raw.chunks(MAX_CHUNK_SIZE)
+        .enumerate()
+        .map(|(index, data)| {
+            let mut payload = Vec::with_capacity(FRAGMENT_HEADER_SIZE + 4 + data.len());
+            payload.extend_from_slice(&hash);
+            payload.extend_from_slice(&count.to_le_bytes());
+            payload.extend_from_slice(&encode_data_chunk(index as u32, data));
+            chunk(msg_chunk_types::FRAGMENT, Bytes::from(payload))
+        })
+        .collect()
+}
+
+/// Fragments of one envelope received so far.
+struct FragmentAssembly {
+    parts: Vec<Option<Bytes>>,
+    received: usize,
+    last_fragment: Instant,
+}
+
+/// Envelopes being reassembled, keyed by (peer, envelope hash).
+type Fragments = HashMap<([u8; 32], [u8; 32]), FragmentAssembly>;
+
+/// Add a fragment; returns the envelope bytes once every fragment is in.
+fn add_fragment(
+    fragments: &Mutex<Fragments>,
+    peer: [u8; 32],
+    payload: &[u8],
+) -> anyhow::Result<Option<Vec<u8>>> {
+    if payload.len() < FRAGMENT_HEADER_SIZE {
+        anyhow::bail!("message fragment too short");
+    }
+    let (header, rest) = payload.split_at(FRAGMENT_HEADER_SIZE);
+    let hash: [u8; 32] = header[..32].try_into().expect("32-byte slice");
+    let count = u32::from_le_bytes(header[32..].try_into().expect("4-byte slice")) as usize;
+    let (index, data) =
+        decode_data_chunk(rest).ok_or_else(|| anyhow::anyhow!("message fragment too short"))?;
+    let index = index as usize;
+    if count == 0 || count > MAX_MESSAGE_BYTES.div_ceil(MAX_CHUNK_SIZE) || index >= count {
+        anyhow::bail!("message fragment {index} of {count} out of range");
+    }
+
+    let mut fragments = fragments.lock().unwrap();
+    fragments.retain(|_, a| a.last_fragment.elapsed() < FRAGMENT_TIMEOUT);
+    if !fragments.contains_key(&(peer, hash)) {
+        let open: Vec<_> = fragments
+            .iter()
+            .filter(|((p, _), _)| *p == peer)
+            .map(|(key, a)| (*key, a.last_fragment))
+            .collect();
+        if open.len() >= MAX_ASSEMBLIES_PER_PEER {
+            let (oldest, _) = open
+                .into_iter()
+                .min_by_key(|(_, at)| *at)
+                .expect("non-empty");
+            fragments.remove(&oldest);
+            tracing::debug!(
+                peer = hex::encode(&peer[..8]),
+                "too many messages in reassembly, dropping the oldest"
+            );
+        }
+    }
+    let assembly = fragments
+        .entry((peer, hash))
+        .or_insert_with(|| FragmentAssembly {
+            parts: vec![None; count],
+            received: 0,
+            last_fragment: Instant::now(),
+        });
+    if assembly.parts.len() != count {
+        anyhow::bail!("message fragment count changed mid-message");
+    }
+    assembly.last_fragment = Instant::now();
+    if assembly.parts[index].is_none() {
+        assembly.parts[index] = Some(Bytes::copy_from_slice(data));
+        assembly.received += 1;
+    }
+    if assembly.received < count {
+        return Ok(None);
+    }
+
+    let assembly = fragments.remove(&(peer, hash)).expect("assembly present");
+    let raw: Vec<u8> = assembly.parts.into_iter().flatten().flatten().collect();
+    if summit_core::crypto::hash(&raw) != hash {
+        anyhow::bail!("reassembled message does not match its hash");
+    }
+    Ok(Some(raw))
+}
+
 // ── Service ───────────────────────────────────────────────────────────────────
 
 pub struct MessagingService {
     store: MessageStore,
+    presence: Option<PresenceHub>,
+    fragments: Arc<Mutex<Fragments>>,
 }
 
 impl MessagingService {
     pub fn new(store: MessageStore) -> Self {
-        Self { store }
+        Self {
+            store,
+            presence: None,
+            fragments: Arc::default(),
+        }
+    }
+
+    /// Relay incoming presence signals to `hub`. Without one they are dropped.
+    pub fn with_presence(mut self, hub: PresenceHub) -> Self {
+        self.presence = Some(hub);
+        self
+    }
+
+    fn handle_envelope(&self, peer_pubkey: &[u8; 32], payload: &[u8]) -> anyhow::Result<()> {
+        let envelope: MessageEnvelope = serde_json::from_slice(payload)
+            .map_err(|e| anyhow::anyhow!("invalid message JSON: {e}"))?;
+
+        if envelope.msg_type == msg_types::PRESENCE {
+            let typing = envelope
+                .payload
+                .get("typing")
+                .and_then(|v| v.as_bool())
+                .ok_or_else(|| anyhow::anyhow!("presence without typing flag"))?;
+            if let Some(hub) = &self.presence {
+                hub.publish(*peer_pubkey, typing);
+            }
+            return Ok(());
+        }
+
+        if let Some(content) = MessageContent::from_envelope(&envelope) {
+            content.validate()?;
+        }
+        if envelope.is_expired(unix_millis()) {
+            tracing::debug!(
+                msg_id = &envelope.msg_id,
+                "dropping already-expired message"
+            );
+            return Ok(());
+        }
+
+        tracing::debug!(
+            sender = &envelope.sender[..16.min(envelope.sender.len())],
+            msg_type = &envelope.msg_type,
+            "message received"
+        );
+
+        self.store.add(*peer_pubkey, envelope);
+
+        Ok(())
     }
 }
 
@@ -80,21 +396,16 @@ impl ChunkService for MessagingService {
     fn handle_chunk(
         &self,
         peer_pubkey: &[u8; 32],
-        _header: &ChunkHeader,
+        header: &ChunkHeader,
         payload: &[u8],
     ) -> anyhow::Result<()> {
-        let envelope: MessageEnvelope = serde_json::from_slice(payload)
-            .map_err(|e| anyhow::anyhow!("invalid message JSON: {e}"))?;
-
-        tracing::debug!(
-            sender = &envelope.sender[..16.min(envelope.sender.len())],
-            msg_type = &envelope.msg_type,
-            "message received"
-        );
-
-        self.store.add(*peer_pubkey, envelope);
-
-        Ok(())
+        if header.type_tag == msg_chunk_types::FRAGMENT {
+            return match add_fragment(&self.fragments, *peer_pubkey, payload)? {
+                Some(raw) => self.handle_envelope(peer_pubkey, &raw),
+                None => Ok(()),
+            };
+        }
+        self.handle_envelope(peer_pubkey, payload)
     }
 }
 
@@ -125,6 +436,8 @@ mod tests {
             sender: "a".repeat(64),
             timestamp,
             payload: serde_json::json!({ "text": "hello" }),
+            group_id: None,
+            expires_at: None,
         }
     }
 
@@ -197,6 +510,242 @@ mod tests {
         assert_eq!(svc.store.get(&peer_b)[0].msg_id, "msg-b");
     }
 
+    #[test]
+    fn plain_text_payload_parses_as_text() {
+        let content = MessageContent::from_envelope(&make_envelope("m", 1)).unwrap();
+        assert_eq!(
+            content,
+            MessageContent::Text {
+                text: "hello".into()
+            }
+        );
+    }
+
+    #[test]
+    fn content_round_trips_through_envelope() {
+        let content = MessageContent::FileRef {
+            file_hash: "ab".repeat(32),
+            filename: "notes.txt".into(),
+        };
+        let mut env = make_envelope("m", 1);
+        env.msg_type = content.msg_type().to_string();
+        env.payload = content.to_payload();
+        assert_eq!(MessageContent::from_envelope(&env), Some(content));
+
+        env.msg_type = msg_types::ACK.to_string();
+        assert_eq!(MessageContent::from_envelope(&env), None);
+    }
+
+    #[test]
+    fn every_content_type_round_trips_through_dispatch() {
+        use crate::file_transfer::FileReassembler;
+        use crate::{IncomingChunk, ServiceDispatcher};
+
+        let svc = std::sync::Arc::new(make_service());
+        let mut dispatcher = ServiceDispatcher::new();
+        dispatcher
+            .register(std::sync::Arc::new(FileReassembler::new(
+                std::env::temp_dir().join("summit-msg-routing-unused"),
+            )))
+            .register(svc.clone());
+
+        let contents = [
+            MessageContent::Text {
+                text: "hello".into(),
+            },
+            MessageContent::Markdown {
+                text: "**hello**".into(),
+            },
+            MessageContent::Blob {
+                mime_type: "image/png".into(),
+                data: "89504e47".into(),
+            },
+            MessageContent::FileRef {
+                file_hash: "ab".repeat(32),
+                filename: "notes.txt".into(),
+            },
+        ];
+        let peer = [1u8; 32];
+        for (i, content) in contents.iter().enumerate() {
+            let mut env = make_envelope(&format!("m{i}"), 1);
+            env.msg_type = content.msg_type().to_string();
+            env.payload = content.to_payload();
+            let raw = Bytes::from(serde_json::to_vec(&env).unwrap());
+            for chunk in message_chunks(raw) {
+                assert_eq!(chunk.schema_id, messaging_schema_id());
+                let incoming = IncomingChunk {
+                    content_hash: summit_core::crypto::hash(&chunk.payload),
+                    type_tag: chunk.type_tag,
+                    schema_id: chunk.schema_id,
+                    payload: chunk.payload,
+                    targeted: false,
+                };
+                assert!(dispatcher.dispatch(&peer, &incoming));
+            }
+        }
+
+        let received: Vec<_> = svc
+            .store
+            .get(&peer)
+            .iter()
+            .map(|env| MessageContent::from_envelope(env).unwrap())
+            .collect();
+        assert_eq!(received, contents);
+    }
+
+    #[test]
+    fn oversized_blob_is_rejected() {
+        let blob = |len| MessageContent::Blob {
+            mime_type: "image/png".into(),
+            data: hex::encode(vec![0u8; len]),
+        };
+        assert!(blob(MAX_INLINE_BLOB).validate().is_ok());
+        assert!(blob(MAX_INLINE_BLOB + 1).validate().is_err());
+
+        let svc = make_service();
+        let mut env = make_envelope("m", 1);
+        env.msg_type = msg_types::BLOB.to_string();
+        env.payload = blob(MAX_INLINE_BLOB + 1).to_payload();
+        let payload = serde_json::to_vec(&env).unwrap();
+        assert!(svc
+            .handle_chunk(&[1u8; 32], &dummy_header(), &payload)
+            .is_err());
+        assert!(svc.store.get(&[1u8; 32]).is_empty());
+    }
+
+    #[test]
+    fn handle_chunk_drops_expired_message() {
+        let svc = make_service();
+        let peer = [1u8; 32];
+        let mut env = make_envelope("old", 100);
+        env.expires_at = Some(1);
+        let payload = serde_json::to_vec(&env).unwrap();
+
+        svc.handle_chunk(&peer, &dummy_header(), &payload).unwrap();
+        assert!(svc.store.get(&peer).is_empty());
+    }
+
+    #[test]
+    fn presence_is_relayed_not_stored() {
+        let hub = PresenceHub::new();
+        let mut events = hub.subscribe();
+        let svc = MessagingService::new(MessageStore::new()).with_presence(hub);
+        let peer = [1u8; 32];
+        let mut env = make_envelope("p", 100);
+        env.msg_type = msg_types::PRESENCE.to_string();
+        env.payload = serde_json::json!({ "typing": true });
+
+        svc.handle_chunk(&peer, &dummy_header(), &serde_json::to_vec(&env).unwrap())
+            .unwrap();
+        assert!(events.try_recv().unwrap().typing);
+        assert!(svc.store.get(&peer).is_empty());
+    }
+
+    #[test]
+    fn large_message_is_fragmented_and_reassembled() {
+        let svc = make_service();
+        let peer = [1u8; 32];
+        let mut env = make_envelope("big", 100);
+        let text = "abcdefghij".repeat(20 * 1024);
+        env.payload = serde_json::json!({ "text": text });
+        let raw = Bytes::from(serde_json::to_vec(&env).unwrap());
+
+        let chunks = message_chunks(raw);
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|c| c.type_tag == msg_chunk_types::FRAGMENT));
+
+        // Out of order, with a duplicate; nothing is stored until the last.
+        let mut header = dummy_header();
+        header.type_tag = msg_chunk_types::FRAGMENT;
+        for chunk in chunks
+            .iter()
+            .skip(1)
+            .rev()
+            .chain(chunks.iter().skip(1).take(1))
+        {
+            svc.handle_chunk(&peer, &header, &chunk.payload).unwrap();
+        }
+        assert!(svc.store.get(&peer).is_empty());
+        svc.handle_chunk(&peer, &header, &chunks[0].payload)
+            .unwrap();
+
+        let msgs = svc.store.get(&peer);
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].payload["text"].as_str(), Some(text.as_str()));
+    }
+
+    #[test]
+    fn small_message_is_one_chunk() {
+        let raw = Bytes::from(serde_json::to_vec(&make_envelope("m", 1)).unwrap());
+        let chunks = message_chunks(raw.clone());
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].type_tag, msg_chunk_types::ENVELOPE);
+        assert_eq!(chunks[0].payload, raw);
+    }
+
+    #[test]
+    fn corrupt_fragment_is_rejected() {
+        let svc = make_service();
+        let peer = [1u8; 32];
+        let raw = Bytes::from(vec![b'x'; MAX_CHUNK_SIZE + 1]);
+        let chunks = message_chunks(raw);
+        let mut header = dummy_header();
+        header.type_tag = msg_chunk_types::FRAGMENT;
+
+        // Reassembled bytes that don't match the hash are dropped.
+        svc.handle_chunk(&peer, &header, &chunks[0].payload)
+            .unwrap();
+        let mut tampered = chunks[1].payload.to_vec();
+        *tampered.last_mut().unwrap() = b'y';
+        assert!(svc.handle_chunk(&peer, &header, &tampered).is_err());
+        assert!(svc.handle_chunk(&peer, &header, b"short").is_err());
+    }
+
+    #[test]
+    fn assemblies_per_peer_are_capped() {
+        let svc = make_service();
+        let peer = [1u8; 32];
+        let mut header = dummy_header();
+        header.type_tag = msg_chunk_types::FRAGMENT;
+        let messages: Vec<_> = (0..=MAX_ASSEMBLIES_PER_PEER)
+            .map(|i| {
+                let mut env = make_envelope(&format!("m{i}"), 100);
+                env.payload = serde_json::json!({ "text": "x".repeat(MAX_CHUNK_SIZE) });
+                message_chunks(Bytes::from(serde_json::to_vec(&env).unwrap()))
+            })
+            .collect();
+
+        // One past the cap: the first message's assembly is dropped.
+        for chunks in &messages {
+            svc.handle_chunk(&peer, &header, &chunks[0].payload)
+                .unwrap();
+        }
+        assert_eq!(svc.fragments.lock().unwrap().len(), MAX_ASSEMBLIES_PER_PEER);
+        // Another peer has a cap of its own.
+        svc.handle_chunk(&[2u8; 32], &header, &messages[0][0].payload)
+            .unwrap();
+        assert_eq!(
+            svc.fragments.lock().unwrap().len(),
+            MAX_ASSEMBLIES_PER_PEER + 1
+        );
+
+        svc.handle_chunk(&peer, &header, &messages[0][1].payload)
+            .unwrap();
+        assert!(svc.store.get(&peer).is_empty());
+        let last = &messages[MAX_ASSEMBLIES_PER_PEER];
+        svc.handle_chunk(&peer, &header, &last[1].payload).unwrap();
+        assert_eq!(svc.store.get(&peer).len(), 1);
+    }
+
+    #[test]
+    fn group_id_ignores_member_order() {
+        let (a, b, c) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        assert_eq!(message_group_id(&[a, b, c]), message_group_id(&[c, a, b]));
+        assert_ne!(message_group_id(&[a, b, c]), message_group_id(&[a, b]));
+    }
+
     #[test]
     fn service_hash_matches_schema_id() {
         let svc = make_service();
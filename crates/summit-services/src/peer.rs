@@ -2,12 +2,33 @@
 
 use std::collections::HashMap;
 use std::net::Ipv6Addr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
 use summit_core::wire::{Contract, ServiceHash};
 
+/// One announced service, with its contract and optional dedicated port.
+/// What we broadcast for ourselves and what we record for each peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceEntry {
+    pub hash: ServiceHash,
+    pub contract: Contract,
+    /// 0 means "use session_port" (typical for Bulk services).
+    pub chunk_port: u16,
+}
+
+impl ServiceEntry {
+    fn from_announcement(ann: &summit_core::wire::CapabilityAnnouncement) -> Self {
+        Self {
+            hash: ann.service_hash,
+            contract: Contract::try_from(ann.contract).unwrap_or(Contract::Bulk),
+            chunk_port: ann.chunk_port,
+        }
+    }
+}
+
 /// Tracked state for a discovered peer.
 ///
 /// Accumulates service announcements over multiple datagrams.
@@ -17,24 +38,29 @@ pub struct PeerEntry {
     /// Peer's link-local address (from the UDP source address).
     pub addr: Ipv6Addr,
 
-    /// Ed25519 public key.
+    /// X25519 static public key.
     pub public_key: [u8; 32],
 
     /// TCP port for session handshakes.
     pub session_port: u16,
 
-    /// Protocol version.
+    /// Protocol version — the highest announced. A late, lower-version
+    /// announcement never downgrades it.
     pub version: u32,
 
-    /// Services this peer offers.
-    /// Key: service hash. Value: (contract, chunk_port).
-    pub services: HashMap<ServiceHash, (Contract, u16)>,
+    /// Services this peer offers, one entry per service hash, in the order
+    /// first announced.
+    pub services: Vec<ServiceEntry>,
 
     /// How many services the peer says it offers (from service_count field).
     pub expected_service_count: u8,
 
     /// Last time any datagram arrived from this peer.
     pub last_seen: Instant,
+
+    /// `sequence` of the newest announcement accepted; anything no newer
+    /// is a replay. 0 for a static peer not yet heard from.
+    pub sequence: u64,
 }
 
 impl PeerEntry {
@@ -43,29 +69,105 @@ impl PeerEntry {
         addr: Ipv6Addr,
         ann: &summit_core::wire::CapabilityAnnouncement,
     ) -> Self {
-        let contract = Contract::try_from(ann.contract).unwrap_or(Contract::Bulk);
-        let mut services = HashMap::new();
-        services.insert(ann.service_hash, (contract, ann.chunk_port));
-
         Self {
             addr,
             public_key: ann.public_key,
             session_port: ann.session_port,
             version: ann.version,
-            services,
+            services: vec![ServiceEntry::from_announcement(ann)],
             expected_service_count: ann.service_count,
             last_seen: Instant::now(),
+            sequence: ann.sequence,
         }
     }
 
-    /// Update from a subsequent announcement datagram.
-    pub fn update_from_announcement(&mut self, ann: &summit_core::wire::CapabilityAnnouncement) {
-        let contract = Contract::try_from(ann.contract).unwrap_or(Contract::Bulk);
-        self.services
-            .insert(ann.service_hash, (contract, ann.chunk_port));
+    /// A peer configured in `network.static_peers`, which never announces
+    /// itself to us. We take it to speak our wire version and run
+    /// `services`.
+    pub fn from_static(
+        addr: Ipv6Addr,
+        public_key: [u8; 32],
+        session_port: u16,
+        services: Vec<ServiceEntry>,
+    ) -> Self {
+        Self {
+            addr,
+            public_key,
+            session_port,
+            version: summit_core::wire::WIRE_VERSION,
+            expected_service_count: services.len() as u8,
+            services,
+            last_seen: Instant::now(),
+            sequence: 0,
+        }
+    }
+
+    /// Update from a subsequent announcement datagram. One older than the
+    /// version we hold only refreshes `last_seen`; returns `false` then.
+    pub fn update_from_announcement(
+        &mut self,
+        ann: &summit_core::wire::CapabilityAnnouncement,
+    ) -> bool {
+        self.last_seen = Instant::now();
+        // The listener has checked the signed address is the source.
+        self.addr = Ipv6Addr::from(ann.addr);
+        if ann.version < self.version {
+            return false;
+        }
+
+        let entry = ServiceEntry::from_announcement(ann);
+        match self.services.iter_mut().find(|s| s.hash == entry.hash) {
+            Some(existing) => *existing = entry,
+            None => self.services.push(entry),
+        }
         self.session_port = ann.session_port;
+        self.version = ann.version;
         self.expected_service_count = ann.service_count;
-        self.last_seen = Instant::now();
+        true
+    }
+
+    /// Does this announcement repeat what we already hold? Peers re-send
+    /// the same datagrams every interval, and those only need `last_seen`
+    /// refreshed.
+    pub fn matches_announcement(&self, ann: &summit_core::wire::CapabilityAnnouncement) -> bool {
+        self.version == ann.version
+            && self.addr == Ipv6Addr::from(ann.addr)
+            && self.session_port == ann.session_port
+            && self.expected_service_count == ann.service_count
+            && self.service(&ann.service_hash) == Some(&ServiceEntry::from_announcement(ann))
+    }
+
+    /// Replace the service list with a complete announced set, dropping
+    /// services the peer no longer offers. A set older than the version we
+    /// hold is ignored. Returns the hashes dropped.
+    pub fn replace_services(
+        &mut self,
+        version: u32,
+        services: Vec<ServiceEntry>,
+    ) -> Vec<ServiceHash> {
+        if version < self.version {
+            return Vec::new();
+        }
+        let dropped = self
+            .services
+            .iter()
+            .filter(|old| !services.iter().any(|s| s.hash == old.hash))
+            .map(|old| old.hash)
+            .collect();
+        self.expected_service_count = services.len() as u8;
+        self.services = services;
+        dropped
+    }
+
+    /// Is `ann` newer than every announcement accepted so far? If so it
+    /// becomes the newest; a replayed or reordered one is not.
+    pub fn accept_sequence(&mut self, ann: &summit_core::wire::CapabilityAnnouncement) -> bool {
+        let sequence = ann.sequence;
+        if sequence <= self.sequence {
+            return false;
+        }
+        self.sequence = sequence;
+        true
     }
 
     /// Have we received all announced services?
@@ -73,19 +175,24 @@ impl PeerEntry {
         self.services.len() >= self.expected_service_count as usize
     }
 
+    /// The entry for a specific service on this peer.
+    pub fn service(&self, hash: &ServiceHash) -> Option<&ServiceEntry> {
+        self.services.iter().find(|s| s.hash == *hash)
+    }
+
     /// Does this peer offer a specific service?
     pub fn has_service(&self, hash: &ServiceHash) -> bool {
-        self.services.contains_key(hash)
+        self.service(hash).is_some()
     }
 
     /// Get the contract for a specific service on this peer.
     pub fn service_contract(&self, hash: &ServiceHash) -> Option<Contract> {
-        self.services.get(hash).map(|(c, _)| *c)
+        self.service(hash).map(|s| s.contract)
     }
 
     /// Get the chunk port for a specific service on this peer.
     pub fn service_chunk_port(&self, hash: &ServiceHash) -> Option<u16> {
-        self.services.get(hash).map(|(_, p)| *p)
+        self.service(hash).map(|s| s.chunk_port)
     }
 }
 
@@ -98,6 +205,151 @@ pub fn new_registry() -> PeerRegistry {
     Arc::new(DashMap::new())
 }
 
+/// Every peer in the registry except ourselves. The listener skips our own
+/// announcements, but bridged or multi-address setups can still surface
+/// our key under another address — never open a session to it.
+pub fn remote_peers(registry: &PeerRegistry, local_public_key: &[u8; 32]) -> Vec<PeerEntry> {
+    registry
+        .iter()
+        .filter(|p| p.key() != local_public_key)
+        .map(|p| p.value().clone())
+        .collect()
+}
+
+/// Remove every peer not heard from within `ttl`. Returns the removed keys,
+/// each with how long it had been silent.
+pub fn expire_peers(registry: &PeerRegistry, ttl: Duration) -> Vec<([u8; 32], Duration)> {
+    let mut expired = Vec::new();
+    registry.retain(|key, entry| {
+        let silent = entry.last_seen.elapsed();
+        if silent < ttl {
+            return true;
+        }
+        expired.push((*key, silent));
+        false
+    });
+    expired
+}
+
+/// How long one peer's per-service datagrams may take to all arrive before
+/// the partial set is discarded. A peer sends its whole set back to back
+/// every `ANNOUNCE_INTERVAL_SECS`.
+pub const ANNOUNCEMENT_FRAGMENT_TIMEOUT: Duration =
+    Duration::from_secs(2 * summit_core::wire::ANNOUNCE_INTERVAL_SECS);
+
+/// A peer's announcement set still missing some services.
+#[derive(Debug)]
+struct PartialAnnouncement {
+    version: u32,
+    /// Indexed by `service_index`; `len()` is the announced `service_count`.
+    services: Vec<Option<ServiceEntry>>,
+    started: Instant,
+}
+
+/// Collects each peer's per-service announcement datagrams, in any order,
+/// until every index in `0..service_count` has arrived. The registry
+/// accumulates services as they come; a complete set is what lets it drop
+/// services a peer stopped announcing.
+#[derive(Debug)]
+pub struct AnnouncementAssembler {
+    pending: HashMap<[u8; 32], PartialAnnouncement>,
+    timeout: Duration,
+}
+
+impl Default for AnnouncementAssembler {
+    fn default() -> Self {
+        Self::new(ANNOUNCEMENT_FRAGMENT_TIMEOUT)
+    }
+}
+
+impl AnnouncementAssembler {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            pending: HashMap::new(),
+            timeout,
+        }
+    }
+
+    /// Add one datagram. Returns the peer's version and complete service
+    /// set, in index order, once the last missing one arrives. A datagram
+    /// with a different count or version than the set being collected
+    /// starts a new set.
+    pub fn add(
+        &mut self,
+        ann: &summit_core::wire::CapabilityAnnouncement,
+    ) -> Option<(u32, Vec<ServiceEntry>)> {
+        let count = ann.service_count as usize;
+        let index = ann.service_index as usize;
+        if index >= count {
+            return None;
+        }
+
+        if !self.pending.contains_key(&ann.public_key) {
+            self.expire();
+        }
+        let fresh = || PartialAnnouncement {
+            version: ann.version,
+            services: vec![None; count],
+            started: Instant::now(),
+        };
+        let partial = self.pending.entry(ann.public_key).or_insert_with(fresh);
+        if partial.version != ann.version
+            || partial.services.len() != count
+            || partial.started.elapsed() >= self.timeout
+        {
+            *partial = fresh();
+        }
+        partial.services[index] = Some(ServiceEntry::from_announcement(ann));
+
+        if partial.services.iter().any(Option::is_none) {
+            return None;
+        }
+        let partial = self.pending.remove(&ann.public_key)?;
+        let version = partial.version;
+        Some((version, partial.services.into_iter().flatten().collect()))
+    }
+
+    /// Discard partial sets older than the timeout. Returns how many.
+    pub fn expire(&mut self) -> usize {
+        let before = self.pending.len();
+        let timeout = self.timeout;
+        self.pending.retain(|_, p| p.started.elapsed() < timeout);
+        before - self.pending.len()
+    }
+
+    /// Peers with a partial set being collected.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Announcement datagrams the listener did not fully process.
+#[derive(Debug, Default)]
+pub struct AnnouncementCounters {
+    /// Dropped by the per-source rate limit.
+    pub rate_limited: AtomicU64,
+    /// Dropped because they failed to parse, their signature did not
+    /// verify, or they came from an address other than the one signed.
+    pub invalid: AtomicU64,
+    /// Dropped because their sequence was no newer than the last accepted
+    /// — replays.
+    pub stale: AtomicU64,
+    /// Repeats of what the registry already held — only `last_seen` refreshed.
+    pub refreshed: AtomicU64,
+}
+
+impl AnnouncementCounters {
+    /// Total datagrams dropped, for any reason.
+    pub fn dropped(&self) -> u64 {
+        self.rate_limited.load(Ordering::Relaxed)
+            + self.invalid.load(Ordering::Relaxed)
+            + self.stale.load(Ordering::Relaxed)
+    }
+}
+
+/// Shared between the announcement listener and the API.
+pub type AnnouncementStats = Arc<AnnouncementCounters>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,4 +360,215 @@ mod tests {
         assert!(registry.is_empty());
         assert_eq!(registry.len(), 0);
     }
+
+    fn announcement(service: u8, chunk_port: u16) -> summit_core::wire::CapabilityAnnouncement {
+        summit_core::wire::CapabilityAnnouncement {
+            service_hash: [service; 32],
+            public_key: [1; 32],
+            version: 4,
+            session_port: 9001,
+            chunk_port,
+            contract: Contract::Bulk as u8,
+            flags: 0,
+            service_count: 2,
+            service_index: 0,
+            addr: Ipv6Addr::LOCALHOST.octets(),
+            sequence: 1,
+            signature: [0; 64],
+        }
+    }
+
+    #[test]
+    fn replayed_sequence_is_refused() {
+        let mut entry =
+            PeerEntry::from_first_announcement(Ipv6Addr::LOCALHOST, &announcement(7, 0));
+        let mut next = announcement(7, 0);
+        next.sequence = 5;
+        assert!(entry.accept_sequence(&next));
+        assert!(!entry.accept_sequence(&next), "same datagram twice");
+        next.sequence = 3;
+        assert!(!entry.accept_sequence(&next), "older than the newest");
+        assert_eq!(entry.sequence, 5);
+    }
+
+    #[test]
+    fn remote_peers_excludes_own_key() {
+        let registry = new_registry();
+        let ours = announcement(7, 0);
+        let mut theirs = announcement(7, 0);
+        theirs.public_key = [2; 32];
+        registry.insert(
+            ours.public_key,
+            PeerEntry::from_first_announcement(Ipv6Addr::LOCALHOST, &ours),
+        );
+        registry.insert(
+            theirs.public_key,
+            PeerEntry::from_first_announcement(Ipv6Addr::LOCALHOST, &theirs),
+        );
+
+        let peers = remote_peers(&registry, &ours.public_key);
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].public_key, [2; 32]);
+    }
+
+    #[test]
+    fn expire_peers_honors_ttl() {
+        let registry = new_registry();
+        let ttl = Duration::from_secs(10);
+        let mut entry =
+            PeerEntry::from_first_announcement(Ipv6Addr::LOCALHOST, &announcement(1, 0));
+
+        // Quiet for less than the TTL: kept
+        entry.last_seen = Instant::now() - Duration::from_secs(9);
+        registry.insert([1; 32], entry.clone());
+        assert!(expire_peers(&registry, ttl).is_empty());
+        assert_eq!(registry.len(), 1);
+
+        // Quiet for longer: removed, reporting how long it was silent
+        entry.last_seen = Instant::now() - Duration::from_secs(11);
+        registry.insert([1; 32], entry);
+        let expired = expire_peers(&registry, ttl);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].0, [1; 32]);
+        assert!(expired[0].1 >= Duration::from_secs(11));
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn services_accumulate_one_entry_per_hash() {
+        let mut entry =
+            PeerEntry::from_first_announcement(Ipv6Addr::LOCALHOST, &announcement(7, 0));
+        assert!(!entry.is_complete());
+
+        let mut second = announcement(8, 5000);
+        second.contract = Contract::Realtime as u8;
+        second.service_index = 1;
+        assert!(entry.update_from_announcement(&second));
+        assert!(entry.is_complete());
+
+        // Re-announcing a service updates its entry in place.
+        assert!(entry.update_from_announcement(&announcement(7, 4000)));
+        assert_eq!(
+            entry.services,
+            vec![
+                ServiceEntry {
+                    hash: [7; 32],
+                    contract: Contract::Bulk,
+                    chunk_port: 4000,
+                },
+                ServiceEntry {
+                    hash: [8; 32],
+                    contract: Contract::Realtime,
+                    chunk_port: 5000,
+                },
+            ]
+        );
+    }
+
+    fn fragment(service: u8, index: u8, count: u8) -> summit_core::wire::CapabilityAnnouncement {
+        let mut ann = announcement(service, 0);
+        ann.service_index = index;
+        ann.service_count = count;
+        ann
+    }
+
+    #[test]
+    fn assembler_completes_out_of_order() {
+        let mut assembler = AnnouncementAssembler::default();
+        assert!(assembler.add(&fragment(9, 2, 3)).is_none());
+        assert!(assembler.add(&fragment(7, 0, 3)).is_none());
+        assert_eq!(assembler.pending(), 1);
+
+        let (version, services) = assembler.add(&fragment(8, 1, 3)).unwrap();
+        assert_eq!(version, 4);
+        let hashes: Vec<_> = services.iter().map(|s| s.hash[0]).collect();
+        assert_eq!(hashes, vec![7, 8, 9]);
+        assert_eq!(assembler.pending(), 0);
+    }
+
+    #[test]
+    fn assembler_restarts_on_new_count_and_discards_stale() {
+        let mut assembler = AnnouncementAssembler::new(Duration::from_millis(20));
+        assert!(assembler.add(&fragment(7, 0, 3)).is_none());
+        // The peer now announces two services: the partial three-set is dropped.
+        assert!(assembler.add(&fragment(8, 1, 2)).is_none());
+        let (_, services) = assembler.add(&fragment(7, 0, 2)).unwrap();
+        assert_eq!(services.len(), 2);
+
+        // An index past the count is nonsense and ignored.
+        assert!(assembler.add(&fragment(7, 2, 2)).is_none());
+        assert_eq!(assembler.pending(), 0);
+
+        assert!(assembler.add(&fragment(7, 0, 2)).is_none());
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(assembler.expire(), 1);
+        assert_eq!(assembler.pending(), 0);
+    }
+
+    #[test]
+    fn complete_set_drops_services_no_longer_announced() {
+        let mut entry = PeerEntry::from_first_announcement(Ipv6Addr::LOCALHOST, &fragment(7, 0, 3));
+        entry.update_from_announcement(&fragment(8, 1, 3));
+        entry.update_from_announcement(&fragment(9, 2, 3));
+        assert!(entry.is_complete());
+
+        // The peer disabled service 8 and re-announced two services.
+        let mut assembler = AnnouncementAssembler::default();
+        assembler.add(&fragment(9, 1, 2));
+        let (version, services) = assembler.add(&fragment(7, 0, 2)).unwrap();
+        let dropped = entry.replace_services(version, services);
+        assert_eq!(dropped, vec![[8; 32]]);
+        assert!(!entry.has_service(&[8; 32]));
+        assert_eq!(entry.expected_service_count, 2);
+        assert!(entry.is_complete());
+
+        // A complete set from an older version changes nothing.
+        let stale = vec![ServiceEntry {
+            hash: [1; 32],
+            contract: Contract::Bulk,
+            chunk_port: 0,
+        }];
+        assert!(entry.replace_services(3, stale).is_empty());
+        assert_eq!(entry.services.len(), 2);
+    }
+
+    #[test]
+    fn repeated_announcement_matches() {
+        let entry = PeerEntry::from_first_announcement(Ipv6Addr::LOCALHOST, &announcement(7, 0));
+        assert!(entry.matches_announcement(&announcement(7, 0)));
+        // A new service, or a changed port, is not a repeat.
+        assert!(!entry.matches_announcement(&announcement(8, 0)));
+        assert!(!entry.matches_announcement(&announcement(7, 5000)));
+
+        let mut upgraded = announcement(7, 0);
+        upgraded.version = 5;
+        assert!(!entry.matches_announcement(&upgraded));
+    }
+
+    #[test]
+    fn lower_version_announcement_does_not_downgrade() {
+        let mut high = announcement(7, 0);
+        high.version = 5;
+        let mut entry = PeerEntry::from_first_announcement(Ipv6Addr::LOCALHOST, &high);
+
+        // A stale announcement arrives late with a lower version and
+        // different fields — only last_seen moves.
+        let mut low = announcement(7, 4000);
+        low.version = 3;
+        low.contract = Contract::Realtime as u8;
+        low.session_port = 9002;
+        let before = entry.last_seen;
+        assert!(!entry.update_from_announcement(&low));
+        assert_eq!(entry.version, 5);
+        assert_eq!(entry.session_port, 9001);
+        assert_eq!(entry.service_contract(&[7; 32]), Some(Contract::Bulk));
+        assert_eq!(entry.service_chunk_port(&[7; 32]), Some(0));
+        assert!(entry.last_seen >= before);
+
+        // Same or higher version still applies.
+        let mut same = announcement(7, 4000);
+        same.version = 5;
+        assert!(entry.update_from_announcement(&same));
+        assert_eq!(entry.service_chunk_port(&[7; 32]), Some(4000));
+    }
 }
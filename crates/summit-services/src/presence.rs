@@ -0,0 +1,171 @@
+//! Presence — ephemeral "is typing" signals between peers.
+//!
+//! Presence travels as a messaging chunk with `msg_type` "presence" but is
+//! never stored: received signals are pushed to live API clients through a
+//! broadcast channel and dropped if nobody is listening. Both directions
+//! are rate limited per peer: every signal spends a token from a small
+//! bucket (`PRESENCE_BURST`, one more per `PRESENCE_REFILL`), and a repeat
+//! of the same state also waits at least `PRESENCE_REPEAT`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// How often an unchanged typing state may be re-sent or re-delivered.
+pub const PRESENCE_REPEAT: Duration = Duration::from_secs(3);
+
+/// Signals a peer may send back to back, whatever their state.
+pub const PRESENCE_BURST: f64 = 4.0;
+
+/// Time to earn back one signal once the burst is spent.
+pub const PRESENCE_REFILL: Duration = Duration::from_secs(1);
+
+/// Undelivered events kept per subscriber before the oldest are skipped.
+const EVENT_CAPACITY: usize = 64;
+
+/// A peer's typing state, as pushed to API clients.
+#[derive(Debug, Clone, Serialize)]
+pub struct PresenceEvent {
+    /// Peer public key, hex-encoded.
+    pub peer: String,
+    pub typing: bool,
+    /// Unix timestamp in milliseconds, when received.
+    pub timestamp: u64,
+}
+
+/// Rate-limit state for one peer.
+struct PeerLimit {
+    /// Last state let through, and when.
+    typing: bool,
+    at: Instant,
+    tokens: f64,
+    refilled: Instant,
+}
+
+/// Per-peer presence rate limits.
+#[derive(Default)]
+struct PresenceLimiter(DashMap<[u8; 32], PeerLimit>);
+
+impl PresenceLimiter {
+    fn allow(&self, peer: [u8; 32], typing: bool) -> bool {
+        self.allow_at(peer, typing, Instant::now())
+    }
+
+    fn allow_at(&self, peer: [u8; 32], typing: bool, now: Instant) -> bool {
+        let mut limit = self.0.entry(peer).or_insert(PeerLimit {
+            typing: !typing,
+            at: now,
+            tokens: PRESENCE_BURST,
+            refilled: now,
+        });
+        let earned =
+            now.duration_since(limit.refilled).as_secs_f64() / PRESENCE_REFILL.as_secs_f64();
+        limit.tokens = (limit.tokens + earned).min(PRESENCE_BURST);
+        limit.refilled = now;
+
+        let repeat_ok = limit.typing != typing || now.duration_since(limit.at) >= PRESENCE_REPEAT;
+        if !repeat_ok || limit.tokens < 1.0 {
+            return false;
+        }
+        limit.tokens -= 1.0;
+        limit.typing = typing;
+        limit.at = now;
+        true
+    }
+}
+
+/// Shared between the messaging service (incoming) and the API (outgoing
+/// and subscribers).
+#[derive(Clone)]
+pub struct PresenceHub {
+    events: broadcast::Sender<PresenceEvent>,
+    incoming: Arc<PresenceLimiter>,
+    outgoing: Arc<PresenceLimiter>,
+}
+
+impl Default for PresenceHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PresenceHub {
+    pub fn new() -> Self {
+        Self {
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            incoming: Arc::default(),
+            outgoing: Arc::default(),
+        }
+    }
+
+    /// Push a received signal to subscribers. Returns false if it was
+    /// rate limited.
+    pub fn publish(&self, peer: [u8; 32], typing: bool) -> bool {
+        if !self.incoming.allow(peer, typing) {
+            return false;
+        }
+        let _ = self.events.send(PresenceEvent {
+            peer: hex::encode(peer),
+            typing,
+            timestamp: crate::messaging_service::unix_millis(),
+        });
+        true
+    }
+
+    /// May we send this signal to `peer` now?
+    pub fn allow_send(&self, peer: [u8; 32], typing: bool) -> bool {
+        self.outgoing.allow(peer, typing)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PresenceEvent> {
+        self.events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_pass_repeats_are_limited() {
+        let hub = PresenceHub::new();
+        let peer = [1u8; 32];
+        assert!(hub.allow_send(peer, true));
+        assert!(!hub.allow_send(peer, true));
+        assert!(hub.allow_send(peer, false));
+        // Other peers are limited separately.
+        assert!(hub.allow_send([2u8; 32], true));
+    }
+
+    #[test]
+    fn alternating_states_are_limited() {
+        let limiter = PresenceLimiter::default();
+        let peer = [1u8; 32];
+        let start = Instant::now();
+        let passed = (0..20)
+            .filter(|i| limiter.allow_at(peer, i % 2 == 0, start))
+            .count();
+        assert_eq!(passed, PRESENCE_BURST as usize);
+
+        // Tokens come back with time.
+        let later = start + PRESENCE_REFILL;
+        assert!(limiter.allow_at(peer, true, later));
+        assert!(!limiter.allow_at(peer, false, later));
+    }
+
+    #[test]
+    fn publish_reaches_subscribers() {
+        let hub = PresenceHub::new();
+        let mut rx = hub.subscribe();
+        assert!(hub.publish([1u8; 32], true));
+        assert!(!hub.publish([1u8; 32], true));
+
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.peer, hex::encode([1u8; 32]));
+        assert!(event.typing);
+        assert!(rx.try_recv().is_err());
+    }
+}
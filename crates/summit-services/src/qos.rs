@@ -6,8 +6,17 @@
 //!   Background — 8 tokens/sec   (only when nothing else is active)
 //!
 //! Each chunk costs 1 token. Empty bucket = drop.
+//!
+//! On top of the per-session buckets, `PeerBandwidth` caps the bytes sent
+//! to each peer across all of its sessions, so one peer cannot take the
+//! whole uplink. It also measures per-peer send and receive rates.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use std::time::Instant;
+use dashmap::DashMap;
 use summit_core::wire::Contract;
 
 const BULK_RATE: f64 = 64.0;
@@ -22,6 +31,20 @@ pub struct TokenBucket {
     refill_rate: f64,
     last_refill: Instant,
     contract: Contract,
+    /// Requests refused for want of tokens.
+    denied: u64,
+}
+
+/// A snapshot of a `TokenBucket`, for QoS debugging.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BucketStats {
+    pub contract: Contract,
+    /// Tokens available now, refill included.
+    pub tokens: f64,
+    pub capacity: f64,
+    /// Tokens added per second.
+    pub rate: f64,
+    pub denied: u64,
 }
 
 impl TokenBucket {
@@ -37,6 +60,7 @@ impl TokenBucket {
             refill_rate,
             last_refill: Instant::now(),
             contract,
+            denied: 0,
         }
     }
 
@@ -56,6 +80,7 @@ impl TokenBucket {
             self.tokens -= 1.0;
             true
         } else {
+            self.denied += 1;
             false
         }
     }
@@ -86,6 +111,7 @@ impl TokenBucket {
             self.tokens -= cost;
             true
         } else {
+            self.denied += 1;
             false
         }
     }
@@ -121,6 +147,169 @@ impl TokenBucket {
     pub fn rate(&self) -> f64 {
         self.refill_rate
     }
+
+    /// Requests refused since the bucket was created.
+    pub fn denied(&self) -> u64 {
+        self.denied
+    }
+
+    /// Current state, counting tokens refilled since the last request.
+    pub fn stats(&self) -> BucketStats {
+        let elapsed = self.last_refill.elapsed().as_secs_f64();
+        BucketStats {
+            contract: self.contract,
+            tokens: (self.tokens + elapsed * self.refill_rate).min(self.capacity),
+            capacity: self.capacity,
+            rate: self.refill_rate,
+            denied: self.denied,
+        }
+    }
+}
+
+/// Window per-peer byte rates are averaged over.
+pub const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Bytes moved to or from one peer, with the peer's upload budget.
+struct PeerUsage {
+    sent: VecDeque<(Instant, u64)>,
+    received: VecDeque<(Instant, u64)>,
+    /// Upload budget in bytes. Goes negative while bulk chunks are held.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl PeerUsage {
+    fn new(cap: u64) -> Self {
+        Self {
+            sent: VecDeque::new(),
+            received: VecDeque::new(),
+            tokens: cap as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, cap: u64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * cap as f64).min(cap as f64);
+        self.last_refill = now;
+    }
+}
+
+/// Bytes per second over the last `RATE_WINDOW`, pruning older samples.
+fn window_rate(samples: &mut VecDeque<(Instant, u64)>) -> u64 {
+    let now = Instant::now();
+    while samples
+        .front()
+        .is_some_and(|(t, _)| now.duration_since(*t) > RATE_WINDOW)
+    {
+        samples.pop_front();
+    }
+    samples.iter().map(|(_, n)| n).sum::<u64>() / RATE_WINDOW.as_secs()
+}
+
+/// A peer's measured byte rates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PeerRates {
+    pub sent_bytes_per_sec: u64,
+    pub received_bytes_per_sec: u64,
+}
+
+/// Per-peer bandwidth accounting and upload cap, keyed on public key and
+/// shared by the send worker, receive loops, and API.
+#[derive(Clone)]
+pub struct PeerBandwidth {
+    /// Bytes/sec per peer. 0 = unlimited. Changes on config reload.
+    cap: Arc<AtomicU64>,
+    peers: Arc<DashMap<[u8; 32], PeerUsage>>,
+}
+
+impl PeerBandwidth {
+    pub fn new(max_bytes_per_sec: u64) -> Self {
+        Self {
+            cap: Arc::new(AtomicU64::new(max_bytes_per_sec)),
+            peers: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub fn cap(&self) -> u64 {
+        self.cap.load(Ordering::Relaxed)
+    }
+
+    /// Change the per-peer cap. Budgets already accrued are clamped to the
+    /// new cap on their next refill.
+    pub fn set_cap(&self, max_bytes_per_sec: u64) {
+        self.cap.store(max_bytes_per_sec, Ordering::Relaxed);
+    }
+
+    pub fn record_sent(&self, peer: [u8; 32], bytes: usize) {
+        let mut usage = self.usage(peer);
+        usage.sent.push_back((Instant::now(), bytes as u64));
+        window_rate(&mut usage.sent);
+    }
+
+    pub fn record_received(&self, peer: [u8; 32], bytes: usize) {
+        let mut usage = self.usage(peer);
+        usage.received.push_back((Instant::now(), bytes as u64));
+        window_rate(&mut usage.received);
+    }
+
+    pub fn rates(&self, peer: &[u8; 32]) -> PeerRates {
+        match self.peers.get_mut(peer) {
+            Some(mut usage) => PeerRates {
+                sent_bytes_per_sec: window_rate(&mut usage.sent),
+                received_bytes_per_sec: window_rate(&mut usage.received),
+            },
+            None => PeerRates::default(),
+        }
+    }
+
+    /// Charge `bytes` of a bulk chunk to `peer`'s budget. Returns how long
+    /// to hold the chunk before sending it — zero while under the cap. The
+    /// budget goes into debt, so held chunks leave in the order reserved.
+    pub fn reserve(&self, peer: [u8; 32], bytes: usize) -> Duration {
+        let cap = self.cap();
+        if cap == 0 {
+            return Duration::ZERO;
+        }
+        let mut usage = self.usage(peer);
+        usage.refill(cap);
+        usage.tokens -= bytes as f64;
+        if usage.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-usage.tokens / cap as f64)
+        }
+    }
+
+    /// Charge `bytes` of a background chunk to `peer`'s budget only if it
+    /// fits now. False means drop it.
+    pub fn try_reserve(&self, peer: [u8; 32], bytes: usize) -> bool {
+        let cap = self.cap();
+        if cap == 0 {
+            return true;
+        }
+        let mut usage = self.usage(peer);
+        usage.refill(cap);
+        if usage.tokens >= bytes as f64 {
+            usage.tokens -= bytes as f64;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Forget every peer `keep` returns false for — those with no session
+    /// left — so departed peers don't accumulate.
+    pub fn retain(&self, keep: impl Fn(&[u8; 32]) -> bool) {
+        self.peers.retain(|peer, _| keep(peer));
+    }
+
+    fn usage(&self, peer: [u8; 32]) -> dashmap::mapref::one::RefMut<'_, [u8; 32], PeerUsage> {
+        self.peers
+            .entry(peer)
+            .or_insert_with(|| PeerUsage::new(self.cap()))
+    }
 }
 
 #[cfg(test)]
@@ -150,6 +339,22 @@ mod tests {
         assert!(allowed < 40); // small margin for float precision + tiny elapsed time
     }
 
+    #[test]
+    fn stats_count_denials() {
+        let mut bucket = TokenBucket::new(Contract::Background);
+        for _ in 0..10 {
+            bucket.allow();
+        }
+        let stats = bucket.stats();
+        assert_eq!(stats.contract, Contract::Background);
+        assert_eq!(stats.capacity, BG_BURST);
+        assert_eq!(stats.rate, BG_RATE);
+        assert!(stats.tokens < 1.0);
+        // Four from the burst, perhaps one refilled in the meantime.
+        assert!((5..=6).contains(&stats.denied), "denied {}", stats.denied);
+        assert_eq!(bucket.denied(), stats.denied);
+    }
+
     #[test]
     fn background_rate_limiting_depletes_tokens() {
         let mut bucket = TokenBucket::new(Contract::Background);
@@ -163,4 +368,66 @@ mod tests {
         assert!(allowed >= 4);
         assert!(allowed < 8);
     }
+
+    #[test]
+    fn peer_cap_holds_bulk_and_drops_background() {
+        let bw = PeerBandwidth::new(1000);
+        let peer = [1u8; 32];
+
+        // One second of budget goes out at once, then chunks are held.
+        assert_eq!(bw.reserve(peer, 1000), Duration::ZERO);
+        let hold = bw.reserve(peer, 500);
+        assert!(hold > Duration::from_millis(400) && hold <= Duration::from_millis(500));
+        assert!(!bw.try_reserve(peer, 1));
+
+        // Other peers have their own budget.
+        assert!(bw.try_reserve([2u8; 32], 1000));
+
+        // Unlimited never holds.
+        let open = PeerBandwidth::new(0);
+        assert_eq!(open.reserve(peer, usize::MAX / 2), Duration::ZERO);
+    }
+
+    #[test]
+    fn peer_cap_changes_live_across_clones() {
+        let bw = PeerBandwidth::new(0);
+        let shared = bw.clone();
+        let peer = [1u8; 32];
+        assert_eq!(bw.reserve(peer, 10_000), Duration::ZERO);
+
+        shared.set_cap(1000);
+        assert_eq!(bw.cap(), 1000);
+        assert!(bw.reserve(peer, 10_000) > Duration::ZERO);
+
+        shared.set_cap(0);
+        assert_eq!(bw.reserve(peer, 10_000), Duration::ZERO);
+    }
+
+    #[test]
+    fn retain_forgets_departed_peers() {
+        let bw = PeerBandwidth::new(1000);
+        let (gone, kept) = ([1u8; 32], [2u8; 32]);
+        bw.reserve(gone, 1000);
+        bw.reserve(kept, 1000);
+        bw.record_sent(gone, 5000);
+
+        bw.retain(|peer| *peer == kept);
+        assert_eq!(bw.rates(&gone), PeerRates::default());
+        assert!(!bw.peers.contains_key(&gone));
+        // The kept peer is still in debt; the other starts afresh.
+        assert!(bw.reserve(kept, 500) > Duration::ZERO);
+        assert_eq!(bw.reserve(gone, 500), Duration::ZERO);
+    }
+
+    #[test]
+    fn peer_rates_average_over_window() {
+        let bw = PeerBandwidth::new(0);
+        let peer = [1u8; 32];
+        bw.record_sent(peer, 5000);
+        bw.record_received(peer, 10_000);
+        let rates = bw.rates(&peer);
+        assert_eq!(rates.sent_bytes_per_sec, 1000);
+        assert_eq!(rates.received_bytes_per_sec, 2000);
+        assert_eq!(bw.rates(&[9u8; 32]), PeerRates::default());
+    }
 }
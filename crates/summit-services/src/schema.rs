@@ -2,6 +2,8 @@
 
 use anyhow::{bail, Context, Result};
 
+use summit_core::wire::Contract;
+
 use crate::file_transfer::FileMetadata;
 
 /// Known schema IDs (precomputed BLAKE3 hashes of schema names).
@@ -15,9 +17,28 @@ pub enum KnownSchema {
     FileMetadata,
     ComputeTask,
     Recovery,
+    /// summit.sync — background cache replication
+    Sync,
+    /// summit.control — session control messages, e.g. Goodbye
+    Control,
 }
 
 impl KnownSchema {
+    /// Every known schema, in declaration order.
+    pub fn all() -> &'static [KnownSchema] {
+        &[
+            Self::TestPing,
+            Self::Message,
+            Self::FileChunk,
+            Self::FileData,
+            Self::FileMetadata,
+            Self::ComputeTask,
+            Self::Recovery,
+            Self::Sync,
+            Self::Control,
+        ]
+    }
+
     pub fn from_id(schema_id: &[u8; 32]) -> Option<Self> {
         let test_ping_id = summit_core::crypto::hash(b"summit.test.ping");
         let file_chunk_id = summit_core::crypto::hash(b"summit.file.chunk");
@@ -26,6 +47,8 @@ impl KnownSchema {
         let message_id = summit_core::wire::messaging_hash();
         let compute_id = summit_core::wire::compute_hash();
         let recovery_id = summit_core::wire::recovery_hash();
+        let sync_id = summit_core::wire::sync_hash();
+        let control_id = summit_core::wire::control_hash();
 
         if schema_id == &test_ping_id {
             Some(Self::TestPing)
@@ -41,6 +64,10 @@ impl KnownSchema {
             Some(Self::ComputeTask)
         } else if schema_id == &recovery_id {
             Some(Self::Recovery)
+        } else if schema_id == &sync_id {
+            Some(Self::Sync)
+        } else if schema_id == &control_id {
+            Some(Self::Control)
         } else {
             None
         }
@@ -72,7 +99,7 @@ impl KnownSchema {
                     .context("invalid compute envelope JSON")?;
                 Ok(())
             }
-            Self::Recovery => Ok(()), // Validated by the handler
+            Self::Recovery | Self::Sync | Self::Control => Ok(()), // Validated by the handler
         }
     }
 
@@ -86,6 +113,8 @@ impl KnownSchema {
             Self::Message => summit_core::wire::messaging_hash(),
             Self::ComputeTask => summit_core::wire::compute_hash(),
             Self::Recovery => summit_core::wire::recovery_hash(),
+            Self::Sync => summit_core::wire::sync_hash(),
+            Self::Control => summit_core::wire::control_hash(),
         }
     }
 
@@ -99,6 +128,46 @@ impl KnownSchema {
             Self::Message => "summit.messaging",
             Self::ComputeTask => "summit.compute",
             Self::Recovery => "summit.recovery",
+            Self::Sync => "summit.sync",
+            Self::Control => "summit.control",
+        }
+    }
+
+    /// The service whose chunks carry this schema, if any.
+    /// Used to look up the negotiated QoS contract for an outgoing chunk.
+    pub fn service_hash(&self) -> Option<summit_core::wire::ServiceHash> {
+        match self {
+            Self::FileChunk | Self::FileData | Self::FileMetadata => {
+                Some(summit_core::wire::file_transfer_hash())
+            }
+            Self::Message => Some(summit_core::wire::messaging_hash()),
+            Self::ComputeTask => Some(summit_core::wire::compute_hash()),
+            Self::Sync => Some(summit_core::wire::sync_hash()),
+            Self::TestPing | Self::Recovery | Self::Control => None,
+        }
+    }
+
+    /// QoS contract chunks of this schema are sent under.
+    /// Recovery traffic (retransmits, NACKs) and session control are always
+    /// Realtime; sync traffic always Background.
+    pub fn contract(&self) -> Contract {
+        match self {
+            Self::Recovery | Self::Control => Contract::Realtime,
+            Self::Sync => Contract::Background,
+            _ => Contract::Bulk,
+        }
+    }
+
+    /// The chunk type_tag this schema travels with, when it has a single one.
+    /// Recovery, sync and control use one tag per control message; messages
+    /// travel as an envelope or as fragments of one.
+    pub fn type_tag(&self) -> Option<u16> {
+        match self {
+            Self::TestPing => Some(1),
+            Self::FileData => Some(2),
+            Self::FileMetadata => Some(3),
+            Self::ComputeTask => Some(0),
+            Self::Message | Self::FileChunk | Self::Recovery | Self::Sync | Self::Control => None,
         }
     }
 
@@ -110,7 +179,7 @@ impl KnownSchema {
             Self::FileChunk | Self::FileData => None,
             Self::Message => None,
             Self::ComputeTask => None,
-            Self::Recovery => None,
+            Self::Recovery | Self::Sync | Self::Control => None,
         }
     }
 }
@@ -127,6 +196,25 @@ fn validate_file_metadata(payload: &[u8]) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn all_round_trips_through_id() {
+        assert_eq!(KnownSchema::all().len(), 9);
+        for schema in KnownSchema::all() {
+            assert_eq!(KnownSchema::from_id(&schema.id()), Some(*schema));
+        }
+        assert_eq!(KnownSchema::Recovery.contract(), Contract::Realtime);
+        assert_eq!(KnownSchema::Control.contract(), Contract::Realtime);
+        assert_eq!(KnownSchema::FileData.contract(), Contract::Bulk);
+        assert_eq!(KnownSchema::Sync.contract(), Contract::Background);
+    }
+
+    #[test]
+    fn multi_tag_schemas_have_no_single_type_tag() {
+        assert_eq!(KnownSchema::Message.type_tag(), None);
+        assert_eq!(KnownSchema::FileData.type_tag(), Some(2));
+        assert_eq!(KnownSchema::ComputeTask.type_tag(), Some(0));
+    }
+
     #[test]
     fn test_ping_validation() {
         let schema = KnownSchema::TestPing;
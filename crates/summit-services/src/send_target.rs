@@ -11,13 +11,17 @@ pub enum SendTarget {
     Broadcast,
 
     /// Send to specific peer by public key.
+    ///
+    /// Not filtered by trust: a targeted send goes out to an untrusted peer
+    /// too, which is how first contact works. The receiver still buffers it
+    /// until it trusts us.
     #[serde(rename = "peer")]
     Peer {
         #[serde(with = "hex_serde")]
         public_key: [u8; 32],
     },
 
-    /// Send to specific session by session ID.
+    /// Send to specific session by session ID. Like `Peer`, not filtered by trust.
     #[serde(rename = "session")]
     Session {
         #[serde(with = "hex_serde")]
@@ -49,6 +53,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn peer_key_accepts_prefix_and_mixed_case() {
+        let json = format!(r#"{{"type":"peer","public_key":"0x{}"}}"#, "Ab".repeat(32));
+        match serde_json::from_str(&json).unwrap() {
+            SendTarget::Peer { public_key } => assert_eq!(public_key, [0xab; 32]),
+            _ => panic!("expected Peer variant"),
+        }
+    }
+
     #[test]
     fn serde_roundtrip_session() {
         let id = [0xcdu8; 32];
@@ -76,8 +89,14 @@ mod hex_serde {
     where
         D: Deserializer<'de>,
     {
+        // Accept what users paste: an optional 0x prefix, either case.
         let s = String::deserialize(deserializer)?;
-        let bytes = hex::decode(&s).map_err(serde::de::Error::custom)?;
+        let s = s.trim();
+        let s = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s);
+        let bytes = hex::decode(s).map_err(serde::de::Error::custom)?;
         if bytes.len() != 32 {
             return Err(serde::de::Error::custom("expected 32 bytes"));
         }
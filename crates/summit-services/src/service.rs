@@ -18,6 +18,32 @@ pub trait ChunkService: Send + Sync {
     /// The contract this service operates under.
     fn contract(&self) -> Contract;
 
+    /// Schema IDs whose chunks the dispatcher routes to this service.
+    /// Defaults to the service hash alone.
+    fn schema_ids(&self) -> Vec<[u8; 32]> {
+        vec![self.service_hash()]
+    }
+
+    /// Type tags routed to this service when a chunk's schema_id matches
+    /// no registered service. Defaults to none.
+    fn type_tags(&self) -> Vec<u16> {
+        Vec::new()
+    }
+
+    /// Whether this service's chunks make this node run something for the
+    /// sender. Chunks for such a service from a receive-only peer are
+    /// dropped. Defaults to false.
+    fn executes(&self) -> bool {
+        false
+    }
+
+    /// Whether this chunk opens new work — a transfer, a message, a task —
+    /// rather than continuing work already in hand. While the service is
+    /// switched off only chunks that don't are delivered. Defaults to true.
+    fn opens_work(&self, _header: &ChunkHeader, _payload: &[u8]) -> bool {
+        true
+    }
+
     /// Called when this service is activated on a session with a peer.
     fn on_activate(&self, peer_pubkey: &[u8; 32]);
 
@@ -26,8 +52,8 @@ pub trait ChunkService: Send + Sync {
 
     /// Handle an incoming chunk that belongs to this service.
     ///
-    /// Called by the daemon's chunk dispatcher after decryption and
-    /// hash verification. The payload is already validated.
+    /// Called by the daemon's chunk dispatcher after decryption, hash
+    /// verification and the trust check. The payload is already validated.
     fn handle_chunk(
         &self,
         peer_pubkey: &[u8; 32],
@@ -1,17 +1,23 @@
 //! Session management — tracks active Noise_XX sessions.
 
 use std::collections::HashMap;
+use std::future::Future;
+use std::net::Ipv6Addr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
-use tokio::net::UdpSocket;
-use tokio::sync::Mutex;
+use tokio::sync::{oneshot, Mutex, Notify};
 
-use summit_core::crypto::Session;
+use summit_core::crypto::{CryptoError, Session};
 use summit_core::wire::{Contract, ServiceHash};
 
+use crate::peer::PeerEntry;
 use crate::qos::TokenBucket;
+use crate::schema::KnownSchema;
+use crate::transport::DatagramSocket;
 
 /// Per-service state within a session.
 #[derive(Debug, Clone)]
@@ -23,7 +29,7 @@ pub struct ServiceOnSession {
 }
 
 /// Metadata about an active session, stored alongside the crypto state.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SessionMeta {
     /// Stable identifier — identical on both sides.
     pub session_id: [u8; 32],
@@ -34,6 +40,12 @@ pub struct SessionMeta {
     /// When this session was established.
     pub established_at: Instant,
     pub peer_pubkey: [u8; 32],
+    /// Service this session was opened for (the HandshakeInit service hash).
+    /// The default file_transfer session carries every negotiated service;
+    /// additional sessions to the same peer are dedicated to one service.
+    pub service: ServiceHash,
+    /// Wire protocol version negotiated with the peer.
+    pub wire_version: u32,
 
     /// Services active on this session, with their contracts.
     /// Built during post-handshake negotiation by intersecting
@@ -52,16 +64,169 @@ impl SessionMeta {
         self.active_services.contains_key(service)
     }
 
-    /// Convenience: get a single contract if all services use the same one.
-    /// Falls back to Bulk if mixed. Used during migration for code that
-    /// still expects a single contract.
+    /// Does any service on this session run under `contract`?
+    pub fn has_contract(&self, contract: Contract) -> bool {
+        self.active_services
+            .values()
+            .any(|s| s.contract == contract)
+    }
+
+    /// The contract an outgoing chunk is sent under on this session.
+    ///
+    /// Chunks explicitly flagged Realtime (recovery, retransmissions) stay
+    /// Realtime. Otherwise the chunk's service is resolved from its schema —
+    /// a schema_id that is itself a service hash maps to that service — and
+    /// the negotiated contract wins. Chunks of services not on the session
+    /// fall back to their own priority flags.
+    pub fn contract_for_chunk(&self, schema_id: &[u8; 32], priority_flags: u8) -> Contract {
+        let flagged = Contract::try_from(priority_flags & 0x03).ok();
+        if flagged == Some(Contract::Realtime) {
+            return Contract::Realtime;
+        }
+        self.contract_for(&chunk_service(schema_id))
+            .or(flagged)
+            .unwrap_or(Contract::Bulk)
+    }
+
+    /// The most urgent contract among the session's active services —
+    /// Realtime if any service runs Realtime, and so on. Bulk when no
+    /// service was negotiated.
     pub fn primary_contract(&self) -> Contract {
-        let mut contracts: Vec<_> = self.active_services.values().map(|s| s.contract).collect();
-        contracts.dedup();
-        if contracts.len() == 1 {
-            contracts[0]
-        } else {
-            Contract::Bulk
+        self.active_services
+            .values()
+            .map(|s| s.contract)
+            .min_by_key(|c| u8::from(*c))
+            .unwrap_or(Contract::Bulk)
+    }
+}
+
+/// Build a session's active service set: the services we offer locally that
+/// the peer also announced.
+///
+/// Both sides compute the same set. If the two announcements disagree on a
+/// contract, the less urgent one wins (e.g. Bulk over Realtime) so neither
+/// side over-commits. The chunk port is the one the peer announced.
+pub fn negotiate_services(
+    local: impl IntoIterator<Item = (ServiceHash, Contract)>,
+    peer: &PeerEntry,
+) -> HashMap<ServiceHash, ServiceOnSession> {
+    local
+        .into_iter()
+        .filter_map(|(hash, local_contract)| {
+            let peer_service = peer.service(&hash)?;
+            let (peer_contract, chunk_port) = (peer_service.contract, peer_service.chunk_port);
+            let contract = if u8::from(peer_contract) > u8::from(local_contract) {
+                peer_contract
+            } else {
+                local_contract
+            };
+            Some((
+                hash,
+                ServiceOnSession {
+                    contract,
+                    chunk_port,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// The service an outgoing chunk belongs to: the service of its known
+/// schema, or the schema_id itself (services may use their hash as schema).
+pub fn chunk_service(schema_id: &[u8; 32]) -> ServiceHash {
+    KnownSchema::from_id(schema_id)
+        .and_then(|s| s.service_hash())
+        .unwrap_or(*schema_id)
+}
+
+/// Pick which of one peer's sessions should carry a chunk.
+///
+/// A session dedicated to the chunk's service wins, then any session that
+/// negotiated the service, then any session at all (e.g. recovery traffic).
+/// Ties go to the longest-established session so the choice is stable.
+pub fn preferred_session<'a>(
+    sessions: impl IntoIterator<Item = &'a SessionMeta>,
+    schema_id: &[u8; 32],
+) -> Option<[u8; 32]> {
+    let service = chunk_service(schema_id);
+    sessions
+        .into_iter()
+        .min_by_key(|m| {
+            let rank = if m.service == service {
+                0
+            } else if m.has_service(&service) {
+                1
+            } else {
+                2
+            };
+            (rank, m.established_at)
+        })
+        .map(|m| m.session_id)
+}
+
+/// The sessions a chunk for `schema_id` goes out on when sent to every peer
+/// in `sessions`: one per distinct peer, picked by `preferred_session`, so a
+/// peer holding several sessions still receives the chunk once. Returned as
+/// (session id, peer) pairs sorted by session id.
+pub fn one_session_per_peer<'a>(
+    sessions: impl IntoIterator<Item = &'a SessionMeta>,
+    schema_id: &[u8; 32],
+) -> Vec<([u8; 32], [u8; 32])> {
+    let mut by_peer: HashMap<[u8; 32], Vec<&SessionMeta>> = HashMap::new();
+    for meta in sessions {
+        by_peer.entry(meta.peer_pubkey).or_default().push(meta);
+    }
+    let mut chosen: Vec<_> = by_peer
+        .into_iter()
+        .filter_map(|(peer, metas)| Some((preferred_session(metas, schema_id)?, peer)))
+        .collect();
+    chosen.sort_unstable();
+    chosen
+}
+
+/// Packets a session's receive loop discarded because they failed to
+/// decrypt, by reason. Replays are the one to watch: a steady stream means
+/// a peer (or someone on path) resending captured traffic, where `too_old`
+/// usually means heavy reordering.
+#[derive(Debug, Default)]
+pub struct DecryptDrops {
+    replayed: AtomicU64,
+    too_old: AtomicU64,
+    too_short: AtomicU64,
+    auth_failed: AtomicU64,
+}
+
+/// A snapshot of `DecryptDrops`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct DecryptDropCounts {
+    /// Nonce already seen inside the replay window.
+    pub replayed: u64,
+    /// Nonce behind the replay window.
+    pub too_old: u64,
+    /// Shorter than a nonce and MAC.
+    pub too_short: u64,
+    /// MAC check failed.
+    pub auth_failed: u64,
+}
+
+impl DecryptDrops {
+    /// Count a packet `Session::decrypt` rejected with `err`.
+    pub fn record(&self, err: &CryptoError) {
+        let counter = match err {
+            CryptoError::Replay => &self.replayed,
+            CryptoError::TooOld => &self.too_old,
+            CryptoError::TooShort => &self.too_short,
+            _ => &self.auth_failed,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn counts(&self) -> DecryptDropCounts {
+        DecryptDropCounts {
+            replayed: self.replayed.load(Ordering::Relaxed),
+            too_old: self.too_old.load(Ordering::Relaxed),
+            too_short: self.too_short.load(Ordering::Relaxed),
+            auth_failed: self.auth_failed.load(Ordering::Relaxed),
         }
     }
 }
@@ -70,8 +235,60 @@ impl SessionMeta {
 pub struct ActiveSession {
     pub meta: SessionMeta,
     pub crypto: Arc<Mutex<Session>>,
-    pub socket: Arc<UdpSocket>, // Dedicated socket for chunk I/O
+    pub socket: Arc<DatagramSocket>, // Dedicated socket for chunk I/O
+    /// Bulk bucket. Reconfigured from the peer's advertised capacity.
     pub bucket: Arc<Mutex<TokenBucket>>,
+    /// Background bucket — separate so background traffic cannot eat bulk tokens.
+    pub background_bucket: Arc<Mutex<TokenBucket>>,
+    /// When a chunk last arrived on this session. Drives idle pruning.
+    pub last_activity: Arc<std::sync::Mutex<Instant>>,
+    /// Signalled when the session is torn down; stops its receive loop.
+    pub closed: Arc<Notify>,
+    /// Packets the receive loop failed to decrypt.
+    pub decrypt_drops: Arc<DecryptDrops>,
+    /// Chunks the receive loop dropped because the handler queue was full.
+    pub receive_queue_drops: Arc<AtomicU64>,
+}
+
+impl ActiveSession {
+    pub fn new(meta: SessionMeta, crypto: Session, socket: Arc<DatagramSocket>) -> Self {
+        Self {
+            meta,
+            crypto: Arc::new(Mutex::new(crypto)),
+            socket,
+            bucket: Arc::new(Mutex::new(TokenBucket::new(Contract::Bulk))),
+            background_bucket: Arc::new(Mutex::new(TokenBucket::new(Contract::Background))),
+            last_activity: Arc::new(std::sync::Mutex::new(Instant::now())),
+            closed: Arc::new(Notify::new()),
+            decrypt_drops: Arc::new(DecryptDrops::default()),
+            receive_queue_drops: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Time since a chunk last arrived (or since establishment).
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity
+            .lock()
+            .map(|t| t.elapsed())
+            .unwrap_or_default()
+    }
+
+    /// Tell the session's receive loop to stop. Call after removing the
+    /// session from the table. The permit is stored, so this is safe to
+    /// call before the loop is waiting.
+    pub fn close(&self) {
+        self.closed.notify_one();
+    }
+
+    /// The token bucket for chunks sent under `contract`.
+    /// Realtime is never rate-limited and has no bucket.
+    pub fn bucket_for(&self, contract: Contract) -> Option<&Arc<Mutex<TokenBucket>>> {
+        match contract {
+            Contract::Realtime => None,
+            Contract::Bulk => Some(&self.bucket),
+            Contract::Background => Some(&self.background_bucket),
+        }
+    }
 }
 
 /// The session table — shared across all tasks.
@@ -82,14 +299,399 @@ pub fn new_session_table() -> SessionTable {
     Arc::new(DashMap::new())
 }
 
+/// Peers whose session was dropped with a cool-down: no handshake with them,
+/// in either direction, until it runs out. Shared by the API, which starts
+/// them, and the session initiator and listener, which honour them.
+///
+/// Also indexed by the address the peer's session used, for the listener:
+/// a HandshakeInit carries no public key.
+#[derive(Clone, Default)]
+pub struct SessionCooldowns {
+    /// Peer → cool-down deadline and the address it was started for.
+    peers: Arc<DashMap<[u8; 32], (Instant, Ipv6Addr)>>,
+    addrs: Arc<DashMap<Ipv6Addr, [u8; 32]>>,
+}
+
+impl SessionCooldowns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold off handshakes with `peer`, last seen at `addr`, for `duration`,
+    /// replacing any cool-down already running.
+    pub fn start(&self, peer: [u8; 32], addr: Ipv6Addr, duration: Duration) {
+        if let Some((_, old)) = self.peers.insert(peer, (Instant::now() + duration, addr)) {
+            self.addrs.remove_if(&old, |_, p| *p == peer);
+        }
+        self.addrs.insert(addr, peer);
+    }
+
+    /// End `peer`'s cool-down early.
+    pub fn clear(&self, peer: &[u8; 32]) {
+        if let Some((_, (_, addr))) = self.peers.remove(peer) {
+            self.addrs.remove_if(&addr, |_, p| p == peer);
+        }
+    }
+
+    /// Time left on `peer`'s cool-down, if one is running. An expired one
+    /// is forgotten.
+    pub fn remaining(&self, peer: &[u8; 32]) -> Option<Duration> {
+        let (deadline, _) = *self.peers.get(peer)?;
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            self.clear(peer);
+            return None;
+        }
+        Some(left)
+    }
+
+    /// Time left on the cool-down of the peer at `addr`, if one is running.
+    pub fn remaining_at(&self, addr: &Ipv6Addr) -> Option<Duration> {
+        let peer = *self.addrs.get(addr)?;
+        self.remaining(&peer)
+    }
+}
+
+/// Where an in-flight handshake with a peer has got to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakePhase {
+    /// We sent HandshakeInit and await the response.
+    Initiator,
+    /// We answered a HandshakeInit and await HandshakeComplete.
+    Responder,
+    /// Noise finished as initiator; waiting for the peer's chunk port.
+    InitiatorWaitingChunk,
+    /// Noise finished as responder; waiting for the peer's chunk port.
+    ResponderWaitingChunk,
+}
+
+impl HandshakePhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Initiator => "initiator",
+            Self::Responder => "responder",
+            Self::InitiatorWaitingChunk => "initiator_waiting_chunk",
+            Self::ResponderWaitingChunk => "responder_waiting_chunk",
+        }
+    }
+}
+
+/// One in-flight handshake, as seen from outside the session layer.
+#[derive(Debug, Clone)]
+pub struct HandshakeInfo {
+    pub peer_addr: Ipv6Addr,
+    pub peer_pubkey: [u8; 32],
+    pub service_hash: ServiceHash,
+    pub phase: HandshakePhase,
+    /// Time spent in this phase so far.
+    pub age: Duration,
+}
+
+/// Read access to the daemon's in-flight handshakes, for diagnostics.
+pub trait HandshakeSource: Send + Sync {
+    fn handshakes(&self) -> Pin<Box<dyn Future<Output = Vec<HandshakeInfo>> + Send + '_>>;
+}
+
+/// What came of asking the session initiator to handshake with a peer now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectOutcome {
+    /// HandshakeInit sent.
+    Initiated,
+    /// The peer already has a default session with us.
+    Connected,
+    /// A handshake with the peer is already past its first message.
+    InProgress,
+    /// The peer isn't in the registry.
+    UnknownPeer,
+    /// The peer announces a wire version too old to talk to.
+    Incompatible,
+    /// We already hold `network.max_sessions` sessions.
+    SessionLimit,
+    /// The init couldn't be sent; the daemon log says why.
+    Failed,
+}
+
+/// A peer to handshake with right away, and where the outcome goes.
+pub type ConnectRequest = ([u8; 32], oneshot::Sender<ConnectOutcome>);
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn decrypt_drops_count_by_reason() {
+        let drops = DecryptDrops::default();
+        for err in [
+            CryptoError::Replay,
+            CryptoError::Replay,
+            CryptoError::TooOld,
+            CryptoError::TooShort,
+            CryptoError::Decrypt,
+        ] {
+            drops.record(&err);
+        }
+        assert_eq!(
+            drops.counts(),
+            DecryptDropCounts {
+                replayed: 2,
+                too_old: 1,
+                too_short: 1,
+                auth_failed: 1,
+            }
+        );
+    }
+
     #[test]
     fn new_session_table_creates_empty() {
         let table = new_session_table();
         assert!(table.is_empty());
         assert_eq!(table.len(), 0);
     }
+
+    fn peer_with(services: &[(ServiceHash, Contract, u16)]) -> PeerEntry {
+        PeerEntry {
+            addr: std::net::Ipv6Addr::LOCALHOST,
+            public_key: [9u8; 32],
+            session_port: 1,
+            version: 1,
+            services: services
+                .iter()
+                .map(|&(hash, contract, chunk_port)| crate::ServiceEntry {
+                    hash,
+                    contract,
+                    chunk_port,
+                })
+                .collect(),
+            expected_service_count: services.len() as u8,
+            last_seen: Instant::now(),
+            sequence: 0,
+        }
+    }
+
+    #[test]
+    fn negotiate_services_intersects_local_and_peer() {
+        use summit_core::wire::{
+            compute_hash, file_transfer_hash, messaging_hash, stream_udp_hash,
+        };
+
+        let peer = peer_with(&[
+            (file_transfer_hash(), Contract::Bulk, 0),
+            (compute_hash(), Contract::Bulk, 0),
+            (stream_udp_hash(), Contract::Realtime, 7000),
+        ]);
+        let local = [
+            (file_transfer_hash(), Contract::Bulk),
+            (messaging_hash(), Contract::Bulk),
+            (stream_udp_hash(), Contract::Realtime),
+        ];
+
+        let active = negotiate_services(local, &peer);
+        assert_eq!(active.len(), 2);
+        assert!(active.contains_key(&file_transfer_hash()));
+        assert!(!active.contains_key(&messaging_hash())); // peer lacks it
+        assert!(!active.contains_key(&compute_hash())); // we lack it
+        let stream = &active[&stream_udp_hash()];
+        assert_eq!(stream.contract, Contract::Realtime);
+        assert_eq!(stream.chunk_port, 7000);
+    }
+
+    fn meta_with(services: &[(ServiceHash, Contract)]) -> SessionMeta {
+        SessionMeta {
+            session_id: [0u8; 32],
+            peer_addr: "[::1]:1".parse().unwrap(),
+            chunk_port: 1,
+            established_at: Instant::now(),
+            peer_pubkey: [9u8; 32],
+            service: summit_core::wire::file_transfer_hash(),
+            wire_version: summit_core::wire::WIRE_VERSION,
+            active_services: services
+                .iter()
+                .map(|(h, c)| {
+                    (
+                        *h,
+                        ServiceOnSession {
+                            contract: *c,
+                            chunk_port: 0,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn contract_for_chunk_uses_negotiated_service_contract() {
+        use summit_core::wire::{file_transfer_hash, recovery_hash, stream_udp_hash};
+
+        let meta = meta_with(&[
+            (file_transfer_hash(), Contract::Bulk),
+            (stream_udp_hash(), Contract::Realtime),
+        ]);
+        // Stream chunk sent with default bulk flags still goes Realtime.
+        assert_eq!(
+            meta.contract_for_chunk(&stream_udp_hash(), 0x02),
+            Contract::Realtime
+        );
+        // File data resolves to the file_transfer service via its schema.
+        assert_eq!(
+            meta.contract_for_chunk(&KnownSchema::FileData.id(), 0x02),
+            Contract::Bulk
+        );
+        // Explicit realtime (retransmit) is never demoted.
+        assert_eq!(
+            meta.contract_for_chunk(&KnownSchema::FileData.id(), 0x01),
+            Contract::Realtime
+        );
+        // Unknown to the session — falls back to the chunk's flags.
+        assert_eq!(
+            meta.contract_for_chunk(&recovery_hash(), 0x03),
+            Contract::Background
+        );
+    }
+
+    #[test]
+    fn primary_contract_is_most_urgent_active_service() {
+        use summit_core::wire::{file_transfer_hash, stream_udp_hash};
+
+        let mixed = meta_with(&[
+            (file_transfer_hash(), Contract::Bulk),
+            (stream_udp_hash(), Contract::Realtime),
+        ]);
+        assert_eq!(mixed.primary_contract(), Contract::Realtime);
+
+        let background = meta_with(&[([5u8; 32], Contract::Background)]);
+        assert_eq!(background.primary_contract(), Contract::Background);
+        assert_eq!(meta_with(&[]).primary_contract(), Contract::Bulk);
+    }
+
+    #[test]
+    fn interleaved_realtime_keeps_full_throughput() {
+        use summit_core::wire::{file_transfer_hash, stream_udp_hash};
+
+        let meta = meta_with(&[
+            (file_transfer_hash(), Contract::Bulk),
+            (stream_udp_hash(), Contract::Realtime),
+        ]);
+        let mut bulk = TokenBucket::new(Contract::Bulk);
+        let mut background = TokenBucket::new(Contract::Background);
+        let has_realtime = meta.has_contract(Contract::Realtime);
+
+        // Same admission steps as the send worker, with no time to refill.
+        let (mut realtime_sent, mut bulk_sent) = (0, 0);
+        for i in 0..400 {
+            let (schema, is_realtime) = if i % 2 == 0 {
+                (stream_udp_hash(), true)
+            } else {
+                (KnownSchema::FileData.id(), false)
+            };
+            let contract = meta.contract_for_chunk(&schema, 0x02);
+            if TokenBucket::should_suppress(contract, has_realtime) {
+                continue;
+            }
+            let allowed = match contract {
+                Contract::Realtime => true,
+                Contract::Bulk => bulk.allow(),
+                Contract::Background => background.allow(),
+            };
+            if allowed && is_realtime {
+                realtime_sent += 1;
+            } else if allowed {
+                bulk_sent += 1;
+            }
+        }
+
+        assert_eq!(realtime_sent, 200);
+        assert!(bulk_sent < 200, "bulk should be rate limited");
+    }
+
+    #[test]
+    fn preferred_session_picks_dedicated_then_default() {
+        use summit_core::wire::{file_transfer_hash, recovery_hash, stream_udp_hash};
+
+        let mut default = meta_with(&[
+            (file_transfer_hash(), Contract::Bulk),
+            (stream_udp_hash(), Contract::Realtime),
+        ]);
+        default.session_id = [1u8; 32];
+        let mut dedicated = meta_with(&[(stream_udp_hash(), Contract::Realtime)]);
+        dedicated.session_id = [2u8; 32];
+        dedicated.service = stream_udp_hash();
+        let sessions = [default, dedicated];
+
+        assert_eq!(
+            preferred_session(&sessions, &stream_udp_hash()),
+            Some([2u8; 32])
+        );
+        assert_eq!(
+            preferred_session(&sessions, &KnownSchema::FileData.id()),
+            Some([1u8; 32])
+        );
+        // Not negotiated anywhere — still goes out on the oldest session.
+        assert_eq!(
+            preferred_session(&sessions, &recovery_hash()),
+            Some([1u8; 32])
+        );
+        assert_eq!(preferred_session(&[], &recovery_hash()), None);
+    }
+
+    #[test]
+    fn cooldown_runs_out_or_is_cleared() {
+        let cooldowns = SessionCooldowns::new();
+        let (a, b) = ([1u8; 32], [2u8; 32]);
+        let (addr_a, addr_b) = ("fe80::1".parse().unwrap(), "fe80::2".parse().unwrap());
+        cooldowns.start(a, addr_a, Duration::from_secs(60));
+        cooldowns.start(b, addr_b, Duration::ZERO);
+
+        let left = cooldowns.remaining(&a).unwrap();
+        assert!(left > Duration::from_secs(59) && left <= Duration::from_secs(60));
+        assert!(cooldowns.remaining_at(&addr_a).is_some());
+        assert_eq!(cooldowns.remaining(&b), None);
+        assert_eq!(cooldowns.remaining_at(&addr_b), None);
+        assert_eq!(cooldowns.remaining(&[3u8; 32]), None);
+
+        // Restarting for a new address moves the address entry.
+        let moved = "fe80::3".parse().unwrap();
+        cooldowns.start(a, moved, Duration::from_secs(60));
+        assert_eq!(cooldowns.remaining_at(&addr_a), None);
+        assert!(cooldowns.remaining_at(&moved).is_some());
+
+        cooldowns.clear(&a);
+        assert_eq!(cooldowns.remaining(&a), None);
+        assert_eq!(cooldowns.remaining_at(&moved), None);
+    }
+
+    #[test]
+    fn one_session_per_peer_collapses_parallel_sessions() {
+        use summit_core::wire::{file_transfer_hash, stream_udp_hash};
+
+        // Peer 9 holds a default and a dedicated session; peer 7 just one.
+        let mut default = meta_with(&[(file_transfer_hash(), Contract::Bulk)]);
+        default.session_id = [1u8; 32];
+        let mut dedicated = meta_with(&[(stream_udp_hash(), Contract::Realtime)]);
+        dedicated.session_id = [2u8; 32];
+        dedicated.service = stream_udp_hash();
+        let mut other = meta_with(&[(file_transfer_hash(), Contract::Bulk)]);
+        other.session_id = [3u8; 32];
+        other.peer_pubkey = [7u8; 32];
+        let sessions = [default, dedicated, other];
+
+        assert_eq!(
+            one_session_per_peer(&sessions, &KnownSchema::FileData.id()),
+            vec![([1u8; 32], [9u8; 32]), ([3u8; 32], [7u8; 32])]
+        );
+        assert_eq!(
+            one_session_per_peer(&sessions, &stream_udp_hash()),
+            vec![([2u8; 32], [9u8; 32]), ([3u8; 32], [7u8; 32])]
+        );
+        assert!(one_session_per_peer(&[], &stream_udp_hash()).is_empty());
+    }
+
+    #[test]
+    fn negotiate_services_mismatched_contract_uses_less_urgent() {
+        use summit_core::wire::stream_udp_hash;
+
+        let peer = peer_with(&[(stream_udp_hash(), Contract::Bulk, 0)]);
+        let active = negotiate_services([(stream_udp_hash(), Contract::Realtime)], &peer);
+        assert_eq!(active[&stream_udp_hash()].contract, Contract::Bulk);
+    }
 }
@@ -0,0 +1,443 @@
+//! Sync service — opportunistic replication of cached chunks.
+//!
+//! Every `interval_secs` each peer the service is active with is offered
+//! the hashes of our most recently used shared chunks — never those of a
+//! transfer sent to one peer. The peer answers with the ones it lacks and
+//! is sent those as data chunks. Each side keeps to the exchange: a WANT is
+//! served only for hashes offered to that peer, and DATA accepted only for
+//! hashes we wanted from it. Only trusted peers take part: one is checked
+//! when its session activates and again at every OFFER and WANT, so a
+//! demotion takes effect at once. Everything travels under the Background
+//! contract, so the send worker holds it back while realtime traffic is
+//! active; anything dropped is offered again next round.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::cache::ChunkCache;
+use crate::chunk_types::OutgoingChunk;
+use crate::send_target::SendTarget;
+use crate::service::ChunkService;
+use crate::trust::TrustRegistry;
+use summit_core::config::SyncSettings;
+use summit_core::wire::{sync, sync_hash, ChunkHeader, Contract, ServiceHash};
+
+/// Hashes per OFFER or WANT chunk; larger lists are split.
+pub const MAX_SYNC_HASHES: usize = 512;
+
+/// Payload of OFFER and WANT chunks: hex-encoded content hashes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncHashes {
+    pub hashes: Vec<String>,
+}
+
+pub struct SyncService {
+    cache: ChunkCache,
+    settings: SyncSettings,
+    chunk_tx: mpsc::Sender<(SendTarget, OutgoingChunk)>,
+    trust: TrustRegistry,
+    /// Peers with an active sync session, and how many sessions each has.
+    peers: DashMap<[u8; 32], usize>,
+    /// Hashes in the latest offer to each peer: what a WANT may ask for.
+    offered: DashMap<[u8; 32], HashSet<[u8; 32]>>,
+    /// Hashes we sent each peer a WANT for and when: what DATA may carry.
+    wanted: DashMap<[u8; 32], HashMap<[u8; 32], Instant>>,
+}
+
+impl SyncService {
+    pub fn new(
+        cache: ChunkCache,
+        settings: SyncSettings,
+        chunk_tx: mpsc::Sender<(SendTarget, OutgoingChunk)>,
+        trust: TrustRegistry,
+    ) -> Self {
+        Self {
+            cache,
+            settings,
+            chunk_tx,
+            trust,
+            peers: DashMap::new(),
+            offered: DashMap::new(),
+            wanted: DashMap::new(),
+        }
+    }
+
+    /// How long a WANT stays outstanding: two offer rounds, after which the
+    /// chunk is offered and wanted afresh anyway.
+    fn want_ttl(&self) -> Duration {
+        Duration::from_secs(2 * self.settings.interval_secs.max(1))
+    }
+
+    /// Peers an offer currently goes to.
+    pub fn peers(&self) -> Vec<[u8; 32]> {
+        self.peers.iter().map(|e| *e.key()).collect()
+    }
+
+    /// Offer our most recently used chunks to every sync peer still
+    /// trusted.
+    pub fn offer(&self) {
+        let peers: Vec<[u8; 32]> = self
+            .peers()
+            .into_iter()
+            .filter(|peer| self.trust.is_trusted(peer))
+            .collect();
+        if peers.is_empty() {
+            return;
+        }
+        let hashes = self.cache.list_shared(self.settings.max_offer_chunks);
+        if hashes.is_empty() {
+            return;
+        }
+        for peer in &peers {
+            self.offered.insert(*peer, hashes.iter().copied().collect());
+            self.send_hashes(peer, sync::OFFER, &hashes);
+        }
+        tracing::debug!(
+            peers = peers.len(),
+            chunks = hashes.len(),
+            "sync offer sent"
+        );
+    }
+
+    /// Offer chunks every `interval_secs` until shutdown.
+    pub async fn run(self: Arc<Self>, mut shutdown: broadcast::Receiver<()>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.settings.interval_secs.max(1)));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => self.offer(),
+                _ = shutdown.recv() => return,
+            }
+        }
+    }
+
+    fn send_hashes(&self, peer: &[u8; 32], type_tag: u16, hashes: &[[u8; 32]]) {
+        for batch in hashes.chunks(MAX_SYNC_HASHES) {
+            let msg = SyncHashes {
+                hashes: batch.iter().map(hex::encode).collect(),
+            };
+            match serde_json::to_vec(&msg) {
+                Ok(raw) => self.send(peer, type_tag, raw.into()),
+                Err(e) => tracing::warn!(error = %e, "failed to encode sync hashes"),
+            }
+        }
+    }
+
+    fn send(&self, peer: &[u8; 32], type_tag: u16, payload: bytes::Bytes) {
+        let chunk = OutgoingChunk {
+            type_tag,
+            schema_id: sync_hash(),
+            payload,
+            priority_flags: 0x03,
+        };
+        let target = SendTarget::Peer { public_key: *peer };
+        // Background traffic: a full queue just skips this round.
+        if let Err(e) = self.chunk_tx.try_send((target, chunk)) {
+            tracing::debug!(error = %e, "sync chunk not enqueued");
+        }
+    }
+}
+
+fn decode_hashes(payload: &[u8]) -> anyhow::Result<Vec<[u8; 32]>> {
+    let msg: SyncHashes =
+        serde_json::from_slice(payload).map_err(|e| anyhow::anyhow!("invalid sync JSON: {e}"))?;
+    Ok(msg
+        .hashes
+        .iter()
+        .take(MAX_SYNC_HASHES)
+        .filter_map(|h| hex::decode(h).ok()?.try_into().ok())
+        .collect())
+}
+
+impl ChunkService for SyncService {
+    fn service_hash(&self) -> ServiceHash {
+        sync_hash()
+    }
+
+    fn contract(&self) -> Contract {
+        Contract::Background
+    }
+
+    fn on_activate(&self, peer_pubkey: &[u8; 32]) {
+        if !self.trust.is_trusted(peer_pubkey) {
+            tracing::debug!(
+                peer = hex::encode(&peer_pubkey[..8]),
+                "sync: peer not trusted, not syncing"
+            );
+            return;
+        }
+        *self.peers.entry(*peer_pubkey).or_insert(0) += 1;
+        tracing::info!(
+            peer = hex::encode(&peer_pubkey[..8]),
+            "sync service activated"
+        );
+    }
+
+    fn on_deactivate(&self, peer_pubkey: &[u8; 32]) {
+        let gone = self
+            .peers
+            .remove_if_mut(peer_pubkey, |_, sessions| {
+                *sessions = sessions.saturating_sub(1);
+                *sessions == 0
+            })
+            .is_some();
+        if gone {
+            self.offered.remove(peer_pubkey);
+            self.wanted.remove(peer_pubkey);
+        }
+        tracing::debug!(
+            peer = hex::encode(&peer_pubkey[..8]),
+            "sync service deactivated"
+        );
+    }
+
+    fn handle_chunk(
+        &self,
+        peer_pubkey: &[u8; 32],
+        header: &ChunkHeader,
+        payload: &[u8],
+    ) -> anyhow::Result<()> {
+        if matches!(header.type_tag, sync::OFFER | sync::WANT)
+            && !self.trust.is_trusted(peer_pubkey)
+        {
+            tracing::debug!(
+                peer = hex::encode(&peer_pubkey[..8]),
+                "sync: peer not trusted, ignoring"
+            );
+            return Ok(());
+        }
+        match header.type_tag {
+            sync::OFFER => {
+                let now = Instant::now();
+                let ttl = self.want_ttl();
+                let mut outstanding = self.wanted.entry(*peer_pubkey).or_default();
+                outstanding.retain(|_, at| now.duration_since(*at) < ttl);
+                let wanted: Vec<[u8; 32]> = decode_hashes(payload)?
+                    .into_iter()
+                    .filter(|h| !self.cache.contains(h))
+                    .collect();
+                for hash in &wanted {
+                    outstanding.insert(*hash, now);
+                }
+                drop(outstanding);
+                if !wanted.is_empty() {
+                    self.send_hashes(peer_pubkey, sync::WANT, &wanted);
+                }
+            }
+            sync::WANT => {
+                let Some(offered) = self.offered.get(peer_pubkey) else {
+                    return Ok(());
+                };
+                let hashes: Vec<[u8; 32]> = decode_hashes(payload)?
+                    .into_iter()
+                    .filter(|h| offered.contains(h))
+                    .collect();
+                drop(offered);
+                for hash in hashes {
+                    if let Some(data) = self.cache.get(&hash)? {
+                        self.send(peer_pubkey, sync::DATA, data);
+                    }
+                }
+            }
+            sync::DATA => {
+                let hash = summit_core::crypto::hash(payload);
+                let asked = self
+                    .wanted
+                    .get_mut(peer_pubkey)
+                    .is_some_and(|mut wanted| wanted.remove(&hash).is_some());
+                if !asked {
+                    tracing::debug!(
+                        peer = hex::encode(&peer_pubkey[..8]),
+                        "sync: unrequested chunk, ignoring"
+                    );
+                    return Ok(());
+                }
+                // Offered to us as shared, so ours to offer on too.
+                self.cache.put(&hash, payload)?;
+                self.cache.share(&hash);
+            }
+            other => {
+                tracing::warn!(type_tag = other, "sync: unknown type_tag, ignoring");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A service that trusts every peer.
+    fn make_service(
+        cache: ChunkCache,
+    ) -> (SyncService, mpsc::Receiver<(SendTarget, OutgoingChunk)>) {
+        let trust = TrustRegistry::new();
+        trust.apply_config(true, &[]);
+        make_service_with(cache, trust)
+    }
+
+    fn make_service_with(
+        cache: ChunkCache,
+        trust: TrustRegistry,
+    ) -> (SyncService, mpsc::Receiver<(SendTarget, OutgoingChunk)>) {
+        let (tx, rx) = mpsc::channel(64);
+        (
+            SyncService::new(cache, SyncSettings::default(), tx, trust),
+            rx,
+        )
+    }
+
+    fn header(type_tag: u16) -> ChunkHeader {
+        ChunkHeader {
+            content_hash: [0u8; 32],
+            schema_id: sync_hash(),
+            type_tag,
+            length: 0,
+            flags: 0,
+            version: 1,
+        }
+    }
+
+    fn share(cache: &ChunkCache, data: &[u8]) {
+        let hash = summit_core::crypto::hash(data);
+        cache.put(&hash, data).unwrap();
+        cache.share(&hash);
+    }
+
+    fn hashes(type_tag: u16, hashes: &[[u8; 32]]) -> (ChunkHeader, Vec<u8>) {
+        let msg = SyncHashes {
+            hashes: hashes.iter().map(hex::encode).collect(),
+        };
+        (header(type_tag), serde_json::to_vec(&msg).unwrap())
+    }
+
+    /// Deliver every queued chunk from one side to the other.
+    fn deliver(
+        rx: &mut mpsc::Receiver<(SendTarget, OutgoingChunk)>,
+        from: &[u8; 32],
+        to: &SyncService,
+    ) -> usize {
+        let mut n = 0;
+        while let Ok((_, chunk)) = rx.try_recv() {
+            assert_eq!(chunk.schema_id, sync_hash());
+            assert_eq!(chunk.priority_flags, 0x03);
+            to.handle_chunk(from, &header(chunk.type_tag), &chunk.payload)
+                .unwrap();
+            n += 1;
+        }
+        n
+    }
+
+    #[test]
+    fn offer_replicates_missing_chunks_only() {
+        let (a_key, b_key) = ([1u8; 32], [2u8; 32]);
+        let (a, mut a_rx) = make_service(ChunkCache::in_memory());
+        let (b, mut b_rx) = make_service(ChunkCache::in_memory());
+
+        let shared = b"both have this".as_slice();
+        let only_a = b"only a has this".as_slice();
+        let private = b"sent to one peer".as_slice();
+        for data in [shared, only_a] {
+            share(&a.cache, data);
+        }
+        a.cache
+            .put(&summit_core::crypto::hash(private), private)
+            .unwrap();
+        share(&b.cache, shared);
+
+        // No active peer, no offer.
+        a.offer();
+        assert!(a_rx.try_recv().is_err());
+
+        a.on_activate(&b_key);
+        a.offer();
+        assert_eq!(deliver(&mut a_rx, &a_key, &b), 1); // OFFER
+        assert_eq!(deliver(&mut b_rx, &b_key, &a), 1); // WANT for only_a
+        assert_eq!(deliver(&mut a_rx, &a_key, &b), 1); // DATA
+
+        assert!(b.cache.has(&summit_core::crypto::hash(only_a)));
+        assert!(!b.cache.has(&summit_core::crypto::hash(private)));
+        assert_eq!(b.cache.count(), 2);
+        // What B got through sync it may offer on.
+        assert_eq!(b.cache.list_shared(10).len(), 2);
+
+        // Nothing left to want, so the next round ends at the offer.
+        a.offer();
+        assert_eq!(deliver(&mut a_rx, &a_key, &b), 1);
+        assert!(b_rx.try_recv().is_err());
+
+        a.on_deactivate(&b_key);
+        assert!(a.peers().is_empty());
+    }
+
+    #[test]
+    fn want_and_data_outside_the_exchange_are_ignored() {
+        let peer = [2u8; 32];
+        let (a, mut a_rx) = make_service(ChunkCache::in_memory());
+        a.on_activate(&peer);
+
+        // A WANT for a cached chunk never offered to this peer.
+        let secret = b"never offered".as_slice();
+        let secret_hash = summit_core::crypto::hash(secret);
+        a.cache.put(&secret_hash, secret).unwrap();
+        let (h, body) = hashes(sync::WANT, &[secret_hash]);
+        a.handle_chunk(&peer, &h, &body).unwrap();
+        assert!(a_rx.try_recv().is_err());
+
+        // DATA nobody asked for.
+        let pushed = b"unsolicited".as_slice();
+        a.handle_chunk(&peer, &header(sync::DATA), pushed).unwrap();
+        assert!(!a.cache.has(&summit_core::crypto::hash(pushed)));
+
+        // DATA we wanted is accepted once.
+        let (h, body) = hashes(sync::OFFER, &[summit_core::crypto::hash(pushed)]);
+        a.handle_chunk(&peer, &h, &body).unwrap();
+        assert_eq!(a_rx.try_recv().unwrap().1.type_tag, sync::WANT);
+        a.handle_chunk(&peer, &header(sync::DATA), pushed).unwrap();
+        assert!(a.cache.has(&summit_core::crypto::hash(pushed)));
+        assert!(a.wanted.get(&peer).unwrap().is_empty());
+
+        a.on_deactivate(&peer);
+        assert!(a.wanted.is_empty() && a.offered.is_empty());
+    }
+
+    #[test]
+    fn untrusted_peers_are_not_synced_with() {
+        let (trusted, stranger) = ([2u8; 32], [3u8; 32]);
+        let trust = TrustRegistry::new();
+        trust.trust(trusted);
+        let (a, mut a_rx) = make_service_with(ChunkCache::in_memory(), trust.clone());
+        share(&a.cache, b"shared");
+        let shared = summit_core::crypto::hash(b"shared");
+
+        a.on_activate(&stranger);
+        assert!(a.peers().is_empty());
+        a.on_activate(&trusted);
+        a.offer();
+        assert!(matches!(
+            a_rx.try_recv().unwrap().0,
+            SendTarget::Peer { public_key } if public_key == trusted
+        ));
+        assert!(a_rx.try_recv().is_err());
+
+        // A stranger's OFFER gets no WANT.
+        let (h, body) = hashes(sync::OFFER, &[[9u8; 32]]);
+        a.handle_chunk(&stranger, &h, &body).unwrap();
+        assert!(a_rx.try_recv().is_err());
+
+        // Demoted after the offer: its WANT goes unanswered, and it is
+        // offered nothing more.
+        trust.block(trusted);
+        let (h, body) = hashes(sync::WANT, &[shared]);
+        a.handle_chunk(&trusted, &h, &body).unwrap();
+        assert!(a_rx.try_recv().is_err());
+        a.offer();
+        assert!(a_rx.try_recv().is_err());
+    }
+}
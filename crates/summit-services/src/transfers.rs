@@ -0,0 +1,219 @@
+//! Outgoing transfer tracking — how long each sent file took to drain.
+//!
+//! The outbound queue is FIFO, so a file has drained once the send worker
+//! has taken as many chunks off the queue as were waiting behind (and
+//! including) its last chunk. The API records that mark when it finishes
+//! enqueueing; the send worker counts chunks as it sends them.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::time::Instant;
+
+/// How many transfers to remember.
+const MAX_RECENT: usize = 32;
+
+/// One outgoing file transfer.
+#[derive(Debug, Clone)]
+pub struct TransferRecord {
+    pub id: u64,
+    pub filename: String,
+    pub bytes: u64,
+    pub chunks: usize,
+    /// Unix time the send was requested.
+    pub started_unix: u64,
+    started_at: Instant,
+    /// Dequeue count at which the last chunk has been sent.
+    drain_mark: Option<u64>,
+    /// Time from request to the last chunk leaving the queue.
+    pub duration: Option<Duration>,
+}
+
+impl TransferRecord {
+    pub fn is_complete(&self) -> bool {
+        self.duration.is_some()
+    }
+
+    /// Effective throughput in MB/s, once complete.
+    pub fn mb_per_sec(&self) -> Option<f64> {
+        let secs = self.duration?.as_secs_f64();
+        (secs > 0.0).then(|| self.bytes as f64 / (1024.0 * 1024.0) / secs)
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Chunks the send worker has taken off the outbound queue, ever.
+    dequeued: AtomicU64,
+    /// Lowest drain mark among incomplete transfers (u64::MAX if none),
+    /// so the per-chunk path only locks when something may complete.
+    next_mark: AtomicU64,
+    next_id: AtomicU64,
+    recent: Mutex<VecDeque<TransferRecord>>,
+}
+
+/// Shared log of recent outgoing transfers.
+#[derive(Clone)]
+pub struct TransferTracker {
+    inner: Arc<Inner>,
+}
+
+impl Default for TransferTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransferTracker {
+    pub fn new() -> Self {
+        let inner = Inner::default();
+        inner.next_mark.store(u64::MAX, Ordering::Relaxed);
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Record the start of a send. Returns its id.
+    pub fn start(&self, filename: String, bytes: u64, chunks: usize) -> u64 {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let started_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut recent = self.inner.recent.lock().unwrap();
+        if recent.len() == MAX_RECENT {
+            recent.pop_front();
+        }
+        recent.push_back(TransferRecord {
+            id,
+            filename,
+            bytes,
+            chunks,
+            started_unix,
+            started_at: Instant::now(),
+            drain_mark: None,
+            duration: None,
+        });
+        id
+    }
+
+    /// All of a transfer's chunks are enqueued; `queue_len` reports how
+    /// many (its last one included) are still waiting in the outbound queue.
+    ///
+    /// The length and the dequeue count are read as one snapshot: if the
+    /// send worker takes a chunk between the two reads, they are read again.
+    pub fn enqueued(&self, id: u64, queue_len: impl Fn() -> usize) {
+        let mark = loop {
+            let before = self.inner.dequeued.load(Ordering::Acquire);
+            let len = queue_len() as u64;
+            if self.inner.dequeued.load(Ordering::Acquire) == before {
+                break before + len;
+            }
+        };
+        let mut recent = self.inner.recent.lock().unwrap();
+        let Some(record) = recent.iter_mut().find(|r| r.id == id) else {
+            return;
+        };
+        record.drain_mark = Some(mark);
+        self.inner.next_mark.fetch_min(mark, Ordering::Relaxed);
+        Self::complete_drained(&mut recent, &self.inner);
+    }
+
+    /// Forget a transfer that failed before all its chunks were enqueued.
+    pub fn abandon(&self, id: u64) {
+        self.inner.recent.lock().unwrap().retain(|r| r.id != id);
+    }
+
+    /// Called by the send worker for every chunk it takes off the queue,
+    /// as soon as it has taken it.
+    pub fn chunk_dequeued(&self) {
+        let dequeued = self.inner.dequeued.fetch_add(1, Ordering::AcqRel) + 1;
+        if dequeued >= self.inner.next_mark.load(Ordering::Relaxed) {
+            let mut recent = self.inner.recent.lock().unwrap();
+            Self::complete_drained(&mut recent, &self.inner);
+        }
+    }
+
+    /// Recent transfers, oldest first.
+    pub fn recent(&self) -> Vec<TransferRecord> {
+        self.inner.recent.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn complete_drained(recent: &mut VecDeque<TransferRecord>, inner: &Inner) {
+        let dequeued = inner.dequeued.load(Ordering::Relaxed);
+        let mut next_mark = u64::MAX;
+        for record in recent.iter_mut().filter(|r| r.duration.is_none()) {
+            match record.drain_mark {
+                Some(mark) if mark <= dequeued => {
+                    record.duration = Some(record.started_at.elapsed());
+                }
+                Some(mark) => next_mark = next_mark.min(mark),
+                None => {}
+            }
+        }
+        inner.next_mark.store(next_mark, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completes_once_queue_ahead_has_drained() {
+        let tracker = TransferTracker::new();
+        let id = tracker.start("a.bin".into(), 1024, 3);
+        tracker.enqueued(id, || 2);
+        assert!(!tracker.recent()[0].is_complete());
+
+        tracker.chunk_dequeued();
+        assert!(!tracker.recent()[0].is_complete());
+        tracker.chunk_dequeued();
+        assert!(tracker.recent()[0].is_complete());
+    }
+
+    #[test]
+    fn empty_queue_completes_immediately() {
+        let tracker = TransferTracker::new();
+        let id = tracker.start("a.bin".into(), 1024, 1);
+        tracker.enqueued(id, || 0);
+        assert!(tracker.recent()[0].is_complete());
+    }
+
+    #[test]
+    fn queue_length_and_dequeue_count_are_read_together() {
+        let tracker = TransferTracker::new();
+        let id = tracker.start("a.bin".into(), 1024, 3);
+        // The worker takes one chunk while the queue length is being read:
+        // the first read of 3 is discarded, the second sees 2.
+        let reads = std::cell::Cell::new(0);
+        tracker.enqueued(id, || {
+            reads.set(reads.get() + 1);
+            if reads.get() == 1 {
+                tracker.chunk_dequeued();
+                3
+            } else {
+                2
+            }
+        });
+        assert_eq!(reads.get(), 2);
+
+        tracker.chunk_dequeued();
+        assert!(!tracker.recent()[0].is_complete());
+        tracker.chunk_dequeued();
+        assert!(tracker.recent()[0].is_complete());
+    }
+
+    #[test]
+    fn keeps_only_recent_transfers() {
+        let tracker = TransferTracker::new();
+        for i in 0..MAX_RECENT + 5 {
+            tracker.start(format!("{i}.bin"), 1, 1);
+        }
+        let recent = tracker.recent();
+        assert_eq!(recent.len(), MAX_RECENT);
+        assert_eq!(recent[0].filename, "5.bin");
+    }
+}
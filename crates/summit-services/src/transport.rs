@@ -0,0 +1,234 @@
+//! Datagram transport — a UDP socket, or an endpoint on an in-process
+//! loopback network.
+//!
+//! The loopback network stands in for a link: each daemon on it is a host
+//! with its own fe80:: address, sockets bind ports on that address, and a
+//! datagram sent to a multicast group reaches every socket that joined the
+//! group on the destination port. Delivery goes through bounded channels;
+//! a full receive queue drops the datagram, as a full socket buffer would.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::sync::{Arc, Mutex};
+
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+
+/// Datagrams queued per loopback socket before new ones are dropped.
+const LOOPBACK_QUEUE: usize = 4096;
+
+/// First port handed out for a loopback bind to port 0.
+const EPHEMERAL_PORTS: u16 = 49152;
+
+/// A datagram socket, over UDP or the loopback network.
+pub enum DatagramSocket {
+    Udp(UdpSocket),
+    Loopback(LoopbackSocket),
+}
+
+impl DatagramSocket {
+    pub async fn send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        match self {
+            DatagramSocket::Udp(s) => s.send_to(buf, target).await,
+            DatagramSocket::Loopback(s) => s.send_to(buf, target),
+        }
+    }
+
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match self {
+            DatagramSocket::Udp(s) => s.recv_from(buf).await,
+            DatagramSocket::Loopback(s) => s.recv_from(buf).await,
+        }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match self {
+            DatagramSocket::Udp(s) => s.local_addr(),
+            DatagramSocket::Loopback(s) => Ok(SocketAddr::V6(s.addr)),
+        }
+    }
+}
+
+impl From<UdpSocket> for DatagramSocket {
+    fn from(socket: UdpSocket) -> Self {
+        DatagramSocket::Udp(socket)
+    }
+}
+
+type Datagram = (Vec<u8>, SocketAddr);
+
+#[derive(Default)]
+struct NetState {
+    hosts: u16,
+    next_port: HashMap<Ipv6Addr, u16>,
+    sockets: HashMap<(Ipv6Addr, u16), mpsc::Sender<Datagram>>,
+    /// Multicast members by (group, port).
+    groups: HashMap<(Ipv6Addr, u16), HashSet<Ipv6Addr>>,
+}
+
+/// An in-process network of loopback sockets.
+#[derive(Clone, Default)]
+pub struct LoopbackNet {
+    state: Arc<Mutex<NetState>>,
+}
+
+impl LoopbackNet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A new host address on the network: fe80::1, fe80::2, …
+    pub fn add_host(&self) -> Ipv6Addr {
+        let mut state = self.state.lock().unwrap();
+        state.hosts += 1;
+        Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, state.hosts)
+    }
+
+    /// Bind `port` on `host`; 0 picks a free port.
+    pub fn bind(&self, host: Ipv6Addr, port: u16) -> io::Result<LoopbackSocket> {
+        let mut state = self.state.lock().unwrap();
+        let port = match port {
+            0 => {
+                let mut port = state
+                    .next_port
+                    .get(&host)
+                    .map_or(EPHEMERAL_PORTS, |p| (*p).max(EPHEMERAL_PORTS));
+                while state.sockets.contains_key(&(host, port)) {
+                    port = port.checked_add(1).ok_or(io::ErrorKind::AddrNotAvailable)?;
+                }
+                state.next_port.insert(host, port.wrapping_add(1));
+                port
+            }
+            port if state.sockets.contains_key(&(host, port)) => {
+                return Err(io::ErrorKind::AddrInUse.into())
+            }
+            port => port,
+        };
+        let (tx, rx) = mpsc::channel(LOOPBACK_QUEUE);
+        state.sockets.insert((host, port), tx);
+        Ok(LoopbackSocket {
+            addr: SocketAddrV6::new(host, port, 0, 0),
+            net: self.clone(),
+            rx: tokio::sync::Mutex::new(rx),
+        })
+    }
+
+    fn deliver(&self, from: SocketAddrV6, to: SocketAddr, datagram: &[u8]) {
+        let SocketAddr::V6(to) = to else {
+            return;
+        };
+        let state = self.state.lock().unwrap();
+        let hosts: Vec<Ipv6Addr> = if to.ip().is_multicast() {
+            state
+                .groups
+                .get(&(*to.ip(), to.port()))
+                .map(|members| members.iter().copied().collect())
+                .unwrap_or_default()
+        } else {
+            vec![*to.ip()]
+        };
+        for host in hosts {
+            if let Some(tx) = state.sockets.get(&(host, to.port())) {
+                // Full or closed: lost, as on a real link.
+                let _ = tx.try_send((datagram.to_vec(), SocketAddr::V6(from)));
+            }
+        }
+    }
+}
+
+/// A socket bound on a `LoopbackNet`. Unbinds when dropped.
+pub struct LoopbackSocket {
+    addr: SocketAddrV6,
+    net: LoopbackNet,
+    rx: tokio::sync::Mutex<mpsc::Receiver<Datagram>>,
+}
+
+impl LoopbackSocket {
+    /// Receive datagrams sent to `group` on this socket's port.
+    pub fn join_multicast(&self, group: Ipv6Addr) {
+        let mut state = self.net.state.lock().unwrap();
+        state
+            .groups
+            .entry((group, self.addr.port()))
+            .or_default()
+            .insert(*self.addr.ip());
+    }
+
+    pub fn send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        self.net.deliver(self.addr, target, buf);
+        Ok(buf.len())
+    }
+
+    /// Like UDP, a datagram longer than `buf` is truncated to fit.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (datagram, from) = self
+            .rx
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or(io::ErrorKind::NotConnected)?;
+        let len = datagram.len().min(buf.len());
+        buf[..len].copy_from_slice(&datagram[..len]);
+        Ok((len, from))
+    }
+}
+
+impl Drop for LoopbackSocket {
+    fn drop(&mut self) {
+        let mut state = self.net.state.lock().unwrap();
+        let key = (*self.addr.ip(), self.addr.port());
+        state.sockets.remove(&key);
+        for ((_, port), members) in state.groups.iter_mut() {
+            if *port == key.1 {
+                members.remove(&key.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn unicast_and_multicast_reach_bound_sockets() {
+        let net = LoopbackNet::new();
+        let (a, b) = (net.add_host(), net.add_host());
+        assert_ne!(a, b);
+
+        let sa = DatagramSocket::Loopback(net.bind(a, 0).unwrap());
+        let sb = DatagramSocket::Loopback(net.bind(b, 0).unwrap());
+        let b_addr = sb.local_addr().unwrap();
+        sa.send_to(b"hello", b_addr).await.unwrap();
+
+        let mut buf = [0u8; 3];
+        let (len, from) = sb.recv_from(&mut buf).await.unwrap();
+        assert_eq!((len, &buf), (3, b"hel"));
+        assert_eq!(from, sa.local_addr().unwrap());
+
+        let group: Ipv6Addr = "ff02::1".parse().unwrap();
+        let la = net.bind(a, 9000).unwrap();
+        let lb = net.bind(b, 9000).unwrap();
+        assert!(net.bind(b, 9000).is_err());
+        la.join_multicast(group);
+        lb.join_multicast(group);
+        sa.send_to(b"all", SocketAddr::V6(SocketAddrV6::new(group, 9000, 0, 0)))
+            .await
+            .unwrap();
+        for listener in [&la, &lb] {
+            let mut buf = [0u8; 16];
+            let (len, _) = listener.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], b"all");
+        }
+
+        // Unbound on drop: the port is free again, and nothing is queued.
+        drop(lb);
+        let lb = net.bind(b, 9000).unwrap();
+        sa.send_to(b"x", SocketAddr::V6(SocketAddrV6::new(group, 9000, 0, 0)))
+            .await
+            .unwrap();
+        assert!(lb.rx.lock().await.try_recv().is_err());
+    }
+}
@@ -1,13 +1,19 @@
 //! Trust management — peer authorization and access control.
 //!
-//! Three-tier trust model:
-//! - Blocked:    Sessions dropped, chunks rejected
-//! - Untrusted:  Sessions exist, chunks buffered (default for new peers)
-//! - Trusted:    Full access, chunks processed immediately
+//! Four-tier trust model:
+//! - Blocked:     Sessions dropped, chunks rejected
+//! - Untrusted:   Sessions exist, chunks buffered (default for new peers)
+//! - ReceiveOnly: Files and messages processed; chunks for services that
+//!   execute anything (compute) dropped, and no broadcasts sent to the peer
+//! - Trusted:     Full access, chunks processed immediately
 //!
 //! Sessions auto-establish (Noise handshake completes) but chunks are only
 //! processed from trusted peers. This allows public discovery while maintaining
 //! user control over data flow.
+//!
+//! The first chunk buffered for a peer publishes a `PeerPendingApproval`
+//! event; more chunks from the same peer stay quiet until its buffer is
+//! flushed or cleared.
 
 use bytes::Bytes;
 use dashmap::DashMap;
@@ -15,6 +21,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 
 /// Trust level for a peer, keyed by their public key.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,10 +32,22 @@ pub enum TrustLevel {
     /// Peer is unknown — sessions allowed, chunks buffered
     #[default]
     Untrusted,
+    /// Peer may send us data but not run anything here — chunks processed
+    /// except for executing services, left out of broadcasts
+    #[serde(rename = "receive_only")]
+    ReceiveOnly,
     /// Peer is trusted — full access, process chunks
     Trusted,
 }
 
+impl TrustLevel {
+    /// Whether a broadcast send reaches a peer at this level. Only trusted
+    /// peers: a receive-only peer is one we take data from, not send to.
+    pub fn gets_broadcasts(self) -> bool {
+        self == TrustLevel::Trusted
+    }
+}
+
 /// Registry of trusted/blocked peers.
 ///
 /// When constructed with a `persist_path`, trust rules are written to disk
@@ -104,15 +123,15 @@ impl TrustRegistry {
     pub fn check(&self, public_key: &[u8; 32]) -> TrustLevel {
         if self.auto_trust.load(std::sync::atomic::Ordering::Relaxed) {
             // In auto-trust mode, return Trusted unless explicitly blocked
+            // or limited to receive-only
             let level = self
                 .rules
                 .get(public_key)
                 .map(|r| *r.value())
                 .unwrap_or(TrustLevel::Trusted);
-            return if matches!(level, TrustLevel::Blocked) {
-                TrustLevel::Blocked
-            } else {
-                TrustLevel::Trusted
+            return match level {
+                TrustLevel::Blocked | TrustLevel::ReceiveOnly => level,
+                _ => TrustLevel::Trusted,
             };
         }
         self.rules
@@ -128,6 +147,14 @@ impl TrustRegistry {
         tracing::info!(peer = hex::encode(public_key), "peer trusted");
     }
 
+    /// Accept files and messages from a peer without letting it run
+    /// anything here.
+    pub fn receive_only(&self, public_key: [u8; 32]) {
+        self.rules.insert(public_key, TrustLevel::ReceiveOnly);
+        self.save_to_disk();
+        tracing::info!(peer = hex::encode(public_key), "peer set to receive-only");
+    }
+
     /// Mark a peer as blocked. Existing sessions will be dropped.
     pub fn block(&self, public_key: [u8; 32]) {
         self.rules.insert(public_key, TrustLevel::Blocked);
@@ -149,21 +176,23 @@ impl TrustRegistry {
             .collect()
     }
 
-    /// Count peers by trust level.
-    pub fn counts(&self) -> (usize, usize, usize) {
+    /// Count peers by trust level: trusted, untrusted, blocked, receive-only.
+    pub fn counts(&self) -> (usize, usize, usize, usize) {
         let mut trusted = 0;
         let mut untrusted = 0;
         let mut blocked = 0;
+        let mut receive_only = 0;
 
         for entry in self.rules.iter() {
             match *entry.value() {
                 TrustLevel::Trusted => trusted += 1,
                 TrustLevel::Untrusted => untrusted += 1,
                 TrustLevel::Blocked => blocked += 1,
+                TrustLevel::ReceiveOnly => receive_only += 1,
             }
         }
 
-        (trusted, untrusted, blocked)
+        (trusted, untrusted, blocked, receive_only)
     }
 }
 
@@ -181,6 +210,7 @@ impl TrustRegistry {
                 let level = match *entry.value() {
                     TrustLevel::Trusted => "trusted",
                     TrustLevel::Blocked => "blocked",
+                    TrustLevel::ReceiveOnly => "receive_only",
                     TrustLevel::Untrusted => "untrusted",
                 };
                 (hex::encode(entry.key()), level.to_string())
@@ -228,6 +258,7 @@ impl TrustRegistry {
                     let level = match level_str.as_str() {
                         "trusted" => TrustLevel::Trusted,
                         "blocked" => TrustLevel::Blocked,
+                        "receive_only" => TrustLevel::ReceiveOnly,
                         _ => TrustLevel::Untrusted,
                     };
                     self.rules.insert(key, level);
@@ -258,12 +289,30 @@ pub struct BufferedChunk {
     pub type_tag: u16,
     pub schema_id: [u8; 32],
     pub payload: Bytes,
+    /// Sent to us alone (`FLAG_TARGETED`): never shared once released.
+    pub targeted: bool,
 }
 
+/// An untrusted peer started sending us data, pushed to /events.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerPendingApproval {
+    /// Peer public key, hex-encoded.
+    pub peer: String,
+    /// Chunks buffered for the peer when the event was raised.
+    pub buffered_chunks: usize,
+    /// Unix timestamp in milliseconds.
+    pub timestamp: u64,
+}
+
+/// Undelivered pending-approval events kept per subscriber.
+const EVENT_CAPACITY: usize = 64;
+
 /// Buffer for chunks from untrusted peers.
 pub struct UntrustedBuffer {
     /// Map: peer_pubkey -> Vec<BufferedChunk>
     buffer: Arc<DashMap<[u8; 32], Vec<BufferedChunk>>>,
+    /// Peers that started buffering, pushed to /events.
+    pending: broadcast::Sender<PeerPendingApproval>,
 }
 
 /// Max buffered chunks per untrusted peer. Prevents memory exhaustion from
@@ -280,11 +329,13 @@ impl UntrustedBuffer {
     pub fn new() -> Self {
         Self {
             buffer: Arc::new(DashMap::new()),
+            pending: broadcast::channel(EVENT_CAPACITY).0,
         }
     }
 
     /// Add a chunk from an untrusted peer (preserves full header for replay).
-    /// Drops the oldest chunk if the per-peer cap is reached.
+    /// Drops the oldest chunk if the per-peer cap is reached. The first chunk
+    /// buffered for a peer raises a `PeerPendingApproval` event.
     pub fn add(
         &self,
         peer_pubkey: [u8; 32],
@@ -292,6 +343,7 @@ impl UntrustedBuffer {
         type_tag: u16,
         schema_id: [u8; 32],
         data: Bytes,
+        targeted: bool,
     ) {
         let mut entry = self.buffer.entry(peer_pubkey).or_default();
         if entry.len() >= MAX_BUFFERED_PER_PEER {
@@ -302,7 +354,23 @@ impl UntrustedBuffer {
             type_tag,
             schema_id,
             payload: data,
+            targeted,
         });
+        let buffered_chunks = entry.len();
+        drop(entry);
+
+        if buffered_chunks == 1 {
+            let _ = self.pending.send(PeerPendingApproval {
+                peer: hex::encode(peer_pubkey),
+                buffered_chunks,
+                timestamp: crate::messaging_service::unix_millis(),
+            });
+        }
+    }
+
+    /// Subscribe to pending-approval events.
+    pub fn subscribe_pending(&self) -> broadcast::Receiver<PeerPendingApproval> {
+        self.pending.subscribe()
     }
 
     /// Retrieve and remove all buffered chunks for a peer (when they become trusted).
@@ -344,6 +412,7 @@ impl Clone for UntrustedBuffer {
     fn clone(&self) -> Self {
         Self {
             buffer: self.buffer.clone(),
+            pending: self.pending.clone(),
         }
     }
 }
@@ -389,14 +458,33 @@ mod tests {
         reg.trust([1u8; 32]);
         reg.trust([2u8; 32]);
         reg.block([3u8; 32]);
+        reg.receive_only([4u8; 32]);
 
         let list = reg.list();
-        assert_eq!(list.len(), 3);
+        assert_eq!(list.len(), 4);
 
-        let (trusted, untrusted, blocked) = reg.counts();
+        let (trusted, untrusted, blocked, receive_only) = reg.counts();
         assert_eq!(trusted, 2);
         assert_eq!(untrusted, 0);
         assert_eq!(blocked, 1);
+        assert_eq!(receive_only, 1);
+    }
+
+    #[test]
+    fn receive_only_survives_auto_trust_and_skips_broadcasts() {
+        let reg = TrustRegistry::new();
+        let peer = [1u8; 32];
+        reg.receive_only(peer);
+        assert_eq!(reg.check(&peer), TrustLevel::ReceiveOnly);
+        assert!(!reg.is_trusted(&peer));
+
+        // auto_trust widens unknown peers, not an explicit receive-only rule
+        reg.apply_config(true, &[]);
+        assert_eq!(reg.check(&peer), TrustLevel::ReceiveOnly);
+        assert_eq!(reg.check(&[2u8; 32]), TrustLevel::Trusted);
+
+        assert!(TrustLevel::Trusted.gets_broadcasts());
+        assert!(!TrustLevel::ReceiveOnly.gets_broadcasts());
     }
 
     #[test]
@@ -405,7 +493,7 @@ mod tests {
         let peer = [1u8; 32];
         let hash = [2u8; 32];
 
-        buf.add(peer, hash, 2, [0u8; 32], Bytes::from_static(b"data"));
+        buf.add(peer, hash, 2, [0u8; 32], Bytes::from_static(b"data"), false);
         assert_eq!(buf.count(&peer), 1);
 
         let flushed = buf.flush(&peer);
@@ -417,15 +505,64 @@ mod tests {
         assert_eq!(buf.count(&peer), 0);
     }
 
+    #[test]
+    fn untrusted_buffer_announces_each_peer_once() {
+        let buf = UntrustedBuffer::new();
+        let mut events = buf.subscribe_pending();
+        let peer = [1u8; 32];
+
+        for i in 0..500u16 {
+            let data = Bytes::copy_from_slice(&i.to_le_bytes());
+            buf.add(peer, [0u8; 32], 2, [0u8; 32], data, false);
+        }
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.peer, hex::encode(peer));
+        assert_eq!(event.buffered_chunks, 1);
+        assert!(events.try_recv().is_err());
+
+        // Once flushed, a peer that buffers again is announced again.
+        buf.flush(&peer);
+        buf.add(
+            peer,
+            [0u8; 32],
+            2,
+            [0u8; 32],
+            Bytes::from_static(b"again"),
+            false,
+        );
+        assert!(events.try_recv().is_ok());
+    }
+
     #[test]
     fn untrusted_buffer_total_and_peers() {
         let buf = UntrustedBuffer::new();
         let peer_a = [1u8; 32];
         let peer_b = [2u8; 32];
 
-        buf.add(peer_a, [10u8; 32], 2, [0u8; 32], Bytes::from_static(b"a1"));
-        buf.add(peer_a, [11u8; 32], 2, [0u8; 32], Bytes::from_static(b"a2"));
-        buf.add(peer_b, [20u8; 32], 2, [0u8; 32], Bytes::from_static(b"b1"));
+        buf.add(
+            peer_a,
+            [10u8; 32],
+            2,
+            [0u8; 32],
+            Bytes::from_static(b"a1"),
+            false,
+        );
+        buf.add(
+            peer_a,
+            [11u8; 32],
+            2,
+            [0u8; 32],
+            Bytes::from_static(b"a2"),
+            false,
+        );
+        buf.add(
+            peer_b,
+            [20u8; 32],
+            2,
+            [0u8; 32],
+            Bytes::from_static(b"b1"),
+            false,
+        );
 
         assert_eq!(buf.total(), 3);
 
@@ -438,7 +575,14 @@ mod tests {
         let buf = UntrustedBuffer::new();
         let peer = [1u8; 32];
 
-        buf.add(peer, [10u8; 32], 2, [0u8; 32], Bytes::from_static(b"data"));
+        buf.add(
+            peer,
+            [10u8; 32],
+            2,
+            [0u8; 32],
+            Bytes::from_static(b"data"),
+            false,
+        );
         assert_eq!(buf.count(&peer), 1);
 
         buf.clear(&peer);
@@ -456,6 +600,7 @@ mod tests {
             let reg = TrustRegistry::with_persistence(path.clone());
             reg.trust([1u8; 32]);
             reg.block([2u8; 32]);
+            reg.receive_only([4u8; 32]);
         }
 
         // File should exist
@@ -466,6 +611,7 @@ mod tests {
         assert_eq!(reg2.check(&[1u8; 32]), TrustLevel::Trusted);
         assert_eq!(reg2.check(&[2u8; 32]), TrustLevel::Blocked);
         assert_eq!(reg2.check(&[3u8; 32]), TrustLevel::Untrusted);
+        assert_eq!(reg2.check(&[4u8; 32]), TrustLevel::ReceiveOnly);
 
         // Remove a rule — should persist
         reg2.remove(&[1u8; 32]);
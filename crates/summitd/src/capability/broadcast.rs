@@ -1,80 +1,186 @@
 //! Capability announcement broadcast.
 //!
 //! Periodically sends one CapabilityAnnouncement datagram per enabled service
-//! to the link-local multicast address ff02::1. Receivers accumulate by
+//! to the configured multicast group (`network.multicast_group`, ff02::1 by
+//! default). Receivers accumulate by
 //! public_key to build each peer's full service set.
 
-use std::net::SocketAddrV6;
+use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
 use socket2::{Domain, Protocol, Socket, Type};
+use tokio::sync::watch;
 use zerocopy::AsBytes;
 
+use summit_core::config::ServicesConfig;
 use summit_core::crypto::Keypair;
-use summit_core::wire::{CapabilityAnnouncement, Contract, ServiceHash, MULTICAST_ADDR_V6};
-
-/// One service to announce, with its contract and optional dedicated port.
-#[derive(Debug, Clone)]
-pub struct ServiceEntry {
-    pub hash: ServiceHash,
-    pub contract: Contract,
-    /// 0 means "use session_port" (typical for Bulk services).
-    pub chunk_port: u16,
+use summit_core::wire::{
+    service_hash, CapabilityAnnouncement, Contract, DISCOVERY_PORT, WIRE_VERSION,
+};
+pub use summit_services::ServiceEntry;
+use summit_services::{DatagramSocket, ServiceDispatcher};
+
+use crate::link::Link;
+
+/// The services this node announces. Replaced when the config is reloaded
+/// or a service is switched on or off through the API; sessions already
+/// established keep the services they negotiated.
+pub type LocalServices = watch::Receiver<Vec<ServiceEntry>>;
+
+/// Built-in services that can be enabled, in announcement order.
+const SERVICE_NAMES: [&str; 5] = [
+    "file_transfer",
+    "messaging",
+    "stream_udp",
+    "compute",
+    "sync",
+];
+
+/// Names of the services enabled in `services`, e.g. "messaging".
+pub fn enabled_service_names(services: &ServicesConfig) -> Vec<String> {
+    let enabled = [
+        services.file_transfer,
+        services.messaging,
+        services.stream_udp,
+        services.compute,
+        services.sync,
+    ];
+    SERVICE_NAMES
+        .into_iter()
+        .zip(enabled)
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// The announcement entries for the named services. `chunk_port` is the
+/// dedicated port realtime streams are offered on.
+pub fn service_entries(names: &[String], chunk_port: u16) -> Vec<ServiceEntry> {
+    names
+        .iter()
+        .map(|name| {
+            let (contract, chunk_port) = match name.as_str() {
+                "stream_udp" => (Contract::Realtime, chunk_port),
+                "sync" => (Contract::Background, 0),
+                _ => (Contract::Bulk, 0),
+            };
+            ServiceEntry {
+                hash: service_hash(format!("summit.{name}").as_bytes()),
+                contract,
+                chunk_port,
+            }
+        })
+        .collect()
 }
 
-/// Broadcast all enabled services on a regular interval.
+/// Keep announcements and chunk routing in step with the enabled service
+/// names: every change is announced on the next broadcast and switches the
+/// matching services on or off in `dispatcher`.
+pub async fn follow_service_names(
+    mut names: watch::Receiver<Vec<String>>,
+    chunk_port: u16,
+    services_tx: watch::Sender<Vec<ServiceEntry>>,
+    dispatcher: Arc<ServiceDispatcher>,
+) {
+    loop {
+        let mut enabled = Vec::new();
+        for name in SERVICE_NAMES {
+            let on = names.borrow_and_update().iter().any(|n| n == name);
+            dispatcher.set_enabled(service_hash(format!("summit.{name}").as_bytes()), on);
+            if on {
+                enabled.push(name.to_string());
+            }
+        }
+        services_tx.send_replace(service_entries(&enabled, chunk_port));
+        if names.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Broadcast all enabled services on a regular interval, and straight away
+/// whenever they change.
 ///
 /// Sends one datagram per service per tick. Cancel by dropping the task handle.
 ///
 /// # Arguments
 /// * `keypair` — This node's identity keypair. Public key goes in each datagram.
-/// * `interface_index` — OS interface index to bind to.
+/// * `link` — Where to send from: the interface, or a loopback host.
+/// * `local_addr` — Our link-local address, signed into every datagram.
+/// * `multicast_group` / `discovery_port` — Where announcements are sent.
 /// * `session_port` — TCP port for session handshakes.
-/// * `services` — List of services to announce. Built from config.
+/// * `services` — Services to announce, re-read every tick.
 pub async fn broadcast_loop(
     keypair: Arc<Keypair>,
-    interface_index: u32,
+    link: Link,
+    local_addr: Ipv6Addr,
+    multicast_group: Ipv6Addr,
+    discovery_port: u16,
     session_port: u16,
-    services: Vec<ServiceEntry>,
+    mut services: LocalServices,
 ) -> Result<()> {
-    let socket = make_multicast_socket(interface_index)
-        .context("failed to create multicast broadcast socket")?;
+    let socket = match &link {
+        Link::Udp { interface_index } => {
+            let socket = make_multicast_socket(*interface_index, local_addr)
+                .context("failed to create multicast broadcast socket")?;
+            DatagramSocket::from(tokio::net::UdpSocket::from_std(socket.into())?)
+        }
+        Link::Loopback { net, host } => DatagramSocket::Loopback(net.bind(*host, 0)?),
+    };
 
     let interval_secs = summit_core::wire::ANNOUNCE_INTERVAL_SECS;
     let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
 
-    let dest = SocketAddrV6::new(MULTICAST_ADDR_V6, 9000, 0, interface_index);
+    let dest = SocketAddrV6::new(multicast_group, discovery_port, 0, link.scope_id());
 
-    let service_count = services.len() as u8;
+    // Microseconds since the epoch at startup, then one more per datagram:
+    // it keeps growing across restarts, so receivers can refuse replays.
+    let mut sequence = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default();
 
     tracing::info!(
-        interface_index,
-        service_count,
+        interface_index = link.scope_id(),
+        group = %multicast_group,
+        port = discovery_port,
+        service_count = services.borrow().len(),
         interval_secs,
         "capability broadcast starting"
     );
 
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            Ok(()) = services.changed() => {}
+        }
+
+        let services = services.borrow_and_update().clone();
+        let service_count = services.len() as u8;
 
         for (index, entry) in services.iter().enumerate() {
-            let announcement = CapabilityAnnouncement {
+            let mut announcement = CapabilityAnnouncement {
                 service_hash: entry.hash,
                 public_key: keypair.public,
-                version: 1,
+                version: WIRE_VERSION,
                 session_port,
                 chunk_port: entry.chunk_port,
                 contract: entry.contract as u8,
                 flags: 0,
                 service_count,
                 service_index: index as u8,
+                addr: local_addr.octets(),
+                sequence,
+                signature: [0; 64],
             };
+            sequence += 1;
+            announcement.sign(&keypair);
 
             let bytes = announcement.as_bytes();
 
-            match socket.send_to(bytes, &dest.into()) {
+            match socket.send_to(bytes, dest.into()).await {
                 Ok(n) => tracing::trace!(service_index = index, bytes = n, "broadcast sent"),
                 Err(e) => tracing::warn!(
                     service_index = index,
@@ -86,16 +192,20 @@ pub async fn broadcast_loop(
     }
 }
 
-/// Create a UDP socket suitable for sending IPv6 multicast.
-fn make_multicast_socket(interface_index: u32) -> Result<socket2::Socket> {
+/// Create a UDP socket suitable for sending IPv6 multicast, bound to
+/// `local_addr` so datagrams leave from the address they sign.
+fn make_multicast_socket(interface_index: u32, local_addr: Ipv6Addr) -> Result<socket2::Socket> {
     let socket = Socket::new(Domain::IPV6, Type::DGRAM, Some(Protocol::UDP)).context("socket()")?;
     socket.set_reuse_address(true).context("SO_REUSEADDR")?;
+    let bind_addr = SocketAddrV6::new(local_addr, 0, 0, interface_index);
+    socket.bind(&bind_addr.into()).context("bind()")?;
     socket
         .set_multicast_if_v6(interface_index)
         .context("IPV6_MULTICAST_IF")?;
     socket
         .set_multicast_hops_v6(1)
         .context("IPV6_MULTICAST_HOPS")?;
+    socket.set_nonblocking(true).context("set_nonblocking")?;
     Ok(socket)
 }
 
@@ -108,3 +218,127 @@ pub fn if_index(name: &str) -> Result<u32> {
     }
     Ok(index)
 }
+
+/// Pick an interface when none is named: the first non-loopback one, by
+/// index, holding a usable IPv6 link-local address.
+pub fn detect_interface() -> Result<String> {
+    let table = std::fs::read_to_string("/proc/net/if_inet6")
+        .context("cannot list IPv6 interfaces (/proc/net/if_inet6); is IPv6 enabled?")?;
+    pick_interface(&table).context(
+        "no interface with an IPv6 link-local address found; \
+         pass one as `summitd <interface>` or set network.interface",
+    )
+}
+
+/// The interface [`detect_interface`] picks from the contents of
+/// `/proc/net/if_inet6`, whose lines are
+/// `<addr> <index> <prefix len> <scope> <flags> <name>`. Malformed lines
+/// are skipped.
+fn pick_interface(table: &str) -> Option<String> {
+    const SCOPE_LINK: u32 = 0x20;
+    // Duplicate address detection still running, or failed.
+    const IFA_F_UNUSABLE: u32 = 0x40 | 0x08;
+
+    table
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [_, index, _, scope, flags, name] = fields[..] else {
+                return None;
+            };
+            let hex = |s| u32::from_str_radix(s, 16).ok();
+            let usable = hex(scope)? == SCOPE_LINK && hex(flags)? & IFA_F_UNUSABLE == 0;
+            if !usable || name == "lo" {
+                return None;
+            }
+            Some((hex(index)?, name.to_string()))
+        })
+        .min()
+        .map(|(_, name)| name)
+}
+
+/// The interface's IPv6 link-local address — the source of our multicast
+/// and the address peers handshake with.
+///
+/// Summit is IPv6-only. Containers and CI runners often have IPv6 disabled,
+/// so each way this can fail says what is missing and how to enable it.
+pub fn link_local_addr(
+    interface: &str,
+    interface_index: u32,
+    multicast_group: Ipv6Addr,
+) -> Result<Ipv6Addr> {
+    let hint = format!(
+        "Summit needs IPv6 with a link-local (fe80::/10) address on '{interface}'. \
+         Check `ip -6 addr show dev {interface}`; if it has none, enable IPv6 \
+         (`sysctl -w net.ipv6.conf.{interface}.disable_ipv6=0`, or \
+         `--sysctl net.ipv6.conf.all.disable_ipv6=0` for Docker)"
+    );
+
+    let probe = std::net::UdpSocket::bind("[::]:0")
+        .with_context(|| format!("IPv6 is unavailable on this host. {hint}"))?;
+    let dest = SocketAddrV6::new(multicast_group, DISCOVERY_PORT, 0, interface_index);
+    probe
+        .connect(dest)
+        .with_context(|| format!("'{interface}' has no IPv6 route to {multicast_group}. {hint}"))?;
+
+    match probe.local_addr()? {
+        SocketAddr::V6(v6) if v6.ip().is_unicast_link_local() => Ok(*v6.ip()),
+        SocketAddr::V6(v6) => anyhow::bail!(
+            "'{interface}' has no link-local address (would send from {}). {hint}",
+            v6.ip()
+        ),
+        SocketAddr::V4(v4) => {
+            anyhow::bail!("'{interface}' resolved to IPv4 address {}. {hint}", v4.ip())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LO: &str = "00000000000000000000000000000001 01 80 10 80       lo";
+
+    #[test]
+    fn picks_lowest_index_usable_link_local() {
+        let table = [
+            LO,
+            "fe800000000000000000000000000003 04 40 20 80    wlan0",
+            "20010db8000000000000000000000001 02 40 00 80     eth0",
+            "fe800000000000000000000000000002 03 40 20 80     eth1",
+        ]
+        .join("\n");
+        // eth0 has only a global address; eth1 is the lowest index with a link-local one.
+        assert_eq!(pick_interface(&table).as_deref(), Some("eth1"));
+    }
+
+    #[test]
+    fn skips_tentative_and_failed_addresses() {
+        let table = [
+            "fe800000000000000000000000000001 02 40 20 40     eth0",
+            "fe800000000000000000000000000002 03 40 20 08     eth1",
+            "fe800000000000000000000000000003 04 40 20 80     eth2",
+        ]
+        .join("\n");
+        assert_eq!(pick_interface(&table).as_deref(), Some("eth2"));
+    }
+
+    #[test]
+    fn skips_loopback_and_malformed_lines() {
+        let table = [
+            LO,
+            "fe800000000000000000000000000001 01 80 20 80       lo",
+            "",
+            "fe800000000000000000000000000002 02 40 20",
+            "fe800000000000000000000000000003 zz 40 20 80     eth0",
+            "fe800000000000000000000000000004 03 40 2g 80     eth1",
+            "fe800000000000000000000000000005 04 40 20 80     eth2 extra",
+        ]
+        .join("\n");
+        assert_eq!(pick_interface(&table), None);
+        assert_eq!(pick_interface(""), None);
+
+        let table = format!("{table}\nfe800000000000000000000000000006 05 40 20 80     eth3");
+        assert_eq!(pick_interface(&table).as_deref(), Some("eth3"));
+    }
+}
@@ -1,40 +1,116 @@
 //! Capability announcement listener.
 //!
-//! Joins the ff02::1 multicast group and listens for CapabilityAnnouncement
-//! datagrams from nearby peers. Valid announcements are upserted into the
-//! peer registry. A separate expiry task removes stale entries.
+//! Joins the configured multicast group (ff02::1 by default) and listens for CapabilityAnnouncement
+//! datagrams from nearby peers. Each source address is rate limited;
+//! announcements whose signature verifies against their `public_key`, sent
+//! from the address they sign, with a sequence newer than the last accepted,
+//! are upserted into the peer registry, and exact repeats only refresh
+//! `last_seen`. Each peer sends one datagram per service; once a full set
+//! has arrived, services it no longer announces are dropped. A separate
+//! expiry task removes stale entries.
 
+use std::collections::HashMap;
 use std::net::{Ipv6Addr, SocketAddrV6};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use socket2::{Domain, Protocol, Socket, Type};
 use tokio::net::UdpSocket;
 use zerocopy::FromBytes;
 
-use summit_core::wire::{CapabilityAnnouncement, MULTICAST_ADDR_V6, PEER_TTL_SECS};
-use summit_services::{PeerEntry, PeerRegistry};
+use summit_core::wire::Contract;
+use summit_core::wire::{CapabilityAnnouncement, MAX_ANNOUNCEMENT_DATAGRAM};
+use summit_services::{
+    expire_peers, AnnouncementAssembler, AnnouncementStats, DatagramSocket, PeerEntry,
+    PeerRegistry, TokenBucket,
+};
 
-/// UDP port on which capability announcements are received.
-pub const ANNOUNCE_PORT: u16 = 9000;
+use crate::link::Link;
+
+/// Announcement datagrams accepted from one source address per second.
+/// A peer sends one per service every ANNOUNCE_INTERVAL_SECS, far below this.
+const SOURCE_RATE_PER_SEC: f64 = 16.0;
+
+/// Datagrams one source may send back-to-back before being limited.
+const SOURCE_BURST: f64 = 32.0;
+
+/// Source addresses tracked at once. Idle ones are forgotten first; past
+/// this, datagrams from new sources are dropped.
+const MAX_TRACKED_SOURCES: usize = 1024;
+
+/// A source quiet this long has a full bucket again and can be forgotten.
+const SOURCE_IDLE: Duration = Duration::from_secs(5);
+
+/// Per-source-address token buckets for incoming announcements.
+#[derive(Default)]
+struct SourceLimiter {
+    sources: HashMap<Ipv6Addr, (TokenBucket, Instant)>,
+}
+
+impl SourceLimiter {
+    fn allow(&mut self, addr: Ipv6Addr) -> bool {
+        let now = Instant::now();
+        if !self.sources.contains_key(&addr) && self.sources.len() >= MAX_TRACKED_SOURCES {
+            self.sources
+                .retain(|_, (_, last)| now.duration_since(*last) < SOURCE_IDLE);
+            if self.sources.len() >= MAX_TRACKED_SOURCES {
+                return false;
+            }
+        }
+        let (bucket, last) = self.sources.entry(addr).or_insert_with(|| {
+            let mut bucket = TokenBucket::new(Contract::Bulk);
+            bucket.reconfigure(SOURCE_RATE_PER_SEC, SOURCE_BURST);
+            (bucket, now)
+        });
+        *last = now;
+        bucket.allow()
+    }
+}
 
 /// Listen for capability announcements and populate the peer registry.
+/// Sets `bound` once the multicast socket is bound.
 ///
 /// Runs forever — cancel by dropping the task handle.
 pub async fn listener_loop(
     registry: PeerRegistry,
-    interface_index: u32,
+    link: Link,
+    multicast_group: Ipv6Addr,
+    discovery_port: u16,
     local_public_key: [u8; 32],
+    stats: AnnouncementStats,
+    bound: Arc<AtomicBool>,
 ) -> Result<()> {
-    let socket = make_listener_socket(interface_index)
-        .context("failed to create multicast listener socket")?;
+    let socket = match &link {
+        Link::Udp { interface_index } => {
+            let socket = make_listener_socket(*interface_index, multicast_group, discovery_port)
+                .context("failed to create multicast listener socket")?;
 
-    // Convert to tokio UdpSocket for async recv
-    let socket = UdpSocket::from_std(socket).context("failed to convert to tokio UdpSocket")?;
+            // Convert to tokio UdpSocket for async recv
+            let socket =
+                UdpSocket::from_std(socket).context("failed to convert to tokio UdpSocket")?;
+            DatagramSocket::from(socket)
+        }
+        Link::Loopback { net, host } => {
+            let socket = net
+                .bind(*host, discovery_port)
+                .context("failed to bind loopback discovery socket")?;
+            socket.join_multicast(multicast_group);
+            DatagramSocket::Loopback(socket)
+        }
+    };
+    bound.store(true, Ordering::Relaxed);
 
-    let mut buf = vec![0u8; 1024];
+    let mut buf = vec![0u8; MAX_ANNOUNCEMENT_DATAGRAM + 1];
+    let mut limiter = SourceLimiter::default();
+    let mut assembler = AnnouncementAssembler::default();
 
-    tracing::info!(port = ANNOUNCE_PORT, "capability listener starting");
+    tracing::info!(
+        group = %multicast_group,
+        port = discovery_port,
+        "capability listener starting"
+    );
 
     loop {
         let (len, peer_addr) = match socket.recv_from(&mut buf).await {
@@ -54,80 +130,170 @@ pub async fn listener_loop(
             }
         };
 
+        if !limiter.allow(sender_addr) {
+            stats.rate_limited.fetch_add(1, Ordering::Relaxed);
+            tracing::trace!(addr = %peer_addr, "announcement rate limited");
+            continue;
+        }
+
+        if len > MAX_ANNOUNCEMENT_DATAGRAM {
+            stats.invalid.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!(
+                addr = %peer_addr,
+                max = MAX_ANNOUNCEMENT_DATAGRAM,
+                "oversized announcement datagram, possibly truncated, discarding"
+            );
+            continue;
+        }
+
         // Attempt to parse as a CapabilityAnnouncement
-        match CapabilityAnnouncement::read_from_prefix(&buf[..len]) {
-            Some(announcement) => {
-                // Ignore our own announcements
-                if announcement.public_key == local_public_key {
-                    tracing::trace!("ignoring own announcement");
-                    continue;
-                }
+        let Some(announcement) = CapabilityAnnouncement::read_from_prefix(&buf[..len]) else {
+            stats.invalid.fetch_add(1, Ordering::Relaxed);
+            tracing::trace!("failed to parse capability announcement");
+            continue;
+        };
 
-                let svc_hash = announcement.service_hash;
-                let svc_index = announcement.service_index;
-                let svc_count = announcement.service_count;
-                let session_port = announcement.session_port;
-
-                tracing::debug!(
-                    service_hash = hex::encode(svc_hash),
-                    service_index = svc_index,
-                    service_count = svc_count,
-                    addr = %peer_addr,
-                    port = session_port,
-                    "service announcement received"
+        // Ignore our own announcements
+        if announcement.public_key == local_public_key {
+            tracing::trace!("ignoring own announcement");
+            continue;
+        }
+
+        if !announcement.verify() {
+            stats.invalid.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!(
+                addr = %peer_addr,
+                public_key = hex::encode(&announcement.public_key[..8]),
+                "dropping announcement with invalid signature"
+            );
+            continue;
+        }
+
+        // The signature binds the address; a copy replayed from anywhere
+        // else must not move the key there.
+        if Ipv6Addr::from(announcement.addr) != sender_addr {
+            stats.invalid.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!(
+                addr = %peer_addr,
+                signed_addr = %Ipv6Addr::from(announcement.addr),
+                public_key = hex::encode(&announcement.public_key[..8]),
+                "dropping announcement signed for another address"
+            );
+            continue;
+        }
+
+        if let Some(mut entry) = registry.get_mut(&announcement.public_key) {
+            if !entry.accept_sequence(&announcement) {
+                stats.stale.fetch_add(1, Ordering::Relaxed);
+                tracing::trace!(
+                    public_key = hex::encode(&announcement.public_key[..8]),
+                    sequence = { announcement.sequence },
+                    "dropping replayed announcement"
                 );
+                continue;
+            }
+        }
 
-                // Upsert into peer registry — accumulate services.
-                registry
-                    .entry(announcement.public_key)
-                    .and_modify(|entry| {
-                        entry.update_from_announcement(&announcement);
-                    })
-                    .or_insert_with(|| {
-                        PeerEntry::from_first_announcement(sender_addr, &announcement)
-                    });
+        // A complete set replaces what the registry holds, so services the
+        // peer stopped announcing go away rather than lingering in a union.
+        if let Some((version, services)) = assembler.add(&announcement) {
+            if let Some(mut entry) = registry.get_mut(&announcement.public_key) {
+                for hash in entry.replace_services(version, services) {
+                    tracing::debug!(
+                        public_key = hex::encode(&announcement.public_key[..8]),
+                        service_hash = hex::encode(hash),
+                        "peer no longer announces service"
+                    );
+                }
             }
-            None => {
-                tracing::trace!("failed to parse capability announcement");
+        }
+
+        // Peers repeat the same datagrams every interval — those only
+        // refresh last_seen.
+        if let Some(mut entry) = registry.get_mut(&announcement.public_key) {
+            if entry.matches_announcement(&announcement) {
+                entry.last_seen = Instant::now();
+                stats.refreshed.fetch_add(1, Ordering::Relaxed);
+                continue;
             }
         }
+
+        let svc_hash = announcement.service_hash;
+        let svc_index = announcement.service_index;
+        let svc_count = announcement.service_count;
+        let session_port = announcement.session_port;
+        let version = announcement.version;
+
+        tracing::debug!(
+            service_hash = hex::encode(svc_hash),
+            service_index = svc_index,
+            service_count = svc_count,
+            addr = %peer_addr,
+            port = session_port,
+            "service announcement received"
+        );
+
+        // Upsert into peer registry — accumulate services, keeping the
+        // highest version seen.
+        registry
+            .entry(announcement.public_key)
+            .and_modify(|entry| {
+                let held = entry.version;
+                if !entry.update_from_announcement(&announcement) {
+                    tracing::debug!(
+                        public_key = hex::encode(&announcement.public_key[..8]),
+                        announced = version,
+                        held,
+                        "ignoring stale lower-version announcement"
+                    );
+                }
+            })
+            .or_insert_with(|| PeerEntry::from_first_announcement(sender_addr, &announcement));
     }
 }
 
-/// Remove registry entries that have not been refreshed within the TTL.
+/// Every `check_interval`, remove registry entries that have not been
+/// refreshed within `ttl`.
 ///
 /// Runs forever — cancel by dropping the task handle.
-pub async fn expiry_loop(registry: PeerRegistry) -> Result<()> {
-    let ttl = Duration::from_secs(PEER_TTL_SECS);
-    let check_interval = Duration::from_secs(1);
+pub async fn expiry_loop(
+    registry: PeerRegistry,
+    ttl: Duration,
+    check_interval: Duration,
+) -> Result<()> {
     let mut interval = tokio::time::interval(check_interval);
 
     loop {
         interval.tick().await;
 
-        let before = registry.len();
-        registry.retain(|_, entry| entry.last_seen.elapsed() < ttl);
-        let after = registry.len();
-
-        if before != after {
-            tracing::debug!(removed = before - after, "expired peer registry entries");
+        for (public_key, silent) in expire_peers(&registry, ttl) {
+            tracing::debug!(
+                peer = hex::encode(&public_key[..8]),
+                last_seen_secs = silent.as_secs_f64(),
+                ttl_secs = ttl.as_secs(),
+                "peer expired from registry"
+            );
         }
     }
 }
 
-/// Create a UDP socket joined to the ff02::1 multicast group.
-fn make_listener_socket(interface_index: u32) -> Result<std::net::UdpSocket> {
+/// Create a UDP socket bound to `port` and joined to `group`.
+fn make_listener_socket(
+    interface_index: u32,
+    group: Ipv6Addr,
+    port: u16,
+) -> Result<std::net::UdpSocket> {
     let socket = Socket::new(Domain::IPV6, Type::DGRAM, Some(Protocol::UDP)).context("socket()")?;
 
     socket.set_reuse_address(true).context("SO_REUSEADDR")?;
     socket.set_only_v6(true).context("IPV6_V6ONLY")?;
     socket.set_nonblocking(true).context("set_nonblocking")?;
 
-    let bind_addr = SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, ANNOUNCE_PORT, 0, 0);
+    let bind_addr = SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, port, 0, 0);
     socket.bind(&bind_addr.into()).context("bind()")?;
 
     socket
-        .join_multicast_v6(&MULTICAST_ADDR_V6, interface_index)
+        .join_multicast_v6(&group, interface_index)
         .context("IPV6_JOIN_GROUP")?;
 
     Ok(socket.into())
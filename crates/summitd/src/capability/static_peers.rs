@@ -0,0 +1,47 @@
+//! Peers from `network.static_peers`.
+//!
+//! Each is put in the registry as if fully announced, running the services
+//! we do, and kept there: the entry's `last_seen` is refreshed on every
+//! expiry interval so it never ages out. An entry the multicast listener
+//! already holds is only refreshed, so announced details win.
+
+use std::time::{Duration, Instant};
+
+use summit_core::config::StaticPeer;
+use summit_services::{PeerEntry, PeerRegistry};
+
+use super::broadcast::LocalServices;
+
+pub async fn static_peers_loop(
+    registry: PeerRegistry,
+    peers: Vec<StaticPeer>,
+    local_services: LocalServices,
+    refresh_interval: Duration,
+) {
+    for peer in &peers {
+        tracing::info!(
+            peer = hex::encode(&peer.public_key[..8]),
+            addr = %peer.addr,
+            session_port = peer.session_port,
+            "static peer"
+        );
+    }
+
+    let mut interval = tokio::time::interval(refresh_interval);
+    loop {
+        interval.tick().await;
+        for peer in &peers {
+            registry
+                .entry(peer.public_key)
+                .and_modify(|entry| entry.last_seen = Instant::now())
+                .or_insert_with(|| {
+                    PeerEntry::from_static(
+                        peer.addr,
+                        peer.public_key,
+                        peer.session_port,
+                        local_services.borrow().clone(),
+                    )
+                });
+        }
+    }
+}
@@ -10,12 +10,11 @@ use tokio::sync::{broadcast, mpsc};
 use summit_core::recovery::Capacity;
 use summit_core::wire;
 use summit_services::{
-    ChunkCache, FileReassembler, OutgoingChunk, SendTarget, SessionTable, TrustLevel,
-    TrustRegistry, UntrustedBuffer,
+    ChunkCache, FileReassembler, OutgoingChunk, PeerBandwidth, SendTarget, ServiceDispatcher,
+    SessionTable, TrustLevel, TrustRegistry, UntrustedBuffer,
 };
 
-use crate::delivery::DeliveryTracker;
-use crate::dispatch::ServiceDispatcher;
+use summit_services::DeliveryTracker;
 
 pub struct ChunkManager {
     sessions: SessionTable,
@@ -26,9 +25,11 @@ pub struct ChunkManager {
     untrusted_buffer: UntrustedBuffer,
     dispatcher: Arc<ServiceDispatcher>,
     outbound_tx: mpsc::Sender<(SendTarget, OutgoingChunk)>,
+    bandwidth: PeerBandwidth,
     shutdown: broadcast::Receiver<()>,
     bulk_rate: u32,
     bulk_burst: u32,
+    recv_channel_depth: usize,
 }
 
 impl ChunkManager {
@@ -42,9 +43,11 @@ impl ChunkManager {
         untrusted_buffer: UntrustedBuffer,
         dispatcher: Arc<ServiceDispatcher>,
         outbound_tx: mpsc::Sender<(SendTarget, OutgoingChunk)>,
+        bandwidth: PeerBandwidth,
         shutdown: broadcast::Receiver<()>,
         bulk_rate: u32,
         bulk_burst: u32,
+        recv_channel_depth: usize,
     ) -> Self {
         Self {
             sessions,
@@ -55,9 +58,11 @@ impl ChunkManager {
             untrusted_buffer,
             dispatcher,
             outbound_tx,
+            bandwidth,
             shutdown,
             bulk_rate,
             bulk_burst,
+            recv_channel_depth,
         }
     }
 
@@ -74,11 +79,18 @@ impl ChunkManager {
 
                 _ = interval.tick() => {
                     self.spawn_new_sessions(&seen_sessions).await;
+                    self.prune_bandwidth();
                 }
             }
         }
     }
 
+    /// Drop bandwidth accounting for peers with no session left.
+    fn prune_bandwidth(&self) {
+        let live: HashSet<[u8; 32]> = self.sessions.iter().map(|s| s.meta.peer_pubkey).collect();
+        self.bandwidth.retain(|peer| live.contains(peer));
+    }
+
     async fn spawn_new_sessions(&self, seen_sessions: &Arc<tokio::sync::Mutex<HashSet<[u8; 32]>>>) {
         let mut seen = seen_sessions.lock().await;
         for entry in self.sessions.iter() {
@@ -97,15 +109,21 @@ impl ChunkManager {
             let crypto = active.crypto.clone();
             let socket = active.socket.clone();
             let bucket = active.bucket.clone();
+            let last_activity = active.last_activity.clone();
+            let closed = active.closed.clone();
+            let decrypt_drops = active.decrypt_drops.clone();
+            let receive_queue_drops = active.receive_queue_drops.clone();
             let reassembler = self.reassembler.clone();
             let peer_pubkey = active.meta.peer_pubkey;
             let service_hashes: Vec<_> = active.meta.active_services.keys().copied().collect();
             let trust = self.trust.clone();
+            let recovery_trust = self.trust.clone();
             let buffer = self.untrusted_buffer.clone();
             let dispatcher = self.dispatcher.clone();
             let cache = self.cache.clone();
             let tracker = self.delivery_tracker.clone();
             let outbound_tx = self.outbound_tx.clone();
+            let bandwidth = self.bandwidth.clone();
 
             // Notify services that this peer's session is now active.
             dispatcher.activate_session(&peer_pubkey, &service_hashes);
@@ -134,11 +152,12 @@ impl ChunkManager {
             }
 
             // Create channel for received chunks
-            let (chunk_tx, mut chunk_rx) = tokio::sync::mpsc::channel::<super::IncomingChunk>(100);
-
-            let reassembler_for_handler = reassembler.clone();
+            let (chunk_tx, mut chunk_rx) =
+                tokio::sync::mpsc::channel::<super::IncomingChunk>(self.recv_channel_depth);
 
-            // Spawn receiver handler (processes chunks, feeds reassembler)
+            // Spawn receiver handler (trust check, then service dispatch)
+            let handler_dispatcher = dispatcher.clone();
+            let handler_cache = self.cache.clone();
             tokio::spawn(async move {
                 while let Some(chunk) = chunk_rx.recv().await {
                     // Check trust level BEFORE processing
@@ -162,14 +181,31 @@ impl ChunkManager {
                                 chunk.type_tag,
                                 chunk.schema_id,
                                 chunk.payload,
+                                chunk.targeted,
                             );
                             continue;
                         }
+                        TrustLevel::ReceiveOnly => {
+                            if handler_dispatcher.executes(&chunk.schema_id, chunk.type_tag) {
+                                tracing::debug!(
+                                    peer = hex::encode(&peer_pubkey[..8]),
+                                    schema_id = hex::encode(chunk.schema_id),
+                                    "chunk for an executing service from receive-only peer, dropping"
+                                );
+                                continue;
+                            }
+                        }
                         TrustLevel::Trusted => {
                             // Process normally
                         }
                     }
 
+                    // Past the trust check, broadcast content may be passed
+                    // on to other peers.
+                    if !chunk.targeted {
+                        handler_cache.share(&chunk.content_hash);
+                    }
+
                     tracing::info!(
                         content_hash = hex::encode(chunk.content_hash),
                         type_tag = chunk.type_tag,
@@ -177,26 +213,12 @@ impl ChunkManager {
                         "chunk received"
                     );
 
-                    // Handle file metadata chunks (type_tag 3)
-                    if chunk.type_tag == 3 {
-                        if let Ok(metadata) =
-                            serde_json::from_slice::<summit_services::FileMetadata>(&chunk.payload)
-                        {
-                            tracing::info!(filename = %metadata.filename, chunks = metadata.chunk_hashes.len(), "file transfer started");
-                            reassembler_for_handler
-                                .add_metadata(metadata, peer_pubkey)
-                                .await;
-                        }
-                    }
-
-                    // Handle file data chunks (type_tag 2)
-                    if chunk.type_tag == 2 {
-                        if let Ok(Some(path)) = reassembler_for_handler
-                            .add_chunk(chunk.content_hash, chunk.payload)
-                            .await
-                        {
-                            tracing::info!(path = %path.display(), "file completed");
-                        }
+                    if !handler_dispatcher.dispatch(&peer_pubkey, &chunk) {
+                        tracing::debug!(
+                            schema_id = hex::encode(chunk.schema_id),
+                            type_tag = chunk.type_tag,
+                            "no service for chunk, dropping"
+                        );
                     }
                 }
             });
@@ -216,10 +238,15 @@ impl ChunkManager {
                     cache,
                     tracker,
                     peer_addr_str,
-                    dispatcher,
                     peer_pubkey,
                     bucket,
+                    bandwidth,
                     reassembler,
+                    recovery_trust,
+                    last_activity,
+                    closed,
+                    decrypt_drops,
+                    receive_queue_drops,
                 )
                 .await
                 {
@@ -1,17 +1,17 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context, Result};
 use bytes::Bytes;
-use tokio::net::UdpSocket;
-use tokio::sync::{mpsc, Mutex};
-use zerocopy::FromBytes;
+use tokio::sync::{mpsc, Mutex, Notify};
 
-use summit_core::crypto::{hash, Session};
-use summit_core::recovery::{Capacity, Gone, Nack};
-use summit_core::wire::{self, ChunkHeader, MAX_UDP_BUF};
+use summit_core::crypto::Session;
+use summit_core::recovery::{Capacity, Gone, Have, Nack};
+use summit_core::wire::{self, ChunkHeader, FLAG_TARGETED, MAX_UDP_BUF};
 use summit_services::{
-    ChunkCache, FileReassembler, KnownSchema, OutgoingChunk, SendTarget, TokenBucket,
+    ChunkCache, DatagramSocket, DecryptDrops, FileReassembler, KnownSchema, OutgoingChunk,
+    PeerBandwidth, SendTarget, TokenBucket, TrustRegistry,
 };
 
 /// How long to wait for data before considering the session dead.
@@ -19,65 +19,117 @@ const RECEIVE_TIMEOUT: Duration = Duration::from_secs(60);
 
 use super::IncomingChunk;
 
-use crate::delivery::DeliveryTracker;
-use crate::dispatch::ServiceDispatcher;
+use summit_services::DeliveryTracker;
+
+/// Room for one chunk on a session's handler queue.
+enum HandlerSlot<'a> {
+    /// Recovery chunks are handled in the receive loop, not queued.
+    NotQueued,
+    Reserved(mpsc::Permit<'a, IncomingChunk>),
+    /// The queue is full; the chunk has been counted in `drops`.
+    Full,
+}
+
+/// Reserve handler queue room for a chunk with `header`, counting it in
+/// `drops` when there is none. Errors once the handler has gone.
+fn claim_handler_slot<'a>(
+    chunk_tx: &'a mpsc::Sender<IncomingChunk>,
+    header: &ChunkHeader,
+    drops: &AtomicU64,
+) -> Result<HandlerSlot<'a>> {
+    if header.schema_id == wire::recovery_hash() {
+        return Ok(HandlerSlot::NotQueued);
+    }
+    match chunk_tx.try_reserve() {
+        Ok(permit) => Ok(HandlerSlot::Reserved(permit)),
+        Err(mpsc::error::TrySendError::Full(())) => {
+            drops.fetch_add(1, Ordering::Relaxed);
+            Ok(HandlerSlot::Full)
+        }
+        Err(mpsc::error::TrySendError::Closed(())) => {
+            bail!("chunk receiver dropped, terminating receive loop")
+        }
+    }
+}
 
 #[allow(clippy::too_many_arguments)]
 pub async fn receive_loop(
-    socket: Arc<UdpSocket>,
+    socket: Arc<DatagramSocket>,
     session: Arc<Mutex<Session>>,
     chunk_tx: mpsc::Sender<IncomingChunk>,
     outbound_tx: mpsc::Sender<(SendTarget, OutgoingChunk)>,
     cache: ChunkCache,
     tracker: DeliveryTracker,
     peer_addr: String,
-    dispatcher: Arc<ServiceDispatcher>,
     peer_pubkey: [u8; 32],
     bucket: Arc<Mutex<TokenBucket>>,
+    bandwidth: PeerBandwidth,
     reassembler: Arc<FileReassembler>,
+    trust: TrustRegistry,
+    last_activity: Arc<std::sync::Mutex<Instant>>,
+    closed: Arc<Notify>,
+    decrypt_drops: Arc<DecryptDrops>,
+    receive_queue_drops: Arc<AtomicU64>,
 ) -> Result<()> {
     let mut buf = vec![0u8; MAX_UDP_BUF];
 
     loop {
-        let (len, _peer) =
-            match tokio::time::timeout(RECEIVE_TIMEOUT, socket.recv_from(&mut buf)).await {
+        let (len, _peer) = tokio::select! {
+            _ = closed.notified() => {
+                tracing::debug!(peer = %peer_addr, "session closed, stopping receive loop");
+                return Ok(());
+            }
+            result = tokio::time::timeout(RECEIVE_TIMEOUT, socket.recv_from(&mut buf)) => match result {
                 Ok(result) => result.context("recv_from failed")?,
                 Err(_) => bail!(
                     "receive timeout — no data for {}s, session presumed dead",
                     RECEIVE_TIMEOUT.as_secs()
                 ),
-            };
+            },
+        };
+
+        if len > wire::MAX_CHUNK_DATAGRAM {
+            tracing::warn!(
+                peer = %peer_addr,
+                max = wire::MAX_CHUNK_DATAGRAM,
+                "oversized chunk datagram, possibly truncated, discarding"
+            );
+            continue;
+        }
 
         let mut plaintext = Vec::new();
         {
             let mut sess = session.lock().await;
             if let Err(e) = sess.decrypt(&buf[..len], &mut plaintext) {
-                tracing::warn!(error = %e, "chunk decryption failed, discarding");
+                decrypt_drops.record(&e);
+                tracing::warn!(error = %e, peer = %peer_addr, "chunk decryption failed, discarding");
                 continue;
             }
         }
-
-        if plaintext.len() < 72 {
-            tracing::trace!("received chunk too short, discarding");
-            continue;
+        // Only authenticated traffic counts as session activity.
+        if let Ok(mut t) = last_activity.lock() {
+            *t = Instant::now();
         }
+        bandwidth.record_received(peer_pubkey, len);
 
-        let header = match ChunkHeader::read_from_prefix(&plaintext[..72]) {
-            Some(h) => h,
-            None => {
-                tracing::trace!("failed to parse chunk header, discarding");
+        let (header, payload) = match wire::open_chunk(&plaintext) {
+            Ok((header, payload)) => (header, Bytes::copy_from_slice(payload)),
+            Err(e @ wire::WireError::HashMismatch) => {
+                // content_hash leads the header, which open_chunk has length-checked
+                tracing::warn!(
+                    error = %e,
+                    claimed_hash = hex::encode(&plaintext[..32]),
+                    peer = %peer_addr,
+                    "chunk content hash mismatch, discarding"
+                );
+                continue;
+            }
+            Err(e) => {
+                tracing::trace!(error = %e, "malformed chunk, discarding");
                 continue;
             }
         };
 
-        let payload = Bytes::copy_from_slice(&plaintext[72..]);
-
-        let computed_hash = hash(&payload);
-        if computed_hash != header.content_hash {
-            tracing::warn!("chunk hash mismatch, discarding");
-            continue;
-        }
-
         // Validate schema
         if let Some(schema) = KnownSchema::from_id(&header.schema_id) {
             if let Err(e) = schema.validate(&payload) {
@@ -97,13 +149,51 @@ pub async fn receive_loop(
             );
         }
 
+        // Session control sits below the service layer and outside
+        // dedup: every Goodbye has the same empty payload, and the
+        // session's replay window already turns away a resent one.
+        if header.schema_id == wire::control_hash() {
+            match header.type_tag {
+                wire::control::GOODBYE => {
+                    tracing::info!(peer = %peer_addr, "peer said goodbye, closing session");
+                    return Ok(());
+                }
+                tag => tracing::debug!(type_tag = tag, "unknown control message, ignoring"),
+            }
+            continue;
+        }
+
+        // Claim room on the handler queue before the chunk counts as
+        // delivered. Dropping a chunk here looks like loss on the wire, so
+        // NACK recovery can fetch it again if it is a file's data chunk;
+        // dropped after the record below, its retransmit would be
+        // deduplicated away. Waiting instead would
+        // stall recovery traffic and leave the kernel to drop datagrams
+        // uncounted once the socket buffer fills.
+        let permit = match claim_handler_slot(&chunk_tx, &header, &receive_queue_drops)? {
+            HandlerSlot::NotQueued => None,
+            HandlerSlot::Reserved(permit) => Some(permit),
+            HandlerSlot::Full => {
+                tracing::debug!(
+                    content_hash = hex::encode(header.content_hash),
+                    peer = %peer_addr,
+                    "chunk handler queue full, dropping chunk"
+                );
+                continue;
+            }
+        };
+
         // Record delivery BEFORE caching (to track all arrivals)
         tracker.record(header.content_hash, peer_addr.clone());
         let delivery_count = tracker.delivery_count(&header.content_hash);
 
-        // Cache the chunk
+        // Cache the chunk. It is shared, if broadcast, only once the
+        // session's chunk handler has checked the peer's trust; sent to us
+        // alone, only its sender may learn we hold it.
         if let Err(e) = cache.put(&header.content_hash, &payload) {
             tracing::warn!(error = %e, "failed to cache chunk");
+        } else if header.flags & FLAG_TARGETED != 0 {
+            cache.mark_exchanged(&header.content_hash, peer_pubkey);
         }
 
         let incoming = IncomingChunk {
@@ -111,6 +201,7 @@ pub async fn receive_loop(
             type_tag: header.type_tag,
             schema_id: header.schema_id,
             payload,
+            targeted: header.flags & FLAG_TARGETED != 0,
         };
 
         tracing::info!(
@@ -135,20 +226,16 @@ pub async fn receive_loop(
                     &outbound_tx,
                     &bucket,
                     &reassembler,
+                    &trust,
                 )
                 .await;
                 continue;
             }
 
-            // Try service dispatch first
-            let dispatched = dispatcher.dispatch(&peer_pubkey, &header, &incoming.payload);
-
-            if !dispatched {
-                // No service handled it — send on the general channel
-                // for backward compatibility with existing handlers.
-                if chunk_tx.send(incoming).await.is_err() {
-                    bail!("chunk receiver dropped, terminating receive loop");
-                }
+            // Trust check and service dispatch happen in the session's
+            // chunk handler.
+            if let Some(permit) = permit {
+                permit.send(incoming);
             }
         } else {
             tracing::debug!(
@@ -160,6 +247,7 @@ pub async fn receive_loop(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_recovery(
     header: &ChunkHeader,
     payload: &[u8],
@@ -168,6 +256,7 @@ async fn handle_recovery(
     chunk_tx: &mpsc::Sender<(SendTarget, OutgoingChunk)>,
     bucket: &Arc<Mutex<TokenBucket>>,
     reassembler: &Arc<FileReassembler>,
+    trust: &TrustRegistry,
 ) {
     let type_tag = header.type_tag;
     match type_tag {
@@ -182,6 +271,16 @@ async fn handle_recovery(
 
             let is_targeted = nack.attempt == 0;
 
+            // `attempt` is the requester's to set, so it decides nothing
+            // about who may ask: only peers we trust are answered at all.
+            if !trust.is_trusted(peer_pubkey) {
+                tracing::debug!(
+                    peer = hex::encode(&peer_pubkey[..8]),
+                    "ignoring NACK from untrusted peer"
+                );
+                return;
+            }
+
             tracing::info!(
                 peer = hex::encode(&peer_pubkey[..8]),
                 missing = nack.missing.len(),
@@ -203,8 +302,18 @@ async fn handle_recovery(
                 (burst, delay)
             };
 
+            // Retransmissions come from the chunk cache alone, by content
+            // hash. The source file is never re-read — `/send` deletes its
+            // upload once chunked — so recovery works after it is gone;
+            // anything evicted meanwhile is reported GONE. A chunk that
+            // was sent to one peer alone is served to that peer only; to
+            // anyone else it is as good as gone.
             let mut retransmitted = 0u32;
             for content_hash in &nack.missing {
+                if !cache.servable_to(content_hash, peer_pubkey) {
+                    gone_hashes.push(*content_hash);
+                    continue;
+                }
                 match cache.get(content_hash) {
                     Ok(Some(data)) => {
                         let chunk = OutgoingChunk {
@@ -221,7 +330,7 @@ async fn handle_recovery(
                             return;
                         }
                         retransmitted += 1;
-                        if retransmitted % batch_size == 0 {
+                        if retransmitted.is_multiple_of(batch_size) {
                             tokio::time::sleep(std::time::Duration::from_millis(batch_delay_ms))
                                 .await;
                         }
@@ -240,6 +349,12 @@ async fn handle_recovery(
                 }
             }
 
+            tracing::debug!(
+                served = retransmitted,
+                gone = gone_hashes.len(),
+                "NACK served from cache"
+            );
+
             // Only send GONE for targeted NACKs (attempt 0).
             // On broadcast NACKs, peers that don't have the chunk just stay silent.
             if is_targeted && !gone_hashes.is_empty() {
@@ -317,8 +432,171 @@ async fn handle_recovery(
             );
         }
 
+        wire::recovery::HAVE => {
+            let have: Have = match serde_json::from_slice(payload) {
+                Ok(h) => h,
+                Err(e) => {
+                    tracing::warn!(error = %e, "invalid HAVE payload");
+                    return;
+                }
+            };
+            reassembler.deliver_have(*peer_pubkey, have);
+        }
+
         _ => {
             tracing::warn!(type_tag, "unknown recovery type_tag");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(schema_id: [u8; 32]) -> ChunkHeader {
+        ChunkHeader {
+            content_hash: [0u8; 32],
+            schema_id,
+            type_tag: 0,
+            length: 0,
+            flags: 0,
+            version: wire::CHUNK_VERSION,
+        }
+    }
+
+    fn incoming() -> IncomingChunk {
+        IncomingChunk {
+            content_hash: [0u8; 32],
+            type_tag: 0,
+            schema_id: wire::messaging_hash(),
+            payload: Bytes::new(),
+            targeted: false,
+        }
+    }
+
+    struct Recovery {
+        cache: ChunkCache,
+        trust: TrustRegistry,
+        reassembler: Arc<FileReassembler>,
+        tx: mpsc::Sender<(SendTarget, OutgoingChunk)>,
+        rx: mpsc::Receiver<(SendTarget, OutgoingChunk)>,
+        bucket: Arc<Mutex<TokenBucket>>,
+    }
+
+    impl Recovery {
+        fn new() -> Self {
+            let (tx, rx) = mpsc::channel(16);
+            Self {
+                cache: ChunkCache::in_memory(),
+                trust: TrustRegistry::new(),
+                reassembler: Arc::new(FileReassembler::new(std::env::temp_dir())),
+                tx,
+                rx,
+                bucket: Arc::new(Mutex::new(TokenBucket::new(wire::Contract::Bulk))),
+            }
+        }
+
+        /// NACK `missing` as `peer`; returns what was sent back.
+        async fn nack(&mut self, peer: [u8; 32], missing: &[[u8; 32]], attempt: u8) -> Vec<u16> {
+            let mut nack = header(wire::recovery_hash());
+            nack.type_tag = wire::recovery::NACK;
+            let payload = serde_json::to_vec(&Nack {
+                missing: missing.to_vec(),
+                attempt,
+            })
+            .unwrap();
+            handle_recovery(
+                &nack,
+                &payload,
+                &peer,
+                &self.cache,
+                &self.tx,
+                &self.bucket,
+                &self.reassembler,
+                &self.trust,
+            )
+            .await;
+            let mut sent = Vec::new();
+            while let Ok((target, chunk)) = self.rx.try_recv() {
+                assert!(matches!(target, SendTarget::Peer { public_key } if public_key == peer));
+                sent.push(chunk.type_tag);
+            }
+            sent
+        }
+    }
+
+    #[tokio::test]
+    async fn untrusted_peer_gets_nothing_back_from_a_targeted_nack() {
+        let mut recovery = Recovery::new();
+        let peer = [5u8; 32];
+        let hash = summit_core::crypto::hash(b"data");
+        recovery.cache.put(&hash, b"data").unwrap();
+        recovery.cache.share(&hash);
+
+        assert!(recovery.nack(peer, &[hash], 0).await.is_empty());
+        recovery.trust.block(peer);
+        assert!(recovery.nack(peer, &[hash], 0).await.is_empty());
+        assert!(recovery.nack(peer, &[hash], 1).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_chunk_sent_to_one_peer_is_served_to_that_peer_only() {
+        let mut recovery = Recovery::new();
+        let (peer, other) = ([5u8; 32], [6u8; 32]);
+        recovery.trust.trust(peer);
+        recovery.trust.trust(other);
+        let hash = summit_core::crypto::hash(b"data");
+        recovery.cache.put(&hash, b"data").unwrap();
+
+        // Received for us alone, or not yet sent: nobody gets it.
+        assert_eq!(
+            recovery.nack(peer, &[hash], 0).await,
+            vec![wire::recovery::GONE]
+        );
+        assert!(recovery.nack(peer, &[hash], 1).await.is_empty());
+
+        recovery.cache.mark_exchanged(&hash, peer);
+        assert_eq!(recovery.nack(peer, &[hash], 0).await, vec![2]);
+        assert_eq!(recovery.nack(peer, &[hash], 1).await, vec![2]);
+        assert!(recovery.nack(other, &[hash], 1).await.is_empty());
+    }
+
+    #[test]
+    fn full_handler_queue_drops_and_counts() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let drops = AtomicU64::new(0);
+        let data = header(wire::messaging_hash());
+
+        match claim_handler_slot(&tx, &data, &drops).unwrap() {
+            HandlerSlot::Reserved(permit) => permit.send(incoming()),
+            _ => panic!("empty queue had no room"),
+        }
+        assert!(matches!(
+            claim_handler_slot(&tx, &data, &drops).unwrap(),
+            HandlerSlot::Full
+        ));
+        assert!(matches!(
+            claim_handler_slot(&tx, &data, &drops).unwrap(),
+            HandlerSlot::Full
+        ));
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+        // Recovery traffic never waits on the queue, full or not.
+        let recovery = header(wire::recovery_hash());
+        assert!(matches!(
+            claim_handler_slot(&tx, &recovery, &drops).unwrap(),
+            HandlerSlot::NotQueued
+        ));
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+        // Once the handler catches up there is room again.
+        rx.try_recv().unwrap();
+        assert!(matches!(
+            claim_handler_slot(&tx, &data, &drops).unwrap(),
+            HandlerSlot::Reserved(_)
+        ));
+
+        drop(rx);
+        assert!(claim_handler_slot(&tx, &data, &drops).is_err());
+    }
+}
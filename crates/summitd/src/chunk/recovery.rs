@@ -1,20 +1,29 @@
 //! Recovery loop — periodically checks for stalled file assemblies
-//! and sends NACKs to request retransmission of missing chunks.
+//! and sends NACKs to request retransmission of missing chunks. Assemblies
+//! whose NACKs have all gone unanswered are abandoned.
 
 use std::sync::Arc;
 use std::time::Duration;
 
 use summit_core::recovery::{Nack, MAX_NACK_HASHES};
 use summit_core::wire;
-use summit_services::{FileReassembler, OutgoingChunk, SendTarget};
+use summit_services::{FileReassembler, NackBackoff, OutgoingChunk, SendTarget};
 use tokio::sync::{broadcast, mpsc};
 
-/// How long to wait after the last chunk before sending a NACK.
-/// Reduced from 3s — capacity exchange prevents premature NACKs.
-const NACK_DELAY: Duration = Duration::from_secs(2);
+/// NACK backoff per assembly: starts at twice the observed NACK round-trip
+/// (`min` until one is measured) and doubles per unanswered attempt.
+const NACK_BACKOFF: NackBackoff = NackBackoff {
+    min: Duration::from_millis(500),
+    max: Duration::from_secs(8),
+};
 
-/// How often to check for stalled assemblies.
-const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// NACK attempts sent only to the original sender before escalating
+/// to a broadcast — anyone holding the chunks can then answer.
+const TARGETED_NACK_ATTEMPTS: u8 = 1;
+
+/// How often to check for stalled assemblies. Kept well below
+/// `NACK_BACKOFF.min` so the backoff, not the tick, sets the pace.
+const CHECK_INTERVAL: Duration = Duration::from_millis(100);
 
 pub async fn recovery_loop(
     reassembler: Arc<FileReassembler>,
@@ -30,6 +39,7 @@ pub async fn recovery_loop(
                 return;
             }
             _ = interval.tick() => {
+                reassembler.abandon_exhausted(NACK_BACKOFF).await;
                 send_nacks(&reassembler, &chunk_tx).await;
             }
         }
@@ -40,16 +50,17 @@ async fn send_nacks(
     reassembler: &FileReassembler,
     chunk_tx: &mpsc::Sender<(SendTarget, OutgoingChunk)>,
 ) {
-    let stalled = reassembler.stalled_assemblies(NACK_DELAY).await;
+    let stalled = reassembler.stalled_assemblies(NACK_BACKOFF).await;
 
     for assembly in stalled {
         if assembly.missing.is_empty() {
             continue;
         }
 
-        // Attempt 0: target the original sender directly.
-        // Attempt 1+: broadcast to all peers — anyone with the chunks can help.
-        let target = if assembly.attempt == 0 {
+        // Early attempts target the original sender directly; after that,
+        // broadcast to all peers — anyone with the chunks can help.
+        let targeted = assembly.attempt < TARGETED_NACK_ATTEMPTS;
+        let target = if targeted {
             SendTarget::Peer {
                 public_key: assembly.sender_pubkey,
             }
@@ -85,14 +96,19 @@ async fn send_nacks(
         }
 
         reassembler
-            .increment_nack_count(&assembly.filename, assembly.missing.len())
+            .record_nack(
+                assembly.sender_pubkey,
+                &assembly.filename,
+                &assembly.missing,
+            )
             .await;
 
         tracing::info!(
             filename = assembly.filename,
             missing = assembly.missing.len(),
             attempt = assembly.attempt,
-            targeted = assembly.attempt == 0,
+            targeted,
+            srtt_ms = assembly.srtt.map(|d| d.as_millis() as u64),
             "NACK sent for stalled file assembly"
         );
     }
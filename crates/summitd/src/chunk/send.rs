@@ -1,21 +1,93 @@
 //! Chunk sending — encrypt, frame, transmit.
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use tokio::net::UdpSocket;
+use rand::Rng;
 use tokio::sync::Mutex;
 use zerocopy::AsBytes;
 
+use summit_core::config::QosConfig;
 use summit_core::crypto::{hash, Session};
-use summit_core::wire::{ChunkHeader, CHUNK_VERSION};
-use summit_services::ChunkCache;
+use summit_core::wire::{self, ChunkHeader, Contract, CHUNK_VERSION};
+use summit_services::{ChunkCache, DatagramSocket, SessionTable};
 
 use super::OutgoingChunk;
 
+/// Spaces out the chunks sent on each session, so a file's chunks don't
+/// leave in one burst that overruns the receiver and defeats NACK recovery.
+///
+/// Each contract has its own gap (`qos.pacing_us_*`), plus up to
+/// `qos.pacing_jitter_us` of random slack. A zero gap means unpaced, the
+/// chunk going out as soon as the token bucket allows.
+pub struct Pacer {
+    /// Gap by contract: Realtime, Bulk, Background.
+    gaps: [Duration; 3],
+    jitter_us: u64,
+    /// Earliest time the next chunk may go, by session and contract.
+    next: HashMap<([u8; 32], u8), Instant>,
+}
+
+/// Sessions tracked before finished ones are swept out of `Pacer::next`.
+const PACER_SWEEP_AT: usize = 1024;
+
+/// Longest a chunk is held for pacing. A backlog deeper than this many
+/// gaps goes out at the rate the clamp allows instead of queueing up ever
+/// longer holds.
+const MAX_PACING_DELAY: Duration = Duration::from_secs(2);
+
+impl Pacer {
+    pub fn new(qos: &QosConfig) -> Self {
+        Self {
+            gaps: [
+                qos.pacing_us_realtime,
+                qos.pacing_us_bulk,
+                qos.pacing_us_background,
+            ]
+            .map(Duration::from_micros),
+            jitter_us: qos.pacing_jitter_us,
+            next: HashMap::new(),
+        }
+    }
+
+    /// How long to hold the next `contract` chunk on `session_id` so it
+    /// goes out no sooner than one gap after the previous one, and never
+    /// more than `MAX_PACING_DELAY` from now.
+    pub fn delay(&mut self, session_id: [u8; 32], contract: Contract) -> Duration {
+        self.delay_at(session_id, contract, Instant::now())
+    }
+
+    fn delay_at(&mut self, session_id: [u8; 32], contract: Contract, now: Instant) -> Duration {
+        let gap = match contract {
+            Contract::Realtime => self.gaps[0],
+            Contract::Bulk => self.gaps[1],
+            Contract::Background => self.gaps[2],
+        };
+        if gap.is_zero() {
+            return Duration::ZERO;
+        }
+        if self.next.len() >= PACER_SWEEP_AT {
+            self.next.retain(|_, at| *at > now);
+        }
+        let jitter = match self.jitter_us {
+            0 => Duration::ZERO,
+            max => Duration::from_micros(rand::thread_rng().gen_range(0..=max)),
+        };
+        let next = self
+            .next
+            .entry((session_id, u8::from(contract)))
+            .or_insert(now);
+        let at = (*next).max(now).min(now + MAX_PACING_DELAY);
+        *next = at + gap + jitter;
+        at - now
+    }
+}
+
 pub async fn send_chunk(
-    socket: Arc<UdpSocket>,
+    socket: Arc<DatagramSocket>,
     peer_addr: SocketAddr,
     session: Arc<Mutex<Session>>,
     chunk: OutgoingChunk,
@@ -36,12 +108,61 @@ pub async fn send_chunk(
         flags: chunk.priority_flags,
         version: CHUNK_VERSION,
     };
+    seal_and_send(&socket, peer_addr, &session, &header, &chunk.payload).await?;
 
+    tracing::info!(
+        %peer_addr,
+        content_hash = hex::encode(content_hash),
+                   payload_len = chunk.payload.len(),
+                   cached = true,
+                   "chunk sent"
+    );
+
+    Ok(())
+}
+
+/// Tell the peer of every session that we are going offline, so it prunes
+/// the session now instead of when it times out. Best effort: one
+/// unacknowledged Goodbye each, not cached.
+pub async fn say_goodbye(sessions: &SessionTable) {
+    let targets: Vec<_> = sessions
+        .iter()
+        .map(|e| {
+            let session = e.value();
+            let mut addr = session.meta.peer_addr;
+            addr.set_port(session.meta.chunk_port);
+            (session.socket.clone(), addr, session.crypto.clone())
+        })
+        .collect();
+
+    let header = ChunkHeader {
+        content_hash: hash(&[]),
+        schema_id: wire::control_hash(),
+        type_tag: wire::control::GOODBYE,
+        length: 0,
+        flags: 0x01, // Realtime
+        version: CHUNK_VERSION,
+    };
+    for (socket, peer_addr, session) in targets {
+        if let Err(e) = seal_and_send(&socket, peer_addr, &session, &header, &[]).await {
+            tracing::debug!(%peer_addr, error = %e, "goodbye not sent");
+        }
+    }
+}
+
+/// Encrypt `header` + `payload` under the session and send it.
+async fn seal_and_send(
+    socket: &DatagramSocket,
+    peer_addr: SocketAddr,
+    session: &Mutex<Session>,
+    header: &ChunkHeader,
+    payload: &[u8],
+) -> Result<()> {
     // Plaintext: [72-byte header] + [payload]
     // After encryption: [8-byte nonce] + [plaintext + 16-byte MAC]
-    let mut plaintext = Vec::with_capacity(72 + chunk.payload.len());
+    let mut plaintext = Vec::with_capacity(72 + payload.len());
     plaintext.extend_from_slice(header.as_bytes());
-    plaintext.extend_from_slice(&chunk.payload);
+    plaintext.extend_from_slice(payload);
 
     let mut ciphertext = Vec::new();
     {
@@ -54,14 +175,63 @@ pub async fn send_chunk(
         .send_to(&ciphertext, peer_addr)
         .await
         .context("failed to send chunk")?;
+    Ok(())
+}
 
-    tracing::info!(
-        %peer_addr,
-        content_hash = hex::encode(content_hash),
-                   payload_len = chunk.payload.len(),
-                   cached = true,
-                   "chunk sent"
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(())
+    fn pacer(bulk_us: u64, jitter_us: u64) -> Pacer {
+        Pacer::new(&QosConfig {
+            pacing_us_bulk: bulk_us,
+            pacing_jitter_us: jitter_us,
+            ..QosConfig::default()
+        })
+    }
+
+    #[test]
+    fn chunks_on_one_session_are_spaced_by_the_gap() {
+        let mut pacer = pacer(200, 0);
+        let now = Instant::now();
+        let gap = Duration::from_micros(200);
+        let (a, b) = ([1u8; 32], [2u8; 32]);
+
+        assert_eq!(pacer.delay_at(a, Contract::Bulk, now), Duration::ZERO);
+        assert_eq!(pacer.delay_at(a, Contract::Bulk, now), gap);
+        assert_eq!(pacer.delay_at(a, Contract::Bulk, now), gap * 2);
+        // Other sessions and unpaced contracts are not held behind them.
+        assert_eq!(pacer.delay_at(b, Contract::Bulk, now), Duration::ZERO);
+        assert_eq!(pacer.delay_at(a, Contract::Realtime, now), Duration::ZERO);
+        // Once the gap has passed, the next chunk goes straight away.
+        let later = now + gap * 3;
+        assert_eq!(pacer.delay_at(a, Contract::Bulk, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn jitter_stays_within_its_bound() {
+        let mut pacer = pacer(100, 50);
+        let now = Instant::now();
+        let mut previous = pacer.delay_at([1u8; 32], Contract::Bulk, now);
+        for _ in 0..100 {
+            let delay = pacer.delay_at([1u8; 32], Contract::Bulk, now);
+            let spacing = delay - previous;
+            assert!(spacing >= Duration::from_micros(100));
+            assert!(spacing <= Duration::from_micros(150));
+            previous = delay;
+        }
+    }
+
+    #[test]
+    fn accumulated_delay_is_clamped() {
+        let mut pacer = pacer(100_000, 0);
+        let now = Instant::now();
+        let delays: Vec<Duration> = (0..50)
+            .map(|_| pacer.delay_at([1u8; 32], Contract::Bulk, now))
+            .collect();
+        // 50 chunks 100 ms apart would reach 4.9 s; the clamp holds at 2 s.
+        assert_eq!(delays[20], MAX_PACING_DELAY);
+        assert!(delays.iter().all(|d| *d <= MAX_PACING_DELAY));
+        assert_eq!(*delays.last().unwrap(), MAX_PACING_DELAY);
+    }
 }
@@ -1,30 +1,63 @@
 //! Send worker — dequeues outbound chunks, resolves targets,
 //! applies QoS, and sends to appropriate sessions.
+//!
+//! Each session has one FIFO per contract, drained by its own task, so a
+//! file's chunks leave in the order they were queued even when pacing or
+//! the peer cap holds some of them. Out-of-order arrival would otherwise
+//! look like loss to the receiver and cost a NACK round.
 
-use tokio::sync::{broadcast, mpsc};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 
-use summit_core::crypto::hash;
-use summit_core::wire::Contract;
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::time::Instant;
+
+use summit_core::crypto::{hash, Session};
+use summit_core::wire::{Contract, FLAG_TARGETED};
 use summit_services::{
-    ChunkCache, SendTarget, SessionTable, TokenBucket, TrustLevel, TrustRegistry,
+    one_session_per_peer, preferred_session, ChunkCache, DatagramSocket, PeerBandwidth, SendTarget,
+    SessionMeta, SessionTable, TokenBucket, TransferTracker, TrustRegistry,
 };
 
+use super::send::Pacer;
 use super::OutgoingChunk;
 
+/// Chunks one session queue holds before the worker waits for it to drain.
+const SESSION_QUEUE_DEPTH: usize = 1024;
+
+/// A chunk waiting in a session queue, to go out no sooner than `at`.
+struct QueuedSend {
+    at: Instant,
+    peer_addr: SocketAddr,
+    peer_pubkey: [u8; 32],
+    chunk: OutgoingChunk,
+}
+
 pub struct SendWorker {
     sessions: SessionTable,
     cache: ChunkCache,
     trust: TrustRegistry,
     chunk_rx: mpsc::Receiver<(SendTarget, OutgoingChunk)>,
+    transfers: TransferTracker,
+    bandwidth: PeerBandwidth,
+    pacer: Pacer,
+    /// Send queues by session and contract.
+    queues: HashMap<([u8; 32], u8), mpsc::Sender<QueuedSend>>,
     shutdown: broadcast::Receiver<()>,
 }
 
 impl SendWorker {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         sessions: SessionTable,
         cache: ChunkCache,
         trust: TrustRegistry,
         chunk_rx: mpsc::Receiver<(SendTarget, OutgoingChunk)>,
+        transfers: TransferTracker,
+        bandwidth: PeerBandwidth,
+        pacer: Pacer,
         shutdown: broadcast::Receiver<()>,
     ) -> Self {
         Self {
@@ -32,6 +65,10 @@ impl SendWorker {
             cache,
             trust,
             chunk_rx,
+            transfers,
+            bandwidth,
+            pacer,
+            queues: HashMap::new(),
             shutdown,
         }
     }
@@ -52,27 +89,47 @@ impl SendWorker {
                             return Ok(());
                         }
                     };
+                    self.transfers.chunk_dequeued();
                     self.send_to_targets(target, chunk).await;
                 }
             }
         }
     }
 
-    async fn send_to_targets(&self, target: SendTarget, chunk: OutgoingChunk) {
+    async fn send_to_targets(&mut self, target: SendTarget, chunk: OutgoingChunk) {
         // Determine which sessions to send to based on target
+        // A peer may hold several sessions (default plus dedicated ones);
+        // each peer receives the chunk once, on the session for its service.
         let target_sessions: Vec<[u8; 32]> = match &target {
-            SendTarget::Broadcast => self
-                .sessions
-                .iter()
-                .filter(|e| self.trust.check(&e.value().meta.peer_pubkey) == TrustLevel::Trusted)
-                .map(|e| *e.key())
-                .collect(),
-            SendTarget::Peer { public_key } => self
-                .sessions
-                .iter()
-                .find(|e| e.value().meta.peer_pubkey == *public_key)
-                .map(|e| vec![*e.key()])
-                .unwrap_or_default(),
+            SendTarget::Broadcast => {
+                let metas: Vec<SessionMeta> = self
+                    .sessions
+                    .iter()
+                    .filter(|e| {
+                        self.trust
+                            .check(&e.value().meta.peer_pubkey)
+                            .gets_broadcasts()
+                    })
+                    .map(|e| e.value().meta.clone())
+                    .collect();
+                one_session_per_peer(&metas, &chunk.schema_id)
+                    .into_iter()
+                    .map(|(session_id, _)| session_id)
+                    .collect()
+            }
+            // Targeted sends skip the trust filter so first contact works;
+            // the receiver buffers until it trusts us.
+            SendTarget::Peer { public_key } => {
+                let metas: Vec<SessionMeta> = self
+                    .sessions
+                    .iter()
+                    .filter(|e| e.value().meta.peer_pubkey == *public_key)
+                    .map(|e| e.value().meta.clone())
+                    .collect();
+                preferred_session(&metas, &chunk.schema_id)
+                    .into_iter()
+                    .collect()
+            }
             SendTarget::Session { session_id } => {
                 if self.sessions.contains_key(session_id) {
                     vec![*session_id]
@@ -94,40 +151,79 @@ impl SendWorker {
         if let Err(e) = self.cache.put(&content_hash, &chunk.payload) {
             tracing::warn!(error = %e, "failed to pre-cache chunk");
         }
+        let targeted = !matches!(target, SendTarget::Broadcast);
+        if !targeted {
+            self.cache.share(&content_hash);
+        }
 
         let has_realtime = self
             .sessions
             .iter()
-            .any(|e| matches!(e.value().meta.primary_contract(), Contract::Realtime));
+            .any(|e| e.value().meta.has_contract(Contract::Realtime));
 
-        let mut send_tasks = Vec::new();
+        // Queues of sessions that have gone away; their tasks end once the
+        // chunks already queued are sent.
+        let sessions = &self.sessions;
+        self.queues.retain(|(id, _), _| sessions.contains_key(id));
 
         for session_id in target_sessions {
             let session = match self.sessions.get(&session_id) {
                 Some(s) => s,
                 None => continue,
             };
+            // Even if dropped below, it was meant for this peer, who may
+            // NACK it.
+            if targeted {
+                self.cache
+                    .mark_exchanged(&content_hash, session.meta.peer_pubkey);
+            }
             let peer_addr = session.meta.peer_addr;
             let chunk_port = session.meta.chunk_port;
             let socket = session.value().socket.clone();
             let crypto = session.value().crypto.clone();
-            let contract = session.meta.primary_contract();
+            // Per-chunk contract: the negotiated contract of the chunk's
+            // service, or Realtime for explicitly flagged recovery traffic.
+            let contract = session
+                .meta
+                .contract_for_chunk(&chunk.schema_id, chunk.priority_flags);
 
             if TokenBucket::should_suppress(contract, has_realtime) {
                 tracing::debug!(%peer_addr, "background chunk suppressed — realtime active");
                 continue;
             }
 
-            // Realtime-priority chunks bypass the token bucket entirely.
+            // Realtime chunks have no bucket and bypass rate limiting entirely.
             // This includes NACK retransmissions and recovery protocol messages.
-            if chunk.priority_flags != 0x01 {
-                let allowed = session.bucket.lock().await.allow();
+            if let Some(bucket) = session.bucket_for(contract) {
+                let allowed = bucket.lock().await.allow();
                 if !allowed {
                     tracing::debug!(%peer_addr, ?contract, "chunk dropped — rate limited");
                     continue;
                 }
             }
 
+            // Per-peer cap, across all of the peer's sessions. Realtime is
+            // never capped; bulk waits its turn, background is dropped.
+            let peer_pubkey = session.meta.peer_pubkey;
+            let bytes = chunk.payload.len();
+            let mut hold = Duration::ZERO;
+            match contract {
+                Contract::Realtime => {}
+                Contract::Bulk => hold = self.bandwidth.reserve(peer_pubkey, bytes),
+                Contract::Background => {
+                    if !self.bandwidth.try_reserve(peer_pubkey, bytes) {
+                        tracing::debug!(%peer_addr, "background chunk dropped — peer cap");
+                        continue;
+                    }
+                }
+            }
+            drop(session);
+
+            // Pacing holds the chunk too; whichever hold is longer wins.
+            let pace = self.pacer.delay(session_id, contract);
+            let capped = !hold.is_zero();
+            hold = hold.max(pace);
+
             // Construct chunk peer address
             let chunk_peer_addr = match peer_addr {
                 std::net::SocketAddr::V6(mut addr) => {
@@ -137,19 +233,71 @@ impl SendWorker {
                 _ => peer_addr,
             };
 
-            let chunk_clone = chunk.clone();
-            let cache_clone = self.cache.clone();
+            // Header priority bits mirror the contract actually applied.
+            let mut chunk_clone = chunk.clone();
+            chunk_clone.priority_flags = u8::from(contract);
+            if targeted {
+                chunk_clone.priority_flags |= FLAG_TARGETED;
+            }
+
+            // The hold is served by the session's own queue, so a capped
+            // peer never delays chunks bound for anyone else.
+            if !hold.is_zero() {
+                tracing::debug!(%peer_addr, ?hold, capped, "chunk held — paced or over peer cap");
+            }
 
-            let task = tokio::spawn(async move {
-                super::send::send_chunk(socket, chunk_peer_addr, crypto, chunk_clone, cache_clone)
-                    .await
-            });
-            send_tasks.push(task);
+            let queue = self
+                .queues
+                .entry((session_id, u8::from(contract)))
+                .or_insert_with(|| {
+                    let (tx, rx) = mpsc::channel(SESSION_QUEUE_DEPTH);
+                    tokio::spawn(drain_queue(
+                        rx,
+                        socket,
+                        crypto,
+                        self.cache.clone(),
+                        self.bandwidth.clone(),
+                    ));
+                    tx
+                });
+            let send = QueuedSend {
+                at: Instant::now() + hold,
+                peer_addr: chunk_peer_addr,
+                peer_pubkey,
+                chunk: chunk_clone,
+            };
+            // A full queue holds the worker here, pushing back on the
+            // outbound queue as waiting on the send itself used to.
+            if queue.send(send).await.is_err() {
+                tracing::debug!(%peer_addr, "session send queue closed, chunk dropped");
+            }
         }
+    }
+}
 
-        // Wait for all sends to complete
-        for task in send_tasks {
-            let _ = task.await;
+/// Send one session queue's chunks in order, each once its hold is up.
+/// Ends when the worker drops the queue and the last chunk has gone out.
+async fn drain_queue(
+    mut rx: mpsc::Receiver<QueuedSend>,
+    socket: Arc<DatagramSocket>,
+    crypto: Arc<Mutex<Session>>,
+    cache: ChunkCache,
+    bandwidth: PeerBandwidth,
+) {
+    while let Some(send) = rx.recv().await {
+        tokio::time::sleep_until(send.at).await;
+        let bytes = send.chunk.payload.len();
+        if let Err(e) = super::send::send_chunk(
+            socket.clone(),
+            send.peer_addr,
+            crypto.clone(),
+            send.chunk,
+            cache.clone(),
+        )
+        .await
+        {
+            tracing::debug!(peer_addr = %send.peer_addr, error = %e, "queued chunk send failed");
         }
+        bandwidth.record_sent(send.peer_pubkey, bytes);
     }
 }
@@ -0,0 +1,763 @@
+//! summitd — Summit peer-to-peer daemon.
+//!
+//! The daemon is a library so that several can run in one process: with
+//! `network.transport = "loopback"` and one [`LoopbackNet`] passed to
+//! [`start_on`] they find each other and exchange chunks over in-memory
+//! channels, which is how the end-to-end tests in `tests/` run without
+//! network namespaces or root.
+
+use std::net::SocketAddrV6;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use tokio::task::JoinHandle;
+
+use summit_api::ApiState;
+use summit_core::config::{CacheBackend, Discovery, SummitConfig, Transport};
+use summit_core::crypto::{Keypair, StorageCipher};
+
+use summit_services::{
+    new_registry, new_session_table, AnnouncementStats, ChunkCache, ComputeStore, FileReassembler,
+    LoopbackNet, MessageStore, PresenceHub, SendTarget, SessionCooldowns, TrustLevel,
+    TrustRegistry, UntrustedBuffer,
+};
+
+mod capability;
+mod chunk;
+mod link;
+mod reload;
+mod session;
+
+use capability::{broadcast, listener, static_peers};
+use link::Link;
+use tokio::sync::mpsc;
+
+/// A running daemon.
+pub struct Daemon {
+    /// What the API serves from: sessions, queues, stores, the shutdown
+    /// channel.
+    pub state: ApiState,
+    exited: JoinHandle<()>,
+}
+
+impl Daemon {
+    /// Ask every task to stop.
+    pub fn shutdown(&self) {
+        self.state.shutdown();
+    }
+
+    /// Wait until the daemon shuts down, or one of its tasks fails.
+    pub async fn wait(self) {
+        let _ = self.exited.await;
+    }
+}
+
+/// Start a daemon with `config`. `interface` overrides
+/// `network.interface`; a loopback daemon has none, and is alone on a
+/// network of its own.
+pub async fn start(config: SummitConfig, interface: Option<String>) -> Result<Daemon> {
+    launch(config, interface, LoopbackNet::new()).await
+}
+
+/// Start a loopback daemon as the next host on `net`: fe80::1 for the
+/// first daemon or host added, fe80::2 for the second, and so on.
+pub async fn start_on(config: SummitConfig, net: &LoopbackNet) -> Result<Daemon> {
+    launch(config, None, net.clone()).await
+}
+
+async fn launch(
+    config: SummitConfig,
+    interface: Option<String>,
+    net: LoopbackNet,
+) -> Result<Daemon> {
+    let started_at = Instant::now();
+    let (interface, link, local_link_addr) = match config.network.transport {
+        Transport::Loopback => {
+            let host = net.add_host();
+            tracing::info!(addr = %host, "summitd starting on the in-process loopback network");
+            ("loopback".to_string(), Link::Loopback { net, host }, host)
+        }
+        Transport::Udp => {
+            // Interface: the argument, else network.interface, else the
+            // first non-loopback one with a link-local address.
+            let (interface, source) = match interface {
+                Some(name) => (name, "argument"),
+                None if !config.network.interface.is_empty() => {
+                    (config.network.interface.clone(), "config")
+                }
+                None => (broadcast::detect_interface()?, "auto-detected"),
+            };
+            tracing::info!(interface, source, "summitd starting");
+
+            let interface_index = broadcast::if_index(&interface)?;
+
+            let local_link_addr = broadcast::link_local_addr(
+                &interface,
+                interface_index,
+                config.network.multicast_group,
+            )
+            .inspect_err(|e| {
+                tracing::error!(
+                    interface,
+                    error = format!("{e:#}"),
+                    "no usable IPv6 link-local address"
+                )
+            })?;
+            tracing::info!(addr = %local_link_addr, "local link-local address");
+            (interface, Link::Udp { interface_index }, local_link_addr)
+        }
+    };
+
+    // Bind session socket — on network.session_port, which static peers
+    // of ours need to be fixed.
+    let session_listen_socket = Arc::new(
+        link.bind(SocketAddrV6::new(
+            local_link_addr,
+            config.network.session_port,
+            0,
+            link.scope_id(),
+        ))
+        .await
+        .context("failed to bind session listen socket")?,
+    );
+    let session_listen_port = session_listen_socket.local_addr()?.port();
+
+    // Keypair — load from disk or generate and persist
+    let keypair_path = &config.identity.keypair_path;
+    let keypair = if keypair_path.exists() {
+        let bytes = std::fs::read(keypair_path)
+            .with_context(|| format!("failed to read keypair from {}", keypair_path.display()))?;
+        if bytes.len() != 32 {
+            anyhow::bail!(
+                "keypair file {} has wrong size ({} bytes, expected 32)",
+                keypair_path.display(),
+                bytes.len()
+            );
+        }
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&bytes);
+        let kp = Keypair::from_private(key);
+        tracing::info!(
+            public_key = hex::encode(kp.public),
+            path = %keypair_path.display(),
+            "loaded keypair from disk"
+        );
+        Arc::new(kp)
+    } else {
+        let kp = Keypair::generate();
+        if let Some(parent) = keypair_path.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+        std::fs::write(keypair_path, *kp.private_bytes())
+            .with_context(|| format!("failed to save keypair to {}", keypair_path.display()))?;
+        // Set restrictive permissions on the keypair file
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(keypair_path, std::fs::Permissions::from_mode(0o600)).ok();
+        }
+        tracing::info!(
+            public_key = hex::encode(kp.public),
+            path = %keypair_path.display(),
+            "generated and saved new keypair"
+        );
+        Arc::new(kp)
+    };
+
+    // Shared state
+    let registry = new_registry();
+    let announcement_stats = AnnouncementStats::default();
+    let sessions = new_session_table();
+    let handshake_tracker = session::HandshakeTracker::shared();
+    let session_cooldowns = SessionCooldowns::new();
+    let message_store = MessageStore::new();
+    let presence = PresenceHub::new();
+    let data_dir = &config.storage.data_dir;
+    let compute_store = match config.services.compute_settings.task_log_path(data_dir) {
+        Some(path) => ComputeStore::with_persistence(path),
+        None => ComputeStore::new(),
+    };
+
+    // Chunk cache
+    let mut cache_fallback = None;
+    let cache = match config.cache.backend {
+        CacheBackend::Disk => {
+            let cache_root = std::env::var("SUMMIT_CACHE")
+                .unwrap_or_else(|_| data_dir.join("cache").to_string_lossy().into_owned());
+            // An unusable root (say /var/cache/summit when not root) falls
+            // back to a temp directory rather than stopping the daemon. It
+            // gets an unpredictable name and mode 0700, and is removed on
+            // shutdown.
+            let cache = match ChunkCache::with_max_bytes(&cache_root, config.cache.max_bytes) {
+                Ok(cache) => cache,
+                Err(e) => {
+                    use std::os::unix::fs::PermissionsExt;
+                    let fallback = tempfile::Builder::new()
+                        .prefix("summit-cache-")
+                        .permissions(std::fs::Permissions::from_mode(0o700))
+                        .tempdir()
+                        .context("failed to create a temp cache directory")?;
+                    tracing::warn!(
+                        root = %cache_root,
+                        fallback = %fallback.path().display(),
+                        error = format!("{e:#}"),
+                        "cache root unusable, falling back to a temp directory"
+                    );
+                    let cache =
+                        ChunkCache::with_max_bytes(fallback.path(), config.cache.max_bytes)?;
+                    cache_fallback = Some(fallback);
+                    cache
+                }
+            };
+            tracing::info!(
+                root = %cache.root().unwrap_or(std::path::Path::new("")).display(),
+                max_bytes = config.cache.max_bytes,
+                chunks = cache.count(),
+                "chunk cache initialized"
+            );
+            cache
+        }
+        CacheBackend::Memory => {
+            tracing::info!(
+                max_bytes = config.cache.max_bytes,
+                "in-memory chunk cache initialized"
+            );
+            ChunkCache::in_memory_with_max_bytes(config.cache.max_bytes)
+        }
+    };
+
+    // Trust
+    let trust_path = data_dir.join("trust.json");
+    let trust_registry = TrustRegistry::with_persistence(trust_path);
+    trust_registry.apply_config(config.trust.auto_trust, &config.trust.trusted_peers);
+    if config.trust.auto_trust {
+        tracing::warn!("auto-trust enabled — all discovered peers will be trusted");
+    }
+    let untrusted_buffer = UntrustedBuffer::new();
+
+    // Outbound chunk queue — bounded so slow UDP sends push back on producers
+    let send_queue_capacity = match config.network.send_queue_capacity {
+        0 => 256,
+        n => n,
+    };
+    tracing::info!(capacity = send_queue_capacity, "outbound chunk queue");
+    let (chunk_tx, chunk_rx) =
+        mpsc::channel::<(SendTarget, chunk::OutgoingChunk)>(send_queue_capacity);
+
+    // Per-session queue between each receive loop and its chunk handler
+    let recv_channel_depth = match config.network.recv_channel_depth {
+        0 => 100,
+        n => n,
+    };
+
+    // File reassembler
+    let file_transfer_path = config.services.file_transfer_settings.storage_path.clone();
+    tracing::info!(path = %file_transfer_path.display(), "file transfer storage path");
+    let mut reassembler = FileReassembler::new(file_transfer_path.clone())
+        .with_compute(compute_store.clone())
+        .with_dedup(cache.clone(), chunk_tx.clone(), trust_registry.clone())
+        .with_max_assemblies(
+            config
+                .services
+                .file_transfer_settings
+                .max_concurrent_assemblies,
+        )
+        .with_on_conflict(config.services.file_transfer_settings.on_conflict);
+    if config.storage.encrypt_at_rest {
+        let cipher = match config.storage.key()? {
+            Some(key) => {
+                tracing::info!("received files encrypted at rest with storage.encryption_key");
+                StorageCipher::new(key)
+            }
+            None => {
+                tracing::info!("received files encrypted at rest with identity-derived key");
+                StorageCipher::from_identity(&keypair)
+            }
+        };
+        reassembler = reassembler.with_encryption(cipher);
+        if config.cache.backend == CacheBackend::Disk {
+            tracing::warn!(
+                "chunk cache is on disk and unencrypted; set cache.backend = \"memory\" to keep received data off disk in plaintext"
+            );
+        }
+    }
+    let reassembler = Arc::new(reassembler);
+
+    // Sync service — only started with the daemon, like compute
+    let sync_service = config.services.sync.then(|| {
+        Arc::new(summit_services::SyncService::new(
+            cache.clone(),
+            config.services.sync_settings.clone(),
+            chunk_tx.clone(),
+            trust_registry.clone(),
+        ))
+    });
+
+    // Service dispatcher
+    let dispatcher = {
+        use summit_services::{ComputeService, MessagingService, ServiceDispatcher};
+        let mut d = ServiceDispatcher::new();
+        d.register(reassembler.clone()).register(Arc::new(
+            MessagingService::new(message_store.clone()).with_presence(presence.clone()),
+        ));
+        if config.services.compute {
+            d.register(Arc::new(ComputeService::new(
+                compute_store.clone(),
+                config.services.compute_settings.clone(),
+                chunk_tx.clone(),
+            )));
+        }
+        if let Some(sync) = &sync_service {
+            d.register(sync.clone());
+        }
+        Arc::new(d)
+    };
+
+    // Enabled services — switched by config reload and the API. Their
+    // announcement entries and dispatcher routing follow.
+    let (service_names_tx, service_names) =
+        tokio::sync::watch::channel(broadcast::enabled_service_names(&config.services));
+    let (services_tx, local_services) = tokio::sync::watch::channel(broadcast::service_entries(
+        &service_names.borrow(),
+        config.network.chunk_port,
+    ));
+    let follow_services = tokio::spawn(broadcast::follow_service_names(
+        service_names,
+        config.network.chunk_port,
+        services_tx,
+        dispatcher.clone(),
+    ));
+    tracing::info!(
+        file_transfer = config.services.file_transfer,
+        messaging = config.services.messaging,
+        stream_udp = config.services.stream_udp,
+        compute = config.services.compute,
+        sync = config.services.sync,
+        "services enabled"
+    );
+
+    // ── Shutdown channel ─────────────────────────────────────────────────────
+    let (shutdown_tx, _) = tokio::sync::broadcast::channel::<()>(1);
+
+    // ── Spawn tasks ──────────────────────────────────────────────────────────
+
+    let multicast_group = config.network.multicast_group;
+    let discovery_port = config.network.discovery_port;
+    let multicast = config.network.discovery == Discovery::Multicast;
+    let static_peers = config.network.static_peers()?;
+
+    // Readiness: the session socket is already bound above, so this flips
+    // once the multicast listener has bound too — at once without one.
+    let ready = Arc::new(AtomicBool::new(!multicast));
+
+    let (broadcast_task, listener_task) = if multicast {
+        let broadcast_task = {
+            let keypair = keypair.clone();
+            let broadcast_services = local_services.clone();
+            let link = link.clone();
+            tokio::spawn(async move {
+                if let Err(e) = broadcast::broadcast_loop(
+                    keypair,
+                    link,
+                    local_link_addr,
+                    multicast_group,
+                    discovery_port,
+                    session_listen_port,
+                    broadcast_services,
+                )
+                .await
+                {
+                    tracing::error!(error = %e, "capability broadcast failed");
+                }
+            })
+        };
+        let listener_task = tokio::spawn(listener::listener_loop(
+            registry.clone(),
+            link.clone(),
+            multicast_group,
+            discovery_port,
+            keypair.public,
+            announcement_stats.clone(),
+            ready.clone(),
+        ));
+        (broadcast_task, listener_task)
+    } else {
+        tracing::info!(
+            discovery = ?config.network.discovery,
+            "multicast discovery disabled, using static peers only"
+        );
+        // Stand-ins that never finish, so the exit watch below only
+        // waits on the tasks that run.
+        (
+            tokio::spawn(std::future::pending()),
+            tokio::spawn(std::future::pending()),
+        )
+    };
+
+    let static_peers_task = tokio::spawn(static_peers::static_peers_loop(
+        registry.clone(),
+        static_peers,
+        local_services.clone(),
+        Duration::from_secs(config.network.peer_expiry_interval_secs),
+    ));
+
+    let expiry_task = tokio::spawn(listener::expiry_loop(
+        registry.clone(),
+        Duration::from_secs(config.network.peer_ttl_secs),
+        Duration::from_secs(config.network.peer_expiry_interval_secs),
+    ));
+
+    let session_listener_task = tokio::spawn(
+        session::listener::SessionListener::new(
+            session_listen_socket.clone(),
+            link.clone(),
+            keypair.clone(),
+            sessions.clone(),
+            handshake_tracker.clone(),
+            local_link_addr,
+            registry.clone(),
+            local_services.clone(),
+            config.network.max_sessions,
+            session_cooldowns.clone(),
+            shutdown_tx.subscribe(),
+        )
+        .run(),
+    );
+
+    // Handshakes requested through the API, answered by the initiator
+    let (connect_tx, connect_rx) = tokio::sync::mpsc::channel(16);
+
+    let session_initiator_task = tokio::spawn(
+        session::initiator::SessionInitiator::new(
+            session_listen_socket,
+            keypair.clone(),
+            registry.clone(),
+            handshake_tracker.clone(),
+            sessions.clone(),
+            link,
+            local_services.clone(),
+            connect_rx,
+            config.network.max_sessions,
+            session_cooldowns.clone(),
+            shutdown_tx.subscribe(),
+        )
+        .run(),
+    );
+
+    // Session health — drop sessions that have gone quiet. Faster and more
+    // predictable than waiting for the receive loop's own timeout.
+    let session_health = {
+        let sessions = sessions.clone();
+        let idle_limit = Duration::from_secs(config.network.session_idle_secs);
+        tokio::spawn(async move {
+            if idle_limit.is_zero() {
+                return;
+            }
+            let mut interval = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                interval.tick().await;
+                let idle: Vec<[u8; 32]> = sessions
+                    .iter()
+                    .filter(|s| s.idle_for() > idle_limit)
+                    .map(|s| *s.key())
+                    .collect();
+                for session_id in idle {
+                    // Removal stops the send path; close() ends the receive
+                    // loop, which deactivates the session's services.
+                    if let Some((_, session)) = sessions.remove(&session_id) {
+                        session.close();
+                        tracing::info!(
+                            session_id = hex::encode(session_id),
+                            peer = %session.meta.peer_addr,
+                            idle_secs = session.idle_for().as_secs(),
+                            reason = "idle",
+                            "session dropped"
+                        );
+                    }
+                }
+            }
+        })
+    };
+
+    let session_printer = {
+        let sessions = sessions.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(5));
+            loop {
+                interval.tick().await;
+                tracing::info!(count = sessions.len(), "session table snapshot");
+                for s in sessions.iter() {
+                    tracing::info!(
+                        session_id = hex::encode(s.meta.session_id),
+                        peer = %s.meta.peer_addr,
+                        "  session"
+                    );
+                }
+            }
+        })
+    };
+
+    let delivery_tracker = summit_services::DeliveryTracker::new();
+    let transfer_tracker = summit_services::TransferTracker::new();
+    let bandwidth = summit_services::PeerBandwidth::new(config.qos.max_bytes_per_sec_per_peer);
+
+    let chunk_manager_task = tokio::spawn(
+        chunk::manager::ChunkManager::new(
+            sessions.clone(),
+            cache.clone(),
+            delivery_tracker.clone(),
+            reassembler.clone(),
+            trust_registry.clone(),
+            untrusted_buffer.clone(),
+            dispatcher.clone(),
+            chunk_tx.clone(),
+            bandwidth.clone(),
+            shutdown_tx.subscribe(),
+            config.network.bulk_rate,
+            config.network.bulk_burst,
+            recv_channel_depth,
+        )
+        .run(),
+    );
+
+    let send_worker_task = tokio::spawn(
+        chunk::send_worker::SendWorker::new(
+            sessions.clone(),
+            cache.clone(),
+            trust_registry.clone(),
+            chunk_rx,
+            transfer_tracker.clone(),
+            bandwidth.clone(),
+            chunk::send::Pacer::new(&config.qos),
+            shutdown_tx.subscribe(),
+        )
+        .run(),
+    );
+
+    let recovery_task = tokio::spawn(chunk::recovery::recovery_loop(
+        reassembler.clone(),
+        chunk_tx.clone(),
+        shutdown_tx.subscribe(),
+    ));
+
+    let stats_printer = {
+        let tracker = delivery_tracker.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(10));
+            loop {
+                interval.tick().await;
+                tracker.print_stats();
+                tracker.evict_expired();
+            }
+        })
+    };
+
+    // Message retention — expire old messages once per hour
+    let message_expiry = {
+        let store = message_store.clone();
+        let retention_days = config.services.messaging_settings.retention_days;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                let removed = store.expire(retention_days);
+                if removed > 0 {
+                    tracing::info!(removed, retention_days, "expired old messages");
+                }
+            }
+        })
+    };
+
+    // Message TTLs — reads already hide expired messages; this frees them
+    let message_ttl_sweep = {
+        let store = message_store.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(10));
+            loop {
+                interval.tick().await;
+                let removed = store.purge_expired();
+                if removed > 0 {
+                    tracing::debug!(removed, "purged messages past their TTL");
+                }
+            }
+        })
+    };
+
+    // Channel for replaying buffered chunks when a peer becomes trusted
+    let (replay_tx, mut replay_rx) =
+        tokio::sync::mpsc::unbounded_channel::<([u8; 32], summit_services::BufferedChunk)>();
+
+    // Status HTTP endpoint
+    let api_config = config.api.clone();
+    let state = summit_api::ApiState {
+        sessions: sessions.clone(),
+        handshakes: Arc::new(session::TrackerView(handshake_tracker.clone())),
+        connect_tx,
+        cache: cache.clone(),
+        registry: registry.clone(),
+        announcements: announcement_stats.clone(),
+        chunk_tx: chunk_tx.clone(),
+        reassembler: reassembler.clone(),
+        trust: trust_registry.clone(),
+        untrusted_buffer: untrusted_buffer.clone(),
+        message_store: message_store.clone(),
+        presence: presence.clone(),
+        compute_store: compute_store.clone(),
+        delivery: delivery_tracker.clone(),
+        transfers: transfer_tracker.clone(),
+        bandwidth: bandwidth.clone(),
+        keypair: keypair.clone(),
+        interface: interface.clone(),
+        file_transfer_path,
+        enabled_services: service_names_tx.clone(),
+        replay_tx: replay_tx.clone(),
+        shutdown_tx: shutdown_tx.clone(),
+        shutting_down: Arc::new(AtomicBool::new(false)),
+        max_sessions: config.network.max_sessions,
+        session_cooldowns,
+        ready: ready.clone(),
+        started_at,
+    };
+    let status_server = {
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = summit_api::serve(state, &api_config).await {
+                tracing::error!(error = %e, "status server failed");
+            }
+        })
+    };
+
+    // Replay task: dispatches buffered chunks from newly-trusted peers
+    let replay = {
+        let replay_dispatcher = dispatcher.clone();
+        let replay_trust = trust_registry.clone();
+        let replay_cache = cache.clone();
+        tokio::spawn(async move {
+            while let Some((peer_pubkey, chunk)) = replay_rx.recv().await {
+                if replay_trust.check(&peer_pubkey) == TrustLevel::ReceiveOnly
+                    && replay_dispatcher.executes(&chunk.schema_id, chunk.type_tag)
+                {
+                    tracing::debug!(
+                        peer = hex::encode(&peer_pubkey[..8]),
+                        schema_id = hex::encode(chunk.schema_id),
+                        "buffered chunk for an executing service from receive-only peer, dropping"
+                    );
+                    continue;
+                }
+                tracing::info!(
+                    peer = hex::encode(&peer_pubkey[..8]),
+                    content_hash = hex::encode(&chunk.content_hash[..8]),
+                    type_tag = chunk.type_tag,
+                    "replaying buffered chunk from newly-trusted peer"
+                );
+                if !chunk.targeted {
+                    replay_cache.share(&chunk.content_hash);
+                }
+
+                // Dispatch to service dispatcher (handles file transfer,
+                // messaging, compute, etc. — no manual type_tag branching needed)
+                let incoming = summit_services::IncomingChunk {
+                    content_hash: chunk.content_hash,
+                    type_tag: chunk.type_tag,
+                    schema_id: chunk.schema_id,
+                    payload: chunk.payload,
+                    targeted: chunk.targeted,
+                };
+                replay_dispatcher.dispatch(&peer_pubkey, &incoming);
+            }
+        })
+    };
+
+    // Compute executor
+    let compute_executor = if config.services.compute {
+        let store = compute_store.clone();
+        let settings = config.services.compute_settings.clone();
+        let tx = chunk_tx.clone();
+        let trust = trust_registry.clone();
+        let keypair = keypair.clone();
+        Some(tokio::spawn(async move {
+            summit_services::compute_executor::run(store, settings, tx, trust, keypair).await;
+        }))
+    } else {
+        None
+    };
+
+    // Sync offers
+    let sync_task = sync_service.map(|sync| tokio::spawn(sync.run(shutdown_tx.subscribe())));
+
+    // Config reload on SIGHUP
+    let reloader = tokio::spawn(
+        reload::Reloader {
+            current: config.clone(),
+            trust: trust_registry.clone(),
+            untrusted_buffer: untrusted_buffer.clone(),
+            replay_tx,
+            bandwidth: bandwidth.clone(),
+            service_names_tx,
+        }
+        .run(),
+    );
+
+    // ── Wait for exit ────────────────────────────────────────────────────────
+
+    // Every task is stopped once the daemon exits, whichever way it
+    // exits, and sessions are closed so their receive loops end with them:
+    // a daemon in a process that carries on leaves nothing running.
+    let mut aborts = vec![
+        broadcast_task.abort_handle(),
+        listener_task.abort_handle(),
+        expiry_task.abort_handle(),
+        static_peers_task.abort_handle(),
+        session_listener_task.abort_handle(),
+        session_initiator_task.abort_handle(),
+        session_printer.abort_handle(),
+        chunk_manager_task.abort_handle(),
+        send_worker_task.abort_handle(),
+        recovery_task.abort_handle(),
+        stats_printer.abort_handle(),
+        status_server.abort_handle(),
+        follow_services.abort_handle(),
+        session_health.abort_handle(),
+        message_expiry.abort_handle(),
+        message_ttl_sweep.abort_handle(),
+        replay.abort_handle(),
+        reloader.abort_handle(),
+    ];
+    aborts.extend(compute_executor.iter().map(JoinHandle::abort_handle));
+    aborts.extend(sync_task.iter().map(JoinHandle::abort_handle));
+    let mut shutdown_rx = shutdown_tx.subscribe();
+    let goodbye_sessions = sessions.clone();
+
+    let exited = tokio::spawn(async move {
+        tokio::select! {
+            _ = shutdown_rx.recv()       => tracing::info!("shutting down"),
+            r = broadcast_task           => tracing::error!("broadcast task exited: {:?}", r),
+            r = listener_task            => tracing::error!("listener task exited: {:?}", r),
+            r = expiry_task              => tracing::error!("expiry task exited: {:?}", r),
+            r = session_listener_task    => tracing::error!("session listener exited: {:?}", r),
+            r = session_initiator_task   => tracing::error!("session initiator exited: {:?}", r),
+            r = session_printer          => tracing::error!("session printer exited: {:?}", r),
+            r = chunk_manager_task       => tracing::error!("chunk manager exited: {:?}", r),
+            r = send_worker_task         => tracing::error!("send worker exited: {:?}", r),
+            r = recovery_task            => tracing::error!("recovery task exited: {:?}", r),
+            r = stats_printer            => tracing::error!("stats printer exited: {:?}", r),
+        }
+        // Tasks that stop on shutdown can win the select above, so say
+        // goodbye whichever branch ended it.
+        chunk::send::say_goodbye(&goodbye_sessions).await;
+        for task in aborts {
+            task.abort();
+        }
+        let open: Vec<[u8; 32]> = goodbye_sessions.iter().map(|s| *s.key()).collect();
+        for session_id in open {
+            if let Some((_, session)) = goodbye_sessions.remove(&session_id) {
+                session.close();
+            }
+        }
+        drop(cache_fallback);
+    });
+
+    Ok(Daemon { state, exited })
+}
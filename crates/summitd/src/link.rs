@@ -0,0 +1,49 @@
+//! The link summitd talks on — UDP on a network interface, or a host on the
+//! in-process loopback network (`network.transport = "loopback"`).
+//!
+//! Session and chunk sockets are bound through here; discovery builds its
+//! multicast sockets from the same choice.
+
+use std::io;
+use std::net::{Ipv6Addr, SocketAddrV6};
+
+use tokio::net::UdpSocket;
+
+use summit_services::{DatagramSocket, LoopbackNet};
+
+#[derive(Clone)]
+pub enum Link {
+    /// UDP on the interface with this OS index.
+    Udp { interface_index: u32 },
+    /// `host` on the loopback network `net`.
+    Loopback { net: LoopbackNet, host: Ipv6Addr },
+}
+
+impl Link {
+    /// Scope id for link-local addresses on this link. Loopback hosts have
+    /// no interface, so 0.
+    pub fn scope_id(&self) -> u32 {
+        match self {
+            Link::Udp { interface_index } => *interface_index,
+            Link::Loopback { .. } => 0,
+        }
+    }
+
+    /// Bind a socket to `addr`; port 0 picks a free one.
+    pub async fn bind(&self, addr: SocketAddrV6) -> io::Result<DatagramSocket> {
+        match self {
+            Link::Udp { .. } => UdpSocket::bind(addr).await.map(DatagramSocket::from),
+            Link::Loopback { net, host } => {
+                net.bind(*host, addr.port()).map(DatagramSocket::Loopback)
+            }
+        }
+    }
+
+    /// Bind a session's dedicated chunk socket on a free port.
+    pub async fn bind_chunk(&self) -> io::Result<DatagramSocket> {
+        match self {
+            Link::Udp { .. } => UdpSocket::bind("[::]:0").await.map(DatagramSocket::from),
+            Link::Loopback { net, host } => net.bind(*host, 0).map(DatagramSocket::Loopback),
+        }
+    }
+}
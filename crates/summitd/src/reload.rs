@@ -0,0 +1,148 @@
+//! Config reload on SIGHUP.
+//!
+//! Trust rules, the per-peer upload cap and the announced services change
+//! live, without dropping sessions. Anything else in the file needs a
+//! restart; those changes are logged and ignored.
+
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{mpsc, watch};
+
+use summit_core::config::{SummitConfig, TrustConfig};
+use summit_services::{BufferedChunk, PeerBandwidth, TrustLevel, TrustRegistry, UntrustedBuffer};
+
+/// Settings a reload applies without a restart. `services.compute` and
+/// `services.sync` are not among them: their tasks only start with the daemon.
+const LIVE_FIELDS: &[&str] = &[
+    "trust.auto_trust",
+    "trust.trusted_peers",
+    "qos.max_bytes_per_sec_per_peer",
+    "services.file_transfer",
+    "services.messaging",
+    "services.stream_udp",
+];
+
+/// Everything a reload touches. `current` is the config in effect: the
+/// startup config plus whatever live changes have been applied since.
+pub struct Reloader {
+    pub current: SummitConfig,
+    pub trust: TrustRegistry,
+    pub untrusted_buffer: UntrustedBuffer,
+    /// Replays chunks buffered from peers a reload makes trusted.
+    pub replay_tx: mpsc::UnboundedSender<([u8; 32], BufferedChunk)>,
+    pub bandwidth: PeerBandwidth,
+    /// Enabled service names, shared with the API's per-service switches.
+    pub service_names_tx: watch::Sender<Vec<String>>,
+}
+
+impl Reloader {
+    /// Reload the config every time the process receives SIGHUP.
+    pub async fn run(mut self) {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::warn!(error = %e, "cannot listen for SIGHUP; config reload disabled");
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            let path = SummitConfig::file_path();
+            tracing::info!(path = %path.display(), "SIGHUP received, reloading config");
+            match SummitConfig::load() {
+                Ok(config) => self.apply(config),
+                Err(e) => {
+                    tracing::warn!(error = %e, "config reload failed, keeping current config")
+                }
+            }
+        }
+    }
+
+    fn apply(&mut self, new: SummitConfig) {
+        let changed = self.current.changed_fields(&new);
+        if changed.is_empty() {
+            tracing::info!("config unchanged");
+            return;
+        }
+        for field in changed
+            .iter()
+            .filter(|f| !LIVE_FIELDS.contains(&f.as_str()))
+        {
+            tracing::warn!(field = %field, "config change needs a restart, ignored");
+        }
+        let changed = |field: &str| changed.iter().any(|f| f == field);
+
+        if changed("trust.auto_trust") || changed("trust.trusted_peers") {
+            self.apply_trust(&new.trust);
+        }
+
+        if changed("qos.max_bytes_per_sec_per_peer") {
+            let cap = new.qos.max_bytes_per_sec_per_peer;
+            self.bandwidth.set_cap(cap);
+            self.current.qos.max_bytes_per_sec_per_peer = cap;
+            tracing::info!(
+                max_bytes_per_sec_per_peer = cap,
+                "per-peer upload cap reloaded"
+            );
+        }
+
+        // Only the services whose setting changed are switched, so ones
+        // toggled through the API since are left as they are.
+        let toggles = [
+            ("file_transfer", new.services.file_transfer),
+            ("messaging", new.services.messaging),
+            ("stream_udp", new.services.stream_udp),
+        ];
+        let toggles: Vec<_> = toggles
+            .into_iter()
+            .filter(|(name, _)| changed(&format!("services.{name}")))
+            .collect();
+        if !toggles.is_empty() {
+            let services = &mut self.current.services;
+            services.file_transfer = new.services.file_transfer;
+            services.messaging = new.services.messaging;
+            services.stream_udp = new.services.stream_udp;
+
+            self.service_names_tx.send_modify(|names| {
+                for (name, enabled) in toggles {
+                    names.retain(|n| n != name);
+                    if enabled {
+                        names.push(name.to_string());
+                    }
+                }
+            });
+            tracing::info!(services = ?*self.service_names_tx.borrow(), "announced services reloaded");
+        }
+    }
+
+    fn apply_trust(&mut self, trust: &TrustConfig) {
+        for removed in self
+            .current
+            .trust
+            .trusted_peers
+            .iter()
+            .filter(|k| !trust.trusted_peers.contains(k))
+        {
+            tracing::warn!(
+                peer = %removed,
+                "removed from trust.trusted_peers but stays trusted until restart; \
+                 use `summit-ctl trust block` to revoke it now"
+            );
+        }
+
+        self.trust
+            .apply_config(trust.auto_trust, &trust.trusted_peers);
+        self.current.trust = trust.clone();
+        tracing::info!(auto_trust = trust.auto_trust, "trust settings reloaded");
+
+        // Chunks held back from peers that are trusted now, as on `trust add`.
+        for (peer, _) in self.untrusted_buffer.peers() {
+            if self.trust.check(&peer) != TrustLevel::Trusted {
+                continue;
+            }
+            for chunk in self.untrusted_buffer.flush(&peer) {
+                if let Err(e) = self.replay_tx.send((peer, chunk)) {
+                    tracing::warn!(error = %e, "failed to send buffered chunk for replay");
+                }
+            }
+        }
+    }
+}
@@ -2,39 +2,130 @@
 //!
 //! Periodically scans the peer registry and initiates Noise_XX
 //! handshakes with discovered peers (on a 3-second interval).
+//! Unanswered handshakes are retried with exponential backoff.
+//!
+//! Each peer gets a default session (opened for file_transfer, carrying every
+//! negotiated service) plus one dedicated session per Realtime service both
+//! sides run. Handshakes to one peer are made one at a time.
+//!
+//! Tie-break: the peer with the lower public key initiates. The higher-key
+//! side waits, but if no handshake arrives within `TIE_BREAK_TIMEOUT` it
+//! initiates anyway. Should both inits cross, the higher-key side abandons
+//! its own and answers the lower-key side's (see the session listener).
+//!
+//! `POST /sessions/connect` asks for a handshake with one peer right away:
+//! that attempt skips the tick, the retry backoff and the tie-break wait.
 
+use std::collections::{HashMap, HashSet};
 use std::net::{SocketAddr, SocketAddrV6};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use tokio::net::UdpSocket;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 use zerocopy::AsBytes;
 
 use summit_core::crypto::{Keypair, NoiseInitiator};
-use summit_core::wire::HandshakeInit;
-use summit_services::{PeerRegistry, SessionTable};
+use summit_core::wire::{
+    file_transfer_hash, negotiate_version, service_name, HandshakeInit, ServiceHash,
+    MIN_WIRE_VERSION, WIRE_VERSION,
+};
+use summit_services::{
+    remote_peers, ConnectOutcome, ConnectRequest, DatagramSocket, PeerEntry, PeerRegistry,
+    SessionCooldowns, SessionTable,
+};
 
 use super::state::SharedTracker;
+use super::{at_session_limit, dedicated_services};
+use crate::capability::broadcast::LocalServices;
+use crate::link::Link;
+
+/// Delay before the first retry of an unanswered handshake.
+const RETRY_BASE: Duration = Duration::from_secs(3);
+
+/// Upper bound on the retry delay — a peer still in the registry is
+/// always retried at least this often.
+const RETRY_MAX: Duration = Duration::from_secs(60);
+
+/// How long the higher-key side waits for the lower-key peer to initiate
+/// before initiating itself.
+const TIE_BREAK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A peer and the service a session with it is opened for.
+type PeerService = ([u8; 32], ServiceHash);
+
+/// Handshake attempts made to one peer since its last session.
+struct AttemptRecord {
+    count: u32,
+    next_retry: Instant,
+}
+
+impl AttemptRecord {
+    /// Backoff after `count` unanswered attempts: 3s, 6s, 12s, … capped at 60s.
+    fn backoff(count: u32) -> Duration {
+        RETRY_BASE
+            .saturating_mul(1u32 << count.saturating_sub(1).min(16))
+            .min(RETRY_MAX)
+    }
+
+    /// May the next attempt go out at `now`?
+    fn due(&self, now: Instant) -> bool {
+        now >= self.next_retry
+    }
+
+    /// Count an attempt made at `now`. Returns the wait before the next one.
+    fn attempted(&mut self, now: Instant) -> Duration {
+        self.count = self.count.saturating_add(1);
+        let backoff = Self::backoff(self.count);
+        self.next_retry = now + backoff;
+        backoff
+    }
+}
+
+/// Drop per-pair state for pairs that now have a session, which resets their
+/// backoff, and for peers `live` no longer reports.
+fn forget_settled<V>(
+    map: &mut HashMap<PeerService, V>,
+    established: &HashSet<PeerService>,
+    live: impl Fn(&[u8; 32]) -> bool,
+) {
+    map.retain(|key, _| !established.contains(key) && live(&key.0));
+}
 
 pub struct SessionInitiator {
-    socket: Arc<UdpSocket>,
+    socket: Arc<DatagramSocket>,
     keypair: Arc<Keypair>,
     registry: PeerRegistry,
     tracker: SharedTracker,
     sessions: SessionTable,
-    interface_index: u32,
+    link: Link,
     shutdown: broadcast::Receiver<()>,
+    /// Handshakes requested through the API.
+    connect_rx: mpsc::Receiver<ConnectRequest>,
+    /// Services we announce — decides which dedicated sessions to open.
+    local_services: LocalServices,
+    /// No handshakes are started while this many sessions are held. 0 = no cap.
+    max_sessions: usize,
+    /// Peers not to handshake with for now, after a session drop. The API
+    /// clears a peer's cool-down before asking us to connect to it.
+    cooldowns: SessionCooldowns,
+    attempts: HashMap<PeerService, AttemptRecord>,
+    /// Lower-key peers we are waiting on to initiate, and since when.
+    deferred_since: HashMap<PeerService, Instant>,
 }
 
 impl SessionInitiator {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        socket: Arc<UdpSocket>,
+        socket: Arc<DatagramSocket>,
         keypair: Arc<Keypair>,
         registry: PeerRegistry,
         tracker: SharedTracker,
         sessions: SessionTable,
-        interface_index: u32,
+        link: Link,
+        local_services: LocalServices,
+        connect_rx: mpsc::Receiver<ConnectRequest>,
+        max_sessions: usize,
+        cooldowns: SessionCooldowns,
         shutdown: broadcast::Receiver<()>,
     ) -> Self {
         Self {
@@ -43,8 +134,14 @@ impl SessionInitiator {
             registry,
             tracker,
             sessions,
-            interface_index,
+            link,
             shutdown,
+            connect_rx,
+            local_services,
+            max_sessions,
+            cooldowns,
+            attempts: HashMap::new(),
+            deferred_since: HashMap::new(),
         }
     }
 
@@ -62,113 +159,375 @@ impl SessionInitiator {
                     tracing::debug!(peers = self.registry.len(), "initiator tick");
                     self.initiate_handshakes().await;
                 }
+
+                Some((peer_pubkey, reply)) = self.connect_rx.recv() => {
+                    let outcome = self.connect(peer_pubkey).await;
+                    tracing::info!(
+                        peer = hex::encode(&peer_pubkey[..8]),
+                        ?outcome,
+                        "handshake requested through the API"
+                    );
+                    let _ = reply.send(outcome);
+                }
+            }
+        }
+    }
+
+    /// Handshake with one peer now for its default session, regardless of
+    /// the tie-break and any retry backoff.
+    async fn connect(&mut self, peer_pubkey: [u8; 32]) -> ConnectOutcome {
+        let service = file_transfer_hash();
+        let Some(entry) = self
+            .registry
+            .get(&peer_pubkey)
+            .filter(|_| peer_pubkey != self.keypair.public)
+            .map(|e| e.value().clone())
+        else {
+            return ConnectOutcome::UnknownPeer;
+        };
+        if negotiate_version(entry.version).is_none() {
+            return ConnectOutcome::Incompatible;
+        }
+        if self
+            .sessions
+            .iter()
+            .any(|s| s.value().meta.peer_pubkey == peer_pubkey && s.value().meta.service == service)
+        {
+            return ConnectOutcome::Connected;
+        }
+        {
+            let tracker = self.tracker.lock().await;
+            if tracker.has_initiator_waiting(&entry.addr)
+                || tracker.responder_service(&entry.addr).is_some()
+            {
+                return ConnectOutcome::InProgress;
             }
+            if at_session_limit(&self.sessions, &tracker, self.max_sessions) {
+                return ConnectOutcome::SessionLimit;
+            }
+        }
+        if self.send_init(&entry, service, Instant::now()).await {
+            ConnectOutcome::Initiated
+        } else {
+            ConnectOutcome::Failed
         }
     }
 
-    async fn initiate_handshakes(&self) {
-        // Collect pubkeys that already have an active session
-        let active_pubkeys: std::collections::HashSet<[u8; 32]> = self
+    async fn initiate_handshakes(&mut self) {
+        // (peer, service) pairs that already have an established session
+        let established: HashSet<PeerService> = self
             .sessions
             .iter()
-            .map(|entry| entry.value().meta.peer_pubkey)
+            .map(|entry| (entry.value().meta.peer_pubkey, entry.value().meta.service))
             .collect();
 
-        for peer in self.registry.iter() {
-            let peer_pubkey: [u8; 32] = *peer.key();
-            let entry = peer.value();
+        // A session resets the backoff; peers gone from the registry are forgotten.
+        let live = |peer: &[u8; 32]| self.registry.contains_key(peer);
+        forget_settled(&mut self.attempts, &established, live);
+        forget_settled(&mut self.deferred_since, &established, live);
 
-            // Skip if we already have an active session with this peer
-            if active_pubkeys.contains(&peer_pubkey) {
+        let now = Instant::now();
+        for entry in remote_peers(&self.registry, &self.keypair.public) {
+            let peer_pubkey = entry.public_key;
+            if self.cooldowns.remaining(&peer_pubkey).is_some() {
+                continue;
+            }
+            if negotiate_version(entry.version).is_none() {
+                tracing::debug!(
+                    peer = hex::encode(&peer_pubkey[..8]),
+                    peer_version = entry.version,
+                    min_version = MIN_WIRE_VERSION,
+                    "peer wire version too old, not initiating"
+                );
                 continue;
             }
 
-            // Skip if a handshake is already in progress for this peer's IP
-            {
+            // One handshake per peer IP at a time. Skip while the Noise
+            // exchange is done and only the chunk_port exchange is
+            // outstanding, or while one of our inits is still awaiting its
+            // retry time. A lingering init from an unanswered attempt is
+            // simply replaced.
+            let responding = {
                 let tracker = self.tracker.lock().await;
-                if tracker.has_initiator(&entry.addr) || tracker.has_initiator_waiting(&entry.addr)
-                {
+                if tracker.has_initiator_waiting(&entry.addr) {
                     continue;
                 }
+                if let Some(key) = tracker.initiator_for(&entry.addr) {
+                    if self.attempts.get(&key).is_some_and(|r| !r.due(now)) {
+                        continue;
+                    }
+                } else if at_session_limit(&self.sessions, &tracker, self.max_sessions) {
+                    // Replacing a lingering init takes no new slot.
+                    tracing::debug!(
+                        max_sessions = self.max_sessions,
+                        "session limit reached, not initiating"
+                    );
+                    continue;
+                }
+                tracker.responder_service(&entry.addr)
+            };
+            if let Some(service) = responding {
+                // The peer's handshake arrived — restart the tie-break wait.
+                if self.keypair.public >= peer_pubkey {
+                    self.deferred_since.insert((peer_pubkey, service), now);
+                }
+                continue;
+            }
+
+            // The default session first, then one per dedicated service.
+            let mut wanted = vec![file_transfer_hash()];
+            wanted.extend(dedicated_services(&self.local_services.borrow(), &entry));
+
+            let Some(service) = wanted.into_iter().find(|service| {
+                let key = (peer_pubkey, *service);
+                !established.contains(&key)
+                    && self.attempts.get(&key).is_none_or(|r| r.due(now))
+                    && self.tie_break_allows(&key, now)
+            }) else {
+                continue;
+            };
+
+            self.send_init(&entry, service, now).await;
+        }
+    }
+
+    /// Send a HandshakeInit to `entry` for a session carrying `service`, and
+    /// count the attempt. Returns whether the init went out.
+    async fn send_init(&mut self, entry: &PeerEntry, service: ServiceHash, now: Instant) -> bool {
+        let peer_pubkey = entry.public_key;
+        let peer_addr = SocketAddr::V6(SocketAddrV6::new(
+            entry.addr,
+            entry.session_port,
+            0,
+            self.link.scope_id(),
+        ));
+
+        tracing::debug!(
+            peer_addr = %peer_addr,
+            service = service_name(&service).unwrap_or("unknown"),
+            "initiating handshake"
+        );
+
+        // Create chunk socket
+        let chunk_socket = match self.link.bind_chunk().await {
+            Ok(s) => Arc::new(s),
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to bind chunk socket");
+                return false;
             }
+        };
 
-            // Only initiate if our public key is lower than peer's
-            if self.keypair.public >= entry.public_key {
+        let local_chunk_port = match chunk_socket.local_addr() {
+            Ok(addr) => addr.port(),
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to get chunk socket addr");
+                return false;
+            }
+        };
+
+        // Create noise initiator
+        let (noise, msg1) = match NoiseInitiator::new(&self.keypair) {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to create noise initiator");
+                return false;
+            }
+        };
+
+        // Build HandshakeInit
+        let init = HandshakeInit {
+            version: WIRE_VERSION,
+            service_hash: service,
+            noise_msg: match msg1.try_into() {
+                Ok(m) => m,
+                Err(_) => {
+                    tracing::warn!("msg1 wrong size");
+                    return false;
+                }
+            },
+            nonce: *noise.nonce(),
+        };
+
+        // Send HandshakeInit
+        if let Err(e) = self.socket.send_to(init.as_bytes(), peer_addr).await {
+            tracing::warn!(error = %e, "failed to send HandshakeInit");
+            return false;
+        }
+
+        let peer_ip = entry.addr;
+        self.tracker.lock().await.add_initiator(
+            peer_ip,
+            peer_pubkey,
+            service,
+            noise,
+            chunk_socket,
+            local_chunk_port,
+        );
+
+        let record = self
+            .attempts
+            .entry((peer_pubkey, service))
+            .or_insert(AttemptRecord {
+                count: 0,
+                next_retry: now,
+            });
+        let backoff = record.attempted(now);
+        if record.count > 1 {
+            tracing::debug!(
+                peer = hex::encode(&peer_pubkey[..8]),
+                service = service_name(&service).unwrap_or("unknown"),
+                attempt = record.count,
+                next_retry_secs = backoff.as_secs(),
+                "handshake retry"
+            );
+        }
+        true
+    }
+
+    /// Lower public key initiates. The higher-key side waits for the peer,
+    /// but only up to TIE_BREAK_TIMEOUT without a handshake arriving.
+    fn tie_break_allows(&mut self, key: &PeerService, now: Instant) -> bool {
+        let peer_key = &key.0;
+        let since = if self.keypair.public < *peer_key {
+            now
+        } else {
+            *self.deferred_since.entry(*key).or_insert(now)
+        };
+        match tie_break(&self.keypair.public, peer_key, since, now) {
+            TieBreak::LowerKey => {
                 tracing::debug!(
                     our_key = hex::encode(&self.keypair.public[..4]),
-                    peer_key = hex::encode(&entry.public_key[..4]),
+                    peer_key = hex::encode(&peer_key[..4]),
+                    "we have lower key, initiating"
+                );
+                true
+            }
+            TieBreak::Wait => {
+                tracing::debug!(
+                    our_key = hex::encode(&self.keypair.public[..4]),
+                    peer_key = hex::encode(&peer_key[..4]),
                     "peer has lower key, waiting"
                 );
-                continue;
+                false
             }
-            tracing::debug!(
-                our_key = hex::encode(&self.keypair.public[..4]),
-                peer_key = hex::encode(&entry.public_key[..4]),
-                "we have lower key, initiating"
-            );
+            TieBreak::TimedOut(waited) => {
+                tracing::info!(
+                    peer_key = hex::encode(&peer_key[..8]),
+                    service = service_name(&key.1).unwrap_or("unknown"),
+                    waited_secs = waited.as_secs(),
+                    "lower-key peer never initiated, initiating anyway"
+                );
+                true
+            }
+        }
+    }
+}
+
+/// Outcome of the tie-break for one peer.
+#[derive(Debug, PartialEq, Eq)]
+enum TieBreak {
+    /// Our key is lower: initiate.
+    LowerKey,
+    /// Theirs is lower and the wait isn't over: leave it to them.
+    Wait,
+    /// Theirs is lower but they stayed silent this long: initiate anyway.
+    TimedOut(Duration),
+}
 
-            let peer_addr = SocketAddr::V6(SocketAddrV6::new(
-                entry.addr,
-                entry.session_port,
-                0,
-                self.interface_index,
-            ));
+/// The tie-break rule, with the higher-key side waiting on the peer since
+/// `waiting_since`.
+fn tie_break(ours: &[u8; 32], peer: &[u8; 32], waiting_since: Instant, now: Instant) -> TieBreak {
+    if ours < peer {
+        return TieBreak::LowerKey;
+    }
+    let waited = now.duration_since(waiting_since);
+    if waited < TIE_BREAK_TIMEOUT {
+        TieBreak::Wait
+    } else {
+        TieBreak::TimedOut(waited)
+    }
+}
 
-            tracing::debug!(peer_addr = %peer_addr, "initiating handshake");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            // Create chunk socket
-            let chunk_socket = match UdpSocket::bind("[::]:0").await {
-                Ok(s) => Arc::new(s),
-                Err(e) => {
-                    tracing::warn!(error = %e, "failed to bind chunk socket");
-                    continue;
-                }
-            };
+    #[test]
+    fn lower_key_initiates_and_higher_key_waits_out_the_timeout() {
+        let (low, high) = ([1u8; 32], [2u8; 32]);
+        let start = Instant::now();
 
-            let local_chunk_port = match chunk_socket.local_addr() {
-                Ok(addr) => addr.port(),
-                Err(e) => {
-                    tracing::warn!(error = %e, "failed to get chunk socket addr");
-                    continue;
-                }
-            };
+        assert_eq!(tie_break(&low, &high, start, start), TieBreak::LowerKey);
+        assert_eq!(tie_break(&high, &low, start, start), TieBreak::Wait);
+        let almost = start + TIE_BREAK_TIMEOUT - Duration::from_millis(1);
+        assert_eq!(tie_break(&high, &low, start, almost), TieBreak::Wait);
+        let over = start + TIE_BREAK_TIMEOUT;
+        assert_eq!(
+            tie_break(&high, &low, start, over),
+            TieBreak::TimedOut(TIE_BREAK_TIMEOUT)
+        );
+        // A handshake from the peer restarts the wait.
+        assert_eq!(tie_break(&high, &low, over, over), TieBreak::Wait);
+    }
 
-            // Create noise initiator
-            let (noise, msg1) = match NoiseInitiator::new(&self.keypair) {
-                Ok(r) => r,
-                Err(e) => {
-                    tracing::warn!(error = %e, "failed to create noise initiator");
-                    continue;
-                }
-            };
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let secs: Vec<u64> = (1..=7)
+            .map(|n| AttemptRecord::backoff(n).as_secs())
+            .collect();
+        assert_eq!(secs, [3, 6, 12, 24, 48, 60, 60]);
+        assert_eq!(AttemptRecord::backoff(0), RETRY_BASE);
+        assert_eq!(AttemptRecord::backoff(u32::MAX), RETRY_MAX);
+    }
 
-            // Build HandshakeInit
-            let init = HandshakeInit {
-                service_hash: summit_core::wire::service_hash(b"summit.file_transfer"),
-                noise_msg: match msg1.try_into() {
-                    Ok(m) => m,
-                    Err(_) => {
-                        tracing::warn!("msg1 wrong size");
-                        continue;
-                    }
-                },
-                nonce: *noise.nonce(),
-            };
+    #[test]
+    fn attempts_wait_out_the_backoff() {
+        let start = Instant::now();
+        let mut record = AttemptRecord {
+            count: 0,
+            next_retry: start,
+        };
+        assert!(record.due(start));
 
-            // Send HandshakeInit
-            if let Err(e) = self.socket.send_to(init.as_bytes(), peer_addr).await {
-                tracing::warn!(error = %e, "failed to send HandshakeInit");
-                continue;
-            }
+        assert_eq!(record.attempted(start), RETRY_BASE);
+        assert!(!record.due(start + RETRY_BASE - Duration::from_millis(1)));
+        assert!(record.due(start + RETRY_BASE));
 
-            let peer_ip = entry.addr;
-            self.tracker.lock().await.add_initiator(
-                peer_ip,
-                peer_pubkey,
-                noise,
-                chunk_socket,
-                local_chunk_port,
-            );
+        let later = start + RETRY_BASE;
+        assert_eq!(record.attempted(later), RETRY_BASE * 2);
+        assert!(!record.due(later + RETRY_BASE));
+        assert!(record.due(later + RETRY_BASE * 2));
+    }
+
+    #[test]
+    fn a_session_resets_the_backoff() {
+        let now = Instant::now();
+        let (peer, gone) = ([1u8; 32], [2u8; 32]);
+        let key = (peer, file_transfer_hash());
+        let mut attempts = HashMap::new();
+        for other in [key, (gone, file_transfer_hash())] {
+            let record = attempts.entry(other).or_insert(AttemptRecord {
+                count: 0,
+                next_retry: now,
+            });
+            for _ in 0..4 {
+                record.attempted(now);
+            }
         }
+        let live = |p: &[u8; 32]| *p == peer;
+
+        // Still unanswered: the count is kept. A peer gone from the registry is forgotten.
+        forget_settled(&mut attempts, &HashSet::new(), live);
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[&key].count, 4);
+
+        // A session forms, so the next attempt starts from the base delay.
+        forget_settled(&mut attempts, &HashSet::from([key]), live);
+        assert!(attempts.is_empty());
+        let mut record = AttemptRecord {
+            count: 0,
+            next_retry: now,
+        };
+        assert_eq!(record.attempted(now), RETRY_BASE);
     }
 }
@@ -8,44 +8,66 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
-use tokio::net::UdpSocket;
-use tokio::sync::{broadcast, Mutex};
+use tokio::sync::broadcast;
 use zerocopy::{AsBytes, FromBytes};
 
 use summit_core::crypto::{Keypair, NoiseResponder};
-use summit_core::wire::{Contract, HandshakeComplete, HandshakeInit, HandshakeResponse};
-use summit_services::{ActiveSession, PeerRegistry, SessionMeta, SessionTable, TokenBucket};
+use summit_core::wire::{
+    negotiate_version, service_name, HandshakeComplete, HandshakeInit, HandshakeResponse,
+    MAX_HANDSHAKE_DATAGRAM, MIN_WIRE_VERSION, WIRE_VERSION,
+};
+use summit_services::{
+    ActiveSession, DatagramSocket, PeerRegistry, SessionCooldowns, SessionMeta, SessionTable,
+};
 
-use super::default_active_services;
 use super::state::SharedTracker;
+use super::{active_services_for, at_session_limit};
+use crate::capability::broadcast::LocalServices;
+use crate::link::Link;
 
 pub struct SessionListener {
-    socket: Arc<UdpSocket>,
+    socket: Arc<DatagramSocket>,
+    link: Link,
     keypair: Arc<Keypair>,
     sessions: SessionTable,
     tracker: SharedTracker,
     local_addr: Ipv6Addr,
     registry: PeerRegistry,
+    /// Services we announce — intersected with the peer's on establishment.
+    local_services: LocalServices,
+    /// Handshakes are ignored while this many sessions are held. 0 = no cap.
+    max_sessions: usize,
+    /// Peers whose HandshakeInits are ignored after a session drop.
+    cooldowns: SessionCooldowns,
     shutdown: broadcast::Receiver<()>,
 }
 
 impl SessionListener {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        socket: Arc<UdpSocket>,
+        socket: Arc<DatagramSocket>,
+        link: Link,
         keypair: Arc<Keypair>,
         sessions: SessionTable,
         tracker: SharedTracker,
         local_addr: Ipv6Addr,
         registry: PeerRegistry,
+        local_services: LocalServices,
+        max_sessions: usize,
+        cooldowns: SessionCooldowns,
         shutdown: broadcast::Receiver<()>,
     ) -> Self {
         Self {
             socket,
+            link,
             keypair,
             sessions,
             tracker,
             local_addr,
             registry,
+            local_services,
+            max_sessions,
+            cooldowns,
             shutdown,
         }
     }
@@ -55,7 +77,7 @@ impl SessionListener {
         const HANDSHAKE_RESPONSE_SIZE: usize = std::mem::size_of::<HandshakeResponse>();
         const HANDSHAKE_COMPLETE_SIZE: usize = std::mem::size_of::<HandshakeComplete>();
 
-        let mut buf = vec![0u8; 512];
+        let mut buf = vec![0u8; MAX_HANDSHAKE_DATAGRAM + 1];
         let mut cleanup_interval = tokio::time::interval(Duration::from_secs(5));
 
         loop {
@@ -88,6 +110,15 @@ impl SessionListener {
                         continue;
                     }
 
+                    if len > MAX_HANDSHAKE_DATAGRAM {
+                        tracing::warn!(
+                            peer_addr = %peer_addr,
+                            max = MAX_HANDSHAKE_DATAGRAM,
+                            "oversized session datagram, possibly truncated, discarding"
+                        );
+                        continue;
+                    }
+
                     let data = &buf[..len];
 
                     if len == HANDSHAKE_INIT_SIZE {
@@ -104,6 +135,15 @@ impl SessionListener {
         }
     }
 
+    /// Wire version for a new session: negotiated from the peer's announced
+    /// version. HandshakeInit versions are checked before any state is kept.
+    fn wire_version_for(&self, peer_pubkey: &[u8; 32]) -> u32 {
+        self.registry
+            .get(peer_pubkey)
+            .and_then(|entry| negotiate_version(entry.version))
+            .unwrap_or(WIRE_VERSION)
+    }
+
     async fn handle_init(&self, data: &[u8], peer_addr: SocketAddr, peer_ip: Ipv6Addr) {
         let init = match HandshakeInit::read_from(data) {
             Some(m) => m,
@@ -115,21 +155,67 @@ impl SessionListener {
 
         tracing::debug!(peer_addr = %peer_addr, "received HandshakeInit");
 
-        // Deduplicate
+        let peer_version = init.version;
+        if negotiate_version(peer_version).is_none() {
+            tracing::warn!(
+                %peer_addr,
+                peer_version,
+                our_version = WIRE_VERSION,
+                min_version = MIN_WIRE_VERSION,
+                "incompatible wire version, refusing handshake"
+            );
+            return;
+        }
+
+        if let Some(left) = self.cooldowns.remaining_at(&peer_ip) {
+            tracing::debug!(
+                %peer_addr,
+                remaining_secs = left.as_secs(),
+                "peer in session drop cool-down, ignoring HandshakeInit"
+            );
+            return;
+        }
+
+        // Deduplicate. Only one handshake per peer IP may be in flight
+        // (later handshake messages carry no service hash to tell them apart).
         {
-            let t = self.tracker.lock().await;
-            if t.has_initiator(&peer_ip) || t.has_initiator_waiting(&peer_ip) {
+            let mut t = self.tracker.lock().await;
+            if t.has_initiator_waiting(&peer_ip) {
                 tracing::debug!(%peer_addr, "already initiating to this peer, ignoring HandshakeInit");
                 return;
             }
-            if t.has_responder(&peer_ip) || t.has_responder_waiting(&peer_ip) {
-                tracing::debug!(%peer_addr, "duplicate HandshakeInit, ignoring");
+            // Crossed inits for the same service: the lower-key side's init
+            // wins. If ours is the higher key we only initiated after the
+            // tie-break timeout, so abandon ours and answer theirs.
+            if let Some((peer_pubkey, service)) = t.initiator_for(&peer_ip) {
+                if service == init.service_hash && self.keypair.public > peer_pubkey {
+                    tracing::debug!(%peer_addr, "crossed HandshakeInit, yielding to lower-key peer");
+                    t.remove_initiator(&peer_ip);
+                } else {
+                    tracing::debug!(%peer_addr, "already initiating to this peer, ignoring HandshakeInit");
+                    return;
+                }
+            }
+            if let Some(service) = t.responder_service(&peer_ip) {
+                if service == init.service_hash {
+                    tracing::debug!(%peer_addr, "duplicate HandshakeInit, ignoring");
+                } else {
+                    tracing::debug!(%peer_addr, "handshake for another service in flight, ignoring HandshakeInit");
+                }
+                return;
+            }
+            if at_session_limit(&self.sessions, &t, self.max_sessions) {
+                tracing::warn!(
+                    %peer_addr,
+                    max_sessions = self.max_sessions,
+                    "session limit reached, ignoring HandshakeInit"
+                );
                 return;
             }
         }
 
         // Create chunk socket
-        let chunk_socket = match UdpSocket::bind("[::]:0").await {
+        let chunk_socket = match self.link.bind_chunk().await {
             Ok(s) => Arc::new(s),
             Err(e) => {
                 tracing::warn!(error = %e, "failed to bind chunk socket");
@@ -196,10 +282,15 @@ impl SessionListener {
                 return;
             }
         };
+        if peer_pubkey == self.keypair.public {
+            tracing::debug!(%peer_addr, "HandshakeInit from our own key, ignoring");
+            return;
+        }
 
         self.tracker.lock().await.add_responder(
             peer_ip,
             peer_pubkey,
+            init.service_hash,
             pending,
             local_chunk_port,
             chunk_socket,
@@ -278,6 +369,7 @@ impl SessionListener {
             state.chunk_socket,
             state.chunk_socket_port,
             state.peer_pubkey,
+            state.service_hash,
         );
     }
 
@@ -316,6 +408,7 @@ impl SessionListener {
             state.chunk_socket,
             state.chunk_socket_port,
             state.peer_pubkey,
+            state.service_hash,
         );
     }
 
@@ -344,29 +437,45 @@ impl SessionListener {
 
             let peer_chunk_port = u16::from_le_bytes([decrypted[0], decrypted[1]]);
             let session_id = state.session.session_id;
-            let active_services = default_active_services();
+            let active_services = active_services_for(
+                &self.local_services.borrow(),
+                &self.registry,
+                &state.peer_pubkey,
+                &state.service_hash,
+            );
+            let service_count = active_services.len();
+            if service_count == 0 {
+                tracing::warn!(
+                    peer_addr = %peer_addr,
+                    "no services in common with peer"
+                );
+            }
 
             self.sessions.insert(
                 session_id,
-                ActiveSession {
-                    meta: SessionMeta {
+                ActiveSession::new(
+                    SessionMeta {
                         session_id,
                         peer_addr,
                         chunk_port: peer_chunk_port,
                         established_at: std::time::Instant::now(),
                         peer_pubkey: state.peer_pubkey,
+                        service: state.service_hash,
+                        wire_version: self.wire_version_for(&state.peer_pubkey),
                         active_services,
                     },
-                    crypto: Arc::new(Mutex::new(state.session)),
-                    socket: state.chunk_socket,
-                    bucket: Arc::new(Mutex::new(TokenBucket::new(Contract::Bulk))),
-                },
+                    state.session,
+                    state.chunk_socket,
+                ),
             );
 
             tracing::info!(
                 peer_addr = %peer_addr,
                 session_id = hex::encode(session_id),
+                peer_pubkey = hex::encode(state.peer_pubkey),
                 peer_chunk_port,
+                services = service_count,
+                service = service_name(&state.service_hash).unwrap_or("unknown"),
                 "session established (initiator)"
             );
         } else if let Some(mut state) = tracker_lock.remove_responder_waiting(&peer_ip) {
@@ -397,29 +506,45 @@ impl SessionListener {
             }
 
             let session_id = state.session.session_id;
-            let active_services = default_active_services();
+            let active_services = active_services_for(
+                &self.local_services.borrow(),
+                &self.registry,
+                &state.peer_pubkey,
+                &state.service_hash,
+            );
+            let service_count = active_services.len();
+            if service_count == 0 {
+                tracing::warn!(
+                    peer_addr = %peer_addr,
+                    "no services in common with peer"
+                );
+            }
 
             self.sessions.insert(
                 session_id,
-                ActiveSession {
-                    meta: SessionMeta {
+                ActiveSession::new(
+                    SessionMeta {
                         session_id,
                         peer_addr,
                         chunk_port: peer_chunk_port,
                         established_at: std::time::Instant::now(),
                         peer_pubkey: state.peer_pubkey,
+                        service: state.service_hash,
+                        wire_version: self.wire_version_for(&state.peer_pubkey),
                         active_services,
                     },
-                    crypto: Arc::new(Mutex::new(state.session)),
-                    socket: state.chunk_socket,
-                    bucket: Arc::new(Mutex::new(TokenBucket::new(Contract::Bulk))),
-                },
+                    state.session,
+                    state.chunk_socket,
+                ),
             );
 
             tracing::info!(
                 peer_addr = %peer_addr,
                 session_id = hex::encode(session_id),
+                peer_pubkey = hex::encode(state.peer_pubkey),
                 peer_chunk_port,
+                services = service_count,
+                service = service_name(&state.service_hash).unwrap_or("unknown"),
                 "session established (responder)"
             );
         } else {
@@ -4,27 +4,59 @@ pub mod initiator;
 pub mod listener;
 mod state;
 
-pub use state::HandshakeTracker;
+pub use state::{HandshakeTracker, TrackerView};
 
 use std::collections::HashMap;
-use summit_core::wire::{service_hash, Contract, ServiceHash};
-use summit_services::ServiceOnSession;
+use summit_core::wire::{file_transfer_hash, Contract, ServiceHash};
+use summit_services::{negotiate_services, PeerRegistry, ServiceOnSession, SessionTable};
 
-/// Build the default set of active services for a newly established session.
-pub fn default_active_services() -> HashMap<ServiceHash, ServiceOnSession> {
-    let mut m = HashMap::new();
-    for name in [
-        b"summit.file_transfer" as &[u8],
-        b"summit.messaging",
-        b"summit.compute",
-    ] {
-        m.insert(
-            service_hash(name),
-            ServiceOnSession {
-                contract: Contract::Bulk,
-                chunk_port: 0,
-            },
-        );
+use crate::capability::broadcast::ServiceEntry;
+
+/// Is there no room for another session under `max_sessions`? Handshakes
+/// in flight count, so several at once can't overshoot. 0 = unlimited.
+pub fn at_session_limit(
+    sessions: &SessionTable,
+    tracker: &HandshakeTracker,
+    max_sessions: usize,
+) -> bool {
+    max_sessions > 0 && sessions.len() + tracker.in_flight() >= max_sessions
+}
+
+/// Active services for a newly established session with `peer_pubkey`:
+/// our enabled services intersected with what the peer announced.
+///
+/// The default session (opened for file_transfer) carries all of them; a
+/// session opened for another service carries only that service.
+/// Empty if the peer is no longer in the registry.
+pub fn active_services_for(
+    local_services: &[ServiceEntry],
+    registry: &PeerRegistry,
+    peer_pubkey: &[u8; 32],
+    service: &ServiceHash,
+) -> HashMap<ServiceHash, ServiceOnSession> {
+    let Some(peer) = registry.get(peer_pubkey) else {
+        return HashMap::new();
+    };
+    let mut negotiated = negotiate_services(
+        local_services.iter().map(|s| (s.hash, s.contract)),
+        peer.value(),
+    );
+    if *service != file_transfer_hash() {
+        negotiated.retain(|hash, _| hash == service);
     }
-    m
+    negotiated
+}
+
+/// Services that get a session of their own with each peer, on top of the
+/// default one: our Realtime services the peer also announced, so their
+/// traffic never queues behind bulk transfers on a shared socket.
+pub fn dedicated_services(
+    local_services: &[ServiceEntry],
+    peer: &summit_services::PeerEntry,
+) -> Vec<ServiceHash> {
+    negotiate_services(local_services.iter().map(|s| (s.hash, s.contract)), peer)
+        .into_iter()
+        .filter(|(_, svc)| svc.contract == Contract::Realtime)
+        .map(|(hash, _)| hash)
+        .collect()
 }
@@ -1,19 +1,35 @@
 //! Handshake state tracking for the single session listener.
 
 use std::collections::HashMap;
+use std::future::Future;
 use std::net::Ipv6Addr;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Instant;
 
-use tokio::net::UdpSocket;
 use tokio::sync::Mutex;
 
 use summit_core::crypto::{NoiseInitiator, ResponderPending, Session};
+use summit_core::wire::ServiceHash;
+use summit_services::{DatagramSocket, HandshakeInfo, HandshakePhase, HandshakeSource};
 
 /// Shared handshake tracker
 pub type SharedTracker = Arc<Mutex<HandshakeTracker>>;
 
+/// Read-only view of a shared tracker, handed to the API.
+pub struct TrackerView(pub SharedTracker);
+
+impl HandshakeSource for TrackerView {
+    fn handshakes(&self) -> Pin<Box<dyn Future<Output = Vec<HandshakeInfo>> + Send + '_>> {
+        Box::pin(async move { self.0.lock().await.snapshot() })
+    }
+}
+
 /// Tracks in-progress handshakes from multiple peers.
+///
+/// Keyed by peer IP: HandshakeResponse, HandshakeComplete and the chunk_port
+/// exchange carry no service hash, so at most one handshake per peer is in
+/// flight at a time. A peer may still hold several established sessions.
 pub struct HandshakeTracker {
     initiators: HashMap<Ipv6Addr, InitiatorState>,
     responders: HashMap<Ipv6Addr, ResponderState>,
@@ -22,24 +38,27 @@ pub struct HandshakeTracker {
 }
 
 pub struct InitiatorState {
+    pub service_hash: ServiceHash,
     pub noise: NoiseInitiator,
     pub started_at: Instant,
-    pub chunk_socket: Arc<UdpSocket>,
+    pub chunk_socket: Arc<DatagramSocket>,
     pub chunk_socket_port: u16,
     pub peer_pubkey: [u8; 32],
 }
 
 pub struct ResponderState {
+    pub service_hash: ServiceHash,
     pub pending: ResponderPending,
     pub started_at: Instant,
-    pub chunk_socket: Arc<UdpSocket>,
+    pub chunk_socket: Arc<DatagramSocket>,
     pub chunk_socket_port: u16,
     pub peer_pubkey: [u8; 32],
 }
 
 pub struct InitiatorWaiting {
+    pub service_hash: ServiceHash,
     pub session: Session,
-    pub chunk_socket: Arc<UdpSocket>,
+    pub chunk_socket: Arc<DatagramSocket>,
     #[allow(dead_code)]
     pub chunk_socket_port: u16,
     pub peer_pubkey: [u8; 32],
@@ -47,8 +66,9 @@ pub struct InitiatorWaiting {
 }
 
 pub struct ResponderWaiting {
+    pub service_hash: ServiceHash,
     pub session: Session,
-    pub chunk_socket: Arc<UdpSocket>,
+    pub chunk_socket: Arc<DatagramSocket>,
     pub local_chunk_port: u16,
     pub peer_pubkey: [u8; 32],
     pub started_at: Instant,
@@ -72,13 +92,15 @@ impl HandshakeTracker {
         &mut self,
         peer_ip: Ipv6Addr,
         peer_pubkey: [u8; 32],
+        service_hash: ServiceHash,
         noise: NoiseInitiator,
-        chunk_socket: Arc<UdpSocket>,
+        chunk_socket: Arc<DatagramSocket>,
         chunk_port: u16,
     ) {
         self.initiators.insert(
             peer_ip,
             InitiatorState {
+                service_hash,
                 noise,
                 started_at: Instant::now(),
                 chunk_socket,
@@ -92,13 +114,15 @@ impl HandshakeTracker {
         &mut self,
         peer_ip: Ipv6Addr,
         peer_pubkey: [u8; 32],
+        service_hash: ServiceHash,
         pending: ResponderPending,
         chunk_port: u16,
-        chunk_socket: Arc<UdpSocket>,
+        chunk_socket: Arc<DatagramSocket>,
     ) {
         self.responders.insert(
             peer_ip,
             ResponderState {
+                service_hash,
                 pending,
                 started_at: Instant::now(),
                 chunk_socket,
@@ -112,13 +136,15 @@ impl HandshakeTracker {
         &mut self,
         peer_ip: Ipv6Addr,
         session: Session,
-        chunk_socket: Arc<UdpSocket>,
+        chunk_socket: Arc<DatagramSocket>,
         chunk_port: u16,
         peer_pubkey: [u8; 32],
+        service_hash: ServiceHash,
     ) {
         self.initiators_waiting.insert(
             peer_ip,
             InitiatorWaiting {
+                service_hash,
                 session,
                 chunk_socket,
                 chunk_socket_port: chunk_port,
@@ -132,13 +158,15 @@ impl HandshakeTracker {
         &mut self,
         peer_ip: Ipv6Addr,
         session: Session,
-        chunk_socket: Arc<UdpSocket>,
+        chunk_socket: Arc<DatagramSocket>,
         local_chunk_port: u16,
         peer_pubkey: [u8; 32],
+        service_hash: ServiceHash,
     ) {
         self.responders_waiting.insert(
             peer_ip,
             ResponderWaiting {
+                service_hash,
                 session,
                 chunk_socket,
                 local_chunk_port,
@@ -164,22 +192,91 @@ impl HandshakeTracker {
         self.responders_waiting.remove(peer_ip)
     }
 
-    pub fn has_responder(&self, peer_ip: &Ipv6Addr) -> bool {
-        self.responders.contains_key(peer_ip)
-    }
-
-    pub fn has_responder_waiting(&self, peer_ip: &Ipv6Addr) -> bool {
-        self.responders_waiting.contains_key(peer_ip)
+    /// Peer public key and service of our in-flight init to `peer_ip`, if any.
+    pub fn initiator_for(&self, peer_ip: &Ipv6Addr) -> Option<([u8; 32], ServiceHash)> {
+        self.initiators
+            .get(peer_ip)
+            .map(|s| (s.peer_pubkey, s.service_hash))
     }
 
-    pub fn has_initiator(&self, peer_ip: &Ipv6Addr) -> bool {
-        self.initiators.contains_key(peer_ip)
+    /// Service of the handshake we are responding to from `peer_ip`, if any.
+    pub fn responder_service(&self, peer_ip: &Ipv6Addr) -> Option<ServiceHash> {
+        self.responders
+            .get(peer_ip)
+            .map(|s| s.service_hash)
+            .or_else(|| self.responders_waiting.get(peer_ip).map(|s| s.service_hash))
     }
 
     pub fn has_initiator_waiting(&self, peer_ip: &Ipv6Addr) -> bool {
         self.initiators_waiting.contains_key(peer_ip)
     }
 
+    /// Handshakes in flight, in any phase.
+    pub fn in_flight(&self) -> usize {
+        self.initiators.len()
+            + self.responders.len()
+            + self.initiators_waiting.len()
+            + self.responders_waiting.len()
+    }
+
+    /// Every in-flight handshake: which phase it is in and for how long.
+    pub fn snapshot(&self) -> Vec<HandshakeInfo> {
+        let info = |peer_addr: &Ipv6Addr,
+                    peer_pubkey: [u8; 32],
+                    service_hash: ServiceHash,
+                    phase: HandshakePhase,
+                    started_at: Instant| HandshakeInfo {
+            peer_addr: *peer_addr,
+            peer_pubkey,
+            service_hash,
+            phase,
+            age: started_at.elapsed(),
+        };
+
+        let initiators = self.initiators.iter().map(|(ip, s)| {
+            info(
+                ip,
+                s.peer_pubkey,
+                s.service_hash,
+                HandshakePhase::Initiator,
+                s.started_at,
+            )
+        });
+        let responders = self.responders.iter().map(|(ip, s)| {
+            info(
+                ip,
+                s.peer_pubkey,
+                s.service_hash,
+                HandshakePhase::Responder,
+                s.started_at,
+            )
+        });
+        let initiators_waiting = self.initiators_waiting.iter().map(|(ip, s)| {
+            info(
+                ip,
+                s.peer_pubkey,
+                s.service_hash,
+                HandshakePhase::InitiatorWaitingChunk,
+                s.started_at,
+            )
+        });
+        let responders_waiting = self.responders_waiting.iter().map(|(ip, s)| {
+            info(
+                ip,
+                s.peer_pubkey,
+                s.service_hash,
+                HandshakePhase::ResponderWaitingChunk,
+                s.started_at,
+            )
+        });
+
+        initiators
+            .chain(responders)
+            .chain(initiators_waiting)
+            .chain(responders_waiting)
+            .collect()
+    }
+
     /// Clean up stale handshakes older than the configured timeout.
     pub fn cleanup_stale(&mut self) {
         let cutoff = Instant::now()
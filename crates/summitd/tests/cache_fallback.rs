@@ -0,0 +1,34 @@
+//! A daemon whose cache root is unusable caches in a private temp directory
+//! and removes it on shutdown.
+
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+
+use common::{config, test_root, write_keypair};
+use summit_core::config::CacheBackend;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn unusable_cache_root_falls_back_to_a_private_temp_dir() {
+    let root = test_root("cache-fallback");
+    write_keypair(&root, "a");
+    let mut config_a = config(&root, "a");
+    config_a.cache.backend = CacheBackend::Disk;
+    // A file where the data directory should be: `cache/` can't go under it.
+    std::fs::write(root.join("a").join("blocked"), b"").unwrap();
+    config_a.storage.data_dir = root.join("a").join("blocked");
+
+    let a = summitd::start(config_a, None).await.unwrap();
+    let fallback = a.state.cache.root().unwrap().to_path_buf();
+    assert!(fallback.starts_with(std::env::temp_dir()));
+    let name = fallback.file_name().unwrap().to_string_lossy().into_owned();
+    assert!(name.starts_with("summit-cache-"));
+    assert_ne!(name, format!("summit-cache-{}", std::process::id()));
+    let mode = std::fs::metadata(&fallback).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+
+    a.shutdown();
+    a.wait().await;
+    assert!(!fallback.exists(), "fallback cache dir left behind");
+    let _ = std::fs::remove_dir_all(&root);
+}
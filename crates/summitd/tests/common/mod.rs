@@ -0,0 +1,68 @@
+//! Fixtures shared by the daemon integration tests: loopback configs rooted
+//! in a per-test directory, keypairs on disk and a polling helper.
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use summit_core::config::{CacheBackend, Discovery, SummitConfig, Transport};
+use summit_core::crypto::Keypair;
+
+/// Session port the static-peer fixtures listen on and dial.
+pub const SESSION_PORT: u16 = 7000;
+
+/// A fresh, empty directory for one test's daemons.
+pub fn test_root(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("summit-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    root
+}
+
+/// A loopback daemon keeping all of its state under `root/name`.
+pub fn config(root: &Path, name: &str) -> SummitConfig {
+    let dir = root.join(name);
+    let mut config = SummitConfig::default();
+    config.network.transport = Transport::Loopback;
+    config.identity.keypair_path = dir.join("keypair");
+    config.cache.backend = CacheBackend::Memory;
+    config.trust.auto_trust = true;
+    config.api.port = 0;
+    config.storage.data_dir = dir.join("data");
+    config.services.file_transfer_settings.storage_path = dir.join("received");
+    config.services.messaging_settings.storage_path = dir.join("messages");
+    config
+}
+
+/// Like [`config`], with multicast discovery off and `peers` (keypair and
+/// loopback address) listed in `network.static_peers`.
+pub fn static_config(root: &Path, name: &str, peers: &[(&Keypair, &str)]) -> SummitConfig {
+    let mut config = config(root, name);
+    config.network.discovery = Discovery::Off;
+    config.network.session_port = SESSION_PORT;
+    config.network.static_peers = peers
+        .iter()
+        .map(|(key, addr)| format!("{}@[{}]:{}", hex::encode(key.public), addr, SESSION_PORT))
+        .collect();
+    config
+}
+
+/// Generate a keypair and store it where [`config`] points `name` at.
+pub fn write_keypair(root: &Path, name: &str) -> Keypair {
+    let keypair = Keypair::generate();
+    std::fs::create_dir_all(root.join(name)).unwrap();
+    std::fs::write(root.join(name).join("keypair"), *keypair.private_bytes()).unwrap();
+    keypair
+}
+
+/// Poll `done` every 100 ms for up to `limit`.
+pub async fn wait_for(limit: Duration, mut done: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + limit;
+    while Instant::now() < deadline {
+        if done() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    done()
+}
@@ -0,0 +1,137 @@
+//! Daemons in one process on the loopback transport: discovery,
+//! handshake, trust and a file transfer end to end, without network
+//! namespaces or root, and the checks on signed announcements.
+
+mod common;
+
+use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use common::{config, test_root, wait_for, write_keypair};
+use summit_core::crypto::Keypair;
+use summit_core::wire::{messaging_hash, CapabilityAnnouncement, Contract};
+use summit_services::{chunk_file, LoopbackNet, SendTarget};
+use zerocopy::AsBytes;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn two_daemons_transfer_a_file_over_loopback() {
+    let root = test_root("loopback");
+    let net = LoopbackNet::new();
+
+    let a = summitd::start_on(config(&root, "a"), &net).await.unwrap();
+    let b = summitd::start_on(config(&root, "b"), &net).await.unwrap();
+
+    let connected = wait_for(Duration::from_secs(20), || {
+        !a.state.sessions.is_empty() && !b.state.sessions.is_empty()
+    })
+    .await;
+    assert!(connected, "no session between the loopback daemons");
+
+    // 3 data chunks, the last one short.
+    let content: Vec<u8> = (0..70_000u32).map(|i| (i % 251) as u8).collect();
+    let src = root.join("loopback.bin");
+    std::fs::write(&src, &content).unwrap();
+    let target = SendTarget::Peer {
+        public_key: b.state.keypair.public,
+    };
+    for chunk in chunk_file(&src).unwrap() {
+        a.state
+            .chunk_tx
+            .send((target.clone(), chunk))
+            .await
+            .unwrap();
+    }
+
+    let received = root.join("b").join("received").join("loopback.bin");
+    let arrived = wait_for(Duration::from_secs(10), || {
+        std::fs::read(&received).is_ok_and(|data| data == content)
+    })
+    .await;
+    assert!(arrived, "file not received intact");
+
+    a.shutdown();
+    b.shutdown();
+    a.wait().await;
+    b.wait().await;
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+fn signed(keypair: &Keypair, addr: Ipv6Addr, sequence: u64) -> CapabilityAnnouncement {
+    let mut announcement = CapabilityAnnouncement {
+        service_hash: messaging_hash(),
+        public_key: keypair.public,
+        version: 1,
+        session_port: 9001,
+        chunk_port: 0,
+        contract: Contract::Bulk as u8,
+        flags: 0,
+        service_count: 1,
+        service_index: 0,
+        addr: addr.octets(),
+        sequence,
+        signature: [0; 64],
+    };
+    announcement.sign(keypair);
+    announcement
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn replayed_and_relocated_announcements_are_refused() {
+    let root = test_root("announcements");
+    let net = LoopbackNet::new();
+    write_keypair(&root, "a");
+    let config_a = config(&root, "a");
+    let dest = SocketAddr::V6(SocketAddrV6::new(
+        config_a.network.multicast_group,
+        config_a.network.discovery_port,
+        0,
+        0,
+    ));
+    let a = summitd::start_on(config_a, &net).await.unwrap();
+    let ready = wait_for(Duration::from_secs(5), || {
+        a.state.ready.load(Ordering::Relaxed)
+    })
+    .await;
+    assert!(ready, "discovery listener never bound");
+
+    let (host_b, host_c) = (net.add_host(), net.add_host());
+    let (socket_b, socket_c) = (net.bind(host_b, 0).unwrap(), net.bind(host_c, 0).unwrap());
+    let key_b = Keypair::generate();
+    let stats = a.state.announcements.clone();
+
+    let first = signed(&key_b, host_b, 10);
+    socket_b.send_to(first.as_bytes(), dest).unwrap();
+    let known = wait_for(Duration::from_secs(5), || {
+        a.state.registry.contains_key(&key_b.public)
+    })
+    .await;
+    assert!(known, "first announcement not accepted");
+
+    // The same datagram again, from the same address: a replay.
+    socket_b.send_to(first.as_bytes(), dest).unwrap();
+    let refused = wait_for(Duration::from_secs(5), || {
+        stats.stale.load(Ordering::Relaxed) == 1
+    })
+    .await;
+    assert!(refused, "replayed announcement not refused");
+
+    // A newer one, but sent from C while it signs B's address.
+    let invalid_before = stats.invalid.load(Ordering::Relaxed);
+    socket_c
+        .send_to(signed(&key_b, host_b, 11).as_bytes(), dest)
+        .unwrap();
+    let refused = wait_for(Duration::from_secs(5), || {
+        stats.invalid.load(Ordering::Relaxed) > invalid_before
+    })
+    .await;
+    assert!(refused, "relocated announcement not refused");
+    let entry = a.state.registry.get(&key_b.public).unwrap();
+    assert_eq!(entry.addr, host_b);
+    assert_eq!(entry.sequence, 10);
+    drop(entry);
+
+    a.shutdown();
+    a.wait().await;
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -0,0 +1,200 @@
+//! Sessions between loopback daemons with multicast discovery off, each
+//! listing the others in `network.static_peers`: forming, saying Goodbye,
+//! `network.max_sessions` and API-requested cool-downs.
+//!
+//! Each test builds its own network, on which the daemons it starts in
+//! order are hosts fe80::1, fe80::2, …
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{static_config, test_root, wait_for, write_keypair};
+use summit_api::ApiState;
+use summit_core::config::ApiConfig;
+use summit_services::LoopbackNet;
+use tower::ServiceExt;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn static_peers_form_a_session_without_multicast() {
+    let root = test_root("static-peers");
+    let net = LoopbackNet::new();
+
+    let (key_a, key_b) = (write_keypair(&root, "a"), write_keypair(&root, "b"));
+    let a = summitd::start_on(static_config(&root, "a", &[(&key_b, "fe80::2")]), &net)
+        .await
+        .unwrap();
+    let b = summitd::start_on(static_config(&root, "b", &[(&key_a, "fe80::1")]), &net)
+        .await
+        .unwrap();
+
+    let connected = wait_for(Duration::from_secs(20), || {
+        !a.state.sessions.is_empty() && !b.state.sessions.is_empty()
+    })
+    .await;
+    assert!(connected, "no session between the static peers");
+    let session = a.state.sessions.iter().next().unwrap();
+    assert_eq!(session.meta.peer_pubkey, key_b.public);
+    drop(session);
+
+    // Nothing was discovered: each registry holds just its static peer.
+    assert_eq!(a.state.registry.len(), 1);
+    assert_eq!(b.state.registry.len(), 1);
+
+    a.shutdown();
+    b.shutdown();
+    a.wait().await;
+    b.wait().await;
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn goodbye_prunes_the_session_on_the_other_side() {
+    let root = test_root("goodbye");
+    let net = LoopbackNet::new();
+
+    let (key_a, key_b) = (write_keypair(&root, "a"), write_keypair(&root, "b"));
+    let a = summitd::start_on(static_config(&root, "a", &[(&key_b, "fe80::2")]), &net)
+        .await
+        .unwrap();
+    let b = summitd::start_on(static_config(&root, "b", &[(&key_a, "fe80::1")]), &net)
+        .await
+        .unwrap();
+
+    let connected = wait_for(Duration::from_secs(20), || {
+        !a.state.sessions.is_empty() && !b.state.sessions.is_empty()
+    })
+    .await;
+    assert!(connected, "no session formed");
+    // Let the chunk manager's one-second tick start A's receive loop.
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    b.shutdown();
+    b.wait().await;
+
+    // Without the Goodbye, A would hold the session for the 30 s idle timeout.
+    let pruned = wait_for(Duration::from_secs(3), || a.state.sessions.is_empty()).await;
+    assert!(pruned, "session outlived the peer's goodbye");
+
+    a.shutdown();
+    a.wait().await;
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn max_sessions_caps_established_sessions() {
+    let root = test_root("session-limit");
+    let net = LoopbackNet::new();
+
+    let key_a = write_keypair(&root, "a");
+    let key_b = write_keypair(&root, "b");
+    let key_c = write_keypair(&root, "c");
+
+    let mut config_a = static_config(&root, "a", &[(&key_b, "fe80::2"), (&key_c, "fe80::3")]);
+    config_a.network.max_sessions = 1;
+    let a = summitd::start_on(config_a, &net).await.unwrap();
+    let b = summitd::start_on(static_config(&root, "b", &[(&key_a, "fe80::1")]), &net)
+        .await
+        .unwrap();
+    let c = summitd::start_on(static_config(&root, "c", &[(&key_a, "fe80::1")]), &net)
+        .await
+        .unwrap();
+
+    wait_for(Duration::from_secs(20), || !a.state.sessions.is_empty()).await;
+    assert_eq!(a.state.sessions.len(), 1, "no session formed");
+
+    // Both peers keep retrying; the cap must hold across several rounds.
+    let until = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < until {
+        assert!(a.state.sessions.len() <= 1, "session limit exceeded");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(b.state.sessions.len() + c.state.sessions.len(), 1);
+
+    for daemon in [&a, &b, &c] {
+        daemon.shutdown();
+    }
+    a.wait().await;
+    b.wait().await;
+    c.wait().await;
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+const COOLDOWN: Duration = Duration::from_secs(5);
+
+async fn drop_session(state: &ApiState, api: &ApiConfig, query: &str) -> serde_json::Value {
+    let id = hex::encode(*state.sessions.iter().next().unwrap().key());
+    let req = Request::builder()
+        .method("DELETE")
+        .uri(format!("/api/sessions/{id}{query}"))
+        .body(Body::empty())
+        .unwrap();
+    let resp = summit_api::router(state.clone(), api)
+        .oneshot(req)
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn dropped_session_stays_down_until_the_cooldown_runs_out() {
+    let root = test_root("session-cooldown");
+    let net = LoopbackNet::new();
+
+    // B gets the lower key: it initiates without waiting out the tie-break,
+    // so it is A's listener that has to hold it off.
+    let key_a = write_keypair(&root, "a");
+    let key_b = loop {
+        let key = write_keypair(&root, "b");
+        if key.public < key_a.public {
+            break key;
+        }
+    };
+    let config_a = static_config(&root, "a", &[(&key_b, "fe80::2")]);
+    let config_b = static_config(&root, "b", &[(&key_a, "fe80::1")]);
+    let (api_a, api_b) = (config_a.api.clone(), config_b.api.clone());
+    let a = summitd::start_on(config_a, &net).await.unwrap();
+    let b = summitd::start_on(config_b, &net).await.unwrap();
+
+    let connected = wait_for(Duration::from_secs(20), || {
+        !a.state.sessions.is_empty() && !b.state.sessions.is_empty()
+    })
+    .await;
+    assert!(connected, "no session formed");
+
+    // A drops with a cool-down, so won't initiate; B drops without one and
+    // starts handshaking again at once, which A's listener must refuse.
+    let query = format!("?cooldown_secs={}", COOLDOWN.as_secs());
+    let dropped = drop_session(&a.state, &api_a, &query).await;
+    assert_eq!(dropped["dropped"], true);
+    assert_eq!(dropped["cooldown_secs"], COOLDOWN.as_secs());
+    let dropped = drop_session(&b.state, &api_b, "").await;
+    assert_eq!(dropped["cooldown_secs"], 0);
+    let started = Instant::now();
+
+    let reformed = wait_for(COOLDOWN - Duration::from_secs(1), || {
+        !a.state.sessions.is_empty() || !b.state.sessions.is_empty()
+    })
+    .await;
+    assert!(!reformed, "session formed during the cool-down");
+    assert!(a.state.session_cooldowns.remaining(&key_b.public).is_some());
+
+    let reformed = wait_for(Duration::from_secs(40), || {
+        !a.state.sessions.is_empty() && !b.state.sessions.is_empty()
+    })
+    .await;
+    assert!(reformed, "no session after the cool-down");
+    assert!(started.elapsed() >= COOLDOWN);
+
+    a.shutdown();
+    b.shutdown();
+    a.wait().await;
+    b.wait().await;
+    let _ = std::fs::remove_dir_all(&root);
+}
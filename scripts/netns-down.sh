@@ -6,9 +6,13 @@ set -euo pipefail
 
 NS_A="summit-a"
 NS_B="summit-b"
+NS_C="summit-c"
+BRIDGE="br-summit"
 
 ip netns del "$NS_A" 2>/dev/null || echo "netns $NS_A not found, skipping"
 ip netns del "$NS_B" 2>/dev/null || echo "netns $NS_B not found, skipping"
+ip netns del "$NS_C" 2>/dev/null || echo "netns $NS_C not found, skipping"
+ip link delete "$BRIDGE" 2>/dev/null || echo "bridge $BRIDGE not found, skipping"
 
 # veth interfaces are deleted automatically when their namespace is deleted
 echo "Network namespaces torn down."
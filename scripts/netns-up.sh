@@ -3,38 +3,45 @@ set -euo pipefail
 
 NS_A="summit-a"
 NS_B="summit-b"
+NS_C="summit-c"
 VETH_A="veth-a"
 VETH_B="veth-b"
+VETH_C="veth-c"
+BRIDGE="br-summit"
 
 # Force cleanup - remove namespace directories directly
-rm -rf /var/run/netns/"$NS_A" /var/run/netns/"$NS_B" 2>/dev/null || true
+rm -rf /var/run/netns/"$NS_A" /var/run/netns/"$NS_B" /var/run/netns/"$NS_C" 2>/dev/null || true
 ip link delete "$VETH_A" 2>/dev/null || true
-
-# Create namespaces
-ip netns add "$NS_A"
-ip netns add "$NS_B"
-
-# Create veth pair
-ip link add "$VETH_A" type veth peer name "$VETH_B"
-echo "Created veth pair $VETH_A <--> $VETH_B"
-
-# Move each end into its namespace
-ip link set "$VETH_A" netns "$NS_A"
-ip link set "$VETH_B" netns "$NS_B"
-
-# Bring up interfaces
-ip netns exec "$NS_A" ip link set lo up
-ip netns exec "$NS_A" ip link set "$VETH_A" up
-ip netns exec "$NS_B" ip link set lo up
-ip netns exec "$NS_B" ip link set "$VETH_B" up
+ip link delete "$BRIDGE" 2>/dev/null || true
+
+# Create the bridge every namespace hangs off. Multicast snooping would
+# filter discovery announcements until an MLD report is seen.
+ip link add "$BRIDGE" type bridge mcast_snooping 0
+ip link set "$BRIDGE" up
+
+# One namespace per node: a veth whose host end joins the bridge
+for pair in "$NS_A:$VETH_A" "$NS_B:$VETH_B" "$NS_C:$VETH_C"; do
+    ns="${pair%%:*}"
+    veth="${pair##*:}"
+
+    ip netns add "$ns"
+    ip link delete "$veth-br" 2>/dev/null || true
+    ip link add "$veth" type veth peer name "$veth-br"
+    ip link set "$veth-br" master "$BRIDGE"
+    ip link set "$veth-br" up
+    ip link set "$veth" netns "$ns"
+
+    ip netns exec "$ns" ip link set lo up
+    ip netns exec "$ns" ip link set "$veth" up
+    echo "Attached $ns ($veth) to $BRIDGE"
+done
 
 sleep 1
 
 echo ""
 echo "Network namespaces ready."
-echo ""
-echo "summit-a interface:"
-ip netns exec "$NS_A" ip addr show "$VETH_A"
-echo ""
-echo "summit-b interface:"
-ip netns exec "$NS_B" ip addr show "$VETH_B"
+for pair in "$NS_A:$VETH_A" "$NS_B:$VETH_B" "$NS_C:$VETH_C"; do
+    echo ""
+    echo "${pair%%:*} interface:"
+    ip netns exec "${pair%%:*}" ip addr show "${pair##*:}"
+done
@@ -79,6 +79,73 @@ fn test_compute_task_simple() {
     result.unwrap();
 }
 
+/// An `echo` task runs on B and its result comes back to A.
+#[test]
+fn test_compute_result_returns_to_submitter() {
+    if !skip_unless_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+
+    let env = [
+        ("SUMMIT_TRUST__AUTO_TRUST", "true"),
+        ("SUMMIT_SERVICES__COMPUTE", "true"),
+    ];
+    let mut node_a = spawn_daemon(NS_A, VETH_A, &env);
+    let mut node_b = spawn_daemon(NS_B, VETH_B, &env);
+
+    let result = (|| -> Result<()> {
+        wait_for_api(NS_A, 40)?;
+        wait_for_api(NS_B, 40)?;
+
+        thread::sleep(Duration::from_secs(8));
+        assert!(!api_get(NS_A, "/status")?["sessions"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+
+        let pubkey_b = get_peer_pubkey(NS_A)?;
+
+        let body = serde_json::json!({
+            "to": pubkey_b,
+            "payload": { "run": "echo hello from B" }
+        })
+        .to_string();
+        let resp = api_post(NS_A, "/compute/submit", &body)?;
+        let task_id = resp["task_id"]
+            .as_str()
+            .context("missing task_id")?
+            .to_string();
+
+        // The result should reach A within a few seconds.
+        let mut task = Value::Null;
+        for _ in 0..20 {
+            thread::sleep(Duration::from_millis(500));
+            let tasks = api_get(NS_A, &format!("/compute/tasks/{}", pubkey_b))?;
+            task = tasks["tasks"]
+                .as_array()
+                .and_then(|l| l.iter().find(|t| t["task_id"] == task_id.as_str()))
+                .cloned()
+                .unwrap_or(Value::Null);
+            if task["status"] == "Completed" {
+                break;
+            }
+        }
+        assert_eq!(task["status"], "Completed", "task: {}", task);
+        assert_eq!(task["result"]["stdout"], "hello from B\n");
+        assert!(task["elapsed_ms"].is_number(), "missing elapsed_ms");
+
+        Ok(())
+    })();
+
+    node_a.kill().ok();
+    node_b.kill().ok();
+    cleanup_summitd();
+    result.unwrap();
+}
+
 /// summit-ctl compute submit via CLI (JSON payload).
 #[test]
 fn test_ctl_compute_submit_json() {
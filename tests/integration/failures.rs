@@ -54,6 +54,65 @@ fn test_link_down_and_recovery() {
     result.unwrap();
 }
 
+/// The lower-key peer's HandshakeInits never arrive. The higher-key peer
+/// must give up waiting after the tie-break timeout and initiate itself.
+#[test]
+fn test_tie_break_higher_key_initiates() {
+    if !skip_unless_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+
+    // HandshakeInit is 84 bytes on the wire, a 132-byte packet with the
+    // UDP and IPv6 headers. Drop it on both sides until we know which side
+    // has the lower key.
+    let guard_a = block_udp_length(NS_A, 132);
+    let guard_b = block_udp_length(NS_B, 132);
+
+    let mut node_a = spawn_daemon(NS_A, VETH_A, &[]);
+    let mut node_b = spawn_daemon(NS_B, VETH_B, &[]);
+
+    let result = (|| -> Result<()> {
+        wait_for_api(NS_A, 40)?;
+        wait_for_api(NS_B, 40)?;
+        wait_for_condition(20, || {
+            get_peer_pubkey(NS_A).is_ok() && get_peer_pubkey(NS_B).is_ok()
+        })?;
+        assert_eq!(
+            session_count(NS_A),
+            0,
+            "session formed despite blocked inits"
+        );
+
+        // NS_A sees B's key and vice versa. Unblock the higher-key side's
+        // inits (they arrive at the lower-key side); keep the lower-key
+        // side's inits blocked.
+        let key_b = get_peer_pubkey(NS_A)?;
+        let key_a = get_peer_pubkey(NS_B)?;
+        let (_kept, lower) = if key_a < key_b {
+            drop(guard_a);
+            (guard_b, NS_A)
+        } else {
+            drop(guard_b);
+            (guard_a, NS_B)
+        };
+        println!("Lower-key side {} cannot reach its peer with inits", lower);
+
+        // Tie-break timeout (10s) + handshake + margin
+        wait_for_condition(30, || session_count(NS_A) > 0 && session_count(NS_B) > 0)?;
+        println!("Session formed via higher-key fallback initiation");
+
+        Ok(())
+    })();
+
+    node_a.kill().ok();
+    node_b.kill().ok();
+    cleanup_summitd();
+    result.unwrap();
+}
+
 /// Apply 50% packet loss, spawn both daemons. Session may or may not form
 /// depending on handshake packet luck. Remove loss, assert session eventually forms.
 #[test]
@@ -183,8 +242,9 @@ fn test_dead_session_pruned() {
         // A should stay alive and responsive
         assert!(daemon_alive(NS_A), "A died after B was killed");
 
-        // Dead session should be pruned after receive timeout (60s) + some margin
-        wait_for_condition(90, || session_count(NS_A) == 0)?;
+        // Dead session should be pruned by the health loop after
+        // session_idle_secs (30s default) + some margin
+        wait_for_condition(45, || session_count(NS_A) == 0)?;
         println!("Dead session pruned from table");
 
         assert!(daemon_alive(NS_A), "A died after session pruning");
@@ -513,6 +573,68 @@ fn test_concurrent_file_transfers() {
     result.unwrap();
 }
 
+/// With data chunks dropped, every file stays half-received. The receiver
+/// must stop opening assemblies at `max_concurrent_assemblies` and stay up.
+#[test]
+fn test_max_concurrent_assemblies() {
+    if !skip_unless_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+
+    // A 1000-byte file is one data chunk: 40 IPv6 + 8 UDP + 8 nonce +
+    // 72 header + 4 index + 1000 data + 16 MAC = 1148. Metadata chunks get
+    // through.
+    let _drop_data = block_udp_length(NS_B, 1148);
+
+    let auto_env = [("SUMMIT_TRUST__AUTO_TRUST", "true")];
+    let b_env = [
+        ("SUMMIT_TRUST__AUTO_TRUST", "true"),
+        ("SUMMIT_FILE_TRANSFER__MAX_CONCURRENT_ASSEMBLIES", "3"),
+    ];
+    let mut node_a = spawn_daemon(NS_A, VETH_A, &auto_env);
+    let mut node_b = spawn_daemon(NS_B, VETH_B, &b_env);
+
+    let mut test_files = Vec::new();
+    for i in 0..8 {
+        let path = format!("/tmp/summit-test-assembly-cap-{}.bin", i);
+        std::fs::write(&path, vec![b'a' + i as u8; 1000]).unwrap();
+        test_files.push(path);
+    }
+
+    let result = (|| -> Result<()> {
+        wait_for_api(NS_A, 40)?;
+        wait_for_api(NS_B, 40)?;
+        let _session = wait_for_session(8)?;
+
+        for path in &test_files {
+            ctl(NS_A, &["send", path])?;
+        }
+        thread::sleep(Duration::from_secs(3));
+
+        assert!(daemon_alive(NS_A), "A died");
+        assert!(daemon_alive(NS_B), "B died with assemblies capped");
+
+        let files = api_get(NS_B, "/files")?;
+        println!("B /files: {}", files);
+        assert_eq!(files["max_concurrent_assemblies"], 3);
+        assert_eq!(files["active_assemblies"], 3, "cap not reached or exceeded");
+        assert_eq!(files["in_progress"].as_array().map(Vec::len), Some(3));
+
+        Ok(())
+    })();
+
+    node_a.kill().ok();
+    node_b.kill().ok();
+    cleanup_summitd();
+    for path in &test_files {
+        std::fs::remove_file(path).ok();
+    }
+    result.unwrap();
+}
+
 /// Apply 20% packet loss, send a small file. Daemon must stay alive.
 /// File may or may not arrive (UDP is lossy). No crash.
 #[test]
@@ -726,7 +848,8 @@ fn test_trust_then_check_buffered_messages() {
 // ══════════════════════════════════════════════════════════════════════════════
 
 /// Submit a long-running task to B, kill B after 2s. A should stay alive,
-/// task status remains Queued (B never reported back).
+/// task status remains Queued (B never reported back). Restarted B still
+/// lists the task, as Interrupted.
 #[test]
 fn test_compute_worker_crash() {
     if !skip_unless_ready() {
@@ -789,6 +912,17 @@ fn test_compute_worker_crash() {
         node_b = spawn_daemon(NS_B, VETH_B, &env);
         wait_for_api(NS_B, 40)?;
         assert!(daemon_alive(NS_A), "A died after B restart");
+
+        // B reloads the task it was running when killed, as Interrupted
+        let tasks = api_get(NS_B, "/compute/tasks")?;
+        let task_list = tasks["tasks"].as_array().context("no tasks")?;
+        let task = task_list
+            .iter()
+            .find(|t| t["task_id"].as_str() == Some(&task_id))
+            .context("task not listed after restart")?;
+        let status = task["status"].as_str().unwrap_or("unknown");
+        println!("Task status on restarted worker: {}", status);
+        assert_eq!(status, "Interrupted", "running task not marked interrupted");
         println!("A still functional after worker crash and restart");
 
         Ok(())
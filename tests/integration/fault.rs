@@ -113,6 +113,49 @@ pub fn block_udp_port(ns: &str, port: u16) -> FaultGuard {
     ])
 }
 
+/// Drop inbound UDP datagrams of one exact size via ip6tables inside a namespace.
+/// `packet_len` is what the length match compares: the whole IPv6 packet,
+/// i.e. 40-byte IPv6 header + 8-byte UDP header + payload.
+pub fn block_udp_length(ns: &str, packet_len: u16) -> FaultGuard {
+    let len_str = packet_len.to_string();
+    let _ = Command::new("ip")
+        .args([
+            "netns",
+            "exec",
+            ns,
+            "ip6tables",
+            "-A",
+            "INPUT",
+            "-p",
+            "udp",
+            "-m",
+            "length",
+            "--length",
+            &len_str,
+            "-j",
+            "DROP",
+        ])
+        .output();
+
+    FaultGuard::new(vec![
+        "ip".into(),
+        "netns".into(),
+        "exec".into(),
+        ns.into(),
+        "ip6tables".into(),
+        "-D".into(),
+        "INPUT".into(),
+        "-p".into(),
+        "udp".into(),
+        "-m".into(),
+        "length".into(),
+        "--length".into(),
+        len_str,
+        "-j".into(),
+        "DROP".into(),
+    ])
+}
+
 /// Block all UDP traffic via ip6tables inside a namespace.
 pub fn block_all_udp(ns: &str) -> FaultGuard {
     let _ = Command::new("ip")
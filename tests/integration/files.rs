@@ -233,3 +233,132 @@ fn test_ctl_files_empty() {
     cleanup_summitd();
     result.unwrap();
 }
+
+/// send --dry-run: reports chunks and targets, sends nothing.
+#[test]
+fn test_send_dry_run() {
+    if !skip_unless_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+    std::fs::remove_dir_all("/tmp/summit-received").ok();
+
+    let auto_env = [("SUMMIT_TRUST__AUTO_TRUST", "true")];
+    let mut node_a = spawn_daemon(NS_A, VETH_A, &auto_env);
+    let mut node_b = spawn_daemon(NS_B, VETH_B, &auto_env);
+
+    // 100 KB: four 32 KB data chunks plus metadata.
+    let test_file = "/tmp/summit-test-dry-run.bin";
+    std::fs::write(test_file, vec![7u8; 100 * 1024]).unwrap();
+
+    let result = (|| -> Result<()> {
+        wait_for_api(NS_A, 40)?;
+        wait_for_api(NS_B, 40)?;
+
+        thread::sleep(Duration::from_secs(8));
+        assert!(!api_get(NS_A, "/status")?["sessions"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+
+        let pubkey_b = get_peer_pubkey(NS_A)?;
+
+        let out = ctl(NS_A, &["--json", "send", test_file, "--dry-run"])?;
+        let plan: Value = serde_json::from_str(&out).context("plan JSON")?;
+        assert_eq!(plan["bytes"], 100 * 1024);
+        assert_eq!(plan["chunks"], 5);
+        let targets = plan["targets"].as_array().context("no targets")?;
+        assert_eq!(targets.len(), 1, "plan: {}", out);
+        assert_eq!(targets[0]["peer_pubkey"].as_str(), Some(pubkey_b.as_str()));
+
+        let out = ctl(NS_A, &["send", test_file, "--dry-run"])?;
+        assert!(out.contains("Dry run"), "dry-run output: {}", out);
+        println!("send --dry-run: {}", out);
+
+        thread::sleep(Duration::from_secs(4));
+        let files_out = ctl(NS_B, &["files"])?;
+        assert!(
+            !files_out.contains("summit-test-dry-run.bin"),
+            "dry run sent the file: {}",
+            files_out
+        );
+
+        Ok(())
+    })();
+
+    node_a.kill().ok();
+    node_b.kill().ok();
+    cleanup_summitd();
+    std::fs::remove_file(test_file).ok();
+    result.unwrap();
+}
+
+/// A multi-chunk file arrives in order: the send worker queues each
+/// session's chunks FIFO, so the receiver sees next to no chunk that
+/// arrived behind a later one.
+#[test]
+fn test_file_chunks_arrive_in_order() {
+    if !skip_unless_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+
+    let auto_env = [("SUMMIT_TRUST__AUTO_TRUST", "true")];
+    let mut node_a = spawn_node(NS_A, &auto_env);
+    let mut node_b = spawn_node(NS_B, &auto_env);
+
+    // 2 MB: 64 data chunks of 32 KB.
+    let test_file = "/tmp/summit-test-in-order.bin";
+    let content: Vec<u8> = (0..2 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(test_file, &content).unwrap();
+
+    let result = (|| -> Result<()> {
+        wait_for_api(NS_A, 40)?;
+        wait_for_api(NS_B, 40)?;
+
+        thread::sleep(Duration::from_secs(8));
+        let pubkey_b = get_peer_pubkey(NS_A)?;
+
+        let send_out = ctl(NS_A, &["send", test_file, "--peer", &pubkey_b])?;
+        assert!(
+            send_out.contains("File queued"),
+            "send output: {}",
+            send_out
+        );
+
+        let received_path = format!("{}/summit-test-in-order.bin", received_dir(NS_B));
+        let mut received = None;
+        for _ in 0..30 {
+            thread::sleep(Duration::from_secs(1));
+            if let Ok(data) = std::fs::read(&received_path) {
+                received = Some(data);
+                break;
+            }
+        }
+        let received = received.context("file not received")?;
+        assert_eq!(received, content, "content mismatch");
+
+        let files = api_get(NS_B, "/files")?;
+        let reordered = files["reordered_chunks"]
+            .as_u64()
+            .context("no reordered_chunks")?;
+        println!("reordered chunks: {}", reordered);
+        assert!(
+            reordered <= 6,
+            "{} of 64 chunks arrived out of order",
+            reordered
+        );
+
+        Ok(())
+    })();
+
+    node_a.kill().ok();
+    node_b.kill().ok();
+    cleanup_summitd();
+    std::fs::remove_file(test_file).ok();
+    result.unwrap();
+}
@@ -89,3 +89,30 @@ fn test_ping_b_to_a() {
     }
     assert!(result.is_ok());
 }
+
+#[test]
+fn test_third_namespace_on_bridge() {
+    if !third_namespace_available() {
+        eprintln!("SKIP: {NS_C} not available — rerun sudo ./scripts/netns-up.sh");
+        return;
+    }
+
+    let addrs: Vec<String> = NODES
+        .iter()
+        .map(|(ns, veth)| {
+            link_local_addr(ns, veth)
+                .unwrap_or_else(|e| panic!("{ns} should have a link-local address: {e}"))
+        })
+        .collect();
+    assert_ne!(addrs[2], addrs[0], "addresses should be different");
+    assert_ne!(addrs[2], addrs[1], "addresses should be different");
+
+    // Every pair that involves summit-c, both directions
+    for (from, to) in [(NS_A, NS_C), (NS_C, NS_A), (NS_B, NS_C), (NS_C, NS_B)] {
+        let addr = peer_link_local_addr(from, to).unwrap();
+        println!("Pinging {addr} from {from}...");
+        if let Err(e) = netns_exec(from, &["ping", "-6", "-c", "3", "-W", "2", &addr]) {
+            panic!("ping6 from {from} to {to} failed: {e}");
+        }
+    }
+}
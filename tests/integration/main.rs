@@ -6,6 +6,11 @@
 //!   sudo ./scripts/netns-up.sh
 //!   sudo cargo test --test integration
 //!
+//! `netns-up.sh` attaches summit-a, summit-b and summit-c to one bridge.
+//! Two-node tests use A and B; multipath tests gate on
+//! `skip_unless_three_ready` and start nodes with `spawn_node`, which gives
+//! each daemon its own received-files directory.
+//!
 //! Daemon tests (those that spawn summitd) run serialized via DAEMON_LOCK
 //! to avoid port 9001 conflicts between parallel tests.
 
@@ -14,8 +19,9 @@
 
 use anyhow::{bail, Context, Result};
 use serde_json::Value;
+use std::io::Write;
 use std::path::PathBuf;
-use std::process::{Child, Command};
+use std::process::{Child, Command, Stdio};
 use std::thread;
 use std::time::Duration;
 
@@ -36,8 +42,13 @@ mod trust;
 
 pub const NS_A: &str = "summit-a";
 pub const NS_B: &str = "summit-b";
+pub const NS_C: &str = "summit-c";
 pub const VETH_A: &str = "veth-a";
 pub const VETH_B: &str = "veth-b";
+pub const VETH_C: &str = "veth-c";
+
+/// Every namespace `netns-up.sh` attaches to the bridge, with its interface.
+pub const NODES: [(&str, &str); 3] = [(NS_A, VETH_A), (NS_B, VETH_B), (NS_C, VETH_C)];
 
 /// Serializes all daemon-based tests so they don't conflict on port 9001.
 pub static DAEMON_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
@@ -82,22 +93,60 @@ pub fn spawn_daemon(ns: &str, iface: &str, extra_env: &[(&str, &str)]) -> Child
     cmd.arg(summitd_path());
     cmd.arg(iface);
     cmd.env("RUST_LOG", "info");
-    // Unique cache dir per daemon so they don't share on-disk state
-    cmd.env(
-        "SUMMIT_CACHE",
-        format!("/tmp/summit-cache-{}-{}", ns, std::process::id()),
-    );
+    // In-memory cache: no shared on-disk state, no fsync per chunk
+    cmd.env("SUMMIT_CACHE__BACKEND", "memory");
     // Unique config path per daemon
-    cmd.env(
-        "SUMMIT_CONFIG",
-        format!("/tmp/summit-config-{}-{}.toml", ns, std::process::id()),
-    );
+    cmd.env("SUMMIT_CONFIG", config_path(ns));
     for (k, v) in extra_env {
         cmd.env(k, v);
     }
     cmd.spawn().expect("failed to spawn summitd")
 }
 
+/// Config file of the daemon `spawn_daemon` starts in `ns`. It is written
+/// with defaults on first start; env overrides still apply on top.
+pub fn config_path(ns: &str) -> String {
+    format!("/tmp/summit-config-{}-{}.toml", ns, std::process::id())
+}
+
+/// Ask a daemon to reload its config file, as `systemctl reload` would.
+/// `ip netns exec` execs summitd, so the child's pid is the daemon's.
+pub fn reload_daemon(daemon: &Child) -> Result<()> {
+    let status = Command::new("kill")
+        .args(["-HUP", &daemon.id().to_string()])
+        .status()
+        .context("kill -HUP")?;
+    if !status.success() {
+        bail!("kill -HUP {} failed", daemon.id());
+    }
+    Ok(())
+}
+
+/// The interface inside one of the harness namespaces.
+pub fn veth_for(ns: &str) -> &'static str {
+    NODES
+        .iter()
+        .find(|(n, _)| *n == ns)
+        .map(|(_, veth)| *veth)
+        .unwrap_or_else(|| panic!("{ns} is not a harness namespace"))
+}
+
+/// Where a daemon started by `spawn_node` stores received files.
+pub fn received_dir(ns: &str) -> String {
+    format!("/tmp/summit-received-{ns}")
+}
+
+/// Spawn a summitd on a harness namespace's own interface, storing received
+/// files under `received_dir(ns)` so several receivers don't share one
+/// directory. The directory is emptied first.
+pub fn spawn_node(ns: &str, extra_env: &[(&str, &str)]) -> Child {
+    let dir = received_dir(ns);
+    std::fs::remove_dir_all(&dir).ok();
+    let mut env = vec![("SUMMIT_FILE_TRANSFER__STORAGE_PATH", dir.as_str())];
+    env.extend_from_slice(extra_env);
+    spawn_daemon(ns, veth_for(ns), &env)
+}
+
 /// Wait until the REST API is reachable inside a namespace.
 /// Polls up to `max_attempts * 500 ms`.
 pub fn wait_for_api(ns: &str, max_attempts: u32) -> Result<()> {
@@ -111,7 +160,7 @@ pub fn wait_for_api(ns: &str, max_attempts: u32) -> Result<()> {
                 "/dev/null",
                 "-w",
                 "%{http_code}",
-                "http://127.0.0.1:9001/api/status",
+                "http://127.0.0.1:9001/api/health",
             ])
             .output();
 
@@ -150,9 +199,10 @@ pub fn api_get(ns: &str, path: &str) -> Result<Value> {
 }
 
 /// Run a JSON POST against the daemon API inside a namespace.
+/// The body goes over stdin, so it may exceed the argument length limit.
 pub fn api_post(ns: &str, path: &str, body: &str) -> Result<Value> {
     let url = format!("http://127.0.0.1:9001/api{}", path);
-    let out = Command::new("ip")
+    let mut child = Command::new("ip")
         .args(["netns", "exec", ns])
         .args([
             "curl",
@@ -161,11 +211,22 @@ pub fn api_post(ns: &str, path: &str, body: &str) -> Result<Value> {
             "POST",
             "-H",
             "Content-Type: application/json",
-            "-d",
-            body,
+            "--data-binary",
+            "@-",
             &url,
         ])
-        .output()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("curl POST {} in {}", path, ns))?;
+    child
+        .stdin
+        .take()
+        .context("curl stdin")?
+        .write_all(body.as_bytes())?;
+    let out = child
+        .wait_with_output()
         .with_context(|| format!("curl POST {} in {}", path, ns))?;
 
     if !out.status.success() {
@@ -195,6 +256,14 @@ pub fn get_peer_pubkey(ns: &str) -> Result<String> {
         .context("no public_key field")
 }
 
+/// Return the daemon's own `public_key` hex string in `ns`.
+pub fn own_pubkey(ns: &str) -> Result<String> {
+    api_get(ns, "/identity")?["public_key"]
+        .as_str()
+        .map(|s| s.to_string())
+        .context("no public_key field")
+}
+
 /// Trust a peer on `ns` via the REST API. Returns the number of flushed chunks.
 pub fn trust_peer(ns: &str, peer_pubkey: &str) -> Result<usize> {
     let body = serde_json::json!({ "public_key": peer_pubkey }).to_string();
@@ -264,10 +333,27 @@ pub fn link_local_addr(ns: &str, iface: &str) -> Result<String> {
     bail!("no link-local address found on {iface} in {ns}")
 }
 
+/// The link-local address of `to_ns`, scoped to `from_ns`'s interface —
+/// what a process in `from_ns` dials to reach it across the bridge.
+pub fn peer_link_local_addr(from_ns: &str, to_ns: &str) -> Result<String> {
+    let addr = link_local_addr(to_ns, veth_for(to_ns))?;
+    let addr = addr.split('%').next().unwrap();
+    Ok(format!("{addr}%{}", veth_for(from_ns)))
+}
+
 /// Check whether the netns environment is up.
 pub fn netns_available() -> bool {
+    namespace_exists(NS_A)
+}
+
+/// Check whether the third namespace is up too.
+pub fn third_namespace_available() -> bool {
+    namespace_exists(NS_C)
+}
+
+fn namespace_exists(ns: &str) -> bool {
     Command::new("ip")
-        .args(["netns", "exec", NS_A, "ip", "link", "show"])
+        .args(["netns", "exec", ns, "ip", "link", "show"])
         .output()
         .map(|o| o.status.success())
         .unwrap_or(false)
@@ -286,6 +372,43 @@ pub fn skip_unless_ready() -> bool {
     true
 }
 
+/// Like `skip_unless_ready`, but also requires the third namespace (NS_C),
+/// which older two-node setups lack.
+pub fn skip_unless_three_ready() -> bool {
+    if !skip_unless_ready() {
+        return false;
+    }
+    if !third_namespace_available() {
+        eprintln!("SKIP: {NS_C} not available — rerun sudo ./scripts/netns-up.sh");
+        return false;
+    }
+    true
+}
+
+/// Wait until `ns` has at least `count` sessions, polling every 500 ms.
+pub fn wait_for_sessions(ns: &str, count: usize, timeout_secs: u64) -> Result<()> {
+    let deadline = std::time::Instant::now() + Duration::from_secs(timeout_secs);
+    loop {
+        let established = api_get(ns, "/status")
+            .ok()
+            .and_then(|s| s["sessions"].as_array().map(|a| a.len()))
+            .unwrap_or(0);
+        if established >= count {
+            return Ok(());
+        }
+        if std::time::Instant::now() >= deadline {
+            bail!(
+                "{} has {} of {} sessions after {}s",
+                ns,
+                established,
+                count,
+                timeout_secs
+            );
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
 /// Wait for sessions to be established on NS_A (returns the first session_id).
 pub fn wait_for_session(secs: u64) -> Result<String> {
     thread::sleep(Duration::from_secs(secs));
@@ -125,6 +125,119 @@ fn test_ctl_messages_send() {
     result.unwrap();
 }
 
+/// A message far larger than one chunk is fragmented and arrives intact.
+#[test]
+fn test_messaging_large_message() {
+    if !skip_unless_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+
+    let auto_env = [("SUMMIT_TRUST__AUTO_TRUST", "true")];
+    let mut node_a = spawn_daemon(NS_A, VETH_A, &auto_env);
+    let mut node_b = spawn_daemon(NS_B, VETH_B, &auto_env);
+
+    let result = (|| -> Result<()> {
+        wait_for_api(NS_A, 40)?;
+        wait_for_api(NS_B, 40)?;
+
+        thread::sleep(Duration::from_secs(8));
+        assert!(!api_get(NS_A, "/status")?["sessions"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+
+        let pubkey_b = get_peer_pubkey(NS_A)?;
+        let pubkey_a = get_peer_pubkey(NS_B)?;
+
+        // 200 KB of text, varied so a misplaced fragment would show.
+        let text: String = (0..200 * 1024)
+            .map(|i| char::from(b'a' + (i % 26) as u8))
+            .collect();
+        let body = serde_json::json!({ "to": pubkey_b, "text": text }).to_string();
+        let resp = api_post(NS_A, "/messages/send", &body)?;
+        assert!(resp["msg_id"].is_string(), "missing msg_id");
+
+        thread::sleep(Duration::from_secs(6));
+
+        let msgs = api_get(NS_B, &format!("/messages/{}", pubkey_a))?;
+        let msg_list = msgs["messages"].as_array().context("no messages")?;
+        let received = msg_list
+            .iter()
+            .find_map(|m| m["content"]["text"].as_str())
+            .context("large message not received on B")?;
+        assert_eq!(received.len(), text.len());
+        assert!(received == text, "large message arrived corrupted");
+
+        println!("200 KB message received intact");
+        Ok(())
+    })();
+
+    node_a.kill().ok();
+    node_b.kill().ok();
+    cleanup_summitd();
+    result.unwrap();
+}
+
+/// Group message from A reaches both B and C, tagged with the same group id.
+#[test]
+fn test_messaging_group_three_nodes() {
+    if !skip_unless_three_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+
+    let auto_env = [("SUMMIT_TRUST__AUTO_TRUST", "true")];
+    let mut nodes: Vec<_> = NODES
+        .iter()
+        .map(|(ns, _)| spawn_node(ns, &auto_env))
+        .collect();
+
+    let result = (|| -> Result<()> {
+        for (ns, _) in NODES {
+            wait_for_api(ns, 40)?;
+        }
+        wait_for_sessions(NS_A, 2, 20)?;
+
+        let pubkey_a = own_pubkey(NS_A)?;
+        let pubkey_b = own_pubkey(NS_B)?;
+        let pubkey_c = own_pubkey(NS_C)?;
+
+        let body =
+            serde_json::json!({ "to": [pubkey_b, pubkey_c], "text": "hello group" }).to_string();
+        api_post(NS_A, "/messages/send", &body)?;
+
+        thread::sleep(Duration::from_secs(4));
+
+        let mut group_ids = Vec::new();
+        for ns in [NS_B, NS_C] {
+            let msgs = api_get(ns, &format!("/messages/{}", pubkey_a))?;
+            let msg_list = msgs["messages"].as_array().context("no messages")?;
+            assert_eq!(msg_list.len(), 1, "{} should hold the group message", ns);
+            assert_eq!(
+                msg_list[0]["content"]["text"].as_str().unwrap(),
+                "hello group"
+            );
+            group_ids.push(msg_list[0]["group_id"].clone());
+        }
+        assert!(group_ids[0].is_string(), "missing group_id");
+        assert_eq!(group_ids[0], group_ids[1], "group ids differ");
+        println!("Group message delivered to B and C");
+
+        Ok(())
+    })();
+
+    for node in &mut nodes {
+        node.kill().ok();
+    }
+    cleanup_summitd();
+    result.unwrap();
+}
+
 /// Multiple messages: verify ordering and count.
 #[test]
 fn test_messaging_multiple() {
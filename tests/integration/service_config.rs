@@ -106,3 +106,122 @@ fn test_service_config_enable_compute() {
     cleanup_summitd();
     result.unwrap();
 }
+
+/// Switching messaging off and on at runtime changes what peers see
+/// announced, without a restart.
+#[test]
+fn test_service_toggle_at_runtime() {
+    if !skip_unless_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+
+    let mut node_a = spawn_daemon(NS_A, VETH_A, &[]);
+    let mut node_b = spawn_daemon(NS_B, VETH_B, &[]);
+
+    let service_count_of_a = || -> Result<u64> {
+        let peers = api_get(NS_B, "/peers")?;
+        let peers_list = peers["peers"].as_array().context("no peers on B")?;
+        let a = peers_list.first().context("B has no peers")?;
+        Ok(a["service_count"].as_u64().unwrap_or(0))
+    };
+
+    let result = (|| -> Result<()> {
+        wait_for_api(NS_A, 40)?;
+        wait_for_api(NS_B, 40)?;
+        thread::sleep(Duration::from_secs(6));
+        assert_eq!(service_count_of_a()?, 2, "A should start with 2 services");
+
+        let out = ctl(NS_A, &["services", "disable", "messaging"])?;
+        assert!(out.contains("messaging disabled"), "unexpected: {}", out);
+        let svc = api_get(NS_A, "/services")?;
+        let messaging = svc["services"]
+            .as_array()
+            .context("no services")?
+            .iter()
+            .find(|s| s["name"] == "messaging")
+            .context("messaging missing")?;
+        assert_eq!(messaging["enabled"], false);
+
+        thread::sleep(Duration::from_secs(4));
+        assert_eq!(service_count_of_a()?, 1, "B should see messaging dropped");
+
+        ctl(NS_A, &["services", "enable", "messaging"])?;
+        thread::sleep(Duration::from_secs(4));
+        assert_eq!(service_count_of_a()?, 2, "B should see messaging back");
+
+        Ok(())
+    })();
+
+    node_a.kill().ok();
+    node_b.kill().ok();
+    cleanup_summitd();
+    result.unwrap();
+}
+
+/// With sync on at both ends, chunks a peer loses from its cache are
+/// offered back and replicated over the Background contract.
+#[test]
+fn test_sync_replicates_cleared_cache() {
+    if !skip_unless_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+
+    let env = [
+        ("SUMMIT_TRUST__AUTO_TRUST", "true"),
+        ("SUMMIT_SERVICES__SYNC", "true"),
+        ("SUMMIT_SYNC__INTERVAL_SECS", "2"),
+    ];
+    let mut node_a = spawn_daemon(NS_A, VETH_A, &env);
+    let mut node_b = spawn_daemon(NS_B, VETH_B, &env);
+
+    let test_file = "/tmp/summit-test-sync.bin";
+    std::fs::write(test_file, vec![0x5Au8; 64 * 1024]).unwrap();
+
+    let result = (|| -> Result<()> {
+        wait_for_api(NS_A, 40)?;
+        wait_for_api(NS_B, 40)?;
+
+        let svc = api_get(NS_A, "/services")?;
+        let sync = svc["services"]
+            .as_array()
+            .context("no services")?
+            .iter()
+            .find(|s| s["name"].as_str() == Some("sync"))
+            .context("sync not in services list")?
+            .clone();
+        assert!(sync["enabled"].as_bool().unwrap_or(false));
+        assert_eq!(sync["contract"].as_str(), Some("Background"));
+
+        wait_for_sessions(NS_A, 1, 20)?;
+        let send_out = ctl(NS_A, &["send", test_file])?;
+        assert!(
+            send_out.contains("File queued"),
+            "send failed: {}",
+            send_out
+        );
+        thread::sleep(Duration::from_secs(4));
+
+        api_post(NS_B, "/cache/clear", "{}")?;
+        assert_eq!(api_get(NS_B, "/cache/list")?["total"].as_u64(), Some(0));
+
+        // A few offer rounds
+        thread::sleep(Duration::from_secs(8));
+        let total = api_get(NS_B, "/cache/list")?["total"].as_u64().unwrap_or(0);
+        assert!(total > 0, "nothing replicated back to B");
+        println!("B re-cached {} chunks via sync", total);
+
+        Ok(())
+    })();
+
+    node_a.kill().ok();
+    node_b.kill().ok();
+    cleanup_summitd();
+    std::fs::remove_file(test_file).ok();
+    result.unwrap();
+}
@@ -51,6 +51,66 @@ fn test_ctl_sessions_inspect() {
     result.unwrap();
 }
 
+/// summit-ctl sessions / sessions --peer: list sessions and look one up by key.
+#[test]
+fn test_ctl_sessions_list() {
+    if !skip_unless_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+
+    let mut node_a = spawn_daemon(NS_A, VETH_A, &[]);
+    let mut node_b = spawn_daemon(NS_B, VETH_B, &[]);
+
+    let result = (|| -> Result<()> {
+        wait_for_api(NS_A, 40)?;
+        wait_for_api(NS_B, 40)?;
+
+        let session_id = wait_for_session(8)?;
+        let pubkey_b = own_pubkey(NS_B)?;
+
+        // API: the peer filter finds the session, an unknown key finds none
+        let by_peer = api_get(NS_A, &format!("/sessions?peer={}", pubkey_b))?;
+        let sessions = by_peer["sessions"]
+            .as_array()
+            .context("no sessions array")?;
+        assert_eq!(sessions.len(), 1, "expected one session with B");
+        assert_eq!(
+            sessions[0]["session_id"].as_str(),
+            Some(session_id.as_str())
+        );
+        let other = api_get(NS_A, &format!("/sessions?peer={}", "00".repeat(32)))?;
+        assert!(other["sessions"].as_array().unwrap().is_empty());
+
+        // summit-ctl sessions — compact table
+        for args in [&["sessions"][..], &["sessions", "list"][..]] {
+            let out = ctl(NS_A, args)?;
+            assert!(out.contains("Active Sessions (1)"), "header: {}", out);
+            assert!(
+                out.contains(&session_id[..16]),
+                "session id missing: {}",
+                out
+            );
+            assert!(out.contains(&pubkey_b[..16]), "peer missing: {}", out);
+        }
+
+        // summit-ctl sessions --peer — full details
+        let out = ctl(NS_A, &["sessions", "--peer", &pubkey_b])?;
+        assert!(out.contains("Sessions with Peer (1)"), "header: {}", out);
+        assert!(out.contains(&session_id), "session id missing: {}", out);
+        println!("{}", out);
+
+        Ok(())
+    })();
+
+    node_a.kill().ok();
+    node_b.kill().ok();
+    cleanup_summitd();
+    result.unwrap();
+}
+
 /// summit-ctl sessions drop: drop an active session and verify it's gone.
 #[test]
 fn test_ctl_sessions_drop() {
@@ -1,3 +1,4 @@
+use crate::fault::*;
 use crate::*;
 
 /// Full trust lifecycle via CLI: list (empty) -> add -> list (Trusted) -> block -> list (Blocked).
@@ -205,3 +206,70 @@ fn test_auto_trust_config() {
     cleanup_summitd();
     result.unwrap();
 }
+
+/// Adding a peer to `trust.trusted_peers` and sending SIGHUP trusts it
+/// without a restart; the session survives and service changes show up.
+#[test]
+fn test_config_reload_trusts_peer() {
+    if !skip_unless_ready() {
+        return;
+    }
+
+    let _lock = DAEMON_LOCK.lock().unwrap();
+    cleanup_summitd();
+    std::fs::remove_file(config_path(NS_A)).ok();
+
+    let mut node_a = spawn_daemon(NS_A, VETH_A, &[]);
+    let mut node_b = spawn_daemon(NS_B, VETH_B, &[]);
+
+    let result = (|| -> Result<()> {
+        wait_for_api(NS_A, 40)?;
+        wait_for_api(NS_B, 40)?;
+        let session_id = wait_for_session(8)?;
+
+        let pubkey_b = get_peer_pubkey(NS_A)?;
+        let peers = api_get(NS_A, "/peers")?;
+        assert_eq!(peers["peers"][0]["trust_level"], "Untrusted");
+
+        std::fs::write(
+            config_path(NS_A),
+            format!("[trust]\ntrusted_peers = [\"{pubkey_b}\"]\n\n[services]\nmessaging = false\n"),
+        )?;
+        reload_daemon(&node_a)?;
+
+        wait_for_condition(10, || {
+            api_get(NS_A, "/peers")
+                .map(|p| p["peers"][0]["trust_level"] == "Trusted")
+                .unwrap_or(false)
+        })?;
+        println!("B trusted after reload");
+
+        let services = api_get(NS_A, "/services")?;
+        let messaging = services["services"]
+            .as_array()
+            .context("no services")?
+            .iter()
+            .find(|s| s["name"] == "messaging")
+            .context("messaging missing")?;
+        assert_eq!(messaging["enabled"], false);
+
+        // Same session, no restart
+        assert!(daemon_alive(NS_A), "A died on reload");
+        let sessions = api_get(NS_A, "/status")?;
+        assert!(
+            sessions["sessions"]
+                .as_array()
+                .context("no sessions")?
+                .iter()
+                .any(|s| s["session_id"] == session_id.as_str()),
+            "session {session_id} dropped by reload"
+        );
+        Ok(())
+    })();
+
+    node_a.kill().ok();
+    node_b.kill().ok();
+    cleanup_summitd();
+    std::fs::remove_file(config_path(NS_A)).ok();
+    result.unwrap();
+}